@@ -0,0 +1,805 @@
+use std::path::Path;
+
+use crate::{Range, RangeSpec};
+
+use super::types::{
+    Args, BankParams, ChecksumFormat, ChecksumGapFill, ChecksumParams, ChecksumTarget, DspicOp,
+    MergeParam, OutputFormat, PageMapParams,
+};
+
+impl Args {
+    /// Serialize the resolved arguments back into canonical HexView syntax.
+    ///
+    /// Options are emitted in processing order with ranges and numbers re-rendered
+    /// in hex, so parsing the result yields an `Args` equal to `self`.
+    pub fn to_command_line(&self) -> Vec<String> {
+        let mut out = Vec::new();
+
+        let inputs: Vec<String> = self
+            .input_file
+            .iter()
+            .chain(&self.extra_inputs)
+            .map(|path| path_str(path))
+            .collect();
+        let input_needs_dashes = inputs
+            .iter()
+            .any(|s| s.starts_with('/') || s.starts_with('-'));
+        if !input_needs_dashes {
+            out.extend(inputs.iter().cloned());
+        }
+
+        if let Some(ref path) = self.error_log {
+            let option = if self.error_log_append { "E+" } else { "E" };
+            out.push(format!("/{option}:{}", path_str(path)));
+        }
+        if self.silent {
+            out.push("/S".to_string());
+        }
+        if self.werror {
+            out.push("/WERROR".to_string());
+        }
+        if self.write_version {
+            out.push("/V".to_string());
+        }
+        match self.verbose {
+            0 => {}
+            1 => out.push("/VERBOSE".to_string()),
+            _ => out.push("-vv".to_string()),
+        }
+        if let Some(ref path) = self.ini_file {
+            out.push(format!("/P:{}", path_str(path)));
+        }
+        for (key, value) in &self.ford_header {
+            out.push(format!("/FORD:{key}={value}"));
+        }
+        for (name, value) in &self.defines {
+            out.push(format!("/DEF:{name}={value}"));
+        }
+
+        if let Some(ref path) = self.import_i16 {
+            out.push(format!("/II2:{}", path_str(path)));
+        }
+        if let Some(ref param) = self.import_binary {
+            out.push(format!("/IN:{}", import_str(&param.file, param.offset)));
+        }
+        if let Some(ref param) = self.import_hex_ascii {
+            out.push(format!("/IA:{}", import_str(&param.file, param.offset)));
+        }
+        if let Some(params) = self.unbank {
+            out.push(format!("/UNBANK:{}", bank_str(params)));
+        }
+
+        for (name, map) in [
+            ("/S08MAP", self.s08_map),
+            ("/S12MAP", self.s12_map),
+            ("/S12XMAP", self.s12x_map),
+        ] {
+            match map {
+                Some(PageMapParams::Standard) => out.push(name.to_string()),
+                Some(PageMapParams::Paged {
+                    ppage_base,
+                    window_start,
+                    window_size,
+                    linear_base,
+                }) => out.push(format!(
+                    "{name}:{},{},{},{}",
+                    hex(ppage_base.into()),
+                    hex(window_start),
+                    hex(window_size),
+                    hex(linear_base)
+                )),
+                None => {}
+            }
+        }
+        for remap in &self.remap {
+            out.push(format!(
+                "/REMAP:{}-{},{},{},{}",
+                hex(remap.start),
+                hex(remap.end),
+                hex(remap.linear),
+                hex(remap.size),
+                hex(remap.inc)
+            ));
+        }
+        if let Some(ref path) = self.map_table {
+            out.push(format!("/MAPTAB:{}", path_str(path)));
+        }
+
+        if !self.dspic_expand.is_empty() {
+            out.push(format!("/CDSPX:{}", dspic_str(&self.dspic_expand)));
+        }
+        if !self.dspic_shrink.is_empty() {
+            out.push(format!("/CDSPS:{}", dspic_str(&self.dspic_shrink)));
+        }
+        if !self.dspic_clear_ghost.is_empty() {
+            out.push(format!("/CDSPG:{}", ranges_str(&self.dspic_clear_ghost)));
+        }
+        if !self.dspic_insert_ghost.is_empty() {
+            let ops = self
+                .dspic_insert_ghost
+                .iter()
+                .map(|op| format!("{};{}", range_str(&op.range), hex(op.value.into())))
+                .collect::<Vec<_>>()
+                .join(":");
+            out.push(format!("/CDSPI:{ops}"));
+        }
+
+        if !self.fill_ranges.is_empty() {
+            out.push(format!("/FR:{}", ranges_str(&self.fill_ranges)));
+        }
+        if self.fill_pattern_set {
+            out.push(match (&self.fill_pattern_file, &self.fill_pattern) {
+                (Some(path), _) => format!("/FP:@{}", path.display()),
+                (None, crate::FillPattern::Bytes(bytes)) => format!("/FP:{}", hex_bytes(bytes)),
+                (None, crate::FillPattern::Increment { start, step }) => {
+                    format!("/FP:INC:0x{start:02X}:0x{step:02X}")
+                }
+                (None, crate::FillPattern::Random { .. }) => "/FP:RANDOM".to_string(),
+            });
+        }
+        if let Some(seed) = self.fill_seed {
+            out.push(format!("/FRSEED:{seed}"));
+        }
+        if !self.cut_ranges.is_empty() {
+            out.push(format!("/CR:{}", ranges_str(&self.cut_ranges)));
+        }
+        if !self.merge_transparent.is_empty() {
+            out.push(format!("/MT:{}", merges_str(&self.merge_transparent)));
+        }
+        if !self.merge_opaque.is_empty() {
+            out.push(format!("/MO:{}", merges_str(&self.merge_opaque)));
+        }
+        for label in &self.labels {
+            out.push(format!("/LABEL:{};{}", range_str(&label.range), label.name));
+        }
+        if !self.address_range.is_empty() {
+            out.push(format!("/AR:{}", ranges_str(&self.address_range)));
+        }
+        if let Some(ref path) = self.log_file {
+            out.push(format!("/L:{}", path_str(path)));
+        }
+        if let Some(ref path) = self.log_output {
+            out.push(format!("/LOGOUT:{}", path_str(path)));
+        }
+        if self.fill_all {
+            out.push("/FA".to_string());
+        }
+        if let Some(ref path) = self.postbuild {
+            out.push(format!("/PB:{}", path_str(path)));
+        }
+
+        if let Some(addr) = self.align_address {
+            out.push(format!("/AD:{}", hex(addr)));
+        }
+        if self.align_length {
+            out.push("/AL".to_string());
+        }
+        if self.align_fill != 0xFF {
+            out.push(format!("/AF:{}", hex(self.align_fill as u32)));
+        }
+        if let Some(erase) = self.align_erase {
+            out.push(format!("/AE:{}", hex(erase)));
+        }
+        if let Some(ref path) = self.erase_sector_map {
+            out.push(format!("/AEMAP:{}", path_str(path)));
+        }
+
+        if let Some(size) = self.split_block_size {
+            out.push(format!("/SB:{}", hex(size)));
+        }
+        if let Some(boundary) = self.split_boundary {
+            out.push(format!("/SBA:{}", hex(boundary)));
+        }
+        if self.swap_word {
+            out.push("/SWAPWORD".to_string());
+        }
+        if self.swap_long {
+            out.push("/SWAPLONG".to_string());
+        }
+
+        for params in &self.write_strings {
+            let mut value = format!("{};", hex(params.address));
+            if params.text.contains(';') || params.text.starts_with('"') {
+                value.push_str(&format!("\"{}\"", params.text));
+            } else {
+                value.push_str(&params.text);
+            }
+            if params.pad.nul_terminate {
+                value.push_str(";NUL");
+            }
+            if let Some(width) = params.pad.width {
+                value.push_str(&format!(";WIDTH={width}"));
+            }
+            if params.pad.fill != 0 {
+                value.push_str(&format!(";FILL={:02X}", params.pad.fill));
+            }
+            out.push(format!("/WS:{value}"));
+        }
+        for params in &self.write_dates {
+            out.push(format!("/WDATE:{};{}", hex(params.address), params.format));
+        }
+        if let Some(date) = self.date {
+            out.push(format!(
+                "/DATE:{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+                date.year, date.month, date.day, date.hour, date.minute, date.second
+            ));
+        }
+
+        for params in &self.checksum {
+            let prefix = if params.per_block { "CSB" } else { "CS" };
+            out.push(checksum_str(prefix, params));
+        }
+        for params in &self.checksum_multi {
+            out.push(checksum_str("CSM", params));
+        }
+        if let Some(ref path) = self.pipeline_file {
+            out.push(format!("/PIPE:{}", path_str(path)));
+        }
+
+        if let Some(ref dp) = self.data_processing {
+            let mut value = String::new();
+            if let Some(ref placement) = dp.placement {
+                value.push_str(&target_str(placement));
+                value.push(':');
+            }
+            match dp.external_command {
+                Some(ref command) => value.push_str(&format!("\"{command}\"")),
+                None => value.push_str(&dp.key_info),
+            }
+            let span = range_excludes_str(dp.range, &dp.exclude);
+            if dp.output_file.is_some() || !span.is_empty() {
+                value.push(';');
+            }
+            if let Some(ref path) = dp.output_file {
+                value.push_str(&path_str(path));
+            }
+            if !span.is_empty() {
+                value.push(';');
+                value.push_str(&span);
+            }
+            if dp.external_command.is_some() {
+                let len = dp.signature_len.map(|len| len.to_string());
+                out.push(format!("/DPEXT{}:{value}", len.unwrap_or_default()));
+            } else {
+                out.push(format!("/DP{}:{value}", dp.method));
+            }
+        }
+        if let Some(ref sv) = self.signature_verify {
+            let mut value = format!("{}!{}", sv.key_info, sv.signature_info);
+            let span = range_excludes_str(sv.range, &sv.exclude);
+            if !span.is_empty() {
+                value.push(';');
+                value.push_str(&span);
+            }
+            out.push(format!("/SV{}:{value}", sv.method));
+        }
+        if let Some(ref path) = self.compare_file {
+            out.push(format!("/CMP:{}", path_str(path)));
+        }
+        if let Some(ref path) = self.map_file {
+            out.push(format!("/MAPFILE:{}", path_str(path)));
+        }
+
+        if let Some(kb) = self.big_hex_file_threshold_kb {
+            out.push(format!("/BHFCT={kb}"));
+        }
+        if let Some(kb) = self.buffer_to_file_threshold_kb {
+            out.push(format!("/BTFST={kb}"));
+        }
+        if self.mmap_input {
+            out.push("/MMAP".to_string());
+        }
+        if let Some(kb) = self.temp_buffer_size_kb {
+            out.push(format!("/BTBS={kb}"));
+        }
+        if let Some(mb) = self.gzip_limit_mb {
+            out.push(format!("/GZMAX={mb}"));
+        }
+
+        if self.echo_args {
+            out.push("/ECHOARGS".to_string());
+        }
+        if self.info {
+            out.push("/INFO".to_string());
+        }
+        match self.help.as_deref() {
+            Some("") => out.push("/HELP".to_string()),
+            Some(topic) => out.push(format!("/HELP:{topic}")),
+            None => {}
+        }
+        if self.elf_use_vma {
+            out.push("/ELFVMA".to_string());
+        }
+        if self.lenient {
+            out.push("/LENIENT".to_string());
+        }
+        if let Some(format) = self.input_format {
+            out.push(format!("/IT:{}", format.name()));
+        }
+        if self.line_ending == crate::LineEnding::Lf {
+            out.push("/LF".to_string());
+        }
+        if self.lowercase_hex {
+            out.push("/LOWER".to_string());
+        }
+        if self.align_records {
+            out.push("/ALIGNREC".to_string());
+        }
+        if let Some(params) = self.bank {
+            out.push(format!("/BANK:{}", bank_str(params)));
+        }
+
+        if let Some(entry) = self.entry_point {
+            out.push(format!("/ENTRY:{}", hex(entry)));
+        }
+        if self.derive_output_path {
+            out.push("/INPLACE".to_string());
+        }
+        if self.create_output_dirs {
+            out.push("/MKDIR".to_string());
+        }
+        // `-o` goes first so a file-only output never takes the next format.
+        for output in &self.outputs {
+            if let Some(ref path) = output.file {
+                out.push("-o".to_string());
+                out.push(path_str(path));
+            }
+            if let Some(ref format) = output.format {
+                out.push(output_format_str(format, output.bytes_per_line));
+            }
+        }
+
+        if input_needs_dashes {
+            out.push("--".to_string());
+            out.extend(inputs);
+        }
+
+        out
+    }
+
+    /// Like [`Args::to_command_line`], joined into a single quoted string.
+    pub fn to_command_line_string(&self) -> String {
+        self.to_command_line()
+            .iter()
+            .map(|arg| quote_arg(arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+fn output_format_str(format: &OutputFormat, bytes_per_line: Option<u8>) -> String {
+    match format {
+        OutputFormat::IntelHex { record_type } => {
+            format!("/XI{}", record_params(bytes_per_line, *record_type))
+        }
+        OutputFormat::IntelHex16 { record_type } => {
+            format!("/XI2{}", record_params(bytes_per_line, *record_type))
+        }
+        OutputFormat::SRecord {
+            record_type,
+            header,
+        } => {
+            let mut s = format!("/XS{}", record_params(bytes_per_line, *record_type));
+            if let Some(header) = header {
+                s.push_str(&format!(":hdr={header}"));
+            }
+            s
+        }
+        OutputFormat::Binary { start, end, fill } => {
+            let params = [start.map(hex), end.map(hex), fill.map(|f| hex(f as u32))];
+            let used = params
+                .iter()
+                .rposition(Option::is_some)
+                .map_or(0, |i| i + 1);
+            let mut s = "/XN".to_string();
+            if used > 0 {
+                let parts: Vec<String> = params[..used]
+                    .iter()
+                    .map(|p| p.clone().unwrap_or_default())
+                    .collect();
+                s.push(':');
+                s.push_str(&parts.join(","));
+            }
+            s
+        }
+        OutputFormat::BinaryRaw => "/XN:RAW".to_string(),
+        OutputFormat::Json => "/XJ".to_string(),
+        OutputFormat::Csv => "/XCSV".to_string(),
+        OutputFormat::HexDump => format!("/XD{}", record_params(bytes_per_line, None)),
+        OutputFormat::HexAscii {
+            line_length,
+            separator,
+            address_prefix,
+        } => {
+            let mut s = "/XA".to_string();
+            if line_length.is_some() || separator.is_some() || address_prefix.is_some() {
+                s.push(':');
+                if let Some(len) = line_length {
+                    s.push_str(&hex(*len));
+                }
+            }
+            if separator.is_some() || address_prefix.is_some() {
+                s.push(':');
+                s.push_str(separator.as_deref().unwrap_or_default());
+            }
+            match address_prefix {
+                Some(crate::AddressPrefixStyle::At) => s.push_str(":ADDR"),
+                Some(crate::AddressPrefixStyle::Plain) => s.push_str(":PLAIN"),
+                None => {}
+            }
+            s
+        }
+        OutputFormat::CCode => "/XC".to_string(),
+        OutputFormat::FordIntelHex { relaxed: false } => "/XF".to_string(),
+        OutputFormat::FordIntelHex { relaxed: true } => "/XF:RELAXED".to_string(),
+        OutputFormat::GmHeader { addr } => optional_addr("/XG", *addr),
+        OutputFormat::GmHeaderOs { addr } => optional_addr("/XGC", *addr),
+        OutputFormat::GmHeaderCal { addr } => optional_addr("/XGCC", *addr),
+        OutputFormat::Gac => "/XGAC".to_string(),
+        OutputFormat::GacSwil => "/XGACSWIL".to_string(),
+        OutputFormat::FlashKernel => "/XK".to_string(),
+        OutputFormat::Porsche => "/XP".to_string(),
+        OutputFormat::SeparateBinary { template } => match template {
+            Some(template) => format!("/XSB:{template}"),
+            None => "/XSB".to_string(),
+        },
+        OutputFormat::Vag => "/XV".to_string(),
+        OutputFormat::Vbf => "/XVBF".to_string(),
+        OutputFormat::FiatBin => "/XB".to_string(),
+        OutputFormat::RustCode => "/XR".to_string(),
+    }
+}
+
+fn hex(value: u32) -> String {
+    format!("0x{value:X}")
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02X}")).collect()
+}
+
+fn path_str(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+fn range_str(range: &Range) -> String {
+    format!("{}-{}", hex(range.start()), hex(range.end()))
+}
+
+/// `range[/exclude...]`; empty when neither is set.
+fn range_excludes_str(range: Option<impl Into<RangeSpec>>, excludes: &[Range]) -> String {
+    let mut s = range
+        .map(|range| range.into().to_string())
+        .unwrap_or_default();
+    for exclude in excludes {
+        s.push('/');
+        s.push_str(&range_str(exclude));
+    }
+    s
+}
+
+fn ranges_str<R: Copy + Into<RangeSpec>>(ranges: &[R]) -> String {
+    ranges
+        .iter()
+        .map(|&range| range.into().to_string())
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn import_str(file: &Path, offset: u32) -> String {
+    if offset == 0 {
+        path_str(file)
+    } else {
+        format!("{};{}", path_str(file), hex(offset))
+    }
+}
+
+/// `window_start,window_size`, plus `,first` unless it is bank 0.
+fn bank_str(params: BankParams) -> String {
+    let window = format!(
+        "{},{}",
+        hex(params.window.start()),
+        hex(params.window.length())
+    );
+    match params.first_bank {
+        0 => window,
+        first => format!("{window},{}", hex(first.into())),
+    }
+}
+
+fn dspic_str(ops: &[DspicOp]) -> String {
+    ops.iter()
+        .map(|op| match op.target {
+            Some(target) => format!("{};{}", range_str(&op.range), hex(target)),
+            None => range_str(&op.range),
+        })
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn merges_str(params: &[MergeParam]) -> String {
+    params
+        .iter()
+        .map(|param| {
+            let mut s = path_str(&param.file);
+            if let Some(offset) = param.offset {
+                let sign = if offset < 0 { "-" } else { "" };
+                s.push_str(&format!(";{sign}0x{:X}", offset.unsigned_abs()));
+            }
+            if let Some(ref range) = param.range {
+                s.push(':');
+                s.push_str(&range_str(range));
+            }
+            s
+        })
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+fn target_str(target: &ChecksumTarget) -> String {
+    match target {
+        ChecksumTarget::Address(addr) => format!("@{}", hex(*addr)),
+        ChecksumTarget::Append => "@append".to_string(),
+        ChecksumTarget::Begin => "@begin".to_string(),
+        ChecksumTarget::Prepend => "@upfront".to_string(),
+        ChecksumTarget::OverwriteEnd => "@end".to_string(),
+        ChecksumTarget::File(path) => path_str(path),
+        ChecksumTarget::Symbol(name) => format!("@SYM:{name}"),
+        ChecksumTarget::Stdout => "@stdout".to_string(),
+    }
+}
+
+fn checksum_str(prefix: &str, params: &ChecksumParams) -> String {
+    let reverse = if params.little_endian { "R" } else { "" };
+    let mut s = format!(
+        "/{prefix}{reverse}{}:{}",
+        params.algorithm,
+        target_str(&params.target)
+    );
+    let span = range_excludes_str(params.range, &params.exclude_ranges);
+    if !span.is_empty() {
+        s.push(';');
+        s.push_str(&span);
+    }
+    if let Some(ref forced) = params.forced_range {
+        s.push_str(&format!(
+            ";!{}#{}",
+            range_str(&forced.range),
+            hex_bytes(&forced.pattern)
+        ));
+    }
+    if params.target_as_fill {
+        s.push_str(";X");
+    }
+    match params.gap_fill {
+        ChecksumGapFill::Skip => {}
+        ChecksumGapFill::Error => s.push_str(";FILL=NONE"),
+        ChecksumGapFill::Fill(byte) => s.push_str(&format!(";FILL={byte:02X}")),
+    }
+    if let Some(initial) = params.initial {
+        s.push_str(&format!(";INIT=0x{initial:X}"));
+    }
+    if params.format != ChecksumFormat::List {
+        s.push_str(&format!(";FMT={}", params.format.name()));
+    }
+    s
+}
+
+fn record_params(len: Option<u8>, record_type: Option<u8>) -> String {
+    match (len, record_type) {
+        (Some(len), Some(record_type)) => format!(":{}:{record_type}", hex(len as u32)),
+        (Some(len), None) => format!(":{}", hex(len as u32)),
+        _ => String::new(),
+    }
+}
+
+fn optional_addr(opt: &str, addr: Option<u32>) -> String {
+    match addr {
+        Some(addr) => format!("{opt}:{}", hex(addr)),
+        None => opt.to_string(),
+    }
+}
+
+/// Quote an argument so `Args::parse_from_str` splits it back unchanged.
+fn quote_arg(arg: &str) -> String {
+    let needs_quotes = arg.is_empty()
+        || arg
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\'));
+    if !needs_quotes {
+        return arg.to_string();
+    }
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    for c in arg.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(cmdline: &str) -> Args {
+        let args = Args::parse_from_str(cmdline).unwrap();
+        let emitted = args.to_command_line();
+        assert_eq!(
+            Args::parse_from(emitted.clone()).unwrap(),
+            args,
+            "{emitted:?}"
+        );
+        let joined = args.to_command_line_string();
+        assert_eq!(Args::parse_from_str(&joined).unwrap(), args, "{joined}");
+        args
+    }
+
+    #[test]
+    fn test_round_trip_every_option_category() {
+        round_trip(
+            "in.hex /E+=err.log /S /WERROR /V -vv /P:cfg.ini \"/FORD:mask_number=M 7\" /DEF:BUILD_ID=42 /II2=extra.hex /IN:raw.bin;0x100 \
+             /IA:data.txt /UNBANK:0x8000,0x4000,0x2 /S12XMAP /REMAP:0x8000-0xBFFF,0x10000,0x4000,0x8000 /MAPTAB:pages.txt \
+             /CDSPX:0x0-0xFF;0x1000:0x200-0x2FF /CDSPS:0x400-0x4FF /CDSPG:0x600,0x10 /CDSPI:0x700-0x7FE;0xFF \
+             /FR:0x1000,0x100:0x3000-0x30FF /FP:A55A /FRSEED:0xFFFFFFFFFFFF /CR:0x1010-0x101F:0x8000-END \
+             /MT:a.hex;-0x10:0x1000-0x10FF+b.s19;128 /MO:c.hex /LABEL:0x0-0x7FFF;boot \
+             /LABEL:0x8000,0x8000;application /AR:0x0-0xFFFF:ALL \
+             /L:cmds.log /LOGOUT:run.log /FA /PB:post.txt /AD:0x10 /AL /AF:0x00 /AE:0x800 /AEMAP:flash.sec /SB:0x400 /SBA:0x1000 \
+             /SWAPWORD /SWAPLONG /WS:0x1F00;v1.2;NUL;WIDTH=8;FILL=20 '/WS:0x1F10;\"a;b c\"' \
+             /WDATE:0x1F20;BCD:YYMMDDhhmm /WDATE:0x1F30;ASCII:YYYY-MM-DD /DATE:2026-03-07T09:05:30 \
+             /CSMR3:@0x2000;0x1000-0x1FFF/0x1100-0x11FF;FILL=00;INIT=0x1234 \
+             /CSM9:@end;!0x4000-0x4003#00;X;FILL=NONE /CSM0:@SYM:__app_crc;START-0x1FFF /PIPE:variant.toml /DP32:@append:key.pem;sig.bin;0x1000-0x1FFF/0x1F00-0x1F3F \
+             /SV4:pub.pem!sig.bin;0x1000-0x1FFF/0x1F00-0x1F3F /CMP:golden.hex /MAPFILE:app.map /BHFCT=1024 /BTFST=2048 /MMAP /BTBS=64 /GZMAX=16 /ECHOARGS /INFO /HELP:CS /LENIENT /IT:SREC /LF /LOWER /ALIGNREC /BANK:0x8000,0x8000 /MKDIR \
+             /XI:0x10:2 /ENTRY:0x8000400 -o out.hex",
+        );
+    }
+
+    #[test]
+    fn test_round_trip_page_maps_and_remaps() {
+        let args = round_trip(
+            "in.hex /S08MAP:0x10,0x8000,0x2000,0x200000 /REMAP:0x4000-0x7FFF,0x1F0000,0x4000,0x4000 \
+             /REMAP:0xC000-0xFFFF,0x1F4000,0x4000,0x4000",
+        );
+        assert_eq!(args.remap.len(), 2);
+        assert_eq!(
+            args.s08_map,
+            Some(PageMapParams::Paged {
+                ppage_base: 0x10,
+                window_start: 0x8000,
+                window_size: 0x2000,
+                linear_base: 0x200000,
+            })
+        );
+        assert_eq!(
+            round_trip("in.hex /S12MAP").s12_map,
+            Some(PageMapParams::Standard)
+        );
+        assert!(Args::parse_from_str("in.hex /S12MAP:0x100,0x8000,0x4000,0").is_err());
+    }
+
+    #[test]
+    fn test_round_trip_output_formats() {
+        for format in [
+            "/XS:0x20:3",
+            "/XI2",
+            "/XI2:0x10:1",
+            "/XS",
+            "/XS:0x10:2:hdr=BOOT",
+            "/XS:hdr=APP",
+            "/XN",
+            "/XN:0x1000",
+            "/XN:0x1000,0x1FFF,0x0",
+            "/XN:,,0xFF",
+            "/XN:RAW",
+            "/XD",
+            "/XJ",
+            "/XCSV",
+            "/XD:8",
+            "/XA:16:\", \"",
+            "/XA:16:' ':ADDR",
+            "/XA:::PLAIN",
+            "/XA",
+            "/XC",
+            "/XF",
+            "/XF:RELAXED",
+            "/XG:0x100",
+            "/XGC",
+            "/XGCC:0x10",
+            "/XGAC",
+            "/XGACSWIL",
+            "/XK",
+            "/XP",
+            "/XSB",
+            "/XSB:{stem}_{index:02}_{addr:08X}.{ext}",
+            "/XV",
+            "/XVBF",
+            "/XB",
+            "/XR",
+        ] {
+            round_trip(&format!("in.hex /CSR2:crc.bin {format} -o out"));
+        }
+    }
+
+    #[test]
+    fn test_round_trip_checksum_output_format() {
+        round_trip(
+            "in.hex /CS9:@stdout;0x0-0xFF;FMT=HEX /CSR0:crc.h;FMT=C /CS2:crc.txt -o out.hex",
+        );
+    }
+
+    #[test]
+    fn test_round_trip_block_checksums_keep_order() {
+        let args =
+            round_trip("in.hex /SB:0x100 /CSBR9:@end /CS0 /CSB2:@append;0x0-0xFFF -o out.hex");
+        let per_block: Vec<_> = args.checksum.iter().map(|cs| cs.per_block).collect();
+        assert_eq!(per_block, vec![true, false, true]);
+    }
+
+    #[test]
+    fn test_round_trip_external_signer_command() {
+        let args = Args::parse_from(vec![
+            "in.hex".into(),
+            "/DPEXT64:@0x1F00:\"sign {payload}; mv x {signature}\";sig.bin;0x0-0xFF".into(),
+        ])
+        .unwrap();
+        assert_eq!(Args::parse_from(args.to_command_line()).unwrap(), args);
+        let joined = args.to_command_line_string();
+        assert_eq!(Args::parse_from_str(&joined).unwrap(), args, "{joined}");
+    }
+
+    #[test]
+    fn test_round_trip_multiple_outputs() {
+        round_trip("in.hex /XI:0x20 -o a.hex /XN:0x0 -o a.bin -o copy.hex");
+        round_trip("in.hex -o a.hex -o b.hex /XS /XK");
+        let args = round_trip("in.hex /XS:0x10 -o a.s19 /XI -o a.hex");
+        assert_eq!(
+            args.to_command_line_string(),
+            "in.hex -o a.s19 /XS:0x10 -o a.hex /XI"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_quotes_paths() {
+        let args = round_trip(r#""dir with space/in.hex" /MO:"dir x\\cal.hex" -o "o'ut.bin""#);
+        assert_eq!(
+            args.to_command_line_string(),
+            r#""dir with space/in.hex" "/MO:dir x\\cal.hex" -o "o'ut.bin""#
+        );
+    }
+
+    #[test]
+    fn test_round_trip_multiple_inputs() {
+        let args = round_trip("a.hex cal_*.hex /MO:b.hex -o out.hex");
+        assert_eq!(
+            args.extra_inputs,
+            vec![std::path::PathBuf::from("cal_*.hex")]
+        );
+        let args =
+            Args::parse_from(vec!["a.hex".into(), "--".into(), "/abs/b.hex".into()]).unwrap();
+        assert_eq!(args.to_command_line(), vec!["--", "a.hex", "/abs/b.hex"]);
+    }
+
+    #[test]
+    fn test_round_trip_fill_patterns() {
+        for pattern in [
+            "/FP:INC",
+            "/FP:inc:0xF0:3",
+            "/FP:@pattern.bin",
+            "/FP:A55A /FP:@p.bin",
+        ] {
+            round_trip(&format!("in.hex /FR:0x0,0x100 {pattern}"));
+        }
+        let args = round_trip("in.hex /FP:@p.bin /FP:0102");
+        assert_eq!(args.fill_pattern_file, None);
+    }
+
+    #[test]
+    fn test_absolute_input_emitted_after_double_dash() {
+        let args = Args::parse_from(vec!["--".into(), "/abs/in.hex".into()]).unwrap();
+        assert_eq!(args.to_command_line(), vec!["--", "/abs/in.hex"]);
+        assert_eq!(Args::parse_from(args.to_command_line()).unwrap(), args);
+    }
+}
@@ -0,0 +1,108 @@
+//! Warnings and notes raised while a command runs (CLI: /S, /E, /WERROR).
+//!
+//! Each one goes to stderr as it is raised unless /S is given, and
+//! [`run`](super::run) appends them all to the /E log as timestamped
+//! `WARNING: ...` or `NOTE: ...` lines before any error. With /WERROR a warning fails the run
+//! before any output file appears, including warnings raised by the writers; notes never do.
+
+use std::cell::RefCell;
+use std::fmt;
+
+use super::error::CliError;
+use crate::PipelineRecord;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticLevel {
+    Warning,
+    Note,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub level: DiagnosticLevel,
+    pub message: String,
+}
+
+/// The /E log line: `WARNING: message` or `NOTE: message`.
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.level {
+            DiagnosticLevel::Warning => write!(f, "WARNING: {}", self.message),
+            DiagnosticLevel::Note => write!(f, "NOTE: {}", self.message),
+        }
+    }
+}
+
+/// Collects the diagnostics of one run; shared by `&` so loaders and
+/// /PB lines can all report into it.
+#[derive(Debug, Default)]
+pub(super) struct Diagnostics {
+    silent: bool,
+    entries: RefCell<Vec<Diagnostic>>,
+}
+
+impl Diagnostics {
+    pub(super) fn new(silent: bool) -> Self {
+        Self {
+            silent,
+            entries: RefCell::default(),
+        }
+    }
+
+    pub(super) fn warn(&self, message: impl Into<String>) {
+        self.push(DiagnosticLevel::Warning, message.into());
+    }
+
+    pub(super) fn note(&self, message: impl Into<String>) {
+        self.push(DiagnosticLevel::Note, message.into());
+    }
+
+    fn push(&self, level: DiagnosticLevel, message: String) {
+        if !self.silent {
+            match level {
+                DiagnosticLevel::Warning => eprintln!("Warning: {message}"),
+                DiagnosticLevel::Note => eprintln!("Note: {message}"),
+            }
+        }
+        self.entries
+            .borrow_mut()
+            .push(Diagnostic { level, message });
+    }
+
+    /// Report and clear the warnings and notes of a pipeline run.
+    pub(super) fn take_from(&self, record: &mut PipelineRecord) {
+        for message in std::mem::take(&mut record.warnings) {
+            self.warn(message);
+        }
+        for message in std::mem::take(&mut record.notes) {
+            self.note(message);
+        }
+    }
+
+    /// /WERROR: fail if anything has been warned about so far.
+    pub(super) fn check_werror(&self, werror: bool) -> Result<(), CliError> {
+        let count = self
+            .entries
+            .borrow()
+            .iter()
+            .filter(|d| d.level == DiagnosticLevel::Warning)
+            .count();
+        if werror && count > 0 {
+            return Err(CliError::Warnings(count));
+        }
+        Ok(())
+    }
+
+    pub(super) fn entries(&self) -> Vec<Diagnostic> {
+        self.entries.borrow().clone()
+    }
+
+    /// The /E log lines so far, stamped with `time`, each ending in a newline.
+    pub(super) fn log_text(&self, time: &crate::DateStamp) -> String {
+        self.entries
+            .borrow()
+            .iter()
+            .map(|d| format!("{} {d}\n", super::log_time(time)))
+            .collect()
+    }
+}
@@ -1,7 +1,14 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
 use thiserror::Error;
 
-use super::types::ParseArgError;
+use super::diagnostics::{Diagnostic, DiagnosticLevel};
+use super::types::{OutputFormat, ParseArgError};
 
+/// Why a run failed. Callers can match on the variant (after
+/// [`CliError::without_context`]) and follow [`std::error::Error::source`]
+/// down to the library error.
 #[derive(Debug, Error)]
 pub enum CliError {
     #[error(transparent)]
@@ -13,14 +20,174 @@ pub enum CliError {
     #[error(transparent)]
     Parse(#[from] crate::ParseError),
     #[error(transparent)]
-    Io(#[from] std::io::Error),
+    Range(#[from] crate::RangeError),
+    /// Reading or writing a file failed; `option` and `path` say which one
+    /// when known, e.g. `/MT:cal2.hex: No such file or directory`.
+    #[error("{}{source}", file_prefix(option.as_deref(), path.as_deref()))]
+    Io {
+        option: Option<String>,
+        path: Option<PathBuf>,
+        source: std::io::Error,
+    },
+    #[error("input parsed as {format} (forced by /IT): {source}")]
+    ForcedInput {
+        format: &'static str,
+        source: crate::ParseError,
+    },
+    /// A settings file (/P INI, /FP pattern, /MAPFILE) lacks something the
+    /// run needs, e.g. `missing [GAC] PartNumber`.
+    #[error("{0}")]
+    Config(String),
+    /// /DP or /SV could not load a key or compute, place or write a signature.
+    #[error("{option}: {message}")]
+    Signature { option: String, message: String },
     #[error("{0}")]
     Unsupported(String),
+    /// A /SV signature did not verify.
     #[error("{0}")]
-    Other(String),
+    Verification(String),
+    /// /WERROR and this many warnings.
+    #[error("{0} warning(s) treated as errors (/WERROR)")]
+    Warnings(usize),
+    /// An error tagged with where it happened, e.g. `/MO:cal2.hex` or a /PB line.
+    #[error("{context}: {source}")]
+    Context {
+        context: String,
+        source: Box<CliError>,
+    },
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(source: std::io::Error) -> Self {
+        Self::Io {
+            option: None,
+            path: None,
+            source,
+        }
+    }
+}
+
+/// Process exit codes, one per error category. 0 is success and 1 is unused.
+pub const EXIT_ARGUMENT: u8 = 2;
+pub const EXIT_IO: u8 = 3;
+pub const EXIT_OPERATION: u8 = 4;
+pub const EXIT_VERIFICATION: u8 = 5;
+pub const EXIT_UNSUPPORTED: u8 = 6;
+
+impl CliError {
+    /// Exit code for this error's category.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Arg(_) => EXIT_ARGUMENT,
+            // A writer refusing the image is a processing failure, not a bad file.
+            Self::Parse(crate::ParseError::InvalidOutput(_)) => EXIT_OPERATION,
+            Self::Io { .. } | Self::Parse(_) | Self::ForcedInput { .. } => EXIT_IO,
+            Self::Ops(_)
+            | Self::Log(_)
+            | Self::Range(_)
+            | Self::Config(_)
+            | Self::Signature { .. }
+            | Self::Warnings(_) => EXIT_OPERATION,
+            Self::Verification(_) => EXIT_VERIFICATION,
+            Self::Unsupported(_) => EXIT_UNSUPPORTED,
+            Self::Context { source, .. } => source.exit_code(),
+        }
+    }
+
+    /// The error under any `Context` tags.
+    pub fn without_context(&self) -> &CliError {
+        match self {
+            Self::Context { source, .. } => source.without_context(),
+            other => other,
+        }
+    }
+
+    pub fn with_context(self, context: impl Into<String>) -> Self {
+        Self::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// Name the option and file an error came from. An I/O error that has
+    /// neither records them; anything else is wrapped in `Context`.
+    pub fn in_file(self, option: &str, path: &Path) -> Self {
+        let option = (!option.is_empty()).then(|| option.to_string());
+        match self {
+            Self::Io {
+                option: None,
+                path: None,
+                source,
+            } => Self::Io {
+                option,
+                path: Some(path.to_path_buf()),
+                source,
+            },
+            other => other.with_context(match option {
+                Some(option) => format!("{option}:{}", path.display()),
+                None => path.display().to_string(),
+            }),
+        }
+    }
+}
+
+/// `/MT:cal2.hex: `, `cal2.hex: `, `/LOGOUT: ` or nothing.
+fn file_prefix(option: Option<&str>, path: Option<&Path>) -> String {
+    match (option, path) {
+        (Some(option), Some(path)) => format!("{option}:{}: ", path.display()),
+        (None, Some(path)) => format!("{}: ", path.display()),
+        (Some(option), None) => format!("{option}: "),
+        (None, None) => String::new(),
+    }
+}
+
+/// [`CliError::in_file`] for `map_err`: `option` is `"/MT"`, or empty for a
+/// positional input.
+pub(super) fn file_context(option: &str, path: &Path) -> impl FnOnce(CliError) -> CliError {
+    move |e| e.in_file(option, path)
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct ExecuteOutput {
-    pub checksum_bytes: Option<Vec<u8>>,
+    /// Result of every /CS or /CSM checksum, in command-line order, then
+    /// those of the /PIPE description.
+    pub checksum_bytes: Vec<Vec<u8>>,
+    /// Every export in command-line order. Only
+    /// [`execute_in_memory_captured`](super::execute_in_memory_captured)
+    /// fills this; other runs write the files.
+    pub outputs: Vec<CapturedOutput>,
+    /// Checksum, /DP signature and /LOGOUT files by path, when captured.
+    pub files: BTreeMap<PathBuf, Vec<u8>>,
+    /// Warnings and notes in the order they were reported.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ExecuteOutput {
+    /// How many of [`Self::diagnostics`] are warnings.
+    pub fn warning_count(&self) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.level == DiagnosticLevel::Warning)
+            .count()
+    }
+}
+
+/// One captured export.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedOutput {
+    /// The format written; /XI when none was given.
+    pub format: OutputFormat,
+    /// Contents by file name: one entry for most formats, one per segment
+    /// for /XSB and the `.c`/`.h` pair for /XC.
+    pub files: BTreeMap<String, Vec<u8>>,
+}
+
+impl CapturedOutput {
+    /// The contents of a single-file export.
+    pub fn bytes(&self) -> Option<&[u8]> {
+        match self.files.len() {
+            1 => self.files.values().next().map(Vec::as_slice),
+            _ => None,
+        }
+    }
 }
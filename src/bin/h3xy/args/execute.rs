@@ -1,26 +1,54 @@
-use crate::{
-    AlignOptions, ChecksumAlgorithm, Pipeline, PipelineDspic, PipelineError, PipelineMerge, Range,
-    RemapOptions,
-};
+//! Running parsed arguments: one driver for files and in-memory blocks, with
+//! the pipeline, checksums, /PB, and reporting in submodules.
+
+mod checksums;
+mod input;
+mod pipeline;
+mod postbuild;
+mod report;
+
+use std::collections::HashMap;
 
-use super::error::{CliError, ExecuteOutput};
-use super::io::{FsProvider, ReadProvider, write_output_for_args};
-use super::io::{load_binary_input, load_hex_ascii_input, load_input, load_intel_hex_16bit_input};
+use crate::{HexFile, PipelineError, PipelineRecord, Range, StageTrace};
+
+use super::diagnostics::Diagnostics;
+use super::error::{CliError, ExecuteOutput, file_context};
+use super::io::{
+    DEFAULT_BUFFER_KB, DEFAULT_GZIP_LIMIT_MB, FsProvider, GzipProvider, MappedInputGuard,
+    ReadProvider, StagedWriter, WriteProvider, missing_output_file, write_output_for_args,
+};
 use super::signature::{
     apply_data_processing, apply_signature_verification, is_supported_data_processing_method,
     is_supported_signature_verify_method,
 };
-use super::types::{Args, ChecksumParams, ChecksumTarget, ParseArgError};
-use std::collections::HashMap;
-use std::path::Path;
+use super::types::Args;
+use input::Inputs;
 
 impl Args {
-    fn wrap_error<T, E: std::fmt::Display>(
+    /// /VERBOSE: a line per pipeline step on stderr; -vv adds its segments.
+    fn trace_stage(&self, stage: &StageTrace) {
+        if self.silent || self.verbose == 0 {
+            return;
+        }
+        eprintln!("{stage}");
+        if self.verbose > 1 {
+            for segment in stage.hexfile.segments().iter().filter(|s| !s.is_empty()) {
+                eprintln!(
+                    "  0x{:X}-0x{:X} ({} byte(s))",
+                    segment.start_address,
+                    segment.end_address(),
+                    segment.len()
+                );
+            }
+        }
+    }
+
+    fn wrap_error<T, E: Into<CliError>>(
         &self,
         opt: &str,
         res: Result<T, E>,
     ) -> Result<T, CliError> {
-        res.map_err(|e| CliError::Other(format!("{opt}: {e}")))
+        res.map_err(|e| e.into().with_context(opt))
     }
 
     fn validate_supported_features(&self) -> Result<(), CliError> {
@@ -29,27 +57,20 @@ impl Args {
                 "cannot combine /MT and /MO in one command".into(),
             ));
         }
-        if self.s12_map && self.s12x_map {
+        // Each page map expects banked input, so only one applies; /REMAP
+        // runs after it on the mapped addresses.
+        if self.s12_map.is_some() && self.s12x_map.is_some() {
             return Err(CliError::Unsupported(
                 "cannot combine /S12MAP and /S12XMAP".into(),
             ));
         }
-        if self.s08_map && (self.s12_map || self.s12x_map) {
+        if self.s08_map.is_some() && (self.s12_map.is_some() || self.s12x_map.is_some()) {
             return Err(CliError::Unsupported(
                 "cannot combine /S08MAP with /S12MAP or /S12XMAP".into(),
             ));
         }
-        if self.remap.is_some() && (self.s12_map || self.s12x_map || self.s08_map) {
-            return Err(CliError::Unsupported(
-                "cannot combine /REMAP with /S12MAP or /S12XMAP".into(),
-            ));
-        }
-        if self.postbuild.is_some() {
-            return Err(CliError::Unsupported(
-                "postbuild (/PB) is not supported yet".into(),
-            ));
-        }
         if let Some(ref params) = self.data_processing
+            && params.external_command.is_none()
             && !is_supported_data_processing_method(params.method)
         {
             return Err(CliError::Unsupported(format!(
@@ -83,7 +104,7 @@ impl Args {
                 "explicit import (/IN, /II2) cannot be combined with input file".into(),
             ));
         }
-        if self.checksum.is_some() && !self.checksum_multi.is_empty() {
+        if !self.checksum.is_empty() && !self.checksum_multi.is_empty() {
             return Err(CliError::Unsupported(
                 "cannot combine /CS* with /CSM* in one command".into(),
             ));
@@ -91,405 +112,192 @@ impl Args {
         Ok(())
     }
 
+    /// Every export needs a `-o` unless /INPLACE derives it. Checked for the
+    /// whole command only: /PB lines pair their exports with the command's.
+    /// /INFO exports nothing.
+    fn validate_output_files(&self) -> Result<(), CliError> {
+        if self.derive_output_path || self.postbuild.is_some() || self.info {
+            return Ok(());
+        }
+        let unnamed = self.outputs.iter().find(|output| output.file.is_none());
+        match unnamed.and_then(|output| output.format.as_ref()) {
+            Some(format) => Err(missing_output_file(format)),
+            None => Ok(()),
+        }
+    }
+
     /// Execute the parsed arguments in HexView processing order.
     pub fn execute(&self) -> Result<ExecuteOutput, CliError> {
         let provider = FsProvider;
-        self.execute_with_provider(&provider)
+        self.execute_with_provider(&provider, &provider, &Diagnostics::new(self.silent))
     }
 
+    /// `diagnostics` keeps what was raised even when the run fails.
     pub(super) fn execute_with_provider<P: ReadProvider>(
         &self,
         provider: &P,
+        writer: &dyn WriteProvider,
+        diagnostics: &Diagnostics,
     ) -> Result<ExecuteOutput, CliError> {
-        self.validate_supported_features()?;
-
-        let hexfile = self.load_hexfile(provider)?;
-        let pipeline = self.build_pipeline(hexfile, provider)?;
-        let result = pipeline
-            .execute(random_fill_bytes, |path| load_input(provider, path))
-            .map_err(|e| match e {
-                PipelineError::Ops(err) => CliError::Other(err.to_string()),
-                PipelineError::Log(err) => CliError::Other(format!("/L: {err}")),
-        })?;
-        let mut hexfile = result.hexfile;
-        let checksum_bytes = self.apply_checksums(&mut hexfile)?;
-        let _signature_bytes = self.apply_data_processing(&mut hexfile)?;
-        self.apply_signature_verification(&hexfile)?;
-        self.write_outputs(&hexfile, provider)?;
-
-        Ok(ExecuteOutput { checksum_bytes })
+        self.execute_from(provider, None, writer, diagnostics)
     }
 
+    /// As [`Self::execute_with_provider`] on disk, but paths naming one of
+    /// `blocks` load that block instead.
     pub(super) fn execute_with_blocks(
         &self,
-        blocks: &HashMap<String, crate::HexFile>,
+        blocks: &HashMap<String, HexFile>,
+        writer: &dyn WriteProvider,
+        diagnostics: &Diagnostics,
     ) -> Result<ExecuteOutput, CliError> {
-        self.validate_supported_features()?;
-
-        let provider = FsProvider;
-        let hexfile = self.load_hexfile_from_blocks(blocks, &provider)?;
-        let pipeline = self.build_pipeline_from_blocks(hexfile, &provider, blocks)?;
-        let result = pipeline
-            .execute(random_fill_bytes, |path| load_block(blocks, path))
-            .map_err(|e| match e {
-                PipelineError::Ops(err) => CliError::Other(err.to_string()),
-                PipelineError::Log(err) => CliError::Other(format!("/L: {err}")),
-        })?;
-        let mut hexfile = result.hexfile;
-        let checksum_bytes = self.apply_checksums(&mut hexfile)?;
-        let _signature_bytes = self.apply_data_processing(&mut hexfile)?;
-        self.apply_signature_verification(&hexfile)?;
-        self.write_outputs(&hexfile, &provider)?;
-
-        Ok(ExecuteOutput { checksum_bytes })
+        self.execute_from(&FsProvider, Some(blocks), writer, diagnostics)
     }
 
-    fn build_pipeline<P: ReadProvider>(
+    fn execute_from<P: ReadProvider>(
         &self,
-        hexfile: crate::HexFile,
         provider: &P,
-    ) -> Result<Pipeline, CliError> {
-        let log_commands = if let Some(ref path) = self.log_file {
-            let content = provider
-                .read_string(path)
-                .map_err(|e| CliError::Other(format!("/L: {e}")))?;
-            Some(
-                crate::parse_log_commands(&content)
-                    .map_err(|e| CliError::Other(format!("/L: {e}")))?,
-            )
-        } else {
-            None
-        };
-
-        let mut merge_transparent = Vec::with_capacity(self.merge_transparent.len());
-        for merge in &self.merge_transparent {
-            let other = load_input(provider, &merge.file)?;
-            merge_transparent.push(PipelineMerge {
-                other,
-                offset: merge.offset.unwrap_or(0),
-                range: merge.range,
-            });
-        }
-        let mut merge_opaque = Vec::with_capacity(self.merge_opaque.len());
-        for merge in &self.merge_opaque {
-            let other = load_input(provider, &merge.file)?;
-            merge_opaque.push(PipelineMerge {
-                other,
-                offset: merge.offset.unwrap_or(0),
-                range: merge.range,
-            });
-        }
-
-        let align = self.align_address.map(|alignment| AlignOptions {
-            alignment,
-            fill_byte: self.align_fill,
-            align_length: self.align_length,
+        blocks: Option<&HashMap<String, HexFile>>,
+        writer: &dyn WriteProvider,
+        diagnostics: &Diagnostics,
+    ) -> Result<ExecuteOutput, CliError> {
+        self.validate_supported_features()?;
+        self.validate_output_files()?;
+        let guard = self.mapped_input().map(|mapped| MappedInputGuard {
+            inner: writer,
+            mapped,
         });
+        let writer = guard.as_ref().map_or(writer, |guard| guard as _);
+        // With /WERROR nothing is written until the last writer has had its
+        // chance to warn.
+        let staged = self.werror.then(|| StagedWriter::new(writer));
+        let writer = staged.as_ref().map_or(writer, |staged| staged as _);
 
-        Ok(Pipeline {
-            hexfile,
-            fill_ranges: self.fill_ranges.clone(),
-            fill_pattern: if self.fill_pattern_set {
-                Some(self.fill_pattern.clone())
-            } else {
-                None
-            },
-            cut_ranges: self.cut_ranges.clone(),
-            merge_transparent,
-            merge_opaque,
-            address_ranges: self.address_range.clone(),
-            log_commands,
-            fill_all: if self.fill_all {
-                Some(self.align_fill)
-            } else {
-                None
-            },
-            align,
-            split: self.split_block_size,
-            swap_word: self.swap_word,
-            swap_long: self.swap_long,
-            checksum: None,
-            map_star12: self.s12_map,
-            map_star12x: self.s12x_map,
-            map_star08: self.s08_map,
-            remap: self.remap.as_ref().map(|remap| RemapOptions {
-                start: remap.start,
-                end: remap.end,
-                linear: remap.linear,
-                size: remap.size,
-                inc: remap.inc,
-            }),
-            dspic_expand: self
-                .dspic_expand
-                .iter()
-                .map(|op| PipelineDspic {
-                    range: op.range,
-                    target: op.target,
-                })
-                .collect(),
-            dspic_shrink: self
-                .dspic_shrink
-                .iter()
-                .map(|op| PipelineDspic {
-                    range: op.range,
-                    target: op.target,
-                })
-                .collect(),
-            dspic_clear_ghost: self.dspic_clear_ghost.clone(),
-        })
-    }
-
-    fn build_pipeline_from_blocks<P: ReadProvider>(
-        &self,
-        hexfile: crate::HexFile,
-        provider: &P,
-        blocks: &HashMap<String, crate::HexFile>,
-    ) -> Result<Pipeline, CliError> {
-        let log_commands = if let Some(ref path) = self.log_file {
-            let content = provider
-                .read_string(path)
-                .map_err(|e| CliError::Other(format!("/L: {e}")))?;
-            Some(
-                crate::parse_log_commands(&content)
-                    .map_err(|e| CliError::Other(format!("/L: {e}")))?,
-            )
-        } else {
-            None
+        let provider = &self.gzip_provider(provider);
+        let inputs = Inputs {
+            provider,
+            blocks,
+            diagnostics,
         };
-
-        let mut merge_transparent = Vec::with_capacity(self.merge_transparent.len());
-        for merge in &self.merge_transparent {
-            let other = load_block(blocks, &merge.file)?;
-            merge_transparent.push(PipelineMerge {
-                other,
-                offset: merge.offset.unwrap_or(0),
-                range: merge.range,
-            });
-        }
-        let mut merge_opaque = Vec::with_capacity(self.merge_opaque.len());
-        for merge in &self.merge_opaque {
-            let other = load_block(blocks, &merge.file)?;
-            merge_opaque.push(PipelineMerge {
-                other,
-                offset: merge.offset.unwrap_or(0),
-                range: merge.range,
-            });
-        }
-
-        let align = self.align_address.map(|alignment| AlignOptions {
-            alignment,
-            fill_byte: self.align_fill,
-            align_length: self.align_length,
-        });
-
-        Ok(Pipeline {
-            hexfile,
-            fill_ranges: self.fill_ranges.clone(),
-            fill_pattern: if self.fill_pattern_set {
-                Some(self.fill_pattern.clone())
-            } else {
-                None
-            },
-            cut_ranges: self.cut_ranges.clone(),
-            merge_transparent,
-            merge_opaque,
-            address_ranges: self.address_range.clone(),
-            log_commands,
-            fill_all: if self.fill_all {
-                Some(self.align_fill)
-            } else {
-                None
-            },
-            align,
-            split: self.split_block_size,
-            swap_word: self.swap_word,
-            swap_long: self.swap_long,
-            checksum: None,
-            map_star12: self.s12_map,
-            map_star12x: self.s12x_map,
-            map_star08: self.s08_map,
-            remap: self.remap.as_ref().map(|remap| RemapOptions {
-                start: remap.start,
-                end: remap.end,
-                linear: remap.linear,
-                size: remap.size,
-                inc: remap.inc,
-            }),
-            dspic_expand: self
-                .dspic_expand
-                .iter()
-                .map(|op| PipelineDspic {
-                    range: op.range,
-                    target: op.target,
-                })
-                .collect(),
-            dspic_shrink: self
-                .dspic_shrink
-                .iter()
-                .map(|op| PipelineDspic {
-                    range: op.range,
-                    target: op.target,
-                })
-                .collect(),
-            dspic_clear_ghost: self.dspic_clear_ghost.clone(),
-        })
-    }
-
-    fn load_hexfile<P: ReadProvider>(&self, provider: &P) -> Result<crate::HexFile, CliError> {
-        if let Some(ref import) = self.import_binary {
-            return load_binary_input(provider, &import.file, import.offset);
-        }
-        if let Some(ref import) = self.import_hex_ascii {
-            let ascii = load_hex_ascii_input(provider, &import.file, import.offset)?;
-            if let Some(ref path) = self.input_file {
-                let mut base = load_input(provider, path)?;
-                if super::io::hexfiles_overlap(&base, &ascii) {
-                    if !self.silent {
-                        eprintln!("Warning: /IA overlaps input file; ignoring input file");
-                    }
-                    return Ok(ascii);
-                }
-                for segment in ascii.segments() {
-                    base.append_segment(segment.clone());
-                }
-                return Ok(base);
-            }
-            return Ok(ascii);
+        let hexfile = self.unbank(self.load_hexfile(&inputs)?)?;
+        let mut pipeline = self.build_pipeline(hexfile, &inputs)?;
+        if self.info {
+            return self.print_info(pipeline, &inputs);
         }
-        if let Some(ref import) = self.import_i16 {
-            return load_intel_hex_16bit_input(provider, import);
+        let deferred_fill = self.defer_fill_all(&mut pipeline);
+        let checksums = pipeline.checksums.clone();
+        let mut result = pipeline
+            .execute_traced(
+                random_fill(self.fill_seed),
+                |path| inputs.load(self, path).map_err(file_context("", path)),
+                |stage| self.trace_stage(stage),
+            )
+            .map_err(|e| self.pipeline_error(e))?;
+        diagnostics.take_from(&mut result.record);
+        let mut checksum_bytes = self.finish_checksums(
+            &checksums,
+            result.checksum_bytes,
+            &mut result.record,
+            writer,
+        )?;
+        let mut record = self.input_record();
+        record.append(result.record);
+        let mut hexfile = result.hexfile;
+        let gap_fill = self.finish_fill_all(deferred_fill, &mut hexfile, &mut record)?;
+        if self.entry_point.is_some() {
+            hexfile.metadata_mut().entry_point = self.entry_point;
         }
-        if let Some(ref path) = self.input_file {
-            return load_input(provider, path);
+        checksum_bytes.extend(self.apply_pipeline_file(
+            &mut hexfile,
+            &mut record,
+            &inputs,
+            writer,
+        )?);
+        let _signature_bytes = self.apply_data_processing(&mut hexfile, &mut record, writer)?;
+        self.apply_signature_verification(&hexfile)?;
+        diagnostics.take_from(&mut record);
+        let export = self.run_postbuild(&mut hexfile, &mut checksum_bytes, &inputs, writer)?;
+        if self.postbuild.is_some() {
+            record.skip("/PB");
         }
-        if self.log_file.is_some() {
-            return Ok(crate::HexFile::new());
+        // Every writer and /CMP read the normalized image; share one copy.
+        hexfile.ensure_normalized();
+        self.write_log_output(&record, writer)?;
+        export.as_ref().unwrap_or(self).write_outputs(
+            &hexfile,
+            gap_fill,
+            provider,
+            writer,
+            diagnostics,
+        )?;
+        diagnostics.check_werror(self.werror)?;
+        if let Some(staged) = staged {
+            staged.commit()?;
         }
-        Err(ParseArgError::MissingInputFile.into())
-    }
+        self.apply_compare(&hexfile, |path| inputs.load(self, path))?;
 
-    fn load_hexfile_from_blocks(
-        &self,
-        blocks: &HashMap<String, crate::HexFile>,
-        provider: &impl ReadProvider,
-    ) -> Result<crate::HexFile, CliError> {
-        if let Some(ref import) = self.import_binary {
-            return load_binary_input(provider, &import.file, import.offset);
-        }
-        if let Some(ref import) = self.import_hex_ascii {
-            let ascii = load_hex_ascii_input(provider, &import.file, import.offset)?;
-            if let Some(ref path) = self.input_file {
-                let mut base = load_block(blocks, path)?;
-                if super::io::hexfiles_overlap(&base, &ascii) {
-                    if !self.silent {
-                        eprintln!("Warning: /IA overlaps input file; ignoring input file");
-                    }
-                    return Ok(ascii);
-                }
-                for segment in ascii.segments() {
-                    base.append_segment(segment.clone());
-                }
-                return Ok(base);
-            }
-            return Ok(ascii);
-        }
-        if let Some(ref import) = self.import_i16 {
-            return load_intel_hex_16bit_input(provider, import);
-        }
-        if let Some(ref path) = self.input_file {
-            return load_block(blocks, path);
-        }
-        if self.log_file.is_some() {
-            return Ok(crate::HexFile::new());
-        }
-        Err(ParseArgError::MissingInputFile.into())
+        Ok(ExecuteOutput {
+            checksum_bytes,
+            diagnostics: diagnostics.entries(),
+            ..ExecuteOutput::default()
+        })
     }
 
-    fn apply_checksums(&self, hexfile: &mut crate::HexFile) -> Result<Option<Vec<u8>>, CliError> {
-        let mut legacy_bytes = None;
-        if let Some(cs_params) = self.checksum.as_ref() {
-            legacy_bytes = Some(self.run_checksum(hexfile, cs_params, false)?);
-        }
-        for cs_params in &self.checksum_multi {
-            self.run_checksum(hexfile, cs_params, true)?;
+    fn gzip_provider<'a, P: ReadProvider>(&self, provider: &'a P) -> GzipProvider<'a, P> {
+        let limit_mb = self.gzip_limit_mb.unwrap_or(DEFAULT_GZIP_LIMIT_MB);
+        GzipProvider {
+            inner: provider,
+            limit: limit_mb as u64 * 1024 * 1024,
         }
-        Ok(legacy_bytes)
     }
 
-    fn run_checksum(
-        &self,
-        hexfile: &mut crate::HexFile,
-        cs_params: &ChecksumParams,
-        is_multi: bool,
-    ) -> Result<Vec<u8>, CliError> {
-        let opt_base = if is_multi {
-            if cs_params.little_endian {
-                "/CSMR"
-            } else {
-                "/CSM"
-            }
-        } else if cs_params.little_endian {
-            "/CSR"
-        } else {
-            "/CS"
-        };
-        let opt = format!("{opt_base}{}", cs_params.algorithm);
-        let algorithm =
-            self.wrap_error(&opt, ChecksumAlgorithm::from_index(cs_params.algorithm))?;
-        let forced_range = cs_params
-            .forced_range
-            .as_ref()
-            .map(|forced| crate::ForcedRange {
-                range: forced.range,
-                pattern: forced.pattern.clone(),
-            });
-        let options = crate::ChecksumOptions {
-            algorithm,
-            range: cs_params.range,
-            little_endian_output: cs_params.little_endian,
-            forced_range,
-            exclude_ranges: cs_params.exclude_ranges.clone(),
-            target_exclude: None,
-        };
-        let target = self.resolve_checksum_target(hexfile, &cs_params.target);
-        let result = self.wrap_error(&opt, hexfile.checksum(&options, &target))?;
-        if let ChecksumTarget::File(path) = &cs_params.target {
-            let formatted = result
-                .iter()
-                .map(|b| format!("{:02X}", b))
-                .collect::<Vec<_>>()
-                .join(",");
-            self.wrap_error(&opt, std::fs::write(path, formatted))?;
-        }
-        Ok(result)
+    /// /BTBS in bytes.
+    pub(super) fn buffer_size(&self) -> usize {
+        (self.temp_buffer_size_kb.unwrap_or(DEFAULT_BUFFER_KB) as usize * 1024).max(1)
     }
 
-    fn resolve_checksum_target(
-        &self,
-        hexfile: &crate::HexFile,
-        target: &ChecksumTarget,
-    ) -> crate::ChecksumTarget {
-        match target {
-            ChecksumTarget::Address(addr) => crate::ChecksumTarget::Address(*addr),
-            ChecksumTarget::Append => crate::ChecksumTarget::Append,
-            ChecksumTarget::Begin => {
-                if let Some(start) = hexfile.min_address() {
-                    crate::ChecksumTarget::Address(start)
+    /// `err` with the option a failed stamp or checksum came from.
+    fn pipeline_error(&self, err: PipelineError) -> CliError {
+        match err {
+            PipelineError::Ops(err) => CliError::Ops(err),
+            PipelineError::Log(err) => CliError::Log(err).with_context("/L"),
+            PipelineError::Range(err) => CliError::Range(err),
+            PipelineError::Stamp { index, source } => {
+                let opt = if index < self.write_strings.len() {
+                    "/WS"
                 } else {
-                    crate::ChecksumTarget::Append
-                }
+                    "/WDATE"
+                };
+                self.pipeline_error(*source).with_context(opt)
+            }
+            PipelineError::Checksum { index, source } => {
+                let opt = self
+                    .checksum_jobs()
+                    .nth(index)
+                    .map(|(cs, opt_base)| format!("{opt_base}{}", cs.algorithm))
+                    .unwrap_or_default();
+                self.pipeline_error(*source).with_context(opt)
             }
-            ChecksumTarget::Prepend => crate::ChecksumTarget::Prepend,
-            ChecksumTarget::OverwriteEnd => crate::ChecksumTarget::OverwriteEnd,
-            ChecksumTarget::File(path) => crate::ChecksumTarget::File(path.clone()),
+            PipelineError::Step { index, source } => self
+                .pipeline_error(*source)
+                .with_context(format!("step {index}")),
         }
     }
 
-    fn apply_data_processing(&self, hexfile: &mut crate::HexFile) -> Result<Option<Vec<u8>>, CliError> {
+    fn apply_data_processing(
+        &self,
+        hexfile: &mut crate::HexFile,
+        record: &mut PipelineRecord,
+        writer: &dyn WriteProvider,
+    ) -> Result<Option<Vec<u8>>, CliError> {
         let Some(ref params) = self.data_processing else {
             return Ok(None);
         };
-        apply_data_processing(hexfile, params)
+        record.skip(if params.external_command.is_some() {
+            "/DPEXT"
+        } else {
+            "/DP"
+        });
+        apply_data_processing(hexfile, params, self.create_output_dirs, writer)
     }
 
     fn apply_signature_verification(&self, hexfile: &crate::HexFile) -> Result<(), CliError> {
@@ -499,29 +307,45 @@ impl Args {
         apply_signature_verification(hexfile, params)
     }
 
+    /// `gap_fill` is a /FA left to the writers by [`Self::defer_fill_all`].
+    /// With /BANK every output gets the banked image.
     fn write_outputs<P: ReadProvider>(
         &self,
         hexfile: &crate::HexFile,
+        gap_fill: Option<u8>,
         provider: &P,
+        writer: &dyn WriteProvider,
+        diagnostics: &Diagnostics,
     ) -> Result<(), CliError> {
-        write_output_for_args(self, hexfile, provider)
+        let banked;
+        let hexfile = match self.bank {
+            Some(params) => {
+                let mut image = hexfile
+                    .to_banked(params.window, params.window.length(), params.first_bank)
+                    .map_err(|e| CliError::from(e).with_context("/BANK"))?;
+                image.ensure_normalized();
+                banked = image;
+                &banked
+            }
+            None => hexfile,
+        };
+        for output in &self.outputs {
+            write_output_for_args(
+                self,
+                output,
+                hexfile,
+                gap_fill,
+                provider,
+                writer,
+                diagnostics,
+            )?;
+        }
+        Ok(())
     }
 }
 
-fn random_fill_bytes(range: Range) -> Vec<u8> {
-    let seed = crate::random_fill_seed_from_time(range);
-    crate::random_fill_bytes(range, seed)
-}
-
-fn load_block(
-    blocks: &HashMap<String, crate::HexFile>,
-    path: &Path,
-) -> Result<crate::HexFile, CliError> {
-    let key = path.to_string_lossy().to_string();
-    if let Some(block) = blocks.get(&key) {
-        return Ok(block.clone());
-    }
-
-    let provider = FsProvider;
-    load_input(&provider, path)
+/// /FR without /FP: reproducible with /FRSEED, otherwise seeded from the clock.
+pub(super) fn random_fill(seed: Option<u64>) -> impl FnMut(Range) -> Vec<u8> {
+    let pattern = crate::FillPattern::Random { seed };
+    move |range| pattern.range_bytes(range)
 }
@@ -0,0 +1,192 @@
+//! /CS, /CSM and /CSB: the pipeline's checksum jobs and their results.
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::{ChecksumAlgorithm, LogCommandKind, PipelineChecksum, PipelineRecord};
+
+use super::super::error::{CliError, file_context};
+use super::super::io::{ReadProvider, WriteProvider, write_file};
+use super::super::mapfile;
+use super::super::types::{
+    Args, ChecksumFormat, ChecksumGapFill, ChecksumParams, ChecksumTarget, ParseArgError,
+};
+
+impl Args {
+    /// Whole-image checksums read the normalized image, so the pipeline
+    /// normalizes it once in place rather than once per job. /CSB and /PB
+    /// scripts work on raw blocks, so they keep the raw segments.
+    pub(super) fn normalize_for_checksums(&self) -> bool {
+        !self.checksum_jobs().any(|(cs, _)| cs.per_block) && self.postbuild.is_none()
+    }
+
+    /// /CS then /CSM jobs in command-line order, with the option each came from.
+    pub(super) fn checksum_jobs(&self) -> impl Iterator<Item = (&ChecksumParams, &'static str)> {
+        let single = self.checksum.iter().map(|cs| {
+            let opt_base = match (cs.per_block, cs.little_endian) {
+                (true, true) => "/CSBR",
+                (true, false) => "/CSB",
+                (false, true) => "/CSR",
+                (false, false) => "/CS",
+            };
+            (cs, opt_base)
+        });
+        let multi = self
+            .checksum_multi
+            .iter()
+            .map(|cs| (cs, if cs.little_endian { "/CSMR" } else { "/CSM" }));
+        single.chain(multi)
+    }
+
+    /// The pipeline's checksum jobs; each sees earlier results. `@SYM`
+    /// targets are looked up now, START/END ranges when the job runs.
+    pub(super) fn pipeline_checksums<P: ReadProvider>(
+        &self,
+        provider: &P,
+    ) -> Result<Vec<PipelineChecksum>, CliError> {
+        self.checksum_jobs()
+            .map(|(cs_params, opt_base)| {
+                let opt = format!("{opt_base}{}", cs_params.algorithm);
+                let algorithm =
+                    self.wrap_error(&opt, ChecksumAlgorithm::from_index(cs_params.algorithm))?;
+                let forced_range =
+                    cs_params
+                        .forced_range
+                        .as_ref()
+                        .map(|forced| crate::ForcedRange {
+                            range: forced.range,
+                            pattern: forced.pattern.clone(),
+                        });
+                let options = crate::ChecksumOptions {
+                    algorithm,
+                    range: None,
+                    little_endian_output: cs_params.little_endian,
+                    forced_range,
+                    exclude_ranges: cs_params.exclude_ranges.clone(),
+                    target_exclude: None,
+                    target_as_fill: cs_params.target_as_fill,
+                    gap_fill: match cs_params.gap_fill {
+                        ChecksumGapFill::Skip => crate::ChecksumGapFill::Skip,
+                        ChecksumGapFill::Error => crate::ChecksumGapFill::Error,
+                        ChecksumGapFill::Fill(byte) => crate::ChecksumGapFill::Fill(byte),
+                    },
+                    initial: cs_params.initial,
+                };
+                Ok(PipelineChecksum {
+                    options,
+                    target: self.resolve_checksum_target(&cs_params.target, &opt, provider)?,
+                    range: cs_params.range,
+                    per_block: cs_params.per_block,
+                })
+            })
+            .collect()
+    }
+
+    /// Write the file and @stdout results of the pipeline's checksum `jobs`
+    /// and follow each `CreateChecksum` in `record` with its result. Returns
+    /// the results in order, one per block for /CSB.
+    pub(super) fn finish_checksums(
+        &self,
+        jobs: &[PipelineChecksum],
+        results: Vec<Vec<Vec<u8>>>,
+        record: &mut PipelineRecord,
+        writer: &dyn WriteProvider,
+    ) -> Result<Vec<Vec<u8>>, CliError> {
+        // The pipeline records its jobs last; the CLI names the skips itself.
+        let recorded = jobs.iter().filter(|job| job.is_recordable()).count();
+        let mut created = record
+            .commands
+            .split_off(record.commands.len() - recorded)
+            .into_iter();
+        record
+            .unrecorded
+            .retain(|&option| option != "/CS" && option != "/CSB");
+
+        let jobs = self.checksum_jobs().zip(jobs).zip(&results);
+        for (((cs_params, opt_base), job), results) in jobs {
+            let opt = format!("{opt_base}{}", cs_params.algorithm);
+            match &cs_params.target {
+                ChecksumTarget::File(path) => {
+                    let formatted = cs_params.format.format(results);
+                    write_file(&opt, path, formatted, self.create_output_dirs, writer)?;
+                }
+                // The requested result, so printed even with /S.
+                ChecksumTarget::Stdout => {
+                    let mut formatted = cs_params.format.format(results);
+                    if cs_params.format != ChecksumFormat::Bin {
+                        formatted.push(b'\n');
+                    }
+                    std::io::stdout().write_all(&formatted)?;
+                }
+                _ => {}
+            }
+
+            let command = job.is_recordable().then(|| {
+                created
+                    .next()
+                    .expect("one CreateChecksum per recordable job")
+            });
+            // `CreateChecksum` has no output format or stdout target either.
+            match command {
+                Some(command)
+                    if cs_params.format == ChecksumFormat::List
+                        && cs_params.target != ChecksumTarget::Stdout =>
+                {
+                    record.commands.push(command);
+                    for result in results {
+                        let hex: String = result.iter().map(|b| format!("{b:02X}")).collect();
+                        record
+                            .commands
+                            .push(LogCommandKind::Comment(format!("{opt} = {hex}")));
+                    }
+                }
+                _ => record.skip(opt_base),
+            }
+        }
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    pub(super) fn resolve_checksum_target<P: ReadProvider>(
+        &self,
+        target: &ChecksumTarget,
+        opt: &str,
+        provider: &P,
+    ) -> Result<crate::ChecksumTarget, CliError> {
+        Ok(match target {
+            ChecksumTarget::Address(addr) => crate::ChecksumTarget::Address(*addr),
+            ChecksumTarget::Append => crate::ChecksumTarget::Append,
+            ChecksumTarget::Begin => crate::ChecksumTarget::Begin,
+            ChecksumTarget::Prepend => crate::ChecksumTarget::Prepend,
+            ChecksumTarget::OverwriteEnd => crate::ChecksumTarget::OverwriteEnd,
+            ChecksumTarget::File(path) => crate::ChecksumTarget::File(path.clone()),
+            // The library leaves file results to the caller, which prints these.
+            ChecksumTarget::Stdout => crate::ChecksumTarget::File(PathBuf::from("-")),
+            ChecksumTarget::Symbol(name) => {
+                crate::ChecksumTarget::Address(self.resolve_symbol(name, opt, provider)?)
+            }
+        })
+    }
+
+    /// Address of `name` in the /MAPFILE linker map.
+    pub(super) fn resolve_symbol<P: ReadProvider>(
+        &self,
+        name: &str,
+        opt: &str,
+        provider: &P,
+    ) -> Result<u32, CliError> {
+        let Some(ref path) = self.map_file else {
+            return Err(
+                ParseArgError::MissingValue(format!("/MAPFILE for {opt} @SYM:{name}")).into(),
+            );
+        };
+        let map = provider
+            .read_string(path)
+            .map_err(|e| file_context("/MAPFILE", path)(e.into()))?;
+        mapfile::resolve_symbol(&map, name).ok_or_else(|| {
+            CliError::Config(format!(
+                "{opt}: symbol {name} not found in {}",
+                path.display()
+            ))
+        })
+    }
+}
@@ -0,0 +1,184 @@
+//! Loading a run's inputs, from files or from in-memory blocks.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::{HexFile, MergeMode, MergeOptions};
+
+use super::super::diagnostics::Diagnostics;
+use super::super::error::{CliError, file_context};
+use super::super::io::{
+    InputOptions, ReadProvider, expand_input_pattern, hexfiles_overlap, input_format_name,
+    load_binary_input, load_hex_ascii_input, load_input_with_options, load_intel_hex_16bit_input,
+    resolve_output_path, same_file,
+};
+use super::super::types::{Args, ParseArgError};
+
+/// Where a run reads its inputs: through `provider`, except for paths that
+/// name one of `blocks` (see [`execute_in_memory`](super::super::execute_in_memory)).
+pub(super) struct Inputs<'a, P> {
+    pub(super) provider: &'a P,
+    pub(super) blocks: Option<&'a HashMap<String, HexFile>>,
+    pub(super) diagnostics: &'a Diagnostics,
+}
+
+impl<P: ReadProvider> Inputs<'_, P> {
+    fn block(&self, path: &Path) -> Option<&HexFile> {
+        self.blocks?.get(path.to_string_lossy().as_ref())
+    }
+
+    /// The block named `path`, or the file loaded with the input options of
+    /// `args`; lenient-parse warnings go to the diagnostics.
+    pub(super) fn load(&self, args: &Args, path: &Path) -> Result<HexFile, CliError> {
+        if let Some(block) = self.block(path) {
+            return Ok(block.clone());
+        }
+        let (hexfile, warnings) =
+            load_input_with_options(self.provider, path, &args.input_options())?;
+        for warning in warnings {
+            self.diagnostics
+                .warn(format!("{}: {warning}", path.display()));
+        }
+        Ok(hexfile)
+    }
+
+    /// [`expand_input_pattern`], leaving block names as they are.
+    pub(super) fn expand(&self, path: &Path) -> Result<Vec<PathBuf>, CliError> {
+        if self.block(path).is_some() {
+            return Ok(vec![path.to_path_buf()]);
+        }
+        expand_input_pattern(path)
+    }
+
+    /// /INFO: what `path` is read as.
+    pub(super) fn format_name(&self, args: &Args, path: &Path) -> Result<String, CliError> {
+        if self.block(path).is_some() {
+            return Ok("in-memory block".to_string());
+        }
+        input_format_name(self.provider, path, &args.input_options())
+    }
+
+    /// Whether `path` names a block or an existing file.
+    pub(super) fn exists(&self, path: &Path) -> bool {
+        self.block(path).is_some() || path.exists()
+    }
+}
+
+impl Args {
+    pub(super) fn input_options(&self) -> InputOptions {
+        InputOptions {
+            elf: crate::ElfParseOptions {
+                use_virtual_address: self.elf_use_vma,
+            },
+            text: crate::ParseOptions {
+                lenient: self.lenient,
+            },
+            format: self.input_format,
+        }
+    }
+
+    /// Positional inputs in command-line order, with file patterns expanded.
+    pub(super) fn input_paths<P: ReadProvider>(
+        &self,
+        inputs: &Inputs<P>,
+    ) -> Result<Vec<PathBuf>, CliError> {
+        let mut paths = Vec::new();
+        for path in self.input_file.iter().chain(&self.extra_inputs) {
+            paths.extend(inputs.expand(path)?);
+        }
+        Ok(paths)
+    }
+
+    /// Load each input in order; later inputs only fill gaps left by earlier ones.
+    fn merge_inputs(
+        &self,
+        paths: Vec<PathBuf>,
+        mut load: impl FnMut(&Path) -> Result<HexFile, CliError>,
+    ) -> Result<Option<HexFile>, CliError> {
+        let mut paths = paths.into_iter();
+        let Some(first) = paths.next() else {
+            return Ok(None);
+        };
+        let mut hexfile = load(&first).map_err(file_context("", &first))?;
+        let options = MergeOptions {
+            mode: MergeMode::Preserve,
+            ..Default::default()
+        };
+        for path in paths {
+            let other = load(&path).map_err(file_context("", &path))?;
+            hexfile.merge_owned(other, &options)?;
+        }
+        Ok(Some(hexfile))
+    }
+
+    /// The /IN file to memory-map: only with /MMAP, and not when an export
+    /// of this run replaces it, which would truncate the mapping.
+    pub(super) fn mapped_input(&self) -> Option<&Path> {
+        let import = self.import_binary.as_ref().filter(|_| self.mmap_input)?;
+        let exported = self
+            .outputs
+            .iter()
+            .filter_map(|output| resolve_output_path(self, output).ok())
+            .any(|path| same_file(&path, &import.file));
+        (!exported).then_some(import.file.as_path())
+    }
+
+    pub(super) fn load_hexfile<P: ReadProvider>(
+        &self,
+        inputs: &Inputs<P>,
+    ) -> Result<HexFile, CliError> {
+        let provider = inputs.provider;
+        if let Some(ref import) = self.import_binary {
+            let mmap = self.mapped_input().is_some();
+            if self.mmap_input && !mmap {
+                inputs.diagnostics.note(
+                    "/MMAP: the /IN file is also an output; reading it instead of mapping it",
+                );
+            }
+            return load_binary_input(provider, &import.file, import.offset, mmap)
+                .map_err(file_context("/IN", &import.file));
+        }
+        if let Some(ref import) = self.import_hex_ascii {
+            let ascii = load_hex_ascii_input(provider, &import.file, import.offset)
+                .map_err(file_context("/IA", &import.file))?;
+            if let Some(mut base) =
+                self.merge_inputs(self.input_paths(inputs)?, |path| inputs.load(self, path))?
+            {
+                if hexfiles_overlap(&base, &ascii) {
+                    inputs
+                        .diagnostics
+                        .warn("/IA overlaps input file; ignoring input file");
+                    return Ok(ascii);
+                }
+                for segment in ascii.segments() {
+                    base.append_segment(segment.clone());
+                }
+                return Ok(base);
+            }
+            return Ok(ascii);
+        }
+        if let Some(ref import) = self.import_i16 {
+            return load_intel_hex_16bit_input(provider, import)
+                .map_err(file_context("/II2", import));
+        }
+        if let Some(hexfile) =
+            self.merge_inputs(self.input_paths(inputs)?, |path| inputs.load(self, path))?
+        {
+            return Ok(hexfile);
+        }
+        if self.log_file.is_some() {
+            return Ok(HexFile::new());
+        }
+        Err(ParseArgError::MissingInputFile.into())
+    }
+
+    /// /UNBANK: the loaded input back at linear addresses.
+    pub(super) fn unbank(&self, hexfile: HexFile) -> Result<HexFile, CliError> {
+        let Some(params) = self.unbank else {
+            return Ok(hexfile);
+        };
+        hexfile
+            .to_linear(params.window, params.window.length(), params.first_bank)
+            .map_err(|e| CliError::from(e).with_context("/UNBANK"))
+    }
+}
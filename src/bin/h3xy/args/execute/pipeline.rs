@@ -0,0 +1,392 @@
+//! The command line as a [`Pipeline`], and the /PIPE description run after it.
+
+use std::path::Path;
+
+use crate::{
+    AddressTable, AlignOptions, BankedMapOptions, LogCommandKind, Pipeline, PipelineDspic,
+    PipelineGhost, PipelineLabel, PipelineMerge, PipelineRecord, PipelineStamp, PipelineStep,
+    Range, RemapOptions,
+};
+
+use super::super::error::{CliError, file_context};
+use super::super::io::{ReadProvider, WriteProvider, write_file};
+use super::super::types::{Args, ChecksumFormat, OutputFormat, PageMapParams};
+use super::input::Inputs;
+use super::random_fill;
+
+impl Args {
+    pub(super) fn build_pipeline<P: ReadProvider>(
+        &self,
+        hexfile: crate::HexFile,
+        inputs: &Inputs<P>,
+    ) -> Result<Pipeline, CliError> {
+        let provider = inputs.provider;
+        let log_commands = if let Some(ref path) = self.log_file {
+            let content = provider
+                .read_string(path)
+                .map_err(|e| file_context("/L", path)(e.into()))?;
+            Some(
+                crate::parse_log_commands(&content)
+                    .map_err(|e| file_context("/L", path)(e.into()))?,
+            )
+        } else {
+            None
+        };
+
+        let mut merge_transparent = Vec::with_capacity(self.merge_transparent.len());
+        for merge in &self.merge_transparent {
+            for path in inputs.expand(&merge.file)? {
+                merge_transparent.push(PipelineMerge {
+                    other: inputs
+                        .load(self, &path)
+                        .map_err(file_context("/MT", &path))?,
+                    offset: merge.offset.unwrap_or(0),
+                    range: merge.range,
+                    path: Some(path),
+                });
+            }
+        }
+        let mut merge_opaque = Vec::with_capacity(self.merge_opaque.len());
+        for merge in &self.merge_opaque {
+            for path in inputs.expand(&merge.file)? {
+                merge_opaque.push(PipelineMerge {
+                    other: inputs
+                        .load(self, &path)
+                        .map_err(file_context("/MO", &path))?,
+                    offset: merge.offset.unwrap_or(0),
+                    range: merge.range,
+                    path: Some(path),
+                });
+            }
+        }
+
+        let align = self.align_address.map(|alignment| AlignOptions {
+            alignment,
+            fill_byte: self.align_fill,
+            align_length: self.align_length,
+        });
+
+        Ok(Pipeline {
+            hexfile,
+            fill_ranges: self.fill_ranges.clone(),
+            fill_pattern: self.fill_pattern(provider)?,
+            cut_ranges: self.cut_ranges.clone(),
+            merge_transparent,
+            merge_opaque,
+            labels: self.pipeline_labels(),
+            address_ranges: self.address_range.clone(),
+            log_commands,
+            fill_all: if self.fill_all {
+                Some(self.align_fill)
+            } else {
+                None
+            },
+            big_image: self.big_image(),
+            align,
+            split: self.split_block_size,
+            split_boundary: self.split_boundary,
+            swap_word: self.swap_word,
+            swap_long: self.swap_long,
+            stamps: self.pipeline_stamps(),
+            normalize_for_checksums: self.normalize_for_checksums(),
+            checksums: self.pipeline_checksums(provider)?,
+            map_star12: banked_map(self.s12_map, BankedMapOptions::STAR12),
+            map_star12x: banked_map(self.s12x_map, BankedMapOptions::STAR12X),
+            map_star08: banked_map(self.s08_map, BankedMapOptions::STAR08),
+            remap: self
+                .remap
+                .iter()
+                .map(|remap| RemapOptions {
+                    start: remap.start,
+                    end: remap.end,
+                    linear: remap.linear,
+                    size: remap.size,
+                    inc: remap.inc,
+                })
+                .collect(),
+            address_table: self.address_table(provider)?,
+            dspic_expand: self
+                .dspic_expand
+                .iter()
+                .map(|op| PipelineDspic {
+                    range: op.range,
+                    target: op.target,
+                })
+                .collect(),
+            dspic_shrink: self
+                .dspic_shrink
+                .iter()
+                .map(|op| PipelineDspic {
+                    range: op.range,
+                    target: op.target,
+                })
+                .collect(),
+            dspic_clear_ghost: self.dspic_clear_ghost.clone(),
+            dspic_insert_ghost: self
+                .dspic_insert_ghost
+                .iter()
+                .map(|op| PipelineGhost {
+                    range: op.range,
+                    value: op.value,
+                })
+                .collect(),
+        })
+    }
+
+    /// The /MAPTAB table, read through the provider.
+    fn address_table<P: ReadProvider>(
+        &self,
+        provider: &P,
+    ) -> Result<Option<AddressTable>, CliError> {
+        let Some(ref path) = self.map_table else {
+            return Ok(None);
+        };
+        let text = provider
+            .read_string(path)
+            .map_err(|e| file_context("/MAPTAB", path)(e.into()))?;
+        let table = text.parse().map_err(|e: crate::AddressTableError| {
+            CliError::Config(e.to_string()).with_context(format!("/MAPTAB:{}", path.display()))
+        })?;
+        Ok(Some(table))
+    }
+
+    /// /FP, with an `@file` pattern read through the provider.
+    fn fill_pattern<P: ReadProvider>(
+        &self,
+        provider: &P,
+    ) -> Result<Option<crate::FillPattern>, CliError> {
+        if !self.fill_pattern_set {
+            return Ok(None);
+        }
+        let Some(ref path) = self.fill_pattern_file else {
+            return Ok(Some(self.fill_pattern.clone()));
+        };
+        let bytes = provider
+            .read_bytes(path)
+            .map_err(|e| file_context("/FP", path)(e.into()))?;
+        if bytes.is_empty() {
+            return Err(CliError::Config(format!(
+                "/FP: {} is empty",
+                path.display()
+            )));
+        }
+        Ok(Some(crate::FillPattern::Bytes(bytes)))
+    }
+
+    /// Take /FA out of the pipeline when, with /BHFCT, nothing after it reads
+    /// the data: the Intel HEX, S-Record and binary writers can then fill the
+    /// gaps while writing. See [`finish_fill_all`].
+    pub(super) fn defer_fill_all(&self, pipeline: &mut Pipeline) -> Option<u8> {
+        let streamed = |format: &Option<OutputFormat>| {
+            matches!(
+                format,
+                None | Some(OutputFormat::IntelHex { .. })
+                    | Some(OutputFormat::SRecord { .. })
+                    | Some(OutputFormat::Binary {
+                        start: None,
+                        end: None,
+                        fill: None,
+                    })
+            )
+        };
+        let deferrable = self.big_hex_file_threshold_kb.is_some()
+            && self.align_address.is_none()
+            && self.split_block_size.is_none()
+            && self.split_boundary.is_none()
+            && !self.swap_word
+            && !self.swap_long
+            && self.write_strings.is_empty()
+            && self.write_dates.is_empty()
+            && self.checksum.is_empty()
+            && self.checksum_multi.is_empty()
+            && self.pipeline_file.is_none()
+            && self.data_processing.is_none()
+            && self.signature_verify.is_none()
+            && self.postbuild.is_none()
+            && self.compare_file.is_none()
+            && self.bank.is_none()
+            && self.outputs.iter().all(|output| streamed(&output.format));
+        if deferrable {
+            pipeline.fill_all.take()
+        } else {
+            None
+        }
+    }
+
+    /// Complete a /FA taken out of the pipeline. Images above /BHFCT keep
+    /// their gaps and the returned byte is filled in by the writers; smaller
+    /// ones are filled now. Either way the /FA span limit applies.
+    pub(super) fn finish_fill_all(
+        &self,
+        deferred: Option<u8>,
+        hexfile: &mut crate::HexFile,
+        record: &mut PipelineRecord,
+    ) -> Result<Option<u8>, CliError> {
+        let Some(fill_byte) = deferred else {
+            return Ok(None);
+        };
+        let (Some(start), Some(end)) = (hexfile.min_address(), hexfile.max_address()) else {
+            return Ok(None);
+        };
+        hexfile
+            .check_fill_span()
+            .map_err(|e| e.with_context("/FA"))?;
+        record.commands.push(LogCommandKind::FillRange {
+            range: Range::from_start_end(start, end).expect("min <= max"),
+            pattern: vec![fill_byte],
+        });
+        if self.big_image().is_some_and(|big| big.applies_to(hexfile)) {
+            Ok(Some(fill_byte))
+        } else {
+            crate::flag_fill_all(hexfile, fill_byte)?;
+            Ok(None)
+        }
+    }
+
+    /// /BHFCT with /BTBS; without /BHFCT big images take the normal path.
+    fn big_image(&self) -> Option<crate::BigImageOptions> {
+        self.big_hex_file_threshold_kb
+            .map(|kb| crate::BigImageOptions {
+                threshold: kb as u64 * 1024,
+                block_size: self.buffer_size(),
+            })
+    }
+
+    fn pipeline_labels(&self) -> Vec<PipelineLabel> {
+        self.labels
+            .iter()
+            .map(|label| PipelineLabel {
+                range: label.range,
+                name: label.name.clone(),
+            })
+            .collect()
+    }
+
+    /// /WS strings, then /WDATE dates, so checksums cover them.
+    fn pipeline_stamps(&self) -> Vec<PipelineStamp> {
+        let mut stamps: Vec<_> = self
+            .write_strings
+            .iter()
+            .map(|params| PipelineStamp::Ascii {
+                address: params.address,
+                text: params.text.clone(),
+                pad: params.pad,
+            })
+            .collect();
+        if !self.write_dates.is_empty() {
+            let date = self.date.unwrap_or_else(crate::DateStamp::now);
+            stamps.extend(self.write_dates.iter().map(|params| PipelineStamp::Date {
+                address: params.address,
+                format: params.format.clone(),
+                date,
+            }));
+        }
+        stamps
+    }
+
+    /// Run the /PIPE steps after the command line's own and before /DP.
+    /// Checksum results with a file target are written as for /CS. Returns
+    /// the checksum results.
+    pub(super) fn apply_pipeline_file<P: ReadProvider>(
+        &self,
+        hexfile: &mut crate::HexFile,
+        record: &mut PipelineRecord,
+        inputs: &Inputs<P>,
+        writer: &dyn WriteProvider,
+    ) -> Result<Vec<Vec<u8>>, CliError> {
+        let Some(ref path) = self.pipeline_file else {
+            return Ok(Vec::new());
+        };
+        let load = |merge: &Path| {
+            inputs
+                .load(self, merge)
+                .map_err(file_context("/PIPE", merge))
+        };
+        let builder = pipeline_file_steps(path, std::mem::take(hexfile), inputs.provider, load)?;
+        let files: Vec<_> = builder
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                PipelineStep::Checksum(checksum) => Some(match checksum.target {
+                    crate::ChecksumTarget::File(ref file) => Some(file.clone()),
+                    _ => None,
+                }),
+                _ => None,
+            })
+            .collect();
+        let result = builder
+            .execute_traced(random_fill(self.fill_seed), load, |stage| {
+                self.trace_stage(stage)
+            })
+            .map_err(|e| {
+                self.pipeline_error(e)
+                    .with_context(format!("/PIPE:{}", path.display()))
+            })?;
+        for (file, results) in files.iter().zip(&result.checksum_bytes) {
+            if let Some(file) = file {
+                let formatted = ChecksumFormat::List.format(results);
+                write_file("/PIPE", file, formatted, self.create_output_dirs, writer)?;
+            }
+        }
+        *hexfile = result.hexfile;
+        record.append(result.record);
+        Ok(result.checksum_bytes.into_iter().flatten().collect())
+    }
+}
+
+/// The /S08MAP, /S12MAP or /S12XMAP mapping.
+fn banked_map(
+    params: Option<PageMapParams>,
+    standard: BankedMapOptions,
+) -> Option<BankedMapOptions> {
+    params.map(|params| match params {
+        PageMapParams::Standard => standard,
+        PageMapParams::Paged {
+            ppage_base,
+            window_start,
+            window_size,
+            linear_base,
+        } => BankedMapOptions::paged(ppage_base, window_start, window_size, linear_base),
+    })
+}
+
+/// The steps of the /PIPE description at `path`: JSON for a `.json` file,
+/// TOML otherwise. Relative merge paths are taken from the file's directory.
+#[cfg(feature = "serde")]
+fn pipeline_file_steps<P: ReadProvider>(
+    path: &Path,
+    hexfile: crate::HexFile,
+    provider: &P,
+    load: impl FnMut(&Path) -> Result<crate::HexFile, CliError>,
+) -> Result<crate::PipelineBuilder, CliError> {
+    let content = provider
+        .read_string(path)
+        .map_err(|e| file_context("/PIPE", path)(e.into()))?;
+    let json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let mut description = if json {
+        crate::PipelineDescription::from_json(&content)
+    } else {
+        crate::PipelineDescription::from_toml(&content)
+    }
+    .map_err(|e| {
+        CliError::Config(e.to_string()).with_context(format!("/PIPE:{}", path.display()))
+    })?;
+    if let Some(dir) = path.parent() {
+        description.resolve_paths(dir);
+    }
+    description.into_builder(hexfile, load)
+}
+
+#[cfg(not(feature = "serde"))]
+fn pipeline_file_steps<P: ReadProvider>(
+    _path: &Path,
+    _hexfile: crate::HexFile,
+    _provider: &P,
+    _load: impl FnMut(&Path) -> Result<crate::HexFile, CliError>,
+) -> Result<crate::PipelineBuilder, CliError> {
+    Err(CliError::Unsupported(
+        "/PIPE requires h3xy built with the `serde` feature".to_string(),
+    ))
+}
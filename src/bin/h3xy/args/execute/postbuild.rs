@@ -0,0 +1,129 @@
+//! /PB: script lines run as option sets after the main command.
+
+use std::path::Path;
+
+use crate::PipelineRecord;
+
+use super::super::error::{CliError, file_context};
+use super::super::io::{ReadProvider, WriteProvider};
+use super::super::parse::{set_output_file, set_output_format};
+use super::super::types::Args;
+use super::input::Inputs;
+use super::random_fill;
+
+impl Args {
+    /// Run each line of the /PB script as its own option set against
+    /// `hexfile`, after the main command's checksums and before export.
+    ///
+    /// Returns the merged export settings if any script line changes them.
+    pub(super) fn run_postbuild<P: ReadProvider>(
+        &self,
+        hexfile: &mut crate::HexFile,
+        checksum_bytes: &mut Vec<Vec<u8>>,
+        inputs: &Inputs<P>,
+        writer: &dyn WriteProvider,
+    ) -> Result<Option<Args>, CliError> {
+        let Some(ref script) = self.postbuild else {
+            return Ok(None);
+        };
+        let content = inputs
+            .provider
+            .read_string(script)
+            .map_err(|e| file_context("/PB", script)(e.into()))?;
+
+        let mut export = None;
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+            let results = self
+                .run_postbuild_line(line, hexfile, inputs, &mut export, writer)
+                .map_err(|e| e.with_context(format!("/PB: {}:{}", script.display(), index + 1)))?;
+            checksum_bytes.extend(results);
+        }
+        Ok(export)
+    }
+
+    /// Returns the line's checksum results.
+    fn run_postbuild_line<P: ReadProvider>(
+        &self,
+        line: &str,
+        hexfile: &mut crate::HexFile,
+        inputs: &Inputs<P>,
+        export: &mut Option<Args>,
+        writer: &dyn WriteProvider,
+    ) -> Result<Vec<Vec<u8>>, CliError> {
+        let mut step = Args::parse_from_str_with(line, |arg| {
+            let path = Path::new(arg);
+            arg.starts_with('/') && path.is_absolute() && inputs.exists(path)
+        })?;
+        if step.postbuild.is_some() {
+            return Err(CliError::Unsupported(
+                "nested postbuild (/PB) is not allowed".into(),
+            ));
+        }
+        if step.input_file.is_some()
+            || step.import_binary.is_some()
+            || step.import_hex_ascii.is_some()
+            || step.import_i16.is_some()
+        {
+            return Err(CliError::Unsupported(
+                "postbuild lines cannot load an input file".into(),
+            ));
+        }
+        step.validate_supported_features()?;
+        step.create_output_dirs |= self.create_output_dirs;
+
+        let current = std::mem::take(hexfile);
+        let seed = step.fill_seed.or(self.fill_seed);
+        let mut pipeline = step.build_pipeline(current, inputs)?;
+        // Later script lines may still work on the raw blocks.
+        pipeline.normalize_for_checksums = false;
+        let checksums = pipeline.checksums.clone();
+        let mut result = pipeline
+            .execute_traced(
+                random_fill(seed),
+                |path| inputs.load(&step, path).map_err(file_context("", path)),
+                |stage| self.trace_stage(stage),
+            )
+            .map_err(|e| step.pipeline_error(e))?;
+        inputs.diagnostics.take_from(&mut result.record);
+        let mut checksum_bytes = step.finish_checksums(
+            &checksums,
+            result.checksum_bytes,
+            &mut result.record,
+            writer,
+        )?;
+        *hexfile = result.hexfile;
+        if step.entry_point.is_some() {
+            hexfile.metadata_mut().entry_point = step.entry_point;
+        }
+        let mut record = PipelineRecord::default();
+        checksum_bytes.extend(step.apply_pipeline_file(hexfile, &mut record, inputs, writer)?);
+        step.apply_data_processing(hexfile, &mut record, writer)?;
+        step.apply_signature_verification(hexfile)?;
+        inputs.diagnostics.take_from(&mut record);
+
+        // Script outputs pair with the command's outputs as if appended to it.
+        for output in step.outputs {
+            let export = export.get_or_insert_with(|| self.clone());
+            if let Some(format) = output.format {
+                set_output_format(export, format, output.bytes_per_line)?;
+            }
+            if let Some(file) = output.file {
+                set_output_file(export, file);
+            }
+        }
+        if step.line_ending == crate::LineEnding::Lf {
+            export.get_or_insert_with(|| self.clone()).line_ending = step.line_ending;
+        }
+        if step.lowercase_hex {
+            export.get_or_insert_with(|| self.clone()).lowercase_hex = true;
+        }
+        if step.align_records {
+            export.get_or_insert_with(|| self.clone()).align_records = true;
+        }
+        Ok(checksum_bytes)
+    }
+}
@@ -0,0 +1,197 @@
+//! What a run reports besides its outputs: /LOGOUT, /CMP and /INFO.
+
+use std::path::{Path, PathBuf};
+
+use crate::{LogCommandKind, Pipeline, PipelineRecord, Range};
+
+use super::super::error::{CliError, ExecuteOutput, file_context};
+use super::super::io::{ReadProvider, WriteProvider, write_file};
+use super::super::types::Args;
+use super::input::Inputs;
+use super::random_fill;
+
+impl Args {
+    /// Start of the /LOGOUT record: how the input was loaded.
+    pub(super) fn input_record(&self) -> PipelineRecord {
+        let mut record = PipelineRecord::default();
+        match self.input_file {
+            Some(ref path) => record.commands.push(LogCommandKind::FileOpen(path.clone())),
+            None => record.commands.push(LogCommandKind::FileNew),
+        }
+        let patterned = self
+            .input_file
+            .iter()
+            .any(|path| path.to_string_lossy().contains(['*', '?', '[']));
+        if patterned || !self.extra_inputs.is_empty() {
+            record.skip("multiple input files");
+        }
+        if self.import_binary.is_some() {
+            record.skip("/IN");
+        }
+        if self.import_hex_ascii.is_some() {
+            record.skip("/IA");
+        }
+        if self.import_i16.is_some() {
+            record.skip("/II2");
+        }
+        if self.unbank.is_some() {
+            record.skip("/UNBANK");
+        }
+        record
+    }
+
+    /// /LOGOUT: write the record, or fail if a step has no /L form.
+    pub(super) fn write_log_output(
+        &self,
+        record: &PipelineRecord,
+        writer: &dyn WriteProvider,
+    ) -> Result<(), CliError> {
+        let Some(ref path) = self.log_output else {
+            return Ok(());
+        };
+        if !record.unrecorded.is_empty() {
+            return Err(CliError::Unsupported(format!(
+                "/LOGOUT: {} cannot be recorded as /L commands",
+                record.unrecorded.join(", ")
+            )));
+        }
+        write_file(
+            "/LOGOUT",
+            path,
+            crate::write_log_commands(&record.commands),
+            self.create_output_dirs,
+            writer,
+        )
+    }
+
+    /// /CMP: print the ranges whose content differs from the reference and fail.
+    /// `/AR` ranges, resolved against the result, restrict the reference too.
+    pub(super) fn apply_compare(
+        &self,
+        hexfile: &crate::HexFile,
+        load: impl FnOnce(&Path) -> Result<crate::HexFile, CliError>,
+    ) -> Result<(), CliError> {
+        let Some(ref path) = self.compare_file else {
+            return Ok(());
+        };
+        let reference = load(path).map_err(file_context("/CMP", path))?;
+        let ranges = crate::resolve_ranges(&self.address_range, hexfile);
+        let ranges = (!self.address_range.is_empty()).then_some(ranges.as_slice());
+        let Err(diffs) = hexfile.content_eq(&reference, ranges) else {
+            if !self.silent {
+                println!("/CMP: content matches {}", path.display());
+            }
+            return Ok(());
+        };
+        let result = hexfile.normalized_lossy();
+        let reference = reference.normalized_lossy();
+        for range in &diffs {
+            println!("{}", compare_line(range, &result, &reference));
+        }
+        Err(CliError::Verification(format!(
+            "/CMP: content differs from {} in {} range(s)",
+            path.display(),
+            diffs.len()
+        )))
+    }
+
+    /// /INFO: print the inputs and the image after loading, /MT and /MO to
+    /// stdout instead of running the remaining steps.
+    pub(super) fn print_info<P: ReadProvider>(
+        &self,
+        pipeline: Pipeline,
+        inputs: &Inputs<P>,
+    ) -> Result<ExecuteOutput, CliError> {
+        let mut listed = Vec::new();
+        if let Some(ref import) = self.import_binary {
+            listed.push((import.file.clone(), "binary (/IN)".to_string()));
+        }
+        if let Some(ref import) = self.import_hex_ascii {
+            listed.push((import.file.clone(), "HEX ASCII (/IA)".to_string()));
+        }
+        if let Some(ref path) = self.import_i16 {
+            listed.push((path.clone(), "16-bit Intel HEX (/II2)".to_string()));
+        }
+        if self.import_binary.is_none() && self.import_i16.is_none() {
+            for path in self.input_paths(inputs)? {
+                let name = inputs
+                    .format_name(self, &path)
+                    .map_err(file_context("", &path))?;
+                listed.push((path, name));
+            }
+        }
+
+        let merges_only = Pipeline {
+            hexfile: pipeline.hexfile,
+            merge_transparent: pipeline.merge_transparent,
+            merge_opaque: pipeline.merge_opaque,
+            labels: pipeline.labels,
+            ..Pipeline::default()
+        };
+        let result = merges_only
+            .execute_without_log(random_fill(self.fill_seed))
+            .map_err(|e| self.pipeline_error(e))?;
+        print!("{}", info_text(&listed, &result.hexfile));
+        Ok(ExecuteOutput::default())
+    }
+}
+
+/// The /INFO summary, one `key: value` line each, segments indented below.
+fn info_text(inputs: &[(PathBuf, String)], hexfile: &crate::HexFile) -> String {
+    let mut out = String::new();
+    for (path, format) in inputs {
+        out.push_str(&format!("input: {} ({format})\n", path.display()));
+    }
+    let report = hexfile.segment_report();
+    out.push_str(&format!("segments: {}\n", report.len()));
+    for info in &report {
+        out.push_str(&format!(
+            "  0x{:08X}-0x{:08X} {} bytes",
+            info.start_address, info.end_address, info.length
+        ));
+        if let Some(ref name) = info.name {
+            out.push_str(&format!(" [{name}]"));
+        }
+        out.push('\n');
+    }
+    let total: usize = report.iter().map(|info| info.length).sum();
+    out.push_str(&format!("total: {total} bytes\n"));
+    match (hexfile.min_address(), hexfile.max_address()) {
+        (Some(min), Some(max)) => {
+            out.push_str(&format!("range: 0x{min:08X}-0x{max:08X}\n"));
+            let gap_bytes = u64::from(max - min) + 1 - total as u64;
+            out.push_str(&format!(
+                "gaps: {} ({gap_bytes} bytes)\n",
+                hexfile.gap_count()
+            ));
+        }
+        _ => out.push_str("range: none\ngaps: 0 (0 bytes)\n"),
+    }
+    out
+}
+
+/// `0x00001000-0x00001003: 01 02 03 04 != 01 FF -- --`, at most 8 bytes per side.
+fn compare_line(range: &Range, result: &crate::HexFile, reference: &crate::HexFile) -> String {
+    const SHOWN: u32 = 8;
+    let len = (range.end() - range.start()).min(SHOWN - 1) + 1;
+    let more = if range.end() - range.start() >= SHOWN {
+        " ..."
+    } else {
+        ""
+    };
+    let bytes = |hexfile: &crate::HexFile| {
+        hexfile
+            .read_bytes(range.start(), len as usize)
+            .iter()
+            .map(|byte| byte.map_or_else(|| "--".to_string(), |b| format!("{b:02X}")))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    format!(
+        "0x{:08X}-0x{:08X}: {}{more} != {}{more}",
+        range.start(),
+        range.end(),
+        bytes(result),
+        bytes(reference)
+    )
+}
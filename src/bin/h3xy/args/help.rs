@@ -0,0 +1,1064 @@
+//! Option reference for `/?` and `/HELP[:option]`.
+//!
+//! Every option accepted by `parse_option` has one entry in [`STAGES`], listed
+//! in processing order. Each entry's example is parsed in the tests, so the
+//! table fails the build instead of drifting from the parser.
+
+use super::error::{EXIT_ARGUMENT, EXIT_IO, EXIT_OPERATION, EXIT_UNSUPPORTED, EXIT_VERIFICATION};
+
+struct Stage {
+    title: &'static str,
+    options: &'static [OptionHelp],
+}
+
+struct OptionHelp {
+    /// Topic name for `/HELP:<name>`, without the leading `/`.
+    name: &'static str,
+    syntax: &'static str,
+    summary: &'static str,
+    /// Extra paragraph for `/HELP:<name>`; may be empty.
+    detail: &'static str,
+    /// Complete command line (without the program name) using the option.
+    example: &'static str,
+}
+
+const GM_EXPERIMENTAL: &str = "Experimental: the layout is our own reading of the GM module \
+     header and has not been checked against HexView output, so it may change.";
+
+const GAC_EXPERIMENTAL: &str = "Experimental: the container layout is our own design; there is \
+     no reference GAC file or HexView output to check it against, so a real GAC consumer may \
+     reject it and the layout may change.";
+
+const fn opt(
+    name: &'static str,
+    syntax: &'static str,
+    summary: &'static str,
+    detail: &'static str,
+    example: &'static str,
+) -> OptionHelp {
+    OptionHelp {
+        name,
+        syntax,
+        summary,
+        detail,
+        example,
+    }
+}
+
+const RANGE_DETAIL: &str = "Ranges are start-end or start,length, separated by ':'. \
+Numbers are decimal, 0x-prefixed hex or b-suffixed binary; lengths may be KiB or MiB \
+(32K, 1M).";
+
+const OPEN_RANGE_DETAIL: &str = "Ranges are start-end or start,length, separated by ':'. \
+Numbers are decimal, 0x-prefixed hex or b-suffixed binary; lengths may be KiB or MiB \
+(32K, 1M). START and END stand for the lowest and highest data address when the step runs \
+(e.g. 0x8000-END), and ALL for both.";
+
+const CHECKSUM_DETAIL: &str = "<n> selects the algorithm: 0/1 byte sum BE/LE, 2/3 word sum \
+BE/LE, 4-6 two's complement sums, 7/8 CRC-16, 9 CRC-32, 10 SHA-1, 11 RIPEMD-160, 12 modular \
+sum, 13/14 CRC-16 CCITT LE/BE, 15 MD5, 17/18 CRC-16 CCITT init 0, 19 SHA-512 over address and \
+length, 20 SHA-256. The target is @append (default), @begin, @upfront, @end, @<address>, \
+@SYM:<symbol> (address from /MAPFILE), a file name, or @stdout to print the result even with \
+/S. Parameters after ';' are a range with /excludes (START-, -END and ALL allowed in the \
+range), !range#pattern to force a fill range, X to use the target as fill, FILL=<byte>|NONE \
+for gaps, INIT=<value>, and FMT=HEX|BIN|C|DEC for a file or @stdout result (default AB,CD).";
+
+const STAGES: &[Stage] = &[
+    Stage {
+        title: "General",
+        options: &[
+            opt("?", "/?", "Show this overview", "", "/?"),
+            opt(
+                "HELP",
+                "/HELP[:option]",
+                "Show the overview, or details for one option",
+                "Help is printed to stdout even with /S and exits with code 0.",
+                "/HELP:CS",
+            ),
+            opt(
+                "E",
+                "/E:<file>",
+                "Write errors to a log file",
+                "The file is emptied first. Each entry is a line starting with the time \
+                 (/DATE fixes it): warnings and notes as 'YYYY-MM-DD HH:MM:SS WARNING: ...' \
+                 or '... NOTE: ...', then '... [Ennn] message' if the run failed, where nnn is \
+                 the exit code.",
+                "in.hex /E:err.log",
+            ),
+            opt(
+                "E+",
+                "/E+:<file>",
+                "Append errors to a log file",
+                "As /E, but entries are added after those of earlier runs.",
+                "in.hex /E+:err.log",
+            ),
+            opt(
+                "S",
+                "/S",
+                "Silent mode: do not print errors, warnings or notes",
+                "The /E log still gets them.",
+                "in.hex /S",
+            ),
+            opt(
+                "WERROR",
+                "/WERROR",
+                "Fail when a warning was raised",
+                "Checked after the exports, so writer warnings count too, but no file is written \
+                 until it passes: a failed run leaves no output files. Exits with code 4. Notes, e.g. a /FR range that keeps existing data, never fail.",
+                "in.hex /IA:data.txt /WERROR /XI -o out.hex",
+            ),
+            opt(
+                "V",
+                "/V",
+                "Append the version string to the /E log",
+                "",
+                "in.hex /E:err.log /V",
+            ),
+            opt(
+                "VERBOSE",
+                "/VERBOSE",
+                "Print each pipeline step as it runs",
+                "One line per step on stderr: the option, its settings, and the segment count, \
+                 address range and byte count after it. Steps that do nothing are left out. \
+                 /S turns it off.",
+                "in.hex /FR:0x1000-0x10FF /VERBOSE -o out.hex",
+            ),
+            opt(
+                "VV",
+                "-vv",
+                "As /VERBOSE, with every segment after each step",
+                "",
+                "in.hex /AD:4 -vv -o out.hex",
+            ),
+            opt(
+                "ECHOARGS",
+                "/ECHOARGS",
+                "Print the effective command line",
+                "",
+                "in.hex /ECHOARGS",
+            ),
+            opt(
+                "INFO",
+                "/INFO",
+                "Print a summary of the input and exit",
+                "Runs after loading the inputs, /IN, /IA, /II2, /MT and /MO, prints the \
+                 input formats, segments, total bytes, address range and gaps to stdout \
+                 (even with /S), and exits 0. No other step runs and no -o is needed.",
+                "in.hex /MO:cal.hex /INFO",
+            ),
+            opt(
+                "P",
+                "/P:<file>",
+                "Read OEM settings from an INI file",
+                "/XF reads [FORDHEADER] and /XC reads [CCODE], falling back to keys in any \
+                 section; other exports ignore sections. Quote a value to keep '=', ';', '#' \
+                 or leading spaces. '!include other.ini' reads a file relative to this one. \
+                 ${NAME} in a value comes from /DEF or the environment; $$ is a literal $.",
+                "in.hex /P:cfg.ini",
+            ),
+            opt(
+                "FORD",
+                "/FORD:<key>=<value>",
+                "Set a [FORDHEADER] value for /XF",
+                "Wins over the INI. Keys are the INI's, with '_' allowed for a space. Repeatable.",
+                "in.hex /XF /FORD:module_id=0x7E0 /FORD:comments=nightly",
+            ),
+            opt(
+                "DEF",
+                "/DEF:<NAME>=<value>",
+                "Define ${NAME} for INI values",
+                "Wins over an environment variable of the same name. In the INI, $$ is a \
+                 literal $, and a ${NAME} that neither defines is an error. Repeatable.",
+                "in.hex /P:gm.ini /DEF:BUILD_ID=0042 /XG -o out.hex",
+            ),
+            opt(
+                "BHFCT",
+                "/BHFCT=<kb>",
+                "Big hex file threshold for the low-memory path",
+                "Above this image span, /FA is filled by the Intel HEX, S-Record and plain \
+                 binary writers as they write, when no later step reads the data; \
+                 otherwise the gaps are filled in /BTBS blocks.",
+                "in.hex /FA /BHFCT=1024 /XN -o out.bin",
+            ),
+            opt(
+                "BTFST",
+                "/BTFST=<kb>",
+                "Buffer-to-file threshold",
+                "Accepted for HexView compatibility; /IN is only memory-mapped with /MMAP. \
+                 Streamed outputs are written to disk at any size.",
+                "/IN:dump.bin /BTFST=1024",
+            ),
+            opt(
+                "MMAP",
+                "/MMAP",
+                "Memory-map the /IN file at any size",
+                "The image references the file instead of a copy until an operation \
+                 modifies it. The file must not change while h3xy runs: when an export \
+                 replaces it the file is read instead, and any other write to it is refused.",
+                "/IN:dump.bin /MMAP /CS9",
+            ),
+            opt(
+                "BTBS",
+                "/BTBS=<kb>",
+                "Buffer size for output writes and big-image fill blocks",
+                "Default 64.",
+                "in.hex /BTBS=64",
+            ),
+        ],
+    },
+    Stage {
+        title: "Input",
+        options: &[
+            opt(
+                "INPUT",
+                "<file>...",
+                "Input files; the format is detected from the content",
+                "Names may use *, ? and [...] patterns; matches are taken in sorted order. \
+                 Several inputs are merged before processing, earlier data winning. \
+                 Use -- before a file name that starts with '/' or '-'.",
+                "in.hex cal_*.hex",
+            ),
+            opt(
+                "IT",
+                "/IT:<format>",
+                "Force the input format: IHEX, SREC, BIN, HEXASCII or I16",
+                "",
+                "in.dat /IT:SREC",
+            ),
+            opt(
+                "LENIENT",
+                "/LENIENT",
+                "Tolerate junk lines and a missing EOF in Intel HEX, or a wrong S5/S6 count in S-Record input",
+                "",
+                "in.hex /LENIENT",
+            ),
+            opt(
+                "ELFVMA",
+                "/ELFVMA",
+                "Place ELF segments at their virtual address",
+                "",
+                "app.elf /ELFVMA",
+            ),
+            opt(
+                "GZMAX",
+                "/GZMAX=<mb>",
+                "Maximum decompressed size of gzip input (default 512)",
+                "",
+                "in.hex.gz /GZMAX=16",
+            ),
+            opt(
+                "IN",
+                "/IN:<file>[;offset]",
+                "Import a binary file at offset",
+                "",
+                "/IN:raw.bin;0x1000",
+            ),
+            opt(
+                "IA",
+                "/IA:<file>[;offset]",
+                "Import a HEX ASCII file at offset",
+                "",
+                "/IA:data.txt;0x1000",
+            ),
+            opt(
+                "II2",
+                "/II2:<file>",
+                "Import a 16-bit Intel HEX file",
+                "",
+                "/II2:in16.hex",
+            ),
+            opt(
+                "UNBANK",
+                "/UNBANK:<window_start>,<window_size>[,first]",
+                "Read a bank-switched input back to linear addresses",
+                "The inverse of /BANK with the same parameters. Data outside the common \
+                 area and the bank windows is an error.",
+                "banked.hex /UNBANK:0x8000,0x8000 /XI -o linear.hex",
+            ),
+        ],
+    },
+    Stage {
+        title: "Address mapping",
+        options: &[
+            opt(
+                "S08MAP",
+                "/S08MAP[:<ppage_base>,<window_start>,<window_size>,<linear_base>]",
+                "Map S08 banked addresses to linear",
+                "With parameters, page P seen at P:<window_start> maps to \
+                 <linear_base> + (P - <ppage_base>) * <window_size>, and the \
+                 non-banked windows stay put. Only one of /S08MAP, /S12MAP and \
+                 /S12XMAP may be given.",
+                "in.hex /S08MAP",
+            ),
+            opt(
+                "S12MAP",
+                "/S12MAP[:<ppage_base>,<window_start>,<window_size>,<linear_base>]",
+                "Map S12 banked addresses to linear",
+                "Parameters as for /S08MAP.",
+                "in.hex /S12MAP:0x20,0x8000,0x2000,0x400000",
+            ),
+            opt(
+                "S12XMAP",
+                "/S12XMAP[:<ppage_base>,<window_start>,<window_size>,<linear_base>]",
+                "Map S12X banked addresses to linear",
+                "Parameters as for /S08MAP.",
+                "in.hex /S12XMAP",
+            ),
+            opt(
+                "REMAP",
+                "/REMAP:<start>-<end>,<linear>,<size>,<inc>",
+                "Map a banked window to linear addresses",
+                "Banks of <size> bytes at <start>, <start>+<inc>, ... up to <end> are \
+                 placed back to back from <linear>. Repeatable; each runs in order \
+                 after any /S08MAP, /S12MAP or /S12XMAP.",
+                "in.hex /REMAP:0x8000-0xBFFF,0x10000,0x4000,0x8000",
+            ),
+            opt(
+                "MAPTAB",
+                "/MAPTAB:<file>",
+                "Move data by an address table",
+                "Each line of <file> is <start>-<end> -> <dest>; # and ; start \
+                 comments. Sources may not overlap, nor may destinations, and moved \
+                 data may not land on data that stays. Runs after the /REMAPs.",
+                "in.hex /MAPTAB:pages.txt",
+            ),
+        ],
+    },
+    Stage {
+        title: "dsPIC",
+        options: &[
+            opt(
+                "CDSPX",
+                "/CDSPX:<range>[;target]",
+                "Expand dsPIC 3-byte words to 4 bytes",
+                "Several operations are separated by ':'.",
+                "in.hex /CDSPX:0x0-0xFF;0x1000",
+            ),
+            opt(
+                "CDSPS",
+                "/CDSPS:<range>[;target]",
+                "Shrink dsPIC 4-byte words to 3 bytes",
+                "Several operations are separated by ':'.",
+                "in.hex /CDSPS:0x0-0xFF",
+            ),
+            opt(
+                "CDSPG",
+                "/CDSPG:<ranges>",
+                "Clear the dsPIC ghost byte in each word",
+                RANGE_DETAIL,
+                "in.hex /CDSPG:0x0-0xFF",
+            ),
+            opt(
+                "CDSPI",
+                "/CDSPI:<range>[;value]",
+                "Insert a dsPIC ghost byte after every 3 bytes",
+                "The packed range becomes 4-byte words from its start, each ending in \
+                 <value> (default 0x00); it may not grow into other data. The range \
+                 length must be a multiple of 3. Several operations are separated by ':'.",
+                "in.hex /CDSPI:0x0-0xBF;0xFF",
+            ),
+        ],
+    },
+    Stage {
+        title: "Fill",
+        options: &[
+            opt(
+                "FR",
+                "/FR:<ranges>",
+                "Fill gaps in the ranges with the /FP pattern",
+                "Without /FP the fill is random. Existing data is kept. START and END \
+                 resolve against the data before /MO and /MT merges.",
+                "in.hex /FR:0x1000,0x100",
+            ),
+            opt(
+                "FP",
+                "/FP:<hexbytes>|@<file>|INC[:start[:step]]|RANDOM",
+                "Fill pattern for /FR (repeated)",
+                "@<file> repeats the file's contents. INC writes a byte ramp from start \
+                 (default 0) in steps of step (default 1), wrapping past 0xFF. Patterns \
+                 restart at the start of each range. RANDOM asks for random bytes, the \
+                 same as leaving /FP out.",
+                "in.hex /FR:0x1000,0x100 /FP:A55A",
+            ),
+            opt(
+                "FRSEED",
+                "/FRSEED:<u64>",
+                "Seed for random /FR fill",
+                "With a seed, identical inputs produce identical output. \
+                 Without one the seed comes from the clock.",
+                "in.hex /FR:0x1000,0x100 /FP:RANDOM /FRSEED:1234",
+            ),
+        ],
+    },
+    Stage {
+        title: "Cut",
+        options: &[opt(
+            "CR",
+            "/CR:<ranges>",
+            "Remove data in the ranges",
+            OPEN_RANGE_DETAIL,
+            "in.hex /CR:0x1010-0x101F",
+        )],
+    },
+    Stage {
+        title: "Merge",
+        options: &[
+            opt(
+                "MT",
+                "/MT:<file>[;offset][:range][+...]",
+                "Merge files; existing data wins",
+                "The offset may be negative. The range selects data of the merged file. \
+                 A file pattern merges every match in sorted order.",
+                "in.hex /MT:a.hex;-0x10:0x1000-0x10FF+b.s19",
+            ),
+            opt(
+                "MO",
+                "/MO:<file>[;offset][:range][+...]",
+                "Merge files; merged data wins",
+                "The offset may be negative. The range selects data of the merged file. \
+                 A file pattern merges every match in sorted order.",
+                "in.hex /MO:c.hex;0x100",
+            ),
+            opt(
+                "LABEL",
+                "/LABEL:<range>;<name>",
+                "Label the data in a range as a named block",
+                "Runs after the merges. Split and cut pieces keep their label, and /XSB \
+                 names the block's file <stem>_<name>.<ext>; /XCSV and /INFO show it. \
+                 Names use letters, digits, '_', '-' and '.'. Repeatable.",
+                "in.hex /MO:cal.hex /LABEL:0x8000-0x9FFF;calibration /XSB -o out.bin",
+            ),
+        ],
+    },
+    Stage {
+        title: "Address filter",
+        options: &[opt(
+            "AR",
+            "/AR:<ranges>",
+            "Keep only data in the ranges",
+            OPEN_RANGE_DETAIL,
+            "in.hex /AR:0x0-0xFFFF",
+        )],
+    },
+    Stage {
+        title: "Log commands",
+        options: &[opt(
+            "L",
+            "/L:<file>",
+            "Run the commands in a log file",
+            "",
+            "/L:cmds.log",
+        )],
+    },
+    Stage {
+        title: "Single region",
+        options: &[opt(
+            "FA",
+            "/FA",
+            "Fill all gaps to make one region (uses /AF)",
+            "Spans over 256 MiB are an error, also when /BHFCT lets the writers fill the gaps.",
+            "in.hex /FA",
+        )],
+    },
+    Stage {
+        title: "Align",
+        options: &[
+            opt(
+                "AD",
+                "/AD:<n>",
+                "Align segment start addresses to n bytes",
+                "The short form /ADxx takes hex digits without a separator.",
+                "in.hex /AD:0x10",
+            ),
+            opt(
+                "AL",
+                "/AL[:n]",
+                "Also align segment lengths",
+                "",
+                "in.hex /AD:0x10 /AL",
+            ),
+            opt(
+                "AF",
+                "/AF:<byte>",
+                "Fill byte for alignment and /FA (default FF)",
+                "The short form /AFxx takes hex digits without a separator.",
+                "in.hex /AD:0x10 /AF:0x00",
+            ),
+            opt(
+                "AE",
+                "/AE:<n>",
+                "Erase block size for OEM headers",
+                "",
+                "in.hex /AE:0x800",
+            ),
+            opt(
+                "AEMAP",
+                "/AEMAP:<file>",
+                "Flash sector layout for OEM header erase lists",
+                "One sector per line, start-end or start,size, with *count for that many \
+                 equal sectors in a row (0x0,16K*4). The erase list is every sector holding \
+                 data, neighbours merged into one run. Replaces /AE; an INI erase list still \
+                 wins. Data outside every sector is an error.",
+                "in.hex /AEMAP:flash.sec /XF -o out.hex",
+            ),
+        ],
+    },
+    Stage {
+        title: "Split",
+        options: &[
+            opt(
+                "SB",
+                "/SB:<size>",
+                "Split segments into blocks of at most size bytes",
+                "Blocks are measured from the start of each segment.",
+                "in.hex /SB:0x400",
+            ),
+            opt(
+                "SBA",
+                "/SBA:<boundary>",
+                "Split blocks where they cross a multiple of boundary",
+                "Runs before /SB, so with both no block crosses a boundary or exceeds the size.",
+                "in.hex /SBA:0x1000 /SB:0x400",
+            ),
+        ],
+    },
+    Stage {
+        title: "Swap",
+        options: &[
+            opt(
+                "SWAPWORD",
+                "/SWAPWORD",
+                "Swap bytes in 16-bit words",
+                "",
+                "in.hex /SWAPWORD",
+            ),
+            opt(
+                "SWAPLONG",
+                "/SWAPLONG",
+                "Swap bytes in 32-bit words",
+                "",
+                "in.hex /SWAPLONG",
+            ),
+        ],
+    },
+    Stage {
+        title: "Stamps",
+        options: &[
+            opt(
+                "WS",
+                "/WS:<addr>;<text>[;NUL][;WIDTH=n][;FILL=xx]",
+                "Write an ASCII string at addr",
+                "NUL appends a 0x00 terminator; WIDTH pads the field with FILL (default 00) \
+                 and fails if the text does not fit. Quote the text to include ';'. \
+                 Stamps are written before checksums and signatures.",
+                "in.hex /WS:0x1F00;v1.2.3;NUL;WIDTH=16",
+            ),
+            opt(
+                "WDATE",
+                "/WDATE:<addr>;<BCD|ASCII>:<pattern>",
+                "Write the build date at addr",
+                "Pattern tokens: YYYY YY MM DD hh mm ss. BCD packs two digits per byte and \
+                 allows tokens only; ASCII copies other characters. Uses /DATE if given, \
+                 otherwise the system clock (UTC).",
+                "in.hex /WDATE:0x1F10;BCD:YYMMDD",
+            ),
+            opt(
+                "DATE",
+                "/DATE:<YYYY-MM-DD[Thh:mm:ss]>",
+                "Date written by /WDATE and the /XF release date",
+                "Makes stamped builds reproducible.",
+                "in.hex /WDATE:0x1F10;ASCII:YYYY-MM-DD /DATE:2026-03-07",
+            ),
+        ],
+    },
+    Stage {
+        title: "Checksums",
+        options: &[
+            opt(
+                "CS",
+                "/CS<n>[:target][;param...]",
+                "Checksum written big-endian",
+                CHECKSUM_DETAIL,
+                "in.hex /CS9:@append;0x1000-0x1FFF",
+            ),
+            opt(
+                "CSR",
+                "/CSR<n>[:target][;param...]",
+                "Checksum written little-endian",
+                CHECKSUM_DETAIL,
+                "in.hex /CSR9:crc.txt",
+            ),
+            opt(
+                "CSB",
+                "/CSB<n>[:target][;param...]",
+                "One checksum per segment, big-endian",
+                CHECKSUM_DETAIL,
+                "in.hex /CSB0",
+            ),
+            opt(
+                "CSBR",
+                "/CSBR<n>[:target][;param...]",
+                "One checksum per segment, little-endian",
+                CHECKSUM_DETAIL,
+                "in.hex /CSBR0",
+            ),
+            opt(
+                "CSM",
+                "/CSM<n>[:target][;param...]",
+                "Checksum that may repeat, big-endian",
+                "Cannot be combined with /CS. Parameters as for /CS.",
+                "in.hex /CSM9:@end /CSM0:@0x2000",
+            ),
+            opt(
+                "CSMR",
+                "/CSMR<n>[:target][;param...]",
+                "Checksum that may repeat, little-endian",
+                "Cannot be combined with /CS. Parameters as for /CS.",
+                "in.hex /CSMR3:@0x2000",
+            ),
+            opt(
+                "MAPFILE",
+                "/MAPFILE:<file>",
+                "Linker map for @SYM: checksum targets",
+                "GCC ld and IAR ILINK map files are recognized. A symbol missing from \
+                 the map fails the run.",
+                "in.hex /MAPFILE:app.map /CS9:@SYM:__app_crc;0x0-0x3FFF",
+            ),
+        ],
+    },
+    Stage {
+        title: "Pipeline file",
+        options: &[opt(
+            "PIPE",
+            "/PIPE:<file>",
+            "Run the steps of a TOML or JSON pipeline description",
+            "Runs after the checksums; a .json file is read as JSON, anything else \
+             as TOML. Merge paths are relative to the file. Checksum results with a \
+             file target are written as for /CS. Needs the `serde` feature.",
+            "in.hex /PIPE:variant.toml /XI -o out.hex",
+        )],
+    },
+    Stage {
+        title: "Data processing",
+        options: &[
+            opt(
+                "DP",
+                "/DP<n>[:@placement]:<key>[,password][;outfile[;range]]",
+                "Sign or MAC the data",
+                "Methods 32/33/38/39, 46-53 and 60-63. The placement is as for /CS; the \
+                 range takes /excludes. The key is a file; MAC methods 60-63 also take \
+                 an inline hex:<digits> key.",
+                "in.hex /DP32:@append:key.pem;sig.bin",
+            ),
+            opt(
+                "DPEXT",
+                "/DPEXT[len][:@placement]:<command>[;outfile[;range]]",
+                "Sign with an external command",
+                "{payload} in the command is the data file and the command writes the \
+                 signature to {signature}; both are substituted already quoted. len is \
+                 the signature size in bytes, required with a placement. Quote a command \
+                 that contains ';'.",
+                "in.hex /DPEXT:\"sign {payload} {signature}\";sig.bin",
+            ),
+        ],
+    },
+    Stage {
+        title: "Signature verification",
+        options: &[opt(
+            "SV",
+            "/SV<n>:<key>!<signature>[;range]",
+            "Verify a signature and fail on mismatch",
+            "Methods 4-13. The range takes /excludes.",
+            "in.hex /SV4:pub.pem!sig.bin",
+        )],
+    },
+    Stage {
+        title: "Postbuild",
+        options: &[opt(
+            "PB",
+            "/PB:<file>",
+            "Run each script line as another set of options",
+            "",
+            "in.hex /PB:post.txt",
+        )],
+    },
+    Stage {
+        title: "Recording",
+        options: &[opt(
+            "LOGOUT",
+            "/LOGOUT:<file>",
+            "Write the steps of this run as /L commands",
+            "",
+            "in.hex /FR:0x0,0x10 /LOGOUT:run.log",
+        )],
+    },
+    Stage {
+        title: "Export",
+        options: &[
+            opt(
+                "XI",
+                "/XI[:len[:type]]",
+                "Intel HEX (record length, record type 0-2)",
+                "",
+                "in.hex /XI:0x20",
+            ),
+            opt(
+                "XI2",
+                "/XI2[:len[:type]]",
+                "16-bit Intel HEX",
+                "",
+                "in.hex /XI2",
+            ),
+            opt(
+                "XS",
+                "/XS[:len[:type]][:hdr=name]",
+                "Motorola S-Record (type 1-3, S0 header text)",
+                "",
+                "in.hex /XS:0x10:2:hdr=BOOT",
+            ),
+            opt(
+                "XN",
+                "/XN[:start[,end[,fill]]] | /XN:RAW",
+                "Binary image in address order",
+                "Gaps are filled (default 0xFF). Without both start and end, an image spanning \
+                 over 256 MiB is refused, as /FA would; give the window in full to write it. \
+                 /XN:RAW writes the segments back to back in order of appearance, without \
+                 filling.",
+                "in.hex /XN:0x1000,0x1FFF,0xFF",
+            ),
+            opt("XJ", "/XJ", "JSON segment map", "", "in.hex /XJ"),
+            opt(
+                "XCSV",
+                "/XCSV",
+                "CSV segment table with CRC-32",
+                "Header row, then one row per segment: address_start, address_end, \
+                 length, crc32 (as /CS9). A TOTAL row gives the byte count and the \
+                 CRC-32 over all data with gaps skipped. Every field is quoted.",
+                "in.hex /XCSV -o regions.csv",
+            ),
+            opt("XD", "/XD[:len]", "Hex dump", "", "in.hex /XD:8"),
+            opt(
+                "XA",
+                "/XA[:len[:separator[:ADDR|PLAIN]]]",
+                "HEX ASCII",
+                "ADDR starts each line with `@00001000: `, PLAIN with `00001000 `; \
+                 each segment then starts a new line. /IA reads `@address:` prefixes back.",
+                "in.hex /XA:16:' ':ADDR",
+            ),
+            opt("XC", "/XC", "C source", "", "in.hex /XC"),
+            opt("XR", "/XR", "Rust source", "", "in.hex /XR"),
+            opt(
+                "XF",
+                "/XF[:RELAXED]",
+                "Ford Intel HEX",
+                "The header comes from the INI's [FORDHEADER] and /FORD. RELAXED fills \
+                 missing keys with placeholders (APPLICATION>TEST, MASK NUMBER>0, \
+                 MODULE TYPE>UNKNOWN, PRODUCTION MODULE PART NUMBER>UNKNOWN, \
+                 WERS NOTICE>NONE, COMMENTS>TEST BUILD, RELEASED BY>h3xy, \
+                 MODULE NAME>UNKNOWN, MODULE ID>0x0000) and needs no INI. The release \
+                 date defaults to /DATE, or today.",
+                "in.hex /XF:RELAXED /FORD:module_id=0x7E0 -o out.hex",
+            ),
+            opt(
+                "XG",
+                "/XG[:addr]",
+                "GM header (experimental)",
+                GM_EXPERIMENTAL,
+                "in.hex /XG:0x1000",
+            ),
+            opt(
+                "XGC",
+                "/XGC[:addr]",
+                "GM operating software header (experimental)",
+                GM_EXPERIMENTAL,
+                "in.hex /XGC",
+            ),
+            opt(
+                "XGCC",
+                "/XGCC[:addr]",
+                "GM calibration header (experimental)",
+                GM_EXPERIMENTAL,
+                "in.hex /XGCC",
+            ),
+            opt(
+                "XGAC",
+                "/XGAC",
+                "GAC binary (experimental)",
+                GAC_EXPERIMENTAL,
+                "in.hex /XGAC",
+            ),
+            opt(
+                "XGACSWIL",
+                "/XGACSWIL",
+                "GAC SWIL binary (experimental)",
+                GAC_EXPERIMENTAL,
+                "in.hex /XGACSWIL",
+            ),
+            opt("XK", "/XK", "Flash kernel", "", "in.hex /XK"),
+            opt("XP", "/XP", "Porsche binary", "", "in.hex /XP"),
+            opt(
+                "XSB",
+                "/XSB[:<template>]",
+                "One binary file per segment",
+                "Files are named <stem>_<addr>.<ext>, or after the block's /LABEL. A template \
+                 sets the name instead, from {stem}, {ext}, {label}, {addr}, {index} (0-based, \
+                 in address order) and {length}; the numbers take a [0][width][x|X|d] spec. \
+                 Two blocks with the same name are an error, before any file is written.",
+                "in.hex /XSB:\"{stem}_{index:02}_{addr:08X}.{ext}\" -o out.bin",
+            ),
+            opt(
+                "XV",
+                "/XV",
+                "VAG binary (experimental)",
+                "Experimental: the layout follows a general VAG/ODX-style description and has \
+                 not been checked against a reference file or a real VAG consumer, so it may \
+                 change.",
+                "in.hex /XV",
+            ),
+            opt("XVBF", "/XVBF", "Volvo VBF", "", "in.hex /XVBF"),
+            opt("XB", "/XB", "Fiat binary", "", "in.hex /XB"),
+            opt(
+                "ENTRY",
+                "/ENTRY:<addr>",
+                "Start address record for /XI and /XS",
+                "",
+                "in.hex /XI /ENTRY:0x8000",
+            ),
+            opt(
+                "CRLF",
+                "/CRLF",
+                "CRLF line endings (default)",
+                "",
+                "in.hex /CRLF",
+            ),
+            opt("LF", "/LF", "LF line endings", "", "in.hex /LF"),
+            opt(
+                "LOWER",
+                "/LOWER",
+                "Lowercase hex digits",
+                "",
+                "in.hex /LOWER",
+            ),
+            opt(
+                "ALIGNREC",
+                "/ALIGNREC",
+                "Start /XI and /XS data records at multiples of the record length",
+                "The first record of a block that starts off a boundary is shortened.",
+                "in.hex /XI:0x20 /ALIGNREC",
+            ),
+            opt(
+                "BANK",
+                "/BANK:<window_start>,<window_size>[,first]",
+                "Export a bank-switched image for a paged microcontroller",
+                "Data from window_start up is cut into window_size banks, numbered from \
+                 first (default 0), each placed at window_start in its bank; lower addresses \
+                 stay common. /XI writes one extended segment record per bank, so banks \
+                 above 15 do not fit.",
+                "in.hex /BANK:0x8000,0x8000,1 /XI -o banked.hex",
+            ),
+            opt(
+                "O",
+                "-o <file>",
+                "Output file; without /X.. the input format is kept",
+                "Each -o belongs to the /X.. just before or after it, so one run can write \
+                 several outputs. Two /X.. options sharing one -o are an error.",
+                "in.hex /XI -o out.hex /XN -o out.bin",
+            ),
+            opt(
+                "INPLACE",
+                "/INPLACE",
+                "Derive a missing -o from the input path (also --in-place)",
+                "The input (or /IN, /IA) path gets the format's extension, so /XI on a .hex \
+                 input overwrites it. Without this, every /X.. needs its own -o.",
+                "in.hex /XC /INPLACE",
+            ),
+            opt(
+                "MKDIR",
+                "/MKDIR",
+                "Create missing directories for output files",
+                "Applies to every -o (including /XSB parts and /XC .c/.h pairs), /CS and \
+                 /SV checksum or signature files, and /LOGOUT. Without it, a missing \
+                 directory is an I/O error naming the path.",
+                "in.hex /XI -o build/out/app.hex /MKDIR",
+            ),
+        ],
+    },
+    Stage {
+        title: "Compare",
+        options: &[opt(
+            "CMP",
+            "/CMP:<file>",
+            "Compare the result's data with a reference file",
+            "Only addresses and bytes count, not the file format or record layout. \
+             /AR ranges restrict both sides. Differing ranges are listed with their \
+             first bytes, and any difference fails the run with exit code 5.",
+            "out.hex /CMP:golden.hex",
+        )],
+    },
+];
+
+const EXIT_CODES: &[(u8, &str)] = &[
+    (0, "Success, including help"),
+    (EXIT_ARGUMENT, "Invalid command line"),
+    (
+        EXIT_IO,
+        "Input or output file missing, unreadable or malformed",
+    ),
+    (
+        EXIT_OPERATION,
+        "Processing step failed (range, checksum, log, OEM header, image an export cannot hold, ...)",
+    ),
+    (EXIT_VERIFICATION, "/SV signature or /CMP comparison failed"),
+    (EXIT_UNSUPPORTED, "Unsupported option or combination"),
+];
+
+/// The `/?` overview: one line per option, grouped by processing stage.
+pub(super) fn overview() -> String {
+    let mut out = format!(
+        "h3xy {} - HexView-compatible hex file processing\n\n\
+         Usage: h3xy <file> [options] [-o <file>]\n\
+         Options are case-insensitive and take values after ':' or '='.\n\
+         Numbers are decimal, 0x-prefixed hex or b-suffixed binary; 64K and 2M\n\
+         count KiB and MiB.\n\
+         /HELP:<option> shows details for one option.\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    for stage in STAGES {
+        out.push_str(&format!("\n{}:\n", stage.title));
+        for option in stage.options {
+            if option.syntax.len() < 30 {
+                out.push_str(&format!("  {:<30}{}\n", option.syntax, option.summary));
+            } else {
+                out.push_str(&format!(
+                    "  {}\n  {:<30}{}\n",
+                    option.syntax, "", option.summary
+                ));
+            }
+        }
+    }
+    out.push_str("\nExit codes:\n");
+    for (code, meaning) in EXIT_CODES {
+        out.push_str(&format!("  {code:<30}{meaning}\n"));
+    }
+    out
+}
+
+/// Details for one option, or `None` if `topic` names no option.
+///
+/// The topic may carry a leading `/` or `-`, a value and a method number, so
+/// `/HELP:/CS9:@end` finds `/CS`.
+pub(super) fn topic(topic: &str) -> Option<String> {
+    let (option, stage) = find(topic)?;
+    let mut out = format!("{}\n  {}\n", option.syntax, option.summary);
+    if !option.detail.is_empty() {
+        out.push('\n');
+        push_wrapped(&mut out, option.detail, 78);
+    }
+    out.push_str(&format!(
+        "\nStage: {}\nExample: h3xy {}\n",
+        stage.title, option.example
+    ));
+    Some(out)
+}
+
+/// Append `text` word-wrapped to `width` columns.
+fn push_wrapped(out: &mut String, text: &str, width: usize) {
+    let mut column = 0;
+    for word in text.split_whitespace() {
+        if column > 0 && column + 1 + word.len() > width {
+            out.push('\n');
+            column = 0;
+        } else if column > 0 {
+            out.push(' ');
+            column += 1;
+        }
+        out.push_str(word);
+        column += word.len();
+    }
+    out.push('\n');
+}
+
+fn find(topic: &str) -> Option<(&'static OptionHelp, &'static Stage)> {
+    let topic = topic.trim();
+    let topic = topic
+        .strip_prefix('/')
+        .or_else(|| topic.strip_prefix('-'))
+        .unwrap_or(topic);
+    let name = topic
+        .split([':', '='])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_uppercase();
+    let lookup = |name: &str| {
+        STAGES.iter().find_map(|stage| {
+            stage
+                .options
+                .iter()
+                .find(|option| option.name == name)
+                .map(|option| (option, stage))
+        })
+    };
+    lookup(&name).or_else(|| lookup(name.trim_end_matches(|c: char| c.is_ascii_digit())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::types::Args;
+    use super::*;
+
+    #[test]
+    fn test_every_example_parses() {
+        for option in STAGES.iter().flat_map(|stage| stage.options) {
+            if let Err(e) = Args::parse_from_str(option.example) {
+                panic!("{} example {:?}: {e}", option.syntax, option.example);
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_emitted_option_has_help() {
+        let args = Args::parse_from_str(
+            "in.hex /E+=err.log /S /WERROR /V /VERBOSE -vv /P:cfg.ini /FORD:module_id=0x7E0 /DEF:BUILD_ID=42 /II2=extra.hex /IN:raw.bin;0x100 \
+             /IA:data.txt /UNBANK:0x8000,0x8000 /S08MAP /S12MAP /S12XMAP /REMAP:0x8000-0xBFFF,0x10000,0x4000,0x8000 \
+             /MAPTAB:pages.txt /CDSPX:0x0-0xFF /CDSPS:0x400-0x4FF /CDSPG:0x600,0x10 /CDSPI:0x700-0x7FE \
+             /FR:0x1000,0x100 /FP:A55A /PIPE:variant.toml \
+             /FRSEED:7 /CR:0x1010-0x101F /MT:a.hex /MO:c.hex /LABEL:0x0-0xFF;boot /AR:0x0-0xFFFF /L:cmds.log /LOGOUT:run.log \
+             /FA /PB:post.txt /AD:0x10 /AL /AF:0x00 /AE:0x800 /AEMAP:flash.sec /SB:0x400 /SBA:0x1000 /SWAPWORD \
+             /SWAPLONG /WS:0x1F00;v1;NUL;WIDTH=4;FILL=20 /WDATE:0x1F10;BCD:YYMMDD /DATE:2026-03-07 \
+             /CSR9 /CSB0 /DP32:key.pem /SV4:pub.pem!sig.bin /BHFCT=1 /BTFST=2 /MMAP /BTBS=3 /GZMAX=4 \
+             /ECHOARGS /INFO /ELFVMA /LENIENT /IT:SREC /LF /LOWER /ALIGNREC /BANK:0x8000,0x8000,1 /MKDIR /HELP:CS /XI /ENTRY:0x0 -o out.hex \
+             /CMP:golden.hex /MAPFILE:app.map",
+        )
+        .unwrap();
+        for arg in args.to_command_line().iter().skip(1) {
+            if arg.starts_with(['/', '-']) {
+                assert!(find(arg).is_some(), "no help entry for {arg}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_topic_lookup() {
+        for name in ["CS", "/cs9", "CS9:@end", "-o", "xi2", "dp46"] {
+            assert!(topic(name).is_some(), "{name}");
+        }
+        assert_eq!(find("CSR3").unwrap().0.name, "CSR");
+        assert_eq!(find("XI2").unwrap().0.name, "XI2");
+        assert!(topic("NOPE").is_none());
+        assert!(topic("").is_none());
+        assert!(topic("CS").unwrap().lines().all(|line| line.len() <= 78));
+    }
+
+    #[test]
+    fn test_overview_lists_stages_in_order() {
+        let text = overview();
+        let positions: Vec<usize> = STAGES
+            .iter()
+            .map(|stage| text.find(&format!("\n{}:\n", stage.title)).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+        assert!(text.contains("/DPEXT[len][:@placement]:<command>[;outfile[;range]]"));
+        assert!(text.contains("\nExit codes:\n  0 "));
+        assert!(text.contains("\n  5                             /SV signature"));
+    }
+}
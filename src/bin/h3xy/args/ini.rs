@@ -1,33 +1,373 @@
-use std::collections::HashMap;
-use std::path::Path;
+//! OEM settings files (CLI: /P).
+//!
+//! `[SECTION]` headers group `key = value` lines; names and keys are
+//! case-insensitive. A value in double or single quotes is kept verbatim, so
+//! it may hold `=`, `;`, `#` or leading spaces. An unquoted value is trimmed
+//! and ends at a ` ;` or ` #` comment. Lines starting with `;` or `#` are
+//! comments, and `!include other.ini` reads another file (relative to this
+//! one) in place. The included file starts in the current section; after it
+//! the including file carries on in its own.
+//!
+//! `${NAME}` in a value is replaced by the /DEF:NAME=value option or, failing
+//! that, the environment variable; `$$` is a literal `$`. Loading fails with
+//! the list of names that neither defines.
 
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use super::error::{CliError, file_context};
 use super::io::ReadProvider;
 
+/// How deep `!include` may nest, which also stops include cycles.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A parsed INI file. Keys before the first header are in section `""`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(super) struct Ini {
+    sections: HashMap<String, HashMap<String, String>>,
+    /// Every key regardless of section; the last one in the file wins.
+    flat: HashMap<String, String>,
+    /// `${NAME}`s found while parsing that nothing defines.
+    undefined: BTreeSet<String>,
+}
+
+/// Looks up a `${NAME}`.
+type Vars<'a> = dyn Fn(&str) -> Option<String> + 'a;
+
+/// Whether `name` can appear in `${name}` and /DEF.
+pub(super) fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// `value` with `$$` and each `${NAME}` replaced; unknown names are added to
+/// `undefined` and left as they are.
+fn substitute(
+    value: &str,
+    vars: &Vars<'_>,
+    undefined: &mut BTreeSet<String>,
+) -> Result<String, String> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if let Some(after) = rest.strip_prefix("$$") {
+            out.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let (name, after) = after
+                .split_once('}')
+                .ok_or_else(|| format!("unclosed '${{' in {value}"))?;
+            if !is_variable_name(name) {
+                return Err(format!("invalid variable name '{name}'"));
+            }
+            match vars(name) {
+                Some(text) => out.push_str(&text),
+                None => {
+                    undefined.insert(name.to_string());
+                }
+            }
+            rest = after;
+        } else {
+            out.push('$');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+impl Ini {
+    /// Keys from every section, for exporters that ignore sections.
+    pub(super) fn into_flat(self) -> HashMap<String, String> {
+        self.flat
+    }
+
+    /// The keys of `name`, falling back to [`Ini::into_flat`] for keys the section
+    /// lacks, so INIs that put a key in another section keep working.
+    pub(super) fn section(&self, name: &str) -> HashMap<String, String> {
+        let mut keys = self.flat.clone();
+        if let Some(section) = self.sections.get(&name.to_ascii_lowercase()) {
+            keys.extend(section.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        keys
+    }
+
+    fn parse(
+        &mut self,
+        path: &Path,
+        provider: &impl ReadProvider,
+        vars: &Vars<'_>,
+        mut section: String,
+        depth: usize,
+    ) -> Result<(), CliError> {
+        let content = provider
+            .read_string(path)
+            .map_err(|e| file_context("/P", path)(e.into()))?;
+        let error = |line: usize, message: String| {
+            CliError::Config(format!("line {line}: {message}")).in_file("/P", path)
+        };
+
+        for (index, raw_line) in content.lines().enumerate() {
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with([';', '#']) {
+                continue;
+            }
+            if let Some(include) = line.strip_prefix("!include") {
+                let include = unquote(include.trim()).map_err(|e| error(index + 1, e))?;
+                if include.is_empty() {
+                    return Err(error(index + 1, "!include needs a file".to_string()));
+                }
+                if depth >= MAX_INCLUDE_DEPTH {
+                    return Err(error(
+                        index + 1,
+                        format!("!include nested more than {MAX_INCLUDE_DEPTH} deep"),
+                    ));
+                }
+                let dir = path.parent().unwrap_or_else(|| Path::new("."));
+                let include_path: PathBuf = dir.join(include);
+                self.parse(&include_path, provider, vars, section.clone(), depth + 1)?;
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_ascii_lowercase();
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim().to_ascii_lowercase();
+            let value = unquote(value.trim())
+                .and_then(|value| substitute(value, vars, &mut self.undefined))
+                .map_err(|e| error(index + 1, e))?;
+            self.sections
+                .entry(section.clone())
+                .or_default()
+                .insert(key.clone(), value.clone());
+            self.flat.insert(key, value);
+        }
+        Ok(())
+    }
+}
+
+/// A quoted value's contents, or an unquoted value up to its comment.
+fn unquote(value: &str) -> Result<&str, String> {
+    if let Some(quote) = value.chars().next().filter(|c| matches!(c, '"' | '\'')) {
+        let (inner, rest) = value[1..]
+            .split_once(quote)
+            .ok_or_else(|| format!("unclosed quote in {value}"))?;
+        let rest = rest.trim_start();
+        if !rest.is_empty() && !rest.starts_with([';', '#']) {
+            return Err(format!("unexpected text after quoted value: {rest}"));
+        }
+        return Ok(inner);
+    }
+    let end = [" ;", " #", "\t;", "\t#"]
+        .iter()
+        .filter_map(|comment| value.find(comment))
+        .min()
+        .unwrap_or(value.len());
+    Ok(value[..end].trim_end())
+}
+
+/// Parse the INI at `path`, taking `${NAME}`s from `defines` (/DEF) and then
+/// the environment.
 pub(super) fn load_ini(
     path: &Path,
     provider: &impl ReadProvider,
-) -> Result<HashMap<String, String>, std::io::Error> {
-    let content = provider.read_string(path)?;
-    let mut map = HashMap::new();
-
-    for line in content.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
-        if line.starts_with(';') || line.starts_with('#') {
-            continue;
-        }
-        if line.starts_with('[') && line.ends_with(']') {
-            continue;
+    defines: &[(String, String)],
+) -> Result<Ini, CliError> {
+    let vars = |name: &str| {
+        defines
+            .iter()
+            .rev()
+            .find(|(define, _)| define == name)
+            .map(|(_, value)| value.clone())
+            .or_else(|| std::env::var(name).ok())
+    };
+    load_ini_with(path, provider, &vars)
+}
+
+fn load_ini_with(
+    path: &Path,
+    provider: &impl ReadProvider,
+    vars: &Vars<'_>,
+) -> Result<Ini, CliError> {
+    let mut ini = Ini::default();
+    ini.parse(path, provider, vars, String::new(), 0)?;
+    if !ini.undefined.is_empty() {
+        let names: Vec<_> = ini.undefined.iter().map(String::as_str).collect();
+        return Err(CliError::Config(format!(
+            "undefined variables (set them with /DEF:NAME=value or the environment): {}",
+            names.join(", ")
+        ))
+        .in_file("/P", path));
+    }
+    Ok(ini)
+}
+
+/// Like [`load_ini`], but a missing file reads as empty.
+pub(super) fn load_ini_if_exists(
+    path: &Path,
+    provider: &impl ReadProvider,
+    defines: &[(String, String)],
+) -> Result<Ini, CliError> {
+    match provider.read_string(path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Ini::default()),
+        _ => load_ini(path, provider, defines),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Files(HashMap<PathBuf, String>);
+
+    impl ReadProvider for Files {
+        fn read_bytes(&self, path: &Path) -> Result<Vec<u8>, std::io::Error> {
+            self.0
+                .get(path)
+                .map(|content| content.clone().into_bytes())
+                .ok_or_else(|| std::io::ErrorKind::NotFound.into())
         }
-        let Some((key, value)) = line.split_once('=') else {
-            continue;
+    }
+
+    fn load(files: &[(&str, &str)]) -> Result<Ini, CliError> {
+        load_with_vars(files, &[])
+    }
+
+    fn load_with_vars(files: &[(&str, &str)], vars: &[(&str, &str)]) -> Result<Ini, CliError> {
+        let provider = Files(
+            files
+                .iter()
+                .map(|(path, content)| (PathBuf::from(path), content.to_string()))
+                .collect(),
+        );
+        let lookup = |name: &str| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
         };
-        let key = key.trim().to_ascii_lowercase();
-        let value = value.trim().trim_matches('"').to_string();
-        map.insert(key, value);
+        load_ini_with(Path::new(files[0].0), &provider, &lookup)
+    }
+
+    #[test]
+    fn test_same_key_in_two_sections() {
+        let ini = load(&[(
+            "cfg.ini",
+            "[FORDHEADER]\nCOMMENTS=ford\n[CCODE]\nComments = c code\nPrefix=drv\n",
+        )])
+        .unwrap();
+        assert_eq!(ini.section("FordHeader")["comments"], "ford");
+        assert_eq!(ini.section("CCODE")["comments"], "c code");
+        // Keys only in another section still resolve, as before sections.
+        assert_eq!(ini.section("FORDHEADER")["prefix"], "drv");
+        assert_eq!(ini.into_flat()["comments"], "c code");
+    }
+
+    #[test]
+    fn test_quoted_values_keep_separators() {
+        let ini = load(&[(
+            "cfg.ini",
+            "; comment\n[A]\nq = \"  a=b ; c # d\" ; note\ns='x=y'\nplain = 1 ; note\n\
+             hash = a#b\nempty =\n",
+        )])
+        .unwrap();
+        let a = ini.section("A");
+        assert_eq!(a["q"], "  a=b ; c # d");
+        assert_eq!(a["s"], "x=y");
+        assert_eq!(a["plain"], "1");
+        assert_eq!(a["hash"], "a#b");
+        assert_eq!(a["empty"], "");
+
+        let err = load(&[("cfg.ini", "[A]\nq=\"open\n")]).unwrap_err();
+        assert!(err.to_string().contains("line 2: unclosed quote"), "{err}");
     }
 
-    Ok(map)
+    #[test]
+    fn test_include_reads_in_place() {
+        let ini = load(&[
+            ("dir/main.ini", "[GM]\nA=1\n!include common.ini\nC=3\n"),
+            ("dir/common.ini", "B=2\nC=2\n[OTHER]\nD=4\n"),
+        ])
+        .unwrap();
+        assert_eq!(ini.section("GM")["b"], "2");
+        assert_eq!(ini.section("GM")["c"], "3");
+        assert_eq!(ini.section("OTHER")["d"], "4");
+
+        let err = load(&[("loop.ini", "!include loop.ini\n")]).unwrap_err();
+        assert!(err.to_string().contains("nested more than"), "{err}");
+        let err = load(&[("main.ini", "!include missing.ini\n")]).unwrap_err();
+        assert!(err.to_string().contains("missing.ini"), "{err}");
+    }
+
+    #[test]
+    fn test_variables_expand_in_values() {
+        let ini = load_with_vars(
+            &[
+                (
+                    "main.ini",
+                    "A = id_${BUILD_ID}\nB='$$5 ${BUILD_ID}'\nC=$x\n!include inc.ini\n",
+                ),
+                ("inc.ini", "D=${TARGET}\n"),
+            ],
+            &[("BUILD_ID", "42"), ("TARGET", "ecu")],
+        )
+        .unwrap()
+        .into_flat();
+        assert_eq!(ini["a"], "id_42");
+        assert_eq!(ini["b"], "$5 42");
+        assert_eq!(ini["c"], "$x");
+        assert_eq!(ini["d"], "ecu");
+    }
+
+    #[test]
+    fn test_undefined_variables_are_listed() {
+        let err = load_with_vars(
+            &[
+                (
+                    "main.ini",
+                    "A=${ZED}\nB=${ALPHA}${KNOWN}\n!include inc.ini\n",
+                ),
+                ("inc.ini", "C=${ALPHA}\n"),
+            ],
+            &[("KNOWN", "1")],
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("main.ini"), "{err}");
+        assert!(
+            err.to_string().contains(
+                "undefined variables (set them with /DEF:NAME=value or the environment): ALPHA, ZED"
+            ),
+            "{err}"
+        );
+
+        let err = load(&[("main.ini", "A=${OPEN\n")]).unwrap_err();
+        assert!(err.to_string().contains("line 1: unclosed '${'"), "{err}");
+        let err = load(&[("main.ini", "A=${1X}\n")]).unwrap_err();
+        assert!(
+            err.to_string().contains("invalid variable name '1X'"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_defines_win_over_environment() {
+        // PATH is always set, so it shows the environment lookup too.
+        let provider = Files(
+            [(PathBuf::from("cfg.ini"), "A=${PATH}\n".to_string())]
+                .into_iter()
+                .collect(),
+        );
+        let ini = load_ini(Path::new("cfg.ini"), &provider, &[]).unwrap();
+        assert_eq!(ini.into_flat()["a"], std::env::var("PATH").unwrap());
+        let defines = [("PATH".to_string(), "cli".to_string())];
+        let ini = load_ini(Path::new("cfg.ini"), &provider, &defines).unwrap();
+        assert_eq!(ini.into_flat()["a"], "cli");
+    }
 }
@@ -1,12 +1,36 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 
 use crate::HexFile;
 
-use super::error::CliError;
+use super::diagnostics::Diagnostics;
+use super::error::{CapturedOutput, CliError};
 use super::ini::load_ini;
-use super::parse_util::parse_number;
-use super::types::Args;
-use super::types::OutputFormat;
+use super::types::{Args, ParseArgError};
+use super::types::{OutputFormat, OutputSpec};
+
+mod ford;
+mod input;
+mod oem;
+mod output;
+mod source;
+
+pub(super) use ford::{FORD_OPTIONAL_KEYS, FORD_REQUIRED_KEYS};
+pub(super) use input::{
+    InputOptions, expand_input_pattern, hexfiles_overlap, input_format_name, load_binary_input,
+    load_hex_ascii_input, load_input_with_options, load_intel_hex_16bit_input,
+};
+
+use ford::write_ford_ihex_output;
+use oem::{
+    write_fiat_output, write_flash_kernel_output, write_gac_output, write_gm_output,
+    write_porsche_output, write_vag_output, write_vbf_output,
+};
+use output::{TextStyle, write_output};
+use source::{write_c_code_output, write_rust_output};
 
 pub(super) trait ReadProvider {
     fn read_bytes(&self, path: &Path) -> Result<Vec<u8>, std::io::Error>;
@@ -16,6 +40,12 @@ pub(super) trait ReadProvider {
         String::from_utf8(bytes)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
     }
+
+    /// Size of `path` if `read_bytes` would return the file on disk as-is,
+    /// so it can be memory-mapped instead.
+    fn mappable_len(&self, _path: &Path) -> Option<u64> {
+        None
+    }
 }
 
 pub(super) struct FsProvider;
@@ -24,398 +54,487 @@ impl ReadProvider for FsProvider {
     fn read_bytes(&self, path: &Path) -> Result<Vec<u8>, std::io::Error> {
         std::fs::read(path)
     }
+
+    fn mappable_len(&self, path: &Path) -> Option<u64> {
+        std::fs::metadata(path).ok().map(|metadata| metadata.len())
+    }
 }
 
-pub(super) fn load_input(provider: &impl ReadProvider, path: &Path) -> Result<HexFile, CliError> {
-    let content = provider.read_bytes(path)?;
-
-    let mut ascii_only = true;
-    let mut first_nonempty_line: Option<Vec<u8>> = None;
-    let mut ascii_lines_checked = 0usize;
-    let mut current_line: Vec<u8> = Vec::new();
-
-    for &b in &content {
-        if b == b'\n' || b == b'\r' {
-            if !current_line.is_empty() {
-                if ascii_lines_checked < 25 {
-                    if !current_line.is_ascii() {
-                        ascii_only = false;
-                    }
-                    ascii_lines_checked += 1;
-                }
-                if first_nonempty_line.is_none() {
-                    first_nonempty_line = Some(current_line.clone());
-                }
-                if ascii_lines_checked >= 25 {
-                    break;
-                }
-            }
-            current_line.clear();
-            continue;
-        }
-        current_line.push(b);
+/// Where exports and checksum, signature and /LOGOUT files go.
+pub(super) trait WriteProvider {
+    fn write_bytes(&self, path: &Path, contents: &[u8]) -> Result<(), std::io::Error>;
+
+    /// /MKDIR: create `dir` and its missing parents.
+    fn create_dir_all(&self, dir: &Path) -> Result<(), std::io::Error>;
+
+    /// Whether large images should stream through a buffered file instead
+    /// of being built in memory first.
+    fn streams_to_disk(&self) -> bool {
+        false
     }
-    if !current_line.is_empty() && first_nonempty_line.is_none() {
-        first_nonempty_line = Some(current_line.clone());
+
+    /// Called before each export is written; later writes belong to it.
+    fn begin_output(&self, _format: &OutputFormat) {}
+
+    /// Called before a streamed file is created on disk; an error stops the
+    /// write before the file is touched.
+    fn check_target(&self, _path: &Path) -> Result<(), std::io::Error> {
+        Ok(())
     }
+}
 
-    if ascii_lines_checked == 0 && !content.is_empty() {
-        ascii_only = content.is_ascii();
+impl WriteProvider for FsProvider {
+    fn write_bytes(&self, path: &Path, contents: &[u8]) -> Result<(), std::io::Error> {
+        std::fs::write(path, contents)
     }
 
-    if !ascii_only {
-        return Ok(crate::parse_binary(&content, 0)?);
+    fn create_dir_all(&self, dir: &Path) -> Result<(), std::io::Error> {
+        std::fs::create_dir_all(dir)
     }
 
-    let first_line = first_nonempty_line.unwrap_or_default();
-    if first_line.first() == Some(&b':') {
-        let hexfile = crate::parse_intel_hex(&content)?;
-        Ok(hexfile)
-    } else if matches!(first_line.first(), Some(b'S') | Some(b's')) {
-        let hexfile = crate::parse_srec(&content)?;
-        Ok(hexfile)
-    } else {
-        let hexfile = crate::parse_binary(&content, 0)?;
-        Ok(hexfile)
+    fn streams_to_disk(&self) -> bool {
+        true
     }
 }
 
-pub(super) fn load_binary_input(
-    provider: &impl ReadProvider,
-    path: &Path,
-    offset: u32,
-) -> Result<HexFile, CliError> {
-    let content = provider.read_bytes(path)?;
-    let hexfile = crate::parse_binary(&content, offset)?;
-    Ok(hexfile)
+/// Keeps every written file in memory for
+/// [`execute_in_memory_captured`](super::execute_in_memory_captured).
+#[derive(Default)]
+pub(super) struct CaptureProvider {
+    files: RefCell<BTreeMap<PathBuf, Vec<u8>>>,
+    outputs: RefCell<Vec<CapturedOutput>>,
 }
 
-pub(super) fn load_hex_ascii_input(
-    provider: &impl ReadProvider,
-    path: &Path,
-    offset: u32,
-) -> Result<HexFile, CliError> {
-    let content = provider.read_bytes(path)?;
-    let hexfile = crate::parse_hex_ascii(&content, offset)?;
-    Ok(hexfile)
+impl CaptureProvider {
+    /// Files written outside any export, then the exports in order.
+    pub(super) fn into_parts(self) -> (BTreeMap<PathBuf, Vec<u8>>, Vec<CapturedOutput>) {
+        (self.files.into_inner(), self.outputs.into_inner())
+    }
 }
 
-pub(super) fn hexfiles_overlap(a: &HexFile, b: &HexFile) -> bool {
-    let mut a_segments = a.segments().to_vec();
-    let mut b_segments = b.segments().to_vec();
-    a_segments.sort_by_key(|s| s.start_address);
-    b_segments.sort_by_key(|s| s.start_address);
-
-    let mut i = 0usize;
-    let mut j = 0usize;
-    while i < a_segments.len() && j < b_segments.len() {
-        let a_seg = &a_segments[i];
-        let b_seg = &b_segments[j];
-        if a_seg.end_address() < b_seg.start_address {
-            i += 1;
-            continue;
-        }
-        if b_seg.end_address() < a_seg.start_address {
-            j += 1;
-            continue;
+impl WriteProvider for CaptureProvider {
+    fn write_bytes(&self, path: &Path, contents: &[u8]) -> Result<(), std::io::Error> {
+        match self.outputs.borrow_mut().last_mut() {
+            Some(output) => {
+                let name = path
+                    .file_name()
+                    .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy());
+                output.files.insert(name.into_owned(), contents.to_vec());
+            }
+            None => {
+                self.files
+                    .borrow_mut()
+                    .insert(path.to_path_buf(), contents.to_vec());
+            }
         }
-        return true;
+        Ok(())
+    }
+
+    fn create_dir_all(&self, _dir: &Path) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    fn begin_output(&self, format: &OutputFormat) {
+        self.outputs.borrow_mut().push(CapturedOutput {
+            format: format.clone(),
+            files: BTreeMap::new(),
+        });
     }
-    false
 }
 
-pub(super) fn load_intel_hex_16bit_input(
-    provider: &impl ReadProvider,
-    path: &Path,
-) -> Result<HexFile, CliError> {
-    let content = provider.read_bytes(path)?;
-    let hexfile = crate::parse_intel_hex_16bit(&content)?;
-    Ok(hexfile)
+/// Keeps every file a run writes in memory until [`commit`](Self::commit),
+/// so /WERROR can fail on a warning raised by a writer before any output
+/// appears. Directories are still created right away.
+pub(super) struct StagedWriter<'a> {
+    inner: &'a dyn WriteProvider,
+    staged: RefCell<Vec<Staged>>,
 }
 
-pub(super) fn write_output(
-    hexfile: &HexFile,
-    path: &PathBuf,
-    format: &Option<OutputFormat>,
-    bytes_per_line: Option<u8>,
-) -> Result<(), CliError> {
-    let format = format
-        .as_ref()
-        .unwrap_or(&OutputFormat::IntelHex { record_type: None });
-
-    match format {
-        OutputFormat::IntelHex { record_type } => {
-            let mode = match record_type {
-                Some(1) => crate::IntelHexMode::ExtendedLinear,
-                Some(2) => crate::IntelHexMode::ExtendedSegment,
-                _ => crate::IntelHexMode::Auto,
-            };
-            let options = crate::IntelHexWriteOptions {
-                bytes_per_line: bytes_per_line.unwrap_or(32),
-                mode,
-            };
-            let output = crate::write_intel_hex(hexfile, &options);
-            std::fs::write(path, output)?;
+enum Staged {
+    Output(OutputFormat),
+    File(PathBuf, Vec<u8>),
+}
+
+impl<'a> StagedWriter<'a> {
+    pub(super) fn new(inner: &'a dyn WriteProvider) -> Self {
+        Self {
+            inner,
+            staged: RefCell::default(),
         }
-        OutputFormat::SRecord { record_type } => {
-            let record_type = match record_type {
-                None => None,
-                Some(0) => Some(crate::SRecordType::S1),
-                Some(1) => Some(crate::SRecordType::S2),
-                Some(2) => Some(crate::SRecordType::S3),
-                Some(other) => {
-                    return Err(CliError::Other(format!(
-                        "unsupported S-Record type {other}"
-                    )));
-                }
-            };
-            let options = crate::SRecordWriteOptions {
-                bytes_per_line: bytes_per_line.unwrap_or(16),
-                record_type,
-            };
-            let output = crate::write_srec(hexfile, &options)?;
-            std::fs::write(path, output)?;
+    }
+
+    /// Hand everything written so far to the inner provider, in order.
+    pub(super) fn commit(self) -> Result<(), CliError> {
+        for staged in self.staged.into_inner() {
+            match staged {
+                Staged::Output(format) => self.inner.begin_output(&format),
+                Staged::File(path, contents) => self
+                    .inner
+                    .write_bytes(&path, &contents)
+                    .map_err(|e| CliError::from(e).in_file("", &path))?,
+            }
         }
-        OutputFormat::Binary => {
-            let options = crate::BinaryWriteOptions::default();
-            let output = crate::write_binary(hexfile, &options);
-            std::fs::write(path, output)?;
+        Ok(())
+    }
+}
+
+impl WriteProvider for StagedWriter<'_> {
+    fn write_bytes(&self, path: &Path, contents: &[u8]) -> Result<(), std::io::Error> {
+        self.staged
+            .borrow_mut()
+            .push(Staged::File(path.to_path_buf(), contents.to_vec()));
+        Ok(())
+    }
+
+    fn create_dir_all(&self, dir: &Path) -> Result<(), std::io::Error> {
+        self.inner.create_dir_all(dir)
+    }
+
+    fn begin_output(&self, format: &OutputFormat) {
+        self.staged
+            .borrow_mut()
+            .push(Staged::Output(format.clone()));
+    }
+}
+
+/// Refuses to replace the /IN file that /MMAP mapped: truncating it under
+/// the mapping would fault every later read of the image.
+pub(super) struct MappedInputGuard<'a> {
+    pub(super) inner: &'a dyn WriteProvider,
+    pub(super) mapped: &'a Path,
+}
+
+impl MappedInputGuard<'_> {
+    fn check(&self, path: &Path) -> Result<(), std::io::Error> {
+        if same_file(path, self.mapped) {
+            return Err(std::io::Error::other(format!(
+                "refusing to overwrite {}: it is the /IN file mapped by /MMAP",
+                self.mapped.display()
+            )));
         }
-        OutputFormat::HexAscii {
-            line_length,
-            separator,
-        } => {
-            let options = crate::HexAsciiWriteOptions {
-                line_length: line_length.unwrap_or(16) as usize,
-                separator: separator.clone(),
-            };
-            let output = crate::write_hex_ascii(hexfile, &options);
-            std::fs::write(path, output)?;
+        Ok(())
+    }
+}
+
+impl WriteProvider for MappedInputGuard<'_> {
+    fn write_bytes(&self, path: &Path, contents: &[u8]) -> Result<(), std::io::Error> {
+        self.check(path)?;
+        self.inner.write_bytes(path, contents)
+    }
+
+    fn create_dir_all(&self, dir: &Path) -> Result<(), std::io::Error> {
+        self.inner.create_dir_all(dir)
+    }
+
+    fn streams_to_disk(&self) -> bool {
+        self.inner.streams_to_disk()
+    }
+
+    fn begin_output(&self, format: &OutputFormat) {
+        self.inner.begin_output(format);
+    }
+
+    fn check_target(&self, path: &Path) -> Result<(), std::io::Error> {
+        self.check(path)?;
+        self.inner.check_target(path)
+    }
+}
+
+/// Whether `a` and `b` name one existing file, through links and different
+/// spellings of the path.
+pub(super) fn same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (std::fs::metadata(a), std::fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
         }
-        OutputFormat::SeparateBinary => write_separate_binary(hexfile, path)?,
-        OutputFormat::CCode => {
-            return Err(CliError::Other(
-                "C-code output must be handled by caller".into(),
-            ));
+    }
+    #[cfg(not(unix))]
+    {
+        matches!((a.canonicalize(), b.canonicalize()), (Ok(a), Ok(b)) if a == b)
+    }
+}
+
+/// Default /BTBS: output write buffer and big-image block size.
+pub(super) const DEFAULT_BUFFER_KB: u32 = 64;
+
+/// Default cap on the decompressed size of gzip input (/GZMAX).
+pub(super) const DEFAULT_GZIP_LIMIT_MB: u32 = 512;
+
+/// Inflates gzip-compressed files (magic `1F 8B 08`) read through `inner`, so
+/// format detection and the import paths see the decompressed content.
+pub(super) struct GzipProvider<'a, P> {
+    pub(super) inner: &'a P,
+    /// Maximum decompressed size in bytes.
+    pub(super) limit: u64,
+}
+
+impl<P: ReadProvider> ReadProvider for GzipProvider<'_, P> {
+    fn read_bytes(&self, path: &Path) -> Result<Vec<u8>, std::io::Error> {
+        let content = self.inner.read_bytes(path)?;
+        if !content.starts_with(&[0x1F, 0x8B, 0x08]) {
+            return Ok(content);
         }
-        OutputFormat::Porsche => {
-            return Err(CliError::Other(
-                "Porsche output must be handled by caller".into(),
+        let mut out = Vec::new();
+        flate2::read::MultiGzDecoder::new(content.as_slice())
+            .take(self.limit.saturating_add(1))
+            .read_to_end(&mut out)
+            .map_err(|e| std::io::Error::new(e.kind(), format!("{}: gzip: {e}", path.display())))?;
+        if out.len() as u64 > self.limit {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "{}: decompressed size exceeds {} bytes (/GZMAX)",
+                    path.display(),
+                    self.limit
+                ),
             ));
         }
-        _ => {
-            return Err(CliError::Other(format!(
-                "Output format {:?} not yet implemented",
-                format
-            )));
-        }
+        Ok(out)
+    }
+
+    fn mappable_len(&self, path: &Path) -> Option<u64> {
+        let len = self.inner.mappable_len(path)?;
+        let mut magic = [0u8; 3];
+        let read = File::open(path).ok()?.read(&mut magic).ok()?;
+        (magic[..read] != [0x1F, 0x8B, 0x08]).then_some(len)
     }
+}
 
-    Ok(())
+/// Stream output through a buffered file. A failed write removes the
+/// partial file rather than leaving a truncated image behind. Write providers that
+/// do not write to disk get the whole image at once.
+fn stream_to_file<E>(
+    writer: &dyn WriteProvider,
+    path: &Path,
+    buffer_size: usize,
+    write: impl FnOnce(&mut dyn Write) -> Result<(), E>,
+) -> Result<(), CliError>
+where
+    CliError: From<E>,
+{
+    if !writer.streams_to_disk() {
+        let mut buffer = Vec::new();
+        write(&mut buffer)?;
+        return Ok(writer.write_bytes(path, &buffer)?);
+    }
+    writer.check_target(path)?;
+    let mut file = BufWriter::with_capacity(buffer_size, File::create(path)?);
+    let result = write(&mut file)
+        .map_err(CliError::from)
+        .and_then(|()| file.flush().map_err(Into::into));
+    if result.is_err() {
+        drop(file);
+        let _ = std::fs::remove_file(path);
+    }
+    result
 }
 
+/// `gap_fill` fills every gap first, as /FA would; see `write_output`.
+#[allow(clippy::too_many_arguments)]
 pub(super) fn write_output_for_args(
     args: &Args,
+    output: &OutputSpec,
+    hexfile: &HexFile,
+    gap_fill: Option<u8>,
+    provider: &impl ReadProvider,
+    writer: &dyn WriteProvider,
+    diagnostics: &Diagnostics,
+) -> Result<(), CliError> {
+    let path = resolve_output_path(args, output)?;
+    let option = output.format.as_ref().map_or("", OutputFormat::option);
+    writer.begin_output(
+        output
+            .format
+            .as_ref()
+            .unwrap_or(&OutputFormat::IntelHex { record_type: None }),
+    );
+    create_parent_dirs(&path, args.create_output_dirs, writer)
+        .map_err(|e| CliError::from(e).in_file(option, &path))?;
+    write_output_to(
+        args,
+        output,
+        &path,
+        hexfile,
+        gap_fill,
+        provider,
+        writer,
+        diagnostics,
+    )
+    .map_err(|e| match e {
+        CliError::Io {
+            option: None,
+            path: None,
+            ..
+        } => e.in_file(option, &path),
+        other => other,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_output_to(
+    args: &Args,
+    output: &OutputSpec,
+    path: &Path,
     hexfile: &HexFile,
+    gap_fill: Option<u8>,
     provider: &impl ReadProvider,
+    writer: &dyn WriteProvider,
+    diagnostics: &Diagnostics,
 ) -> Result<(), CliError> {
-    match args.output_format {
+    match output.format {
         Some(OutputFormat::CCode) => {
-            let path = resolve_c_code_output_path(args)?;
-            write_c_code_output(args, hexfile, &path, provider)?;
+            write_c_code_output(args, hexfile, path, provider, writer)?;
             Ok(())
         }
-        Some(OutputFormat::FordIntelHex) => {
-            let path = resolve_ford_output_path(args)?;
-            write_ford_ihex_output(args, hexfile, &path, provider)?;
+        Some(OutputFormat::FordIntelHex { .. }) => {
+            write_ford_ihex_output(args, output, hexfile, path, provider, writer, diagnostics)?;
             Ok(())
         }
         Some(OutputFormat::Porsche) => {
-            let path = resolve_porsche_output_path(args)?;
-            write_porsche_output(args, hexfile, &path)?;
+            write_porsche_output(args, hexfile, path, writer)?;
+            Ok(())
+        }
+        Some(OutputFormat::Vbf) => {
+            write_vbf_output(args, hexfile, path, provider, writer)?;
+            Ok(())
+        }
+        Some(
+            OutputFormat::GmHeader { .. }
+            | OutputFormat::GmHeaderOs { .. }
+            | OutputFormat::GmHeaderCal { .. },
+        ) => {
+            write_gm_output(args, output, hexfile, path, provider, writer)?;
+            Ok(())
+        }
+        Some(OutputFormat::RustCode) => {
+            write_rust_output(args, hexfile, path, provider, writer)?;
+            Ok(())
+        }
+        Some(OutputFormat::FiatBin) => {
+            write_fiat_output(args, hexfile, path, provider, writer)?;
+            Ok(())
+        }
+        Some(OutputFormat::FlashKernel) => {
+            write_flash_kernel_output(args, hexfile, path, provider, writer)?;
+            Ok(())
+        }
+        Some(OutputFormat::Vag) => {
+            write_vag_output(args, hexfile, path, provider, writer)?;
+            Ok(())
+        }
+        Some(OutputFormat::SRecord {
+            record_type,
+            header: None,
+        }) if args.ini_file.is_some() => {
+            let ini = load_optional_ini(args, provider)?;
+            let format = OutputFormat::SRecord {
+                record_type,
+                header: ini.get("header").cloned(),
+            };
+            write_output(
+                hexfile,
+                path,
+                &Some(format),
+                output.bytes_per_line,
+                TextStyle::from_args(args, gap_fill),
+                writer,
+            )?;
+            Ok(())
+        }
+        Some(OutputFormat::Gac | OutputFormat::GacSwil) => {
+            write_gac_output(args, output, hexfile, path, provider, writer)?;
             Ok(())
         }
         _ => {
-            if let Some(ref path) = args.output_file {
-                write_output(hexfile, path, &args.output_format, args.bytes_per_line)?;
-            }
+            write_output(
+                hexfile,
+                path,
+                &output.format,
+                output.bytes_per_line,
+                TextStyle::from_args(args, gap_fill),
+                writer,
+            )?;
             Ok(())
         }
     }
 }
 
-pub(super) fn write_c_code_output(
+/// /XB, /XK, /XR and /XS only read the INI when `/P` is given.
+fn load_optional_ini(
     args: &Args,
-    hexfile: &HexFile,
-    output_path: &Path,
     provider: &impl ReadProvider,
-) -> Result<(), CliError> {
-    let ini_path = resolve_ini_path(args)?;
-    let ini = load_ini(&ini_path, provider)?;
-
-    let prefix = ini
-        .get("prefix")
-        .cloned()
-        .unwrap_or_else(|| "flashDrv".to_string());
-    let word_size = ini
-        .get("wordsize")
-        .map(|v| parse_number(v))
-        .transpose()?
-        .unwrap_or(0);
-    let word_type = ini
-        .get("wordtype")
-        .map(|v| parse_number(v))
-        .transpose()?
-        .unwrap_or(0);
-    let decrypt = ini
-        .get("decryption")
-        .map(|v| parse_number(v).map(|n| n != 0))
-        .transpose()?
-        .unwrap_or(false);
-    let decrypt_value = ini
-        .get("decryptvalue")
-        .map(|v| parse_number(v))
-        .transpose()?
-        .unwrap_or(0);
-
-    let word_type = match word_type {
-        0 => crate::CCodeWordType::Intel,
-        1 => crate::CCodeWordType::Motorola,
-        other => {
-            return Err(CliError::Other(format!("unsupported WordType {other}")));
-        }
-    };
-
-    let header_name = output_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or(&prefix)
-        .to_string();
-
-    let options = crate::CCodeWriteOptions {
-        prefix: prefix.clone(),
-        header_name,
-        word_size: word_size as u8,
-        word_type,
-        decrypt,
-        decrypt_value,
-    };
-    let output = crate::write_c_code(hexfile, &options)?;
-
-    let (c_path, h_path) = derive_c_code_paths(output_path, &prefix);
-    std::fs::write(c_path, output.c)?;
-    std::fs::write(h_path, output.h)?;
-    Ok(())
-}
-
-pub(super) fn resolve_c_code_output_path(args: &Args) -> Result<PathBuf, CliError> {
-    if let Some(path) = args.output_file.clone() {
-        return Ok(path);
-    }
-
-    if let Some(ref input) = args.input_file {
-        return Ok(input.with_extension("c"));
-    }
-
-    if let Some(ref import) = args.import_binary {
-        return Ok(import.file.with_extension("c"));
+) -> Result<std::collections::HashMap<String, String>, CliError> {
+    match args.ini_file {
+        Some(ref path) => Ok(load_ini(path, provider, &args.defines)?.into_flat()),
+        None => Ok(std::collections::HashMap::new()),
     }
+}
 
-    if let Some(ref import) = args.import_hex_ascii {
-        return Ok(import.file.with_extension("c"));
+/// With /MKDIR, create the missing parent directories of `path`.
+fn create_parent_dirs(
+    path: &Path,
+    enabled: bool,
+    writer: &dyn WriteProvider,
+) -> std::io::Result<()> {
+    match path.parent() {
+        Some(dir) if enabled && !dir.as_os_str().is_empty() => writer.create_dir_all(dir),
+        _ => Ok(()),
     }
-
-    Err(CliError::Other(
-        "output file required for /XC (use -o <file>)".into(),
-    ))
 }
 
-pub(super) fn write_ford_ihex_output(
-    args: &Args,
-    hexfile: &HexFile,
-    output_path: &Path,
-    provider: &impl ReadProvider,
+/// Write an auxiliary output file (checksum, signature, log) named by
+/// `option`, creating its directory first with /MKDIR.
+pub(super) fn write_file(
+    option: &str,
+    path: &Path,
+    contents: impl AsRef<[u8]>,
+    create_dirs: bool,
+    writer: &dyn WriteProvider,
 ) -> Result<(), CliError> {
-    let ini_path = resolve_ini_path(args)?;
-    let ini = load_ini(&ini_path, provider)?;
-
-    let header = build_ford_header(args, hexfile, output_path, &ini)?;
-    let options = crate::IntelHexWriteOptions {
-        bytes_per_line: args.bytes_per_line.unwrap_or(32),
-        mode: crate::IntelHexMode::Auto,
-    };
-    let data = crate::write_intel_hex(hexfile, &options);
-
-    let mut output = Vec::new();
-    output.extend_from_slice(header.as_bytes());
-    output.extend_from_slice(data.as_slice());
-    std::fs::write(output_path, output)?;
-    Ok(())
+    create_parent_dirs(path, create_dirs, writer)
+        .and_then(|()| writer.write_bytes(path, contents.as_ref()))
+        .map_err(|e| CliError::from(e).in_file(option, path))
 }
 
-pub(super) fn resolve_ford_output_path(args: &Args) -> Result<PathBuf, CliError> {
-    if let Some(path) = args.output_file.clone() {
+/// Use `-o` if given. With /INPLACE, fall back to the input (or import) path
+/// with the format's extension; otherwise a missing `-o` is an error.
+pub(super) fn resolve_output_path(args: &Args, output: &OutputSpec) -> Result<PathBuf, CliError> {
+    if let Some(path) = output.file.clone() {
         return Ok(path);
     }
+    let format = output
+        .format
+        .as_ref()
+        .expect("an output without -o has a format");
+    if !args.derive_output_path {
+        return Err(missing_output_file(format));
+    }
 
+    let extension = format.extension();
     if let Some(ref input) = args.input_file {
-        return Ok(input.with_extension("hex"));
+        return Ok(input.with_extension(extension));
     }
 
     if let Some(ref import) = args.import_binary {
-        return Ok(import.file.with_extension("hex"));
+        return Ok(import.file.with_extension(extension));
     }
 
     if let Some(ref import) = args.import_hex_ascii {
-        return Ok(import.file.with_extension("hex"));
+        return Ok(import.file.with_extension(extension));
     }
 
-    Err(CliError::Other(
-        "output file required for /XF (use -o <file>)".into(),
+    Err(ParseArgError::InvalidOption(format!(
+        "/INPLACE: no input file to derive the {} output from (use -o <file>)",
+        format.option()
     ))
+    .into())
 }
 
-pub(super) fn write_porsche_output(
-    args: &Args,
-    hexfile: &HexFile,
-    output_path: &Path,
-) -> Result<(), CliError> {
-    let mut normalized = hexfile.normalized_lossy();
-    if normalized.segments().is_empty() {
-        std::fs::write(output_path, [])?;
-        return Ok(());
-    }
-
-    let fill = args.align_fill;
-    normalized.fill_gaps(fill);
-    let data = normalized.segments()[0].data.clone();
-    let checksum = byte_sum_u16(&data);
-    let mut output = data;
-    output.extend_from_slice(&checksum.to_be_bytes());
-    std::fs::write(output_path, output)?;
-    Ok(())
-}
-
-pub(super) fn resolve_porsche_output_path(args: &Args) -> Result<PathBuf, CliError> {
-    if let Some(path) = args.output_file.clone() {
-        return Ok(path);
-    }
-
-    if let Some(ref input) = args.input_file {
-        return Ok(input.with_extension("bin"));
-    }
-
-    if let Some(ref import) = args.import_binary {
-        return Ok(import.file.with_extension("bin"));
-    }
-
-    if let Some(ref import) = args.import_hex_ascii {
-        return Ok(import.file.with_extension("bin"));
-    }
-
-    Err(CliError::Other(
-        "output file required for /XP (use -o <file>)".into(),
-    ))
+pub(super) fn missing_output_file(format: &OutputFormat) -> CliError {
+    ParseArgError::MissingOutputFile(format.option()).into()
 }
 
 fn resolve_ini_path(args: &Args) -> Result<PathBuf, CliError> {
@@ -435,177 +554,19 @@ fn resolve_ini_path(args: &Args) -> Result<PathBuf, CliError> {
         return Ok(import.file.with_extension("ini"));
     }
 
-    Err(CliError::Other(
+    Err(CliError::Config(
         "INI file required for /XC (use /P:<file>)".into(),
     ))
 }
 
-fn derive_c_code_paths(output_path: &Path, prefix: &str) -> (PathBuf, PathBuf) {
-    let dir = output_path.parent().unwrap_or_else(|| Path::new("."));
-    let stem = output_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or(prefix);
-    let c_path = dir.join(format!("{stem}.c"));
-    let h_path = dir.join(format!("{stem}.h"));
-    (c_path, h_path)
-}
-
-fn build_ford_header(
-    args: &Args,
-    hexfile: &HexFile,
-    output_path: &Path,
-    ini: &std::collections::HashMap<String, String>,
-) -> Result<String, CliError> {
-    let mut lines = Vec::new();
-
-    let required = [
-        "application",
-        "mask number",
-        "module type",
-        "production module part number",
-        "wers notice",
-        "comments",
-        "released by",
-        "module name",
-        "module id",
-    ];
-    for key in required {
-        let value = ini
-            .get(key)
-            .ok_or_else(|| CliError::Other(format!("missing [FORDHEADER] {key}")))?;
-        lines.push(format!("{}>{}", key.to_ascii_uppercase(), value));
-    }
-
-    let file_name = ini.get("file name").cloned().unwrap_or_else(|| {
-        output_path
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or("output.hex")
-            .to_string()
-    });
-    lines.insert(2, format!("FILE NAME>{file_name}"));
-
-    let release_date = ini
-        .get("release date")
-        .cloned()
-        .unwrap_or_else(|| current_date_mmddyyyy().unwrap_or_else(|| "01/01/1970".to_string()));
-    lines.insert(3, format!("RELEASE DATE>{release_date}"));
-
-    let download_format = ini
-        .get("download format")
-        .cloned()
-        .unwrap_or_else(|| "0x00".to_string());
-    lines.push(format!("DOWNLOAD FORMAT>{download_format}"));
-
-    let checksum = compute_ford_checksum(hexfile);
-    lines.push(format!("FILE CHECKSUM>0x{checksum:04X}"));
-
-    let flash_indicator = ini
-        .get("flash indicator")
-        .cloned()
-        .unwrap_or_else(|| "0".to_string());
-    lines.push(format!("FLASH INDICATOR>{flash_indicator}"));
-
-    lines.push("FLASH ERASE".to_string());
-    let erase = ini
-        .get("flash erase sectors")
-        .cloned()
-        .unwrap_or_else(|| format_erase_sectors(hexfile, args.align_erase));
-    lines.push(format!("SECTORS>{erase}"));
-
-    lines.push("$".to_string());
-    Ok(lines.join("\n") + "\n")
-}
-
-fn compute_ford_checksum(hexfile: &HexFile) -> u16 {
-    let mut sum: u16 = 0;
-    let mut segments = hexfile.normalized_lossy().into_segments();
-    segments.sort_by_key(|s| s.start_address);
-    for segment in segments {
-        for byte in segment.data {
-            sum = sum.wrapping_add(byte as u16);
-        }
-    }
-    sum
-}
-
-fn format_erase_sectors(hexfile: &HexFile, alignment: Option<u32>) -> String {
-    let mut segments = hexfile.normalized_lossy().into_segments();
-    segments.sort_by_key(|s| s.start_address);
-    let mut parts = Vec::new();
-
-    for segment in segments {
-        let start = segment.start_address;
-        let len = segment.len() as u32;
-        let (aligned_start, aligned_len) = if let Some(align) = alignment.filter(|a| *a > 0) {
-            let start64 = start as u64;
-            let len64 = len as u64;
-            let align64 = align as u64;
-            let aligned_start = (start64 / align64) * align64;
-            let end = start64 + len64 - 1;
-            let aligned_end = (end + 1).div_ceil(align64) * align64 - 1;
-            let aligned_len = aligned_end - aligned_start + 1;
-            (aligned_start as u32, aligned_len as u32)
-        } else {
-            (start, len)
-        };
-        parts.push(format!("0x{aligned_start:X},0x{aligned_len:X}"));
-    }
-
-    parts
-        .into_iter()
-        .map(|p| format!(":{p}"))
-        .collect::<String>()
-}
-
-fn byte_sum_u16(data: &[u8]) -> u16 {
-    data.iter().fold(0u16, |acc, &b| acc.wrapping_add(b as u16))
-}
-
-fn current_date_mmddyyyy() -> Option<String> {
-    let output = std::process::Command::new("date")
-        .arg("+%m/%d/%Y")
-        .output()
-        .ok()?;
-    let date = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    if date.is_empty() { None } else { Some(date) }
-}
-
-fn write_separate_binary(hexfile: &HexFile, path: &Path) -> Result<(), CliError> {
-    let normalized = hexfile.normalized_lossy();
-    let mut segments = normalized.into_segments();
-    segments.sort_by_key(|s| s.start_address);
-
-    if segments.is_empty() {
-        return Ok(());
-    }
-
-    let dir = path.parent().unwrap_or_else(|| Path::new("."));
-    let stem = path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("output");
-    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("bin");
-
-    for segment in segments {
-        let filename = format!("{stem}_{:x}.{ext}", segment.start_address);
-        let out_path = dir.join(filename);
-        std::fs::write(out_path, segment.data)?;
-    }
-
-    Ok(())
-}
-
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use crate::Segment;
     use std::fs;
+    use std::path::PathBuf;
     use std::sync::atomic::{AtomicUsize, Ordering};
     use std::time::{SystemTime, UNIX_EPOCH};
 
-    fn unique_temp_dir() -> PathBuf {
+    pub(super) fn unique_temp_dir() -> PathBuf {
         static COUNTER: AtomicUsize = AtomicUsize::new(0);
         let stamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -620,91 +581,4 @@ mod tests {
         let _ = fs::create_dir_all(&dir);
         dir
     }
-
-    #[test]
-    fn test_write_separate_binary_outputs_segments() {
-        let dir = unique_temp_dir();
-        let output = dir.join("out.bin");
-        let hexfile = HexFile::with_segments(vec![
-            Segment::new(0x1000, vec![0xAA, 0xBB]),
-            Segment::new(0x2000, vec![0xCC]),
-        ]);
-
-        write_output(&hexfile, &output, &Some(OutputFormat::SeparateBinary), None).unwrap();
-
-        let file1 = dir.join("out_1000.bin");
-        let file2 = dir.join("out_2000.bin");
-        assert_eq!(fs::read(file1).unwrap(), vec![0xAA, 0xBB]);
-        assert_eq!(fs::read(file2).unwrap(), vec![0xCC]);
-
-        let _ = fs::remove_dir_all(dir);
-    }
-
-    #[test]
-    fn test_write_ford_ihex_happy_path() {
-        let dir = unique_temp_dir();
-        let ini_path = dir.join("ford.ini");
-        let output = dir.join("ford.hex");
-        let ini = "[FORDHEADER]\nAPPLICATION=APP\nMASK NUMBER=7\nMODULE TYPE=TYPE\nPRODUCTION MODULE PART NUMBER=PN\nWERS NOTICE=WERS\nCOMMENTS=Note\nRELEASED BY=Dev\nMODULE NAME=MOD\nMODULE ID=0x1234\nRELEASE DATE=01/02/2003\nDOWNLOAD FORMAT=0x01\nFLASH INDICATOR=1\n";
-        fs::write(&ini_path, ini).unwrap();
-
-        let args = Args {
-            ini_file: Some(ini_path),
-            bytes_per_line: Some(16),
-            ..Args::default()
-        };
-        let hexfile = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01, 0x02])]);
-        let provider = FsProvider;
-
-        write_ford_ihex_output(&args, &hexfile, &output, &provider).unwrap();
-        let content = fs::read_to_string(&output).unwrap();
-        assert!(content.contains("APPLICATION>APP"));
-        assert!(content.contains("FILE CHECKSUM>"));
-        assert!(content.contains("$"));
-        assert!(content.contains(":"));
-
-        let _ = fs::remove_dir_all(dir);
-    }
-
-    #[test]
-    fn test_write_ford_ihex_missing_required() {
-        let dir = unique_temp_dir();
-        let ini_path = dir.join("ford.ini");
-        let output = dir.join("ford.hex");
-        fs::write(&ini_path, "[FORDHEADER]\nAPPLICATION=APP\n").unwrap();
-
-        let args = Args {
-            ini_file: Some(ini_path),
-            ..Args::default()
-        };
-        let hexfile = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01])]);
-        let provider = FsProvider;
-        let result = write_ford_ihex_output(&args, &hexfile, &output, &provider);
-        assert!(result.is_err());
-
-        let _ = fs::remove_dir_all(dir);
-    }
-
-    #[test]
-    fn test_write_porsche_output_appends_checksum() {
-        let dir = unique_temp_dir();
-        let output = dir.join("porsche.bin");
-        let args = Args {
-            align_fill: 0xFF,
-            ..Args::default()
-        };
-        let hexfile = HexFile::with_segments(vec![
-            Segment::new(0x1000, vec![0x01, 0x02]),
-            Segment::new(0x1004, vec![0x03]),
-        ]);
-
-        write_porsche_output(&args, &hexfile, &output).unwrap();
-        let data = fs::read(&output).unwrap();
-        // data: 0x01,0x02,0xFF,0xFF,0x03 then checksum
-        assert_eq!(&data[..5], &[0x01, 0x02, 0xFF, 0xFF, 0x03]);
-        let checksum = u16::from_be_bytes([data[5], data[6]]);
-        assert_eq!(checksum, 0x01 + 0x02 + 0xFF + 0xFF + 0x03);
-
-        let _ = fs::remove_dir_all(dir);
-    }
 }
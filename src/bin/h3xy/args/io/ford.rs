@@ -0,0 +1,376 @@
+use std::path::Path;
+
+use crate::HexFile;
+
+use super::super::diagnostics::Diagnostics;
+use super::super::error::CliError;
+use super::super::ini::{load_ini, load_ini_if_exists};
+use super::super::types::{Args, OutputFormat, OutputSpec};
+use super::oem::erase_ranges;
+use super::{ReadProvider, WriteProvider, resolve_ini_path};
+
+pub(super) fn write_ford_ihex_output(
+    args: &Args,
+    output: &OutputSpec,
+    hexfile: &HexFile,
+    output_path: &Path,
+    provider: &impl ReadProvider,
+    writer: &dyn WriteProvider,
+    diagnostics: &Diagnostics,
+) -> Result<(), CliError> {
+    let relaxed = matches!(
+        output.format,
+        Some(OutputFormat::FordIntelHex { relaxed: true })
+    );
+    // Relaxed mode only needs an INI that /P names explicitly.
+    let ini = match resolve_ini_path(args) {
+        Ok(path) if relaxed && args.ini_file.is_none() => {
+            load_ini_if_exists(&path, provider, &args.defines)?
+        }
+        Ok(path) => load_ini(&path, provider, &args.defines)?,
+        Err(_) if relaxed => Default::default(),
+        Err(e) => return Err(e),
+    }
+    .section("FORDHEADER");
+
+    let (header, placeholders) =
+        build_ford_header(args, hexfile, output_path, &ini, relaxed, provider)?;
+    if !placeholders.is_empty() {
+        diagnostics.warn(format!(
+            "/XF:RELAXED: placeholder [FORDHEADER] values for {}",
+            placeholders.join(", ")
+        ));
+    }
+    let options = crate::IntelHexWriteOptions {
+        bytes_per_line: output.bytes_per_line.unwrap_or(32),
+        mode: crate::IntelHexMode::Auto,
+        ..Default::default()
+    };
+    let data = crate::write_intel_hex(hexfile, &options)?;
+
+    let mut output = Vec::new();
+    output.extend_from_slice(header.as_bytes());
+    output.extend_from_slice(data.as_slice());
+    writer.write_bytes(output_path, &output)?;
+    Ok(())
+}
+
+/// `[FORDHEADER]` keys /XF requires, each with the placeholder /XF:RELAXED
+/// uses when neither the INI nor /FORD sets it.
+pub(crate) const FORD_REQUIRED_KEYS: [(&str, &str); 9] = [
+    ("application", "TEST"),
+    ("mask number", "0"),
+    ("module type", "UNKNOWN"),
+    ("production module part number", "UNKNOWN"),
+    ("wers notice", "NONE"),
+    ("comments", "TEST BUILD"),
+    ("released by", "h3xy"),
+    ("module name", "UNKNOWN"),
+    ("module id", "0x0000"),
+];
+
+/// `[FORDHEADER]` keys that are derived when absent.
+pub(crate) const FORD_OPTIONAL_KEYS: [&str; 5] = [
+    "file name",
+    "release date",
+    "download format",
+    "flash indicator",
+    "flash erase sectors",
+];
+
+fn build_ford_header(
+    args: &Args,
+    hexfile: &HexFile,
+    output_path: &Path,
+    ini: &std::collections::HashMap<String, String>,
+    relaxed: bool,
+    provider: &impl ReadProvider,
+) -> Result<(String, Vec<&'static str>), CliError> {
+    let mut ini = ini.clone();
+    ini.extend(args.ford_header.iter().cloned());
+    let mut lines = Vec::new();
+    let mut placeholders = Vec::new();
+
+    for (key, placeholder) in FORD_REQUIRED_KEYS {
+        let value = match ini.get(key) {
+            Some(value) => value.as_str(),
+            None if relaxed => {
+                placeholders.push(key);
+                placeholder
+            }
+            None => {
+                return Err(CliError::Config(format!(
+                    "missing [FORDHEADER] {key} (set it with /FORD:{key}=..., or use /XF:RELAXED)"
+                )));
+            }
+        };
+        lines.push(format!("{}>{}", key.to_ascii_uppercase(), value));
+    }
+
+    let file_name = ini.get("file name").cloned().unwrap_or_else(|| {
+        output_path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("output.hex")
+            .to_string()
+    });
+    lines.insert(2, format!("FILE NAME>{file_name}"));
+
+    let release_date = ini.get("release date").cloned().unwrap_or_else(|| {
+        let date = args.date.unwrap_or_else(crate::DateStamp::now);
+        format!("{:02}/{:02}/{:04}", date.month, date.day, date.year)
+    });
+    lines.insert(3, format!("RELEASE DATE>{release_date}"));
+
+    let download_format = ini
+        .get("download format")
+        .cloned()
+        .unwrap_or_else(|| "0x00".to_string());
+    lines.push(format!("DOWNLOAD FORMAT>{download_format}"));
+
+    let checksum = compute_ford_checksum(hexfile);
+    lines.push(format!("FILE CHECKSUM>0x{checksum:04X}"));
+
+    let flash_indicator = ini
+        .get("flash indicator")
+        .cloned()
+        .unwrap_or_else(|| "0".to_string());
+    lines.push(format!("FLASH INDICATOR>{flash_indicator}"));
+
+    lines.push("FLASH ERASE".to_string());
+    let erase = match ini.get("flash erase sectors") {
+        Some(sectors) => sectors.clone(),
+        None => format_erase_sectors(&erase_ranges(args, hexfile, provider)?),
+    };
+    lines.push(format!("SECTORS>{erase}"));
+
+    lines.push("$".to_string());
+    Ok((lines.join("\n") + "\n", placeholders))
+}
+
+fn compute_ford_checksum(hexfile: &HexFile) -> u16 {
+    let mut sum: u16 = 0;
+    for segment in hexfile.normalized_segments().iter() {
+        for &byte in &segment.data {
+            sum = sum.wrapping_add(byte as u16);
+        }
+    }
+    sum
+}
+
+fn format_erase_sectors(blocks: &[(u32, u32)]) -> String {
+    blocks
+        .iter()
+        .map(|(start, len)| format!(":0x{start:X},0x{len:X}"))
+        .collect::<String>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::FsProvider;
+    use super::super::tests::unique_temp_dir;
+    use super::*;
+    use crate::Segment;
+    use std::fs;
+
+    #[test]
+    fn test_write_ford_ihex_happy_path() {
+        let dir = unique_temp_dir();
+        let ini_path = dir.join("ford.ini");
+        let output = dir.join("ford.hex");
+        let ini = "[FORDHEADER]\nAPPLICATION=APP\nMASK NUMBER=7\nMODULE TYPE=TYPE\nPRODUCTION MODULE PART NUMBER=PN\nWERS NOTICE=WERS\nCOMMENTS=Note\nRELEASED BY=Dev\nMODULE NAME=MOD\nMODULE ID=0x1234\nRELEASE DATE=01/02/2003\nDOWNLOAD FORMAT=0x01\nFLASH INDICATOR=1\n";
+        fs::write(&ini_path, ini).unwrap();
+
+        let args = Args {
+            ini_file: Some(ini_path),
+            ..Args::default()
+        };
+        let spec = OutputSpec {
+            format: Some(OutputFormat::FordIntelHex { relaxed: false }),
+            bytes_per_line: Some(16),
+            ..OutputSpec::default()
+        };
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01, 0x02])]);
+        let provider = FsProvider;
+
+        write_ford_ihex_output(
+            &args,
+            &spec,
+            &hexfile,
+            &output,
+            &provider,
+            &FsProvider,
+            &Diagnostics::new(true),
+        )
+        .unwrap();
+        let content = fs::read_to_string(&output).unwrap();
+        assert!(content.contains("APPLICATION>APP"));
+        assert!(content.contains("FILE CHECKSUM>"));
+        assert!(content.contains("$"));
+        assert!(content.contains(":"));
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_write_ford_ihex_missing_required() {
+        let dir = unique_temp_dir();
+        let ini_path = dir.join("ford.ini");
+        let output = dir.join("ford.hex");
+        fs::write(&ini_path, "[FORDHEADER]\nAPPLICATION=APP\n").unwrap();
+
+        let args = Args {
+            ini_file: Some(ini_path),
+            ..Args::default()
+        };
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01])]);
+        let provider = FsProvider;
+        let spec = OutputSpec::default();
+        let result = write_ford_ihex_output(
+            &args,
+            &spec,
+            &hexfile,
+            &output,
+            &provider,
+            &FsProvider,
+            &Diagnostics::new(true),
+        );
+        assert!(result.is_err());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_write_ford_ihex_relaxed_uses_defaults_and_overrides() {
+        let dir = unique_temp_dir();
+        let output = dir.join("ford.hex");
+        let args = Args {
+            input_file: Some(dir.join("app.hex")),
+            ford_header: vec![
+                ("module id".to_string(), "0x7E0".to_string()),
+                ("flash indicator".to_string(), "1".to_string()),
+            ],
+            date: Some("2026-03-07".parse().unwrap()),
+            ..Args::default()
+        };
+        let spec = OutputSpec {
+            format: Some(OutputFormat::FordIntelHex { relaxed: true }),
+            ..OutputSpec::default()
+        };
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01, 0x02])]);
+        let diagnostics = Diagnostics::new(true);
+
+        write_ford_ihex_output(
+            &args,
+            &spec,
+            &hexfile,
+            &output,
+            &FsProvider,
+            &FsProvider,
+            &diagnostics,
+        )
+        .unwrap();
+        let warnings: Vec<_> = diagnostics
+            .entries()
+            .into_iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                "WARNING: /XF:RELAXED: placeholder [FORDHEADER] values for application, \
+              mask number, module type, production module part number, wers notice, \
+              comments, released by, module name"
+            ]
+        );
+        let content = fs::read_to_string(&output).unwrap();
+        let header: Vec<_> = content.lines().take_while(|line| *line != "$").collect();
+        assert_eq!(
+            header,
+            [
+                "APPLICATION>TEST",
+                "MASK NUMBER>0",
+                "FILE NAME>ford.hex",
+                "RELEASE DATE>03/07/2026",
+                "MODULE TYPE>UNKNOWN",
+                "PRODUCTION MODULE PART NUMBER>UNKNOWN",
+                "WERS NOTICE>NONE",
+                "COMMENTS>TEST BUILD",
+                "RELEASED BY>h3xy",
+                "MODULE NAME>UNKNOWN",
+                "MODULE ID>0x7E0",
+                "DOWNLOAD FORMAT>0x00",
+                "FILE CHECKSUM>0x0003",
+                "FLASH INDICATOR>1",
+                "FLASH ERASE",
+                "SECTORS>:0x1000,0x2",
+            ]
+        );
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_write_ford_ihex_overrides_win_over_ini_in_strict_mode() {
+        let dir = unique_temp_dir();
+        let ini_path = dir.join("ford.ini");
+        let output = dir.join("ford.hex");
+        fs::write(&ini_path, "[FORDHEADER]\nAPPLICATION=APP\nCOMMENTS=ini\n").unwrap();
+
+        let overrides = [
+            ("comments", "cli"),
+            ("mask number", "7"),
+            ("module type", "T"),
+            ("production module part number", "PN"),
+            ("wers notice", "W"),
+            ("released by", "Dev"),
+            ("module name", "M"),
+        ];
+        let mut args = Args {
+            ini_file: Some(ini_path),
+            ford_header: overrides
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..Args::default()
+        };
+        let spec = OutputSpec {
+            format: Some(OutputFormat::FordIntelHex { relaxed: false }),
+            ..OutputSpec::default()
+        };
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01])]);
+
+        let err = write_ford_ihex_output(
+            &args,
+            &spec,
+            &hexfile,
+            &output,
+            &FsProvider,
+            &FsProvider,
+            &Diagnostics::new(true),
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("missing [FORDHEADER] module id"),
+            "{err}"
+        );
+
+        args.ford_header
+            .push(("module id".to_string(), "1".to_string()));
+        write_ford_ihex_output(
+            &args,
+            &spec,
+            &hexfile,
+            &output,
+            &FsProvider,
+            &FsProvider,
+            &Diagnostics::new(true),
+        )
+        .unwrap();
+        let content = fs::read_to_string(&output).unwrap();
+        assert!(content.contains("APPLICATION>APP\n"), "{content}");
+        assert!(content.contains("COMMENTS>cli\n"), "{content}");
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,292 @@
+use std::path::{Path, PathBuf};
+
+use crate::HexFile;
+
+use super::super::error::CliError;
+use super::super::types::{InputFormat, ParseArgError};
+use super::ReadProvider;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct InputOptions {
+    pub(crate) elf: crate::ElfParseOptions,
+    /// Intel HEX and S-Record options (/LENIENT).
+    pub(crate) text: crate::ParseOptions,
+    /// Skip auto-detection and use this parser (/IT).
+    pub(crate) format: Option<InputFormat>,
+}
+
+/// Expand a `*`, `?` or `[...]` file pattern into the matching files, sorted.
+/// Paths without wildcards, or that name an existing file, are returned as-is.
+pub(crate) fn expand_input_pattern(path: &Path) -> Result<Vec<PathBuf>, CliError> {
+    let text = path.to_string_lossy();
+    if !text.contains(['*', '?', '[']) || path.exists() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let entries = glob::glob(&text).map_err(|e| {
+        ParseArgError::InvalidOption(format!("invalid file pattern {text}: {}", e.msg))
+    })?;
+    let mut paths = Vec::new();
+    for entry in entries {
+        paths.push(entry.map_err(|e| CliError::from(std::io::Error::from(e)))?);
+    }
+    if paths.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("no files match {text}"),
+        )
+        .into());
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Load an auto-detected input file, returning any lenient-parse warnings.
+pub(crate) fn load_input_with_options(
+    provider: &impl ReadProvider,
+    path: &Path,
+    options: &InputOptions,
+) -> Result<(HexFile, Vec<crate::ParseWarning>), CliError> {
+    let content = provider.read_bytes(path)?;
+
+    if let Some(format) = options.format {
+        return parse_forced(&content, format, options).map_err(|source| CliError::ForcedInput {
+            format: format.name(),
+            source,
+        });
+    }
+
+    match detect_format(&content, options.text.lenient) {
+        DetectedFormat::Elf => Ok((
+            crate::parse_elf_with_options(&content, &options.elf)?,
+            Vec::new(),
+        )),
+        DetectedFormat::Vbf => Ok((crate::parse_vbf(&content)?, Vec::new())),
+        #[cfg(feature = "serde")]
+        DetectedFormat::Json => Ok((crate::parse_json(&content)?, Vec::new())),
+        DetectedFormat::IntelHex => Ok(crate::parse_intel_hex_with(&content, &options.text)?),
+        DetectedFormat::SRecord => Ok(crate::parse_srec_with(&content, &options.text)?),
+        DetectedFormat::Binary => Ok((crate::parse_binary(&content, 0)?, Vec::new())),
+    }
+}
+
+/// What auto-detection takes an input file to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DetectedFormat {
+    Elf,
+    Vbf,
+    #[cfg(feature = "serde")]
+    Json,
+    IntelHex,
+    SRecord,
+    Binary,
+}
+
+impl DetectedFormat {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Elf => "ELF",
+            Self::Vbf => "VBF",
+            #[cfg(feature = "serde")]
+            Self::Json => "JSON",
+            Self::IntelHex => "Intel HEX",
+            Self::SRecord => "S-Record",
+            Self::Binary => "binary",
+        }
+    }
+}
+
+/// Name of the format `path` is read as, for /INFO: the /IT format, or what
+/// auto-detection picks.
+pub(crate) fn input_format_name(
+    provider: &impl ReadProvider,
+    path: &Path,
+    options: &InputOptions,
+) -> Result<String, CliError> {
+    if let Some(format) = options.format {
+        return Ok(format!("{} (/IT)", format.description()));
+    }
+    let content = provider.read_bytes(path)?;
+    Ok(detect_format(&content, options.text.lenient)
+        .name()
+        .to_string())
+}
+
+fn detect_format(content: &[u8], lenient: bool) -> DetectedFormat {
+    if content.starts_with(b"\x7fELF") {
+        return DetectedFormat::Elf;
+    }
+
+    if looks_like_vbf(content) {
+        return DetectedFormat::Vbf;
+    }
+
+    #[cfg(feature = "serde")]
+    if content.trim_ascii_start().starts_with(b"{") {
+        return DetectedFormat::Json;
+    }
+
+    let mut ascii_only = true;
+    let mut first_nonempty_line: Option<Vec<u8>> = None;
+    let mut saw_intel_record = false;
+    let mut ascii_lines_checked = 0usize;
+    let mut current_line: Vec<u8> = Vec::new();
+
+    for &b in content {
+        if b == b'\n' || b == b'\r' {
+            if !current_line.is_empty() {
+                if ascii_lines_checked < 25 {
+                    if !current_line.is_ascii() {
+                        ascii_only = false;
+                    }
+                    saw_intel_record |= current_line.first() == Some(&b':');
+                    ascii_lines_checked += 1;
+                }
+                if first_nonempty_line.is_none() {
+                    first_nonempty_line = Some(current_line.clone());
+                }
+                if ascii_lines_checked >= 25 {
+                    break;
+                }
+            }
+            current_line.clear();
+            continue;
+        }
+        current_line.push(b);
+    }
+    if !current_line.is_empty() && first_nonempty_line.is_none() {
+        first_nonempty_line = Some(current_line.clone());
+    }
+
+    if ascii_lines_checked == 0 && !content.is_empty() {
+        ascii_only = content.is_ascii();
+    }
+
+    if !ascii_only {
+        return DetectedFormat::Binary;
+    }
+
+    // Lenient mode skips leading junk, so any record line marks Intel-HEX.
+    let first_line = first_nonempty_line.unwrap_or_default();
+    if first_line.first() == Some(&b':') || (lenient && saw_intel_record) {
+        DetectedFormat::IntelHex
+    } else if matches!(first_line.first(), Some(b'S') | Some(b's')) {
+        DetectedFormat::SRecord
+    } else {
+        DetectedFormat::Binary
+    }
+}
+
+/// VBF files start with an ASCII header containing `vbf_version`; the data
+/// after it is binary, so check before the ASCII sniffing.
+fn looks_like_vbf(content: &[u8]) -> bool {
+    let head = &content[..content.len().min(512)];
+    head.windows(b"vbf_version".len())
+        .any(|w| w == b"vbf_version")
+}
+
+fn parse_forced(
+    content: &[u8],
+    format: InputFormat,
+    options: &InputOptions,
+) -> Result<(HexFile, Vec<crate::ParseWarning>), crate::ParseError> {
+    let hexfile = match format {
+        InputFormat::IntelHex => return crate::parse_intel_hex_with(content, &options.text),
+        InputFormat::SRecord => return crate::parse_srec_with(content, &options.text),
+        InputFormat::Binary => crate::parse_binary(content, 0)?,
+        InputFormat::HexAscii => crate::parse_hex_ascii(content, 0)?,
+        InputFormat::IntelHex16 => crate::parse_intel_hex_16bit(content)?,
+    };
+    Ok((hexfile, Vec::new()))
+}
+
+/// Load `/IN`, memory-mapping it with `mmap` when the provider reads it
+/// straight from disk. The caller must then keep every write of the run off
+/// `path`, as [`MappedInputGuard`](super::MappedInputGuard) does.
+pub(crate) fn load_binary_input(
+    provider: &impl ReadProvider,
+    path: &Path,
+    offset: u32,
+    mmap: bool,
+) -> Result<HexFile, CliError> {
+    if mmap && provider.mappable_len(path).is_some() {
+        // SAFETY: the run writes through a MappedInputGuard for `path`, and
+        // /MMAP documents that other processes must leave the file alone.
+        return Ok(unsafe { crate::parse_binary_mmap(path, offset) }?);
+    }
+    let content = provider.read_bytes(path)?;
+    let hexfile = crate::parse_binary(&content, offset)?;
+    Ok(hexfile)
+}
+
+pub(crate) fn load_hex_ascii_input(
+    provider: &impl ReadProvider,
+    path: &Path,
+    offset: u32,
+) -> Result<HexFile, CliError> {
+    let content = provider.read_bytes(path)?;
+    let hexfile = crate::parse_hex_ascii(&content, offset)?;
+    Ok(hexfile)
+}
+
+pub(crate) fn hexfiles_overlap(a: &HexFile, b: &HexFile) -> bool {
+    let mut a_segments = a.segments().to_vec();
+    let mut b_segments = b.segments().to_vec();
+    a_segments.sort_by_key(|s| s.start_address);
+    b_segments.sort_by_key(|s| s.start_address);
+
+    let mut i = 0usize;
+    let mut j = 0usize;
+    while i < a_segments.len() && j < b_segments.len() {
+        let a_seg = &a_segments[i];
+        let b_seg = &b_segments[j];
+        if a_seg.end_address() < b_seg.start_address {
+            i += 1;
+            continue;
+        }
+        if b_seg.end_address() < a_seg.start_address {
+            j += 1;
+            continue;
+        }
+        return true;
+    }
+    false
+}
+
+pub(crate) fn load_intel_hex_16bit_input(
+    provider: &impl ReadProvider,
+    path: &Path,
+) -> Result<HexFile, CliError> {
+    let content = provider.read_bytes(path)?;
+    let hexfile = crate::parse_intel_hex_16bit(&content)?;
+    Ok(hexfile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::unique_temp_dir;
+    use super::super::{FsProvider, GzipProvider};
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_load_binary_input_maps_on_request() {
+        let dir = unique_temp_dir();
+        let path = dir.join("dump.bin");
+        fs::write(&path, [0xAA; 16]).unwrap();
+
+        let load = |mmap| load_binary_input(&FsProvider, &path, 0x100, mmap).unwrap();
+        assert!(load(true).segments()[0].data.is_mapped());
+        assert!(!load(false).segments()[0].data.is_mapped());
+        assert_eq!(load(true), load(false));
+
+        let gzip = GzipProvider {
+            inner: &FsProvider,
+            limit: 1024,
+        };
+        assert_eq!(gzip.mappable_len(&path), Some(16));
+        fs::write(&path, [0x1F, 0x8B, 0x08, 0x00]).unwrap();
+        assert_eq!(gzip.mappable_len(&path), None);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,321 @@
+use std::path::Path;
+
+use crate::HexFile;
+
+use super::super::error::{CliError, file_context};
+use super::super::ini::load_ini;
+use super::super::parse_util::{parse_hexview_ranges, parse_number};
+use super::super::types::{Args, OutputFormat, OutputSpec};
+use super::{ReadProvider, WriteProvider, load_optional_ini, resolve_ini_path};
+
+pub(super) fn write_vbf_output(
+    args: &Args,
+    hexfile: &HexFile,
+    output_path: &Path,
+    provider: &impl ReadProvider,
+    writer: &dyn WriteProvider,
+) -> Result<(), CliError> {
+    let ini_path = resolve_ini_path(args)?;
+    let ini = load_ini(&ini_path, provider, &args.defines)?.into_flat();
+
+    let required = |key: &str| {
+        ini.get(key)
+            .cloned()
+            .ok_or_else(|| CliError::Config(format!("missing VBF header {key}")))
+    };
+    let defaults = crate::VbfWriteOptions::default();
+    let text = |key: &str, default: String| ini.get(key).cloned().unwrap_or(default);
+
+    let erase = match ini.get("erase") {
+        Some(value) => Some(
+            parse_hexview_ranges(value)?
+                .iter()
+                .map(|range| (range.start(), range.length()))
+                .collect(),
+        ),
+        None => Some(erase_ranges(args, hexfile, provider)?),
+    };
+    let options = crate::VbfWriteOptions {
+        version: text("vbf_version", defaults.version),
+        sw_part_number: required("sw_part_number")?,
+        sw_version: text("sw_version", defaults.sw_version),
+        sw_part_type: text("sw_part_type", defaults.sw_part_type),
+        network: text("network", defaults.network),
+        ecu_address: parse_number(&required("ecu_address")?)?,
+        frame_format: text("frame_format", defaults.frame_format),
+        erase,
+        call: ini.get("call").map(|v| parse_number(v)).transpose()?,
+    };
+
+    writer.write_bytes(output_path, &crate::write_vbf(hexfile, &options))?;
+    Ok(())
+}
+
+pub(super) fn write_gm_output(
+    args: &Args,
+    output: &OutputSpec,
+    hexfile: &HexFile,
+    output_path: &Path,
+    provider: &impl ReadProvider,
+    writer: &dyn WriteProvider,
+) -> Result<(), CliError> {
+    let ini_path = resolve_ini_path(args)?;
+    let ini = load_ini(&ini_path, provider, &args.defines)?.into_flat();
+
+    let required = |key: &str| {
+        ini.get(key)
+            .ok_or_else(|| CliError::Config(format!("missing [GMHEADER] {key}")))
+    };
+    let word = |key: &str, value: &str| -> Result<u16, CliError> {
+        u16::try_from(parse_number(value)?)
+            .map_err(|_| CliError::Config(format!("[GMHEADER] {key} exceeds 16 bits: {value}")))
+    };
+    let part_number = || -> Result<u32, CliError> { Ok(parse_number(required("partnumber")?)?) };
+    let design_level_suffix = || -> Result<[u8; 2], CliError> {
+        let dls = required("dls")?;
+        <[u8; 2]>::try_from(dls.as_bytes()).map_err(|_| {
+            CliError::Config(format!("[GMHEADER] DLS must be 2 characters, got '{dls}'"))
+        })
+    };
+
+    let (kind, address) = match output.format {
+        Some(OutputFormat::GmHeaderOs { addr }) => (
+            crate::GmHeaderKind::OperatingSystem {
+                part_number: part_number()?,
+                design_level_suffix: design_level_suffix()?,
+                header_format_id: ini
+                    .get("hfi")
+                    .map(|v| word("hfi", v))
+                    .transpose()?
+                    .unwrap_or(0),
+            },
+            addr,
+        ),
+        Some(OutputFormat::GmHeaderCal { addr }) => (
+            crate::GmHeaderKind::Calibration {
+                part_number: part_number()?,
+                design_level_suffix: design_level_suffix()?,
+            },
+            addr,
+        ),
+        Some(OutputFormat::GmHeader { addr }) => (crate::GmHeaderKind::Basic, addr),
+        _ => return Err(CliError::Unsupported("not a GM header format".into())),
+    };
+    let options = crate::GmHeaderOptions {
+        kind,
+        module_id: word("moduleid", required("moduleid")?)?,
+        address,
+        fill_byte: args.align_fill,
+    };
+    let hex_options = crate::IntelHexWriteOptions {
+        bytes_per_line: output.bytes_per_line.unwrap_or(32),
+        mode: crate::IntelHexMode::Auto,
+        ..Default::default()
+    };
+    writer.write_bytes(
+        output_path,
+        &crate::write_gm_header(hexfile, &options, &hex_options)?,
+    )?;
+    Ok(())
+}
+
+pub(super) fn write_fiat_output(
+    args: &Args,
+    hexfile: &HexFile,
+    output_path: &Path,
+    provider: &impl ReadProvider,
+    writer: &dyn WriteProvider,
+) -> Result<(), CliError> {
+    let ini = load_optional_ini(args, provider)?;
+    let options = crate::FiatBinWriteOptions {
+        fill_byte: args.align_fill,
+        alignment: ini
+            .get("alignment")
+            .map(|v| parse_number(v))
+            .transpose()?
+            .unwrap_or(0),
+    };
+    writer.write_bytes(output_path, &crate::write_fiat_bin(hexfile, &options)?)?;
+    Ok(())
+}
+
+pub(super) fn write_flash_kernel_output(
+    args: &Args,
+    hexfile: &HexFile,
+    output_path: &Path,
+    provider: &impl ReadProvider,
+    writer: &dyn WriteProvider,
+) -> Result<(), CliError> {
+    let ini = load_optional_ini(args, provider)?;
+    let options = crate::FlashKernelWriteOptions {
+        entry_address: ini.get("entry").map(|v| parse_number(v)).transpose()?,
+    };
+    writer.write_bytes(output_path, &crate::write_flash_kernel(hexfile, &options)?)?;
+    Ok(())
+}
+
+pub(super) fn write_vag_output(
+    args: &Args,
+    hexfile: &HexFile,
+    output_path: &Path,
+    provider: &impl ReadProvider,
+    writer: &dyn WriteProvider,
+) -> Result<(), CliError> {
+    let ini_path = resolve_ini_path(args)?;
+    let ini = load_ini(&ini_path, provider, &args.defines)?.into_flat();
+
+    let algorithm = ini
+        .get("checksum")
+        .ok_or_else(|| CliError::Config("missing [VAG] Checksum".into()))?;
+    let algorithm = u8::try_from(parse_number(algorithm)?)
+        .map_err(|_| CliError::Config(format!("invalid [VAG] Checksum {algorithm}")))?;
+    let algorithm = crate::ChecksumAlgorithm::from_index(algorithm)
+        .map_err(|e| CliError::from(e).with_context("[VAG] Checksum"))?;
+    let block_size = ini
+        .get("blocksize")
+        .map(|v| parse_number(v))
+        .transpose()?
+        .unwrap_or(0);
+
+    let options = crate::VagWriteOptions {
+        block_size,
+        algorithm,
+    };
+    writer.write_bytes(output_path, &crate::write_vag(hexfile, &options)?)?;
+    Ok(())
+}
+
+pub(super) fn write_gac_output(
+    args: &Args,
+    output: &OutputSpec,
+    hexfile: &HexFile,
+    output_path: &Path,
+    provider: &impl ReadProvider,
+    writer: &dyn WriteProvider,
+) -> Result<(), CliError> {
+    let ini_path = resolve_ini_path(args)?;
+    let ini = load_ini(&ini_path, provider, &args.defines)?.into_flat();
+
+    let part_number = ini
+        .get("partnumber")
+        .cloned()
+        .ok_or_else(|| CliError::Config("missing [GAC] PartNumber".into()))?;
+    let sw_version = ini
+        .get("swversion")
+        .cloned()
+        .ok_or_else(|| CliError::Config("missing [GAC] SwVersion".into()))?;
+    let options = crate::GacWriteOptions {
+        part_number,
+        sw_version,
+        swil: matches!(output.format, Some(OutputFormat::GacSwil)),
+        fill_byte: args.align_fill,
+    };
+    writer.write_bytes(output_path, &crate::write_gac(hexfile, &options)?)?;
+    Ok(())
+}
+
+pub(super) fn write_porsche_output(
+    args: &Args,
+    hexfile: &HexFile,
+    output_path: &Path,
+    writer: &dyn WriteProvider,
+) -> Result<(), CliError> {
+    let mut normalized = hexfile.normalized_lossy();
+    if normalized.segments().is_empty() {
+        writer.write_bytes(output_path, &[])?;
+        return Ok(());
+    }
+
+    let fill = args.align_fill;
+    normalized.fill_gaps(fill)?;
+    let data = normalized.segments()[0].data.clone();
+    let checksum = byte_sum_u16(&data);
+    let mut output = data;
+    output.extend_from_slice(&checksum.to_be_bytes());
+    writer.write_bytes(output_path, &output)?;
+    Ok(())
+}
+
+/// Erase blocks (start, length): the /AEMAP sectors holding data, merged into
+/// runs, or else [`erase_blocks`].
+pub(super) fn erase_ranges(
+    args: &Args,
+    hexfile: &HexFile,
+    provider: &impl ReadProvider,
+) -> Result<Vec<(u32, u32)>, CliError> {
+    let Some(ref path) = args.erase_sector_map else {
+        return Ok(erase_blocks(hexfile, args.align_erase));
+    };
+    let context = || format!("/AEMAP:{}", path.display());
+    let text = provider
+        .read_string(path)
+        .map_err(|e| file_context("/AEMAP", path)(e.into()))?;
+    let map: crate::SectorMap = text.parse().map_err(|e: crate::SectorMapError| {
+        CliError::Config(e.to_string()).with_context(context())
+    })?;
+    let runs = hexfile
+        .touched_sectors(&map)
+        .map_err(|e| CliError::from(e).with_context(context()))?;
+    Ok(runs.iter().map(|run| (run.start(), run.length())).collect())
+}
+
+/// Erase blocks (start, length) covering each segment, widened to /AE alignment.
+fn erase_blocks(hexfile: &HexFile, alignment: Option<u32>) -> Vec<(u32, u32)> {
+    let mut blocks = Vec::new();
+
+    for segment in hexfile.normalized_segments().iter() {
+        let start = segment.start_address;
+        let len = segment.len() as u32;
+        let (aligned_start, aligned_len) = if let Some(align) = alignment.filter(|a| *a > 0) {
+            let start64 = start as u64;
+            let len64 = len as u64;
+            let align64 = align as u64;
+            let aligned_start = (start64 / align64) * align64;
+            let end = start64 + len64 - 1;
+            let aligned_end = (end + 1).div_ceil(align64) * align64 - 1;
+            let aligned_len = aligned_end - aligned_start + 1;
+            (aligned_start as u32, aligned_len as u32)
+        } else {
+            (start, len)
+        };
+        blocks.push((aligned_start, aligned_len));
+    }
+
+    blocks
+}
+
+fn byte_sum_u16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |acc, &b| acc.wrapping_add(b as u16))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::FsProvider;
+    use super::super::tests::unique_temp_dir;
+    use super::*;
+    use crate::Segment;
+    use std::fs;
+
+    #[test]
+    fn test_write_porsche_output_appends_checksum() {
+        let dir = unique_temp_dir();
+        let output = dir.join("porsche.bin");
+        let args = Args {
+            align_fill: 0xFF,
+            ..Args::default()
+        };
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x1000, vec![0x01, 0x02]),
+            Segment::new(0x1004, vec![0x03]),
+        ]);
+
+        write_porsche_output(&args, &hexfile, &output, &FsProvider).unwrap();
+        let data = fs::read(&output).unwrap();
+        // data: 0x01,0x02,0xFF,0xFF,0x03 then checksum
+        assert_eq!(&data[..5], &[0x01, 0x02, 0xFF, 0xFF, 0x03]);
+        let checksum = u16::from_be_bytes([data[5], data[6]]);
+        assert_eq!(checksum, 0x01 + 0x02 + 0xFF + 0xFF + 0x03);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,409 @@
+use std::path::Path;
+
+use crate::HexFile;
+
+use super::super::error::CliError;
+use super::super::types::{Args, OutputFormat};
+use super::{DEFAULT_BUFFER_KB, WriteProvider, stream_to_file};
+
+/// /XI record type parameter: 1 = extended linear, 2 = extended segment.
+fn intel_hex_mode(record_type: Option<u8>) -> crate::IntelHexMode {
+    match record_type {
+        Some(1) => crate::IntelHexMode::ExtendedLinear,
+        Some(2) => crate::IntelHexMode::ExtendedSegment,
+        _ => crate::IntelHexMode::Auto,
+    }
+}
+
+/// Line ending and hex digit case for the generic text formats, and the
+/// write buffer size for the streamed ones.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct TextStyle {
+    pub(super) line_ending: crate::LineEnding,
+    pub(super) uppercase: bool,
+    /// /ALIGNREC: /XI and /XS records start at multiples of their length.
+    pub(super) align_records: bool,
+    /// /BTBS in bytes.
+    pub(super) buffer_size: usize,
+    /// Fill every gap with this byte while writing, as /FA would.
+    pub(super) gap_fill: Option<u8>,
+    /// /BANK: /XI without a record type writes extended segment records, one
+    /// per bank.
+    pub(super) banked: bool,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            line_ending: crate::LineEnding::CrLf,
+            uppercase: true,
+            align_records: false,
+            buffer_size: DEFAULT_BUFFER_KB as usize * 1024,
+            gap_fill: None,
+            banked: false,
+        }
+    }
+}
+
+impl TextStyle {
+    pub(super) fn from_args(args: &Args, gap_fill: Option<u8>) -> Self {
+        Self {
+            line_ending: args.line_ending,
+            uppercase: !args.lowercase_hex,
+            align_records: args.align_records,
+            buffer_size: args.buffer_size(),
+            gap_fill,
+            banked: args.bank.is_some(),
+        }
+    }
+}
+
+/// With `style.gap_fill`, Intel HEX, S-Record and plain binary output fill the
+/// gaps while writing; other formats write a filled copy.
+pub(super) fn write_output(
+    hexfile: &HexFile,
+    path: &Path,
+    format: &Option<OutputFormat>,
+    bytes_per_line: Option<u8>,
+    style: TextStyle,
+    writer: &dyn WriteProvider,
+) -> Result<(), CliError> {
+    let format = format
+        .as_ref()
+        .unwrap_or(&OutputFormat::IntelHex { record_type: None });
+
+    let streamed = matches!(
+        format,
+        OutputFormat::IntelHex { .. }
+            | OutputFormat::SRecord { .. }
+            | OutputFormat::Binary {
+                start: None,
+                end: None,
+                fill: None,
+            }
+    );
+    if let Some(fill_byte) = style.gap_fill
+        && !streamed
+    {
+        let mut filled = hexfile.clone();
+        filled.fill_gaps(fill_byte)?;
+        let style = TextStyle {
+            gap_fill: None,
+            ..style
+        };
+        return write_output(
+            &filled,
+            path,
+            &Some(format.clone()),
+            bytes_per_line,
+            style,
+            writer,
+        );
+    }
+
+    match format {
+        OutputFormat::IntelHex { record_type } => {
+            let options = crate::IntelHexWriteOptions {
+                bytes_per_line: bytes_per_line.unwrap_or(32),
+                mode: match record_type {
+                    None if style.banked => crate::IntelHexMode::ExtendedSegment,
+                    _ => intel_hex_mode(*record_type),
+                },
+                line_ending: style.line_ending,
+                uppercase: style.uppercase,
+                align_records: style.align_records,
+                ..Default::default()
+            };
+            stream_to_file(writer, path, style.buffer_size, |w| match style.gap_fill {
+                Some(fill_byte) => {
+                    crate::write_intel_hex_filled_to(hexfile, &options, fill_byte, w)
+                }
+                None => crate::write_intel_hex_to(hexfile, &options, w),
+            })?;
+        }
+        OutputFormat::IntelHex16 { record_type } => {
+            let options = crate::IntelHexWriteOptions {
+                bytes_per_line: bytes_per_line.unwrap_or(16),
+                mode: intel_hex_mode(*record_type),
+                line_ending: style.line_ending,
+                uppercase: style.uppercase,
+                ..Default::default()
+            };
+            let output = crate::write_intel_hex_16bit(hexfile, &options)?;
+            writer.write_bytes(path, &output)?;
+        }
+        OutputFormat::SRecord {
+            record_type,
+            header,
+        } => {
+            let record_type = match record_type {
+                None => None,
+                Some(0) => Some(crate::SRecordType::S1),
+                Some(1) => Some(crate::SRecordType::S2),
+                Some(2) => Some(crate::SRecordType::S3),
+                Some(other) => {
+                    return Err(CliError::Unsupported(format!(
+                        "unsupported S-Record type {other}"
+                    )));
+                }
+            };
+            let options = crate::SRecordWriteOptions {
+                bytes_per_line: bytes_per_line.unwrap_or(16),
+                record_type,
+                header: header.clone(),
+                emit_count: header.is_some() || hexfile.metadata().header.is_some(),
+                entry_point: None,
+                line_ending: style.line_ending,
+                uppercase: style.uppercase,
+                align_records: style.align_records,
+            };
+            stream_to_file(writer, path, style.buffer_size, |w| match style.gap_fill {
+                Some(fill_byte) => crate::write_srec_filled_to(hexfile, &options, fill_byte, w),
+                None => crate::write_srec_to(hexfile, &options, w),
+            })?;
+        }
+        OutputFormat::HexDump => {
+            let options = crate::HexDumpOptions {
+                bytes_per_line: bytes_per_line.unwrap_or(16) as usize,
+                ..Default::default()
+            };
+            writer.write_bytes(path, &crate::write_hex_dump(hexfile, &options))?;
+        }
+        OutputFormat::Binary { start, end, fill } => {
+            let options = crate::BinaryWriteOptions {
+                start: *start,
+                end: *end,
+                fill: fill.or(style.gap_fill).unwrap_or(0xFF),
+                // A window given in full is written however wide it is.
+                max_span: match (start, end) {
+                    (Some(_), Some(_)) => u64::MAX,
+                    _ => crate::DEFAULT_MAX_FILL_SPAN,
+                },
+                ..Default::default()
+            };
+            stream_to_file(writer, path, style.buffer_size, |w| {
+                crate::write_binary_to(hexfile, &options, w)
+            })?;
+        }
+        OutputFormat::BinaryRaw => {
+            let options = crate::BinaryWriteOptions {
+                concatenate_in_order: true,
+                ..Default::default()
+            };
+            stream_to_file(writer, path, style.buffer_size, |w| {
+                crate::write_binary_to(hexfile, &options, w)
+            })?;
+        }
+        OutputFormat::Json => write_json_output(hexfile, path, writer)?,
+        OutputFormat::Csv => write_csv_report(hexfile, path, writer)?,
+        OutputFormat::HexAscii {
+            line_length,
+            separator,
+            address_prefix,
+        } => {
+            let options = crate::HexAsciiWriteOptions {
+                line_length: line_length.unwrap_or(16) as usize,
+                separator: separator.clone(),
+                line_ending: style.line_ending,
+                uppercase: style.uppercase,
+                address_prefix: *address_prefix,
+            };
+            let output = crate::write_hex_ascii(hexfile, &options);
+            writer.write_bytes(path, &output)?;
+        }
+        OutputFormat::SeparateBinary { template } => {
+            let options = crate::SeparateBinaryOptions {
+                template: template.clone(),
+            };
+            write_separate_binary(hexfile, path, &options, writer)?
+        }
+        OutputFormat::FiatBin => {
+            let options = crate::FiatBinWriteOptions::default();
+            writer.write_bytes(path, &crate::write_fiat_bin(hexfile, &options)?)?;
+        }
+        OutputFormat::FlashKernel => {
+            let options = crate::FlashKernelWriteOptions::default();
+            writer.write_bytes(path, &crate::write_flash_kernel(hexfile, &options)?)?;
+        }
+        OutputFormat::RustCode => {
+            let options = crate::RustCodeWriteOptions::default();
+            writer.write_bytes(path, &crate::write_rust_code(hexfile, &options)?)?;
+        }
+        OutputFormat::CCode => {
+            return Err(CliError::Unsupported(
+                "C-code output must be handled by caller".into(),
+            ));
+        }
+        OutputFormat::Porsche => {
+            return Err(CliError::Unsupported(
+                "Porsche output must be handled by caller".into(),
+            ));
+        }
+        _ => {
+            return Err(CliError::Unsupported(format!(
+                "Output format {:?} not yet implemented",
+                format
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+fn write_json_output(
+    hexfile: &HexFile,
+    path: &Path,
+    writer: &dyn WriteProvider,
+) -> Result<(), CliError> {
+    let output = crate::write_json(hexfile, &crate::JsonWriteOptions::default())?;
+    writer.write_bytes(path, &output)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "serde"))]
+fn write_json_output(
+    _hexfile: &HexFile,
+    _path: &Path,
+    _sink: &dyn WriteProvider,
+) -> Result<(), CliError> {
+    Err(CliError::Unsupported(
+        "/XJ requires h3xy built with the `serde` feature".to_string(),
+    ))
+}
+
+/// `/XCSV`: a quoted row per normalized segment, then a TOTAL row whose
+/// CRC-32 covers all data with gaps skipped. A `label` column is added when
+/// any segment has one.
+fn write_csv_report(
+    hexfile: &HexFile,
+    path: &Path,
+    writer: &dyn WriteProvider,
+) -> Result<(), CliError> {
+    let report = hexfile.segment_report();
+    let labelled = report.iter().any(|info| info.name.is_some());
+    let mut out = String::from("\"address_start\",\"address_end\",\"length\",\"crc32\"");
+    out.push_str(if labelled { ",\"label\"\r\n" } else { "\r\n" });
+    for info in &report {
+        out.push_str(&format!(
+            "\"0x{:08X}\",\"0x{:08X}\",\"{}\",\"0x{:08X}\"",
+            info.start_address, info.end_address, info.length, info.crc32
+        ));
+        if labelled {
+            out.push_str(&format!(
+                ",\"{}\"",
+                info.name.as_deref().unwrap_or_default()
+            ));
+        }
+        out.push_str("\r\n");
+    }
+    let crc = hexfile.calculate_checksum(&crate::ChecksumOptions {
+        algorithm: crate::ChecksumAlgorithm::Crc32,
+        ..Default::default()
+    })?;
+    let length: usize = report.iter().map(|info| info.length).sum();
+    out.push_str(&format!(
+        "\"TOTAL\",\"\",\"{length}\",\"0x{}\"",
+        crc.iter().map(|b| format!("{b:02X}")).collect::<String>()
+    ));
+    out.push_str(if labelled { ",\"\"\r\n" } else { "\r\n" });
+    writer.write_bytes(path, out.as_bytes())?;
+    Ok(())
+}
+
+fn write_separate_binary(
+    hexfile: &HexFile,
+    path: &Path,
+    options: &crate::SeparateBinaryOptions,
+    writer: &dyn WriteProvider,
+) -> Result<(), CliError> {
+    let segments = hexfile.normalized_segments();
+
+    if segments.is_empty() {
+        return Ok(());
+    }
+
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("output");
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("bin");
+
+    // Every name is known before the first file is written.
+    let names = crate::separate_binary_names(&segments, stem, ext, options)?;
+    for (name, segment) in names.iter().zip(segments.iter()) {
+        writer.write_bytes(&dir.join(name), &segment.data)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::unique_temp_dir;
+    use super::super::{CaptureProvider, FsProvider};
+    use super::*;
+    use crate::Segment;
+    use std::fs;
+
+    #[test]
+    fn test_write_separate_binary_outputs_segments() {
+        let dir = unique_temp_dir();
+        let output = dir.join("out.bin");
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x1000, vec![0xAA, 0xBB]),
+            Segment::new(0x2000, vec![0xCC]),
+        ]);
+
+        write_output(
+            &hexfile,
+            &output,
+            &Some(OutputFormat::SeparateBinary { template: None }),
+            None,
+            TextStyle::default(),
+            &FsProvider,
+        )
+        .unwrap();
+
+        let file1 = dir.join("out_1000.bin");
+        let file2 = dir.join("out_2000.bin");
+        assert_eq!(fs::read(file1).unwrap(), vec![0xAA, 0xBB]);
+        assert_eq!(fs::read(file2).unwrap(), vec![0xCC]);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_write_output_streams_through_write_provider() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x1000, vec![0xAA, 0xBB])]);
+        let writer = CaptureProvider::default();
+
+        for (name, format) in [
+            ("out.hex", OutputFormat::IntelHex { record_type: None }),
+            ("out.bin", OutputFormat::SeparateBinary { template: None }),
+        ] {
+            write_output(
+                &hexfile,
+                Path::new(name),
+                &Some(format),
+                None,
+                TextStyle::default(),
+                &writer,
+            )
+            .unwrap();
+        }
+
+        let (files, outputs) = writer.into_parts();
+        assert!(outputs.is_empty());
+        let names: Vec<_> = files.keys().map(|path| path.to_str().unwrap()).collect();
+        assert_eq!(names, ["out.hex", "out_1000.bin"]);
+        assert_eq!(
+            crate::parse_intel_hex(&files[Path::new("out.hex")])
+                .unwrap()
+                .segments(),
+            hexfile.segments()
+        );
+        assert!(!Path::new("out_1000.bin").exists());
+    }
+}
@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+
+use crate::HexFile;
+
+use super::super::error::CliError;
+use super::super::ini::load_ini;
+use super::super::parse_util::parse_number;
+use super::super::types::Args;
+use super::{ReadProvider, WriteProvider, load_optional_ini, resolve_ini_path};
+
+pub(super) fn write_c_code_output(
+    args: &Args,
+    hexfile: &HexFile,
+    output_path: &Path,
+    provider: &impl ReadProvider,
+    writer: &dyn WriteProvider,
+) -> Result<(), CliError> {
+    let ini_path = resolve_ini_path(args)?;
+    let ini = load_ini(&ini_path, provider, &args.defines)?.section("CCODE");
+
+    let prefix = ini
+        .get("prefix")
+        .cloned()
+        .unwrap_or_else(|| "flashDrv".to_string());
+    let word_size = ini
+        .get("wordsize")
+        .map(|v| parse_number(v))
+        .transpose()?
+        .unwrap_or(0);
+    let word_type = ini
+        .get("wordtype")
+        .map(|v| parse_number(v))
+        .transpose()?
+        .unwrap_or(0);
+    let decrypt = ini
+        .get("decryption")
+        .map(|v| parse_number(v).map(|n| n != 0))
+        .transpose()?
+        .unwrap_or(false);
+    let decrypt_value = ini
+        .get("decryptvalue")
+        .map(|v| parse_number(v))
+        .transpose()?
+        .unwrap_or(0);
+    let per_segment = ini
+        .get("persegment")
+        .map(|v| parse_number(v).map(|n| n != 0))
+        .transpose()?
+        .unwrap_or(false);
+    let emit_descriptor_table = ini
+        .get("descriptortable")
+        .map(|v| parse_number(v).map(|n| n != 0))
+        .transpose()?
+        .unwrap_or(false);
+    let bytes_per_line = ini
+        .get("bytesperline")
+        .map(|v| parse_number(v))
+        .transpose()?
+        .unwrap_or(0);
+    let use_uppercase_hex = ini
+        .get("uppercasehex")
+        .map(|v| parse_number(v).map(|n| n != 0))
+        .transpose()?
+        .unwrap_or(true);
+
+    let word_type = match word_type {
+        0 => crate::CCodeWordType::Intel,
+        1 => crate::CCodeWordType::Motorola,
+        other => {
+            return Err(CliError::Config(format!("unsupported WordType {other}")));
+        }
+    };
+
+    let header_name = output_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(&prefix)
+        .to_string();
+
+    let options = crate::CCodeWriteOptions {
+        prefix: prefix.clone(),
+        header_name,
+        word_size: word_size as u8,
+        word_type,
+        decrypt,
+        decrypt_value,
+        per_segment,
+        emit_descriptor_table,
+        qualifier: ini.get("qualifier").cloned().unwrap_or_default(),
+        bytes_per_line: bytes_per_line as usize,
+        use_uppercase_hex,
+    };
+    let output = crate::write_c_code(hexfile, &options)?;
+
+    let (c_path, h_path) = derive_c_code_paths(output_path, &prefix);
+    writer.write_bytes(&c_path, &output.c)?;
+    writer.write_bytes(&h_path, &output.h)?;
+    Ok(())
+}
+
+fn derive_c_code_paths(output_path: &Path, prefix: &str) -> (PathBuf, PathBuf) {
+    let dir = output_path.parent().unwrap_or_else(|| Path::new("."));
+    let stem = output_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(prefix);
+    let c_path = dir.join(format!("{stem}.c"));
+    let h_path = dir.join(format!("{stem}.h"));
+    (c_path, h_path)
+}
+
+pub(super) fn write_rust_output(
+    args: &Args,
+    hexfile: &HexFile,
+    output_path: &Path,
+    provider: &impl ReadProvider,
+    writer: &dyn WriteProvider,
+) -> Result<(), CliError> {
+    let ini = load_optional_ini(args, provider)?;
+    let defaults = crate::RustCodeWriteOptions::default();
+    let options = crate::RustCodeWriteOptions {
+        prefix: ini.get("prefix").cloned().unwrap_or(defaults.prefix),
+        per_segment: ini
+            .get("persegment")
+            .map(|v| parse_number(v).map(|n| n != 0))
+            .transpose()?
+            .unwrap_or(defaults.per_segment),
+        bytes_per_line: ini
+            .get("bytesperline")
+            .map(|v| parse_number(v))
+            .transpose()?
+            .map_or(defaults.bytes_per_line, |n| n as usize),
+    };
+    writer.write_bytes(output_path, &crate::write_rust_code(hexfile, &options)?)?;
+    Ok(())
+}
@@ -0,0 +1,183 @@
+//! Message authentication codes for /DP (HMAC-SHA256, AES-128-CMAC).
+//!
+//! The MAC covers the same payload as the signature methods. The key is
+//! `hex:<digits>` given inline, or read from the key_info path: file text that
+//! is entirely hex digits is decoded, anything else is used as raw bytes. A
+//! missing file is an error, so a mistyped path never becomes the key.
+
+use aes::Aes128;
+use cmac::Cmac;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use super::signature::{key_source, load_key_material};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum MacMethod {
+    HmacSha256 { with_metadata: bool },
+    AesCmac { with_metadata: bool },
+}
+
+impl MacMethod {
+    pub(super) fn with_metadata(self) -> bool {
+        match self {
+            MacMethod::HmacSha256 { with_metadata } | MacMethod::AesCmac { with_metadata } => {
+                with_metadata
+            }
+        }
+    }
+}
+
+pub(super) fn map_mac_method(method: u8) -> Option<MacMethod> {
+    match method {
+        60 => Some(MacMethod::HmacSha256 {
+            with_metadata: false,
+        }),
+        61 => Some(MacMethod::HmacSha256 {
+            with_metadata: true,
+        }),
+        62 => Some(MacMethod::AesCmac {
+            with_metadata: false,
+        }),
+        63 => Some(MacMethod::AesCmac {
+            with_metadata: true,
+        }),
+        _ => None,
+    }
+}
+
+pub(super) fn compute_mac(
+    method: MacMethod,
+    payload: &[u8],
+    key_info: &str,
+) -> Result<Vec<u8>, String> {
+    let key = match key_source(key_info)?.strip_prefix("hex:") {
+        Some(digits) => parse_hex_key(digits)?,
+        None => decode_key(load_key_material(key_info)?),
+    };
+    match method {
+        MacMethod::HmacSha256 { .. } => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(&key).map_err(|e| e.to_string())?;
+            mac.update(payload);
+            Ok(mac.finalize().into_bytes().to_vec())
+        }
+        MacMethod::AesCmac { .. } => {
+            let mut mac = Cmac::<Aes128>::new_from_slice(&key)
+                .map_err(|_| format!("AES-128-CMAC key must be 16 bytes, got {}", key.len()))?;
+            mac.update(payload);
+            Ok(mac.finalize().into_bytes().to_vec())
+        }
+    }
+}
+
+fn parse_hex_key(digits: &str) -> Result<Vec<u8>, String> {
+    hex_bytes(digits.trim().as_bytes())
+        .ok_or_else(|| format!("hex: key must be an even number of hex digits, got '{digits}'"))
+}
+
+/// Key file contents: hex text decoded, anything else as it is.
+fn decode_key(material: Vec<u8>) -> Vec<u8> {
+    hex_bytes(material.trim_ascii()).unwrap_or(material)
+}
+
+fn hex_bytes(text: &[u8]) -> Option<Vec<u8>> {
+    if text.is_empty() || !text.len().is_multiple_of(2) || !text.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    let bytes = text
+        .chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).unwrap_or_default();
+            u8::from_str_radix(pair, 16).unwrap_or_default()
+        })
+        .collect();
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(data: &[u8]) -> String {
+        data.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn test_hmac_sha256_rfc4231() {
+        // Test case 2: the key file holds "Jefe", which is not hex, so it is
+        // used as raw bytes.
+        let path = std::env::temp_dir().join(format!("h3xy_hmac_{}.key", std::process::id()));
+        std::fs::write(&path, "Jefe").unwrap();
+        let mac = compute_mac(
+            MacMethod::HmacSha256 {
+                with_metadata: false,
+            },
+            b"what do ya want for nothing?",
+            path.to_str().unwrap(),
+        )
+        .unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            hex(&mac),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+
+        // Test case 1: 20 x 0x0b given as hex.
+        let mac = compute_mac(
+            MacMethod::HmacSha256 {
+                with_metadata: false,
+            },
+            b"Hi There",
+            "hex:0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b",
+        )
+        .unwrap();
+        assert_eq!(
+            hex(&mac),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+    }
+
+    #[test]
+    fn test_aes_cmac_nist_sp800_38b() {
+        let method = MacMethod::AesCmac {
+            with_metadata: false,
+        };
+        let key = "hex:2b7e151628aed2a6abf7158809cf4f3c";
+        assert_eq!(
+            hex(&compute_mac(method, b"", key).unwrap()),
+            "bb1d6929e95937287fa37d129b756746"
+        );
+        let message = [
+            0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93,
+            0x17, 0x2a,
+        ];
+        assert_eq!(
+            hex(&compute_mac(method, &message, key).unwrap()),
+            "070a16b46b4d4144f79bdd9dd04a287c"
+        );
+    }
+
+    #[test]
+    fn test_aes_cmac_rejects_wrong_key_length() {
+        let method = MacMethod::AesCmac {
+            with_metadata: false,
+        };
+        let err = compute_mac(method, b"data", "hex:00112233").unwrap_err();
+        assert!(err.contains("16 bytes"), "{err}");
+    }
+
+    #[test]
+    fn test_mac_key_must_be_a_file_or_hex_literal() {
+        let method = MacMethod::HmacSha256 {
+            with_metadata: false,
+        };
+        let missing = std::env::temp_dir().join("h3xy_no_such_mac.key");
+        let err = compute_mac(method, b"data", missing.to_str().unwrap()).unwrap_err();
+        assert!(err.starts_with("key file "), "{err}");
+        assert!(err.contains("h3xy_no_such_mac.key"), "{err}");
+
+        let err = compute_mac(method, b"data", "hex:abc").unwrap_err();
+        assert!(err.contains("even number of hex digits"), "{err}");
+        assert!(compute_mac(method, b"data", "").is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! Symbol lookup in linker map files for checksum placement (`/CS:@SYM:<name>`
+//! with `/MAPFILE:<file>`).
+//!
+//! GCC ld maps give symbols as `0x<address>  <name>` lines, including
+//! `<name> = .` assignments and `PROVIDE (<name> = .)`. IAR ILINK maps list
+//! them in the entry list as `<name>  0x<address> ...`, with `'` digit
+//! separators and long names wrapped onto a line of their own.
+
+/// Address of `symbol`, from the first line that defines it.
+pub(super) fn resolve_symbol(map: &str, symbol: &str) -> Option<u32> {
+    if is_iar(map) {
+        iar_symbol(map, symbol)
+    } else {
+        gnu_symbol(map, symbol)
+    }
+}
+
+fn is_iar(map: &str) -> bool {
+    map.contains("*** ENTRY LIST") || map.lines().take(20).any(|line| line.contains("IAR"))
+}
+
+fn gnu_symbol(map: &str, symbol: &str) -> Option<u32> {
+    map.lines().find_map(|line| {
+        let line = line.trim_start();
+        let (address, rest) = line.split_once(char::is_whitespace)?;
+        let address = parse_address(address)?;
+        let rest = rest.trim_start();
+        let rest = ["PROVIDE_HIDDEN", "PROVIDE"]
+            .iter()
+            .find_map(|keyword| rest.strip_prefix(keyword))
+            .map(|rest| rest.trim_start().trim_start_matches('('))
+            .unwrap_or(rest);
+        let name = rest
+            .split(|c: char| c.is_whitespace() || c == '=' || c == ')')
+            .next()?;
+        (name == symbol).then_some(address)
+    })
+}
+
+fn iar_symbol(map: &str, symbol: &str) -> Option<u32> {
+    let mut wrapped = false;
+    for line in map.lines() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let address = match tokens.as_slice() {
+            [name, address, ..] if *name == symbol => parse_address(address),
+            [address, ..] if wrapped => parse_address(address),
+            _ => None,
+        };
+        if address.is_some() {
+            return address;
+        }
+        wrapped = tokens.as_slice() == [symbol];
+    }
+    None
+}
+
+fn parse_address(token: &str) -> Option<u32> {
+    let digits = token
+        .strip_prefix("0x")
+        .or_else(|| token.strip_prefix("0X"))?
+        .replace('\'', "");
+    u64::from_str_radix(&digits, 16)
+        .ok()
+        .and_then(|value| u32::try_from(value).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GNU_MAP: &str = "\
+Memory Configuration
+
+Name             Origin             Length             Attributes
+FLASH            0x0000000008000000 0x0000000000100000 xr
+
+ .text.main     0x0000000008000400       0x24 build/main.o
+                0x0000000008000400                main
+ .rodata.__app_crc
+                0x0000000008004000        0x4 build/crc.o
+                0x0000000008004000                __app_crc
+                0x0000000008004004                __app_end = .
+                [!provide]                        PROVIDE (__unused = .)
+                0x0000000020000000                PROVIDE (__heap_start = .)
+";
+
+    const IAR_MAP: &str = "\
+###############################################################################
+#
+# IAR ELF Linker V9.30.1.335/W64 for ARM                  14/Mar/2026  10:02:11
+#
+###############################################################################
+
+*******************************************************************************
+*** ENTRY LIST
+***
+
+Entry                       Address   Size  Type      Object
+-----                       -------   ----  ----      ------
+__app_crc               0x800'4000     0x4  Data  Gb  crc.o [1]
+__iar_program_start     0x800'0101         Code  Gb  cmain.o [4]
+__a_very_long_symbol_name_that_wraps
+                        0x800'4100     0x8  Data  Gb  table.o [1]
+main                    0x800'0401    0x24  Code  Gb  main.o [1]
+";
+
+    #[test]
+    fn test_gnu_map_symbols() {
+        assert_eq!(resolve_symbol(GNU_MAP, "__app_crc"), Some(0x0800_4000));
+        assert_eq!(resolve_symbol(GNU_MAP, "__app_end"), Some(0x0800_4004));
+        assert_eq!(resolve_symbol(GNU_MAP, "__heap_start"), Some(0x2000_0000));
+        assert_eq!(resolve_symbol(GNU_MAP, "main"), Some(0x0800_0400));
+        assert_eq!(resolve_symbol(GNU_MAP, "__unused"), None);
+        assert_eq!(resolve_symbol(GNU_MAP, "build/crc.o"), None);
+        assert_eq!(resolve_symbol(GNU_MAP, "__app"), None);
+    }
+
+    #[test]
+    fn test_iar_map_symbols() {
+        assert_eq!(resolve_symbol(IAR_MAP, "__app_crc"), Some(0x0800_4000));
+        assert_eq!(
+            resolve_symbol(IAR_MAP, "__a_very_long_symbol_name_that_wraps"),
+            Some(0x0800_4100)
+        );
+        assert_eq!(resolve_symbol(IAR_MAP, "main"), Some(0x0800_0401));
+        assert_eq!(resolve_symbol(IAR_MAP, "Entry"), None);
+        assert_eq!(resolve_symbol(IAR_MAP, "__missing"), None);
+    }
+}
@@ -1,15 +1,15 @@
 //! HexView-compatible CLI argument parsing and execution.
 //!
 //! Processing order matches HexView (implemented subset):
-//! 1. Read input file
+//! 1. Read input file, back to linear addresses with /UNBANK
 //! 2. Open error log (/E)
-//! 3. Set silent mode (/S)
+//! 3. Set silent mode (/S); /VERBOSE and -vv trace each pipeline step to stderr
 //! 4. Import 16-bit Hex (/II2)
-//! 5. Address mapping (/S08MAP, /S12MAP, /REMAP)
-//! 6. dsPIC ops (/CDSPX, /CDSPS, /CDSPG)
+//! 5. Address mapping (/S08MAP, /S12MAP, /S12XMAP, then each /REMAP, then /MAPTAB)
+//! 6. dsPIC ops (/CDSPX, /CDSPS, /CDSPG, /CDSPI)
 //! 7. Fill ranges (/FR)
 //! 8. Cut ranges (/CR)
-//! 9. Merge files (/MT, /MO)
+//! 9. Merge files (/MT, /MO), then label blocks (/LABEL)
 //! 10. Address range filter (/AR)
 //! 11. Execute log commands (/L)
 //! 12. Create single-region (/FA)
@@ -17,16 +17,29 @@
 //! 14. Split blocks (/SB)
 //! 15. Swap bytes (/SWAPWORD, /SWAPLONG)
 //! 16. Checksum (/CS, /CSM)
-//! 17. Data processing signature subset (/DP32/33/38/39/46/47/48/49)
-//! 18. Signature verification subset (/SV4..11)
-//! 19. Export (/Xx)
+//! 17. Pipeline description file (/PIPE)
+//! 18. Data processing signature/MAC subset (/DP32/33/38/39/46-53/60-63, /DPEXT)
+//! 19. Signature verification subset (/SV4..13)
+//! 20. Postbuild script (/PB): each script line runs steps 5-19 again
+//! 21. Write the recorded steps as /L commands (/LOGOUT)
+//! 22. Export (/Xx), of the bank-switched image with /BANK
 //!
-//! Note: /PB remains unsupported (proprietary DLL-backed).
+//! `/?` or `/HELP[:option]` prints the option reference instead of processing.
+//! Failures exit with the code of their `CliError` category (2-6, see
+//! [`CliError::exit_code`]); the `/E` log (`/E+` appends to it) gets a
+//! `YYYY-MM-DD HH:MM:SS [E<code>] <message>` line, after a `WARNING:` or
+//! `NOTE:` line for each diagnostic raised on the way (see the `diagnostics`
+//! module).
 
+mod command_line;
+mod diagnostics;
 mod error;
 mod execute;
+mod help;
 mod ini;
 mod io;
+mod mac;
+mod mapfile;
 mod parse;
 mod parse_util;
 mod signature;
@@ -36,30 +49,68 @@ use std::io::Write;
 use std::process::ExitCode;
 use std::{collections::HashMap, path::Path};
 
-pub use error::{CliError, ExecuteOutput};
-pub use types::Args;
+use diagnostics::Diagnostics;
+use io::{CaptureProvider, FsProvider};
+
+pub use diagnostics::{Diagnostic, DiagnosticLevel};
+pub use error::{
+    CapturedOutput, CliError, EXIT_ARGUMENT, EXIT_IO, EXIT_OPERATION, EXIT_UNSUPPORTED,
+    EXIT_VERIFICATION, ExecuteOutput,
+};
+pub use types::{Args, ChecksumFormat, OutputFormat};
 
 pub fn run() -> ExitCode {
-    let args = match Args::parse() {
+    let cli_args: Vec<String> = std::env::args().skip(1).collect();
+    if cli_args.is_empty() {
+        print!("{}", help::overview());
+        return ExitCode::SUCCESS;
+    }
+    let args = match Args::parse_from(cli_args) {
         Ok(args) => args,
         Err(e) => {
             eprintln!("Error: {e}");
-            return ExitCode::FAILURE;
+            return ExitCode::from(EXIT_ARGUMENT);
         }
     };
 
-    if let Some(ref path) = args.error_log {
+    // Explicitly requested help is printed even with /S.
+    if let Some(ref topic) = args.help {
+        if topic.is_empty() {
+            print!("{}", help::overview());
+        } else if let Some(text) = help::topic(topic) {
+            print!("{text}");
+        } else {
+            eprintln!("Error: no help for option: {topic}");
+            return ExitCode::from(EXIT_ARGUMENT);
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    if args.echo_args {
+        println!("{}", args.to_command_line_string());
+    }
+
+    if let Some(ref path) = args.error_log
+        && !args.error_log_append
+    {
         let _ = std::fs::write(path, "");
     }
 
-    if let Err(e) = args.execute() {
-        if let Some(ref path) = args.error_log {
-            let _ = std::fs::write(path, format!("{e}"));
+    let diagnostics = Diagnostics::new(args.silent);
+    let result = args.execute_with_provider(&FsProvider, &FsProvider, &diagnostics);
+    if let Some(ref path) = args.error_log {
+        let time = args.date.unwrap_or_else(crate::DateStamp::now);
+        let mut log = diagnostics.log_text(&time);
+        if let Err(ref e) = result {
+            log.push_str(&error_log_entry(&time, e));
         }
+        let _ = append_error_log(path, &log);
+    }
+    if let Err(e) = result {
         if !args.silent {
             eprintln!("Error: {e}");
         }
-        return ExitCode::FAILURE;
+        return ExitCode::from(e.exit_code());
     }
 
     if args.write_version
@@ -77,13 +128,64 @@ pub fn run() -> ExitCode {
     ExitCode::SUCCESS
 }
 
+/// `YYYY-MM-DD HH:MM:SS`, the start of every /E entry.
+fn log_time(time: &crate::DateStamp) -> String {
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        time.year, time.month, time.day, time.hour, time.minute, time.second
+    )
+}
+
+/// The /E line for a failed run: `YYYY-MM-DD HH:MM:SS [E004] message`.
+fn error_log_entry(time: &crate::DateStamp, error: &CliError) -> String {
+    format!("{} [E{:03}] {error}\n", log_time(time), error.exit_code())
+}
+
+/// Add `entries` to the /E log, starting a new line if an earlier run's /V
+/// banner left it mid-line.
+fn append_error_log(path: &Path, entries: &str) -> std::io::Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let mid_line = std::fs::read(path).is_ok_and(|log| log.last().is_some_and(|&b| b != b'\n'));
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    if mid_line {
+        file.write_all(b"\n")?;
+    }
+    file.write_all(entries.as_bytes())
+}
+
 pub fn execute_in_memory(
     args: &str,
     blocks: &HashMap<String, crate::HexFile>,
 ) -> Result<ExecuteOutput, CliError> {
-    let parsed = Args::parse_from_str_with(args, |arg| {
+    let args = parse_with_blocks(args, blocks)?;
+    args.execute_with_blocks(blocks, &FsProvider, &Diagnostics::new(args.silent))
+}
+
+/// Like [`execute_in_memory`], but nothing is written to disk: exports land
+/// in [`ExecuteOutput::outputs`] and checksum, signature and /LOGOUT files
+/// in [`ExecuteOutput::files`].
+pub fn execute_in_memory_captured(
+    args: &str,
+    blocks: &HashMap<String, crate::HexFile>,
+) -> Result<ExecuteOutput, CliError> {
+    let writer = CaptureProvider::default();
+    let args = parse_with_blocks(args, blocks)?;
+    let mut output = args.execute_with_blocks(blocks, &writer, &Diagnostics::new(args.silent))?;
+    (output.files, output.outputs) = writer.into_parts();
+    Ok(output)
+}
+
+fn parse_with_blocks(
+    args: &str,
+    blocks: &HashMap<String, crate::HexFile>,
+) -> Result<Args, CliError> {
+    Ok(Args::parse_from_str_with(args, |arg| {
         let path = Path::new(arg);
         arg.starts_with('/') && path.is_absolute() && (blocks.contains_key(arg) || path.exists())
-    })?;
-    parsed.execute_with_blocks(blocks)
+    })?)
 }
@@ -1,13 +1,19 @@
 use std::path::PathBuf;
 
-use crate::Range;
+use crate::{Range, RangeSpec};
 
 use super::parse_util::{
-    parse_checksum, parse_data_processing_params, parse_dspic_op, parse_hex_ascii_params,
-    parse_hex_bytes, parse_hexview_ranges, parse_import_param, parse_merge_params, parse_number,
-    parse_output_params, parse_remap, parse_signature_verify_params, split_option, strip_quotes,
+    parse_bank, parse_binary_params, parse_checksum, parse_data_processing_params, parse_define,
+    parse_dspic_ghost_op, parse_dspic_op, parse_external_signer_params, parse_fill_pattern,
+    parse_ford_header, parse_hex_ascii_params, parse_hexview_range_specs, parse_hexview_ranges,
+    parse_import_param, parse_label, parse_merge_params, parse_number, parse_output_params,
+    parse_page_map, parse_remap, parse_signature_verify_params, parse_u64, parse_write_date,
+    parse_write_string, split_option, strip_quotes,
+};
+use super::types::{
+    Args, ChecksumParams, InputFormat, MergeParam, OutputFormat, OutputSpec, PageMapParams,
+    ParseArgError,
 };
-use super::types::{Args, MergeParam, OutputFormat, ParseArgError};
 
 type ValueParser = fn(&mut Args, &str, &str) -> Result<bool, ParseArgError>;
 
@@ -17,6 +23,11 @@ fn extend_ranges(target: &mut Vec<Range>, value: &str) -> Result<(), ParseArgErr
     Ok(())
 }
 
+fn extend_range_specs(target: &mut Vec<RangeSpec>, value: &str) -> Result<(), ParseArgError> {
+    target.extend(parse_hexview_range_specs(value)?);
+    Ok(())
+}
+
 fn extend_merges(target: &mut Vec<MergeParam>, value: &str) -> Result<(), ParseArgError> {
     let params = parse_merge_params(value)?;
     target.extend(params);
@@ -44,10 +55,70 @@ fn parse_simple_flag(args: &mut Args, opt_upper: &str) -> bool {
             args.silent = true;
             true
         }
+        "WERROR" => {
+            args.werror = true;
+            true
+        }
         "V" => {
             args.write_version = true;
             true
         }
+        "VERBOSE" => {
+            args.verbose = args.verbose.max(1);
+            true
+        }
+        "VV" => {
+            args.verbose = 2;
+            true
+        }
+        "ECHOARGS" => {
+            args.echo_args = true;
+            true
+        }
+        "INFO" => {
+            args.info = true;
+            true
+        }
+        "?" | "HELP" => {
+            args.help = Some(String::new());
+            true
+        }
+        "ELFVMA" => {
+            args.elf_use_vma = true;
+            true
+        }
+        "LENIENT" => {
+            args.lenient = true;
+            true
+        }
+        "MMAP" => {
+            args.mmap_input = true;
+            true
+        }
+        "INPLACE" | "-IN-PLACE" => {
+            args.derive_output_path = true;
+            true
+        }
+        "MKDIR" => {
+            args.create_output_dirs = true;
+            true
+        }
+        "CRLF" => {
+            args.line_ending = crate::LineEnding::CrLf;
+            true
+        }
+        "LF" => {
+            args.line_ending = crate::LineEnding::Lf;
+            true
+        }
+        "LOWER" => {
+            args.lowercase_hex = true;
+            true
+        }
+        "ALIGNREC" => {
+            args.align_records = true;
+            true
+        }
         "FA" => {
             args.fill_all = true;
             true
@@ -61,15 +132,15 @@ fn parse_simple_flag(args: &mut Args, opt_upper: &str) -> bool {
             true
         }
         "S08" | "S08MAP" => {
-            args.s08_map = true;
+            args.s08_map = Some(PageMapParams::Standard);
             true
         }
         "S12MAP" => {
-            args.s12_map = true;
+            args.s12_map = Some(PageMapParams::Standard);
             true
         }
         "S12XMAP" => {
-            args.s12x_map = true;
+            args.s12x_map = Some(PageMapParams::Standard);
             true
         }
         "AL" => {
@@ -98,20 +169,39 @@ fn parse_import_option(
             args.import_hex_ascii = Some(parse_import_param(value)?);
             Ok(true)
         }
+        "IT" => {
+            let format = InputFormat::from_name(strip_quotes(value))
+                .ok_or_else(|| ParseArgError::InvalidOption(format!("/IT:{value}")))?;
+            args.input_format = Some(format);
+            Ok(true)
+        }
         _ => Ok(false),
     }
 }
 
 fn parse_path_option(args: &mut Args, key_upper: &str, value: &str) -> Result<bool, ParseArgError> {
     match key_upper {
-        "E" => {
+        "E" | "E+" => {
             args.error_log = Some(PathBuf::from(strip_quotes(value)));
+            args.error_log_append = key_upper == "E+";
             Ok(true)
         }
         "L" => {
             args.log_file = Some(PathBuf::from(strip_quotes(value)));
             Ok(true)
         }
+        "MAPTAB" => {
+            args.map_table = Some(PathBuf::from(strip_quotes(value)));
+            Ok(true)
+        }
+        "PIPE" => {
+            args.pipeline_file = Some(PathBuf::from(strip_quotes(value)));
+            Ok(true)
+        }
+        "AEMAP" => {
+            args.erase_sector_map = Some(PathBuf::from(strip_quotes(value)));
+            Ok(true)
+        }
         "P" => {
             args.ini_file = Some(PathBuf::from(strip_quotes(value)));
             Ok(true)
@@ -120,6 +210,22 @@ fn parse_path_option(args: &mut Args, key_upper: &str, value: &str) -> Result<bo
             args.postbuild = Some(PathBuf::from(strip_quotes(value)));
             Ok(true)
         }
+        "LOGOUT" => {
+            args.log_output = Some(PathBuf::from(strip_quotes(value)));
+            Ok(true)
+        }
+        "CMP" => {
+            args.compare_file = Some(PathBuf::from(strip_quotes(value)));
+            Ok(true)
+        }
+        "MAPFILE" => {
+            args.map_file = Some(PathBuf::from(strip_quotes(value)));
+            Ok(true)
+        }
+        "HELP" => {
+            args.help = Some(strip_quotes(value).to_string());
+            Ok(true)
+        }
         _ => Ok(false),
     }
 }
@@ -131,15 +237,15 @@ fn parse_range_option(
 ) -> Result<bool, ParseArgError> {
     match key_upper {
         "AR" => {
-            extend_ranges(&mut args.address_range, value)?;
+            extend_range_specs(&mut args.address_range, value)?;
             Ok(true)
         }
         "CR" => {
-            extend_ranges(&mut args.cut_ranges, value)?;
+            extend_range_specs(&mut args.cut_ranges, value)?;
             Ok(true)
         }
         "FR" => {
-            extend_ranges(&mut args.fill_ranges, value)?;
+            extend_range_specs(&mut args.fill_ranges, value)?;
             Ok(true)
         }
         "CDSPG" => {
@@ -186,6 +292,10 @@ fn parse_numeric_option(
             args.temp_buffer_size_kb = Some(parse_number(value)?);
             Ok(true)
         }
+        "GZMAX" => {
+            args.gzip_limit_mb = Some(parse_number(value)?);
+            Ok(true)
+        }
         "AD" => {
             args.align_address = Some(parse_number(value)?);
             Ok(true)
@@ -213,6 +323,14 @@ fn parse_numeric_option(
             args.split_block_size = Some(parse_number(value)?);
             Ok(true)
         }
+        "SBA" => {
+            args.split_boundary = Some(parse_number(value)?);
+            Ok(true)
+        }
+        "ENTRY" => {
+            args.entry_point = Some(parse_number(value)?);
+            Ok(true)
+        }
         _ => Ok(false),
     }
 }
@@ -223,7 +341,7 @@ fn parse_checksum_option(
     value: &str,
 ) -> Result<bool, ParseArgError> {
     fn reject_mixed(args: &Args, is_multi: bool) -> Result<(), ParseArgError> {
-        if is_multi && args.checksum.is_some() {
+        if is_multi && !args.checksum.is_empty() {
             return Err(ParseArgError::InvalidOption(
                 "cannot combine /CS* with /CSM*".to_string(),
             ));
@@ -247,14 +365,25 @@ fn parse_checksum_option(
             .push(parse_checksum(algo, value, false)?);
         return Ok(true);
     }
+    if let Some(algo) = key_upper.strip_prefix("CSBR") {
+        reject_mixed(args, false)?;
+        args.checksum.push(parse_block_checksum(algo, value, true)?);
+        return Ok(true);
+    }
+    if let Some(algo) = key_upper.strip_prefix("CSB") {
+        reject_mixed(args, false)?;
+        args.checksum
+            .push(parse_block_checksum(algo, value, false)?);
+        return Ok(true);
+    }
     if let Some(algo) = key_upper.strip_prefix("CSR") {
         reject_mixed(args, false)?;
-        args.checksum = Some(parse_checksum(algo, value, true)?);
+        args.checksum.push(parse_checksum(algo, value, true)?);
         return Ok(true);
     }
     if let Some(algo) = key_upper.strip_prefix("CS") {
         reject_mixed(args, false)?;
-        args.checksum = Some(parse_checksum(algo, value, false)?);
+        args.checksum.push(parse_checksum(algo, value, false)?);
         return Ok(true);
     }
     Ok(false)
@@ -264,7 +393,7 @@ fn parse_bare_checksum_option(args: &mut Args, opt_upper: &str) -> Result<bool,
     if let Some(algo) = opt_upper.strip_prefix("CSMR")
         && algo.chars().all(|ch| ch.is_ascii_digit())
     {
-        if args.checksum.is_some() {
+        if !args.checksum.is_empty() {
             return Err(ParseArgError::InvalidOption(
                 "cannot combine /CS* with /CSM*".to_string(),
             ));
@@ -276,7 +405,7 @@ fn parse_bare_checksum_option(args: &mut Args, opt_upper: &str) -> Result<bool,
     if let Some(algo) = opt_upper.strip_prefix("CSM")
         && algo.chars().all(|ch| ch.is_ascii_digit())
     {
-        if args.checksum.is_some() {
+        if !args.checksum.is_empty() {
             return Err(ParseArgError::InvalidOption(
                 "cannot combine /CS* with /CSM*".to_string(),
             ));
@@ -285,6 +414,21 @@ fn parse_bare_checksum_option(args: &mut Args, opt_upper: &str) -> Result<bool,
             .push(parse_checksum(algo, "@append", false)?);
         return Ok(true);
     }
+    if let Some((algo, little_endian)) = opt_upper
+        .strip_prefix("CSBR")
+        .map(|algo| (algo, true))
+        .or_else(|| opt_upper.strip_prefix("CSB").map(|algo| (algo, false)))
+        && algo.chars().all(|ch| ch.is_ascii_digit())
+    {
+        if !args.checksum_multi.is_empty() {
+            return Err(ParseArgError::InvalidOption(
+                "cannot combine /CS* with /CSM*".to_string(),
+            ));
+        }
+        args.checksum
+            .push(parse_block_checksum(algo, "@append", little_endian)?);
+        return Ok(true);
+    }
     if let Some(algo) = opt_upper.strip_prefix("CSR")
         && algo.chars().all(|ch| ch.is_ascii_digit())
     {
@@ -293,7 +437,7 @@ fn parse_bare_checksum_option(args: &mut Args, opt_upper: &str) -> Result<bool,
                 "cannot combine /CS* with /CSM*".to_string(),
             ));
         }
-        args.checksum = Some(parse_checksum(algo, "@append", true)?);
+        args.checksum.push(parse_checksum(algo, "@append", true)?);
         return Ok(true);
     }
     if let Some(algo) = opt_upper.strip_prefix("CS")
@@ -304,17 +448,39 @@ fn parse_bare_checksum_option(args: &mut Args, opt_upper: &str) -> Result<bool,
                 "cannot combine /CS* with /CSM*".to_string(),
             ));
         }
-        args.checksum = Some(parse_checksum(algo, "@append", false)?);
+        args.checksum.push(parse_checksum(algo, "@append", false)?);
         return Ok(true);
     }
     Ok(false)
 }
 
+fn parse_block_checksum(
+    algo: &str,
+    value: &str,
+    little_endian: bool,
+) -> Result<ChecksumParams, ParseArgError> {
+    let mut params = parse_checksum(algo, value, little_endian)?;
+    params.per_block = true;
+    Ok(params)
+}
+
 fn parse_data_processing_option(
     args: &mut Args,
     key_upper: &str,
     value: &str,
 ) -> Result<bool, ParseArgError> {
+    if let Some(len_str) = key_upper.strip_prefix("DPEXT") {
+        let signature_len = if len_str.is_empty() {
+            None
+        } else {
+            match len_str.parse::<usize>() {
+                Ok(len) if len > 0 => Some(len),
+                _ => return Err(ParseArgError::InvalidNumber(len_str.to_string())),
+            }
+        };
+        args.data_processing = Some(parse_external_signer_params(signature_len, value)?);
+        return Ok(true);
+    }
     if let Some(method_str) = key_upper.strip_prefix("DP") {
         let method = method_str
             .parse::<u8>()
@@ -358,6 +524,12 @@ fn parse_dspic_option(
             }
             Ok(true)
         }
+        "CDSPI" => {
+            for part in value.split(':').filter(|p| !p.is_empty()) {
+                args.dspic_insert_ghost.push(parse_dspic_ghost_op(part)?);
+            }
+            Ok(true)
+        }
         _ => Ok(false),
     }
 }
@@ -368,13 +540,79 @@ fn parse_value_option(
     value: &str,
 ) -> Result<bool, ParseArgError> {
     match key_upper {
+        "FP" if value.trim().eq_ignore_ascii_case("RANDOM") => {
+            args.fill_pattern = crate::FillPattern::default();
+            args.fill_pattern_file = None;
+            args.fill_pattern_set = false;
+            Ok(true)
+        }
         "FP" => {
-            args.fill_pattern = parse_hex_bytes(value)?;
+            match value.trim().strip_prefix('@') {
+                Some(path) => {
+                    args.fill_pattern = crate::FillPattern::default();
+                    args.fill_pattern_file = Some(PathBuf::from(strip_quotes(path)));
+                }
+                None => {
+                    args.fill_pattern = parse_fill_pattern(value)?;
+                    args.fill_pattern_file = None;
+                }
+            }
             args.fill_pattern_set = true;
             Ok(true)
         }
+        "WS" => {
+            args.write_strings.push(parse_write_string(value)?);
+            Ok(true)
+        }
+        "WDATE" => {
+            args.write_dates.push(parse_write_date(value)?);
+            Ok(true)
+        }
+        "DATE" => {
+            let date = value
+                .parse()
+                .map_err(|e| ParseArgError::InvalidOption(format!("/DATE: {e}")))?;
+            args.date = Some(date);
+            Ok(true)
+        }
+        "FRSEED" => {
+            args.fill_seed = Some(parse_u64(value)?);
+            Ok(true)
+        }
         "REMAP" => {
-            args.remap = Some(parse_remap(value)?);
+            args.remap.push(parse_remap(value)?);
+            Ok(true)
+        }
+        "S08" | "S08MAP" => {
+            args.s08_map = Some(parse_page_map(value)?);
+            Ok(true)
+        }
+        "S12MAP" => {
+            args.s12_map = Some(parse_page_map(value)?);
+            Ok(true)
+        }
+        "S12XMAP" => {
+            args.s12x_map = Some(parse_page_map(value)?);
+            Ok(true)
+        }
+        "BANK" => {
+            args.bank = Some(parse_bank(value)?);
+            Ok(true)
+        }
+        "LABEL" => {
+            args.labels.push(parse_label(value)?);
+            Ok(true)
+        }
+        "FORD" => {
+            args.ford_header.push(parse_ford_header(value)?);
+            Ok(true)
+        }
+        "DEF" => {
+            args.defines.push(parse_define(value)?);
+            Ok(true)
+        }
+        "UNBANK" => {
+            args.unbank = Some(parse_bank(value)?);
             Ok(true)
         }
         _ => Ok(false),
@@ -389,14 +627,56 @@ fn parse_output_option_value(
     parse_output_option(args, key_upper, Some(value))
 }
 
-fn set_output_format(args: &mut Args, format: OutputFormat) -> Result<(), ParseArgError> {
-    if args.output_format.is_some() {
-        return Err(ParseArgError::DuplicateOutputFormat);
+/// Give the pending `-o` its format, or start a new output.
+pub(super) fn set_output_format(
+    args: &mut Args,
+    format: OutputFormat,
+    bytes_per_line: Option<u8>,
+) -> Result<(), ParseArgError> {
+    match args.outputs.last_mut() {
+        Some(output) if output.format.is_none() => {
+            output.format = Some(format);
+            output.bytes_per_line = bytes_per_line;
+        }
+        // Two formats would share the next -o.
+        Some(output) if output.file.is_none() => {
+            return Err(ParseArgError::DuplicateOutputFormat);
+        }
+        _ => args.outputs.push(OutputSpec {
+            format: Some(format),
+            file: None,
+            bytes_per_line,
+        }),
     }
-    args.output_format = Some(format);
     Ok(())
 }
 
+/// Give the last /Xx its `-o` file, or start a new output.
+pub(super) fn set_output_file(args: &mut Args, file: PathBuf) {
+    match args.outputs.last_mut() {
+        Some(output) if output.file.is_none() => output.file = Some(file),
+        _ => args.outputs.push(OutputSpec {
+            format: None,
+            file: Some(file),
+            bytes_per_line: None,
+        }),
+    }
+}
+
+/// Split a trailing `hdr=name` off the /XS parameters.
+fn split_srec_header(value: &str) -> (&str, Option<String>) {
+    let (params, last) = match value.rsplit_once(':') {
+        Some((params, last)) => (params, last),
+        None => ("", value),
+    };
+    match last.get(..4) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("hdr=") => {
+            (params, Some(last[4..].to_string()))
+        }
+        _ => (value, None),
+    }
+}
+
 fn parse_output_option(
     args: &mut Args,
     key_upper: &str,
@@ -404,117 +684,172 @@ fn parse_output_option(
 ) -> Result<bool, ParseArgError> {
     match key_upper {
         "XI" => {
-            if let Some(value) = value {
-                let (len, rec_type) = parse_output_params(value)?;
-                if rec_type.is_some() && len.is_none() {
-                    return Err(ParseArgError::InvalidOption(
-                        "record type requires reclinelen".to_string(),
-                    ));
-                }
-                args.bytes_per_line = len;
-                set_output_format(
-                    args,
-                    OutputFormat::IntelHex {
-                        record_type: rec_type,
-                    },
-                )?;
-            } else {
-                set_output_format(args, OutputFormat::IntelHex { record_type: None })?;
+            let (len, rec_type) = parse_output_params(value.unwrap_or(""))?;
+            if rec_type.is_some() && len.is_none() {
+                return Err(ParseArgError::InvalidOption(
+                    "record type requires reclinelen".to_string(),
+                ));
             }
+            set_output_format(
+                args,
+                OutputFormat::IntelHex {
+                    record_type: rec_type,
+                },
+                len,
+            )?;
+            Ok(true)
+        }
+        "XI2" => {
+            let (len, rec_type) = parse_output_params(value.unwrap_or(""))?;
+            if rec_type.is_some() && len.is_none() {
+                return Err(ParseArgError::InvalidOption(
+                    "record type requires reclinelen".to_string(),
+                ));
+            }
+            set_output_format(
+                args,
+                OutputFormat::IntelHex16 {
+                    record_type: rec_type,
+                },
+                len,
+            )?;
             Ok(true)
         }
         "XS" => {
-            if let Some(value) = value {
-                let (len, rec_type) = parse_output_params(value)?;
-                if rec_type.is_some() && len.is_none() {
-                    return Err(ParseArgError::InvalidOption(
-                        "record type requires reclinelen".to_string(),
-                    ));
-                }
-                args.bytes_per_line = len;
-                set_output_format(
-                    args,
-                    OutputFormat::SRecord {
-                        record_type: rec_type,
-                    },
-                )?;
-            } else {
-                set_output_format(args, OutputFormat::SRecord { record_type: None })?;
+            let (params, header) = split_srec_header(value.unwrap_or(""));
+            let (len, rec_type) = parse_output_params(params)?;
+            if rec_type.is_some() && len.is_none() {
+                return Err(ParseArgError::InvalidOption(
+                    "record type requires reclinelen".to_string(),
+                ));
             }
+            set_output_format(
+                args,
+                OutputFormat::SRecord {
+                    record_type: rec_type,
+                    header,
+                },
+                len,
+            )?;
+            Ok(true)
+        }
+        "XN" if value.is_some_and(|v| v.trim().eq_ignore_ascii_case("RAW")) => {
+            set_output_format(args, OutputFormat::BinaryRaw, None)?;
             Ok(true)
         }
         "XN" => {
-            set_output_format(args, OutputFormat::Binary)?;
+            let (start, end, fill) = parse_binary_params(value.unwrap_or(""))?;
+            set_output_format(args, OutputFormat::Binary { start, end, fill }, None)?;
+            Ok(true)
+        }
+        "XJ" => {
+            set_output_format(args, OutputFormat::Json, None)?;
+            Ok(true)
+        }
+        "XCSV" => {
+            set_output_format(args, OutputFormat::Csv, None)?;
+            Ok(true)
+        }
+        "XD" => {
+            let (len, rec_type) = parse_output_params(value.unwrap_or(""))?;
+            if rec_type.is_some() {
+                return Err(ParseArgError::InvalidOption(
+                    "/XD only takes a line length".to_string(),
+                ));
+            }
+            set_output_format(args, OutputFormat::HexDump, len)?;
             Ok(true)
         }
         "XA" => {
-            let (line_length, separator) = if let Some(value) = value {
+            let (line_length, separator, address_prefix) = if let Some(value) = value {
                 parse_hex_ascii_params(value)?
             } else {
-                (None, None)
+                (None, None, None)
             };
             set_output_format(
                 args,
                 OutputFormat::HexAscii {
                     line_length,
                     separator,
+                    address_prefix,
                 },
+                None,
             )?;
             Ok(true)
         }
         "XC" => {
-            set_output_format(args, OutputFormat::CCode)?;
+            set_output_format(args, OutputFormat::CCode, None)?;
             Ok(true)
         }
         "XF" => {
-            set_output_format(args, OutputFormat::FordIntelHex)?;
+            let relaxed = match value.map(str::trim) {
+                None => false,
+                Some(v) if v.eq_ignore_ascii_case("RELAXED") => true,
+                Some(v) => {
+                    return Err(ParseArgError::InvalidOption(format!(
+                        "/XF:{v}: expected RELAXED"
+                    )));
+                }
+            };
+            set_output_format(args, OutputFormat::FordIntelHex { relaxed }, None)?;
             Ok(true)
         }
         "XG" => {
             let addr = parse_optional_addr(value)?;
-            set_output_format(args, OutputFormat::GmHeader { addr })?;
+            set_output_format(args, OutputFormat::GmHeader { addr }, None)?;
             Ok(true)
         }
         "XGC" => {
             let addr = parse_optional_addr(value)?;
-            set_output_format(args, OutputFormat::GmHeaderOs { addr })?;
+            set_output_format(args, OutputFormat::GmHeaderOs { addr }, None)?;
             Ok(true)
         }
         "XGCC" => {
             let addr = parse_optional_addr(value)?;
-            set_output_format(args, OutputFormat::GmHeaderCal { addr })?;
+            set_output_format(args, OutputFormat::GmHeaderCal { addr }, None)?;
             Ok(true)
         }
         "XGAC" => {
-            set_output_format(args, OutputFormat::Gac)?;
+            set_output_format(args, OutputFormat::Gac, None)?;
             Ok(true)
         }
         "XGACSWIL" => {
-            set_output_format(args, OutputFormat::GacSwil)?;
+            set_output_format(args, OutputFormat::GacSwil, None)?;
             Ok(true)
         }
         "XK" => {
-            set_output_format(args, OutputFormat::FlashKernel)?;
+            set_output_format(args, OutputFormat::FlashKernel, None)?;
             Ok(true)
         }
         "XP" => {
-            set_output_format(args, OutputFormat::Porsche)?;
+            set_output_format(args, OutputFormat::Porsche, None)?;
             Ok(true)
         }
         "XSB" => {
-            set_output_format(args, OutputFormat::SeparateBinary)?;
+            let template = value
+                .map(|v| {
+                    strip_quotes(v.trim())
+                        .parse()
+                        .map_err(|e| ParseArgError::InvalidTemplate(format!("/XSB:{v}: {e}")))
+                })
+                .transpose()?;
+            set_output_format(args, OutputFormat::SeparateBinary { template }, None)?;
             Ok(true)
         }
         "XV" => {
-            set_output_format(args, OutputFormat::Vag)?;
+            set_output_format(args, OutputFormat::Vag, None)?;
             Ok(true)
         }
         "XVBF" => {
-            set_output_format(args, OutputFormat::Vbf)?;
+            set_output_format(args, OutputFormat::Vbf, None)?;
             Ok(true)
         }
         "XB" => {
-            set_output_format(args, OutputFormat::FiatBin)?;
+            set_output_format(args, OutputFormat::FiatBin, None)?;
+            Ok(true)
+        }
+        "XR" => {
+            set_output_format(args, OutputFormat::RustCode, None)?;
             Ok(true)
         }
         _ => Ok(false),
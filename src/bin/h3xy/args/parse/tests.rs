@@ -1,4 +1,4 @@
-use super::super::types::ChecksumTarget;
+use super::super::types::{ChecksumFormat, ChecksumTarget, WriteStringParams};
 use super::*;
 
 #[test]
@@ -8,6 +8,110 @@ fn test_output_record_type_requires_length() {
     assert!(result.is_err());
 }
 
+#[test]
+fn test_parse_xs_header_parameter() {
+    let mut args = Args::default();
+    parse_option(&mut args, "XS:0x20:2:HDR=boot").unwrap();
+    assert_eq!(args.outputs[0].bytes_per_line, Some(0x20));
+    assert_eq!(
+        args.outputs[0].format,
+        Some(OutputFormat::SRecord {
+            record_type: Some(2),
+            header: Some("boot".to_string()),
+        })
+    );
+
+    let mut args = Args::default();
+    parse_option(&mut args, "XS:hdr=app").unwrap();
+    assert_eq!(args.outputs[0].bytes_per_line, None);
+    assert_eq!(
+        args.outputs[0].format,
+        Some(OutputFormat::SRecord {
+            record_type: None,
+            header: Some("app".to_string()),
+        })
+    );
+}
+
+#[test]
+fn test_parse_xsb_template_fails_at_parse_time() {
+    let mut args = Args::default();
+    parse_option(&mut args, "XSB:\"{stem}_{index:02}_{addr:08X}.{ext}\"").unwrap();
+    let Some(OutputFormat::SeparateBinary {
+        template: Some(template),
+    }) = &args.outputs[0].format
+    else {
+        panic!("{:?}", args.outputs[0].format);
+    };
+    assert_eq!(template.to_string(), "{stem}_{index:02}_{addr:08X}.{ext}");
+
+    let err = parse_option(&mut Args::default(), "XSB:{stem}_{addr:8q}").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid file name template: /XSB:{stem}_{addr:8q}: invalid format spec '8q' for \
+         {addr} at column 8"
+    );
+}
+
+#[test]
+fn test_parse_ford_header_overrides() {
+    let mut args = Args::default();
+    parse_option(&mut args, "XF:relaxed").unwrap();
+    parse_option(&mut args, "FORD:Module_ID=0x7E0").unwrap();
+    parse_option(&mut args, "FORD:release date = 01/02/2003").unwrap();
+    assert_eq!(
+        args.outputs[0].format,
+        Some(OutputFormat::FordIntelHex { relaxed: true })
+    );
+    assert_eq!(
+        args.ford_header,
+        [
+            ("module id".to_string(), "0x7E0".to_string()),
+            ("release date".to_string(), "01/02/2003".to_string()),
+        ]
+    );
+
+    assert!(parse_option(&mut args, "FORD:moduleid=1").is_err());
+    assert!(parse_option(&mut args, "FORD:module id").is_err());
+    assert!(parse_option(&mut Args::default(), "XF:LAX").is_err());
+}
+
+#[test]
+fn test_parse_defines() {
+    let mut args = Args::default();
+    parse_option(&mut args, "DEF:BUILD_ID=0042").unwrap();
+    parse_option(&mut args, "DEF:\"NOTE=a=b c\"").unwrap();
+    assert_eq!(
+        args.defines,
+        [
+            ("BUILD_ID".to_string(), "0042".to_string()),
+            ("NOTE".to_string(), "a=b c".to_string()),
+        ]
+    );
+
+    assert!(parse_option(&mut args, "DEF:BUILD_ID").is_err());
+    assert!(parse_option(&mut args, "DEF:1ST=x").is_err());
+    assert!(parse_option(&mut args, "DEF:A-B=x").is_err());
+}
+
+#[test]
+fn test_parse_verbose_levels() {
+    let args = Args::parse_from(vec!["in.hex".into(), "-vv".into(), "/VERBOSE".into()]).unwrap();
+    assert_eq!(args.verbose, 2);
+    let args = Args::parse_from(vec!["in.hex".into(), "/verbose".into()]).unwrap();
+    assert_eq!(args.verbose, 1);
+}
+
+#[test]
+fn test_parse_input_format_override() {
+    let mut args = Args::default();
+    parse_option(&mut args, "IT:bin").unwrap();
+    assert_eq!(args.input_format, Some(InputFormat::Binary));
+    parse_option(&mut args, "IT=I16").unwrap();
+    assert_eq!(args.input_format, Some(InputFormat::IntelHex16));
+    assert!(parse_option(&mut args, "IT:ELF").is_err());
+}
+
 #[test]
 fn test_parse_ad_no_separator_hex() {
     let mut args = Args::default();
@@ -26,7 +130,7 @@ fn test_parse_af_no_separator_hex() {
 fn test_parse_checksum_without_target_defaults_append() {
     let mut args = Args::default();
     parse_option(&mut args, "CS0").unwrap();
-    let checksum = args.checksum.expect("checksum parsed");
+    let checksum = args.checksum.first().expect("checksum parsed");
     assert_eq!(checksum.algorithm, 0);
     assert!(matches!(checksum.target, ChecksumTarget::Append));
 }
@@ -35,12 +139,67 @@ fn test_parse_checksum_without_target_defaults_append() {
 fn test_parse_checksum_reverse_without_target_defaults_append() {
     let mut args = Args::default();
     parse_option(&mut args, "CSR9").unwrap();
-    let checksum = args.checksum.expect("checksum parsed");
+    let checksum = args.checksum.first().expect("checksum parsed");
     assert_eq!(checksum.algorithm, 9);
     assert!(checksum.little_endian);
     assert!(matches!(checksum.target, ChecksumTarget::Append));
 }
 
+#[test]
+fn test_parse_checksum_repeated_keeps_order() {
+    let mut args = Args::default();
+    parse_option(&mut args, "CS9:@0x7FF0;0x0-0x7FEF").unwrap();
+    parse_option(&mut args, "CS0:@append").unwrap();
+    assert_eq!(args.checksum.len(), 2);
+    assert_eq!(args.checksum[0].algorithm, 9);
+    assert_eq!(args.checksum[0].target, ChecksumTarget::Address(0x7FF0));
+    assert_eq!(args.checksum[1].algorithm, 0);
+}
+
+#[test]
+fn test_parse_checksum_symbol_target() {
+    let mut args = Args::default();
+    parse_option(&mut args, "MAPFILE:\"build/app.map\"").unwrap();
+    parse_option(&mut args, "CS9:@sym:__app_crc;0x0-0x3FFF").unwrap();
+    assert_eq!(args.map_file, Some(PathBuf::from("build/app.map")));
+    assert_eq!(
+        args.checksum[0].target,
+        ChecksumTarget::Symbol("__app_crc".to_string())
+    );
+    assert!(parse_option(&mut args, "CS9:@SYM:").is_err());
+}
+
+#[test]
+fn test_parse_checksum_output_format_and_stdout() {
+    let mut args = Args::default();
+    parse_option(&mut args, "CS9:@stdout;0x0-0xFF;fmt=hex").unwrap();
+    parse_option(&mut args, "CSR0:crc.h;FMT=C").unwrap();
+    assert_eq!(args.checksum[0].target, ChecksumTarget::Stdout);
+    assert_eq!(args.checksum[0].format, ChecksumFormat::Hex);
+    assert_eq!(
+        args.checksum[1].target,
+        ChecksumTarget::File(PathBuf::from("crc.h"))
+    );
+    assert_eq!(args.checksum[1].format, ChecksumFormat::C);
+    assert!(parse_option(&mut args, "CS9:@append;FMT=HEX").is_err());
+    assert!(parse_option(&mut args, "CS9:crc.txt;FMT=XML").is_err());
+}
+
+#[test]
+fn test_parse_block_checksum_options() {
+    let mut args = Args::default();
+    parse_option(&mut args, "CSB9").unwrap();
+    parse_option(&mut args, "CSBR2:@end").unwrap();
+    assert_eq!(args.checksum.len(), 2);
+    assert!(args.checksum.iter().all(|cs| cs.per_block));
+    assert_eq!(args.checksum[0].algorithm, 9);
+    assert_eq!(args.checksum[0].target, ChecksumTarget::Append);
+    assert!(!args.checksum[0].little_endian);
+    assert_eq!(args.checksum[1].target, ChecksumTarget::OverwriteEnd);
+    assert!(args.checksum[1].little_endian);
+    assert!(parse_option(&mut args, "CSM0").is_err());
+}
+
 #[test]
 fn test_parse_dp_signature_subset_option() {
     let mut args = Args::default();
@@ -51,11 +210,74 @@ fn test_parse_dp_signature_subset_option() {
     assert_eq!(dp.key_info, "key.pem");
 }
 
+#[test]
+fn test_parse_dp_sv_signed_range() {
+    let mut args = Args::default();
+    parse_option(&mut args, "DP50:key.pem;;0x1000-0x1FFF/0x1F00-0x1F3F").unwrap();
+    parse_option(
+        &mut args,
+        "SV12:pub.pem!sig.bin;0x1000-0x1FFF/0x1F00-0x1F3F",
+    )
+    .unwrap();
+    let dp = args.data_processing.expect("data processing parsed");
+    let sv = args
+        .signature_verify
+        .expect("signature verification parsed");
+    assert_eq!(dp.output_file, None);
+    assert_eq!(sv.signature_info, "sig.bin");
+    for (range, exclude) in [(dp.range, dp.exclude), (sv.range, sv.exclude)] {
+        assert_eq!(range, Some(Range::from_start_end(0x1000, 0x1FFF).unwrap()));
+        assert_eq!(
+            exclude,
+            vec![Range::from_start_end(0x1F00, 0x1F3F).unwrap()]
+        );
+    }
+}
+
+#[test]
+fn test_parse_dp_external_signer() {
+    let mut args = Args::default();
+    parse_option(
+        &mut args,
+        "DPEXT256:@append:\"hsm-sign --in {payload}; mv out {signature}\";sig.bin;0x1000-0x1FFF",
+    )
+    .unwrap();
+    let dp = args.data_processing.expect("data processing parsed");
+    assert_eq!(
+        dp.external_command.as_deref(),
+        Some("hsm-sign --in {payload}; mv out {signature}")
+    );
+    assert!(matches!(dp.placement, Some(ChecksumTarget::Append)));
+    assert_eq!(dp.signature_len, Some(256));
+    assert_eq!(dp.output_file, Some(PathBuf::from("sig.bin")));
+    assert_eq!(
+        dp.range,
+        Some(Range::from_start_end(0x1000, 0x1FFF).unwrap())
+    );
+
+    let mut args = Args::default();
+    parse_option(&mut args, "DPEXT:sign {payload} {signature}").unwrap();
+    let dp = args.data_processing.expect("data processing parsed");
+    assert_eq!(
+        dp.external_command.as_deref(),
+        Some("sign {payload} {signature}")
+    );
+    assert_eq!(dp.placement, None);
+    assert_eq!(dp.signature_len, None);
+
+    assert!(parse_option(&mut Args::default(), "DPEXT:").is_err());
+    assert!(parse_option(&mut Args::default(), "DPEXT:@append:sign").is_err());
+    assert!(parse_option(&mut Args::default(), "DPEXT0:sign").is_err());
+    assert!(parse_option(&mut Args::default(), "DPEXT:\"sign {payload}").is_err());
+}
+
 #[test]
 fn test_parse_sv_option() {
     let mut args = Args::default();
     parse_option(&mut args, "SV4:pub.pem!sig.bin").unwrap();
-    let sv = args.signature_verify.expect("signature verification parsed");
+    let sv = args
+        .signature_verify
+        .expect("signature verification parsed");
     assert_eq!(sv.method, 4);
     assert_eq!(sv.key_info, "pub.pem");
     assert_eq!(sv.signature_info, "sig.bin");
@@ -103,3 +325,143 @@ fn test_parse_checksum_mixed_multi_then_legacy_rejected() {
     parse_option(&mut args, "CSM0:@append").unwrap();
     assert!(parse_option(&mut args, "CS0:@append").is_err());
 }
+
+#[test]
+fn test_parse_output_pairs_with_its_file() {
+    let args = Args::parse_from_str("in.hex /XI:0x10 -o a.hex -o b.s19 /XS /XN -o c.bin").unwrap();
+    let outputs: Vec<_> = args
+        .outputs
+        .iter()
+        .map(|o| (o.format.clone(), o.file.clone(), o.bytes_per_line))
+        .collect();
+    assert_eq!(
+        outputs,
+        vec![
+            (
+                Some(OutputFormat::IntelHex { record_type: None }),
+                Some(PathBuf::from("a.hex")),
+                Some(0x10)
+            ),
+            (
+                Some(OutputFormat::SRecord {
+                    record_type: None,
+                    header: None
+                }),
+                Some(PathBuf::from("b.s19")),
+                None
+            ),
+            (
+                Some(OutputFormat::Binary {
+                    start: None,
+                    end: None,
+                    fill: None
+                }),
+                Some(PathBuf::from("c.bin")),
+                None
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_two_formats_for_one_file_rejected() {
+    let err = Args::parse_from_str("in.hex /XI /XS -o out.hex").unwrap_err();
+    assert!(matches!(err, ParseArgError::DuplicateOutputFormat));
+    let args = Args::parse_from_str("in.hex -o out.hex /XI /XS").unwrap();
+    assert_eq!(args.outputs.len(), 2);
+}
+
+#[test]
+fn test_parse_random_fill_seed() {
+    let args = Args::parse_from_str("in.hex /FR:0x0,0x10 /FP:A5 /FP:random /FRSEED:0x10").unwrap();
+    assert!(!args.fill_pattern_set);
+    assert_eq!(args.fill_seed, Some(0x10));
+    let args = Args::parse_from_str("in.hex /FRSEED:18446744073709551615").unwrap();
+    assert_eq!(args.fill_seed, Some(u64::MAX));
+    assert!(Args::parse_from_str("in.hex /FRSEED:-1").is_err());
+}
+
+#[test]
+fn test_parse_fill_pattern_modes() {
+    let args = Args::parse_from_str("in.hex /FP:INC").unwrap();
+    assert_eq!(
+        args.fill_pattern,
+        crate::FillPattern::Increment { start: 0, step: 1 }
+    );
+    let args = Args::parse_from_str("in.hex /FP:inc:0x80:2").unwrap();
+    assert_eq!(
+        args.fill_pattern,
+        crate::FillPattern::Increment {
+            start: 0x80,
+            step: 2
+        }
+    );
+    let args = Args::parse_from_str("in.hex /FP:@\"fill.bin\"").unwrap();
+    assert!(args.fill_pattern_set);
+    assert_eq!(args.fill_pattern_file, Some(PathBuf::from("fill.bin")));
+    assert!(Args::parse_from_str("in.hex /FP:INC:0x100").is_err());
+    assert!(Args::parse_from_str("in.hex /FP:INC:0:1:2").is_err());
+}
+
+#[test]
+fn test_parse_stamps() {
+    let args = Args::parse_from_str(
+        "in.hex /WS:0x100;v1.2;nul;WIDTH=8;FILL=0x20 '/WS:0x200;\"a;b\"' \
+         /WDATE:0x300;BCD:YYMMDD /DATE:2026-03-07",
+    )
+    .unwrap();
+    assert_eq!(
+        args.write_strings,
+        vec![
+            WriteStringParams {
+                address: 0x100,
+                text: "v1.2".to_string(),
+                pad: crate::AsciiPad {
+                    nul_terminate: true,
+                    width: Some(8),
+                    fill: 0x20,
+                },
+            },
+            WriteStringParams {
+                address: 0x200,
+                text: "a;b".to_string(),
+                pad: crate::AsciiPad::default(),
+            },
+        ]
+    );
+    assert_eq!(args.write_dates[0].address, 0x300);
+    assert_eq!(args.write_dates[0].format.to_string(), "BCD:YYMMDD");
+    assert_eq!(args.date, Some("2026-03-07".parse().unwrap()));
+
+    assert!(Args::parse_from_str("in.hex /WS:0x100").is_err());
+    assert!(Args::parse_from_str("in.hex /WS:0x100;abc;PAD").is_err());
+    assert!(Args::parse_from_str("in.hex /WS:0x100;abc;FILL=2020").is_err());
+    assert!(Args::parse_from_str("in.hex /WDATE:0x100;BCD:YY-MM").is_err());
+    assert!(Args::parse_from_str("in.hex /DATE:2026-13-01").is_err());
+}
+
+#[test]
+fn test_parse_windows_drive_paths() {
+    let mut args = Args::default();
+    parse_option(&mut args, r#"MO:"C:\builds\cal.hex";0x1000"#).unwrap();
+    parse_option(&mut args, r"MT=D:\cal\a.hex:0x0-0xFF").unwrap();
+    parse_option(&mut args, r"L:C:\logs\cmds.log").unwrap();
+    parse_option(&mut args, r"E=C:\logs\err.log").unwrap();
+    parse_option(&mut args, r#"P:"C:\my settings\h3xy.ini""#).unwrap();
+    assert_eq!(
+        args.merge_opaque[0].file,
+        PathBuf::from(r"C:\builds\cal.hex")
+    );
+    assert_eq!(args.merge_opaque[0].offset, Some(0x1000));
+    assert_eq!(
+        args.merge_transparent[0].file,
+        PathBuf::from(r"D:\cal\a.hex")
+    );
+    assert!(args.merge_transparent[0].range.is_some());
+    assert_eq!(args.log_file, Some(PathBuf::from(r"C:\logs\cmds.log")));
+    assert_eq!(args.error_log, Some(PathBuf::from(r"C:\logs\err.log")));
+    assert_eq!(
+        args.ini_file,
+        Some(PathBuf::from(r"C:\my settings\h3xy.ini"))
+    );
+}
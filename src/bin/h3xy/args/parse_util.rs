@@ -1,30 +1,59 @@
 use std::path::PathBuf;
 
-use crate::Range;
+use crate::{FillPattern, Range, RangeSpec};
 
+use super::ini::is_variable_name;
+use super::io::{FORD_OPTIONAL_KEYS, FORD_REQUIRED_KEYS};
 use super::types::{
-    ChecksumParams, ChecksumTarget, DataProcessingParams, DspicOp, ForcedRange, ImportParam,
-    MergeParam, ParseArgError, RemapParams, SignatureVerifyParams,
+    BankParams, ChecksumFormat, ChecksumGapFill, ChecksumParams, ChecksumTarget,
+    DataProcessingParams, DspicGhostOp, DspicOp, ForcedRange, ImportParam, LabelParams, MergeParam,
+    PageMapParams, ParseArgError, RemapParams, SignatureVerifyParams, WriteDateParams,
+    WriteStringParams,
 };
 
+/// Split `KEY:value` or `KEY=value` at whichever separator comes first, so
+/// `/L=C:\logs\run.log` keeps its drive letter in the value.
 pub(super) fn split_option(opt: &str) -> Option<(&str, &str)> {
-    if let Some(pos) = opt.find(':') {
-        Some((&opt[..pos], &opt[pos + 1..]))
-    } else if let Some(pos) = opt.find('=') {
-        Some((&opt[..pos], &opt[pos + 1..]))
-    } else {
-        None
-    }
+    let pos = opt.find([':', '='])?;
+    Some((&opt[..pos], &opt[pos + 1..]))
 }
 
 pub(super) fn strip_quotes(s: &str) -> &str {
     s.trim_matches(|c| c == '"' || c == '\'')
 }
 
+/// Split a file path off the front of `s`, returning the path and the rest
+/// from the first `stops` character on. A quoted path runs to its closing
+/// quote; an unquoted one skips a `C:\` drive prefix before looking for
+/// `stops`.
+pub(super) fn split_path<'a>(
+    s: &'a str,
+    stops: &[char],
+) -> Result<(&'a str, &'a str), ParseArgError> {
+    if let Some(quote) = s.chars().next().filter(|c| *c == '"' || *c == '\'') {
+        let (path, rest) = s[1..]
+            .split_once(quote)
+            .ok_or_else(|| ParseArgError::InvalidOption(format!("unclosed quote: {s}")))?;
+        return Ok((path, rest));
+    }
+    let skip = if has_drive_prefix(s) { 2 } else { 0 };
+    let end = s[skip..].find(stops).map_or(s.len(), |pos| skip + pos);
+    Ok(s.split_at(end))
+}
+
+/// `C:\` or `C:/`.
+fn has_drive_prefix(s: &str) -> bool {
+    matches!(s.as_bytes(), [drive, b':', b'\\' | b'/', ..] if drive.is_ascii_alphabetic())
+}
+
 pub(super) fn parse_hexview_ranges(s: &str) -> Result<Vec<Range>, ParseArgError> {
     crate::parse_hexview_ranges(s).map_err(|e| ParseArgError::InvalidRange(e.to_string()))
 }
 
+pub(super) fn parse_hexview_range_specs(s: &str) -> Result<Vec<RangeSpec>, ParseArgError> {
+    crate::parse_hexview_range_specs(s).map_err(|e| ParseArgError::InvalidRange(e.to_string()))
+}
+
 pub(super) fn parse_hex_bytes(s: &str) -> Result<Vec<u8>, ParseArgError> {
     let s = s.trim();
     if !s.len().is_multiple_of(2) {
@@ -51,6 +80,9 @@ pub(super) fn parse_number(s: &str) -> Result<u32, ParseArgError> {
     if s.is_empty() {
         return Err(ParseArgError::InvalidNumber("empty".to_string()));
     }
+    if let Some(size) = parse_size_suffix(s)? {
+        return Ok(size);
+    }
 
     let (radix, digits) = if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
         (16, hex)
@@ -75,6 +107,126 @@ pub(super) fn parse_number(s: &str) -> Result<u32, ParseArgError> {
     u32::from_str_radix(&cleaned, radix).map_err(|e| ParseArgError::InvalidNumber(e.to_string()))
 }
 
+/// `64K` or `2M`: a decimal count of KiB or MiB. `None` without a K/M suffix.
+fn parse_size_suffix(s: &str) -> Result<Option<u32>, ParseArgError> {
+    let unit: u32 = match s.as_bytes().last() {
+        Some(b'k' | b'K') => 1024,
+        Some(b'm' | b'M') => 1024 * 1024,
+        _ => return Ok(None),
+    };
+    let count = &s[..s.len() - 1];
+    if count.is_empty() || !count.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ParseArgError::InvalidNumber(format!(
+            "{s}: K and M need a decimal count"
+        )));
+    }
+    count
+        .parse::<u32>()
+        .ok()
+        .and_then(|count| count.checked_mul(unit))
+        .map(Some)
+        .ok_or_else(|| ParseArgError::InvalidNumber(format!("{s} exceeds u32")))
+}
+
+/// /FP value: hex bytes, or `INC[:start[:step]]` for a byte ramp.
+pub(super) fn parse_fill_pattern(s: &str) -> Result<FillPattern, ParseArgError> {
+    let s = s.trim();
+    let mut parts = s.split(':');
+    if !parts.next().unwrap_or_default().eq_ignore_ascii_case("INC") {
+        return Ok(FillPattern::Bytes(parse_hex_bytes(s)?));
+    }
+    let mut byte = |default: u8| -> Result<u8, ParseArgError> {
+        match parts.next() {
+            Some(part) => u8::try_from(parse_number(part)?)
+                .map_err(|_| ParseArgError::InvalidNumber(format!("{part} exceeds 0xFF"))),
+            None => Ok(default),
+        }
+    };
+    let start = byte(0)?;
+    let step = byte(1)?;
+    if parts.next().is_some() {
+        return Err(ParseArgError::InvalidOption(format!("/FP:{s}")));
+    }
+    Ok(FillPattern::Increment { start, step })
+}
+
+/// /WS value: `addr;text[;NUL][;WIDTH=n][;FILL=xx]`. Quote the text to
+/// include `;`.
+pub(super) fn parse_write_string(value: &str) -> Result<WriteStringParams, ParseArgError> {
+    let (address, rest) = value
+        .split_once(';')
+        .ok_or_else(|| ParseArgError::MissingValue("/WS text".to_string()))?;
+    let address = parse_number(address)?;
+    let (text, options) = match rest.strip_prefix('"') {
+        Some(quoted) => {
+            let (text, tail) = quoted.split_once('"').ok_or_else(|| {
+                ParseArgError::InvalidOption(format!("/WS: unclosed quote: {rest}"))
+            })?;
+            if !tail.is_empty() && !tail.starts_with(';') {
+                return Err(ParseArgError::InvalidOption(format!("/WS: {value}")));
+            }
+            (text, tail.trim_start_matches(';'))
+        }
+        None => rest.split_once(';').unwrap_or((rest, "")),
+    };
+    if !text.is_ascii() {
+        return Err(ParseArgError::InvalidOption(format!(
+            "/WS: {text:?} is not ASCII"
+        )));
+    }
+
+    let mut pad = crate::AsciiPad::default();
+    for option in options.split(';').map(str::trim).filter(|o| !o.is_empty()) {
+        let upper = option.to_ascii_uppercase();
+        if upper == "NUL" {
+            pad.nul_terminate = true;
+        } else if let Some(width) = upper.strip_prefix("WIDTH=") {
+            pad.width = Some(parse_number(width)? as usize);
+        } else if let Some(fill) = upper.strip_prefix("FILL=") {
+            let digits = fill.strip_prefix("0X").unwrap_or(fill);
+            match parse_hex_bytes(digits)?.as_slice() {
+                [byte] => pad.fill = *byte,
+                _ => {
+                    return Err(ParseArgError::InvalidNumber(format!(
+                        "/WS fill must be one byte: {fill}"
+                    )));
+                }
+            }
+        } else {
+            return Err(ParseArgError::InvalidOption(format!("/WS: {option}")));
+        }
+    }
+    Ok(WriteStringParams {
+        address,
+        text: text.to_string(),
+        pad,
+    })
+}
+
+/// /WDATE value: `addr;BCD:pattern` or `addr;ASCII:pattern`.
+pub(super) fn parse_write_date(value: &str) -> Result<WriteDateParams, ParseArgError> {
+    let (address, format) = value
+        .split_once(';')
+        .ok_or_else(|| ParseArgError::MissingValue("/WDATE format".to_string()))?;
+    Ok(WriteDateParams {
+        address: parse_number(address)?,
+        format: format
+            .trim()
+            .parse()
+            .map_err(|e| ParseArgError::InvalidOption(format!("/WDATE: {e}")))?,
+    })
+}
+
+/// Decimal or `0x` hex, for values wider than an address (/FRSEED).
+pub(super) fn parse_u64(s: &str) -> Result<u64, ParseArgError> {
+    let s = s.trim();
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .map_err(|e| ParseArgError::InvalidNumber(format!("{s}: {e}")))
+}
+
 pub(super) fn parse_signed_number(s: &str) -> Result<i64, ParseArgError> {
     let s = s.trim();
     if s.is_empty() {
@@ -90,18 +242,15 @@ pub(super) fn parse_signed_number(s: &str) -> Result<i64, ParseArgError> {
 }
 
 pub(super) fn parse_merge_param(s: &str) -> Result<MergeParam, ParseArgError> {
-    let s = strip_quotes(s);
-    let (file_and_offset, range_str) = if let Some((left, right)) = s.split_once(':') {
-        (left, Some(right))
-    } else {
-        (s, None)
+    let (file, rest) = split_path(s, &[';', ':'])?;
+    let (offset_str, range_str) = match rest.split_once(':') {
+        Some((left, right)) => (left, Some(right)),
+        None => (rest, None),
     };
-
-    let (file, offset) = if let Some((file, offset_str)) = file_and_offset.split_once(';') {
-        let offset = parse_signed_number(offset_str)?;
-        (file, Some(offset))
-    } else {
-        (file_and_offset, None)
+    let offset = match offset_str.strip_prefix(';') {
+        Some(offset_str) => Some(parse_signed_number(offset_str)?),
+        None if offset_str.is_empty() => None,
+        None => return Err(ParseArgError::InvalidOption(format!("merge file: {s}"))),
     };
 
     let range = if let Some(range_str) = range_str {
@@ -152,11 +301,15 @@ pub(super) fn parse_merge_params(value: &str) -> Result<Vec<MergeParam>, ParseAr
 }
 
 pub(super) fn parse_import_param(value: &str) -> Result<ImportParam, ParseArgError> {
-    let value = strip_quotes(value);
-    let (file, offset) = if let Some((file, offset_str)) = value.split_once(';') {
-        (file, parse_number(offset_str)?)
-    } else {
-        (value, 0)
+    let (file, rest) = split_path(value, &[';'])?;
+    let offset = match rest.strip_prefix(';') {
+        Some(offset_str) => parse_number(offset_str)?,
+        None if rest.is_empty() => 0,
+        None => {
+            return Err(ParseArgError::InvalidOption(format!(
+                "import file: {value}"
+            )));
+        }
     };
 
     Ok(ImportParam {
@@ -186,6 +339,42 @@ pub(super) fn parse_remap(s: &str) -> Result<RemapParams, ParseArgError> {
     })
 }
 
+pub(super) fn parse_page_map(s: &str) -> Result<PageMapParams, ParseArgError> {
+    let parts: Vec<&str> = s.split(',').collect();
+    if parts.len() != 4 {
+        return Err(ParseArgError::InvalidOption(format!(
+            "page map requires 4 parameters: {s}"
+        )));
+    }
+    let ppage_base = parse_number(parts[0])?;
+    let ppage_base = u8::try_from(ppage_base)
+        .map_err(|_| ParseArgError::InvalidOption(format!("PPAGE base exceeds 0xFF: {s}")))?;
+
+    Ok(PageMapParams::Paged {
+        ppage_base,
+        window_start: parse_number(parts[1])?,
+        window_size: parse_number(parts[2])?,
+        linear_base: parse_number(parts[3])?,
+    })
+}
+
+pub(super) fn parse_bank(s: &str) -> Result<BankParams, ParseArgError> {
+    let parts: Vec<&str> = s.split(',').collect();
+    if !(2..=3).contains(&parts.len()) {
+        return Err(ParseArgError::InvalidOption(format!(
+            "bank requires window_start,window_size[,first]: {s}"
+        )));
+    }
+    let window = Range::from_start_length(parse_number(parts[0])?, parse_number(parts[1])?)
+        .map_err(|e| ParseArgError::InvalidRange(format!("{s}: {e}")))?;
+    let first_bank = match parts.get(2) {
+        Some(first) => u16::try_from(parse_number(first)?)
+            .map_err(|_| ParseArgError::InvalidOption(format!("first bank exceeds 0xFFFF: {s}")))?,
+        None => 0,
+    };
+    Ok(BankParams { window, first_bank })
+}
+
 pub(super) fn parse_checksum(
     algo: &str,
     target: &str,
@@ -198,16 +387,52 @@ pub(super) fn parse_checksum(
             .map_err(|_| ParseArgError::InvalidNumber(algo.to_string()))?
     };
 
-    let mut parts = target.split(';');
-    let target_str = parts.next().unwrap_or_default();
+    let (target_str, rest) = split_path(target, &[';'])?;
+    let parts = rest.split(';').skip(1);
     let mut range = None;
     let mut forced_range = None;
     let mut exclude_ranges = Vec::new();
+    let mut target_as_fill = false;
+    let mut gap_fill = ChecksumGapFill::Skip;
+    let mut initial = None;
+    let mut format = None;
 
     for part in parts {
         if part.is_empty() {
             continue;
         }
+        if part.eq_ignore_ascii_case("X") {
+            target_as_fill = true;
+            continue;
+        }
+        if let Some(fill) = part
+            .get(..5)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("FILL="))
+            .map(|_| &part[5..])
+        {
+            gap_fill = parse_checksum_gap_fill(fill)?;
+            continue;
+        }
+        if let Some(init) = part
+            .get(..5)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("INIT="))
+            .map(|_| &part[5..])
+        {
+            initial = Some(parse_number(init)? as u64);
+            continue;
+        }
+        if let Some(name) = part
+            .get(..4)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("FMT="))
+            .map(|_| &part[4..])
+        {
+            format = Some(ChecksumFormat::from_name(name).ok_or_else(|| {
+                ParseArgError::InvalidOption(format!(
+                    "checksum FMT={name}: expected HEX, BIN, C or DEC"
+                ))
+            })?);
+            continue;
+        }
         if let Some(forced) = part.strip_prefix('!') {
             if forced_range.is_some() {
                 return Err(ParseArgError::InvalidOption(
@@ -248,19 +473,9 @@ pub(super) fn parse_checksum(
             ));
         }
 
-        let mut pieces = part.split('/');
-        let range_part = pieces.next().unwrap_or_default();
-        if !range_part.is_empty() {
-            let ranges = parse_hexview_ranges(range_part)?;
-            range = ranges.into_iter().next();
-        }
-        for exclude in pieces {
-            if exclude.is_empty() {
-                continue;
-            }
-            let ranges = parse_hexview_ranges(exclude)?;
-            exclude_ranges.extend(ranges);
-        }
+        let (part_range, part_excludes) = parse_range_spec_with_excludes(part)?;
+        range = part_range;
+        exclude_ranges.extend(part_excludes);
     }
 
     let target = if target_str.is_empty() {
@@ -270,6 +485,11 @@ pub(super) fn parse_checksum(
     } else {
         ChecksumTarget::File(PathBuf::from(target_str))
     };
+    if format.is_some() && !matches!(target, ChecksumTarget::File(_) | ChecksumTarget::Stdout) {
+        return Err(ParseArgError::InvalidOption(
+            "checksum FMT= needs a file or @stdout target".to_string(),
+        ));
+    }
 
     Ok(ChecksumParams {
         algorithm,
@@ -278,16 +498,84 @@ pub(super) fn parse_checksum(
         range,
         forced_range,
         exclude_ranges,
+        target_as_fill,
+        gap_fill,
+        initial,
+        per_block: false,
+        format: format.unwrap_or_default(),
     })
 }
 
+/// `range[/exclude...]`, as used by /CS, /DP and /SV. Only the first range of
+/// the leading part is kept; either side may be empty.
+fn parse_range_with_excludes(part: &str) -> Result<(Option<Range>, Vec<Range>), ParseArgError> {
+    let (spec, excludes) = parse_range_spec_with_excludes(part)?;
+    let range = spec
+        .map(|spec| {
+            spec.fixed().ok_or_else(|| {
+                ParseArgError::InvalidRange(format!("{part}: START, END and ALL need /CS"))
+            })
+        })
+        .transpose()?;
+    Ok((range, excludes))
+}
+
+/// [`parse_range_with_excludes`] with `START`, `END` or `ALL` allowed in the
+/// leading range.
+fn parse_range_spec_with_excludes(
+    part: &str,
+) -> Result<(Option<RangeSpec>, Vec<Range>), ParseArgError> {
+    let mut pieces = part.split('/');
+    let range_part = pieces.next().unwrap_or_default();
+    let range = if range_part.is_empty() {
+        None
+    } else {
+        parse_hexview_range_specs(range_part)?.into_iter().next()
+    };
+    let mut excludes = Vec::new();
+    for exclude in pieces {
+        if exclude.is_empty() {
+            continue;
+        }
+        excludes.extend(parse_hexview_ranges(exclude)?);
+    }
+    Ok((range, excludes))
+}
+
+fn parse_checksum_gap_fill(value: &str) -> Result<ChecksumGapFill, ParseArgError> {
+    if value.eq_ignore_ascii_case("NONE") {
+        return Ok(ChecksumGapFill::Error);
+    }
+    let digits = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    match parse_hex_bytes(digits)?.as_slice() {
+        [byte] => Ok(ChecksumGapFill::Fill(*byte)),
+        _ => Err(ParseArgError::InvalidNumber(format!(
+            "checksum fill must be one byte: {value}"
+        ))),
+    }
+}
+
 fn parse_placement_target(target: &str) -> Result<ChecksumTarget, ParseArgError> {
+    if let Some(symbol) = target
+        .get(..4)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("SYM:"))
+        .map(|_| target[4..].trim())
+    {
+        if symbol.is_empty() {
+            return Err(ParseArgError::MissingValue("@SYM: symbol name".to_string()));
+        }
+        return Ok(ChecksumTarget::Symbol(symbol.to_string()));
+    }
     let target_upper = target.to_ascii_uppercase();
     match target_upper.as_str() {
         "APPEND" => Ok(ChecksumTarget::Append),
         "BEGIN" => Ok(ChecksumTarget::Begin),
         "UPFRONT" => Ok(ChecksumTarget::Prepend),
         "END" => Ok(ChecksumTarget::OverwriteEnd),
+        "STDOUT" => Ok(ChecksumTarget::Stdout),
         _ => Ok(ChecksumTarget::Address(parse_number(target)?)),
     }
 }
@@ -296,34 +584,20 @@ pub(super) fn parse_data_processing_params(
     method: u8,
     value: &str,
 ) -> Result<DataProcessingParams, ParseArgError> {
-    let (params_part, output_file) = if let Some((left, right)) = value.split_once(';') {
-        let output = strip_quotes(right).trim();
-        let output_file = if output.is_empty() {
-            None
-        } else {
-            Some(PathBuf::from(output))
-        };
-        (left, output_file)
-    } else {
-        (value, None)
-    };
+    let (placement, rest) = split_data_processing_placement(value)?;
+    let (key_and_meta, tail) = rest.split_once(';').unwrap_or((rest, ""));
+    let (output_file, range_part) = split_output_file(tail)?;
+    let (range, exclude) = parse_range_with_excludes(range_part)?;
 
-    let (placement, key_and_meta) = if let Some(rest) = params_part.strip_prefix('@') {
-        let (placement_raw, right) = rest.split_once(':').ok_or_else(|| {
-            ParseArgError::InvalidOption("data processing placement missing ':'".to_string())
-        })?;
-        (Some(parse_placement_target(placement_raw)?), right)
-    } else {
-        (None, params_part)
-    };
-
-    let key_info = strip_quotes(key_and_meta)
+    // The optional second field is the private key password.
+    let key_info = strip_quotes(key_and_meta).trim().to_string();
+    if key_info
         .split(',')
         .next()
-        .map(str::trim)
         .unwrap_or_default()
-        .to_string();
-    if key_info.is_empty() {
+        .trim()
+        .is_empty()
+    {
         return Err(ParseArgError::MissingValue(format!("/DP{method} keyinfo")));
     }
 
@@ -332,9 +606,86 @@ pub(super) fn parse_data_processing_params(
         placement,
         key_info,
         output_file,
+        range,
+        exclude,
+        external_command: None,
+        signature_len: None,
+    })
+}
+
+/// /DPEXT[len][:@placement]:command[;outfilename[;range]]. A command
+/// containing `;` must be wrapped in double quotes. A placement needs the
+/// signature length, since a short signature would leave stale bytes behind.
+pub(super) fn parse_external_signer_params(
+    signature_len: Option<usize>,
+    value: &str,
+) -> Result<DataProcessingParams, ParseArgError> {
+    let (placement, rest) = split_data_processing_placement(value)?;
+    if placement.is_some() && signature_len.is_none() {
+        return Err(ParseArgError::InvalidOption(
+            "/DPEXT placement needs the signature length: /DPEXT<len>:@...".to_string(),
+        ));
+    }
+    let (command, tail) = if let Some(quoted) = rest.strip_prefix('"') {
+        let (command, tail) = quoted.split_once('"').ok_or_else(|| {
+            ParseArgError::InvalidOption("/DPEXT command missing closing quote".to_string())
+        })?;
+        let tail = if tail.is_empty() {
+            tail
+        } else {
+            tail.strip_prefix(';').ok_or_else(|| {
+                ParseArgError::InvalidOption(format!(
+                    "/DPEXT: unexpected text after command: {tail}"
+                ))
+            })?
+        };
+        (command, tail)
+    } else {
+        rest.split_once(';').unwrap_or((rest, ""))
+    };
+    let command = command.trim();
+    if command.is_empty() {
+        return Err(ParseArgError::MissingValue("/DPEXT command".to_string()));
+    }
+    let (output_file, range_part) = split_output_file(tail)?;
+    let (range, exclude) = parse_range_with_excludes(range_part)?;
+
+    Ok(DataProcessingParams {
+        method: 0,
+        placement,
+        key_info: String::new(),
+        output_file,
+        range,
+        exclude,
+        external_command: Some(command.to_string()),
+        signature_len,
     })
 }
 
+fn split_data_processing_placement(
+    value: &str,
+) -> Result<(Option<ChecksumTarget>, &str), ParseArgError> {
+    let Some(rest) = value.strip_prefix('@') else {
+        return Ok((None, value));
+    };
+    let (placement_raw, right) = rest.split_once(':').ok_or_else(|| {
+        ParseArgError::InvalidOption("data processing placement missing ':'".to_string())
+    })?;
+    Ok((Some(parse_placement_target(placement_raw)?), right))
+}
+
+/// Splits `outfilename[;range]` after the key info or command.
+fn split_output_file(tail: &str) -> Result<(Option<PathBuf>, &str), ParseArgError> {
+    let (output, range_part) = split_path(tail.trim(), &[';'])?;
+    let output = output.trim();
+    let output_file = if output.is_empty() {
+        None
+    } else {
+        Some(PathBuf::from(output))
+    };
+    Ok((output_file, range_part.trim_start_matches(';').trim()))
+}
+
 pub(super) fn parse_signature_verify_params(
     method: u8,
     value: &str,
@@ -342,18 +693,24 @@ pub(super) fn parse_signature_verify_params(
     let (key_raw, signature_raw) = value.split_once('!').ok_or_else(|| {
         ParseArgError::InvalidOption("signature verification requires keyinfo!signatureinfo".into())
     })?;
+    let (signature_raw, range_part) = signature_raw.split_once(';').unwrap_or((signature_raw, ""));
+    let (range, exclude) = parse_range_with_excludes(range_part.trim())?;
     let key_info = strip_quotes(key_raw).trim().to_string();
     let signature_info = strip_quotes(signature_raw).trim().to_string();
     if key_info.is_empty() {
         return Err(ParseArgError::MissingValue(format!("/SV{method} keyinfo")));
     }
     if signature_info.is_empty() {
-        return Err(ParseArgError::MissingValue(format!("/SV{method} signatureinfo")));
+        return Err(ParseArgError::MissingValue(format!(
+            "/SV{method} signatureinfo"
+        )));
     }
     Ok(SignatureVerifyParams {
         method,
         key_info,
         signature_info,
+        range,
+        exclude,
     })
 }
 
@@ -381,6 +738,80 @@ pub(super) fn parse_dspic_op(s: &str) -> Result<DspicOp, ParseArgError> {
     }
 }
 
+pub(super) fn parse_dspic_ghost_op(s: &str) -> Result<DspicGhostOp, ParseArgError> {
+    let s = strip_quotes(s);
+    let (range_str, value) = match s.split_once(';') {
+        Some((range_str, value_str)) => {
+            let value = parse_number(value_str)?;
+            let value = u8::try_from(value)
+                .map_err(|_| ParseArgError::InvalidNumber(value_str.to_string()))?;
+            (range_str, value)
+        }
+        None => (s, 0x00),
+    };
+    let range = parse_hexview_ranges(range_str)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| ParseArgError::InvalidRange(s.to_string()))?;
+    Ok(DspicGhostOp { range, value })
+}
+
+/// `range;name`. Names end up in /XSB file names, so only letters, digits,
+/// `_`, `-` and `.` are allowed.
+pub(super) fn parse_label(s: &str) -> Result<LabelParams, ParseArgError> {
+    let s = strip_quotes(s);
+    let (range_str, name) = s
+        .split_once(';')
+        .ok_or_else(|| ParseArgError::InvalidOption(format!("/LABEL requires range;name: {s}")))?;
+    let valid = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.');
+    if name.is_empty() || !name.chars().all(valid) {
+        return Err(ParseArgError::InvalidOption(format!(
+            "/LABEL name may only use letters, digits, '_', '-' and '.': {name}"
+        )));
+    }
+    let range = parse_hexview_ranges(range_str)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| ParseArgError::InvalidRange(s.to_string()))?;
+    Ok(LabelParams {
+        range,
+        name: name.to_string(),
+    })
+}
+
+/// `key=value` for /FORD. Keys are the `[FORDHEADER]` INI keys, matched
+/// case-insensitively with `_` accepted for a space.
+pub(super) fn parse_ford_header(s: &str) -> Result<(String, String), ParseArgError> {
+    let s = strip_quotes(s);
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| ParseArgError::InvalidOption(format!("/FORD requires key=value: {s}")))?;
+    let key = key.trim().to_ascii_lowercase().replace('_', " ");
+    let known = FORD_REQUIRED_KEYS.iter().any(|(k, _)| *k == key)
+        || FORD_OPTIONAL_KEYS.contains(&key.as_str());
+    if !known {
+        return Err(ParseArgError::InvalidOption(format!(
+            "/FORD: unknown [FORDHEADER] key '{key}'"
+        )));
+    }
+    Ok((key, value.trim().to_string()))
+}
+
+/// `NAME=value` for /DEF.
+pub(super) fn parse_define(s: &str) -> Result<(String, String), ParseArgError> {
+    let s = strip_quotes(s);
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| ParseArgError::InvalidOption(format!("/DEF requires NAME=value: {s}")))?;
+    let name = name.trim();
+    if !is_variable_name(name) {
+        return Err(ParseArgError::InvalidOption(format!(
+            "/DEF name may only use letters, digits and '_', and not start with a digit: {name}"
+        )));
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
 pub(super) fn parse_output_params(s: &str) -> Result<(Option<u8>, Option<u8>), ParseArgError> {
     if s.is_empty() {
         return Ok((None, None));
@@ -418,16 +849,23 @@ pub(super) fn parse_output_params(s: &str) -> Result<(Option<u8>, Option<u8>), P
     Ok((len, rec_type))
 }
 
-pub(super) fn parse_hex_ascii_params(
-    value: &str,
-) -> Result<(Option<u32>, Option<String>), ParseArgError> {
+/// `/XA` parameters: line length, separator, address prefix.
+type HexAsciiParams = (
+    Option<u32>,
+    Option<String>,
+    Option<crate::AddressPrefixStyle>,
+);
+
+/// Parses `/XA` parameters `len[:separator[:ADDR|PLAIN]]`. The separator may
+/// itself contain `:`; only a trailing `:ADDR` or `:PLAIN` selects a prefix.
+pub(super) fn parse_hex_ascii_params(value: &str) -> Result<HexAsciiParams, ParseArgError> {
     if value.is_empty() {
-        return Ok((None, None));
+        return Ok((None, None, None));
     }
 
     let mut parts = value.splitn(2, ':');
     let len_part = parts.next().unwrap_or_default();
-    let sep_part = parts.next();
+    let mut sep_part = parts.next();
 
     let line_length = if len_part.is_empty() {
         None
@@ -435,15 +873,81 @@ pub(super) fn parse_hex_ascii_params(
         Some(parse_number(len_part)?)
     };
 
-    let separator = sep_part.map(|s| strip_quotes(s).to_string());
+    let mut address_prefix = None;
+    if let Some((sep, style)) = sep_part.and_then(|s| s.rsplit_once(':')) {
+        let style = style.trim();
+        if style.eq_ignore_ascii_case("ADDR") {
+            address_prefix = Some(crate::AddressPrefixStyle::At);
+        } else if style.eq_ignore_ascii_case("PLAIN") {
+            address_prefix = Some(crate::AddressPrefixStyle::Plain);
+        }
+        if address_prefix.is_some() {
+            sep_part = Some(sep);
+        }
+    }
+
+    let separator = sep_part
+        .map(|s| strip_quotes(s).to_string())
+        .filter(|s| !s.is_empty());
 
-    Ok((line_length, separator))
+    Ok((line_length, separator, address_prefix))
+}
+
+/// `/XN` image parameters: start, end, fill.
+type BinaryParams = (Option<u32>, Option<u32>, Option<u8>);
+
+/// Parses `/XN` parameters `start[,end[,fill]]`.
+pub(super) fn parse_binary_params(value: &str) -> Result<BinaryParams, ParseArgError> {
+    if value.is_empty() {
+        return Ok((None, None, None));
+    }
+
+    let parts: Vec<&str> = value.split(',').collect();
+    if parts.len() > 3 {
+        return Err(ParseArgError::InvalidOption(format!(
+            "/XN expects start[,end[,fill]]: {value}"
+        )));
+    }
+    let optional = |idx: usize| -> Result<Option<u32>, ParseArgError> {
+        match parts.get(idx) {
+            Some(part) if !part.trim().is_empty() => parse_number(part).map(Some),
+            _ => Ok(None),
+        }
+    };
+    let fill = optional(2)?
+        .map(|fill| {
+            u8::try_from(fill)
+                .map_err(|_| ParseArgError::InvalidNumber(format!("fill byte {fill:#X}")))
+        })
+        .transpose()?;
+    Ok((optional(0)?, optional(1)?, fill))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_hex_ascii_params_address_prefix() {
+        use crate::AddressPrefixStyle;
+        assert_eq!(
+            parse_hex_ascii_params("16:' ':ADDR").unwrap(),
+            (
+                Some(16),
+                Some(" ".to_string()),
+                Some(AddressPrefixStyle::At)
+            )
+        );
+        assert_eq!(
+            parse_hex_ascii_params("::plain").unwrap(),
+            (None, None, Some(AddressPrefixStyle::Plain))
+        );
+        assert_eq!(
+            parse_hex_ascii_params("8:: ").unwrap(),
+            (Some(8), Some(": ".to_string()), None)
+        );
+    }
+
     #[test]
     fn test_parse_signed_number_negative_hex() {
         assert_eq!(parse_signed_number("-0x10").unwrap(), -16);
@@ -469,6 +973,21 @@ mod tests {
         assert_eq!(parse_number("255u").unwrap(), 255);
     }
 
+    #[test]
+    fn test_parse_number_with_size_suffix() {
+        assert_eq!(parse_number("4K").unwrap(), 4 * 1024);
+        assert_eq!(parse_number("64k").unwrap(), 0x1_0000);
+        assert_eq!(parse_number("2M").unwrap(), 2 * 1024 * 1024);
+        assert!(parse_number("0x10K").is_err());
+        assert!(parse_number("1.5M").is_err());
+        assert!(parse_number("K").is_err());
+        assert!(parse_number("4096M").is_err());
+        assert_eq!(
+            parse_hexview_ranges("0x8000,32K").unwrap(),
+            vec![Range::from_start_end(0x8000, 0xFFFF).unwrap()]
+        );
+    }
+
     #[test]
     fn test_parse_merge_params_with_range() {
         let params = parse_merge_params("cal1.hex;-0x10:0x1000-0x10FF+cal2.s19;128").unwrap();
@@ -478,6 +997,67 @@ mod tests {
         assert_eq!(params[1].offset, Some(128));
     }
 
+    #[test]
+    fn test_split_option_keeps_drive_letter_in_value() {
+        assert_eq!(
+            split_option(r"L:C:\logs\run.log"),
+            Some(("L", r"C:\logs\run.log"))
+        );
+        assert_eq!(
+            split_option(r"L=C:\logs\run.log"),
+            Some(("L", r"C:\logs\run.log"))
+        );
+        assert_eq!(split_option("XS:hdr=app"), Some(("XS", "hdr=app")));
+    }
+
+    #[test]
+    fn test_parse_merge_params_windows_paths() {
+        let params = parse_merge_params(
+            r#"C:\builds\cal.hex;0x1000:0x0-0xFF+"D:\my builds\b.hex";-0x10+'E:/x+y.hex':0x10-0x1F"#,
+        )
+        .unwrap();
+        assert_eq!(params.len(), 3);
+        assert_eq!(params[0].file, PathBuf::from(r"C:\builds\cal.hex"));
+        assert_eq!(params[0].offset, Some(0x1000));
+        assert_eq!(
+            params[0].range,
+            Some(Range::from_start_end(0x0, 0xFF).unwrap())
+        );
+        assert_eq!(params[1].file, PathBuf::from(r"D:\my builds\b.hex"));
+        assert_eq!(params[1].offset, Some(-0x10));
+        assert_eq!(params[2].file, PathBuf::from("E:/x+y.hex"));
+        assert_eq!(params[2].offset, None);
+        assert!(params[2].range.is_some());
+
+        assert!(parse_merge_params(r#""C:\cal.hex"junk"#).is_err());
+        assert!(parse_merge_params(r#""C:\cal.hex;0x10"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_windows_paths_in_file_values() {
+        let param = parse_import_param(r#""C:\data\in.bin";0x100"#).unwrap();
+        assert_eq!(param.file, PathBuf::from(r"C:\data\in.bin"));
+        assert_eq!(param.offset, 0x100);
+        let param = parse_import_param(r"C:\data\in.bin").unwrap();
+        assert_eq!(param.file, PathBuf::from(r"C:\data\in.bin"));
+
+        let params = parse_checksum("0", r"C:\out\cs.bin;0x0-0xFF", false).unwrap();
+        assert_eq!(
+            params.target,
+            ChecksumTarget::File(PathBuf::from(r"C:\out\cs.bin"))
+        );
+        assert!(params.range.is_some());
+        let params = parse_checksum("0", r#""C:\out;1\cs.bin";0x0-0xFF"#, false).unwrap();
+        assert_eq!(
+            params.target,
+            ChecksumTarget::File(PathBuf::from(r"C:\out;1\cs.bin"))
+        );
+
+        let params = parse_data_processing_params(10, r#"key.pem;"C:\sig;1.bin";0x0-0xF"#).unwrap();
+        assert_eq!(params.output_file, Some(PathBuf::from(r"C:\sig;1.bin")));
+        assert!(params.range.is_some());
+    }
+
     #[test]
     fn test_parse_import_param_with_offset() {
         let param = parse_import_param("file.bin;0x1000").unwrap();
@@ -528,11 +1108,43 @@ mod tests {
         let result = parse_checksum("0", "@append;!0x1000-0x1001#F", false);
         assert!(result.is_err());
     }
+    #[test]
+    fn test_parse_checksum_target_as_fill_suffix() {
+        let params = parse_checksum("0", "@0x1FFE;0x1000-0x1FFF;x", false).unwrap();
+        assert!(params.target_as_fill);
+        assert_eq!(params.range.unwrap().fixed().unwrap().end(), 0x1FFF);
+    }
+
+    #[test]
+    fn test_parse_checksum_gap_fill_suffix() {
+        let params = parse_checksum("9", "@append;0x0-0xFFFF;FILL=00", false).unwrap();
+        assert_eq!(params.gap_fill, ChecksumGapFill::Fill(0x00));
+        let params = parse_checksum("9", "@append;fill=none", false).unwrap();
+        assert_eq!(params.gap_fill, ChecksumGapFill::Error);
+        assert!(parse_checksum("9", "@append;FILL=0000", false).is_err());
+    }
+
+    #[test]
+    fn test_parse_checksum_initial_suffix() {
+        let params = parse_checksum("9", "@append;0x0-0xFF;INIT=0xCBF43926", false).unwrap();
+        assert_eq!(params.initial, Some(0xCBF4_3926));
+        assert_eq!(params.range.unwrap().fixed().unwrap().end(), 0xFF);
+    }
+
+    #[test]
+    fn test_parse_checksum_open_range() {
+        let params = parse_checksum("9", "@append;0x1000-END/0x1100-0x11FF", false).unwrap();
+        assert_eq!(params.range, Some(RangeSpec::new(Some(0x1000), None)));
+        assert_eq!(params.exclude_ranges.len(), 1);
+        assert!(parse_checksum("9", "@append;0x1000-0x1FFF/0x1800-END", false).is_err());
+        assert!(parse_data_processing_params(32, "key.pem;sig.bin;ALL").is_err());
+    }
+
     #[test]
     fn test_parse_checksum_empty_target_defaults_append() {
         let params = parse_checksum("0", ";0x1000-0x1003", false).unwrap();
         assert!(matches!(params.target, ChecksumTarget::Append));
-        assert_eq!(params.range.unwrap().start(), 0x1000);
+        assert_eq!(params.range.unwrap().fixed().unwrap().start(), 0x1000);
     }
 
     #[test]
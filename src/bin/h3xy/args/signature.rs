@@ -1,30 +1,60 @@
-use std::path::Path;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use ed25519_dalek::pkcs8::{
     DecodePrivateKey as EdDecodePrivateKey, DecodePublicKey as EdDecodePublicKey,
 };
-use ed25519_dalek::{Signature as EdSignature, SigningKey as EdSigningKey, VerifyingKey as EdVerifyingKey};
+use ed25519_dalek::{
+    Signature as EdSignature, SigningKey as EdSigningKey, VerifyingKey as EdVerifyingKey,
+};
+use p256::ecdsa::{
+    Signature as EcdsaSignature, SigningKey as EcdsaSigningKey, VerifyingKey as EcdsaVerifyingKey,
+};
+use p256::{PublicKey as P256PublicKey, SecretKey as P256SecretKey};
+use pkcs8::EncryptedPrivateKeyInfo;
 use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey};
 use rsa::pkcs1v15::{
     Signature as RsaPkcs1v15Signature, SigningKey as RsaPkcs1v15SigningKey,
     VerifyingKey as RsaPkcs1v15VerifyingKey,
 };
-use rsa::pss::{Signature as RsaPssSignature, SigningKey as RsaPssSigningKey, VerifyingKey as RsaPssVerifyingKey};
-use rsa::{RsaPrivateKey, RsaPublicKey};
+use rsa::pss::{
+    Signature as RsaPssSignature, SigningKey as RsaPssSigningKey,
+    VerifyingKey as RsaPssVerifyingKey,
+};
 use rsa::signature::{SignatureEncoding, Signer, Verifier};
+use rsa::{RsaPrivateKey, RsaPublicKey};
 use sha2::{Digest, Sha256, Sha512};
 use x509_cert::Certificate;
 use x509_cert::der::{Decode, DecodePem, Encode};
 
 use super::error::CliError;
+use super::io::{WriteProvider, write_file};
+use super::mac::{compute_mac, map_mac_method};
 use super::types::{ChecksumTarget, DataProcessingParams, SignatureVerifyParams};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum SignatureMethod {
-    RsaPkcs1v15Sha256 { with_metadata: bool },
-    RsaPssSha256 { with_metadata: bool },
-    Ed25519Ph { with_metadata: bool },
-    Ed25519Sha512Data { with_metadata: bool },
+    RsaPkcs1v15Sha256 {
+        with_metadata: bool,
+    },
+    RsaPssSha256 {
+        with_metadata: bool,
+    },
+    Ed25519Ph {
+        with_metadata: bool,
+    },
+    Ed25519Sha512Data {
+        with_metadata: bool,
+    },
+    /// NIST P-256 over SHA-256; `der` selects ASN.1 DER instead of 64-byte r||s.
+    EcdsaP256Sha256 {
+        with_metadata: bool,
+        der: bool,
+    },
 }
 
 impl SignatureMethod {
@@ -39,17 +69,20 @@ impl SignatureMethod {
                 with_metadata: true
             } | SignatureMethod::Ed25519Sha512Data {
                 with_metadata: true
+            } | SignatureMethod::EcdsaP256Sha256 {
+                with_metadata: true,
+                ..
             }
         )
     }
 }
 
 pub(super) fn is_supported_data_processing_method(method: u8) -> bool {
-    matches!(method, 32 | 33 | 38 | 39 | 46 | 47 | 48 | 49)
+    matches!(method, 32 | 33 | 38 | 39 | 46..=53 | 60..=63)
 }
 
 pub(super) fn is_supported_signature_verify_method(method: u8) -> bool {
-    matches!(method, 4..=11)
+    matches!(method, 4..=13)
 }
 
 fn map_data_processing_method(method: u8) -> Option<SignatureMethod> {
@@ -78,6 +111,22 @@ fn map_data_processing_method(method: u8) -> Option<SignatureMethod> {
         49 => Some(SignatureMethod::Ed25519Sha512Data {
             with_metadata: true,
         }),
+        50 => Some(SignatureMethod::EcdsaP256Sha256 {
+            with_metadata: false,
+            der: false,
+        }),
+        51 => Some(SignatureMethod::EcdsaP256Sha256 {
+            with_metadata: true,
+            der: false,
+        }),
+        52 => Some(SignatureMethod::EcdsaP256Sha256 {
+            with_metadata: false,
+            der: true,
+        }),
+        53 => Some(SignatureMethod::EcdsaP256Sha256 {
+            with_metadata: true,
+            der: true,
+        }),
         _ => None,
     }
 }
@@ -108,6 +157,15 @@ fn map_signature_verify_method(method: u8) -> Option<SignatureMethod> {
         11 => Some(SignatureMethod::Ed25519Sha512Data {
             with_metadata: true,
         }),
+        // Verification accepts both r||s and DER signatures.
+        12 => Some(SignatureMethod::EcdsaP256Sha256 {
+            with_metadata: false,
+            der: false,
+        }),
+        13 => Some(SignatureMethod::EcdsaP256Sha256 {
+            with_metadata: true,
+            der: false,
+        }),
         _ => None,
     }
 }
@@ -115,24 +173,143 @@ fn map_signature_verify_method(method: u8) -> Option<SignatureMethod> {
 pub(super) fn apply_data_processing(
     hexfile: &mut crate::HexFile,
     params: &DataProcessingParams,
+    create_dirs: bool,
+    writer: &dyn WriteProvider,
 ) -> Result<Option<Vec<u8>>, CliError> {
-    let Some(method) = map_data_processing_method(params.method) else {
-        return Ok(None);
+    let label = if params.external_command.is_some() {
+        "/DPEXT".to_string()
+    } else {
+        format!("/DP{}", params.method)
     };
-    let payload = signature_payload(hexfile, method.with_metadata())?;
-    let signature = sign_payload(method, &payload, &params.key_info)
-        .map_err(|e| CliError::Other(format!("/DP{}: {e}", params.method)))?;
+    let signature = if let Some(command) = params.external_command.as_deref() {
+        let payload = signature_payload(hexfile, false, params.range, &params.exclude)?;
+        run_external_signer(command, &payload, params.signature_len)
+    } else if let Some(method) = map_data_processing_method(params.method) {
+        let payload = signature_payload(
+            hexfile,
+            method.with_metadata(),
+            params.range,
+            &params.exclude,
+        )?;
+        sign_payload(method, &payload, &params.key_info)
+    } else if let Some(method) = map_mac_method(params.method) {
+        let payload = signature_payload(
+            hexfile,
+            method.with_metadata(),
+            params.range,
+            &params.exclude,
+        )?;
+        compute_mac(method, &payload, &params.key_info)
+    } else {
+        return Ok(None);
+    }
+    .map_err(|message| CliError::Signature {
+        option: label.clone(),
+        message,
+    })?;
     if let Some(target) = params.placement.as_ref() {
-        place_signature(hexfile, target, &signature)
-            .map_err(|e| CliError::Other(format!("/DP{}: {e}", params.method)))?;
+        place_signature(hexfile, target, &signature).map_err(|message| CliError::Signature {
+            option: label.clone(),
+            message,
+        })?;
     }
     if let Some(path) = params.output_file.as_ref() {
-        std::fs::write(path, &signature)
-            .map_err(|e| CliError::Other(format!("/DP{}: {e}", params.method)))?;
+        write_file(&label, path, &signature, create_dirs, writer)?;
     }
     Ok(Some(signature))
 }
 
+/// Run a /DPEXT command: the payload goes to a temp file substituted for
+/// `{payload}`, and the command must write the signature to `{signature}`.
+/// Both files live in a fresh private directory and are substituted quoted.
+fn run_external_signer(
+    command: &str,
+    payload: &[u8],
+    signature_len: Option<usize>,
+) -> Result<Vec<u8>, String> {
+    let dir = create_private_temp_dir().map_err(|e| format!("cannot create temp dir: {e}"))?;
+    let payload_path = dir.join("payload.bin");
+    let signature_path = dir.join("signature.bin");
+
+    let result = (|| {
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&payload_path)
+            .and_then(|mut file| file.write_all(payload))
+            .map_err(|e| format!("cannot write payload file: {e}"))?;
+        let command = command
+            .replace("{payload}", &shell_quote(&payload_path))
+            .replace("{signature}", &shell_quote(&signature_path));
+        let output = if cfg!(windows) {
+            Command::new("cmd").args(["/C", &command]).output()
+        } else {
+            Command::new("sh").args(["-c", &command]).output()
+        }
+        .map_err(|e| format!("cannot run signer: {e}"))?;
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let with_stderr = |msg: String| {
+            if stderr.is_empty() {
+                msg
+            } else {
+                format!("{msg}; stderr: {stderr}")
+            }
+        };
+        if !output.status.success() {
+            return Err(with_stderr(format!("signer failed ({})", output.status)));
+        }
+        match std::fs::read(&signature_path) {
+            Ok(signature) if signature.is_empty() => {
+                Err(with_stderr("signer wrote an empty signature".to_string()))
+            }
+            Ok(signature) if signature_len.is_some_and(|len| signature.len() != len) => {
+                Err(with_stderr(format!(
+                    "signer wrote {} bytes, expected {}",
+                    signature.len(),
+                    signature_len.unwrap_or_default()
+                )))
+            }
+            Ok(signature) => Ok(signature),
+            Err(e) => Err(with_stderr(format!(
+                "signer did not write a signature: {e}"
+            ))),
+        }
+    })();
+
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+/// A new directory under the system temp dir, 0700 on unix. `create` fails
+/// on an existing path, so a name someone else planted is never reused.
+fn create_private_temp_dir() -> std::io::Result<PathBuf> {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    let base = std::env::temp_dir();
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    loop {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let nonce = RandomState::new().build_hasher().finish();
+        let dir = base.join(format!("h3xy-{}-{id}-{nonce:016x}", std::process::id()));
+        match builder.create(&dir) {
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            result => return result.map(|()| dir),
+        }
+    }
+}
+
+/// Quote a path for `sh -c` (single quotes) or `cmd /C` (double quotes,
+/// which Windows paths cannot contain).
+fn shell_quote(path: &Path) -> String {
+    let path = path.display().to_string();
+    if cfg!(windows) {
+        format!("\"{path}\"")
+    } else {
+        format!("'{}'", path.replace('\'', "'\\''"))
+    }
+}
+
 pub(super) fn apply_signature_verification(
     hexfile: &crate::HexFile,
     params: &SignatureVerifyParams,
@@ -140,16 +317,35 @@ pub(super) fn apply_signature_verification(
     let Some(method) = map_signature_verify_method(params.method) else {
         return Ok(());
     };
-    let payload = signature_payload(hexfile, method.with_metadata())?;
-    let signature_bytes = load_signature_bytes(&params.signature_info)
-        .map_err(|e| CliError::Other(format!("/SV{}: {e}", params.method)))?;
+    let payload = signature_payload(
+        hexfile,
+        method.with_metadata(),
+        params.range,
+        &params.exclude,
+    )?;
+    let signature_bytes =
+        load_signature_bytes(&params.signature_info).map_err(|message| CliError::Signature {
+            option: format!("/SV{}", params.method),
+            message,
+        })?;
     verify_payload(method, &payload, &params.key_info, &signature_bytes)
-        .map_err(|e| CliError::Other(format!("/SV{}: {e}", params.method)))?;
+        .map_err(|e| CliError::Verification(format!("/SV{}: {e}", params.method)))?;
     Ok(())
 }
 
-fn signature_payload(hexfile: &crate::HexFile, with_metadata: bool) -> Result<Vec<u8>, CliError> {
-    let normalized = hexfile.normalized_lossy();
+/// Concatenated data inside `range` (default: everything) minus `exclude`.
+/// The metadata prefix describes that filtered span.
+fn signature_payload(
+    hexfile: &crate::HexFile,
+    with_metadata: bool,
+    range: Option<crate::Range>,
+    exclude: &[crate::Range],
+) -> Result<Vec<u8>, CliError> {
+    let mut normalized = hexfile.normalized_lossy();
+    if let Some(range) = range {
+        normalized.filter_range(range);
+    }
+    normalized.cut_ranges(exclude);
     let mut data = Vec::new();
     for seg in normalized.segments() {
         data.extend_from_slice(&seg.data);
@@ -158,8 +354,9 @@ fn signature_payload(hexfile: &crate::HexFile, with_metadata: bool) -> Result<Ve
         return Ok(data);
     }
     let start = normalized.min_address().unwrap_or(0);
-    let len = u32::try_from(data.len())
-        .map_err(|_| CliError::Other("signature payload length exceeds u32".to_string()))?;
+    let len = u32::try_from(data.len()).map_err(|_| {
+        crate::OpsError::AddressOverflow("signature payload length exceeds u32".to_string())
+    })?;
     let mut out = Vec::with_capacity(8 + data.len());
     out.extend_from_slice(&start.to_be_bytes());
     out.extend_from_slice(&len.to_be_bytes());
@@ -213,9 +410,11 @@ fn place_signature(
             }
             Ok(())
         }
-        ChecksumTarget::File(_) => {
-            Err("file target is not valid for /DP placement".to_string())
+        ChecksumTarget::File(_) => Err("file target is not valid for /DP placement".to_string()),
+        ChecksumTarget::Symbol(_) => {
+            Err("symbol target is not valid for /DP placement".to_string())
         }
+        ChecksumTarget::Stdout => Err("@stdout is not valid for /DP placement".to_string()),
     }
 }
 
@@ -248,7 +447,11 @@ fn parse_hex_signature(s: &str) -> Result<Vec<u8>, String> {
     Ok(out)
 }
 
-fn sign_payload(method: SignatureMethod, payload: &[u8], key_info: &str) -> Result<Vec<u8>, String> {
+fn sign_payload(
+    method: SignatureMethod,
+    payload: &[u8],
+    key_info: &str,
+) -> Result<Vec<u8>, String> {
     match method {
         SignatureMethod::RsaPkcs1v15Sha256 { .. } => {
             let key = load_rsa_private_key(key_info)?;
@@ -273,6 +476,15 @@ fn sign_payload(method: SignatureMethod, payload: &[u8], key_info: &str) -> Resu
             let digest = Sha512::digest(payload);
             Ok(key.sign(&digest).to_bytes().to_vec())
         }
+        SignatureMethod::EcdsaP256Sha256 { der, .. } => {
+            let key = EcdsaSigningKey::from(load_p256_private_key(key_info)?);
+            let signature: EcdsaSignature = key.sign(payload);
+            if der {
+                Ok(signature.to_der().as_bytes().to_vec())
+            } else {
+                Ok(signature.to_bytes().to_vec())
+            }
+        }
     }
 }
 
@@ -317,27 +529,73 @@ fn verify_payload(
             key.verify(&digest, &signature)
                 .map_err(|_| "signature verification failed".to_string())
         }
+        SignatureMethod::EcdsaP256Sha256 { .. } => {
+            let key = EcdsaVerifyingKey::from(load_p256_public_key(key_info)?);
+            let signature = EcdsaSignature::from_slice(signature_bytes)
+                .or_else(|_| EcdsaSignature::from_der(signature_bytes))
+                .map_err(|_| "invalid ECDSA P-256 signature bytes".to_string())?;
+            key.verify(payload, &signature)
+                .map_err(|_| "signature verification failed".to_string())
+        }
     }
 }
 
-fn load_key_material(key_info: &str) -> Result<Vec<u8>, String> {
-    let key_source = key_info
-        .split(',')
-        .next()
-        .map(str::trim)
-        .ok_or_else(|| "missing key info".to_string())?;
-    if key_source.is_empty() {
-        return Err("missing key info".to_string());
+/// First key info field: the key file. A missing file is an error, never
+/// key text.
+pub(super) fn load_key_material(key_info: &str) -> Result<Vec<u8>, String> {
+    let key_source = key_source(key_info)?;
+    std::fs::read(Path::new(key_source)).map_err(|e| format!("key file {key_source}: {e}"))
+}
+
+pub(super) fn key_source(key_info: &str) -> Result<&str, String> {
+    match key_info.split(',').next().map(str::trim) {
+        Some(source) if !source.is_empty() => Ok(source),
+        _ => Err("missing key info".to_string()),
     }
-    let path = Path::new(key_source);
-    if path.exists() {
-        return std::fs::read(path).map_err(|e| e.to_string());
+}
+
+/// Second key info field: a literal password, `env:VAR` or `file:path`.
+fn key_password(key_info: &str) -> Result<Option<String>, String> {
+    let Some(field) = key_info.split(',').nth(1).map(str::trim) else {
+        return Ok(None);
+    };
+    if field.is_empty() {
+        return Ok(None);
+    }
+    if let Some(var) = field.strip_prefix("env:") {
+        return std::env::var(var)
+            .map(Some)
+            .map_err(|_| format!("password environment variable {var} is not set"));
     }
-    Ok(key_source.as_bytes().to_vec())
+    if let Some(path) = field.strip_prefix("file:") {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("password file {path}: {e}"))?;
+        return Ok(Some(text.trim_end_matches(['\r', '\n']).to_string()));
+    }
+    Ok(Some(field.to_string()))
 }
 
-fn load_rsa_private_key(key_info: &str) -> Result<RsaPrivateKey, String> {
+/// Load private key material, decrypting an `ENCRYPTED PRIVATE KEY` (PEM or
+/// DER) to plain PKCS#8 DER with the key info password.
+fn load_private_key_material(key_info: &str) -> Result<Vec<u8>, String> {
     let material = load_key_material(key_info)?;
+    let encrypted = match pkcs8::der::pem::decode_vec(material.trim_ascii()) {
+        Ok(("ENCRYPTED PRIVATE KEY", der)) => der,
+        _ if EncryptedPrivateKeyInfo::try_from(material.as_slice()).is_ok() => material,
+        _ => return Ok(material),
+    };
+    let info = EncryptedPrivateKeyInfo::try_from(encrypted.as_slice())
+        .map_err(|e| format!("invalid encrypted private key: {e}"))?;
+    let password = key_password(key_info)?
+        .ok_or_else(|| "private key is encrypted but no password was given".to_string())?;
+    let decrypted = info
+        .decrypt(password)
+        .map_err(|_| "wrong password for encrypted private key".to_string())?;
+    Ok(decrypted.as_bytes().to_vec())
+}
+
+fn load_rsa_private_key(key_info: &str) -> Result<RsaPrivateKey, String> {
+    let material = load_private_key_material(key_info)?;
     if let Ok(text) = std::str::from_utf8(&material) {
         let text = text.trim();
         if let Ok(key) = RsaPrivateKey::from_pkcs8_pem(text) {
@@ -382,7 +640,7 @@ fn load_rsa_public_key(key_info: &str) -> Result<RsaPublicKey, String> {
 }
 
 fn load_ed25519_private_key(key_info: &str) -> Result<EdSigningKey, String> {
-    let material = load_key_material(key_info)?;
+    let material = load_private_key_material(key_info)?;
     if let Ok(text) = std::str::from_utf8(&material) {
         let text = text.trim();
         if let Ok(key) = EdSigningKey::from_pkcs8_pem(text) {
@@ -414,20 +672,51 @@ fn load_ed25519_public_key(key_info: &str) -> Result<EdVerifyingKey, String> {
     Err("unable to parse ed25519 public key or certificate".to_string())
 }
 
+fn load_p256_private_key(key_info: &str) -> Result<P256SecretKey, String> {
+    let material = load_private_key_material(key_info)?;
+    if let Ok(text) = std::str::from_utf8(&material) {
+        let text = text.trim();
+        if let Ok(key) = P256SecretKey::from_pkcs8_pem(text) {
+            return Ok(key);
+        }
+        if let Ok(key) = P256SecretKey::from_sec1_pem(text) {
+            return Ok(key);
+        }
+    }
+    if let Ok(key) = P256SecretKey::from_pkcs8_der(&material) {
+        return Ok(key);
+    }
+    if let Ok(key) = P256SecretKey::from_sec1_der(&material) {
+        return Ok(key);
+    }
+    Err("unable to parse ECDSA P-256 private key".to_string())
+}
+
+fn load_p256_public_key(key_info: &str) -> Result<P256PublicKey, String> {
+    let material = load_key_material(key_info)?;
+    if let Ok(text) = std::str::from_utf8(&material) {
+        let text = text.trim();
+        if let Ok(key) = P256PublicKey::from_public_key_pem(text) {
+            return Ok(key);
+        }
+    }
+    if let Ok(key) = P256PublicKey::from_public_key_der(&material) {
+        return Ok(key);
+    }
+    if let Some(spki_der) = extract_spki_from_certificate(&material)
+        && let Ok(key) = P256PublicKey::from_public_key_der(&spki_der)
+    {
+        return Ok(key);
+    }
+    Err("unable to parse ECDSA P-256 public key or certificate".to_string())
+}
+
 fn extract_spki_from_certificate(material: &[u8]) -> Option<Vec<u8>> {
     if let Ok(cert) = Certificate::from_pem(material) {
-        return cert
-            .tbs_certificate
-            .subject_public_key_info
-            .to_der()
-            .ok();
+        return cert.tbs_certificate.subject_public_key_info.to_der().ok();
     }
     if let Ok(cert) = Certificate::from_der(material) {
-        return cert
-            .tbs_certificate
-            .subject_public_key_info
-            .to_der()
-            .ok();
+        return cert.tbs_certificate.subject_public_key_info.to_der().ok();
     }
     None
 }
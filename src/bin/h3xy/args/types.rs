@@ -1,27 +1,52 @@
 use std::path::PathBuf;
 
-use crate::Range;
+use crate::{Range, RangeSpec};
 
-use super::parse::parse_option;
+use super::parse::{parse_option, set_output_file};
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Args {
-    // Input
+    // Input; may be a `*`/`?`/`[...]` pattern, like the /MO and /MT files
     pub input_file: Option<PathBuf>,
-
-    // Output (special: uses space separator)
-    pub output_file: Option<PathBuf>,
+    // Further positional inputs, merged transparently after the first in order
+    pub extra_inputs: Vec<PathBuf>,
 
     // INI file: /P:file
     pub ini_file: Option<PathBuf>,
-
-    // Error log: /E=file
+    // [FORDHEADER] values for /XF that win over the INI: /FORD:key=value
+    // (repeatable; `_` may stand for a space in the key)
+    pub ford_header: Vec<(String, String)>,
+    // Values for `${NAME}` in the INI, ahead of the environment:
+    // /DEF:NAME=value (repeatable)
+    pub defines: Vec<(String, String)>,
+
+    // Error log: /E=file, or /E+=file to append to it
     pub error_log: Option<PathBuf>,
+    pub error_log_append: bool,
 
     // Silent mode: /S
     pub silent: bool,
+    // Fail when anything raised a warning: /WERROR
+    pub werror: bool,
+    // Print each pipeline step to stderr: /VERBOSE (1), -vv (2, with segments)
+    pub verbose: u8,
     // Write version string to error log: /V
     pub write_version: bool,
+    // Print the effective command line to stdout: /ECHOARGS
+    pub echo_args: bool,
+    // Print a summary of the loaded input and exit: /INFO
+    pub info: bool,
+    // Print the option reference and exit: /? or /HELP[:option]
+    pub help: Option<String>,
+
+    // Place ELF input segments at their virtual address: /ELFVMA
+    pub elf_use_vma: bool,
+
+    // Tolerate junk lines and a missing/early EOF in Intel-HEX input: /LENIENT
+    pub lenient: bool,
+
+    // Force the input parser instead of auto-detecting: /IT:IHEX|SREC|BIN|HEXASCII|I16
+    pub input_format: Option<InputFormat>,
 
     // Import 16-bit Intel HEX: /II2=file
     pub import_i16: Option<PathBuf>,
@@ -29,31 +54,48 @@ pub struct Args {
     pub import_binary: Option<ImportParam>,
     // Import HEX ASCII: /IA:file[;offset]
     pub import_hex_ascii: Option<ImportParam>,
+    // Read a bank-switched input back to linear addresses: /UNBANK:window_start,window_size[,first]
+    pub unbank: Option<BankParams>,
 
-    // Address mapping
-    pub remap: Option<RemapParams>,
-    pub s08_map: bool,
-    pub s12_map: bool,
-    pub s12x_map: bool,
+    // Address mapping: /S08MAP, /S12MAP or /S12XMAP, then each /REMAP in order
+    pub remap: Vec<RemapParams>,
+    pub s08_map: Option<PageMapParams>,
+    pub s12_map: Option<PageMapParams>,
+    pub s12x_map: Option<PageMapParams>,
+    // Address table, applied after the /REMAPs: /MAPTAB:file
+    pub map_table: Option<PathBuf>,
 
     // Fill ranges: /FR:'range' with /FP:pattern
-    pub fill_ranges: Vec<Range>,
-    pub fill_pattern: Vec<u8>,
+    pub fill_ranges: Vec<RangeSpec>,
+    pub fill_pattern: crate::FillPattern,
+    // Pattern read from a file: /FP:@file (replaces fill_pattern when set)
+    pub fill_pattern_file: Option<PathBuf>,
     pub fill_pattern_set: bool,
+    // Fixed seed for random fill: /FRSEED:xxx (default: seeded from the clock)
+    pub fill_seed: Option<u64>,
 
     // Cut ranges: /CR:'range1':'range2'
-    pub cut_ranges: Vec<Range>,
+    pub cut_ranges: Vec<RangeSpec>,
 
     // Merge: /MO:file[;offset] or /MT:file[;offset]
     pub merge_opaque: Vec<MergeParam>,
     pub merge_transparent: Vec<MergeParam>,
 
+    // Block labels, applied in order after the merges: /LABEL:'range';name
+    pub labels: Vec<LabelParams>,
+
     // Address range filter: /AR:'range'
-    pub address_range: Vec<Range>,
+    pub address_range: Vec<RangeSpec>,
 
     // Log file: /L:file
     pub log_file: Option<PathBuf>,
 
+    // Pipeline description, run after the checksums: /PIPE:file
+    pub pipeline_file: Option<PathBuf>,
+
+    // Record the run as /L commands: /LOGOUT:file
+    pub log_output: Option<PathBuf>,
+
     // Single region: /FA
     pub fill_all: bool,
 
@@ -65,24 +107,49 @@ pub struct Args {
     pub align_length: bool,
     pub align_fill: u8,
     pub align_erase: Option<u32>, // /AE:zzzz
+    // Flash sector layout for erase lists, instead of /AE: /AEMAP:file
+    pub erase_sector_map: Option<PathBuf>,
+
+    // Stamps, written after /AR and before checksums (strings first):
+    // /WS:addr;text[;NUL][;WIDTH=n][;FILL=xx], /WDATE:addr;BCD:pattern|ASCII:pattern
+    pub write_strings: Vec<WriteStringParams>,
+    pub write_dates: Vec<WriteDateParams>,
+    // Date for /WDATE and the /XF release date: /DATE:YYYY-MM-DD[Thh:mm:ss]
+    // (default: system clock, UTC)
+    pub date: Option<crate::DateStamp>,
 
     // Checksum: /CSx[:target] or /CSRx[:target] (little-endian, default target @append)
-    pub checksum: Option<ChecksumParams>,
+    // (repeatable, applied in command-line order)
+    pub checksum: Vec<ChecksumParams>,
     // Multi-checksum: /CSMx[:target] or /CSMRx[:target] (repeatable, ordered)
     pub checksum_multi: Vec<ChecksumParams>,
 
-    // Data processing (signature subset): /DPn[:@placement]:param[,section,key][;outfilename]
+    // Data processing (signature/MAC subset): /DPn[:@placement]:key[,password][;outfilename]
     pub data_processing: Option<DataProcessingParams>,
     // Signature verification: /SVn:keyinfo!signatureinfo
     pub signature_verify: Option<SignatureVerifyParams>,
+    // Compare the result with a reference file: /CMP:file
+    pub compare_file: Option<PathBuf>,
+    // Linker map for @SYM: checksum targets: /MAPFILE:file
+    pub map_file: Option<PathBuf>,
 
     // Split blocks: /sb:size
     pub split_block_size: Option<u32>,
+    // Split blocks at address multiples: /SBA:boundary
+    pub split_boundary: Option<u32>,
 
     // Large file thresholds (performance tuning)
     pub big_hex_file_threshold_kb: Option<u32>, // /BHFCT=xxx
     pub buffer_to_file_threshold_kb: Option<u32>, // /BTFST=xxx
-    pub temp_buffer_size_kb: Option<u32>,       // /BTBS=xxx
+    // Memory-map /IN input regardless of size: /MMAP
+    pub mmap_input: bool,
+    // Derive a missing -o from the input path: /INPLACE or --in-place
+    pub derive_output_path: bool,
+    // Create missing parent directories of output files: /MKDIR
+    pub create_output_dirs: bool,
+    pub temp_buffer_size_kb: Option<u32>, // /BTBS=xxx
+    // Maximum decompressed size of gzip input in MB: /GZMAX=xxx (default 512)
+    pub gzip_limit_mb: Option<u32>,
 
     // Byte swap: /swapword or /swaplong
     pub swap_word: bool,
@@ -92,28 +159,105 @@ pub struct Args {
     pub dspic_expand: Vec<DspicOp>,
     pub dspic_shrink: Vec<DspicOp>,
     pub dspic_clear_ghost: Vec<Range>,
+    // Ghost byte insertion: /CDSPI:'range'[;value] (value defaults to 0x00)
+    pub dspic_insert_ghost: Vec<DspicGhostOp>,
 
-    // Output format (only one allowed)
-    pub output_format: Option<OutputFormat>,
+    // Exports in command-line order: /Xx[...] each with its own -o file
+    pub outputs: Vec<OutputSpec>,
 
     // Output format options
-    pub bytes_per_line: Option<u8>,
+    // Start address for /XI and /XS: /ENTRY:addr (default: from the input)
+    pub entry_point: Option<u32>,
+    // Line ending for /XI, /XI2, /XS and /XA: /CRLF (default) or /LF
+    pub line_ending: crate::LineEnding,
+    // Lowercase hex digits for /XI, /XI2, /XS and /XA: /LOWER
+    pub lowercase_hex: bool,
+    // Start /XI and /XS data records at multiples of the record length: /ALIGNREC
+    pub align_records: bool,
+    // Bank-switched export, applied just before writing: /BANK:window_start,window_size[,first]
+    pub bank: Option<BankParams>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    IntelHex,
+    SRecord,
+    Binary,
+    HexAscii,
+    IntelHex16,
+}
+
+impl InputFormat {
+    /// Parse an /IT value (case-insensitive).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "IHEX" => Some(Self::IntelHex),
+            "SREC" => Some(Self::SRecord),
+            "BIN" => Some(Self::Binary),
+            "HEXASCII" => Some(Self::HexAscii),
+            "I16" => Some(Self::IntelHex16),
+            _ => None,
+        }
+    }
+
+    /// Human-readable name, for /INFO.
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::IntelHex => "Intel HEX",
+            Self::SRecord => "S-Record",
+            Self::Binary => "binary",
+            Self::HexAscii => "HEX ASCII",
+            Self::IntelHex16 => "16-bit Intel HEX",
+        }
+    }
+
+    /// The /IT value naming this format.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::IntelHex => "IHEX",
+            Self::SRecord => "SREC",
+            Self::Binary => "BIN",
+            Self::HexAscii => "HEXASCII",
+            Self::IntelHex16 => "I16",
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MergeParam {
     pub file: PathBuf,
     pub offset: Option<i64>,
     pub range: Option<Range>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ImportParam {
     pub file: PathBuf,
     pub offset: u32,
 }
 
-#[derive(Debug, Clone)]
+/// /S12MAP etc. alone use the standard windows;
+/// `:ppage_base,window_start,window_size,linear_base` maps custom pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageMapParams {
+    Standard,
+    Paged {
+        ppage_base: u8,
+        window_start: u32,
+        window_size: u32,
+        linear_base: u32,
+    },
+}
+
+/// /BANK and /UNBANK: banks fill the whole CPU window and are numbered from
+/// `first_bank` (default 0).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BankParams {
+    pub window: Range,
+    pub first_bank: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RemapParams {
     pub start: u32,
     pub end: u32,
@@ -122,23 +266,143 @@ pub struct RemapParams {
     pub inc: u32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ChecksumParams {
     pub algorithm: u8,
     pub target: ChecksumTarget,
     pub little_endian: bool,
-    pub range: Option<Range>,
+    // START/END resolve against the data when the checksum runs
+    pub range: Option<RangeSpec>,
     pub forced_range: Option<ForcedRange>,
     pub exclude_ranges: Vec<Range>,
+    // ;X suffix: checksum target bytes count as fill instead of being skipped
+    pub target_as_fill: bool,
+    // ;FILL=xx suffix (or ;FILL=NONE to reject gaps)
+    pub gap_fill: ChecksumGapFill,
+    // ;INIT=xx suffix: sum seed or previous CRC to continue from
+    pub initial: Option<u64>,
+    // /CSB: checksum each block separately instead of the whole image
+    pub per_block: bool,
+    // ;FMT=HEX|BIN|C|DEC suffix: layout of a file or @stdout result
+    pub format: ChecksumFormat,
+}
+
+/// Layout of a /CS result written to a file or `@stdout`: `;FMT=...`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChecksumFormat {
+    /// Comma-separated hex bytes, `AB,CD`.
+    #[default]
+    List,
+    /// Hex digits, `ABCD`.
+    Hex,
+    /// The raw bytes.
+    Bin,
+    /// `#define CHECKSUM 0xABCD`; a brace list of bytes above 8 bytes.
+    C,
+    /// The bytes read as one big-endian number, in decimal.
+    Dec,
+}
+
+impl ChecksumFormat {
+    /// Parse a `FMT=` value (case-insensitive).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "LIST" => Some(Self::List),
+            "HEX" => Some(Self::Hex),
+            "BIN" => Some(Self::Bin),
+            "C" => Some(Self::C),
+            "DEC" => Some(Self::Dec),
+            _ => None,
+        }
+    }
+
+    /// The `FMT=` value naming this format.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::List => "LIST",
+            Self::Hex => "HEX",
+            Self::Bin => "BIN",
+            Self::C => "C",
+            Self::Dec => "DEC",
+        }
+    }
+
+    /// Lay out checksum results, e.g. entries of
+    /// [`ExecuteOutput::checksum_bytes`](super::ExecuteOutput). Several
+    /// results (one per /CSB block) go on separate lines, or back to back
+    /// for `Bin`. Text has no trailing newline.
+    pub fn format(self, results: &[Vec<u8>]) -> Vec<u8> {
+        if self == Self::Bin {
+            return results.concat();
+        }
+        let hex = |result: &[u8], sep: &str| {
+            result
+                .iter()
+                .map(|b| format!("{b:02X}"))
+                .collect::<Vec<_>>()
+                .join(sep)
+        };
+        results
+            .iter()
+            .enumerate()
+            .map(|(index, result)| match self {
+                Self::List => hex(result, ","),
+                Self::Hex => hex(result, ""),
+                Self::C => {
+                    let name = if results.len() == 1 {
+                        "CHECKSUM".to_string()
+                    } else {
+                        format!("CHECKSUM_{index}")
+                    };
+                    if result.len() <= 8 {
+                        format!("#define {name} 0x{}", hex(result, ""))
+                    } else {
+                        format!("#define {name} {{ 0x{} }}", hex(result, ", 0x"))
+                    }
+                }
+                Self::Dec => decimal(result),
+                Self::Bin => unreachable!("handled above"),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+            .into_bytes()
+    }
 }
 
-#[derive(Debug, Clone)]
+/// `bytes` as a big-endian unsigned number of any length, in decimal.
+fn decimal(bytes: &[u8]) -> String {
+    // Base-10 digits, least significant first.
+    let mut digits = vec![0u8];
+    for &byte in bytes {
+        let mut carry = u32::from(byte);
+        for digit in &mut digits {
+            let value = u32::from(*digit) * 256 + carry;
+            *digit = (value % 10) as u8;
+            carry = value / 10;
+        }
+        while carry > 0 {
+            digits.push((carry % 10) as u8);
+            carry /= 10;
+        }
+    }
+    digits.iter().rev().map(|d| char::from(b'0' + d)).collect()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChecksumGapFill {
+    #[default]
+    Skip,
+    Error,
+    Fill(u8),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ForcedRange {
     pub range: Range,
     pub pattern: Vec<u8>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ChecksumTarget {
     Address(u32),
     Append,
@@ -146,44 +410,111 @@ pub enum ChecksumTarget {
     Prepend,
     OverwriteEnd,
     File(PathBuf),
+    /// `@SYM:<name>`: the address of a linker symbol from /MAPFILE.
+    Symbol(String),
+    /// `@stdout`: print the result, even with /S.
+    Stdout,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DataProcessingParams {
     pub method: u8,
     pub placement: Option<ChecksumTarget>,
     pub key_info: String,
     pub output_file: Option<PathBuf>,
+    // Signed span: ;range[/exclude...] after the output file (default: all data)
+    pub range: Option<Range>,
+    pub exclude: Vec<Range>,
+    // /DPEXT: command that signs the payload instead of key_info (method 0)
+    pub external_command: Option<String>,
+    // /DPEXT<len>: signature length the command must produce
+    pub signature_len: Option<usize>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SignatureVerifyParams {
     pub method: u8,
     pub key_info: String,
     pub signature_info: String,
+    // Verified span: ;range[/exclude...] after the signature info
+    pub range: Option<Range>,
+    pub exclude: Vec<Range>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteStringParams {
+    pub address: u32,
+    pub text: String,
+    pub pad: crate::AsciiPad,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteDateParams {
+    pub address: u32,
+    pub format: crate::DateFormat,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DspicOp {
     pub range: Range,
     pub target: Option<u32>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DspicGhostOp {
+    pub range: Range,
+    pub value: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelParams {
+    pub range: Range,
+    pub name: String,
+}
+
+/// One export. `-o` pairs with the /Xx before or after it; a second /Xx
+/// before that pair has its file is an error.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutputSpec {
+    /// `None` keeps the input format.
+    pub format: Option<OutputFormat>,
+    /// `None` derives the path from the input with /INPLACE and is an
+    /// error otherwise.
+    pub file: Option<PathBuf>,
+    // Record/line length given with the format: /XI:len, /XS:len, /XD:len
+    pub bytes_per_line: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum OutputFormat {
     IntelHex {
         record_type: Option<u8>,
     }, // /XI[:len[:type]]
+    IntelHex16 {
+        record_type: Option<u8>,
+    }, // /XI2[:len[:type]]
     SRecord {
         record_type: Option<u8>,
-    }, // /XS[:len[:type]]
-    Binary, // /XN
+        header: Option<String>,
+    }, // /XS[:len[:type]][:hdr=name]
+    Binary {
+        start: Option<u32>,
+        end: Option<u32>,
+        fill: Option<u8>,
+    }, // /XN[:start[,end[,fill]]]
+    BinaryRaw, // /XN:RAW
+    HexDump,   // /XD[:len]
+    Json,      // /XJ
+    Csv,       // /XCSV
     HexAscii {
         line_length: Option<u32>,
         separator: Option<String>,
-    }, // /XA
-    CCode,  // /XC
-    FordIntelHex, // /XF
+        address_prefix: Option<crate::AddressPrefixStyle>,
+    }, // /XA[:len[:separator[:ADDR|PLAIN]]]
+    CCode,     // /XC
+    FordIntelHex {
+        relaxed: bool,
+    }, // /XF[:RELAXED]
     GmHeader {
         addr: Option<u32>,
     }, // /XG
@@ -193,14 +524,74 @@ pub enum OutputFormat {
     GmHeaderCal {
         addr: Option<u32>,
     }, // /XGCC
-    Gac,    // /XGAC
-    GacSwil, // /XGACSWIL
+    Gac,       // /XGAC
+    GacSwil,   // /XGACSWIL
     FlashKernel, // /XK
-    Porsche, // /XP
-    SeparateBinary, // /XSB
-    Vag,    // /XV
-    Vbf,    // /XVBF
-    FiatBin, // /XB
+    Porsche,   // /XP
+    SeparateBinary {
+        template: Option<crate::FileNameTemplate>,
+    }, // /XSB[:template]
+    Vag,       // /XV
+    Vbf,       // /XVBF
+    FiatBin,   // /XB
+    RustCode,  // /XR
+}
+
+impl OutputFormat {
+    /// The /X option that selects this format, for messages.
+    pub fn option(&self) -> &'static str {
+        match self {
+            Self::IntelHex { .. } => "/XI",
+            Self::IntelHex16 { .. } => "/XI2",
+            Self::SRecord { .. } => "/XS",
+            Self::Binary { .. } | Self::BinaryRaw => "/XN",
+            Self::HexDump => "/XD",
+            Self::Json => "/XJ",
+            Self::Csv => "/XCSV",
+            Self::HexAscii { .. } => "/XA",
+            Self::CCode => "/XC",
+            Self::FordIntelHex { .. } => "/XF",
+            Self::GmHeader { .. } => "/XG",
+            Self::GmHeaderOs { .. } => "/XGC",
+            Self::GmHeaderCal { .. } => "/XGCC",
+            Self::Gac => "/XGAC",
+            Self::GacSwil => "/XGACSWIL",
+            Self::FlashKernel => "/XK",
+            Self::Porsche => "/XP",
+            Self::SeparateBinary { .. } => "/XSB",
+            Self::Vag => "/XV",
+            Self::Vbf => "/XVBF",
+            Self::FiatBin => "/XB",
+            Self::RustCode => "/XR",
+        }
+    }
+
+    /// Extension of a path derived from the input with /INPLACE.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::IntelHex { .. }
+            | Self::IntelHex16 { .. }
+            | Self::FordIntelHex { .. }
+            | Self::GmHeader { .. }
+            | Self::GmHeaderOs { .. }
+            | Self::GmHeaderCal { .. } => "hex",
+            Self::SRecord { .. } => "s19",
+            Self::Binary { .. }
+            | Self::BinaryRaw
+            | Self::FlashKernel
+            | Self::Porsche
+            | Self::SeparateBinary { .. }
+            | Self::Vag
+            | Self::FiatBin => "bin",
+            Self::HexDump | Self::HexAscii { .. } => "txt",
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::CCode => "c",
+            Self::Gac | Self::GacSwil => "gac",
+            Self::Vbf => "vbf",
+            Self::RustCode => "rs",
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -209,8 +600,12 @@ pub enum ParseArgError {
     InvalidOption(String),
     InvalidRange(String),
     InvalidNumber(String),
+    /// A malformed /XSB file name template: option, then what and where.
+    InvalidTemplate(String),
     DuplicateOutputFormat,
     MissingValue(String),
+    /// An export has no `-o` and /INPLACE is not set; holds its /X option.
+    MissingOutputFile(&'static str),
 }
 
 impl std::fmt::Display for ParseArgError {
@@ -220,8 +615,13 @@ impl std::fmt::Display for ParseArgError {
             Self::InvalidOption(s) => write!(f, "invalid option: {s}"),
             Self::InvalidRange(s) => write!(f, "invalid range: {s}"),
             Self::InvalidNumber(s) => write!(f, "invalid number: {s}"),
+            Self::InvalidTemplate(s) => write!(f, "invalid file name template: {s}"),
             Self::DuplicateOutputFormat => write!(f, "multiple output formats specified"),
             Self::MissingValue(s) => write!(f, "missing value for {s}"),
+            Self::MissingOutputFile(option) => write!(
+                f,
+                "output file required for {option} (use -o <file>, or /INPLACE to derive it from the input)"
+            ),
         }
     }
 }
@@ -264,7 +664,6 @@ impl Args {
         F: Fn(&str) -> bool,
     {
         let mut result = Args {
-            fill_pattern: vec![0xFF],
             fill_pattern_set: false,
             align_fill: 0xFF,
             ..Default::default()
@@ -284,39 +683,51 @@ impl Args {
                 let next = args_iter
                     .next()
                     .ok_or(ParseArgError::MissingValue("-o".into()))?;
-                result.output_file = Some(PathBuf::from(next));
+                set_output_file(&mut result, PathBuf::from(next));
                 continue;
             }
 
-            if force_positional {
-                if result.input_file.is_none() {
-                    result.input_file = Some(PathBuf::from(arg));
-                    continue;
-                }
-                return Err(ParseArgError::InvalidOption(arg.clone()));
+            if force_positional || is_unix_path(arg) {
+                result.push_input(PathBuf::from(arg));
+                continue;
             }
 
             if let Some(opt) = arg.strip_prefix('/').or_else(|| arg.strip_prefix('-')) {
                 match parse_option(&mut result, opt) {
                     Ok(()) => {}
+                    Err(ParseArgError::InvalidOption(_)) if is_existing_abs_path(arg) => {
+                        result.push_input(PathBuf::from(arg));
+                    }
                     Err(ParseArgError::InvalidOption(_)) => {
-                        if result.input_file.is_none() && is_existing_abs_path(arg) {
-                            result.input_file = Some(PathBuf::from(arg));
-                        } else {
-                            return Err(ParseArgError::InvalidOption(arg.clone()));
-                        }
+                        return Err(ParseArgError::InvalidOption(arg.clone()));
                     }
                     Err(e) => return Err(e),
                 }
-            } else if result.input_file.is_none() {
-                result.input_file = Some(PathBuf::from(arg));
             } else {
-                return Err(ParseArgError::InvalidOption(arg.clone()));
+                result.push_input(PathBuf::from(arg));
             }
         }
 
         Ok(result)
     }
+
+    fn push_input(&mut self, path: PathBuf) {
+        if self.input_file.is_none() {
+            self.input_file = Some(path);
+        } else {
+            self.extra_inputs.push(path);
+        }
+    }
+}
+
+/// `/srv/builds/app.hex` is a file, not an option: no option name contains
+/// a `/`, so one before any `:` or `=` value means a path.
+fn is_unix_path(arg: &str) -> bool {
+    arg.strip_prefix('/').is_some_and(|rest| {
+        rest.split([':', '='])
+            .next()
+            .is_some_and(|name| name.contains('/'))
+    })
 }
 
 fn split_cli_args(input: &str) -> Result<Vec<String>, ParseArgError> {
@@ -381,9 +792,38 @@ fn split_cli_args(input: &str) -> Result<Vec<String>, ParseArgError> {
 
 #[cfg(test)]
 mod tests {
-    use super::Args;
+    use super::{Args, ChecksumFormat, ParseArgError};
     use std::path::PathBuf;
 
+    #[test]
+    fn test_checksum_format_layouts() {
+        let crc = vec![vec![0xCB, 0xF4, 0x39, 0x26]];
+        let text = |format: ChecksumFormat, results: &[Vec<u8>]| {
+            String::from_utf8(format.format(results)).unwrap()
+        };
+        assert_eq!(text(ChecksumFormat::List, &crc), "CB,F4,39,26");
+        assert_eq!(text(ChecksumFormat::Hex, &crc), "CBF43926");
+        assert_eq!(text(ChecksumFormat::Dec, &crc), "3421780262");
+        assert_eq!(text(ChecksumFormat::C, &crc), "#define CHECKSUM 0xCBF43926");
+        assert_eq!(ChecksumFormat::Bin.format(&crc), crc[0]);
+
+        let blocks = vec![vec![0x00, 0x0A], vec![0x01, 0x00]];
+        assert_eq!(text(ChecksumFormat::Hex, &blocks), "000A\n0100");
+        assert_eq!(text(ChecksumFormat::Dec, &blocks), "10\n256");
+        assert_eq!(
+            text(ChecksumFormat::C, &blocks),
+            "#define CHECKSUM_0 0x000A\n#define CHECKSUM_1 0x0100"
+        );
+        assert_eq!(
+            ChecksumFormat::Bin.format(&blocks),
+            vec![0x00, 0x0A, 0x01, 0x00]
+        );
+
+        let digest = vec![vec![0xFF; 16]];
+        assert_eq!(text(ChecksumFormat::Dec, &digest), u128::MAX.to_string());
+        assert!(text(ChecksumFormat::C, &digest).starts_with("#define CHECKSUM { 0xFF, 0xFF,"));
+    }
+
     #[test]
     fn test_parse_double_dash_forces_positional() {
         let args = vec!["--".to_string(), "/tmp/input.hex".to_string()];
@@ -401,4 +841,30 @@ mod tests {
         assert_eq!(parsed.input_file, Some(path.clone()));
         let _ = std::fs::remove_file(path);
     }
+
+    #[test]
+    fn test_parse_absolute_path_missing_file() {
+        let args = vec![
+            "/srv/builds/app.hex".to_string(),
+            "/MO:/tmp/cal.hex".to_string(),
+            "/XI".to_string(),
+        ];
+        let parsed = Args::parse_from_with(args, |_| false).unwrap();
+        assert_eq!(
+            parsed.input_file,
+            Some(PathBuf::from("/srv/builds/app.hex"))
+        );
+        assert_eq!(parsed.merge_opaque[0].file, PathBuf::from("/tmp/cal.hex"));
+    }
+
+    #[test]
+    fn test_parse_invalid_option_still_rejected() {
+        for arg in ["/BOGUS", "/BOGUS:a/b", "/app.hex"] {
+            let err = Args::parse_from_with(vec![arg.to_string()], |_| false).unwrap_err();
+            assert!(
+                matches!(&err, ParseArgError::InvalidOption(s) if s == arg),
+                "{arg}: {err}"
+            );
+        }
+    }
 }
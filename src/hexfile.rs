@@ -1,3 +1,6 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
 use thiserror::Error;
 
 use crate::Segment;
@@ -16,6 +19,26 @@ pub enum HexFileError {
     },
 }
 
+/// Input format a `HexFile` was parsed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SourceFormat {
+    IntelHex,
+    SRecord,
+}
+
+/// Non-data fields carried over from the parsed input so a format conversion
+/// can write them back out. Writers use these when their own option is unset.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexFileMetadata {
+    /// Start address from an Intel-HEX type 03/05 or S7-S9 record.
+    pub entry_point: Option<u32>,
+    /// Module name from an S-Record S0 header.
+    pub header: Option<String>,
+    pub source_format: Option<SourceFormat>,
+}
+
 /// A collection of memory segments.
 ///
 /// Segments may overlap and preserve insertion order. Operations that iterate raw segments
@@ -26,27 +49,64 @@ pub enum HexFileError {
 ///
 /// Use `append_segment` for high-priority data (wins on overlap).
 /// Use `prepend_segment` for low-priority data (loses on overlap).
-#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HexFile {
     segments: Vec<Segment>,
+    metadata: HexFileMetadata,
+    /// Set while the segments are known to be normalized; any mutable access
+    /// clears it. Lets lookups binary-search without re-checking the layout.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    known_normalized: bool,
+}
+
+impl PartialEq for HexFile {
+    fn eq(&self, other: &Self) -> bool {
+        self.segments == other.segments && self.metadata == other.metadata
+    }
 }
 
+impl Eq for HexFile {}
+
 impl HexFile {
     pub fn new() -> Self {
-        Self { segments: vec![] }
+        Self::default()
     }
 
     pub fn with_segments(segments: Vec<Segment>) -> Self {
         Self {
             segments: segments.into_iter().filter(|s| !s.is_empty()).collect(),
+            metadata: HexFileMetadata::default(),
+            known_normalized: false,
+        }
+    }
+
+    fn from_normalized(segments: Vec<Segment>) -> Self {
+        Self {
+            segments,
+            metadata: HexFileMetadata::default(),
+            known_normalized: true,
         }
     }
 
+    pub fn metadata(&self) -> &HexFileMetadata {
+        &self.metadata
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut HexFileMetadata {
+        &mut self.metadata
+    }
+
+    pub fn set_metadata(&mut self, metadata: HexFileMetadata) {
+        self.metadata = metadata;
+    }
+
     pub fn segments(&self) -> &[Segment] {
         &self.segments
     }
 
     pub fn segments_mut(&mut self) -> &mut Vec<Segment> {
+        self.known_normalized = false;
         &mut self.segments
     }
 
@@ -56,6 +116,7 @@ impl HexFile {
 
     pub fn set_segments(&mut self, segments: Vec<Segment>) {
         self.segments = segments.into_iter().filter(|s| !s.is_empty()).collect();
+        self.known_normalized = false;
     }
 
     /// Add segment with HIGH priority (wins on overlap after normalize).
@@ -64,6 +125,7 @@ impl HexFile {
             return;
         }
         self.segments.push(segment);
+        self.known_normalized = false;
     }
 
     /// Add segment with LOW priority (loses on overlap after normalize).
@@ -72,6 +134,7 @@ impl HexFile {
             return;
         }
         self.segments.insert(0, segment);
+        self.known_normalized = false;
     }
 
     pub fn is_empty(&self) -> bool {
@@ -108,7 +171,7 @@ impl HexFile {
         self.segments.iter().map(|s| s.len()).sum()
     }
 
-    /// Returns sorted/merged copy. Errors if any segments overlap.
+    /// Returns sorted/merged copy of the segments. Errors if any segments overlap.
     pub fn normalized(&self) -> Result<HexFile, HexFileError> {
         let mut sorted: Vec<_> = self.segments.iter().filter(|s| !s.is_empty()).collect();
         if sorted.is_empty() {
@@ -129,7 +192,7 @@ impl HexFile {
                         new_end: seg.end_address(),
                     });
                 }
-                if last.is_contiguous_with(seg) {
+                if last.joins(seg) {
                     last.data.extend_from_slice(&seg.data);
                     continue;
                 }
@@ -137,12 +200,51 @@ impl HexFile {
             merged.push(seg.clone());
         }
 
-        Ok(HexFile { segments: merged })
+        Ok(HexFile::with_segments(merged))
+    }
+
+    /// True if the segments already are what `normalized_lossy()` returns:
+    /// non-empty, sorted, within u32 and separated by gaps or a change of
+    /// label.
+    pub fn is_normalized(&self) -> bool {
+        self.known_normalized
+            || self
+                .segments
+                .iter()
+                .all(|s| !s.is_empty() && s.start_address as u64 + s.len() as u64 <= 1 << 32)
+                && self.segments.windows(2).all(|pair| {
+                    let next = pair[0].end_address() as u64 + 1;
+                    next < pair[1].start_address as u64
+                        || next == pair[1].start_address as u64 && pair[0].name != pair[1].name
+                })
+    }
+
+    /// Normalize in place (last wins), so later read-only consumers can
+    /// borrow the segments instead of each building a normalized copy.
+    /// Does nothing if the file is already normalized.
+    pub fn ensure_normalized(&mut self) {
+        if !self.is_normalized() {
+            self.segments = self.normalized_lossy().into_segments();
+        }
+        self.known_normalized = true;
+    }
+
+    /// The `normalized_lossy()` segments, borrowed when the file is already
+    /// normalized.
+    pub fn normalized_segments(&self) -> Cow<'_, [Segment]> {
+        if self.is_normalized() {
+            Cow::Borrowed(&self.segments)
+        } else {
+            Cow::Owned(self.normalized_lossy().into_segments())
+        }
     }
 
     /// Returns sorted/merged copy. Later-inserted segments overwrite earlier ones on overlap.
     /// Bytes that would overflow u32 address space are silently dropped.
     pub fn normalized_lossy(&self) -> HexFile {
+        if self.is_normalized() {
+            return HexFile::from_normalized(self.segments.clone());
+        }
         let mut truncated: Vec<Segment> = self
             .segments
             .iter()
@@ -167,35 +269,33 @@ impl HexFile {
 
         if !has_overlap {
             truncated.sort_by_key(|s| s.start_address);
-            return HexFile {
-                segments: merge_adjacent_segments(truncated),
-            };
+            return HexFile::from_normalized(merge_adjacent_segments(truncated));
         }
 
-        let mut merged: Vec<Segment> = Vec::new();
-        for seg in truncated {
-            merged = overlay_segment(merged, seg);
-        }
-        merged.sort_by_key(|s| s.start_address);
-        HexFile {
-            segments: merge_adjacent_segments(merged),
-        }
+        HexFile::from_normalized(merge_adjacent_segments(overlay_segments(truncated)))
     }
 
-    /// Count gaps between segments (after sorting).
+    /// Count gaps between segments (after sorting). Labelled neighbours
+    /// with no bytes between them are not a gap.
     pub fn gap_count(&self) -> usize {
-        let segments = self.normalized_lossy().into_segments();
-        if segments.len() <= 1 {
-            return 0;
-        }
-        segments.len() - 1
+        self.normalized_segments()
+            .windows(2)
+            .filter(|pair| !pair[0].is_contiguous_with(&pair[1]))
+            .count()
     }
 
     // --- Address-based access ---
 
     /// Read a single byte at address. Returns None if address is not covered by any segment.
     /// If multiple segments overlap, the most recently added segment wins.
+    ///
+    /// O(log n) after `ensure_normalized()` or on a `normalized_lossy()` copy;
+    /// otherwise every segment is scanned.
     pub fn read_byte(&self, addr: u32) -> Option<u8> {
+        if self.known_normalized {
+            let seg = find_segment(&self.segments, addr)?;
+            return Some(seg.data[(addr - seg.start_address) as usize]);
+        }
         for seg in self.segments.iter().rev() {
             if seg.is_empty() {
                 continue;
@@ -228,25 +328,29 @@ impl HexFile {
         let end = addr
             .checked_add(len as u32)
             .and_then(|v| v.checked_sub(1))?;
-        let normalized = self.normalized_lossy();
-        for segment in normalized.segments() {
-            if end < segment.start_address {
-                break;
-            }
-            if addr >= segment.start_address && end <= segment.end_address() {
-                let offset = (addr - segment.start_address) as usize;
-                return Some(segment.data[offset..offset + len].to_vec());
-            }
+        let normalized = self.normalized_segments();
+        let segment = find_segment(&normalized, addr)?;
+        if end > segment.end_address() {
+            return None;
         }
-        None
+        let offset = (addr - segment.start_address) as usize;
+        Some(segment.data[offset..offset + len].to_vec())
     }
 
-    /// Write bytes at address. Creates new segment, will overlap with existing data.
+    /// Write bytes at address with the highest priority. A write that touches or
+    /// overlaps the last segment is merged into it; otherwise it becomes a new
+    /// segment that may overlap existing data.
     /// Use normalized_lossy() after to merge and resolve overlaps.
     pub fn write_bytes(&mut self, addr: u32, data: &[u8]) {
         if data.is_empty() {
             return;
         }
+        self.known_normalized = false;
+        if let Some(last) = self.segments.last_mut()
+            && coalesce_write(last, addr, data)
+        {
+            return;
+        }
         self.segments.push(Segment::new(addr, data.to_vec()));
     }
 
@@ -270,6 +374,13 @@ impl HexFile {
     }
 }
 
+/// The segment holding `addr` in sorted, non-overlapping `segments`.
+fn find_segment(segments: &[Segment], addr: u32) -> Option<&Segment> {
+    let index = segments.partition_point(|s| s.start_address <= addr);
+    let segment = segments[..index].last()?;
+    (addr <= segment.end_address()).then_some(segment)
+}
+
 fn truncate_segment_to_u32(segment: &Segment) -> Option<Segment> {
     if segment.is_empty() {
         return None;
@@ -278,9 +389,7 @@ fn truncate_segment_to_u32(segment: &Segment) -> Option<Segment> {
         .saturating_sub(segment.start_address as u64)
         .saturating_add(1);
     let data_len_u64 = segment.data.len() as u64;
-    let len_u64 = data_len_u64
-        .min(max_len_u64)
-        .min(usize::MAX as u64);
+    let len_u64 = data_len_u64.min(max_len_u64).min(usize::MAX as u64);
     if len_u64 == 0 {
         return None;
     }
@@ -288,17 +397,14 @@ fn truncate_segment_to_u32(segment: &Segment) -> Option<Segment> {
     if len == segment.data.len() {
         return Some(segment.clone());
     }
-    Some(Segment::new(
-        segment.start_address,
-        segment.data[..len].to_vec(),
-    ))
+    Some(segment.piece(segment.start_address, segment.data[..len].to_vec()))
 }
 
 fn merge_adjacent_segments(segments: Vec<Segment>) -> Vec<Segment> {
     let mut merged: Vec<Segment> = Vec::with_capacity(segments.len());
     for seg in segments {
         if let Some(last) = merged.last_mut()
-            && last.is_contiguous_with(&seg)
+            && last.joins(&seg)
         {
             last.data.extend_from_slice(&seg.data);
             continue;
@@ -308,59 +414,73 @@ fn merge_adjacent_segments(segments: Vec<Segment>) -> Vec<Segment> {
     merged
 }
 
-fn overlay_segment(segments: Vec<Segment>, seg: Segment) -> Vec<Segment> {
-    if segments.is_empty() {
-        return vec![seg];
-    }
-    let seg_start = seg.start_address;
-    let seg_end = seg.end_address();
-    let mut next: Vec<Segment> = Vec::with_capacity(segments.len() + 1);
-    let mut new_seg = Some(seg);
-
-    for cur in segments {
-        if cur.end_address() < seg_start {
-            next.push(cur);
-            continue;
+/// Overlay `data` at `addr` onto `last` if the two touch or overlap; `data`
+/// wins. Both are the newest data, so this matches pushing a new segment.
+fn coalesce_write(last: &mut Segment, addr: u32, data: &[u8]) -> bool {
+    if last.is_empty() {
+        return false;
+    }
+    let last_start = last.start_address as u64;
+    let last_end = last_start + last.data.len() as u64 - 1;
+    let start = addr as u64;
+    let end = start + data.len() as u64 - 1;
+    if end + 1 < last_start || start > last_end + 1 || end > u32::MAX as u64 {
+        return false;
+    }
+    if start < last_start {
+        let mut merged = data.to_vec();
+        if last_end > end {
+            merged.extend_from_slice(&last.data[(end + 1 - last_start) as usize..]);
         }
-        if cur.start_address > seg_end {
-            if let Some(seg) = new_seg.take() {
-                next.push(seg);
-            }
-            next.push(cur);
-            continue;
+        *last = last.piece(addr, merged);
+    } else {
+        let offset = (start - last_start) as usize;
+        let needed = offset + data.len();
+        if needed > last.data.len() {
+            last.data.resize(needed, 0);
         }
+        last.data[offset..needed].copy_from_slice(data);
+    }
+    true
+}
 
-        if cur.start_address < seg_start {
-            let left_len = (seg_start - cur.start_address) as usize;
-            if left_len > 0 {
-                next.push(Segment::new(
-                    cur.start_address,
-                    cur.data[..left_len].to_vec(),
-                ));
+/// Resolve overlaps last-wins, returning non-overlapping segments sorted by
+/// address. Each segment only touches the entries it overlaps.
+fn overlay_segments(segments: Vec<Segment>) -> Vec<Segment> {
+    let mut map: BTreeMap<u32, Segment> = BTreeMap::new();
+    for seg in segments {
+        let start = seg.start_address;
+        let end = seg.end_address();
+
+        // An entry starting below `start` keeps its head and loses any
+        // bytes up to `end`.
+        let mut tail = None;
+        if let Some((&cur_start, cur)) = map.range_mut(..start).next_back() {
+            let cur_end = cur.end_address();
+            if cur_end >= start {
+                if cur_end > end {
+                    let data = cur.data.split_off((end - cur_start) as usize + 1);
+                    tail = Some(cur.piece(end + 1, data));
+                }
+                cur.data.truncate((start - cur_start) as usize);
             }
         }
 
-        if cur.end_address() > seg_end {
-            if let Some(seg) = new_seg.take() {
-                next.push(seg);
-            }
-            if let Some(right_start) = seg_end.checked_add(1) {
-                let right_offset = (right_start - cur.start_address) as usize;
-                if right_offset < cur.data.len() {
-                    next.push(Segment::new(
-                        right_start,
-                        cur.data[right_offset..].to_vec(),
-                    ));
-                }
+        let covered: Vec<u32> = map.range(start..=end).map(|(&s, _)| s).collect();
+        for cur_start in covered {
+            let mut cur = map.remove(&cur_start).expect("covered entry");
+            if cur.end_address() > end {
+                let data = cur.data.split_off((end - cur_start) as usize + 1);
+                tail = Some(cur.piece(end + 1, data));
             }
         }
-    }
 
-    if let Some(seg) = new_seg.take() {
-        next.push(seg);
+        if let Some(tail) = tail {
+            map.insert(end + 1, tail);
+        }
+        map.insert(start, seg);
     }
-
-    merge_adjacent_segments(next)
+    map.into_values().collect()
 }
 
 #[cfg(test)]
@@ -466,6 +586,32 @@ mod tests {
         assert_eq!(hf.read_bytes_contiguous(0x100, 4), None);
     }
 
+    #[test]
+    fn test_lookups_on_normalized_file() {
+        let mut hf = HexFile::with_segments(vec![
+            Segment::new(0x300, vec![0x03; 4]),
+            Segment::new(0x100, vec![0x01; 4]),
+            Segment::new(0x104, vec![0x02; 4]),
+            Segment::new(0x102, vec![0xFF]),
+        ]);
+        let raw: Vec<_> = (0xFE..0x310).map(|a| hf.read_byte(a)).collect();
+        hf.ensure_normalized();
+        let indexed: Vec<_> = (0xFE..0x310).map(|a| hf.read_byte(a)).collect();
+        assert_eq!(indexed, raw);
+        assert_eq!(
+            hf.read_bytes_contiguous(0x101, 4),
+            Some(vec![0x01, 0xFF, 0x01, 0x02])
+        );
+        assert_eq!(hf.read_bytes_contiguous(0x106, 3), None);
+        assert_eq!(hf.read_bytes_contiguous(0x2FF, 2), None);
+
+        // Mutation drops back to the last-wins scan.
+        hf.append_segment(Segment::new(0x300, vec![0x44]));
+        assert_eq!(hf.read_byte(0x300), Some(0x44));
+        hf.segments_mut().push(Segment::new(0x301, vec![0x55]));
+        assert_eq!(hf.read_byte(0x301), Some(0x55));
+    }
+
     #[test]
     fn test_write_bytes() {
         let mut hf = HexFile::new();
@@ -475,6 +621,69 @@ mod tests {
         assert_eq!(norm.segments[0].data, vec![0x01, 0xFF]);
     }
 
+    #[test]
+    fn test_write_bytes_coalesces_with_last_segment() {
+        let mut hf = HexFile::with_segments(vec![Segment::new(0x100, vec![0xAA; 4])]);
+        for addr in 0x200..0x300u32 {
+            hf.write_bytes(addr, &[addr as u8]);
+        }
+        hf.write_bytes(0x1FE, &[0x11, 0x22, 0x33]);
+        hf.write_bytes(0x102, &[0x44]);
+        assert_eq!(hf.segments().len(), 3);
+        assert_eq!(hf.segments()[1].start_address, 0x1FE);
+        assert_eq!(hf.segments()[1].len(), 0x102);
+        assert_eq!(hf.read_byte(0x200), Some(0x33));
+        assert_eq!(hf.read_byte(0x2FF), Some(0xFF));
+        assert_eq!(hf.read_byte(0x102), Some(0x44));
+    }
+
+    #[test]
+    fn test_normalized_lossy_many_overlaps() {
+        // Each write lands on bytes of several earlier ones; compare against
+        // a byte-by-byte last-wins model.
+        let mut segments = Vec::new();
+        let mut model = vec![None; 0x400];
+        for i in 0..200u32 {
+            let start = (i * 37) % 0x3C0;
+            let len = 1 + (i * 13) % 0x40;
+            segments.push(Segment::new(start, vec![i as u8; len as usize]));
+            for addr in start..start + len {
+                model[addr as usize] = Some(i as u8);
+            }
+        }
+        let norm = HexFile::with_segments(segments).normalized_lossy();
+        for (addr, expected) in model.iter().enumerate() {
+            assert_eq!(norm.read_byte(addr as u32), *expected, "{addr:#X}");
+        }
+        assert!(
+            norm.segments()
+                .windows(2)
+                .all(|w| w[0].end_address() + 1 < w[1].start_address)
+        );
+    }
+
+    #[test]
+    fn test_ensure_normalized_in_place() {
+        let mut hf = HexFile::with_segments(vec![
+            Segment::new(0x200, vec![0x02; 4]),
+            Segment::new(0x100, vec![0x01; 4]),
+            Segment::new(0x102, vec![0xFF; 4]),
+        ]);
+        assert!(!hf.is_normalized());
+        assert!(matches!(hf.normalized_segments(), Cow::Owned(_)));
+        let expected = hf.normalized_lossy();
+
+        hf.ensure_normalized();
+        assert!(hf.is_normalized());
+        assert_eq!(hf, expected);
+        assert!(matches!(hf.normalized_segments(), Cow::Borrowed(_)));
+        assert_eq!(hf.normalized_lossy(), expected);
+
+        // Touching segments still need a merge.
+        hf.append_segment(Segment::new(0x204, vec![0x03]));
+        assert!(!hf.is_normalized());
+    }
+
     #[test]
     fn test_sorted_order() {
         let hf = HexFile::with_segments(vec![
@@ -1,11 +1,43 @@
-use crate::io::ParseError;
-use crate::{HexFile, Segment};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
 
-#[derive(Debug, Clone, Default)]
+use memmap2::Mmap;
+
+use crate::io::{ParseError, sorted_segments};
+use crate::{DEFAULT_MAX_FILL_SPAN, HexFile, Segment, SegmentData};
+
+/// Gap fill is written in pieces of at most this many bytes.
+const FILL_CHUNK: usize = 64 * 1024;
+
+#[derive(Debug, Clone)]
 pub struct BinaryWriteOptions {
-    /// If set, fills gaps between min/max addresses with this byte.
-    /// If None, segments are concatenated in ascending address order.
-    pub fill_gaps: Option<u8>,
+    /// First address of the image; defaults to the lowest data address.
+    pub start: Option<u32>,
+    /// Last address of the image (inclusive); defaults to the highest data address.
+    pub end: Option<u32>,
+    /// Byte written wherever `[start, end]` has no data.
+    pub fill: u8,
+    /// Ignore `start`/`end`/`fill` and write the segments back to back in the
+    /// order they appear, without filling gaps. CLI: /XN:RAW.
+    pub concatenate_in_order: bool,
+    /// Largest image `[start, end]` may span, in bytes. A wider one is an
+    /// error instead of gigabytes of fill; defaults to
+    /// [`DEFAULT_MAX_FILL_SPAN`], the limit of [`HexFile::fill_gaps`].
+    pub max_span: u64,
+}
+
+impl Default for BinaryWriteOptions {
+    fn default() -> Self {
+        Self {
+            start: None,
+            end: None,
+            fill: 0xFF,
+            concatenate_in_order: false,
+            max_span: DEFAULT_MAX_FILL_SPAN,
+        }
+    }
 }
 
 /// Parse a raw binary blob into a single segment at the given base address.
@@ -35,30 +67,132 @@ pub fn parse_binary(data: &[u8], base_address: u32) -> Result<HexFile, ParseErro
     )]))
 }
 
-/// Write the HexFile to a binary blob.
-/// CLI: /XN.
-pub fn write_binary(hexfile: &HexFile, options: &BinaryWriteOptions) -> Vec<u8> {
-    if hexfile.segments().is_empty() {
-        return Vec::new();
+/// As [`parse_binary`], but memory-maps `path` instead of reading it, so the
+/// segment references the mapping until an operation modifies it.
+/// CLI: /IN with /MMAP.
+///
+/// # Safety
+///
+/// Nothing, in this process or another, may truncate or write the file while
+/// the result or any segment cloned from it is alive. Reads of a truncated
+/// mapping fault (SIGBUS), and writes change the image underneath it. In
+/// particular, do not write an output to `path` from the mapped image.
+pub unsafe fn parse_binary_mmap(
+    path: impl AsRef<Path>,
+    base_address: u32,
+) -> Result<HexFile, ParseError> {
+    let file = File::open(path.as_ref())?;
+    if file.metadata()?.len() == 0 {
+        return Ok(HexFile::new());
+    }
+    // SAFETY: the mapping is read-only, and the caller guarantees the file
+    // is not modified while it is mapped.
+    let map = unsafe { Mmap::map(&file)? };
+    let len = u32::try_from(map.len()).map_err(|_| {
+        ParseError::AddressOverflow(format!("{:#X} + {} exceeds u32", base_address, map.len()))
+    })?;
+    base_address.checked_add(len - 1).ok_or_else(|| {
+        ParseError::AddressOverflow(format!("{:#X} + {} exceeds u32", base_address, len))
+    })?;
+
+    let len = map.len();
+    Ok(HexFile::with_segments(vec![Segment::with_data(
+        base_address,
+        SegmentData::mapped(Arc::new(map), 0, len),
+    )]))
+}
+
+/// Write the HexFile as a memory image of `[start, end]` in address order,
+/// gaps filled. Data outside the window is dropped; overlaps resolve last-wins.
+/// CLI: /XN[:start[,end[,fill]]].
+pub fn write_binary(
+    hexfile: &HexFile,
+    options: &BinaryWriteOptions,
+) -> Result<Vec<u8>, ParseError> {
+    let mut out = Vec::new();
+    if !options.concatenate_in_order
+        && let Some((start, end)) = image_window(hexfile, options)?
+    {
+        let len = usize::try_from(end as u64 - start as u64 + 1).map_err(|_| {
+            ParseError::InvalidOutput(format!("binary image {start:#X}-{end:#X} is too large"))
+        })?;
+        out.reserve_exact(len);
+    }
+    write_binary_to(hexfile, options, &mut out)?;
+    Ok(out)
+}
+
+/// Stream the output of [`write_binary`] into `writer`. Gaps are written in
+/// small pieces, so no buffer the size of the image is allocated.
+pub fn write_binary_to<W: Write>(
+    hexfile: &HexFile,
+    options: &BinaryWriteOptions,
+    mut writer: W,
+) -> Result<(), ParseError> {
+    if options.concatenate_in_order {
+        return concatenate_in_order(hexfile, &mut writer);
     }
 
-    if let Some(fill) = options.fill_gaps {
-        let mut filled = hexfile.normalized_lossy();
-        filled.fill_gaps(fill);
-        if let Some(segment) = filled.segments().first() {
-            return segment.data.clone();
+    let Some((start, end)) = image_window(hexfile, options)? else {
+        return Ok(());
+    };
+    let fill = vec![options.fill; (FILL_CHUNK as u64).min(end as u64 - start as u64 + 1) as usize];
+    let write_fill = |writer: &mut W, mut len: u64| -> std::io::Result<()> {
+        while len > 0 {
+            let count = len.min(fill.len() as u64) as usize;
+            writer.write_all(&fill[..count])?;
+            len -= count as u64;
+        }
+        Ok(())
+    };
+    let mut cursor = start as u64;
+    for segment in sorted_segments(hexfile).iter() {
+        let seg_start = segment.start_address.max(start);
+        let seg_end = segment.end_address().min(end);
+        if seg_start > seg_end {
+            continue;
         }
-        return Vec::new();
+        write_fill(&mut writer, seg_start as u64 - cursor)?;
+        let src = (seg_start - segment.start_address) as usize;
+        let count = (seg_end - seg_start) as usize + 1;
+        writer.write_all(&segment.data[src..src + count])?;
+        cursor = seg_end as u64 + 1;
     }
+    write_fill(&mut writer, end as u64 + 1 - cursor)?;
+    Ok(())
+}
 
-    let mut segments: Vec<_> = hexfile.segments().iter().filter(|s| !s.is_empty()).collect();
-    segments.sort_by_key(|s| s.start_address);
-    let total_len: usize = segments.iter().map(|s| s.len()).sum();
-    let mut out = Vec::with_capacity(total_len);
-    for segment in segments {
-        out.extend_from_slice(&segment.data);
+/// `[start, end]` of the image, or `None` for an empty file without bounds.
+fn image_window(
+    hexfile: &HexFile,
+    options: &BinaryWriteOptions,
+) -> Result<Option<(u32, u32)>, ParseError> {
+    let (Some(start), Some(end)) = (
+        options.start.or(hexfile.min_address()),
+        options.end.or(hexfile.max_address()),
+    ) else {
+        return Ok(None);
+    };
+    if start > end {
+        return Err(ParseError::InvalidOutput(format!(
+            "binary image start {start:#X} is after end {end:#X}"
+        )));
+    }
+    let span = end as u64 - start as u64 + 1;
+    if span > options.max_span {
+        return Err(ParseError::InvalidOutput(format!(
+            "binary image {start:#X}-{end:#X} is {span} bytes, over the {}-byte limit",
+            options.max_span
+        )));
     }
-    out
+    Ok(Some((start, end)))
+}
+
+fn concatenate_in_order<W: Write>(hexfile: &HexFile, writer: &mut W) -> Result<(), ParseError> {
+    for segment in hexfile.segments() {
+        writer.write_all(&segment.data)?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -74,6 +208,40 @@ mod tests {
         assert_eq!(hexfile.segments()[0].data, data);
     }
 
+    #[test]
+    fn test_parse_binary_mmap_references_file() {
+        let path = std::env::temp_dir().join(format!("h3xy_mmap_{}.bin", std::process::id()));
+        let data: Vec<u8> = (0..=255).collect();
+        std::fs::write(&path, &data).unwrap();
+
+        // SAFETY: only this test touches the file, and it rewrites it after
+        // the last mapped image is dropped.
+        let mapped = unsafe { parse_binary_mmap(&path, 0x1000) }.unwrap();
+        assert!(mapped.segments()[0].data.is_mapped());
+        assert_eq!(mapped, parse_binary(&data, 0x1000).unwrap());
+        let out = write_binary(&mapped, &BinaryWriteOptions::default()).unwrap();
+        assert_eq!(out, data);
+
+        // Writing copies the segment; the file is left alone.
+        let mut edited = mapped.clone();
+        edited.segments_mut()[0].data[0] = 0xEE;
+        assert!(!edited.segments()[0].data.is_mapped());
+        assert!(mapped.segments()[0].data.is_mapped());
+        assert_eq!(mapped.segments()[0].data[0], 0x00);
+        drop(mapped);
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+
+        assert!(unsafe { parse_binary_mmap(&path, u32::MAX) }.is_err());
+        std::fs::write(&path, []).unwrap();
+        assert!(
+            unsafe { parse_binary_mmap(&path, 0) }
+                .unwrap()
+                .segments()
+                .is_empty()
+        );
+        let _ = std::fs::remove_file(path);
+    }
+
     #[test]
     fn test_parse_binary_overflow() {
         let data = vec![0xAA, 0xBB];
@@ -82,27 +250,101 @@ mod tests {
     }
 
     #[test]
-    fn test_write_binary_address_order() {
+    fn test_write_binary_concatenate_in_order() {
         let hexfile = HexFile::with_segments(vec![
             Segment::new(0x2000, vec![0x01, 0x02]),
             Segment::new(0x1000, vec![0xAA]),
         ]);
-        let out = write_binary(&hexfile, &BinaryWriteOptions::default());
-        assert_eq!(out, vec![0xAA, 0x01, 0x02]);
+        let options = BinaryWriteOptions {
+            concatenate_in_order: true,
+            ..Default::default()
+        };
+        let out = write_binary(&hexfile, &options).unwrap();
+        assert_eq!(out, vec![0x01, 0x02, 0xAA]);
     }
 
     #[test]
-    fn test_write_binary_fill_gaps() {
+    fn test_write_binary_default_is_address_ordered_image() {
         let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x1003, vec![0x01, 0x02]),
             Segment::new(0x1000, vec![0xAA]),
-            Segment::new(0x1002, vec![0xBB]),
         ]);
-        let out = write_binary(
-            &hexfile,
-            &BinaryWriteOptions {
-                fill_gaps: Some(0x00),
-            },
+        let out = write_binary(&hexfile, &BinaryWriteOptions::default()).unwrap();
+        assert_eq!(out, vec![0xAA, 0xFF, 0xFF, 0x01, 0x02]);
+    }
+
+    #[test]
+    fn test_write_binary_refuses_span_over_limit() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x0, vec![0xAA]),
+            Segment::new(0xFFFF_FFFF, vec![0xBB]),
+        ]);
+        let mut sink = Vec::new();
+        let err = write_binary_to(&hexfile, &BinaryWriteOptions::default(), &mut sink).unwrap_err();
+        assert!(
+            err.to_string().contains("over the 268435456-byte limit"),
+            "{err}"
         );
-        assert_eq!(out, vec![0xAA, 0x00, 0xBB]);
+        assert!(sink.is_empty());
+
+        let small = HexFile::with_segments(vec![
+            Segment::new(0x1000, vec![0xAA]),
+            Segment::new(0x100F, vec![0xBB]),
+        ]);
+        let options = BinaryWriteOptions {
+            max_span: 0xF,
+            ..Default::default()
+        };
+        assert!(write_binary(&small, &options).is_err());
+        let options = BinaryWriteOptions {
+            max_span: 0x10,
+            ..Default::default()
+        };
+        assert_eq!(write_binary(&small, &options).unwrap().len(), 0x10);
+    }
+
+    #[test]
+    fn test_write_binary_fills_interior_gaps() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x1002, vec![0xBB]),
+            Segment::new(0x1000, vec![0xAA]),
+        ]);
+        let out = write_binary(&hexfile, &BinaryWriteOptions::default()).unwrap();
+        assert_eq!(out, vec![0xAA, 0xFF, 0xBB]);
+    }
+
+    #[test]
+    fn test_write_binary_explicit_window() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01, 0x02, 0x03])]);
+        let options = BinaryWriteOptions {
+            start: Some(0x0FFE),
+            end: Some(0x1001),
+            fill: 0x00,
+            ..Default::default()
+        };
+        let out = write_binary(&hexfile, &options).unwrap();
+        assert_eq!(out, vec![0x00, 0x00, 0x01, 0x02]);
+
+        let options = BinaryWriteOptions {
+            start: Some(0x2000),
+            end: Some(0x1000),
+            ..Default::default()
+        };
+        assert!(write_binary(&hexfile, &options).is_err());
+    }
+
+    #[test]
+    fn test_write_binary_to_streams_large_gaps() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x4_0000, vec![0xBB]),
+            Segment::new(0x1000, vec![0xAA]),
+        ]);
+        let mut out = std::io::BufWriter::with_capacity(100, Vec::new());
+        write_binary_to(&hexfile, &BinaryWriteOptions::default(), &mut out).unwrap();
+        let out = out.into_inner().unwrap();
+        assert_eq!(out.len(), 0x3_F001);
+        assert_eq!(out[0], 0xAA);
+        assert_eq!(out[0x3_F000], 0xBB);
+        assert!(out[1..0x3_F000].iter().all(|&b| b == 0xFF));
     }
 }
@@ -16,6 +16,35 @@ pub struct CCodeWriteOptions {
     pub word_type: CCodeWordType,
     pub decrypt: bool,
     pub decrypt_value: u32,
+    /// Name the per-segment arrays `<prefix>_block<n>` instead of `<prefix>Blk<n>`.
+    pub per_segment: bool,
+    /// Emit a `<prefix>_blocks[]` table of `{address, length, data}` entries.
+    pub emit_descriptor_table: bool,
+    /// Storage qualifier placed between `const` and the element type
+    /// (e.g. `PROGMEM`, `__flash`); empty for none.
+    pub qualifier: String,
+    /// Initializer bytes per line, rounded down to whole elements;
+    /// 0 keeps 12 elements per line.
+    pub bytes_per_line: usize,
+    pub use_uppercase_hex: bool,
+}
+
+impl Default for CCodeWriteOptions {
+    fn default() -> Self {
+        Self {
+            prefix: "flashDrv".to_string(),
+            header_name: "flashDrv".to_string(),
+            word_size: 0,
+            word_type: CCodeWordType::Intel,
+            decrypt: false,
+            decrypt_value: 0,
+            per_segment: false,
+            emit_descriptor_table: false,
+            qualifier: String::new(),
+            bytes_per_line: 0,
+            use_uppercase_hex: true,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -40,7 +69,7 @@ pub fn write_c_code(
         }
     };
 
-    let segments = normalized_sorted_segments(hexfile);
+    let segments = &*normalized_sorted_segments(hexfile);
 
     let prefix = options.prefix.trim();
     if prefix.is_empty() {
@@ -48,6 +77,25 @@ pub fn write_c_code(
             "Prefix must not be empty".to_string(),
         ));
     }
+    let ident = sanitize_identifier(prefix);
+    let qualifier = options.qualifier.trim();
+    let c_type = if qualifier.is_empty() {
+        c_type.to_string()
+    } else {
+        format!("{qualifier} {c_type}")
+    };
+    let per_line = if options.bytes_per_line == 0 {
+        12
+    } else {
+        (options.bytes_per_line / elem_bytes).max(1)
+    };
+    let array_name = |idx: usize| {
+        if options.per_segment {
+            format!("{ident}_block{idx}")
+        } else {
+            format!("{ident}Blk{idx}")
+        }
+    };
 
     let mut header = Vec::new();
     header.extend_from_slice(b"#pragma once\n#include <stdint.h>\n\n");
@@ -99,15 +147,59 @@ pub fn write_c_code(
             )
             .as_bytes(),
         );
-        header
-            .extend_from_slice(format!("extern const {c_type} {prefix}Blk{idx}[];\n\n").as_bytes());
+        let name = array_name(idx);
+        header.extend_from_slice(format!("extern const {c_type} {name}[];\n\n").as_bytes());
 
-        source.extend_from_slice(format!("const {c_type} {prefix}Blk{idx}[] = {{\n").as_bytes());
+        source.extend_from_slice(format!("const {c_type} {name}[] = {{\n").as_bytes());
         let values = segment_to_values(segment, elem_bytes, options)?;
-        write_values(&mut source, &values, elem_bytes);
+        write_values(
+            &mut source,
+            &values,
+            elem_bytes,
+            per_line,
+            options.use_uppercase_hex,
+        );
         source.extend_from_slice(b"};\n\n");
     }
 
+    if options.emit_descriptor_table {
+        let type_name = format!("{ident}_block_t");
+        header.extend_from_slice(
+            format!(
+                "typedef struct {{\n    uint32_t address;\n    uint32_t length;\n    const {c_type} *data;\n}} {type_name};\n\n"
+            )
+            .as_bytes(),
+        );
+        header.extend_from_slice(
+            format!(
+                "extern const {type_name} {ident}_blocks[{}_BLOCK_COUNT];\n",
+                sanitize_define(prefix)
+            )
+            .as_bytes(),
+        );
+
+        source.extend_from_slice(
+            format!(
+                "const {type_name} {ident}_blocks[{}_BLOCK_COUNT] = {{\n",
+                sanitize_define(prefix)
+            )
+            .as_bytes(),
+        );
+        for (idx, segment) in segments.iter().enumerate() {
+            let separator = if idx + 1 == segments.len() { "" } else { "," };
+            source.extend_from_slice(
+                format!(
+                    "    {{ 0x{:08X}u, 0x{:X}u, {} }}{separator}\n",
+                    segment.start_address,
+                    segment.len(),
+                    array_name(idx)
+                )
+                .as_bytes(),
+            );
+        }
+        source.extend_from_slice(b"};\n");
+    }
+
     Ok(CCodeOutput {
         c: source,
         h: header,
@@ -153,35 +245,48 @@ fn segment_to_values(
     Ok(values)
 }
 
-fn write_values(out: &mut Vec<u8>, values: &[u32], elem_bytes: usize) {
-    let per_line = 12usize;
+fn write_values(
+    out: &mut Vec<u8>,
+    values: &[u32],
+    elem_bytes: usize,
+    per_line: usize,
+    uppercase: bool,
+) {
     for (idx, value) in values.iter().enumerate() {
         if idx % per_line == 0 {
             out.extend_from_slice(b"    ");
         }
         let width = elem_bytes * 2;
-        let formatted = format!("0x{:0width$X}", value, width = width);
+        let formatted = if uppercase {
+            format!("0x{:0width$X}", value, width = width)
+        } else {
+            format!("0x{:0width$x}", value, width = width)
+        };
         out.extend_from_slice(formatted.as_bytes());
-        if idx + 1 != values.len() {
-            out.extend_from_slice(b", ");
-        }
-        if (idx + 1) % per_line == 0 || idx + 1 == values.len() {
-            out.extend_from_slice(b"\n");
+        let last = idx + 1 == values.len();
+        let line_end = last || (idx + 1) % per_line == 0;
+        if !last {
+            out.push(b',');
         }
+        out.extend_from_slice(if line_end { b"\n" } else { b" " });
     }
 }
 
-fn sanitize_define(prefix: &str) -> String {
-    prefix
+/// Map `prefix` to a valid C identifier: non-alphanumerics become `_` and a
+/// leading digit gets a `_` in front.
+fn sanitize_identifier(prefix: &str) -> String {
+    let mut ident: String = prefix
         .chars()
-        .map(|c| {
-            if c.is_ascii_alphanumeric() {
-                c.to_ascii_uppercase()
-            } else {
-                '_'
-            }
-        })
-        .collect()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+fn sanitize_define(prefix: &str) -> String {
+    sanitize_identifier(prefix).to_ascii_uppercase()
 }
 
 #[cfg(test)]
@@ -191,14 +296,7 @@ mod tests {
     #[test]
     fn test_write_c_code_basic() {
         let hexfile = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01, 0x02, 0x03])]);
-        let options = CCodeWriteOptions {
-            prefix: "flashDrv".to_string(),
-            header_name: "flashDrv".to_string(),
-            word_size: 0,
-            word_type: CCodeWordType::Intel,
-            decrypt: false,
-            decrypt_value: 0,
-        };
+        let options = CCodeWriteOptions::default();
         let output = write_c_code(&hexfile, &options).unwrap();
         assert!(
             String::from_utf8(output.c)
@@ -211,4 +309,93 @@ mod tests {
                 .contains("FLASHDRV_BLOCK0_ADDRESS")
         );
     }
+
+    #[test]
+    fn test_write_c_code_per_segment_descriptor_table() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x2000, vec![0xAA]),
+            Segment::new(0x1000, vec![0x01, 0x02]),
+        ]);
+        let options = CCodeWriteOptions {
+            prefix: "3-flash.drv".to_string(),
+            header_name: "drv".to_string(),
+            per_segment: true,
+            emit_descriptor_table: true,
+            ..Default::default()
+        };
+        let output = write_c_code(&hexfile, &options).unwrap();
+        let c = String::from_utf8(output.c).unwrap();
+        let h = String::from_utf8(output.h).unwrap();
+
+        assert!(c.contains("const uint8_t _3_flash_drv_block0[] = {\n    0x01, 0x02\n};"));
+        assert!(c.contains("const uint8_t _3_flash_drv_block1[] = {\n    0xAA\n};"));
+        assert!(c.ends_with(
+            "const _3_flash_drv_block_t _3_flash_drv_blocks[_3_FLASH_DRV_BLOCK_COUNT] = {\n\
+             \x20   { 0x00001000u, 0x2u, _3_flash_drv_block0 },\n\
+             \x20   { 0x00002000u, 0x1u, _3_flash_drv_block1 }\n\
+             };\n"
+        ));
+        assert!(h.contains("extern const uint8_t _3_flash_drv_block1[];"));
+        assert!(h.contains("    const uint8_t *data;\n} _3_flash_drv_block_t;"));
+        assert!(h.contains(
+            "extern const _3_flash_drv_block_t _3_flash_drv_blocks[_3_FLASH_DRV_BLOCK_COUNT];"
+        ));
+        assert_c_sane(&c);
+        assert_c_sane(&h);
+    }
+
+    /// Cheap C syntax sanity check: balanced braces, every top-level `}`
+    /// ends its declaration with `;`, no empty or trailing list elements.
+    fn assert_c_sane(source: &str) {
+        let mut depth = 0i32;
+        for (idx, c) in source.char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    assert!(depth >= 0, "unbalanced '}}' at {idx}");
+                    if depth == 0 {
+                        // `};` or `} name_t;`
+                        let rest = source[idx + 1..].trim_start_matches(|c: char| {
+                            c.is_alphanumeric() || c == '_' || c == ' '
+                        });
+                        assert!(rest.starts_with(';'), "missing ';' after '}}' at {idx}");
+                    }
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(depth, 0, "unbalanced braces");
+        let compact: String = source.chars().filter(|c| !c.is_whitespace()).collect();
+        for bad in [",,", "{,", ",}", "={}"] {
+            assert!(!compact.contains(bad), "found '{bad}'");
+        }
+    }
+
+    #[test]
+    fn test_write_c_code_qualifier_line_width_and_case() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x1000, (0xA0u8..0xA6).collect()),
+            Segment::new(0x2000, vec![0xBC, 0xDE]),
+        ]);
+        let options = CCodeWriteOptions {
+            word_size: 1,
+            qualifier: "PROGMEM".to_string(),
+            bytes_per_line: 4,
+            use_uppercase_hex: false,
+            emit_descriptor_table: true,
+            ..Default::default()
+        };
+        let output = write_c_code(&hexfile, &options).unwrap();
+        let c = String::from_utf8(output.c).unwrap();
+        let h = String::from_utf8(output.h).unwrap();
+
+        assert!(c.contains(
+            "const PROGMEM uint16_t flashDrvBlk0[] = {\n    0xa1a0, 0xa3a2,\n    0xa5a4\n};"
+        ));
+        assert!(h.contains("extern const PROGMEM uint16_t flashDrvBlk1[];"));
+        assert!(h.contains("    const PROGMEM uint16_t *data;"));
+        assert_c_sane(&c);
+        assert_c_sane(&h);
+    }
 }
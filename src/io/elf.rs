@@ -0,0 +1,203 @@
+use crate::io::ParseError;
+use crate::{HexFile, Segment};
+
+const PT_LOAD: u32 = 1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ElfParseOptions {
+    /// Place segments at the virtual address (VMA) instead of the physical
+    /// load address (LMA).
+    pub use_virtual_address: bool,
+}
+
+/// Parse an ELF file (32/64-bit, either endianness) into one segment per
+/// `PT_LOAD` program header with file data, at its physical address.
+/// The zero-initialized tail (`p_memsz > p_filesz`) is not emitted.
+pub fn parse_elf(data: &[u8]) -> Result<HexFile, ParseError> {
+    parse_elf_with_options(data, &ElfParseOptions::default())
+}
+
+/// Parse an ELF file with explicit address selection.
+pub fn parse_elf_with_options(
+    data: &[u8],
+    options: &ElfParseOptions,
+) -> Result<HexFile, ParseError> {
+    if data.get(..4) != Some(b"\x7fELF".as_slice()) {
+        return Err(ParseError::InvalidElf("missing ELF magic".to_string()));
+    }
+    let reader = Reader {
+        data,
+        is_64: match data.get(4) {
+            Some(1) => false,
+            Some(2) => true,
+            other => {
+                return Err(ParseError::InvalidElf(format!(
+                    "unsupported ELF class {other:?}"
+                )));
+            }
+        },
+        little_endian: match data.get(5) {
+            Some(1) => true,
+            Some(2) => false,
+            other => {
+                return Err(ParseError::InvalidElf(format!(
+                    "unsupported ELF data encoding {other:?}"
+                )));
+            }
+        },
+    };
+
+    let (phoff, phentsize, phnum) = if reader.is_64 {
+        (reader.u64(0x20)?, reader.u16(0x36)?, reader.u16(0x38)?)
+    } else {
+        (
+            reader.u32(0x1C)? as u64,
+            reader.u16(0x2A)?,
+            reader.u16(0x2C)?,
+        )
+    };
+
+    let mut segments = Vec::new();
+    for index in 0..phnum as u64 {
+        let base = phoff.saturating_add(index * phentsize as u64);
+        let header = if reader.is_64 {
+            ProgramHeader {
+                p_type: reader.u32(base)?,
+                offset: reader.u64(base + 0x08)?,
+                vaddr: reader.u64(base + 0x10)?,
+                paddr: reader.u64(base + 0x18)?,
+                filesz: reader.u64(base + 0x20)?,
+            }
+        } else {
+            ProgramHeader {
+                p_type: reader.u32(base)?,
+                offset: reader.u32(base + 0x04)? as u64,
+                vaddr: reader.u32(base + 0x08)? as u64,
+                paddr: reader.u32(base + 0x0C)? as u64,
+                filesz: reader.u32(base + 0x10)? as u64,
+            }
+        };
+        if header.p_type != PT_LOAD || header.filesz == 0 {
+            continue;
+        }
+
+        let address = if options.use_virtual_address {
+            header.vaddr
+        } else {
+            header.paddr
+        };
+        let end = address.saturating_add(header.filesz - 1);
+        let address = u32::try_from(address)
+            .ok()
+            .filter(|_| end <= u32::MAX as u64)
+            .ok_or_else(|| {
+                ParseError::AddressOverflow(format!(
+                    "ELF program header {index} at {address:#X} (+{:#X})",
+                    header.filesz
+                ))
+            })?;
+        let bytes = reader.slice(header.offset, header.filesz)?;
+        segments.push(Segment::new(address, bytes.to_vec()));
+    }
+
+    Ok(HexFile::with_segments(segments))
+}
+
+struct ProgramHeader {
+    p_type: u32,
+    offset: u64,
+    vaddr: u64,
+    paddr: u64,
+    filesz: u64,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    is_64: bool,
+    little_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn slice(&self, offset: u64, len: u64) -> Result<&'a [u8], ParseError> {
+        let start = usize::try_from(offset).map_err(|_| ParseError::UnexpectedEof)?;
+        let len = usize::try_from(len).map_err(|_| ParseError::UnexpectedEof)?;
+        start
+            .checked_add(len)
+            .and_then(|end| self.data.get(start..end))
+            .ok_or(ParseError::UnexpectedEof)
+    }
+
+    fn bytes<const N: usize>(&self, offset: u64) -> Result<[u8; N], ParseError> {
+        let mut out: [u8; N] = self.slice(offset, N as u64)?.try_into().unwrap();
+        if !self.little_endian {
+            out.reverse();
+        }
+        Ok(out)
+    }
+
+    fn u16(&self, offset: u64) -> Result<u16, ParseError> {
+        self.bytes(offset).map(u16::from_le_bytes)
+    }
+
+    fn u32(&self, offset: u64) -> Result<u32, ParseError> {
+        self.bytes(offset).map(u32::from_le_bytes)
+    }
+
+    fn u64(&self, offset: u64) -> Result<u64, ParseError> {
+        self.bytes(offset).map(u64::from_le_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal ELF32 big-endian image with one PT_LOAD at LMA 0x100 / VMA 0x2000.
+    fn elf32_be() -> Vec<u8> {
+        let mut elf = vec![0u8; 0x34 + 0x20];
+        elf[..6].copy_from_slice(&[0x7F, b'E', b'L', b'F', 1, 2]);
+        elf[0x1C..0x20].copy_from_slice(&0x34u32.to_be_bytes());
+        elf[0x2A..0x2C].copy_from_slice(&0x20u16.to_be_bytes());
+        elf[0x2C..0x2E].copy_from_slice(&1u16.to_be_bytes());
+        let ph = 0x34;
+        elf[ph..ph + 4].copy_from_slice(&PT_LOAD.to_be_bytes());
+        elf[ph + 0x04..ph + 0x08].copy_from_slice(&0x54u32.to_be_bytes());
+        elf[ph + 0x08..ph + 0x0C].copy_from_slice(&0x2000u32.to_be_bytes());
+        elf[ph + 0x0C..ph + 0x10].copy_from_slice(&0x100u32.to_be_bytes());
+        elf[ph + 0x10..ph + 0x14].copy_from_slice(&3u32.to_be_bytes());
+        elf[ph + 0x14..ph + 0x18].copy_from_slice(&8u32.to_be_bytes());
+        elf.extend_from_slice(&[0xDE, 0xAD, 0xBE]);
+        elf
+    }
+
+    #[test]
+    fn test_parse_elf32_big_endian_lma_and_vma() {
+        let elf = elf32_be();
+        let hexfile = parse_elf(&elf).unwrap();
+        assert_eq!(
+            hexfile.segments(),
+            &[Segment::new(0x100, vec![0xDE, 0xAD, 0xBE])]
+        );
+
+        let options = ElfParseOptions {
+            use_virtual_address: true,
+        };
+        let hexfile = parse_elf_with_options(&elf, &options).unwrap();
+        assert_eq!(hexfile.segments()[0].start_address, 0x2000);
+    }
+
+    #[test]
+    fn test_parse_elf_truncated_segment() {
+        let mut elf = elf32_be();
+        elf.truncate(elf.len() - 1);
+        assert!(matches!(parse_elf(&elf), Err(ParseError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn test_parse_elf_rejects_bad_magic() {
+        assert!(matches!(
+            parse_elf(b"\x7fELX\x01\x01"),
+            Err(ParseError::InvalidElf(_))
+        ));
+    }
+}
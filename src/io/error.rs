@@ -2,28 +2,133 @@ use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum ParseError {
-    #[error("invalid record at line {line}: {message}")]
-    InvalidRecord { line: usize, message: String },
+    /// `record` holds the start of the offending line, when known.
+    #[error("invalid record at line {line}: {message}{}", record_suffix(record))]
+    InvalidRecord {
+        line: usize,
+        message: String,
+        record: Option<String>,
+    },
 
-    #[error("checksum mismatch at line {line}: expected {expected:02X}, got {actual:02X}")]
+    #[error(
+        "checksum mismatch at line {line}: expected {expected:02X}, got {actual:02X}{}",
+        record_suffix(record)
+    )]
     ChecksumMismatch {
         line: usize,
         expected: u8,
         actual: u8,
+        record: Option<String>,
     },
 
     #[error("unexpected end of file")]
     UnexpectedEof,
 
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
     #[error("address overflow: {0}")]
     AddressOverflow(String),
 
-    #[error("invalid hex digit at line {line}: {char}")]
-    InvalidHexDigit { line: usize, char: char },
+    #[error("invalid hex digit at line {line}: {char}{}", record_suffix(record))]
+    InvalidHexDigit {
+        line: usize,
+        char: char,
+        record: Option<String>,
+    },
 
     #[error("unsupported record type at line {line}: {record_type:02X}")]
     UnsupportedRecordType { line: usize, record_type: u8 },
 
     #[error("invalid output: {0}")]
     InvalidOutput(String),
+
+    #[error("invalid VBF: {0}")]
+    InvalidVbf(String),
+
+    #[error("invalid ELF: {0}")]
+    InvalidElf(String),
+
+    #[error("invalid JSON: {0}")]
+    InvalidJson(String),
+}
+
+/// Characters of the offending line kept in a record error.
+const RECORD_TEXT_MAX: usize = 60;
+
+impl ParseError {
+    /// Attach the offending line to a record error that lacks it, truncated
+    /// to `RECORD_TEXT_MAX` characters.
+    pub(crate) fn with_record(mut self, line: &[u8]) -> Self {
+        if let Self::InvalidRecord { record, .. }
+        | Self::ChecksumMismatch { record, .. }
+        | Self::InvalidHexDigit { record, .. } = &mut self
+            && record.is_none()
+        {
+            let text = String::from_utf8_lossy(line.trim_ascii());
+            *record = Some(match text.char_indices().nth(RECORD_TEXT_MAX) {
+                Some((end, _)) => format!("{}...", &text[..end]),
+                None => text.into_owned(),
+            });
+        }
+        self
+    }
+
+    /// As `with_record`, finding the error's line in the whole `input`.
+    pub(crate) fn with_record_in(self, input: &[u8]) -> Self {
+        let line = match self {
+            Self::InvalidRecord { line, .. }
+            | Self::ChecksumMismatch { line, .. }
+            | Self::InvalidHexDigit { line, .. } => line,
+            _ => return self,
+        };
+        match input.split(|&b| b == b'\n').nth(line.wrapping_sub(1)) {
+            Some(text) => self.with_record(text),
+            None => self,
+        }
+    }
+}
+
+fn record_suffix(record: &Option<String>) -> String {
+    match record {
+        Some(text) => format!(" in record \"{text}\""),
+        None => String::new(),
+    }
+}
+
+/// A problem tolerated by a lenient parser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseWarning {
+    /// A line that is not a record was skipped.
+    SkippedLine { line: usize },
+    /// Lines after the EOF record were ignored, starting at `line`.
+    DataAfterEof { line: usize },
+    /// The input ended without an EOF record.
+    MissingEof,
+    /// An S-Record S5/S6 count disagreed with the data records before it.
+    RecordCountMismatch {
+        line: usize,
+        expected: u32,
+        found: u32,
+    },
+}
+
+impl std::fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SkippedLine { line } => write!(f, "line {line}: skipped non-record line"),
+            Self::DataAfterEof { line } => {
+                write!(f, "line {line}: ignored data after EOF record")
+            }
+            Self::MissingEof => write!(f, "missing EOF record"),
+            Self::RecordCountMismatch {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {line}: record count says {expected}, found {found} data records"
+            ),
+        }
+    }
 }
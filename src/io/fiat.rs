@@ -0,0 +1,94 @@
+//! Fiat binary container export (CLI: /XB).
+//!
+//! Layout (big-endian):
+//!
+//! - start address (u32), image length (u32)
+//! - image: data from the lowest address with gaps filled by `fill_byte`,
+//!   padded with `fill_byte` to a multiple of `alignment`
+//! - 16-bit wrapping byte sum of the image (u16)
+
+use crate::HexFile;
+use crate::io::{ParseError, normalized_sorted_segments};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FiatBinWriteOptions {
+    pub fill_byte: u8,
+    /// Image length is padded to a multiple of this; 0 or 1 disables padding.
+    pub alignment: u32,
+}
+
+impl Default for FiatBinWriteOptions {
+    fn default() -> Self {
+        Self {
+            fill_byte: 0xFF,
+            alignment: 0,
+        }
+    }
+}
+
+/// Write a Fiat binary container. CLI: /XB.
+pub fn write_fiat_bin(
+    hexfile: &HexFile,
+    options: &FiatBinWriteOptions,
+) -> Result<Vec<u8>, ParseError> {
+    let segments = &*normalized_sorted_segments(hexfile);
+    let Some(start) = segments.first().map(|s| s.start_address) else {
+        return Err(ParseError::InvalidOutput(
+            "Fiat binary requires data".to_string(),
+        ));
+    };
+
+    let mut image = Vec::new();
+    for segment in segments {
+        let offset = (segment.start_address - start) as usize;
+        image.resize(offset, options.fill_byte);
+        image.extend_from_slice(&segment.data);
+    }
+    if options.alignment > 1 {
+        let aligned = image.len().div_ceil(options.alignment as usize) * options.alignment as usize;
+        image.resize(aligned, options.fill_byte);
+    }
+    let length = u32::try_from(image.len())
+        .map_err(|_| ParseError::InvalidOutput("Fiat binary image exceeds 32 bits".to_string()))?;
+    let sum = image
+        .iter()
+        .fold(0u16, |acc, &b| acc.wrapping_add(b as u16));
+
+    let mut out = Vec::with_capacity(image.len() + 10);
+    out.extend_from_slice(&start.to_be_bytes());
+    out.extend_from_slice(&length.to_be_bytes());
+    out.extend_from_slice(&image);
+    out.extend_from_slice(&sum.to_be_bytes());
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Segment;
+
+    #[test]
+    fn test_write_fiat_bin_fills_gaps_and_pads() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x1003, vec![0x03]),
+            Segment::new(0x1000, vec![0x01]),
+        ]);
+        let options = FiatBinWriteOptions {
+            fill_byte: 0x00,
+            alignment: 8,
+        };
+        let out = write_fiat_bin(&hexfile, &options).unwrap();
+        assert_eq!(
+            out,
+            vec![
+                0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x08, 0x01, 0x00, 0x00, 0x03, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x04
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_fiat_bin_rejects_empty() {
+        assert!(write_fiat_bin(&HexFile::new(), &FiatBinWriteOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,79 @@
+//! Flash kernel export (CLI: /XK).
+//!
+//! Layout (little-endian): image length (u32), entry address (u32), then the
+//! image. The image must be a single contiguous segment.
+
+use crate::HexFile;
+use crate::io::{ParseError, normalized_sorted_segments};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlashKernelWriteOptions {
+    /// Entry point written to the header; `None` uses the image start address.
+    pub entry_address: Option<u32>,
+}
+
+/// Write a flash kernel image. CLI: /XK.
+pub fn write_flash_kernel(
+    hexfile: &HexFile,
+    options: &FlashKernelWriteOptions,
+) -> Result<Vec<u8>, ParseError> {
+    let segments = &*normalized_sorted_segments(hexfile);
+    let segment = match segments {
+        [segment] => segment,
+        [] => {
+            return Err(ParseError::InvalidOutput(
+                "flash kernel requires data".to_string(),
+            ));
+        }
+        [first, second, ..] => {
+            return Err(ParseError::InvalidOutput(format!(
+                "flash kernel supports one segment, got {} (second at {:#X} after {:#X}); fill gaps first (/FA)",
+                segments.len(),
+                second.start_address,
+                first.start_address
+            )));
+        }
+    };
+
+    let length = u32::try_from(segment.len())
+        .map_err(|_| ParseError::InvalidOutput("flash kernel image exceeds 32 bits".to_string()))?;
+    let entry = options.entry_address.unwrap_or(segment.start_address);
+
+    let mut out = Vec::with_capacity(segment.len() + 8);
+    out.extend_from_slice(&length.to_le_bytes());
+    out.extend_from_slice(&entry.to_le_bytes());
+    out.extend_from_slice(&segment.data);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Segment;
+
+    #[test]
+    fn test_write_flash_kernel_header() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x2000_0000, vec![0xAA, 0xBB])]);
+        let out = write_flash_kernel(&hexfile, &FlashKernelWriteOptions::default()).unwrap();
+        assert_eq!(
+            out,
+            vec![0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0xAA, 0xBB]
+        );
+
+        let options = FlashKernelWriteOptions {
+            entry_address: Some(0x2000_0101),
+        };
+        let out = write_flash_kernel(&hexfile, &options).unwrap();
+        assert_eq!(&out[4..8], &[0x01, 0x01, 0x00, 0x20]);
+    }
+
+    #[test]
+    fn test_write_flash_kernel_rejects_multiple_segments() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x1000, vec![0x01]),
+            Segment::new(0x2000, vec![0x02]),
+        ]);
+        let err = write_flash_kernel(&hexfile, &FlashKernelWriteOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("one segment"), "{err}");
+    }
+}
@@ -0,0 +1,158 @@
+//! GAC flash container export (CLI: /XGAC, /XGACSWIL).
+//!
+//! Experimental: the request named the options but gave no layout, and there
+//! is no reference container or HexView output to check against. The header,
+//! block table and SWIL interleave below are our own design, so a real GAC
+//! consumer may not accept them, and the tests only pin them against
+//! regressions. Expect changes once a reference container is available.
+//!
+//! Layout (multi-byte values big-endian):
+//!
+//! - 0x00: part number, ASCII, 16 bytes, zero padded
+//! - 0x10: software version, ASCII, 8 bytes, zero padded
+//! - 0x18: block count (u16)
+//! - 0x1A: block table, per block: length (u32), start address (u32)
+//! - then the block data in table order, without padding between blocks
+//!
+//! Blocks are the normalized segments sorted by address. The SWIL variant
+//! stores each block as interleaved 16-bit words (high and low byte swapped);
+//! odd-length blocks get one `fill_byte` appended first and the table length
+//! includes it.
+
+use crate::HexFile;
+use crate::io::{ParseError, normalized_sorted_segments};
+
+const PART_NUMBER_LEN: usize = 16;
+const SW_VERSION_LEN: usize = 8;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GacWriteOptions {
+    pub part_number: String,
+    pub sw_version: String,
+    /// /XGACSWIL: word-interleaved block data.
+    pub swil: bool,
+    /// Pads odd-length blocks in SWIL mode.
+    pub fill_byte: u8,
+}
+
+/// Write a GAC container. CLI: /XGAC, /XGACSWIL.
+pub fn write_gac(hexfile: &HexFile, options: &GacWriteOptions) -> Result<Vec<u8>, ParseError> {
+    let segments = &*normalized_sorted_segments(hexfile);
+    let count = u16::try_from(segments.len()).map_err(|_| {
+        ParseError::InvalidOutput(format!(
+            "GAC supports at most 65535 blocks, got {}",
+            segments.len()
+        ))
+    })?;
+
+    let mut out = Vec::new();
+    push_padded(
+        &mut out,
+        "part number",
+        &options.part_number,
+        PART_NUMBER_LEN,
+    )?;
+    push_padded(
+        &mut out,
+        "software version",
+        &options.sw_version,
+        SW_VERSION_LEN,
+    )?;
+    out.extend_from_slice(&count.to_be_bytes());
+
+    let blocks: Vec<(u32, Vec<u8>)> = segments
+        .iter()
+        .map(|segment| {
+            let mut data = segment.data.to_vec();
+            if options.swil {
+                if data.len() % 2 != 0 {
+                    data.push(options.fill_byte);
+                }
+                for word in data.chunks_exact_mut(2) {
+                    word.swap(0, 1);
+                }
+            }
+            (segment.start_address, data)
+        })
+        .collect();
+
+    for (address, data) in &blocks {
+        let len = u32::try_from(data.len()).map_err(|_| {
+            ParseError::InvalidOutput(format!("GAC block at {address:#X} exceeds 32 bits"))
+        })?;
+        out.extend_from_slice(&len.to_be_bytes());
+        out.extend_from_slice(&address.to_be_bytes());
+    }
+    for (_, data) in &blocks {
+        out.extend_from_slice(data);
+    }
+    Ok(out)
+}
+
+fn push_padded(out: &mut Vec<u8>, name: &str, value: &str, len: usize) -> Result<(), ParseError> {
+    if !value.is_ascii() || value.len() > len {
+        return Err(ParseError::InvalidOutput(format!(
+            "GAC {name} must be at most {len} ASCII characters, got '{value}'"
+        )));
+    }
+    out.extend_from_slice(value.as_bytes());
+    out.resize(out.len() + len - value.len(), 0);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Segment;
+
+    fn image() -> HexFile {
+        HexFile::with_segments(vec![
+            Segment::new(0x8000, vec![0x11, 0x22, 0x33]),
+            Segment::new(0x1000, vec![0xAA, 0xBB]),
+        ])
+    }
+
+    fn options(swil: bool) -> GacWriteOptions {
+        GacWriteOptions {
+            part_number: "8E0907115".to_string(),
+            sw_version: "0010".to_string(),
+            swil,
+            fill_byte: 0xFF,
+        }
+    }
+
+    fn expected_header() -> Vec<u8> {
+        let mut header = b"8E0907115".to_vec();
+        header.resize(16, 0);
+        header.extend_from_slice(b"0010\0\0\0\0");
+        header.extend_from_slice(&[0x00, 0x02]);
+        header
+    }
+
+    #[test]
+    fn test_write_gac_layout() {
+        let mut expected = expected_header();
+        expected.extend_from_slice(&[0, 0, 0, 2, 0, 0, 0x10, 0x00]);
+        expected.extend_from_slice(&[0, 0, 0, 3, 0, 0, 0x80, 0x00]);
+        expected.extend_from_slice(&[0xAA, 0xBB, 0x11, 0x22, 0x33]);
+        assert_eq!(write_gac(&image(), &options(false)).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_write_gac_swil_swaps_words_and_pads() {
+        let mut expected = expected_header();
+        expected.extend_from_slice(&[0, 0, 0, 2, 0, 0, 0x10, 0x00]);
+        expected.extend_from_slice(&[0, 0, 0, 4, 0, 0, 0x80, 0x00]);
+        expected.extend_from_slice(&[0xBB, 0xAA, 0x22, 0x11, 0xFF, 0x33]);
+        assert_eq!(write_gac(&image(), &options(true)).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_write_gac_rejects_long_part_number() {
+        let options = GacWriteOptions {
+            part_number: "X".repeat(17),
+            ..options(false)
+        };
+        assert!(write_gac(&image(), &options).is_err());
+    }
+}
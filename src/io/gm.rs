@@ -0,0 +1,248 @@
+//! GM-style module header export (CLI: /XG, /XGC, /XGCC).
+//!
+//! Experimental: this layout is our own reading of the GM module header.
+//! There is no HexView reference output to check it against, so the tests
+//! only pin it against regressions and it may still change.
+//!
+//! The header is big-endian. All variants start with a 16-bit checksum and
+//! the 16-bit module ID, followed by:
+//!
+//! - /XG: product memory address (4), number of bytes (4)
+//! - /XGC (OS): part number (4), design level suffix (2 ASCII), header format
+//!   ID (2), region count (1), then start (4) and length (4) per region
+//! - /XGCC (calibration): part number (4), design level suffix (2 ASCII),
+//!   product memory address (4), number of bytes (4)
+//!
+//! Product memory address / number of bytes describe the data span; /XGC lists
+//! every contiguous region instead. The checksum is the 16-bit wrapping byte
+//! sum of the header bytes after it plus the data span, gaps counted as
+//! `fill_byte`.
+
+use crate::io::{IntelHexWriteOptions, ParseError, normalized_sorted_segments, write_intel_hex};
+use crate::{HexFile, Segment};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GmHeaderKind {
+    /// /XG: module ID, product memory address and number of bytes.
+    Basic,
+    /// /XGC: operating system header with a region table.
+    OperatingSystem {
+        part_number: u32,
+        design_level_suffix: [u8; 2],
+        header_format_id: u16,
+    },
+    /// /XGCC: calibration header.
+    Calibration {
+        part_number: u32,
+        design_level_suffix: [u8; 2],
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GmHeaderOptions {
+    pub kind: GmHeaderKind,
+    pub module_id: u16,
+    /// Header address; `None` places it directly before the data.
+    pub address: Option<u32>,
+    /// Byte counted for gaps in the data span.
+    pub fill_byte: u8,
+}
+
+/// Return a copy of `hexfile` with the GM header added.
+pub fn add_gm_header(hexfile: &HexFile, options: &GmHeaderOptions) -> Result<HexFile, ParseError> {
+    let segments = &*normalized_sorted_segments(hexfile);
+    let (Some(first), Some(last)) = (segments.first(), segments.last()) else {
+        return Err(ParseError::InvalidOutput(
+            "GM header requires data".to_string(),
+        ));
+    };
+    let start = first.start_address;
+    let span = last.end_address() as u64 - start as u64 + 1;
+    let span = u32::try_from(span).map_err(|_| {
+        ParseError::InvalidOutput("GM header data span exceeds 32 bits".to_string())
+    })?;
+
+    let mut body = options.module_id.to_be_bytes().to_vec();
+    match &options.kind {
+        GmHeaderKind::Basic => {
+            body.extend_from_slice(&start.to_be_bytes());
+            body.extend_from_slice(&span.to_be_bytes());
+        }
+        GmHeaderKind::OperatingSystem {
+            part_number,
+            design_level_suffix,
+            header_format_id,
+        } => {
+            let count = u8::try_from(segments.len()).map_err(|_| {
+                ParseError::InvalidOutput(format!(
+                    "GM OS header supports at most 255 regions, got {}",
+                    segments.len()
+                ))
+            })?;
+            body.extend_from_slice(&part_number.to_be_bytes());
+            body.extend_from_slice(design_level_suffix);
+            body.extend_from_slice(&header_format_id.to_be_bytes());
+            body.push(count);
+            for segment in segments {
+                body.extend_from_slice(&segment.start_address.to_be_bytes());
+                body.extend_from_slice(&(segment.len() as u32).to_be_bytes());
+            }
+        }
+        GmHeaderKind::Calibration {
+            part_number,
+            design_level_suffix,
+        } => {
+            body.extend_from_slice(&part_number.to_be_bytes());
+            body.extend_from_slice(design_level_suffix);
+            body.extend_from_slice(&start.to_be_bytes());
+            body.extend_from_slice(&span.to_be_bytes());
+        }
+    }
+
+    let header_len = 2 + body.len() as u32;
+    let header_addr = match options.address {
+        Some(addr) => addr,
+        None => start.checked_sub(header_len).ok_or_else(|| {
+            ParseError::InvalidOutput(format!(
+                "no room for {header_len}-byte GM header before {start:#X}"
+            ))
+        })?,
+    };
+    let header_end = header_addr as u64 + header_len as u64 - 1;
+    if header_end > u32::MAX as u64 {
+        return Err(ParseError::AddressOverflow(format!(
+            "GM header at {header_addr:#X}"
+        )));
+    }
+    if let Some(segment) = segments
+        .iter()
+        .find(|s| s.start_address as u64 <= header_end && header_addr <= s.end_address())
+    {
+        return Err(ParseError::InvalidOutput(format!(
+            "GM header at {header_addr:#X} overlaps data at {:#X}",
+            segment.start_address
+        )));
+    }
+
+    let mut sum = byte_sum(0, &body);
+    let mut next = start;
+    for segment in segments {
+        let gap = (segment.start_address - next) as u64;
+        sum = sum.wrapping_add((gap * options.fill_byte as u64) as u16);
+        sum = byte_sum(sum, &segment.data);
+        next = segment.end_address().wrapping_add(1);
+    }
+
+    let mut header = sum.to_be_bytes().to_vec();
+    header.extend_from_slice(&body);
+    let mut out = HexFile::with_segments(segments.to_vec());
+    out.append_segment(Segment::new(header_addr, header));
+    Ok(out)
+}
+
+/// Add the GM header and write Intel HEX. CLI: /XG, /XGC, /XGCC.
+pub fn write_gm_header(
+    hexfile: &HexFile,
+    options: &GmHeaderOptions,
+    hex_options: &IntelHexWriteOptions,
+) -> Result<Vec<u8>, ParseError> {
+    let with_header = add_gm_header(hexfile, options)?;
+    write_intel_hex(&with_header, hex_options)
+}
+
+fn byte_sum(init: u16, data: &[u8]) -> u16 {
+    data.iter().fold(init, |acc, &b| acc.wrapping_add(b as u16))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image() -> HexFile {
+        HexFile::with_segments(vec![
+            Segment::new(0x1010, vec![0x01, 0x02, 0x03, 0x04]),
+            Segment::new(0x1016, vec![0x10]),
+        ])
+    }
+
+    fn header_bytes(hexfile: &HexFile, options: &GmHeaderOptions) -> (u32, Vec<u8>) {
+        let out = add_gm_header(hexfile, options).unwrap();
+        let segments = out.segments();
+        let header = segments.last().unwrap();
+        (header.start_address, header.data.to_vec())
+    }
+
+    #[test]
+    fn test_gm_header_basic_layout() {
+        let options = GmHeaderOptions {
+            kind: GmHeaderKind::Basic,
+            module_id: 0x0005,
+            address: None,
+            fill_byte: 0xFF,
+        };
+        let hex = write_gm_header(&image(), &options, &IntelHexWriteOptions::default()).unwrap();
+        // Header at 0x1004: sum = 0x2C (header) + 0x1A (data) + 2 gap bytes * 0xFF = 0x0244.
+        assert_eq!(
+            String::from_utf8(hex).unwrap(),
+            ":101004000244000500001010000000070102030460\r\n\
+             :0110160010C9\r\n\
+             :00000001FF\r\n"
+        );
+    }
+
+    #[test]
+    fn test_gm_header_os_region_table() {
+        let options = GmHeaderOptions {
+            kind: GmHeaderKind::OperatingSystem {
+                part_number: 0x0123_4567,
+                design_level_suffix: *b"AB",
+                header_format_id: 0x0001,
+            },
+            module_id: 0x0001,
+            address: Some(0x0000),
+            fill_byte: 0x00,
+        };
+        let (addr, header) = header_bytes(&image(), &options);
+        assert_eq!(addr, 0x0000);
+        let body = [
+            0x00, 0x01, 0x01, 0x23, 0x45, 0x67, b'A', b'B', 0x00, 0x01, 0x02, 0x00, 0x00, 0x10,
+            0x10, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x10, 0x16, 0x00, 0x00, 0x00, 0x01,
+        ];
+        let sum = byte_sum(byte_sum(0, &body), &[0x01, 0x02, 0x03, 0x04, 0x10]);
+        assert_eq!(&header[..2], &sum.to_be_bytes());
+        assert_eq!(&header[2..], &body);
+    }
+
+    #[test]
+    fn test_gm_header_calibration_fields() {
+        let options = GmHeaderOptions {
+            kind: GmHeaderKind::Calibration {
+                part_number: 0x00BC_614E,
+                design_level_suffix: *b"AA",
+            },
+            module_id: 0x0002,
+            address: Some(0x2000),
+            fill_byte: 0xFF,
+        };
+        let (_, header) = header_bytes(&image(), &options);
+        assert_eq!(
+            &header[2..],
+            &[
+                0x00, 0x02, 0x00, 0xBC, 0x61, 0x4E, b'A', b'A', 0x00, 0x00, 0x10, 0x10, 0x00, 0x00,
+                0x00, 0x07
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gm_header_rejects_overlap_and_empty() {
+        let options = GmHeaderOptions {
+            kind: GmHeaderKind::Basic,
+            module_id: 1,
+            address: Some(0x1008),
+            fill_byte: 0xFF,
+        };
+        assert!(add_gm_header(&image(), &options).is_err());
+        assert!(add_gm_header(&HexFile::new(), &options).is_err());
+    }
+}
@@ -1,10 +1,39 @@
-use crate::io::{ParseError, normalized_sorted_segments, push_crlf, push_hex_byte};
+use crate::io::{LineEnding, ParseError, normalized_sorted_segments, push_hex_byte};
 use crate::{HexFile, Segment};
 
+/// Address column written at the start of each HEX ASCII line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressPrefixStyle {
+    /// `@00001000: `. CLI: /XA:len:sep:ADDR.
+    At,
+    /// `00001000 `. CLI: /XA:len:sep:PLAIN.
+    Plain,
+}
+
+impl AddressPrefixStyle {
+    fn push(self, out: &mut Vec<u8>, address: u32, uppercase: bool) {
+        if self == AddressPrefixStyle::At {
+            out.push(b'@');
+        }
+        for byte in address.to_be_bytes() {
+            push_hex_byte(out, byte, uppercase);
+        }
+        match self {
+            AddressPrefixStyle::At => out.extend_from_slice(b": "),
+            AddressPrefixStyle::Plain => out.push(b' '),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct HexAsciiWriteOptions {
     pub line_length: usize,
     pub separator: Option<String>,
+    pub line_ending: LineEnding,
+    /// Uppercase hex digits (data and address prefix); lowercase otherwise.
+    pub uppercase: bool,
+    /// Start each line with its address. Each segment then starts a new line.
+    pub address_prefix: Option<AddressPrefixStyle>,
 }
 
 impl Default for HexAsciiWriteOptions {
@@ -12,35 +41,105 @@ impl Default for HexAsciiWriteOptions {
         Self {
             line_length: 16,
             separator: None,
+            line_ending: LineEnding::CrLf,
+            uppercase: true,
+            address_prefix: None,
         }
     }
 }
 
-/// Parse a HEX ASCII data file into a single segment at the given base address.
-/// Non-hex characters are treated as separators. CLI: /IA.
+#[derive(Debug, Clone, Default)]
+pub struct HexAsciiParseOptions {
+    /// Read the first token of every line as a `Plain` address prefix.
+    /// `@address:` prefixes are recognised regardless.
+    pub address_prefix: Option<AddressPrefixStyle>,
+}
+
+/// Parse a HEX ASCII data file. Non-hex characters are treated as separators.
+/// Data starts at `base_address`; a line prefixed `@address:` places its data
+/// at that address instead, so the result may have several segments. CLI: /IA.
 pub fn parse_hex_ascii(data: &[u8], base_address: u32) -> Result<HexFile, ParseError> {
+    parse_hex_ascii_with_options(data, base_address, &HexAsciiParseOptions::default())
+}
+
+/// As [`parse_hex_ascii`], with control over address prefixes.
+pub fn parse_hex_ascii_with_options(
+    data: &[u8],
+    base_address: u32,
+    options: &HexAsciiParseOptions,
+) -> Result<HexFile, ParseError> {
+    parse_lines(data, base_address, options).map_err(|e| e.with_record_in(data))
+}
+
+fn parse_lines(
+    data: &[u8],
+    base_address: u32,
+    options: &HexAsciiParseOptions,
+) -> Result<HexFile, ParseError> {
+    let mut segments = Vec::new();
+    let mut start = base_address;
     let mut bytes = Vec::new();
-    let mut line_no = 1usize;
-    let mut token_digits: Vec<u8> = Vec::new();
 
-    let mut idx = 0usize;
-    while idx < data.len() {
-        let b = data[idx];
-        if b == b'\r' {
-            idx += 1;
-            continue;
-        }
-        if b == b'\n' {
-            if !token_digits.is_empty() {
-                push_hex_token(&token_digits, &mut bytes, line_no)?;
-                token_digits.clear();
+    for (idx, line) in data.split(|&b| b == b'\n').enumerate() {
+        let line_no = idx + 1;
+        let mut rest = line.trim_ascii_start();
+        if let Some((address, tail)) = split_address_prefix(rest, options.address_prefix, line_no)?
+        {
+            if u64::from(address) != u64::from(start) + bytes.len() as u64 {
+                push_segment(&mut segments, start, std::mem::take(&mut bytes))?;
+                start = address;
             }
-            line_no += 1;
-            idx += 1;
-            continue;
+            rest = tail;
         }
-        if b == b'0' && idx + 1 < data.len() && token_digits.is_empty() {
-            let next = data[idx + 1];
+        parse_tokens(rest, &mut bytes, line_no)?;
+    }
+    push_segment(&mut segments, start, bytes)?;
+
+    Ok(HexFile::with_segments(segments))
+}
+
+/// Splits `@address:` (or a `Plain` leading address) off a line.
+fn split_address_prefix(
+    line: &[u8],
+    style: Option<AddressPrefixStyle>,
+    line_no: usize,
+) -> Result<Option<(u32, &[u8])>, ParseError> {
+    let at = match line.first() {
+        Some(b'@') => true,
+        Some(_) if style == Some(AddressPrefixStyle::Plain) => false,
+        _ => return Ok(None),
+    };
+    let digits_start = usize::from(at);
+    let digits = line[digits_start..]
+        .iter()
+        .take_while(|b| b.is_ascii_hexdigit())
+        .count();
+    let end = digits_start + digits;
+    let terminated = match line.get(end) {
+        Some(b':') => true,
+        Some(b) => !at && b.is_ascii_whitespace(),
+        None => !at,
+    };
+    if digits == 0 || digits > 8 || !terminated {
+        return Err(ParseError::InvalidRecord {
+            line: line_no,
+            message: "invalid address prefix".to_string(),
+            record: None,
+        });
+    }
+    let text = std::str::from_utf8(&line[digits_start..end]).expect("hex digits are ASCII");
+    let address = u32::from_str_radix(text, 16).expect("at most 8 hex digits");
+    Ok(Some((address, line.get(end + 1..).unwrap_or_default())))
+}
+
+fn parse_tokens(line: &[u8], bytes: &mut Vec<u8>, line_no: usize) -> Result<(), ParseError> {
+    let mut token_digits: Vec<u8> = Vec::new();
+
+    let mut idx = 0usize;
+    while idx < line.len() {
+        let b = line[idx];
+        if b == b'0' && idx + 1 < line.len() && token_digits.is_empty() {
+            let next = line[idx + 1];
             if next == b'x' || next == b'X' {
                 idx += 2;
                 continue;
@@ -52,42 +151,36 @@ pub fn parse_hex_ascii(data: &[u8], base_address: u32) -> Result<HexFile, ParseE
             continue;
         }
         if !token_digits.is_empty() {
-            push_hex_token(&token_digits, &mut bytes, line_no)?;
+            push_hex_token(&token_digits, bytes, line_no)?;
             token_digits.clear();
         }
         idx += 1;
     }
 
     if !token_digits.is_empty() {
-        push_hex_token(&token_digits, &mut bytes, line_no)?;
+        push_hex_token(&token_digits, bytes, line_no)?;
     }
+    Ok(())
+}
 
+fn push_segment(segments: &mut Vec<Segment>, start: u32, bytes: Vec<u8>) -> Result<(), ParseError> {
     if bytes.is_empty() {
-        return Ok(HexFile::new());
+        return Ok(());
     }
-
-    let len = bytes.len() as u32;
-    let end = base_address
-        .checked_add(len.saturating_sub(1))
-        .ok_or_else(|| {
-            ParseError::AddressOverflow(format!("{:#X} + {} exceeds u32", base_address, len))
-        })?;
-    if end < base_address {
+    let len = bytes.len() as u64;
+    if u64::from(start) + len - 1 > u64::from(u32::MAX) {
         return Err(ParseError::AddressOverflow(format!(
             "{:#X} + {} exceeds u32",
-            base_address, len
+            start, len
         )));
     }
-
-    Ok(HexFile::with_segments(vec![Segment::new(
-        base_address,
-        bytes,
-    )]))
+    segments.push(Segment::new(start, bytes));
+    Ok(())
 }
 
 /// Write the HexFile to HEX ASCII bytes. CLI: /XA.
 pub fn write_hex_ascii(hexfile: &HexFile, options: &HexAsciiWriteOptions) -> Vec<u8> {
-    let segments = normalized_sorted_segments(hexfile);
+    let segments = &*normalized_sorted_segments(hexfile);
 
     let mut out = Vec::new();
     let mut line_len = options.line_length;
@@ -99,20 +192,33 @@ pub fn write_hex_ascii(hexfile: &HexFile, options: &HexAsciiWriteOptions) -> Vec
     let mut current_count = 0usize;
 
     for segment in segments {
-        for &byte in &segment.data {
+        if options.address_prefix.is_some() && current_count > 0 {
+            options.line_ending.push(&mut out);
+            current_count = 0;
+        }
+        for (offset, &byte) in segment.data.iter().enumerate() {
             if current_count == line_len {
-                push_crlf(&mut out);
+                options.line_ending.push(&mut out);
                 current_count = 0;
             } else if current_count > 0 && !sep.is_empty() {
                 out.extend_from_slice(sep.as_bytes());
             }
-            push_hex_byte(&mut out, byte);
+            if current_count == 0
+                && let Some(style) = options.address_prefix
+            {
+                style.push(
+                    &mut out,
+                    segment.start_address + offset as u32,
+                    options.uppercase,
+                );
+            }
+            push_hex_byte(&mut out, byte, options.uppercase);
             current_count += 1;
         }
     }
 
     if !out.is_empty() {
-        push_crlf(&mut out);
+        options.line_ending.push(&mut out);
     }
 
     out
@@ -125,6 +231,7 @@ fn push_hex_token(digits: &[u8], out: &mut Vec<u8>, line: usize) -> Result<(), P
             .ok_or(ParseError::InvalidHexDigit {
                 line,
                 char: digits[0] as char,
+                record: None,
             })?;
         out.push(hi as u8);
         return Ok(());
@@ -134,6 +241,7 @@ fn push_hex_token(digits: &[u8], out: &mut Vec<u8>, line: usize) -> Result<(), P
         return Err(ParseError::InvalidRecord {
             line,
             message: "odd number of hex digits".to_string(),
+            record: None,
         });
     }
 
@@ -144,12 +252,14 @@ fn push_hex_token(digits: &[u8], out: &mut Vec<u8>, line: usize) -> Result<(), P
             .ok_or(ParseError::InvalidHexDigit {
                 line,
                 char: hi as char,
+                record: None,
             })?;
         let lo = (lo as char)
             .to_digit(16)
             .ok_or(ParseError::InvalidHexDigit {
                 line,
                 char: lo as char,
+                record: None,
             })?;
         out.push(((hi << 4) | lo) as u8);
     }
@@ -166,6 +276,7 @@ mod tests {
         let options = HexAsciiWriteOptions {
             line_length: 2,
             separator: Some(", ".to_string()),
+            ..Default::default()
         };
         let out = write_hex_ascii(&hexfile, &options);
         let parsed = parse_hex_ascii(&out, 0x1000).unwrap();
@@ -174,6 +285,79 @@ mod tests {
         assert_eq!(parsed.segments()[0].data, vec![0xDE, 0xAD, 0xBE]);
     }
 
+    #[test]
+    fn test_hex_ascii_lowercase_lf() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0, vec![0xDE, 0xAD, 0xBE])]);
+        let options = HexAsciiWriteOptions {
+            line_length: 2,
+            separator: Some(" ".to_string()),
+            line_ending: LineEnding::Lf,
+            uppercase: false,
+            address_prefix: None,
+        };
+        assert_eq!(write_hex_ascii(&hexfile, &options), b"de ad\nbe\n");
+    }
+
+    #[test]
+    fn test_hex_ascii_address_prefix_restarts_per_segment() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x1000, vec![0xAA, 0xBB, 0xCC]),
+            Segment::new(0x2000, vec![0xDD]),
+        ]);
+        let options = HexAsciiWriteOptions {
+            line_length: 2,
+            separator: Some(" ".to_string()),
+            line_ending: LineEnding::Lf,
+            uppercase: false,
+            address_prefix: Some(AddressPrefixStyle::At),
+        };
+        let out = write_hex_ascii(&hexfile, &options);
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            "@00001000: aa bb\n@00001002: cc\n@00002000: dd\n"
+        );
+        assert_eq!(
+            parse_hex_ascii(&out, 0).unwrap().segments(),
+            hexfile.segments()
+        );
+
+        let options = HexAsciiWriteOptions {
+            line_length: 0,
+            address_prefix: Some(AddressPrefixStyle::Plain),
+            ..options
+        };
+        let out = write_hex_ascii(&hexfile, &options);
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            "00001000 aa bb cc\n00002000 dd\n"
+        );
+        let parse_options = HexAsciiParseOptions {
+            address_prefix: Some(AddressPrefixStyle::Plain),
+        };
+        let parsed = parse_hex_ascii_with_options(&out, 0, &parse_options).unwrap();
+        assert_eq!(parsed.segments(), hexfile.segments());
+    }
+
+    #[test]
+    fn test_hex_ascii_prefix_lines_continue_or_jump() {
+        let data = b"11 22\n@00000010: 33\n@00000011: 44\n55\n@8: 66\n";
+        let parsed = parse_hex_ascii(data, 0x4).unwrap();
+        assert_eq!(
+            parsed.segments(),
+            &[
+                Segment::new(0x4, vec![0x11, 0x22]),
+                Segment::new(0x10, vec![0x33, 0x44, 0x55]),
+                Segment::new(0x8, vec![0x66]),
+            ]
+        );
+
+        let err = parse_hex_ascii(b"AA\n@1000 BB\n", 0).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid record at line 2: invalid address prefix in record \"@1000 BB\""
+        );
+    }
+
     #[test]
     fn test_hex_ascii_odd_digits_error() {
         let data = b"0A1";
@@ -181,6 +365,15 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_hex_ascii_error_shows_line_text() {
+        let err = parse_hex_ascii(b"12 34\r\n56 789\n", 0).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid record at line 2: odd number of hex digits in record \"56 789\""
+        );
+    }
+
     #[test]
     fn test_hex_ascii_accepts_0x_prefix() {
         let data = b"0x12, 0x34\n0XAB";
@@ -0,0 +1,133 @@
+//! Human-readable hex dump (CLI: /XD).
+//!
+//! Each line is `AAAAAAAA: hex bytes  |ascii|`, starting at the segment start
+//! address. A short last line is padded so the ASCII gutter stays aligned.
+//! Segments are separated by a blank line and a `-- gap --` line naming the
+//! missing address range.
+
+use crate::HexFile;
+use crate::io::{normalized_sorted_segments, push_crlf};
+
+#[derive(Debug, Clone)]
+pub struct HexDumpOptions {
+    /// Bytes per line; 0 means 16.
+    pub bytes_per_line: usize,
+    pub show_ascii: bool,
+    pub uppercase: bool,
+}
+
+impl Default for HexDumpOptions {
+    fn default() -> Self {
+        Self {
+            bytes_per_line: 16,
+            show_ascii: true,
+            uppercase: true,
+        }
+    }
+}
+
+/// Write an `xxd`-style dump of all segments. CLI: /XD.
+pub fn write_hex_dump(hexfile: &HexFile, options: &HexDumpOptions) -> Vec<u8> {
+    let segments = &*normalized_sorted_segments(hexfile);
+    let bytes_per_line = if options.bytes_per_line == 0 {
+        16
+    } else {
+        options.bytes_per_line
+    };
+    let hex_width = bytes_per_line * 3 - 1;
+
+    let mut out = Vec::new();
+    let mut previous_end: Option<u32> = None;
+    for segment in segments {
+        if let Some(end) = previous_end {
+            let gap_start = end as u64 + 1;
+            let gap_end = segment.start_address as u64 - 1;
+            push_crlf(&mut out);
+            let line = format!(
+                "-- gap {} - {} ({} bytes) --",
+                hex(gap_start, 8, options.uppercase),
+                hex(gap_end, 8, options.uppercase),
+                gap_end - gap_start + 1
+            );
+            out.extend_from_slice(line.as_bytes());
+            push_crlf(&mut out);
+        }
+
+        let mut addr = segment.start_address as u64;
+        for chunk in segment.data.chunks(bytes_per_line) {
+            let bytes: Vec<String> = chunk
+                .iter()
+                .map(|&b| hex(b as u64, 2, options.uppercase))
+                .collect();
+            let mut line = format!("{}: ", hex(addr, 8, options.uppercase));
+            if options.show_ascii {
+                let ascii: String = chunk
+                    .iter()
+                    .map(|&b| {
+                        if b.is_ascii_graphic() || b == b' ' {
+                            b as char
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect();
+                line.push_str(&format!("{:<hex_width$}  |{ascii}|", bytes.join(" ")));
+            } else {
+                line.push_str(&bytes.join(" "));
+            }
+            out.extend_from_slice(line.as_bytes());
+            push_crlf(&mut out);
+            addr += chunk.len() as u64;
+        }
+        previous_end = Some(segment.end_address());
+    }
+    out
+}
+
+fn hex(value: u64, width: usize, uppercase: bool) -> String {
+    if uppercase {
+        format!("{value:0width$X}")
+    } else {
+        format!("{value:0width$x}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Segment;
+
+    #[test]
+    fn test_hex_dump_partial_last_line_and_gap() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x1000, b"Hello, h3xy!\x00\x01\x7F\xFFAB".to_vec()),
+            Segment::new(0x2000, vec![0x0A]),
+        ]);
+        let out = String::from_utf8(write_hex_dump(&hexfile, &HexDumpOptions::default())).unwrap();
+        assert_eq!(
+            out,
+            "00001000: 48 65 6C 6C 6F 2C 20 68 33 78 79 21 00 01 7F FF  |Hello, h3xy!....|\r\n\
+             00001010: 41 42                                            |AB|\r\n\
+             \r\n\
+             -- gap 00001012 - 00001FFF (4078 bytes) --\r\n\
+             00002000: 0A                                               |.|\r\n"
+        );
+    }
+
+    #[test]
+    fn test_hex_dump_lowercase_without_ascii() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0xABCD, vec![0xDE, 0xAD, 0xBE])]);
+        let options = HexDumpOptions {
+            bytes_per_line: 2,
+            show_ascii: false,
+            uppercase: false,
+        };
+        let out = String::from_utf8(write_hex_dump(&hexfile, &options)).unwrap();
+        assert_eq!(out, "0000abcd: de ad\r\n0000abcf: be\r\n");
+    }
+
+    #[test]
+    fn test_hex_dump_empty() {
+        assert!(write_hex_dump(&HexFile::new(), &HexDumpOptions::default()).is_empty());
+    }
+}
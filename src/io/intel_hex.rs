@@ -1,10 +1,20 @@
-use super::{ParseError, normalized_sorted_segments, push_crlf, push_hex_byte};
-use crate::{HexFile, Segment};
+use std::io::{BufRead, Write};
+
+use super::{
+    LineEnding, ParseError, ParseWarning, RunReader, contiguous_runs, normalized_sorted_segments,
+    push_hex_byte, sorted_segments,
+};
+use crate::{HexFile, OpsError, Segment, SourceFormat};
 
 const RECORD_DATA: u8 = 0x00;
 const RECORD_EOF: u8 = 0x01;
 const RECORD_EXTENDED_SEGMENT: u8 = 0x02;
+const RECORD_START_SEGMENT: u8 = 0x03;
 const RECORD_EXTENDED_LINEAR: u8 = 0x04;
+const RECORD_START_LINEAR: u8 = 0x05;
+
+/// Highest address a type 02 segment base (<< 4) plus 16-bit offset reaches.
+const EXTENDED_SEGMENT_MAX: u32 = 0xFFFFF;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum IntelHexMode {
@@ -18,6 +28,17 @@ pub enum IntelHexMode {
 pub struct IntelHexWriteOptions {
     pub bytes_per_line: u8,
     pub mode: IntelHexMode,
+    /// Written as a type 05 start linear address record before EOF.
+    pub entry_point: Option<u32>,
+    pub line_ending: LineEnding,
+    /// Uppercase hex digits; lowercase otherwise.
+    pub uppercase: bool,
+    /// Skip the leading extended address record when its value is zero, also
+    /// in the forced modes. `Auto` already leaves it out.
+    pub omit_initial_extended: bool,
+    /// Start every data record at a multiple of `bytes_per_line`; the first
+    /// record of an unaligned segment is shortened. CLI: /ALIGNREC.
+    pub align_records: bool,
 }
 
 impl Default for IntelHexWriteOptions {
@@ -25,53 +46,132 @@ impl Default for IntelHexWriteOptions {
         Self {
             bytes_per_line: 32,
             mode: IntelHexMode::Auto,
+            entry_point: None,
+            line_ending: LineEnding::CrLf,
+            uppercase: true,
+            omit_initial_extended: false,
+            align_records: false,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Tolerate a missing EOF record, ignore everything after EOF, and skip
+    /// lines that do not start with ':'. For S-Record input, accept an S5/S6
+    /// count that disagrees with the data records. Each is reported as a
+    /// warning. Malformed records are still errors.
+    pub lenient: bool,
+}
+
 /// Parse Intel-HEX input. CLI: auto-detect Intel-HEX input.
 pub fn parse_intel_hex(input: &[u8]) -> Result<HexFile, ParseError> {
-    let text = std::str::from_utf8(input).map_err(|e| ParseError::InvalidRecord {
-        line: 1,
-        message: format!("invalid UTF-8: {e}"),
-    })?;
+    parse_intel_hex_reader(input)
+}
+
+/// Parse Intel-HEX input, returning any warnings from lenient mode. CLI: /LENIENT.
+pub fn parse_intel_hex_with(
+    input: &[u8],
+    options: &ParseOptions,
+) -> Result<(HexFile, Vec<ParseWarning>), ParseError> {
+    parse_lines(input, options)
+}
+
+/// Parse Intel-HEX from any buffered reader, one line at a time.
+///
+/// Lines are handled as raw bytes, so only the current line is buffered
+/// besides the segment data itself.
+pub fn parse_intel_hex_reader<R: BufRead>(reader: R) -> Result<HexFile, ParseError> {
+    parse_lines(reader, &ParseOptions::default()).map(|(hexfile, _)| hexfile)
+}
 
-    let mut segments: Vec<Segment> = Vec::new();
-    let mut current_segment: Option<Segment> = None;
-    let mut extended_address: u32 = 0;
-    let mut eof_seen = false;
+fn parse_lines<R: BufRead>(
+    mut reader: R,
+    options: &ParseOptions,
+) -> Result<(HexFile, Vec<ParseWarning>), ParseError> {
+    let mut parser = IntelHexParser {
+        lenient: options.lenient,
+        ..Default::default()
+    };
+    let mut line = Vec::new();
+    let mut line_num = 0;
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        line_num += 1;
+        parser
+            .parse_line(line.trim_ascii(), line_num)
+            .map_err(|e| e.with_record(&line))?;
+    }
+    parser.finish()
+}
 
-    for (line_num, line) in text.lines().enumerate() {
-        let line_num = line_num + 1;
-        let line = line.trim();
+#[derive(Default)]
+struct IntelHexParser {
+    segments: Vec<Segment>,
+    /// Start and bytes of the segment being read, kept as a plain Vec so
+    /// each record appends without going through `SegmentData`.
+    current_segment: Option<(u32, Vec<u8>)>,
+    extended_address: u32,
+    entry_point: Option<u32>,
+    eof_seen: bool,
+    lenient: bool,
+    warnings: Vec<ParseWarning>,
+    /// Decoded bytes of the current record, reused across lines.
+    record: Vec<u8>,
+}
 
+impl IntelHexParser {
+    fn parse_line(&mut self, line: &[u8], line_num: usize) -> Result<(), ParseError> {
         if line.is_empty() {
-            continue;
+            return Ok(());
         }
 
-        if eof_seen {
+        if self.eof_seen {
+            if self.lenient {
+                // Report only the first trailing line; the rest is dropped too.
+                if !matches!(
+                    self.warnings.last(),
+                    Some(ParseWarning::DataAfterEof { .. })
+                ) {
+                    self.warnings
+                        .push(ParseWarning::DataAfterEof { line: line_num });
+                }
+                return Ok(());
+            }
             return Err(ParseError::InvalidRecord {
                 line: line_num,
                 message: "data after EOF record".to_string(),
+                record: None,
             });
         }
 
-        if !line.starts_with(':') {
+        if !line.starts_with(b":") {
+            if self.lenient {
+                self.warnings
+                    .push(ParseWarning::SkippedLine { line: line_num });
+                return Ok(());
+            }
             return Err(ParseError::InvalidRecord {
                 line: line_num,
                 message: "line does not start with ':'".to_string(),
+                record: None,
             });
         }
 
-        let hex_str = &line[1..];
-        if hex_str.len() < 10 {
+        let record = &line[1..];
+        if record.len() < 10 {
             return Err(ParseError::InvalidRecord {
                 line: line_num,
                 message: "record too short".to_string(),
+                record: None,
             });
         }
 
-        let bytes = parse_hex_bytes(hex_str, line_num)?;
+        let mut bytes = std::mem::take(&mut self.record);
+        parse_hex_bytes(record, &mut bytes, line_num)?;
         validate_checksum(&bytes, line_num)?;
 
         let byte_count = bytes[0] as usize;
@@ -84,6 +184,7 @@ pub fn parse_intel_hex(input: &[u8]) -> Result<HexFile, ParseError> {
                     byte_count,
                     bytes.len().saturating_sub(5),
                 ),
+                record: None,
             });
         }
 
@@ -95,6 +196,7 @@ pub fn parse_intel_hex(input: &[u8]) -> Result<HexFile, ParseError> {
                     byte_count,
                     bytes.len() - 5
                 ),
+                record: None,
             });
         }
 
@@ -104,7 +206,8 @@ pub fn parse_intel_hex(input: &[u8]) -> Result<HexFile, ParseError> {
 
         match record_type {
             RECORD_DATA => {
-                let full_address = extended_address
+                let full_address = self
+                    .extended_address
                     .checked_add(address as u32)
                     .ok_or_else(|| ParseError::AddressOverflow(format!("line {line_num}")))?;
 
@@ -114,51 +217,62 @@ pub fn parse_intel_hex(input: &[u8]) -> Result<HexFile, ParseError> {
                         .ok_or_else(|| ParseError::AddressOverflow(format!("line {line_num}")))?;
                 }
 
-                match &mut current_segment {
-                    Some(seg) if seg.end_address().checked_add(1) == Some(full_address) => {
-                        seg.data.extend_from_slice(data);
-                    }
-                    Some(seg) => {
-                        segments.push(std::mem::replace(
-                            seg,
-                            Segment::new(full_address, data.to_vec()),
-                        ));
+                match &mut self.current_segment {
+                    Some((start, seg))
+                        if *start as u64 + seg.len() as u64 == full_address as u64 =>
+                    {
+                        seg.extend_from_slice(data);
                     }
-                    None => {
-                        current_segment = Some(Segment::new(full_address, data.to_vec()));
+                    _ => {
+                        self.flush_segment();
+                        self.current_segment = Some((full_address, data.to_vec()));
                     }
                 }
             }
             RECORD_EOF => {
-                eof_seen = true;
+                self.eof_seen = true;
             }
             RECORD_EXTENDED_SEGMENT => {
                 if byte_count != 2 {
                     return Err(ParseError::InvalidRecord {
                         line: line_num,
                         message: "extended segment address must have 2 data bytes".to_string(),
+                        record: None,
                     });
                 }
-                if let Some(seg) = current_segment.take() {
-                    segments.push(seg);
-                }
+                self.flush_segment();
                 let base = u16::from_be_bytes([data[0], data[1]]);
-                extended_address = (base as u32) << 4;
+                self.extended_address = (base as u32) << 4;
             }
             RECORD_EXTENDED_LINEAR => {
                 if byte_count != 2 {
                     return Err(ParseError::InvalidRecord {
                         line: line_num,
                         message: "extended linear address must have 2 data bytes".to_string(),
+                        record: None,
                     });
                 }
-                if let Some(seg) = current_segment.take() {
-                    segments.push(seg);
-                }
+                self.flush_segment();
                 let base = u16::from_be_bytes([data[0], data[1]]);
-                extended_address = (base as u32) << 16;
+                self.extended_address = (base as u32) << 16;
+            }
+            RECORD_START_SEGMENT | RECORD_START_LINEAR => {
+                if byte_count != 4 {
+                    return Err(ParseError::InvalidRecord {
+                        line: line_num,
+                        message: "start address must have 4 data bytes".to_string(),
+                        record: None,
+                    });
+                }
+                self.entry_point = Some(if record_type == RECORD_START_SEGMENT {
+                    // CS:IP, flattened to a physical address.
+                    let cs = u16::from_be_bytes([data[0], data[1]]) as u32;
+                    let ip = u16::from_be_bytes([data[2], data[3]]) as u32;
+                    (cs << 4) + ip
+                } else {
+                    u32::from_be_bytes([data[0], data[1], data[2], data[3]])
+                });
             }
-            0x03 | 0x05 => {}
             _ => {
                 return Err(ParseError::UnsupportedRecordType {
                     line: line_num,
@@ -166,17 +280,32 @@ pub fn parse_intel_hex(input: &[u8]) -> Result<HexFile, ParseError> {
                 });
             }
         }
+        self.record = bytes;
+        Ok(())
     }
 
-    if !eof_seen {
-        return Err(ParseError::UnexpectedEof);
+    fn flush_segment(&mut self) {
+        if let Some((start, data)) = self.current_segment.take() {
+            self.segments.push(Segment::new(start, data));
+        }
     }
 
-    if let Some(seg) = current_segment {
-        segments.push(seg);
-    }
+    fn finish(mut self) -> Result<(HexFile, Vec<ParseWarning>), ParseError> {
+        if !self.eof_seen {
+            if !self.lenient {
+                return Err(ParseError::UnexpectedEof);
+            }
+            self.warnings.push(ParseWarning::MissingEof);
+        }
 
-    Ok(HexFile::with_segments(segments))
+        self.flush_segment();
+
+        let mut hexfile = HexFile::with_segments(self.segments);
+        let metadata = hexfile.metadata_mut();
+        metadata.entry_point = self.entry_point;
+        metadata.source_format = Some(SourceFormat::IntelHex);
+        Ok((hexfile, self.warnings))
+    }
 }
 
 /// Parse Intel-HEX with 16-bit addressing (address * 2). CLI: /II2.
@@ -196,50 +325,108 @@ pub fn parse_intel_hex_16bit(input: &[u8]) -> Result<HexFile, ParseError> {
                     ParseError::AddressOverflow("16-bit address overflow".to_string())
                 })?;
         }
-        segments.push(Segment::new(start, seg.data.clone()));
+        segments.push(Segment::with_data(start, seg.data.clone()));
     }
 
-    Ok(HexFile::with_segments(segments))
+    let mut scaled = HexFile::with_segments(segments);
+    scaled.set_metadata(hexfile.metadata().clone());
+    Ok(scaled)
 }
 
 /// Write Intel-HEX output. CLI: /XI.
-pub fn write_intel_hex(hexfile: &HexFile, options: &IntelHexWriteOptions) -> Vec<u8> {
-    let segments = normalized_sorted_segments(hexfile);
-    let mut output = Vec::new();
-    let bytes_per_line = if options.bytes_per_line == 0 {
+///
+/// Without `options.entry_point` the parsed entry point in the metadata is used.
+/// Forced `ExtendedSegment` mode only reaches 0xFFFFF; data above that is an
+/// `AddressOverflow` error. `Auto` switches to `ExtendedLinear` instead.
+pub fn write_intel_hex(
+    hexfile: &HexFile,
+    options: &IntelHexWriteOptions,
+) -> Result<Vec<u8>, ParseError> {
+    let segments = sorted_segments(hexfile);
+    let bytes_per_line = intel_hex_bytes_per_line(options);
+    let total_bytes: usize = segments.iter().map(|s| s.len()).sum();
+    let total_records: usize = segments
+        .iter()
+        .map(|s| s.len().div_ceil(bytes_per_line))
+        .sum();
+    // Rough reserve: 2 hex chars per byte + per-record overhead.
+    let mut output =
+        Vec::with_capacity(total_bytes.saturating_mul(2) + total_records.saturating_mul(12) + 64);
+    write_segments(&segments, None, hexfile, options, &mut output)?;
+    Ok(output)
+}
+
+/// Stream Intel-HEX output into `writer`, one record at a time. Produces the
+/// same bytes as [`write_intel_hex`].
+pub fn write_intel_hex_to<W: Write>(
+    hexfile: &HexFile,
+    options: &IntelHexWriteOptions,
+    mut writer: W,
+) -> Result<(), ParseError> {
+    let segments = sorted_segments(hexfile);
+    write_segments(&segments, None, hexfile, options, &mut writer)
+}
+
+/// Stream Intel-HEX output of `hexfile` with every gap between its lowest and
+/// highest address filled with `fill_byte`. Produces the same bytes as
+/// [`HexFile::fill_gaps`] followed by [`write_intel_hex_to`], without
+/// building the filled image.
+pub fn write_intel_hex_filled_to<W: Write>(
+    hexfile: &HexFile,
+    options: &IntelHexWriteOptions,
+    fill_byte: u8,
+    mut writer: W,
+) -> Result<(), ParseError> {
+    let segments = sorted_segments(hexfile);
+    write_segments(&segments, Some(fill_byte), hexfile, options, &mut writer)
+}
+
+fn intel_hex_bytes_per_line(options: &IntelHexWriteOptions) -> usize {
+    if options.bytes_per_line == 0 {
         16
     } else {
-        options.bytes_per_line
-    } as usize;
-    let auto_mode = matches!(options.mode, IntelHexMode::Auto);
+        options.bytes_per_line as usize
+    }
+}
+
+fn write_segments<W: Write>(
+    segments: &[Segment],
+    gap_fill: Option<u8>,
+    hexfile: &HexFile,
+    options: &IntelHexWriteOptions,
+    writer: &mut W,
+) -> Result<(), ParseError> {
     let max_addr = segments.iter().map(|s| s.end_address()).max();
-    let auto_force_linear = auto_mode && matches!(max_addr, Some(max) if max > 0xFFFFF);
+    if let (IntelHexMode::ExtendedSegment, Some(max)) = (options.mode, max_addr)
+        && max > EXTENDED_SEGMENT_MAX
+    {
+        return Err(ParseError::AddressOverflow(format!(
+            "max address {max:#X} exceeds ExtendedSegment limit {EXTENDED_SEGMENT_MAX:#X}"
+        )));
+    }
+    let mut output = RecordSink::new(writer, options);
+    let bytes_per_line = intel_hex_bytes_per_line(options);
+    let auto_mode = matches!(options.mode, IntelHexMode::Auto);
+    let auto_force_linear =
+        auto_mode && matches!(max_addr, Some(max) if max > EXTENDED_SEGMENT_MAX);
     let fixed_mode = if auto_mode { None } else { Some(options.mode) };
+    // Record boundaries are counted from here: the run start, or address 0.
+    let line_origin = |run_start: u32| if options.align_records { 0 } else { run_start };
 
     let mut current_extended: Option<u16> = None;
     let mut current_mode: Option<IntelHexMode> = fixed_mode;
 
-    let total_bytes: usize = segments.iter().map(|s| s.len()).sum();
-    let total_records: usize = if bytes_per_line == 0 {
-        0
-    } else {
-        segments
-            .iter()
-            .map(|s| s.len().div_ceil(bytes_per_line))
-            .sum()
-    };
-    // Rough reserve: 2 hex chars per byte + per-record overhead.
-    output.reserve(total_bytes.saturating_mul(2) + total_records.saturating_mul(12) + 64);
-
-    for segment in segments {
-        let mut addr = segment.start_address;
+    for run in contiguous_runs(segments, gap_fill) {
+        let seg_start = run.start;
+        let seg_len = run.len;
+        let mut reader = RunReader::new(&run);
+        let mut addr = seg_start;
         let mut data_offset = 0;
-        let seg_start = segment.start_address;
 
-        while data_offset < segment.len() {
+        while data_offset < seg_len {
             let line_mode = if let Some(mode) = fixed_mode {
                 mode
-            } else if auto_force_linear || addr > 0xFFFFF {
+            } else if auto_force_linear || addr > EXTENDED_SEGMENT_MAX {
                 IntelHexMode::ExtendedLinear
             } else {
                 IntelHexMode::ExtendedSegment
@@ -275,6 +462,9 @@ pub fn write_intel_hex(hexfile: &HexFile, options: &IntelHexWriteOptions) -> Vec
                     should_emit = false;
                 }
             }
+            if options.omit_initial_extended && current_extended.is_none() && needed_extended == 0 {
+                should_emit = false;
+            }
 
             if should_emit {
                 current_extended = Some(needed_extended);
@@ -284,31 +474,131 @@ pub fn write_intel_hex(hexfile: &HexFile, options: &IntelHexWriteOptions) -> Vec
                     IntelHexMode::ExtendedSegment => RECORD_EXTENDED_SEGMENT,
                     IntelHexMode::Auto => unreachable!(),
                 };
-                write_record(&mut output, record_type, 0, &needed_extended.to_be_bytes());
+                output.record(record_type, 0, &needed_extended.to_be_bytes())?;
             }
 
             let offset_addr = (addr & 0xFFFF) as u16;
 
             let remaining_in_bank = 0x10000u32.saturating_sub(offset_addr as u32) as usize;
-            let remaining_data = segment.len() - data_offset;
-            let offset_from_start = addr.saturating_sub(seg_start);
+            let remaining_data = (seg_len - data_offset).min(usize::MAX as u64) as usize;
+            let offset_from_start = addr - line_origin(seg_start);
             let line_offset = (offset_from_start % bytes_per_line as u32) as usize;
             let line_remaining = bytes_per_line - line_offset;
             let chunk_len = line_remaining.min(remaining_in_bank).min(remaining_data);
 
-            let chunk = &segment.data[data_offset..data_offset + chunk_len];
-            write_record(&mut output, RECORD_DATA, offset_addr, chunk);
+            output.record(RECORD_DATA, offset_addr, reader.take(chunk_len))?;
 
-            data_offset += chunk_len;
+            data_offset += chunk_len as u64;
             addr = addr.wrapping_add(chunk_len as u32);
         }
     }
 
-    write_record(&mut output, RECORD_EOF, 0, &[]);
-    output
+    if let Some(entry_point) = options.entry_point.or(hexfile.metadata().entry_point) {
+        output.record(RECORD_START_LINEAR, 0, &entry_point.to_be_bytes())?;
+    }
+    output.record(RECORD_EOF, 0, &[])?;
+    Ok(())
+}
+
+/// Encodes each record into a reused line buffer before handing it to the writer.
+struct RecordSink<'a, W> {
+    writer: &'a mut W,
+    options: &'a IntelHexWriteOptions,
+    line: Vec<u8>,
+}
+
+impl<'a, W: Write> RecordSink<'a, W> {
+    fn new(writer: &'a mut W, options: &'a IntelHexWriteOptions) -> Self {
+        Self {
+            writer,
+            options,
+            line: Vec::with_capacity(2 * 255 + 13),
+        }
+    }
+
+    fn record(&mut self, record_type: u8, address: u16, data: &[u8]) -> std::io::Result<()> {
+        self.line.clear();
+        write_record(&mut self.line, self.options, record_type, address, data);
+        self.writer.write_all(&self.line)
+    }
+}
+
+/// Write Intel-HEX with 16-bit addressing (address / 2), the inverse of
+/// [`parse_intel_hex_16bit`]. CLI: /XI2.
+///
+/// Every record address is the word address of its first byte, so records
+/// may only start on even byte addresses.
+pub fn write_intel_hex_16bit(
+    hexfile: &HexFile,
+    options: &IntelHexWriteOptions,
+) -> Result<Vec<u8>, OpsError> {
+    let segments = &*normalized_sorted_segments(hexfile);
+    let bytes_per_line = intel_hex_bytes_per_line(options);
+    let mut output = Vec::new();
+    let mut current_extended: Option<u16> = None;
+
+    for segment in segments {
+        let mut addr = segment.start_address;
+        for chunk in segment.data.chunks(bytes_per_line) {
+            if addr % 2 != 0 {
+                return Err(OpsError::AddressNotDivisible {
+                    address: addr,
+                    divisor: 2,
+                });
+            }
+            let word_addr = addr / 2;
+            let (record_type, extended) = match options.mode {
+                IntelHexMode::ExtendedSegment => {
+                    if word_addr > 0xFFFFF {
+                        return Err(OpsError::AddressOverflow(format!(
+                            "word address {word_addr:#X} exceeds extended segment range"
+                        )));
+                    }
+                    (RECORD_EXTENDED_SEGMENT, ((word_addr >> 4) & 0xF000) as u16)
+                }
+                _ => (RECORD_EXTENDED_LINEAR, (word_addr >> 16) as u16),
+            };
+            let implicit_zero = (options.mode == IntelHexMode::Auto
+                || options.omit_initial_extended)
+                && current_extended.is_none()
+                && extended == 0;
+            if current_extended != Some(extended) && !implicit_zero {
+                write_record(
+                    &mut output,
+                    options,
+                    record_type,
+                    0,
+                    &extended.to_be_bytes(),
+                );
+            }
+            current_extended = Some(extended);
+
+            write_record(&mut output, options, RECORD_DATA, word_addr as u16, chunk);
+            addr = addr.wrapping_add(chunk.len() as u32);
+        }
+    }
+
+    if let Some(entry_point) = options.entry_point.or(hexfile.metadata().entry_point) {
+        write_record(
+            &mut output,
+            options,
+            RECORD_START_LINEAR,
+            0,
+            &entry_point.to_be_bytes(),
+        );
+    }
+    write_record(&mut output, options, RECORD_EOF, 0, &[]);
+    Ok(output)
 }
 
-fn write_record(output: &mut Vec<u8>, record_type: u8, address: u16, data: &[u8]) {
+fn write_record(
+    output: &mut Vec<u8>,
+    options: &IntelHexWriteOptions,
+    record_type: u8,
+    address: u16,
+    data: &[u8],
+) {
+    let upper = options.uppercase;
     let byte_count = data.len() as u8;
     let addr_bytes = address.to_be_bytes();
 
@@ -323,47 +613,68 @@ fn write_record(output: &mut Vec<u8>, record_type: u8, address: u16, data: &[u8]
     checksum = (!checksum).wrapping_add(1);
 
     output.push(b':');
-    push_hex_byte(output, byte_count);
-    push_hex_byte(output, addr_bytes[0]);
-    push_hex_byte(output, addr_bytes[1]);
-    push_hex_byte(output, record_type);
+    push_hex_byte(output, byte_count, upper);
+    push_hex_byte(output, addr_bytes[0], upper);
+    push_hex_byte(output, addr_bytes[1], upper);
+    push_hex_byte(output, record_type, upper);
     for &b in data {
-        push_hex_byte(output, b);
+        push_hex_byte(output, b, upper);
     }
-    push_hex_byte(output, checksum);
-    push_crlf(output);
+    push_hex_byte(output, checksum, upper);
+    options.line_ending.push(output);
 }
 
-fn parse_hex_bytes(hex_str: &str, line_num: usize) -> Result<Vec<u8>, ParseError> {
-    let bytes = hex_str.as_bytes();
+/// Decode hex digit pairs into `out`, replacing its contents.
+fn parse_hex_bytes(bytes: &[u8], out: &mut Vec<u8>, line_num: usize) -> Result<(), ParseError> {
     if !bytes.len().is_multiple_of(2) {
         return Err(ParseError::InvalidRecord {
             line: line_num,
             message: "odd number of hex digits".to_string(),
+            record: None,
         });
     }
 
-    let mut out = Vec::with_capacity(bytes.len() / 2);
+    out.clear();
+    out.reserve(bytes.len() / 2);
     for chunk in bytes.chunks_exact(2) {
-        let high = hex_digit(chunk[0], line_num)?;
-        let low = hex_digit(chunk[1], line_num)?;
+        let high = HEX_DIGITS[chunk[0] as usize];
+        let low = HEX_DIGITS[chunk[1] as usize];
+        if (high | low) & 0xF0 != 0 {
+            let bad = if high == INVALID_DIGIT {
+                chunk[0]
+            } else {
+                chunk[1]
+            };
+            return Err(ParseError::InvalidHexDigit {
+                line: line_num,
+                char: bad as char,
+                record: None,
+            });
+        }
         out.push((high << 4) | low);
     }
 
-    Ok(out)
+    Ok(())
 }
 
-fn hex_digit(b: u8, line_num: usize) -> Result<u8, ParseError> {
-    match b {
-        b'0'..=b'9' => Ok(b - b'0'),
-        b'A'..=b'F' => Ok(b - b'A' + 10),
-        b'a'..=b'f' => Ok(b - b'a' + 10),
-        _ => Err(ParseError::InvalidHexDigit {
-            line: line_num,
-            char: b as char,
-        }),
+const INVALID_DIGIT: u8 = 0xFF;
+
+/// Value of each ASCII hex digit (either case); `INVALID_DIGIT` otherwise.
+const HEX_DIGITS: [u8; 256] = {
+    let mut table = [INVALID_DIGIT; 256];
+    let mut i = 0;
+    while i < 10 {
+        table[b'0' as usize + i] = i as u8;
+        i += 1;
     }
-}
+    let mut i = 0;
+    while i < 6 {
+        table[b'A' as usize + i] = 10 + i as u8;
+        table[b'a' as usize + i] = 10 + i as u8;
+        i += 1;
+    }
+    table
+};
 
 fn validate_checksum(bytes: &[u8], line_num: usize) -> Result<(), ParseError> {
     let sum: u8 = bytes.iter().fold(0u8, |acc, &b| acc.wrapping_add(b));
@@ -377,6 +688,7 @@ fn validate_checksum(bytes: &[u8], line_num: usize) -> Result<(), ParseError> {
             line: line_num,
             expected,
             actual,
+            record: None,
         });
     }
     Ok(())
@@ -397,6 +709,42 @@ mod tests {
         assert_eq!(hf.segments()[0].len(), 32);
     }
 
+    #[test]
+    fn test_parse_lowercase_digits() {
+        let input = b":10010000214601360121470136007efe09d2190140\n:00000001ff\n";
+        let hf = parse_intel_hex(input).unwrap();
+        assert_eq!(hf.segments()[0].data[10..14], [0x7E, 0xFE, 0x09, 0xD2]);
+        assert!(matches!(
+            parse_intel_hex(b":0100000001fg\n:00000001FF\n"),
+            Err(ParseError::InvalidHexDigit {
+                line: 1,
+                char: 'g',
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_error_shows_record_text() {
+        let err = parse_intel_hex(b":0100000055AA\n:0100010055AB\r\n:00000001FF\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "checksum mismatch at line 2: expected A9, got AB in record \":0100010055AB\""
+        );
+
+        let long = format!(":10000000{}GG\n", "00".repeat(40));
+        let err = parse_intel_hex(long.as_bytes()).unwrap_err();
+        let ParseError::InvalidHexDigit {
+            record: Some(record),
+            ..
+        } = &err
+        else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert_eq!(record, &format!("{}...", &long[..60]));
+        assert!(err.to_string().ends_with(&format!("\"{record}\"")), "{err}");
+    }
+
     #[test]
     fn test_parse_extended_linear() {
         let input = b":020000040800F2\n\
@@ -417,6 +765,55 @@ mod tests {
         assert_eq!(hf.segments()[0].start_address, 0x00010000);
     }
 
+    #[test]
+    fn test_start_address_records() {
+        let input = b":0400000508000400EB\n:00000001FF\n";
+        assert_eq!(
+            parse_intel_hex(input).unwrap().metadata().entry_point,
+            Some(0x0800_0400)
+        );
+
+        let input = b":0400000312340010A3\n:00000001FF\n";
+        assert_eq!(
+            parse_intel_hex(input).unwrap().metadata().entry_point,
+            Some(0x12350)
+        );
+
+        let hf = HexFile::with_segments(vec![Segment::new(0x0, vec![0xAA])]);
+        let options = IntelHexWriteOptions {
+            entry_point: Some(0x0800_0400),
+            ..Default::default()
+        };
+        let out = String::from_utf8(write_intel_hex(&hf, &options).unwrap()).unwrap();
+        assert!(
+            out.ends_with(":0400000508000400EB\r\n:00000001FF\r\n"),
+            "{out}"
+        );
+    }
+
+    #[test]
+    fn test_write_entry_point_option_overrides_metadata() {
+        let mut hf = HexFile::with_segments(vec![Segment::new(0x0, vec![0xAA])]);
+        hf.metadata_mut().entry_point = Some(0x1234);
+        let out = write_intel_hex(&hf, &IntelHexWriteOptions::default()).unwrap();
+        assert_eq!(
+            parse_intel_hex(&out).unwrap().metadata().entry_point,
+            Some(0x1234)
+        );
+
+        let options = IntelHexWriteOptions {
+            entry_point: Some(0x0800_0400),
+            ..Default::default()
+        };
+        let out = write_intel_hex(&hf, &options).unwrap();
+        let parsed = parse_intel_hex(&out).unwrap();
+        assert_eq!(parsed.metadata().entry_point, Some(0x0800_0400));
+        assert_eq!(
+            parsed.metadata().source_format,
+            Some(SourceFormat::IntelHex)
+        );
+    }
+
     #[test]
     fn test_parse_16bit_addresses_scaled() {
         let input = b":02000100AABB98\n:00000001FF\n";
@@ -426,6 +823,56 @@ mod tests {
         assert_eq!(hf.segments()[0].data, vec![0xAA, 0xBB]);
     }
 
+    #[test]
+    fn test_write_16bit_roundtrip() {
+        let hf = HexFile::with_segments(vec![
+            Segment::new(0x0000, (0u8..40).collect()),
+            Segment::new(0x2_0000, vec![0xAA, 0xBB, 0xCC]),
+        ]);
+        let options = IntelHexWriteOptions {
+            bytes_per_line: 16,
+            ..Default::default()
+        };
+        let out = write_intel_hex_16bit(&hf, &options).unwrap();
+        let text = String::from_utf8(out.clone()).unwrap();
+        assert!(text.starts_with(":10000000"), "{text}");
+        assert!(text.contains(":10000800"), "{text}");
+        assert!(
+            text.contains(":020000040001F9\r\n:03000000AABBCC"),
+            "{text}"
+        );
+
+        let parsed = parse_intel_hex_16bit(&out).unwrap();
+        assert_eq!(parsed.normalized_lossy(), hf.normalized_lossy());
+    }
+
+    #[test]
+    fn test_write_16bit_rejects_odd_addresses() {
+        let hf = HexFile::with_segments(vec![Segment::new(0x0001, vec![0xAA, 0xBB])]);
+        let result = write_intel_hex_16bit(&hf, &IntelHexWriteOptions::default());
+        assert!(matches!(
+            result,
+            Err(OpsError::AddressNotDivisible {
+                address: 0x0001,
+                divisor: 2
+            })
+        ));
+
+        let hf = HexFile::with_segments(vec![Segment::new(0x0000, vec![0xAA; 6])]);
+        let options = IntelHexWriteOptions {
+            bytes_per_line: 3,
+            ..Default::default()
+        };
+        let result = write_intel_hex_16bit(&hf, &options);
+        assert!(matches!(
+            result,
+            Err(OpsError::AddressNotDivisible {
+                address: 0x0003,
+                ..
+            })
+        ));
+    }
+
     #[test]
     fn test_parse_16bit_overflow() {
         let input = b":0200000480007A\n:01000000AA55\n:00000001FF\n";
@@ -455,7 +902,7 @@ mod tests {
                       :10001000101112131415161718191A1B1C1D1E1F68\n\
                       :00000001FF\n";
         let hf = parse_intel_hex(input).unwrap();
-        let output = write_intel_hex(&hf, &IntelHexWriteOptions::default());
+        let output = write_intel_hex(&hf, &IntelHexWriteOptions::default()).unwrap();
         let hf2 = parse_intel_hex(&output).unwrap();
         assert_eq!(hf, hf2);
     }
@@ -463,19 +910,76 @@ mod tests {
     #[test]
     fn test_write_simple() {
         let hf = HexFile::with_segments(vec![Segment::new(0x0100, vec![0x00, 0x01, 0x02, 0x03])]);
-        let output = write_intel_hex(&hf, &IntelHexWriteOptions::default());
+        let output = write_intel_hex(&hf, &IntelHexWriteOptions::default()).unwrap();
         let text = String::from_utf8(output).unwrap();
         assert!(text.contains(":0401000000010203F5"));
         assert!(text.contains(":00000001FF"));
     }
 
+    #[test]
+    fn test_write_lowercase_lf() {
+        let hf = HexFile::with_segments(vec![Segment::new(0x00AB, vec![0xCD, 0xEF])]);
+        let options = IntelHexWriteOptions {
+            line_ending: LineEnding::Lf,
+            uppercase: false,
+            ..Default::default()
+        };
+        let output = write_intel_hex(&hf, &options).unwrap();
+        assert_eq!(output, b":0200ab00cdef97\n:00000001ff\n");
+        assert_eq!(parse_intel_hex(&output).unwrap().segments(), hf.segments());
+    }
+
+    #[test]
+    fn test_write_below_64k_has_no_extended_record() {
+        let hf = HexFile::with_segments(vec![
+            Segment::new(0x0000, vec![0x01, 0x02]),
+            Segment::new(0xFFFE, vec![0x03, 0x04]),
+        ]);
+        let output = write_intel_hex(&hf, &IntelHexWriteOptions::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            ":020000000102FB\r\n:02FFFE000304FA\r\n:00000001FF\r\n"
+        );
+    }
+
+    #[test]
+    fn test_write_forced_mode_omit_initial_extended() {
+        let hf = HexFile::with_segments(vec![
+            Segment::new(0x0100, vec![0xAA]),
+            Segment::new(0x10000, vec![0xBB]),
+        ]);
+        let forced = IntelHexWriteOptions {
+            mode: IntelHexMode::ExtendedLinear,
+            ..Default::default()
+        };
+        let text = String::from_utf8(write_intel_hex(&hf, &forced).unwrap()).unwrap();
+        assert!(text.starts_with(":020000040000FA\r\n"));
+
+        let options = IntelHexWriteOptions {
+            omit_initial_extended: true,
+            ..forced
+        };
+        let text = String::from_utf8(write_intel_hex(&hf, &options).unwrap()).unwrap();
+        assert_eq!(
+            text,
+            ":01010000AA54\r\n:020000040001F9\r\n:01000000BB44\r\n:00000001FF\r\n"
+        );
+        assert_eq!(
+            parse_intel_hex(text.as_bytes()).unwrap().segments(),
+            hf.segments()
+        );
+
+        let text = String::from_utf8(write_intel_hex_16bit(&hf, &options).unwrap()).unwrap();
+        assert!(text.starts_with(":01008000AA"), "{text}");
+    }
+
     #[test]
     fn test_write_auto_mixed_modes() {
         let hf = HexFile::with_segments(vec![
             Segment::new(0x12000, vec![0xAA]),
             Segment::new(0x120000, vec![0xBB]),
         ]);
-        let output = write_intel_hex(&hf, &IntelHexWriteOptions::default());
+        let output = write_intel_hex(&hf, &IntelHexWriteOptions::default()).unwrap();
         let text = String::from_utf8(output).unwrap();
         assert!(!text.contains(":02000002")); // extended segment suppressed when > 0xFFFFF
         assert!(text.contains(":02000004")); // extended linear only
@@ -485,7 +989,7 @@ mod tests {
     fn test_write_extended_segment_first_line_respects_bytes_per_line() {
         let data: Vec<u8> = (0u8..64u8).collect();
         let hf = HexFile::with_segments(vec![Segment::new(0x10000, data)]);
-        let output = write_intel_hex(&hf, &IntelHexWriteOptions::default());
+        let output = write_intel_hex(&hf, &IntelHexWriteOptions::default()).unwrap();
         let text = String::from_utf8(output).unwrap();
         let lines: Vec<&str> = text.lines().collect();
         let ext_idx = lines
@@ -502,7 +1006,7 @@ mod tests {
     fn test_write_extended_segment_boundary_alignment() {
         let data: Vec<u8> = (0u8..0x30u8).collect();
         let hf = HexFile::with_segments(vec![Segment::new(0xFFF0, data)]);
-        let output = write_intel_hex(&hf, &IntelHexWriteOptions::default());
+        let output = write_intel_hex(&hf, &IntelHexWriteOptions::default()).unwrap();
         let text = String::from_utf8(output).unwrap();
         let lines: Vec<&str> = text.lines().collect();
         let ext_idx = lines
@@ -512,4 +1016,278 @@ mod tests {
         let first_data = lines[ext_idx + 1];
         assert!(first_data.starts_with(":10"));
     }
+
+    /// (address, length) of each data record, with the extended base applied.
+    fn data_records(text: &str) -> Vec<(u32, usize)> {
+        let mut base = 0u32;
+        let mut records = Vec::new();
+        for line in text.lines() {
+            let len = usize::from_str_radix(&line[1..3], 16).unwrap();
+            let offset = u32::from_str_radix(&line[3..7], 16).unwrap();
+            let value = || u32::from_str_radix(&line[9..13], 16).unwrap();
+            match &line[7..9] {
+                "00" => records.push((base + offset, len)),
+                "02" => base = value() << 4,
+                "04" => base = value() << 16,
+                _ => {}
+            }
+        }
+        records
+    }
+
+    #[test]
+    fn test_write_align_records() {
+        let hf = HexFile::with_segments(vec![Segment::new(0x1007, vec![0xAB; 0x29])]);
+        let options = IntelHexWriteOptions {
+            bytes_per_line: 16,
+            align_records: true,
+            ..Default::default()
+        };
+        let output = write_intel_hex(&hf, &options).unwrap();
+        let text = String::from_utf8(output.clone()).unwrap();
+        assert_eq!(
+            data_records(&text),
+            vec![(0x1007, 9), (0x1010, 16), (0x1020, 16)]
+        );
+        assert_eq!(parse_intel_hex(&output).unwrap().segments(), hf.segments());
+
+        let unaligned = IntelHexWriteOptions {
+            align_records: false,
+            ..options.clone()
+        };
+        let text = String::from_utf8(write_intel_hex(&hf, &unaligned).unwrap()).unwrap();
+        assert_eq!(
+            data_records(&text),
+            vec![(0x1007, 16), (0x1017, 16), (0x1027, 9)]
+        );
+
+        // A bank boundary still splits a record, and alignment carries on past
+        // it: 0x20000 is 8 bytes past a multiple of 0x18.
+        let hf = HexFile::with_segments(vec![Segment::new(0x1_FFF4, vec![0xCD; 0x20])]);
+        for mode in [IntelHexMode::ExtendedLinear, IntelHexMode::ExtendedSegment] {
+            let options = IntelHexWriteOptions {
+                bytes_per_line: 0x18,
+                mode,
+                align_records: true,
+                ..Default::default()
+            };
+            let output = write_intel_hex(&hf, &options).unwrap();
+            let text = String::from_utf8(output.clone()).unwrap();
+            assert_eq!(
+                data_records(&text),
+                vec![(0x1_FFF4, 4), (0x1_FFF8, 8), (0x2_0000, 16), (0x2_0010, 4)],
+                "{mode:?}"
+            );
+            assert_eq!(
+                parse_intel_hex(&output)
+                    .unwrap()
+                    .normalized_lossy()
+                    .segments(),
+                hf.segments()
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_reader_multi_megabyte() {
+        // 4 MiB spread over 64 linear pages, read through a tiny buffer so
+        // lines straddle refills.
+        let data: Vec<u8> = (0..4 * 1024 * 1024u32)
+            .map(|i| (i * 7 + (i >> 11)) as u8)
+            .collect();
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x0801_0000, data.clone())]);
+        let text = write_intel_hex(&hexfile, &IntelHexWriteOptions::default()).unwrap();
+        assert!(text.len() > 8 * 1024 * 1024);
+
+        let reader = std::io::BufReader::with_capacity(7, text.as_slice());
+        let parsed = parse_intel_hex_reader(reader).unwrap().normalized_lossy();
+        assert_eq!(parsed.segments().len(), 1);
+        assert_eq!(parsed.segments()[0].start_address, 0x0801_0000);
+        assert!(parsed.segments()[0].data == data);
+
+        // Corrupt one data digit deep in the file; the reported line must match.
+        let target_line = 100_000;
+        let offset = text
+            .split(|&b| b == b'\n')
+            .take(target_line - 1)
+            .map(|line| line.len() + 1)
+            .sum::<usize>();
+        let mut corrupt = text.clone();
+        corrupt[offset + 9] = b'G';
+        let reader = std::io::BufReader::with_capacity(64, corrupt.as_slice());
+        match parse_intel_hex_reader(reader) {
+            Err(ParseError::InvalidHexDigit {
+                line, char: 'G', ..
+            }) => assert_eq!(line, target_line),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_reader_propagates_io_error() {
+        struct Failing;
+        impl std::io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("disk gone"))
+            }
+        }
+        let err = parse_intel_hex_reader(std::io::BufReader::new(Failing)).unwrap_err();
+        assert!(matches!(err, ParseError::Io(_)), "{err}");
+    }
+
+    /// Accepts at most 5 bytes per call so records are split across writes.
+    struct Trickle(Vec<u8>);
+
+    impl Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(5);
+            self.0.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_extended_segment_limit() {
+        let segment = IntelHexWriteOptions {
+            mode: IntelHexMode::ExtendedSegment,
+            ..Default::default()
+        };
+        let top = HexFile::with_segments(vec![Segment::new(0x000F_FFFE, vec![0x11, 0x22])]);
+        let text = String::from_utf8(write_intel_hex(&top, &segment).unwrap()).unwrap();
+        assert_eq!(
+            text,
+            ":02000002F0000C\r\n:02FFFE001122CE\r\n:00000001FF\r\n"
+        );
+        assert_eq!(
+            parse_intel_hex(text.as_bytes()).unwrap().segments(),
+            top.segments()
+        );
+
+        let over = HexFile::with_segments(vec![Segment::new(0x000F_FFFE, vec![0x11, 0x22, 0x33])]);
+        let err = write_intel_hex(&over, &segment).unwrap_err();
+        assert!(
+            matches!(&err, ParseError::AddressOverflow(msg) if msg.contains("0x100000")),
+            "{err}"
+        );
+        let mut sink = Vec::new();
+        assert!(write_intel_hex_to(&over, &segment, &mut sink).is_err());
+        assert!(write_intel_hex_filled_to(&over, &segment, 0xFF, &mut sink).is_err());
+
+        // Auto stays in segment mode up to 0xFFFFF and goes linear above it.
+        let auto = IntelHexWriteOptions::default();
+        let text = String::from_utf8(write_intel_hex(&top, &auto).unwrap()).unwrap();
+        assert!(text.starts_with(":02000002F0000C\r\n"), "{text}");
+        let text = String::from_utf8(write_intel_hex(&over, &auto).unwrap()).unwrap();
+        assert!(!text.contains(":02000002"), "{text}");
+        assert_eq!(
+            parse_intel_hex(text.as_bytes())
+                .unwrap()
+                .normalized_lossy()
+                .segments(),
+            over.segments()
+        );
+    }
+
+    #[test]
+    fn test_write_intel_hex_to_matches_vec_output() {
+        let mut hexfile = HexFile::with_segments(vec![
+            Segment::new(0x000E_FFF0, (0..=0xFFu8).collect()),
+            Segment::new(0x0001_0000, vec![0xAA; 70]),
+        ]);
+        hexfile.metadata_mut().entry_point = Some(0x0001_0000);
+        for mode in [
+            IntelHexMode::Auto,
+            IntelHexMode::ExtendedLinear,
+            IntelHexMode::ExtendedSegment,
+        ] {
+            let options = IntelHexWriteOptions {
+                bytes_per_line: 0x20,
+                mode,
+                ..Default::default()
+            };
+            let mut sink = Trickle(Vec::new());
+            write_intel_hex_to(&hexfile, &options, &mut sink).unwrap();
+            assert_eq!(
+                sink.0,
+                write_intel_hex(&hexfile, &options).unwrap(),
+                "{mode:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_write_intel_hex_filled_matches_fill_gaps() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x000E_FFF0, (0..=0xFFu8).collect()),
+            Segment::new(0x0001_0003, vec![0xAA; 70]),
+            Segment::new(0x0001_0049, vec![0xBB; 5]),
+        ]);
+        let mut filled = hexfile.clone();
+        filled.fill_gaps(0x5A).unwrap();
+        let mut chunked = hexfile.clone();
+        chunked.fill_gaps_chunked(0x5A, 0x333).unwrap();
+        for mode in [IntelHexMode::Auto, IntelHexMode::ExtendedSegment] {
+            let options = IntelHexWriteOptions {
+                bytes_per_line: 0x13,
+                mode,
+                ..Default::default()
+            };
+            let expected = write_intel_hex(&filled, &options).unwrap();
+            let mut out = Vec::new();
+            write_intel_hex_filled_to(&hexfile, &options, 0x5A, &mut out).unwrap();
+            assert_eq!(out, expected, "{mode:?}");
+            assert_eq!(
+                write_intel_hex(&chunked, &options).unwrap(),
+                expected,
+                "{mode:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_lenient_collects_warnings() {
+        let input = b"; exported by vendor tool\n\
+                      :0100000055AA\n\
+                      \n\
+                      garbage\n\
+                      :00000001FF\n\
+                      :0100010066\n\
+                      trailer\n";
+        assert!(parse_intel_hex(input).is_err());
+
+        let options = ParseOptions { lenient: true };
+        let (hexfile, warnings) = parse_intel_hex_with(input, &options).unwrap();
+        assert_eq!(hexfile.segments(), &[Segment::new(0, vec![0x55])]);
+        assert_eq!(
+            warnings,
+            vec![
+                ParseWarning::SkippedLine { line: 1 },
+                ParseWarning::SkippedLine { line: 4 },
+                ParseWarning::DataAfterEof { line: 6 },
+            ]
+        );
+        assert_eq!(
+            warnings[2].to_string(),
+            "line 6: ignored data after EOF record"
+        );
+    }
+
+    #[test]
+    fn test_parse_lenient_missing_eof_and_bad_record() {
+        let options = ParseOptions { lenient: true };
+        let (hexfile, warnings) = parse_intel_hex_with(b":0100000055AA\n", &options).unwrap();
+        assert_eq!(hexfile.segments().len(), 1);
+        assert_eq!(warnings, vec![ParseWarning::MissingEof]);
+
+        // Records themselves are still validated.
+        let err = parse_intel_hex_with(b":0100000055AB\n:00000001FF\n", &options).unwrap_err();
+        assert!(
+            matches!(err, ParseError::ChecksumMismatch { line: 1, .. }),
+            "{err}"
+        );
+    }
 }
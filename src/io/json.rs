@@ -0,0 +1,313 @@
+//! JSON segment map (CLI: /XJ), available with the `serde` feature.
+//!
+//! Document layout:
+//!
+//! ```json
+//! {
+//!   "encoding": "hex",
+//!   "min_address": 4096,
+//!   "max_address": 4098,
+//!   "total_bytes": 3,
+//!   "segments": [
+//!     { "start_address": 4096, "end_address": 4098, "length": 3, "data": "010203" }
+//!   ]
+//! }
+//! ```
+//!
+//! Segments are normalized and sorted by address. `data` is uppercase hex or
+//! padded standard base64, as named by `encoding`. The summary fields and the
+//! per-segment `end_address`/`length` are ignored when reading a document back.
+//!
+//! The derived `Serialize`/`Deserialize` impls on `Segment` and `HexFile` always
+//! encode data as hex (see [`hex_bytes`]); use `write_json` for base64.
+
+use serde::{Deserialize, Serialize};
+
+use crate::io::{ParseError, normalized_sorted_segments};
+use crate::{HexFile, Segment};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonDataEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+#[derive(Debug, Clone)]
+pub struct JsonWriteOptions {
+    pub encoding: JsonDataEncoding,
+    /// Indent the document; otherwise write it on a single line.
+    pub pretty: bool,
+}
+
+impl Default for JsonWriteOptions {
+    fn default() -> Self {
+        Self {
+            encoding: JsonDataEncoding::Hex,
+            pretty: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonDocument {
+    #[serde(default)]
+    encoding: JsonDataEncoding,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_address: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_address: Option<u32>,
+    #[serde(default)]
+    total_bytes: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entry_point: Option<u32>,
+    segments: Vec<JsonSegment>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonSegment {
+    start_address: u32,
+    #[serde(default)]
+    end_address: u32,
+    #[serde(default)]
+    length: u32,
+    data: String,
+}
+
+/// Write the segment map as a JSON document. CLI: /XJ.
+pub fn write_json(hexfile: &HexFile, options: &JsonWriteOptions) -> Result<Vec<u8>, ParseError> {
+    let segments = &*normalized_sorted_segments(hexfile);
+    let document = JsonDocument {
+        encoding: options.encoding,
+        min_address: segments.first().map(|s| s.start_address),
+        max_address: segments.last().map(|s| s.end_address()),
+        total_bytes: segments.iter().map(|s| s.len() as u64).sum(),
+        entry_point: hexfile.metadata().entry_point,
+        segments: segments
+            .iter()
+            .map(|segment| JsonSegment {
+                start_address: segment.start_address,
+                end_address: segment.end_address(),
+                length: segment.len() as u32,
+                data: match options.encoding {
+                    JsonDataEncoding::Hex => encode_hex(&segment.data),
+                    JsonDataEncoding::Base64 => encode_base64(&segment.data),
+                },
+            })
+            .collect(),
+    };
+
+    let mut out = if options.pretty {
+        serde_json::to_vec_pretty(&document)
+    } else {
+        serde_json::to_vec(&document)
+    }
+    .map_err(|e| ParseError::InvalidOutput(format!("JSON: {e}")))?;
+    out.push(b'\n');
+    Ok(out)
+}
+
+/// Parse a document written by `write_json`.
+pub fn parse_json(data: &[u8]) -> Result<HexFile, ParseError> {
+    let document: JsonDocument =
+        serde_json::from_slice(data).map_err(|e| ParseError::InvalidJson(e.to_string()))?;
+
+    let mut segments = Vec::with_capacity(document.segments.len());
+    for segment in document.segments {
+        let bytes = match document.encoding {
+            JsonDataEncoding::Hex => decode_hex(&segment.data),
+            JsonDataEncoding::Base64 => decode_base64(&segment.data),
+        }
+        .ok_or_else(|| {
+            ParseError::InvalidJson(format!(
+                "segment at {:#X}: data is not valid {}",
+                segment.start_address,
+                match document.encoding {
+                    JsonDataEncoding::Hex => "hex",
+                    JsonDataEncoding::Base64 => "base64",
+                }
+            ))
+        })?;
+        if !bytes.is_empty()
+            && segment
+                .start_address
+                .checked_add(bytes.len() as u32 - 1)
+                .is_none()
+        {
+            return Err(ParseError::AddressOverflow(format!(
+                "{:#X} + {} exceeds u32",
+                segment.start_address,
+                bytes.len()
+            )));
+        }
+        segments.push(Segment::new(segment.start_address, bytes));
+    }
+
+    let mut hexfile = HexFile::with_segments(segments);
+    hexfile.metadata_mut().entry_point = document.entry_point;
+    Ok(hexfile)
+}
+
+/// `#[serde(with = ...)]` helper storing bytes as an uppercase hex string.
+pub mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::encode_hex(data))
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: From<Vec<u8>>,
+    {
+        let text = String::deserialize(deserializer)?;
+        super::decode_hex(&text)
+            .map(T::from)
+            .ok_or_else(|| D::Error::custom("invalid hex string"))
+    }
+}
+
+fn encode_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{b:02X}")).collect()
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.as_bytes();
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    text.chunks(2)
+        .map(|pair| {
+            let hi = (pair[0] as char).to_digit(16)?;
+            let lo = (pair[1] as char).to_digit(16)?;
+            Some((hi * 16 + lo) as u8)
+        })
+        .collect()
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn encode_base64(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i)) as usize & 0x3F] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let text = text.as_bytes();
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    for (index, chunk) in text.chunks(4).enumerate() {
+        let last = index == text.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return None;
+        }
+        let mut n = 0u32;
+        for &c in &chunk[..4 - padding] {
+            let value = BASE64.iter().position(|&b| b == c)? as u32;
+            n = n << 6 | value;
+        }
+        n <<= 6 * padding as u32;
+        out.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> HexFile {
+        let mut hexfile = HexFile::with_segments(vec![
+            Segment::new(0x2000, vec![0xFF]),
+            Segment::new(0x1000, vec![0x01, 0x02, 0x03, 0x04]),
+        ]);
+        hexfile.metadata_mut().entry_point = Some(0x1000);
+        hexfile
+    }
+
+    #[test]
+    fn test_json_round_trip_hex_and_base64() {
+        for encoding in [JsonDataEncoding::Hex, JsonDataEncoding::Base64] {
+            let options = JsonWriteOptions {
+                encoding,
+                pretty: false,
+            };
+            let out = write_json(&sample(), &options).unwrap();
+            let parsed = parse_json(&out).unwrap();
+            assert_eq!(
+                parsed.segments(),
+                &[
+                    Segment::new(0x1000, vec![0x01, 0x02, 0x03, 0x04]),
+                    Segment::new(0x2000, vec![0xFF]),
+                ]
+            );
+            assert_eq!(parsed.metadata().entry_point, Some(0x1000));
+        }
+    }
+
+    #[test]
+    fn test_write_json_summary_fields() {
+        let options = JsonWriteOptions {
+            encoding: JsonDataEncoding::Base64,
+            pretty: false,
+        };
+        let out = String::from_utf8(write_json(&sample(), &options).unwrap()).unwrap();
+        assert_eq!(
+            out,
+            "{\"encoding\":\"base64\",\"min_address\":4096,\"max_address\":8192,\
+             \"total_bytes\":5,\"entry_point\":4096,\"segments\":[\
+             {\"start_address\":4096,\"end_address\":4099,\"length\":4,\"data\":\"AQIDBA==\"},\
+             {\"start_address\":8192,\"end_address\":8192,\"length\":1,\"data\":\"/w==\"}]}\n"
+        );
+    }
+
+    #[test]
+    fn test_base64_codec() {
+        for data in [&b""[..], b"f", b"fo", b"foo", b"foob", b"fooba", b"foobar"] {
+            assert_eq!(decode_base64(&encode_base64(data)).unwrap(), data);
+        }
+        assert_eq!(encode_base64(b"foobar"), "Zm9vYmFy");
+        assert!(decode_base64("Zg=").is_none());
+        assert!(decode_base64("Zg==Zg==").is_none());
+    }
+
+    #[test]
+    fn test_serde_impls_round_trip() {
+        let hexfile = sample();
+        let text = serde_json::to_string(&hexfile).unwrap();
+        assert!(text.contains("\"data\":\"01020304\""), "{text}");
+        let back: HexFile = serde_json::from_str(&text).unwrap();
+        assert_eq!(back, hexfile);
+
+        let range = crate::Range::from_start_end(0x10, 0x1F).unwrap();
+        let text = serde_json::to_string(&range).unwrap();
+        assert_eq!(text, "{\"start\":16,\"end\":31}");
+        assert_eq!(serde_json::from_str::<crate::Range>(&text).unwrap(), range);
+        assert!(serde_json::from_str::<crate::Range>("{\"start\":2,\"end\":1}").is_err());
+    }
+
+    #[test]
+    fn test_parse_json_rejects_bad_data() {
+        let err = parse_json(br#"{"segments":[{"start_address":0,"data":"0G"}]}"#).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidJson(_)), "{err}");
+        assert!(parse_json(b"not json").is_err());
+    }
+}
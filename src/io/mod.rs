@@ -1,31 +1,210 @@
 mod binary;
 mod c_code;
+mod elf;
 mod error;
+mod fiat;
+mod flash_kernel;
+mod gac;
+mod gm;
 mod hex_ascii;
+mod hex_dump;
 mod intel_hex;
+#[cfg(feature = "serde")]
+mod json;
+mod rust_code;
+mod separate_binary;
 mod srec;
+mod vag;
+mod vbf;
+
+use std::borrow::Cow;
 
 use crate::Segment;
 
-pub use binary::{BinaryWriteOptions, parse_binary, write_binary};
+pub use binary::{
+    BinaryWriteOptions, parse_binary, parse_binary_mmap, write_binary, write_binary_to,
+};
 pub use c_code::{CCodeOutput, CCodeWordType, CCodeWriteOptions, write_c_code};
-pub use error::ParseError;
-pub use hex_ascii::{HexAsciiWriteOptions, parse_hex_ascii, write_hex_ascii};
+pub use elf::{ElfParseOptions, parse_elf, parse_elf_with_options};
+pub use error::{ParseError, ParseWarning};
+pub use fiat::{FiatBinWriteOptions, write_fiat_bin};
+pub use flash_kernel::{FlashKernelWriteOptions, write_flash_kernel};
+pub use gac::{GacWriteOptions, write_gac};
+pub use gm::{GmHeaderKind, GmHeaderOptions, add_gm_header, write_gm_header};
+pub use hex_ascii::{
+    AddressPrefixStyle, HexAsciiParseOptions, HexAsciiWriteOptions, parse_hex_ascii,
+    parse_hex_ascii_with_options, write_hex_ascii,
+};
+pub use hex_dump::{HexDumpOptions, write_hex_dump};
 pub use intel_hex::{
-    IntelHexMode, IntelHexWriteOptions, parse_intel_hex, parse_intel_hex_16bit, write_intel_hex,
+    IntelHexMode, IntelHexWriteOptions, ParseOptions, parse_intel_hex, parse_intel_hex_16bit,
+    parse_intel_hex_reader, parse_intel_hex_with, write_intel_hex, write_intel_hex_16bit,
+    write_intel_hex_filled_to, write_intel_hex_to,
+};
+#[cfg(feature = "serde")]
+pub use json::{JsonDataEncoding, JsonWriteOptions, hex_bytes, parse_json, write_json};
+pub use rust_code::{RustCodeWriteOptions, write_rust_code};
+pub use separate_binary::{
+    FileNameTemplate, FileNameTemplateError, SeparateBinaryOptions, separate_binary_names,
+    write_separate_binary,
 };
-pub use srec::{SRecordType, SRecordWriteOptions, parse_srec, write_srec};
+pub use srec::{
+    SRecordType, SRecordWriteOptions, parse_srec, parse_srec_with, write_srec,
+    write_srec_filled_to, write_srec_to,
+};
+pub use vag::{VagWriteOptions, write_vag};
+pub use vbf::{VbfHeader, VbfWriteOptions, parse_vbf, parse_vbf_with_header, write_vbf};
+
+fn normalized_sorted_segments(hexfile: &crate::HexFile) -> Cow<'_, [Segment]> {
+    hexfile.normalized_segments()
+}
+
+/// Sorted, non-overlapping, non-empty segments. Borrows the file's own
+/// segments when they already are, so the streaming writers do not copy the
+/// image. Adjacent segments are not merged; see [`contiguous_runs`].
+fn sorted_segments(hexfile: &crate::HexFile) -> Cow<'_, [Segment]> {
+    let segments = hexfile.segments();
+    let in_range = |s: &Segment| s.start_address as u64 + s.len() as u64 <= 1 << 32;
+    let normalized = segments.iter().all(|s| !s.is_empty() && in_range(s))
+        && segments
+            .windows(2)
+            .all(|pair| pair[0].end_address() < pair[1].start_address);
+    if normalized {
+        Cow::Borrowed(segments)
+    } else {
+        normalized_sorted_segments(hexfile)
+    }
+}
+
+/// One contiguous stretch of output: adjacent segments and, with a gap fill,
+/// the fill between them. Writers lay out a run exactly as they would one
+/// merged segment.
+struct Run<'a> {
+    start: u32,
+    len: u64,
+    pieces: Vec<Piece<'a>>,
+}
+
+#[derive(Clone, Copy)]
+enum Piece<'a> {
+    Data(&'a [u8]),
+    Fill(u8, u64),
+}
+
+impl Piece<'_> {
+    fn len(&self) -> u64 {
+        match self {
+            Piece::Data(data) => data.len() as u64,
+            Piece::Fill(_, len) => *len,
+        }
+    }
+}
+
+/// Group sorted, non-overlapping segments into runs. With `gap_fill`, the
+/// whole span is one run, as after [`HexFile::fill_gaps`](crate::HexFile::fill_gaps).
+fn contiguous_runs(segments: &[Segment], gap_fill: Option<u8>) -> Vec<Run<'_>> {
+    let mut runs: Vec<Run<'_>> = Vec::new();
+    for segment in segments {
+        let start = segment.start_address as u64;
+        match runs.last_mut() {
+            Some(run) if run.start as u64 + run.len == start => {}
+            Some(run) if let Some(fill) = gap_fill => {
+                let gap = start - (run.start as u64 + run.len);
+                run.pieces.push(Piece::Fill(fill, gap));
+                run.len += gap;
+            }
+            _ => {
+                runs.push(Run {
+                    start: segment.start_address,
+                    len: 0,
+                    pieces: Vec::new(),
+                });
+            }
+        }
+        let run = runs.last_mut().expect("pushed above");
+        run.pieces.push(Piece::Data(&segment.data));
+        run.len += segment.len() as u64;
+    }
+    runs
+}
+
+/// Reads a run as one byte stream.
+struct RunReader<'a> {
+    pieces: &'a [Piece<'a>],
+    offset: u64,
+    buf: Vec<u8>,
+}
+
+impl<'a> RunReader<'a> {
+    fn new(run: &'a Run<'a>) -> Self {
+        Self {
+            pieces: &run.pieces,
+            offset: 0,
+            buf: Vec::new(),
+        }
+    }
+
+    /// The next `len` bytes, borrowed when they lie in one segment. Panics if
+    /// the run is shorter.
+    fn take(&mut self, len: usize) -> &[u8] {
+        if let Piece::Data(data) = self.pieces[0]
+            && data.len() as u64 - self.offset >= len as u64
+        {
+            let start = self.offset as usize;
+            self.advance(len as u64);
+            return &data[start..start + len];
+        }
+        self.buf.clear();
+        while self.buf.len() < len {
+            let piece = self.pieces[0];
+            let count = (piece.len() - self.offset).min((len - self.buf.len()) as u64);
+            match piece {
+                Piece::Data(data) => {
+                    let start = self.offset as usize;
+                    self.buf
+                        .extend_from_slice(&data[start..start + count as usize]);
+                }
+                Piece::Fill(fill, _) => self.buf.resize(self.buf.len() + count as usize, fill),
+            }
+            self.advance(count);
+        }
+        &self.buf
+    }
+
+    fn advance(&mut self, count: u64) {
+        self.offset += count;
+        if self.offset == self.pieces[0].len() {
+            self.pieces = &self.pieces[1..];
+            self.offset = 0;
+        }
+    }
+}
+
+/// Line terminator for text output formats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    #[default]
+    CrLf,
+}
 
-fn normalized_sorted_segments(hexfile: &crate::HexFile) -> Vec<Segment> {
-    let mut segments = hexfile.normalized_lossy().into_segments();
-    segments.sort_by_key(|s| s.start_address);
-    segments
+impl LineEnding {
+    fn push(self, out: &mut Vec<u8>) {
+        if self == LineEnding::CrLf {
+            out.push(b'\r');
+        }
+        out.push(b'\n');
+    }
 }
 
-fn push_hex_byte(out: &mut Vec<u8>, byte: u8) {
-    const HEX: &[u8; 16] = b"0123456789ABCDEF";
-    out.push(HEX[(byte >> 4) as usize]);
-    out.push(HEX[(byte & 0x0F) as usize]);
+fn push_hex_byte(out: &mut Vec<u8>, byte: u8, uppercase: bool) {
+    let hex: &[u8; 16] = if uppercase {
+        b"0123456789ABCDEF"
+    } else {
+        b"0123456789abcdef"
+    };
+    out.push(hex[(byte >> 4) as usize]);
+    out.push(hex[(byte & 0x0F) as usize]);
 }
 
 fn push_crlf(out: &mut Vec<u8>) {
@@ -0,0 +1,171 @@
+//! Rust source export (CLI: /XR).
+//!
+//! Per segment: `pub const <PREFIX>_BLOCK<n>_ADDRESS: u32` and
+//! `pub static <PREFIX>_BLOCK<n>: [u8; N]`. Without `per_segment` a single
+//! `<Prefix>Block { address, data }` struct and a `<PREFIX>_BLOCKS` table are
+//! written instead. Layout follows rustfmt: four-space indent, trailing commas.
+
+use crate::HexFile;
+use crate::io::{ParseError, normalized_sorted_segments};
+
+#[derive(Debug, Clone)]
+pub struct RustCodeWriteOptions {
+    pub prefix: String,
+    /// One static array per segment instead of a struct table.
+    pub per_segment: bool,
+    /// Initializer bytes per line; 0 means 16.
+    pub bytes_per_line: usize,
+}
+
+impl Default for RustCodeWriteOptions {
+    fn default() -> Self {
+        Self {
+            prefix: "flashDrv".to_string(),
+            per_segment: false,
+            bytes_per_line: 16,
+        }
+    }
+}
+
+/// Write Rust source embedding the data. CLI: /XR.
+pub fn write_rust_code(
+    hexfile: &HexFile,
+    options: &RustCodeWriteOptions,
+) -> Result<Vec<u8>, ParseError> {
+    let prefix = options.prefix.trim();
+    if prefix.is_empty() {
+        return Err(ParseError::InvalidOutput(
+            "Prefix must not be empty".to_string(),
+        ));
+    }
+    let upper = upper_snake(prefix);
+    let bytes_per_line = if options.bytes_per_line == 0 {
+        16
+    } else {
+        options.bytes_per_line
+    };
+    let segments = &*normalized_sorted_segments(hexfile);
+
+    let mut out = String::new();
+    if options.per_segment {
+        for (idx, segment) in segments.iter().enumerate() {
+            if idx > 0 {
+                out.push('\n');
+            }
+            out.push_str(&format!(
+                "pub const {upper}_BLOCK{idx}_ADDRESS: u32 = 0x{:08X};\n",
+                segment.start_address
+            ));
+            out.push_str(&format!(
+                "pub static {upper}_BLOCK{idx}: [u8; {}] = [\n",
+                segment.len()
+            ));
+            push_bytes(&mut out, &segment.data, bytes_per_line, "    ");
+            out.push_str("];\n");
+        }
+    } else {
+        let type_name = format!("{}Block", camel(prefix));
+        out.push_str(&format!(
+            "pub struct {type_name} {{\n    pub address: u32,\n    pub data: &'static [u8],\n}}\n\n"
+        ));
+        out.push_str(&format!(
+            "pub static {upper}_BLOCKS: [{type_name}; {}] = [\n",
+            segments.len()
+        ));
+        for segment in segments {
+            out.push_str(&format!("    {type_name} {{\n"));
+            out.push_str(&format!(
+                "        address: 0x{:08X},\n",
+                segment.start_address
+            ));
+            out.push_str("        data: &[\n");
+            push_bytes(&mut out, &segment.data, bytes_per_line, "            ");
+            out.push_str("        ],\n    },\n");
+        }
+        out.push_str("];\n");
+    }
+    Ok(out.into_bytes())
+}
+
+fn push_bytes(out: &mut String, data: &[u8], bytes_per_line: usize, indent: &str) {
+    for chunk in data.chunks(bytes_per_line) {
+        let line: Vec<String> = chunk.iter().map(|b| format!("0x{b:02X},")).collect();
+        out.push_str(indent);
+        out.push_str(&line.join(" "));
+        out.push('\n');
+    }
+}
+
+/// `flashDrv` / `flash-drv` -> `FLASHDRV` / `FLASH_DRV`; a leading digit gets `_`.
+fn upper_snake(prefix: &str) -> String {
+    let mut ident: String = prefix
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+/// `flashDrv` / `flash-drv` -> `FlashDrv`; a leading digit gets `_`.
+fn camel(prefix: &str) -> String {
+    let mut ident = String::new();
+    for part in prefix
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|p| !p.is_empty())
+    {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            ident.push(first.to_ascii_uppercase());
+            ident.push_str(chars.as_str());
+        }
+    }
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Segment;
+
+    #[test]
+    fn test_write_rust_code_per_segment() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01, 0x02, 0x03])]);
+        let options = RustCodeWriteOptions {
+            prefix: "flash-drv".to_string(),
+            per_segment: true,
+            bytes_per_line: 2,
+        };
+        let out = String::from_utf8(write_rust_code(&hexfile, &options).unwrap()).unwrap();
+        assert_eq!(
+            out,
+            "pub const FLASH_DRV_BLOCK0_ADDRESS: u32 = 0x00001000;\n\
+             pub static FLASH_DRV_BLOCK0: [u8; 3] = [\n\
+             \x20   0x01, 0x02,\n\
+             \x20   0x03,\n\
+             ];\n"
+        );
+    }
+
+    #[test]
+    fn test_identifiers_stay_valid() {
+        assert_eq!(upper_snake("3drv.x"), "_3DRV_X");
+        assert_eq!(camel("3drv.x"), "_3drvX");
+        assert_eq!(camel("flashDrv"), "FlashDrv");
+        let options = RustCodeWriteOptions {
+            prefix: " ".to_string(),
+            ..Default::default()
+        };
+        assert!(write_rust_code(&HexFile::new(), &options).is_err());
+    }
+}
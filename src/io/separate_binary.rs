@@ -0,0 +1,389 @@
+//! Separate binary export (CLI: /XSB): one raw binary file per block.
+//!
+//! Without a template a block is named `{stem}_{addr}.{ext}`, or after its
+//! label (see [`crate::HexFile::label_range`]): `{stem}_{label}.{ext}`, with
+//! `_{addr}` added when several blocks share the label.
+//!
+//! A [`FileNameTemplate`] replaces that, e.g.
+//! `{stem}_{index:02}_{addr:08X}.{ext}`. Placeholders:
+//!
+//! - `stem`, `ext`: the output path's file stem and extension
+//! - `label`: the block's label, empty when it has none
+//! - `addr`, `index`, `length`: start address, 0-based position in address
+//!   order, and byte count
+//!
+//! The numeric ones take a `[0][width][x|X|d]` spec; `addr` defaults to
+//! lowercase hex, the others to decimal. `{{` and `}}` are literal braces.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::io::{ParseError, normalized_sorted_segments};
+use crate::{HexFile, Segment};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SeparateBinaryOptions {
+    /// File name for each block; `None` keeps the default naming.
+    pub template: Option<FileNameTemplate>,
+}
+
+/// A parsed `/XSB` file name template; see the module docs for the syntax.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileNameTemplate {
+    source: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Stem,
+    Ext,
+    Label,
+    Number(NumberField, NumberSpec),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NumberField {
+    Addr,
+    Index,
+    Length,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Radix {
+    Decimal,
+    LowerHex,
+    UpperHex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct NumberSpec {
+    zero_pad: bool,
+    width: usize,
+    radix: Radix,
+}
+
+/// A malformed template. `column` is the 1-based character position of the
+/// offending `{`, `}` or separator.
+#[derive(Debug, Error)]
+pub enum FileNameTemplateError {
+    #[error("unknown placeholder {{{name}}} at column {column}")]
+    UnknownPlaceholder { name: String, column: usize },
+
+    #[error("invalid format spec '{spec}' for {{{name}}} at column {column}")]
+    InvalidSpec {
+        name: String,
+        spec: String,
+        column: usize,
+    },
+
+    #[error("unclosed '{{' at column {column}")]
+    Unclosed { column: usize },
+
+    #[error("unmatched '}}' at column {column}")]
+    Unmatched { column: usize },
+
+    #[error("path separator at column {column}: file names may not contain one")]
+    PathSeparator { column: usize },
+}
+
+impl FromStr for FileNameTemplate {
+    type Err = FileNameTemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().zip(1..).peekable();
+        while let Some((c, column)) = chars.next() {
+            match c {
+                '{' if chars.peek().map(|&(c, _)| c) == Some('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek().map(|&(c, _)| c) == Some('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '}' => return Err(FileNameTemplateError::Unmatched { column }),
+                '/' | '\\' => return Err(FileNameTemplateError::PathSeparator { column }),
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some(('}', _)) => break,
+                            Some((c, _)) => placeholder.push(c),
+                            None => return Err(FileNameTemplateError::Unclosed { column }),
+                        }
+                    }
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(parse_placeholder(&placeholder, column)?);
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self {
+            source: s.to_string(),
+            parts,
+        })
+    }
+}
+
+/// One `{...}` body; `column` is where its `{` is, for errors.
+fn parse_placeholder(placeholder: &str, column: usize) -> Result<Part, FileNameTemplateError> {
+    let (name, spec) = match placeholder.split_once(':') {
+        Some((name, spec)) => (name.trim(), Some(spec)),
+        None => (placeholder.trim(), None),
+    };
+    let (field, default_radix) = match name {
+        "addr" => (NumberField::Addr, Radix::LowerHex),
+        "index" => (NumberField::Index, Radix::Decimal),
+        "length" => (NumberField::Length, Radix::Decimal),
+        "stem" | "ext" | "label" => {
+            if let Some(spec) = spec {
+                return Err(FileNameTemplateError::InvalidSpec {
+                    name: name.to_string(),
+                    spec: spec.to_string(),
+                    column,
+                });
+            }
+            return Ok(match name {
+                "stem" => Part::Stem,
+                "ext" => Part::Ext,
+                _ => Part::Label,
+            });
+        }
+        _ => {
+            return Err(FileNameTemplateError::UnknownPlaceholder {
+                name: name.to_string(),
+                column,
+            });
+        }
+    };
+    let spec = match spec {
+        Some(spec) => {
+            parse_spec(spec, default_radix).ok_or_else(|| FileNameTemplateError::InvalidSpec {
+                name: name.to_string(),
+                spec: spec.to_string(),
+                column,
+            })?
+        }
+        None => NumberSpec {
+            zero_pad: false,
+            width: 0,
+            radix: default_radix,
+        },
+    };
+    Ok(Part::Number(field, spec))
+}
+
+/// `[0][width][x|X|d]`.
+fn parse_spec(spec: &str, default_radix: Radix) -> Option<NumberSpec> {
+    let (digits, radix) = match spec.as_bytes().last() {
+        Some(b'x') => (&spec[..spec.len() - 1], Radix::LowerHex),
+        Some(b'X') => (&spec[..spec.len() - 1], Radix::UpperHex),
+        Some(b'd') => (&spec[..spec.len() - 1], Radix::Decimal),
+        _ => (spec, default_radix),
+    };
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let zero_pad = digits.starts_with('0');
+    let width = if digits.is_empty() {
+        0
+    } else {
+        digits.parse().ok().filter(|&width: &usize| width <= 32)?
+    };
+    Some(NumberSpec {
+        zero_pad,
+        width,
+        radix,
+    })
+}
+
+impl fmt::Display for FileNameTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FileNameTemplate {
+    fn render(&self, segment: &Segment, index: usize, stem: &str, ext: &str) -> String {
+        let mut name = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => name.push_str(text),
+                Part::Stem => name.push_str(stem),
+                Part::Ext => name.push_str(ext),
+                Part::Label => name.push_str(segment.name.as_deref().unwrap_or_default()),
+                Part::Number(field, spec) => {
+                    let value = match field {
+                        NumberField::Addr => segment.start_address as u64,
+                        NumberField::Index => index as u64,
+                        NumberField::Length => segment.len() as u64,
+                    };
+                    let width = spec.width;
+                    let digits = match (spec.radix, spec.zero_pad) {
+                        (Radix::Decimal, true) => format!("{value:0width$}"),
+                        (Radix::Decimal, false) => format!("{value:width$}"),
+                        (Radix::LowerHex, true) => format!("{value:0width$x}"),
+                        (Radix::LowerHex, false) => format!("{value:width$x}"),
+                        (Radix::UpperHex, true) => format!("{value:0width$X}"),
+                        (Radix::UpperHex, false) => format!("{value:width$X}"),
+                    };
+                    name.push_str(&digits);
+                }
+            }
+        }
+        name
+    }
+}
+
+/// The file name for each of `segments` (sorted and normalized), in order.
+/// Errors before anything is written when two blocks would share a name.
+pub fn separate_binary_names(
+    segments: &[Segment],
+    stem: &str,
+    ext: &str,
+    options: &SeparateBinaryOptions,
+) -> Result<Vec<String>, ParseError> {
+    let names: Vec<String> = match &options.template {
+        Some(template) => segments
+            .iter()
+            .enumerate()
+            .map(|(index, segment)| template.render(segment, index, stem, ext))
+            .collect(),
+        None => default_names(segments, stem, ext),
+    };
+
+    let mut seen = HashMap::with_capacity(names.len());
+    for (name, segment) in names.iter().zip(segments) {
+        if let Some(first) = seen.insert(name.as_str(), segment.start_address) {
+            return Err(ParseError::InvalidOutput(format!(
+                "blocks at 0x{first:X} and 0x{:X} would both be written to '{name}'",
+                segment.start_address
+            )));
+        }
+    }
+    Ok(names)
+}
+
+fn default_names(segments: &[Segment], stem: &str, ext: &str) -> Vec<String> {
+    // A label names its file; one used by several blocks also gets the address.
+    let shared = |name: &str| {
+        segments
+            .iter()
+            .filter(|s| s.name.as_deref() == Some(name))
+            .count()
+            > 1
+    };
+    segments
+        .iter()
+        .map(|segment| match segment.name.as_deref() {
+            Some(name) if shared(name) => {
+                format!("{stem}_{name}_{:x}.{ext}", segment.start_address)
+            }
+            Some(name) => format!("{stem}_{name}.{ext}"),
+            None => format!("{stem}_{:x}.{ext}", segment.start_address),
+        })
+        .collect()
+}
+
+/// One `(file name, contents)` pair per block, in address order. CLI: /XSB.
+pub fn write_separate_binary(
+    hexfile: &HexFile,
+    stem: &str,
+    ext: &str,
+    options: &SeparateBinaryOptions,
+) -> Result<Vec<(String, Vec<u8>)>, ParseError> {
+    let segments = normalized_sorted_segments(hexfile);
+    let names = separate_binary_names(&segments, stem, ext, options)?;
+    Ok(names
+        .into_iter()
+        .zip(segments.iter())
+        .map(|(name, segment)| (name, segment.data.to_vec()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(template: &str, segments: &[Segment]) -> Result<Vec<String>, ParseError> {
+        let options = SeparateBinaryOptions {
+            template: Some(template.parse().unwrap()),
+        };
+        separate_binary_names(segments, "out", "bin", &options)
+    }
+
+    #[test]
+    fn test_template_pads_and_indexes_by_address() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x8000, vec![0x02; 0x20]),
+            Segment::new(0x1000, vec![0x01; 4]),
+        ]);
+        let options = SeparateBinaryOptions {
+            template: Some(
+                "{stem}_{index:02}_{addr:08X}_{length:x}.{ext}"
+                    .parse()
+                    .unwrap(),
+            ),
+        };
+        let files = write_separate_binary(&hexfile, "out", "bin", &options).unwrap();
+        let written: Vec<_> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(written, ["out_00_00001000_4.bin", "out_01_00008000_20.bin"]);
+        assert_eq!(files[0].1, vec![0x01; 4]);
+
+        let segments = [Segment::new(0xAB, vec![0; 3])];
+        assert_eq!(
+            names("{{{addr:6d}}}-{addr:x}", &segments).unwrap(),
+            ["{   171}-ab"]
+        );
+    }
+
+    #[test]
+    fn test_template_collision_is_an_error() {
+        let segments = [Segment::new(0x1000, vec![1]), Segment::new(0x2000, vec![2])];
+        let err = names("{stem}_{label}.{ext}", &segments).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid output: blocks at 0x1000 and 0x2000 would both be written to 'out_.bin'"
+        );
+        assert!(names("{stem}_{index}.{ext}", &segments).is_ok());
+    }
+
+    #[test]
+    fn test_template_rejects_bad_syntax() {
+        for (template, message) in [
+            (
+                "{stem}_{address}.bin",
+                "unknown placeholder {address} at column 8",
+            ),
+            (
+                "{addr:08Q}",
+                "invalid format spec '08Q' for {addr} at column 1",
+            ),
+            (
+                "x{stem:4}",
+                "invalid format spec '4' for {stem} at column 2",
+            ),
+            ("{stem}_{index", "unclosed '{' at column 8"),
+            ("index}", "unmatched '}' at column 6"),
+            (
+                "out/{index}",
+                "path separator at column 4: file names may not contain one",
+            ),
+        ] {
+            let err = template.parse::<FileNameTemplate>().unwrap_err();
+            assert_eq!(err.to_string(), message, "{template}");
+        }
+    }
+}
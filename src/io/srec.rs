@@ -1,5 +1,10 @@
-use crate::io::{ParseError, normalized_sorted_segments, push_crlf, push_hex_byte};
-use crate::{HexFile, Segment};
+use std::io::Write;
+
+use crate::io::{
+    LineEnding, ParseError, ParseOptions, ParseWarning, RunReader, contiguous_runs, push_hex_byte,
+    sorted_segments,
+};
+use crate::{HexFile, Segment, SourceFormat};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SRecordType {
@@ -12,6 +17,18 @@ pub enum SRecordType {
 pub struct SRecordWriteOptions {
     pub bytes_per_line: u8,
     pub record_type: Option<SRecordType>,
+    /// Module name written as an S0 header record.
+    pub header: Option<String>,
+    /// Write an S5 (or S6 above 0xFFFF) data record count before the terminator.
+    pub emit_count: bool,
+    /// Address stored in the S7/S8/S9 terminator.
+    pub entry_point: Option<u32>,
+    pub line_ending: LineEnding,
+    /// Uppercase hex digits; lowercase otherwise. The `S` prefix stays uppercase.
+    pub uppercase: bool,
+    /// Start every data record at a multiple of `bytes_per_line`; the first
+    /// record of an unaligned segment is shortened. CLI: /ALIGNREC.
+    pub align_records: bool,
 }
 
 impl Default for SRecordWriteOptions {
@@ -19,13 +36,45 @@ impl Default for SRecordWriteOptions {
         Self {
             bytes_per_line: 16,
             record_type: None,
+            header: None,
+            emit_count: false,
+            entry_point: None,
+            line_ending: LineEnding::CrLf,
+            uppercase: true,
+            align_records: false,
         }
     }
 }
 
 /// Parse Motorola S-Record input. CLI: auto-detect S-Record input.
+///
+/// S5/S6 count records are checked against the data records seen so far.
+/// The S0 text and a non-zero S7/S8/S9 terminator address are kept in the
+/// metadata.
 pub fn parse_srec(data: &[u8]) -> Result<HexFile, ParseError> {
+    parse_srec_with(data, &ParseOptions::default()).map(|(hexfile, _)| hexfile)
+}
+
+/// Parse S-Record input with options. CLI: /LENIENT turns a wrong S5/S6
+/// count into a warning.
+pub fn parse_srec_with(
+    data: &[u8],
+    options: &ParseOptions,
+) -> Result<(HexFile, Vec<ParseWarning>), ParseError> {
+    parse_records(data, options).map_err(|e| e.with_record_in(data))
+}
+
+fn parse_records(
+    data: &[u8],
+    options: &ParseOptions,
+) -> Result<(HexFile, Vec<ParseWarning>), ParseError> {
+    let mut warnings = Vec::new();
     let mut hexfile = HexFile::new();
+    hexfile.metadata_mut().source_format = Some(SourceFormat::SRecord);
+    let mut data_records = 0u32;
+    // Start and bytes of the segment being read; a plain Vec keeps the
+    // per-record append off `SegmentData`.
+    let mut current: Option<(u32, Vec<u8>)> = None;
 
     for (idx, raw_line) in data.split(|&b| b == b'\n').enumerate() {
         let line_no = idx + 1;
@@ -40,6 +89,7 @@ pub fn parse_srec(data: &[u8]) -> Result<HexFile, ParseError> {
             return Err(ParseError::InvalidRecord {
                 line: line_no,
                 message: "missing S-record prefix".to_string(),
+                record: None,
             });
         }
 
@@ -49,6 +99,7 @@ pub fn parse_srec(data: &[u8]) -> Result<HexFile, ParseError> {
             return Err(ParseError::InvalidRecord {
                 line: line_no,
                 message: "missing record length".to_string(),
+                record: None,
             });
         }
 
@@ -61,6 +112,7 @@ pub fn parse_srec(data: &[u8]) -> Result<HexFile, ParseError> {
                     count + 1,
                     record_bytes.len()
                 ),
+                record: None,
             });
         }
 
@@ -71,12 +123,69 @@ pub fn parse_srec(data: &[u8]) -> Result<HexFile, ParseError> {
                 line: line_no,
                 expected,
                 actual,
+                record: None,
             });
         }
 
         match record_type {
-            '0' | '5' | '7' | '8' | '9' => continue,
+            '0' => {
+                let text = record_bytes
+                    .get(3..record_bytes.len() - 1)
+                    .unwrap_or_default();
+                let text = String::from_utf8_lossy(text);
+                let text = text.trim_end_matches('\0');
+                if !text.is_empty() {
+                    hexfile.metadata_mut().header = Some(text.to_string());
+                }
+            }
+            '7' | '8' | '9' => {
+                let addr_len = match record_type {
+                    '9' => 2,
+                    '8' => 3,
+                    _ => 4,
+                };
+                if count != addr_len + 1 {
+                    return Err(ParseError::InvalidRecord {
+                        line: line_no,
+                        message: format!(
+                            "S{record_type} record must hold a {addr_len}-byte address"
+                        ),
+                        record: None,
+                    });
+                }
+                let entry = parse_address(&record_bytes[1..1 + addr_len]);
+                if entry != 0 {
+                    hexfile.metadata_mut().entry_point = Some(entry);
+                }
+            }
+            '5' | '6' => {
+                let addr_len = if record_type == '5' { 2 } else { 3 };
+                if count != addr_len + 1 {
+                    return Err(ParseError::InvalidRecord {
+                        line: line_no,
+                        message: format!("S{record_type} record must hold a {addr_len}-byte count"),
+                        record: None,
+                    });
+                }
+                let expected = parse_address(&record_bytes[1..1 + addr_len]);
+                if expected != data_records && options.lenient {
+                    warnings.push(ParseWarning::RecordCountMismatch {
+                        line: line_no,
+                        expected,
+                        found: data_records,
+                    });
+                } else if expected != data_records {
+                    return Err(ParseError::InvalidRecord {
+                        line: line_no,
+                        message: format!(
+                            "record count mismatch: S{record_type} says {expected}, found {data_records}"
+                        ),
+                        record: None,
+                    });
+                }
+            }
             '1' | '2' | '3' => {
+                data_records = data_records.wrapping_add(1);
                 let addr_len = match record_type {
                     '1' => 2,
                     '2' => 3,
@@ -89,6 +198,7 @@ pub fn parse_srec(data: &[u8]) -> Result<HexFile, ParseError> {
                         .ok_or(ParseError::InvalidRecord {
                             line: line_no,
                             message: "record length too short".to_string(),
+                            record: None,
                         })?;
                 let addr_end = 1 + addr_len;
                 let data_start = addr_end;
@@ -98,12 +208,13 @@ pub fn parse_srec(data: &[u8]) -> Result<HexFile, ParseError> {
                     return Err(ParseError::InvalidRecord {
                         line: line_no,
                         message: "data length mismatch".to_string(),
+                        record: None,
                     });
                 }
 
                 let addr = parse_address(&record_bytes[1..addr_end]);
                 if data_len > 0 {
-                    let data = record_bytes[data_start..data_end].to_vec();
+                    let data = &record_bytes[data_start..data_end];
                     let end = addr.checked_add(data.len() as u32 - 1).ok_or_else(|| {
                         ParseError::AddressOverflow(format!(
                             "{:#X} + {} exceeds u32",
@@ -118,7 +229,17 @@ pub fn parse_srec(data: &[u8]) -> Result<HexFile, ParseError> {
                             data.len()
                         )));
                     }
-                    hexfile.append_segment(Segment::new(addr, data));
+                    // Extend the current segment while records are contiguous.
+                    match &mut current {
+                        Some((start, seg)) if *start as u64 + seg.len() as u64 == addr as u64 => {
+                            seg.extend_from_slice(data);
+                        }
+                        _ => {
+                            if let Some((start, seg)) = current.replace((addr, data.to_vec())) {
+                                hexfile.append_segment(Segment::new(start, seg));
+                            }
+                        }
+                    }
                 }
             }
             other => {
@@ -130,13 +251,59 @@ pub fn parse_srec(data: &[u8]) -> Result<HexFile, ParseError> {
         }
     }
 
-    Ok(hexfile)
+    if let Some((start, seg)) = current {
+        hexfile.append_segment(Segment::new(start, seg));
+    }
+    Ok((hexfile, warnings))
 }
 
 /// Write Motorola S-Record output. CLI: /XS.
+///
+/// Unset `header` and `entry_point` options fall back to the parsed metadata.
 pub fn write_srec(hexfile: &HexFile, options: &SRecordWriteOptions) -> Result<Vec<u8>, ParseError> {
-    let normalized = hexfile.normalized_lossy();
-    let max_addr = normalized.max_address().unwrap_or(0);
+    let mut out = Vec::new();
+    write_srec_to(hexfile, options, &mut out)?;
+    Ok(out)
+}
+
+/// Stream S-Record output into `writer`, one record at a time. Produces the
+/// same bytes as [`write_srec`]; option errors are reported before anything
+/// is written.
+pub fn write_srec_to<W: Write>(
+    hexfile: &HexFile,
+    options: &SRecordWriteOptions,
+    writer: W,
+) -> Result<(), ParseError> {
+    write_records(hexfile, options, None, writer)
+}
+
+/// Stream S-Record output of `hexfile` with every gap between its lowest and
+/// highest address filled with `fill_byte`. Produces the same bytes as
+/// [`HexFile::fill_gaps`] followed by [`write_srec_to`], without building the
+/// filled image.
+pub fn write_srec_filled_to<W: Write>(
+    hexfile: &HexFile,
+    options: &SRecordWriteOptions,
+    fill_byte: u8,
+    writer: W,
+) -> Result<(), ParseError> {
+    write_records(hexfile, options, Some(fill_byte), writer)
+}
+
+fn write_records<W: Write>(
+    hexfile: &HexFile,
+    options: &SRecordWriteOptions,
+    gap_fill: Option<u8>,
+    mut writer: W,
+) -> Result<(), ParseError> {
+    let metadata = hexfile.metadata();
+    let header = options.header.as_ref().or(metadata.header.as_ref());
+    let segments = sorted_segments(hexfile);
+    let entry_point = options.entry_point.or(metadata.entry_point).unwrap_or(0);
+    let max_addr = segments
+        .last()
+        .map_or(0, |s| s.end_address())
+        .max(entry_point);
 
     let auto_type = if max_addr <= 0xFFFF {
         SRecordType::S1
@@ -166,49 +333,104 @@ pub fn write_srec(hexfile: &HexFile, options: &SRecordWriteOptions) -> Result<Ve
         options.bytes_per_line
     } as usize;
 
-    let segments = normalized_sorted_segments(&normalized);
+    // Bytes until the next record boundary at `addr`.
+    let line_room = |addr: u32| {
+        if options.align_records {
+            bytes_per_line as u64 - addr as u64 % bytes_per_line as u64
+        } else {
+            bytes_per_line as u64
+        }
+    };
+    let runs = contiguous_runs(&segments, gap_fill);
+    let data_records: u64 = runs
+        .iter()
+        .map(|run| {
+            let first = line_room(run.start).min(run.len);
+            u64::from(first > 0) + (run.len - first).div_ceil(bytes_per_line as u64)
+        })
+        .sum();
+    let count_record = if !options.emit_count {
+        None
+    } else if data_records <= 0xFFFF {
+        Some(('5', 2usize))
+    } else if data_records <= 0xFF_FFFF {
+        Some(('6', 3usize))
+    } else {
+        return Err(ParseError::InvalidOutput(format!(
+            "{data_records} data records exceed the S6 count limit"
+        )));
+    };
 
-    let mut out = Vec::new();
+    let mut line = Vec::new();
+    let mut out = |digit: char, record: &[u8]| -> Result<(), ParseError> {
+        line.clear();
+        push_record_line(&mut line, options, digit, record, expected_checksum(record));
+        writer.write_all(&line)?;
+        Ok(())
+    };
     let (addr_len, record_digit) = match record_type {
         SRecordType::S1 => (2usize, '1'),
         SRecordType::S2 => (3usize, '2'),
         SRecordType::S3 => (4usize, '3'),
     };
 
-    for segment in segments {
-        let mut addr = segment.start_address;
-        for chunk in segment.data.chunks(bytes_per_line) {
+    if let Some(header) = header {
+        // S0: 16-bit address 0x0000, module name as data.
+        let name = header.as_bytes();
+        let count = u8::try_from(name.len() + 3)
+            .ok()
+            .filter(|_| name.len() <= 252)
+            .ok_or_else(|| {
+                ParseError::InvalidOutput(format!(
+                    "S0 header is {} bytes, at most 252 allowed",
+                    name.len()
+                ))
+            })?;
+        let mut record = vec![count, 0x00, 0x00];
+        record.extend_from_slice(name);
+        out('0', &record)?;
+    }
+
+    let mut record = Vec::with_capacity(1 + addr_len + bytes_per_line);
+    for run in &runs {
+        let mut addr = run.start;
+        let mut remaining = run.len;
+        let mut reader = RunReader::new(run);
+        while remaining > 0 {
+            let chunk = reader.take(remaining.min(line_room(addr)) as usize);
+            remaining -= chunk.len() as u64;
             let addr_bytes = addr.to_be_bytes();
             let addr_slice = &addr_bytes[4 - addr_len..];
             let count = (addr_len + chunk.len() + 1) as u8;
-            let mut record = Vec::with_capacity(1 + addr_len + chunk.len() + 1);
+            record.clear();
             record.push(count);
             record.extend_from_slice(addr_slice);
             record.extend_from_slice(chunk);
-            let checksum = expected_checksum(&record);
-
-            push_record_line(&mut out, record_digit, &record, checksum);
+            out(record_digit, &record)?;
             addr = addr
                 .checked_add(chunk.len() as u32)
                 .ok_or_else(|| ParseError::AddressOverflow("address overflow".to_string()))?;
         }
     }
 
+    if let Some((digit, count_len)) = count_record {
+        let mut record = vec![(count_len + 1) as u8];
+        record.extend_from_slice(&(data_records as u32).to_be_bytes()[4 - count_len..]);
+        out(digit, &record)?;
+    }
+
     let term_digit = match record_type {
         SRecordType::S1 => '9',
         SRecordType::S2 => '8',
         SRecordType::S3 => '7',
     };
-    let addr_bytes = [0u8; 4];
+    let addr_bytes = entry_point.to_be_bytes();
     let addr_slice = &addr_bytes[4 - addr_len..];
     let count = (addr_len + 1) as u8;
     let mut term = Vec::with_capacity(1 + addr_len);
     term.push(count);
     term.extend_from_slice(addr_slice);
-    let checksum = expected_checksum(&term);
-    push_record_line(&mut out, term_digit, &term, checksum);
-
-    Ok(out)
+    out(term_digit, &term)
 }
 
 fn parse_hex_bytes(data: &[u8], line: usize) -> Result<Vec<u8>, ParseError> {
@@ -216,6 +438,7 @@ fn parse_hex_bytes(data: &[u8], line: usize) -> Result<Vec<u8>, ParseError> {
         return Err(ParseError::InvalidRecord {
             line,
             message: "odd number of hex digits".to_string(),
+            record: None,
         });
     }
     let mut out = Vec::with_capacity(data.len() / 2);
@@ -226,12 +449,14 @@ fn parse_hex_bytes(data: &[u8], line: usize) -> Result<Vec<u8>, ParseError> {
             .ok_or(ParseError::InvalidHexDigit {
                 line,
                 char: hi as char,
+                record: None,
             })?;
         let lo = (lo as char)
             .to_digit(16)
             .ok_or(ParseError::InvalidHexDigit {
                 line,
                 char: lo as char,
+                record: None,
             })?;
         out.push(((hi << 4) | lo) as u8);
     }
@@ -260,19 +485,26 @@ fn max_address_for(record_type: SRecordType) -> u32 {
     }
 }
 
-fn push_record_line(out: &mut Vec<u8>, record_digit: char, data: &[u8], checksum: u8) {
+fn push_record_line(
+    out: &mut Vec<u8>,
+    options: &SRecordWriteOptions,
+    record_digit: char,
+    data: &[u8],
+    checksum: u8,
+) {
     out.push(b'S');
     out.push(record_digit as u8);
     for &byte in data {
-        push_hex_byte(out, byte);
+        push_hex_byte(out, byte, options.uppercase);
     }
-    push_hex_byte(out, checksum);
-    push_crlf(out);
+    push_hex_byte(out, checksum, options.uppercase);
+    options.line_ending.push(out);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::HexFileMetadata;
 
     #[test]
     fn test_srec_roundtrip_s1() {
@@ -280,6 +512,7 @@ mod tests {
         let options = SRecordWriteOptions {
             bytes_per_line: 16,
             record_type: Some(SRecordType::S1),
+            ..Default::default()
         };
         let out = write_srec(&hexfile, &options).unwrap();
         let parsed = parse_srec(&out).unwrap();
@@ -289,6 +522,20 @@ mod tests {
         assert_eq!(norm.segments()[0].data, vec![0x01, 0x02, 0x03]);
     }
 
+    #[test]
+    fn test_srec_lowercase_lf_roundtrip() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0xABCD, vec![0xEF])]);
+        let options = SRecordWriteOptions {
+            line_ending: LineEnding::Lf,
+            uppercase: false,
+            ..Default::default()
+        };
+        let out = write_srec(&hexfile, &options).unwrap();
+        assert_eq!(out, b"S104abcdef94\nS9030000fc\n");
+        let parsed = parse_srec(&out).unwrap();
+        assert_eq!(parsed.segments(), hexfile.segments());
+    }
+
     #[test]
     fn test_srec_bad_checksum() {
         let line = b"S11310000102030405060708090A0B0C0D0E0F00\n";
@@ -312,4 +559,246 @@ mod tests {
         assert_eq!(norm.segments()[0].start_address, 0x0000);
         assert_eq!(norm.segments()[0].data, vec![0x01, 0x02]);
     }
+
+    #[test]
+    fn test_srec_header_and_count_records() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x0000, vec![0x01, 0x02, 0x03])]);
+        let options = SRecordWriteOptions {
+            bytes_per_line: 2,
+            header: Some("HDR".to_string()),
+            emit_count: true,
+            ..Default::default()
+        };
+        let out = write_srec(&hexfile, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            "S00600004844521B\r\n\
+             S10500000102F7\r\n\
+             S104000203F6\r\n\
+             S5030002FA\r\n\
+             S9030000FC\r\n"
+        );
+        assert_eq!(
+            parse_srec(&out).unwrap().normalized_lossy().segments()[0].data,
+            vec![0x01, 0x02, 0x03]
+        );
+    }
+
+    #[test]
+    fn test_srec_count_uses_s6_above_16_bits() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x0000, vec![0xAA; 0x1_0000])]);
+        let options = SRecordWriteOptions {
+            bytes_per_line: 1,
+            record_type: Some(SRecordType::S3),
+            emit_count: true,
+            ..Default::default()
+        };
+        let out = write_srec(&hexfile, &options).unwrap();
+        let text = String::from_utf8(out.clone()).unwrap();
+        assert!(text.contains("S604010000FA\r\n"), "missing S6 record");
+        assert!(parse_srec(&out).is_ok());
+    }
+
+    #[test]
+    fn test_srec_entry_point_in_terminator() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x0800_0000, vec![0x01])]);
+        let options = SRecordWriteOptions {
+            entry_point: Some(0x0800_0400),
+            ..Default::default()
+        };
+        let out = write_srec(&hexfile, &options).unwrap();
+        assert!(
+            String::from_utf8(out.clone())
+                .unwrap()
+                .ends_with("S70508000400EE\r\n")
+        );
+        assert_eq!(
+            parse_srec(&out).unwrap().metadata().entry_point,
+            Some(0x0800_0400)
+        );
+
+        let out = write_srec(&hexfile, &SRecordWriteOptions::default()).unwrap();
+        assert_eq!(parse_srec(&out).unwrap().metadata().entry_point, None);
+
+        let options = SRecordWriteOptions {
+            record_type: Some(SRecordType::S1),
+            entry_point: Some(0x1_0000),
+            ..Default::default()
+        };
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x0, vec![0x01])]);
+        assert!(write_srec(&hexfile, &options).is_err());
+    }
+
+    #[test]
+    fn test_srec_metadata_roundtrip_and_option_precedence() {
+        let input = b"S00600004844521B\r\nS10500000102F7\r\nS9030100FB\r\n";
+        let parsed = parse_srec(input).unwrap();
+        assert_eq!(
+            parsed.metadata(),
+            &HexFileMetadata {
+                entry_point: Some(0x0100),
+                header: Some("HDR".to_string()),
+                source_format: Some(SourceFormat::SRecord),
+            }
+        );
+
+        let out = write_srec(&parsed, &SRecordWriteOptions::default()).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("S00600004844521B\r\n"), "{text}");
+        assert!(text.ends_with("S9030100FB\r\n"), "{text}");
+
+        let options = SRecordWriteOptions {
+            header: Some("X".to_string()),
+            entry_point: Some(0x0200),
+            ..Default::default()
+        };
+        let reparsed = parse_srec(&write_srec(&parsed, &options).unwrap()).unwrap();
+        assert_eq!(reparsed.metadata().header.as_deref(), Some("X"));
+        assert_eq!(reparsed.metadata().entry_point, Some(0x0200));
+    }
+
+    #[test]
+    fn test_parse_coalesces_contiguous_records() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(
+            0x1000,
+            (0..4096u32).map(|i| i as u8).collect(),
+        )]);
+        let text = write_srec(&hexfile, &SRecordWriteOptions::default()).unwrap();
+        let parsed = parse_srec(&text).unwrap();
+        assert_eq!(parsed.segments(), hexfile.segments());
+
+        // Out of order or gapped records still start a new segment.
+        let parsed = parse_srec(b"S1050002CCDD4F\nS1050000AABB95\nS1040004EE09\n").unwrap();
+        assert_eq!(
+            parsed.segments(),
+            [
+                Segment::new(0x0002, vec![0xCC, 0xDD]),
+                Segment::new(0x0000, vec![0xAA, 0xBB]),
+                Segment::new(0x0004, vec![0xEE]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_srec_count_mismatch_rejected() {
+        let data = b"S10500000102F7\nS5030002FA\nS9030000FC\n";
+        let err = parse_srec(data).unwrap_err();
+        assert!(
+            matches!(err, ParseError::InvalidRecord { line: 2, .. }),
+            "{err:?}"
+        );
+        assert!(
+            err.to_string().ends_with(" in record \"S5030002FA\""),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_srec_count_mismatch_lenient_warns() {
+        let data = b"S10500000102F7\nS5030002FA\nS9030000FC\n";
+        let options = ParseOptions { lenient: true };
+        let (hexfile, warnings) = parse_srec_with(data, &options).unwrap();
+        assert_eq!(hexfile.segments()[0].data, vec![0x01, 0x02]);
+        assert_eq!(
+            warnings,
+            vec![ParseWarning::RecordCountMismatch {
+                line: 2,
+                expected: 2,
+                found: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_srec_count_matches_split_records() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x1000, vec![0x11; 5]),
+            Segment::new(0x2000, vec![0x22; 3]),
+        ]);
+        let options = SRecordWriteOptions {
+            bytes_per_line: 2,
+            emit_count: true,
+            ..Default::default()
+        };
+        let out = write_srec(&hexfile, &options).unwrap();
+        let text = String::from_utf8(out.clone()).unwrap();
+        assert_eq!(text.lines().filter(|l| l.starts_with("S1")).count(), 5);
+        assert!(text.contains("S5030005F7\r\n"), "{text}");
+        assert!(parse_srec_with(&out, &ParseOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn test_srec_align_records() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x1007, vec![0x5A; 0x20])]);
+        let options = SRecordWriteOptions {
+            bytes_per_line: 16,
+            emit_count: true,
+            align_records: true,
+            ..Default::default()
+        };
+        let out = write_srec(&hexfile, &options).unwrap();
+        let text = String::from_utf8(out.clone()).unwrap();
+        let addresses: Vec<&str> = text
+            .lines()
+            .filter(|l| l.starts_with("S1"))
+            .map(|l| &l[4..8])
+            .collect();
+        assert_eq!(addresses, vec!["1007", "1010", "1020"]);
+        assert!(text.contains("S5030003F9\r\n"), "{text}");
+        let parsed = parse_srec_with(&out, &ParseOptions::default()).unwrap().0;
+        assert_eq!(parsed.segments(), hexfile.segments());
+    }
+
+    #[test]
+    fn test_write_srec_to_matches_vec_output() {
+        let mut hexfile = HexFile::with_segments(vec![
+            Segment::new(0x0001_0000, (0..=0xFFu8).collect()),
+            Segment::new(0x20, vec![0x55; 17]),
+        ]);
+        hexfile.metadata_mut().header = Some("APP".to_string());
+        let options = SRecordWriteOptions {
+            emit_count: true,
+            entry_point: Some(0x0001_0000),
+            ..Default::default()
+        };
+        let mut sink = std::io::BufWriter::with_capacity(7, Vec::new());
+        write_srec_to(&hexfile, &options, &mut sink).unwrap();
+        let streamed = sink.into_inner().unwrap();
+        assert_eq!(streamed, write_srec(&hexfile, &options).unwrap());
+    }
+
+    #[test]
+    fn test_write_srec_filled_matches_fill_gaps() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x0001_0000, (0..=0xFFu8).collect()),
+            Segment::new(0x20, vec![0x55; 17]),
+            Segment::new(0x31, vec![0x66; 3]),
+        ]);
+        let mut filled = hexfile.clone();
+        filled.fill_gaps(0x00).unwrap();
+        let mut chunked = hexfile.clone();
+        chunked.fill_gaps_chunked(0x00, 0x101).unwrap();
+        let options = SRecordWriteOptions {
+            bytes_per_line: 13,
+            emit_count: true,
+            ..Default::default()
+        };
+        let expected = write_srec(&filled, &options).unwrap();
+        let mut out = Vec::new();
+        write_srec_filled_to(&hexfile, &options, 0x00, &mut out).unwrap();
+        assert_eq!(out, expected);
+        assert_eq!(write_srec(&chunked, &options).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_write_srec_to_rejects_options_before_writing() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x0001_0000, vec![0x01])]);
+        let options = SRecordWriteOptions {
+            record_type: Some(SRecordType::S1),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        assert!(write_srec_to(&hexfile, &options, &mut out).is_err());
+        assert!(out.is_empty());
+    }
 }
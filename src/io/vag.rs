@@ -0,0 +1,116 @@
+//! VAG flash container export (CLI: /XV).
+//!
+//! Experimental: the request only described a "VAG/ODX-style" container, and
+//! there is no reference file to check the header and trailer words against.
+//! The layout below follows that description and has not been checked
+//! against a real VAG consumer; the tests only pin it against regressions.
+//!
+//! Layout (multi-byte header values big-endian):
+//!
+//! - per block: start address (u32), length (u32), data, block checksum
+//! - trailer: block count (u32), file checksum
+//!
+//! Blocks are the normalized segments sorted by address, split into chunks
+//! of at most `block_size` bytes (0 keeps segments whole). Both checksums use
+//! `algorithm` in its native byte order: the block checksum covers the block
+//! data at its address, the file checksum covers every byte before the trailer.
+
+use crate::io::{ParseError, normalized_sorted_segments};
+use crate::{ChecksumAlgorithm, ChecksumOptions, HexFile, Segment};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VagWriteOptions {
+    /// Maximum block length in bytes; 0 keeps each segment as one block.
+    pub block_size: u32,
+    pub algorithm: ChecksumAlgorithm,
+}
+
+/// Write a VAG container. CLI: /XV.
+pub fn write_vag(hexfile: &HexFile, options: &VagWriteOptions) -> Result<Vec<u8>, ParseError> {
+    let mut blocks = HexFile::with_segments(normalized_sorted_segments(hexfile).into_owned());
+    if options.block_size > 0 {
+        blocks.split(options.block_size);
+    }
+
+    let mut out = Vec::new();
+    for block in blocks.segments() {
+        let len = u32::try_from(block.len()).map_err(|_| {
+            ParseError::InvalidOutput(format!(
+                "VAG block at {:#X} exceeds 32 bits",
+                block.start_address
+            ))
+        })?;
+        out.extend_from_slice(&block.start_address.to_be_bytes());
+        out.extend_from_slice(&len.to_be_bytes());
+        out.extend_from_slice(&block.data);
+        out.extend_from_slice(&checksum(options.algorithm, block.clone())?);
+    }
+
+    let count = blocks.segments().len() as u32;
+    let file_checksum = checksum(options.algorithm, Segment::new(0, out.clone()))?;
+    out.extend_from_slice(&count.to_be_bytes());
+    out.extend_from_slice(&file_checksum);
+    Ok(out)
+}
+
+fn checksum(algorithm: ChecksumAlgorithm, segment: Segment) -> Result<Vec<u8>, ParseError> {
+    let options = ChecksumOptions {
+        algorithm,
+        ..Default::default()
+    };
+    HexFile::with_segments(vec![segment])
+        .calculate_checksum(&options)
+        .map_err(|e| ParseError::InvalidOutput(format!("VAG checksum: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_vag_byte_sum_layout() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x2000, vec![0x10]),
+            Segment::new(0x1000, vec![0x01, 0x02, 0x03]),
+        ]);
+        let options = VagWriteOptions {
+            block_size: 2,
+            algorithm: ChecksumAlgorithm::ByteSumBe,
+        };
+        let out = write_vag(&hexfile, &options).unwrap();
+
+        let mut expected = vec![0, 0, 0x10, 0x00, 0, 0, 0, 2, 0x01, 0x02, 0x00, 0x03];
+        expected.extend_from_slice(&[0, 0, 0x10, 0x02, 0, 0, 0, 1, 0x03, 0x00, 0x03]);
+        expected.extend_from_slice(&[0, 0, 0x20, 0x00, 0, 0, 0, 1, 0x10, 0x00, 0x10]);
+        let file_sum = expected
+            .iter()
+            .fold(0u16, |acc, &b| acc.wrapping_add(b as u16));
+        expected.extend_from_slice(&[0, 0, 0, 3]);
+        expected.extend_from_slice(&file_sum.to_be_bytes());
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_write_vag_crc32_block_checksum() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x0, b"123456789".to_vec())]);
+        let options = VagWriteOptions {
+            block_size: 0,
+            algorithm: ChecksumAlgorithm::Crc32,
+        };
+        let out = write_vag(&hexfile, &options).unwrap();
+        assert_eq!(&out[17..21], &[0xCB, 0xF4, 0x39, 0x26]);
+        assert_eq!(&out[21..25], &[0, 0, 0, 1]);
+        assert_eq!(out.len(), 29);
+    }
+
+    #[test]
+    fn test_write_vag_reports_checksum_error() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x0, vec![0x01, 0x02, 0x03])]);
+        let options = VagWriteOptions {
+            block_size: 0,
+            algorithm: ChecksumAlgorithm::WordSumBe,
+        };
+        let err = write_vag(&hexfile, &options).unwrap_err();
+        assert!(err.to_string().contains("VAG checksum"), "{err}");
+    }
+}
@@ -0,0 +1,474 @@
+use crate::io::{ParseError, normalized_sorted_segments};
+use crate::{HexFile, Segment};
+
+const BLOCK_CRC: crc::Crc<u16> = crc::Crc::<u16>::new(&crc::CRC_16_IBM_3740);
+const FILE_CRC: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+
+/// Header values for VBF output. `file_checksum` is always computed by the writer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VbfWriteOptions {
+    /// Written as `vbf_version = <version>;`.
+    pub version: String,
+    pub sw_part_number: String,
+    /// Omitted from the header when empty.
+    pub sw_version: String,
+    /// e.g. `EXE`, `DATA`, `SBL`.
+    pub sw_part_type: String,
+    /// e.g. `CAN_HS`, `CAN_MS`.
+    pub network: String,
+    pub ecu_address: u32,
+    /// e.g. `CAN_STANDARD`, `CAN_EXTENDED`.
+    pub frame_format: String,
+    /// Erase blocks as (start, length); `None` erases exactly the data segments.
+    pub erase: Option<Vec<(u32, u32)>>,
+    /// Optional `call` entry (SBL/EXE start address).
+    pub call: Option<u32>,
+}
+
+impl Default for VbfWriteOptions {
+    fn default() -> Self {
+        Self {
+            version: "2.2".to_string(),
+            sw_part_number: String::new(),
+            sw_version: String::new(),
+            sw_part_type: "EXE".to_string(),
+            network: "CAN_HS".to_string(),
+            ecu_address: 0,
+            frame_format: "CAN_STANDARD".to_string(),
+            erase: None,
+            call: None,
+        }
+    }
+}
+
+/// Header of a parsed VBF file, kept alongside the data for re-export.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VbfHeader {
+    pub version: String,
+    pub sw_part_number: Option<String>,
+    pub sw_version: Option<String>,
+    pub sw_part_type: Option<String>,
+    pub network: Option<String>,
+    pub ecu_address: Option<u32>,
+    pub frame_format: Option<String>,
+    pub erase: Vec<(u32, u32)>,
+    pub call: Option<u32>,
+    pub file_checksum: Option<u32>,
+    /// Every `key = value` header entry in file order, quotes stripped.
+    pub entries: Vec<(String, String)>,
+}
+
+impl VbfHeader {
+    /// Writer options reproducing this header; missing fields use the writer defaults.
+    pub fn to_write_options(&self) -> VbfWriteOptions {
+        let defaults = VbfWriteOptions::default();
+        VbfWriteOptions {
+            version: self.version.clone(),
+            sw_part_number: self.sw_part_number.clone().unwrap_or_default(),
+            sw_version: self.sw_version.clone().unwrap_or_default(),
+            sw_part_type: self.sw_part_type.clone().unwrap_or(defaults.sw_part_type),
+            network: self.network.clone().unwrap_or(defaults.network),
+            ecu_address: self.ecu_address.unwrap_or_default(),
+            frame_format: self.frame_format.clone().unwrap_or(defaults.frame_format),
+            erase: (!self.erase.is_empty()).then(|| self.erase.clone()),
+            call: self.call,
+        }
+    }
+}
+
+/// Parse a VBF file. CLI: input auto-detected by `vbf_version`.
+pub fn parse_vbf(data: &[u8]) -> Result<HexFile, ParseError> {
+    parse_vbf_with_header(data).map(|(hexfile, _)| hexfile)
+}
+
+/// Parse a VBF file and return its header. Block CRCs and `file_checksum`
+/// (when present) are validated.
+pub fn parse_vbf_with_header(data: &[u8]) -> Result<(HexFile, VbfHeader), ParseError> {
+    let (text, header_len) = split_header(data)?;
+    let header = parse_header(&text)?;
+
+    let blocks = &data[header_len..];
+    let mut segments = Vec::new();
+    let mut rest = blocks;
+    while !rest.is_empty() {
+        if rest.len() < 8 {
+            return Err(ParseError::UnexpectedEof);
+        }
+        let address = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]);
+        let length = u32::from_be_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+        let Some(block) = rest.get(8..8 + length + 2) else {
+            return Err(ParseError::UnexpectedEof);
+        };
+        let (payload, crc) = block.split_at(length);
+        let expected = u16::from_be_bytes([crc[0], crc[1]]);
+        let actual = BLOCK_CRC.checksum(payload);
+        if expected != actual {
+            return Err(ParseError::InvalidVbf(format!(
+                "block at {address:#X}: CRC expected {expected:04X}, got {actual:04X}"
+            )));
+        }
+        if address as u64 + length as u64 > u32::MAX as u64 + 1 {
+            return Err(ParseError::AddressOverflow(format!(
+                "VBF block at {address:#X} with length {length:#X}"
+            )));
+        }
+        segments.push(Segment::new(address, payload.to_vec()));
+        rest = &rest[8 + length + 2..];
+    }
+
+    if let Some(expected) = header.file_checksum {
+        let actual = FILE_CRC.checksum(blocks);
+        if expected != actual {
+            return Err(ParseError::InvalidVbf(format!(
+                "file_checksum expected {expected:08X}, got {actual:08X}"
+            )));
+        }
+    }
+
+    Ok((HexFile::with_segments(segments), header))
+}
+
+/// Strip comments from the ASCII header and return it with the offset of the
+/// first data byte (just past the `}` closing `header {`).
+fn split_header(data: &[u8]) -> Result<(String, usize), ParseError> {
+    let mut text = String::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut i = 0;
+    while i < data.len() {
+        let b = data[i];
+        let next = data.get(i + 1).copied();
+        if !b.is_ascii() {
+            return Err(ParseError::InvalidVbf(format!(
+                "non-ASCII byte in header at offset {i}"
+            )));
+        }
+        if in_string {
+            in_string = b != b'"';
+        } else if b == b'/' && next == Some(b'/') {
+            while i < data.len() && data[i] != b'\n' {
+                i += 1;
+            }
+            continue;
+        } else if b == b'/' && next == Some(b'*') {
+            let Some(end) = data[i + 2..].windows(2).position(|w| w == b"*/") else {
+                return Err(ParseError::UnexpectedEof);
+            };
+            i += 2 + end + 2;
+            continue;
+        } else if b == b'"' {
+            in_string = true;
+        } else if b == b'{' {
+            depth += 1;
+        } else if b == b'}' {
+            depth = depth
+                .checked_sub(1)
+                .ok_or_else(|| ParseError::InvalidVbf(format!("unbalanced '}}' at offset {i}")))?;
+            if depth == 0 {
+                text.push('}');
+                return Ok((text, i + 1));
+            }
+        }
+        text.push(b as char);
+        i += 1;
+    }
+    Err(ParseError::UnexpectedEof)
+}
+
+fn parse_header(text: &str) -> Result<VbfHeader, ParseError> {
+    let (preamble, body) = text
+        .split_once('{')
+        .ok_or_else(|| ParseError::InvalidVbf("missing header block".to_string()))?;
+    let body = body.strip_suffix('}').unwrap_or(body);
+
+    let mut preamble_parts = preamble.split(';');
+    let version = preamble_parts
+        .next()
+        .and_then(|s| s.split_once('='))
+        .filter(|(key, _)| key.trim() == "vbf_version")
+        .map(|(_, value)| value.trim().to_string())
+        .ok_or_else(|| ParseError::InvalidVbf("missing vbf_version".to_string()))?;
+    if preamble_parts.next().map(str::trim) != Some("header") {
+        return Err(ParseError::InvalidVbf("missing header block".to_string()));
+    }
+
+    let mut header = VbfHeader {
+        version,
+        ..Default::default()
+    };
+    for statement in split_statements(body) {
+        let Some((key, value)) = statement.split_once('=') else {
+            return Err(ParseError::InvalidVbf(format!(
+                "malformed header entry '{statement}'"
+            )));
+        };
+        let key = key.trim();
+        let value = unquote(value.trim());
+        match key {
+            "sw_part_number" => header.sw_part_number = Some(value.clone()),
+            "sw_version" => header.sw_version = Some(value.clone()),
+            "sw_part_type" => header.sw_part_type = Some(value.clone()),
+            "network" => header.network = Some(value.clone()),
+            "frame_format" => header.frame_format = Some(value.clone()),
+            "ecu_address" => header.ecu_address = Some(parse_header_number(key, &value)?),
+            "call" => header.call = Some(parse_header_number(key, &value)?),
+            "file_checksum" => header.file_checksum = Some(parse_header_number(key, &value)?),
+            "erase" => header.erase = parse_erase(&value)?,
+            _ => {}
+        }
+        header.entries.push((key.to_string(), value));
+    }
+    Ok(header)
+}
+
+/// Split on `;` outside strings and braces.
+fn split_statements(body: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut start = 0;
+    for (i, ch) in body.char_indices() {
+        match ch {
+            '"' => in_string = !in_string,
+            '{' if !in_string => depth += 1,
+            '}' if !in_string => depth = depth.saturating_sub(1),
+            ';' if !in_string && depth == 0 => {
+                out.push(body[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    out.push(body[start..].trim());
+    out.retain(|s| !s.is_empty());
+    out
+}
+
+fn unquote(value: &str) -> String {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+        .to_string()
+}
+
+fn parse_header_number(key: &str, value: &str) -> Result<u32, ParseError> {
+    let parsed = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => value.parse::<u32>(),
+    };
+    parsed.map_err(|_| ParseError::InvalidVbf(format!("invalid {key} value '{value}'")))
+}
+
+fn parse_erase(value: &str) -> Result<Vec<(u32, u32)>, ParseError> {
+    let numbers = value
+        .split(|c: char| c == ',' || c == '{' || c == '}' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| parse_header_number("erase", s))
+        .collect::<Result<Vec<_>, _>>()?;
+    if numbers.len() % 2 != 0 {
+        return Err(ParseError::InvalidVbf(format!(
+            "erase entries must be start/length pairs: '{value}'"
+        )));
+    }
+    Ok(numbers.chunks(2).map(|pair| (pair[0], pair[1])).collect())
+}
+
+/// Write VBF 2.x output. CLI: /XVBF.
+///
+/// Each normalized segment becomes one data block: start address (u32 BE),
+/// length (u32 BE), data, CRC-16/CCITT-FALSE over the data (u16 BE).
+/// `file_checksum` is CRC-32 over all data blocks as written.
+pub fn write_vbf(hexfile: &HexFile, options: &VbfWriteOptions) -> Vec<u8> {
+    let segments = &*normalized_sorted_segments(hexfile);
+
+    let mut blocks = Vec::new();
+    for segment in segments {
+        blocks.extend_from_slice(&segment.start_address.to_be_bytes());
+        blocks.extend_from_slice(&(segment.len() as u32).to_be_bytes());
+        blocks.extend_from_slice(&segment.data);
+        blocks.extend_from_slice(&BLOCK_CRC.checksum(&segment.data).to_be_bytes());
+    }
+
+    let erase = options.erase.clone().unwrap_or_else(|| {
+        segments
+            .iter()
+            .map(|s| (s.start_address, s.len() as u32))
+            .collect()
+    });
+
+    let mut header = format!("vbf_version = {};\n\nheader {{\n", options.version);
+    header.push_str(&format!(
+        "\tsw_part_number = \"{}\";\n",
+        options.sw_part_number
+    ));
+    if !options.sw_version.is_empty() {
+        header.push_str(&format!("\tsw_version = \"{}\";\n", options.sw_version));
+    }
+    header.push_str(&format!("\tsw_part_type = {};\n", options.sw_part_type));
+    header.push_str(&format!("\tnetwork = {};\n", options.network));
+    header.push_str(&format!("\tecu_address = 0x{:X};\n", options.ecu_address));
+    header.push_str(&format!("\tframe_format = {};\n", options.frame_format));
+    let erase = erase
+        .iter()
+        .map(|(start, len)| format!("{{ 0x{start:08X}, 0x{len:08X} }}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    header.push_str(&format!("\terase = {{ {erase} }};\n"));
+    if let Some(call) = options.call {
+        header.push_str(&format!("\tcall = 0x{call:08X};\n"));
+    }
+    header.push_str(&format!(
+        "\tfile_checksum = 0x{:08X};\n}}",
+        FILE_CRC.checksum(&blocks)
+    ));
+
+    let mut out = header.into_bytes();
+    out.extend_from_slice(&blocks);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Segment;
+
+    fn reference_options() -> VbfWriteOptions {
+        VbfWriteOptions {
+            sw_part_number: "31400123".to_string(),
+            sw_version: "AA".to_string(),
+            ecu_address: 0x7A1,
+            call: Some(0x1000),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_write_vbf_matches_reference() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x2000, vec![0xAA, 0xBB]),
+            Segment::new(0x1000, b"123456789".to_vec()),
+        ]);
+
+        let mut expected = b"vbf_version = 2.2;\n\nheader {\n\
+            \tsw_part_number = \"31400123\";\n\
+            \tsw_version = \"AA\";\n\
+            \tsw_part_type = EXE;\n\
+            \tnetwork = CAN_HS;\n\
+            \tecu_address = 0x7A1;\n\
+            \tframe_format = CAN_STANDARD;\n\
+            \terase = { { 0x00001000, 0x00000009 }, { 0x00002000, 0x00000002 } };\n\
+            \tcall = 0x00001000;\n\
+            \tfile_checksum = 0x8DE712B6;\n}"
+            .to_vec();
+        expected.extend_from_slice(&[0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x09]);
+        expected.extend_from_slice(b"123456789");
+        expected.extend_from_slice(&[0x29, 0xB1]);
+        expected.extend_from_slice(&[0x00, 0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x02]);
+        expected.extend_from_slice(&[0xAA, 0xBB, 0xF9, 0x0A]);
+
+        assert_eq!(write_vbf(&hexfile, &reference_options()), expected);
+    }
+
+    #[test]
+    fn test_write_vbf_explicit_erase_and_optional_fields() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01])]);
+        let options = VbfWriteOptions {
+            sw_version: String::new(),
+            call: None,
+            erase: Some(vec![(0x0, 0x10000)]),
+            ..reference_options()
+        };
+        let out = write_vbf(&hexfile, &options);
+        let text = String::from_utf8_lossy(&out);
+        assert!(text.contains("\terase = { { 0x00000000, 0x00010000 } };\n"));
+        assert!(!text.contains("sw_version"));
+        assert!(!text.contains("call"));
+    }
+
+    fn reference_vbf() -> Vec<u8> {
+        // Hand-written header: comments, CRLF, extra entries and string braces.
+        let mut data = b"vbf_version = 2.3;\r\n\r\nheader {\r\n\
+            \t// Volvo software part\r\n\
+            \tdescription = { \"App {main}\", \"second; line\" };\r\n\
+            \tsw_part_number = \"31400123\";\r\n\
+            \tsw_part_type = SBL; /* secondary bootloader */\r\n\
+            \tnetwork = CAN_MS;\r\n\
+            \tecu_address = 0x7A1;\r\n\
+            \tframe_format = CAN_EXTENDED;\r\n\
+            \terase = { { 0x00001000, 0x00000100 },\r\n\t          { 0x00002000, 0x100 } };\r\n\
+            \tcall = 0x00001000;\r\n\
+            \tfile_checksum = 0x8DE712B6;\r\n}"
+            .to_vec();
+        data.extend_from_slice(&[0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x09]);
+        data.extend_from_slice(b"123456789");
+        data.extend_from_slice(&[0x29, 0xB1]);
+        data.extend_from_slice(&[0x00, 0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x02]);
+        data.extend_from_slice(&[0xAA, 0xBB, 0xF9, 0x0A]);
+        data
+    }
+
+    #[test]
+    fn test_parse_vbf_reference() {
+        let (hexfile, header) = parse_vbf_with_header(&reference_vbf()).unwrap();
+        assert_eq!(
+            hexfile.segments(),
+            &[
+                Segment::new(0x1000, b"123456789".to_vec()),
+                Segment::new(0x2000, vec![0xAA, 0xBB]),
+            ]
+        );
+        assert_eq!(header.version, "2.3");
+        assert_eq!(header.sw_part_number.as_deref(), Some("31400123"));
+        assert_eq!(header.sw_part_type.as_deref(), Some("SBL"));
+        assert_eq!(header.network.as_deref(), Some("CAN_MS"));
+        assert_eq!(header.ecu_address, Some(0x7A1));
+        assert_eq!(header.erase, vec![(0x1000, 0x100), (0x2000, 0x100)]);
+        assert_eq!(header.call, Some(0x1000));
+        assert_eq!(header.entries[0].0, "description");
+    }
+
+    #[test]
+    fn test_vbf_round_trip_through_header() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x1000, b"123456789".to_vec()),
+            Segment::new(0x2000, vec![0xAA, 0xBB]),
+        ]);
+        let written = write_vbf(&hexfile, &reference_options());
+        let (parsed, header) = parse_vbf_with_header(&written).unwrap();
+        assert_eq!(parsed.segments(), hexfile.segments());
+        assert_eq!(
+            header.to_write_options().erase,
+            Some(vec![(0x1000, 9), (0x2000, 2)])
+        );
+        assert_eq!(write_vbf(&parsed, &header.to_write_options()), written);
+    }
+
+    #[test]
+    fn test_parse_vbf_rejects_bad_block_crc() {
+        let mut data = reference_vbf();
+        let last = data.len() - 1;
+        data[last] ^= 0xFF;
+        let err = parse_vbf(&data).unwrap_err();
+        assert!(err.to_string().contains("block at 0x2000"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_vbf_rejects_bad_file_checksum() {
+        let mut data = reference_vbf();
+        let pos = data.windows(10).position(|w| w == b"0x8DE712B6").unwrap();
+        data[pos + 9] = b'7';
+        let err = parse_vbf(&data).unwrap_err();
+        assert!(err.to_string().contains("file_checksum"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_vbf_truncated_block() {
+        let mut data = reference_vbf();
+        data.truncate(data.len() - 3);
+        assert!(matches!(parse_vbf(&data), Err(ParseError::UnexpectedEof)));
+    }
+}
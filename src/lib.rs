@@ -7,27 +7,51 @@ pub mod range;
 pub mod segment;
 
 pub use error::Error;
-pub use hexfile::{HexFile, HexFileError};
+pub use hexfile::{HexFile, HexFileError, HexFileMetadata, SourceFormat};
 pub use io::{
-    BinaryWriteOptions, CCodeOutput, CCodeWordType, CCodeWriteOptions, HexAsciiWriteOptions,
-    SRecordType, SRecordWriteOptions, parse_binary, parse_hex_ascii, parse_srec, write_binary,
-    write_c_code, write_hex_ascii, write_srec,
+    AddressPrefixStyle, BinaryWriteOptions, CCodeOutput, CCodeWordType, CCodeWriteOptions,
+    HexAsciiParseOptions, HexAsciiWriteOptions, SRecordType, SRecordWriteOptions, VagWriteOptions,
+    VbfHeader, VbfWriteOptions, parse_binary, parse_binary_mmap, parse_hex_ascii,
+    parse_hex_ascii_with_options, parse_srec, parse_srec_with, parse_vbf, parse_vbf_with_header,
+    write_binary, write_binary_to, write_c_code, write_hex_ascii, write_srec, write_srec_filled_to,
+    write_srec_to, write_vag, write_vbf,
 };
 pub use io::{
-    IntelHexMode, IntelHexWriteOptions, ParseError, parse_intel_hex, parse_intel_hex_16bit,
-    write_intel_hex,
+    ElfParseOptions, FiatBinWriteOptions, FlashKernelWriteOptions, GacWriteOptions, GmHeaderKind,
+    GmHeaderOptions, HexDumpOptions, IntelHexMode, IntelHexWriteOptions, LineEnding, ParseError,
+    ParseOptions, ParseWarning, RustCodeWriteOptions, add_gm_header, parse_elf,
+    parse_elf_with_options, parse_intel_hex, parse_intel_hex_16bit, parse_intel_hex_reader,
+    parse_intel_hex_with, write_fiat_bin, write_flash_kernel, write_gac, write_gm_header,
+    write_hex_dump, write_intel_hex, write_intel_hex_16bit, write_intel_hex_filled_to,
+    write_intel_hex_to, write_rust_code,
 };
+pub use io::{
+    FileNameTemplate, FileNameTemplateError, SeparateBinaryOptions, separate_binary_names,
+    write_separate_binary,
+};
+#[cfg(feature = "serde")]
+pub use io::{JsonDataEncoding, JsonWriteOptions, parse_json, write_json};
 pub use ops::{
-    AlignOptions, BankedMapOptions, ChecksumAlgorithm, ChecksumJob, ChecksumOptions,
-    ChecksumTarget, FillOptions, ForcedRange, LogCommand, LogCommandKind, LogError, MergeMode,
-    MergeOptions, OpsError, Pipeline, PipelineChecksum, PipelineDspic, PipelineError,
-    PipelineMerge, PipelineResult, RemapOptions, SwapMode, execute_log_commands, execute_log_file,
-    flag_align, flag_checksum, flag_cut_ranges, flag_dspic_clear_ghost, flag_dspic_expand,
-    flag_dspic_shrink, flag_execute_log_file, flag_fill_all, flag_fill_ranges_pattern,
-    flag_fill_ranges_random, flag_filter_ranges, flag_map_star08, flag_map_star12,
-    flag_map_star12x, flag_merge_opaque, flag_merge_transparent, flag_remap, flag_split,
-    flag_swap_long, flag_swap_word, parse_log_commands, random_fill_bytes,
-    random_fill_seed_from_time,
+    AddressMapping, AddressTable, AddressTableError, AlignOptions, AsciiPad, BankedMapOptions,
+    BigImageOptions, ChecksumAlgorithm, ChecksumGapFill, ChecksumJob, ChecksumOptions,
+    ChecksumTarget, CustomStep, DEFAULT_MAX_FILL_SPAN, DateEncoding, DateFormat, DateStamp,
+    FillOptions, FillPattern, ForcedRange, LogCommand, LogCommandKind, LogError, LogSaveFormat,
+    MergeMode, MergeOptions, OpsError, Pipeline, PipelineBuilder, PipelineChecksum, PipelineDspic,
+    PipelineError, PipelineGhost, PipelineLabel, PipelineMerge, PipelineRecord, PipelineResult,
+    PipelineStamp, PipelineStep, RemapOptions, Sector, SectorMap, SectorMapError, SegmentInfo,
+    StageTrace, SwapMode, execute_log_commands, execute_log_file, flag_align, flag_checksum,
+    flag_checksum_blocks, flag_cut_ranges, flag_dspic_clear_ghost, flag_dspic_expand,
+    flag_dspic_insert_ghost, flag_dspic_shrink, flag_execute_log_file, flag_fill_all,
+    flag_fill_all_chunked, flag_fill_ranges_pattern, flag_fill_ranges_random, flag_filter_ranges,
+    flag_map_star08, flag_map_star12, flag_map_star12x, flag_merge_opaque, flag_merge_transparent,
+    flag_remap, flag_remap_table, flag_split, flag_split_at_boundaries, flag_swap_long,
+    flag_swap_word, parse_log_commands, random_fill_bytes, random_fill_seed,
+    random_fill_seed_from_time, seeded_random_fill, write_log_commands,
+};
+#[cfg(feature = "serde")]
+pub use ops::{DescriptionError, PageWindow, PipelineDescription, StepDescription};
+pub use range::{
+    Range, RangeError, RangeSet, RangeSpec, parse_hexview_range_specs, parse_hexview_ranges,
+    parse_ranges, resolve_ranges,
 };
-pub use range::{Range, RangeError, parse_hexview_ranges, parse_ranges};
-pub use segment::Segment;
+pub use segment::{Segment, SegmentData};
@@ -0,0 +1,324 @@
+//! Address translation tables (CLI: /MAPTAB).
+//!
+//! A table file has one mapping per line, `src_start-src_end -> dst_start`
+//! (or `src_start,length -> dst_start`). `#` and `;` start a comment. Source
+//! ranges may not overlap, and neither may the destination ranges.
+
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use super::OpsError;
+use crate::range::parse_number;
+use crate::{HexFile, Range, Segment};
+
+/// Data in `source` moves to start at `dest`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressMapping {
+    /// 1-based line in the table file, used in error messages.
+    pub line: usize,
+    pub source: Range,
+    pub dest: u32,
+}
+
+impl AddressMapping {
+    /// Where `source` lands; `None` if it runs past 0xFFFFFFFF.
+    pub fn dest_range(&self) -> Option<Range> {
+        Range::from_start_length(self.dest, self.source.length()).ok()
+    }
+}
+
+/// Validated mappings for [`HexFile::remap_table`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AddressTable {
+    mappings: Vec<AddressMapping>,
+}
+
+#[derive(Debug, Error)]
+pub enum AddressTableError {
+    #[error("line {line}: {message}")]
+    Syntax { line: usize, message: String },
+
+    #[error("line {line}: destination of {range} runs past 0xFFFFFFFF")]
+    DestinationOverflow { line: usize, range: Range },
+
+    #[error("line {line}: source {range} overlaps line {other}")]
+    SourceOverlap {
+        line: usize,
+        other: usize,
+        range: Range,
+    },
+
+    #[error("line {line}: destination {range} overlaps line {other}")]
+    DestinationCollision {
+        line: usize,
+        other: usize,
+        range: Range,
+    },
+}
+
+impl AddressTable {
+    /// Checks that no two sources and no two destinations overlap.
+    pub fn new(mappings: Vec<AddressMapping>) -> Result<Self, AddressTableError> {
+        let mut dests = Vec::with_capacity(mappings.len());
+        for mapping in &mappings {
+            let range = mapping
+                .dest_range()
+                .ok_or(AddressTableError::DestinationOverflow {
+                    line: mapping.line,
+                    range: mapping.source,
+                })?;
+            dests.push((range, mapping.line));
+        }
+        let sources = mappings.iter().map(|m| (m.source, m.line)).collect();
+        if let Some((line, other, range)) = first_overlap(sources) {
+            return Err(AddressTableError::SourceOverlap { line, other, range });
+        }
+        if let Some((line, other, range)) = first_overlap(dests) {
+            return Err(AddressTableError::DestinationCollision { line, other, range });
+        }
+        Ok(Self { mappings })
+    }
+
+    pub fn mappings(&self) -> &[AddressMapping] {
+        &self.mappings
+    }
+
+    /// The table mapping each destination back to its source.
+    pub fn inverse(&self) -> Self {
+        let mappings = self
+            .mappings
+            .iter()
+            .map(|m| AddressMapping {
+                line: m.line,
+                source: m.dest_range().expect("checked by AddressTable::new"),
+                dest: m.source.start(),
+            })
+            .collect();
+        Self { mappings }
+    }
+}
+
+/// The later line of the first overlapping pair, the line it overlaps, and
+/// its range.
+fn first_overlap(mut ranges: Vec<(Range, usize)>) -> Option<(usize, usize, Range)> {
+    ranges.sort_by_key(|(range, line)| (range.start(), *line));
+    ranges.windows(2).find_map(|pair| {
+        let ((first, first_line), (second, second_line)) = (pair[0], pair[1]);
+        if !first.overlaps(&second) {
+            return None;
+        }
+        Some(if first_line < second_line {
+            (second_line, first_line, second)
+        } else {
+            (first_line, second_line, first)
+        })
+    })
+}
+
+/// The bytes of `segment` from `start` to `end` inclusive.
+pub(super) fn slice(segment: &Segment, start: u32, end: u32) -> Segment {
+    if start == segment.start_address && end == segment.end_address() {
+        return segment.clone();
+    }
+    let offset = (start - segment.start_address) as usize;
+    let len = (end - start) as usize + 1;
+    segment.piece(start, segment.data[offset..offset + len].to_vec())
+}
+
+impl FromStr for AddressTable {
+    type Err = AddressTableError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut mappings = Vec::new();
+        for (index, raw_line) in s.lines().enumerate() {
+            let line = index + 1;
+            let text = raw_line.split(['#', ';']).next().unwrap_or_default().trim();
+            if text.is_empty() {
+                continue;
+            }
+            let syntax = |message: String| AddressTableError::Syntax { line, message };
+            let (source, dest) = text
+                .split_once("->")
+                .ok_or_else(|| syntax(format!("expected 'start-end -> dest', got '{text}'")))?;
+            mappings.push(AddressMapping {
+                line,
+                source: source.trim().parse().map_err(|e| syntax(format!("{e}")))?,
+                dest: parse_number(dest).map_err(|e| syntax(format!("{e}")))?,
+            });
+        }
+        Self::new(mappings)
+    }
+}
+
+/// One `0x1000-0x1FFF -> 0x8000` line per mapping, which parses back to the
+/// same mappings (line numbers aside).
+impl fmt::Display for AddressTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for mapping in &self.mappings {
+            writeln!(f, "{} -> 0x{:X}", mapping.source, mapping.dest)?;
+        }
+        Ok(())
+    }
+}
+
+impl HexFile {
+    /// A copy with the data in each source range of `table` moved to its
+    /// destination; data outside every source stays put. Errors if moved
+    /// data lands on data that stays.
+    pub fn remap_table(&self, table: &AddressTable) -> Result<HexFile, OpsError> {
+        let mut order: Vec<usize> = (0..table.mappings.len()).collect();
+        order.sort_by_key(|&i| table.mappings[i].source.start());
+
+        let mut kept = Vec::new();
+        let mut moved = Vec::new();
+        for segment in self.segments() {
+            let seg_start = segment.start_address;
+            let seg_end = segment.end_address();
+            let mut cursor = Some(seg_start);
+            let first = order.partition_point(|&i| table.mappings[i].source.end() < seg_start);
+            for &i in &order[first..] {
+                let mapping = &table.mappings[i];
+                let Some(from) = cursor else { break };
+                if mapping.source.start() > seg_end {
+                    break;
+                }
+                let start = mapping.source.start().max(seg_start);
+                let end = mapping.source.end().min(seg_end);
+                if from < start {
+                    kept.push(slice(segment, from, start - 1));
+                }
+                let dest = mapping.dest + (start - mapping.source.start());
+                let mut piece = slice(segment, start, end);
+                piece.start_address = dest;
+                moved.push((mapping.line, piece));
+                cursor = end.checked_add(1).filter(|&next| next <= seg_end);
+            }
+            if let Some(from) = cursor {
+                kept.push(slice(segment, from, seg_end));
+            }
+        }
+
+        kept.sort_by_key(|s| s.start_address);
+        for (line, segment) in &moved {
+            let first = kept.partition_point(|s| s.end_address() < segment.start_address);
+            if let Some(other) = kept.get(first)
+                && other.start_address <= segment.end_address()
+            {
+                return Err(OpsError::AddressCollision(format!(
+                    "address table line {line}: {:#X} already holds data",
+                    other.start_address.max(segment.start_address)
+                )));
+            }
+        }
+
+        let mut segments = kept;
+        segments.extend(moved.into_iter().map(|(_, segment)| segment));
+        segments.sort_by_key(|s| s.start_address);
+        let mut result = HexFile::with_segments(segments);
+        result.set_metadata(self.metadata().clone());
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(text: &str) -> AddressTable {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_skips_comments_and_keeps_lines() {
+        let table = table("# pages\n\n0x10000-0x10FFF -> 0x1000 ; page 1\n0x20000,4K->0x2000\n");
+        assert_eq!(
+            table.mappings(),
+            [
+                AddressMapping {
+                    line: 3,
+                    source: Range::from_start_end(0x10000, 0x10FFF).unwrap(),
+                    dest: 0x1000,
+                },
+                AddressMapping {
+                    line: 4,
+                    source: Range::from_start_length(0x20000, 0x1000).unwrap(),
+                    dest: 0x2000,
+                },
+            ]
+        );
+        assert_eq!(
+            table.to_string(),
+            "0x10000-0x10FFF -> 0x1000\n0x20000-0x20FFF -> 0x2000\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_reports_line_numbers() {
+        let err = "0x0-0xF -> 0x100\n0x10-0x1F 0x200\n"
+            .parse::<AddressTable>()
+            .unwrap_err();
+        assert!(
+            matches!(err, AddressTableError::Syntax { line: 2, .. }),
+            "{err}"
+        );
+
+        let err = "0x0-0xF -> 0x100\n\n0x8-0x17 -> 0x200\n"
+            .parse::<AddressTable>()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "line 3: source 0x8-0x17 overlaps line 1");
+
+        let err = "0x0-0xF -> 0x100\n0x10-0x1F -> 0x108\n"
+            .parse::<AddressTable>()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2: destination 0x108-0x117 overlaps line 1"
+        );
+
+        let err = "0x0-0xF -> 0xFFFFFFF8".parse::<AddressTable>().unwrap_err();
+        assert!(matches!(
+            err,
+            AddressTableError::DestinationOverflow { line: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_remap_table_splits_segments_at_sources() {
+        let hf = HexFile::with_segments(vec![Segment::new(0x0FFE, vec![1, 2, 3, 4, 5, 6])]);
+        let mapped = hf
+            .remap_table(&table("0x1000-0x1001 -> 0x8000\n0x1003-0x1003 -> 0x9000"))
+            .unwrap();
+
+        let segments: Vec<_> = mapped
+            .segments()
+            .iter()
+            .map(|s| (s.start_address, s.data.to_vec()))
+            .collect();
+        assert_eq!(
+            segments,
+            [
+                (0x0FFE, vec![1, 2]),
+                (0x1002, vec![5]),
+                (0x8000, vec![3, 4]),
+                (0x9000, vec![6]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remap_table_rejects_landing_on_kept_data() {
+        let hf = HexFile::with_segments(vec![
+            Segment::new(0x100, vec![0xAA; 4]),
+            Segment::new(0x2000, vec![0xBB; 4]),
+        ]);
+        let err = hf
+            .remap_table(&table("\n0x2000-0x2003 -> 0x102"))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "address collision: address table line 2: 0x102 already holds data"
+        );
+    }
+}
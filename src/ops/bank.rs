@@ -0,0 +1,197 @@
+//! Bank-switched images for paged microcontrollers (CLI: /BANK, /UNBANK).
+//!
+//! A banked address is `bank << 16 | cpu_address`. Linear data from
+//! `window.start()` upwards is cut into `bank_size` pieces, the first going
+//! to `first_bank`; each lands at the start of the CPU window in its bank.
+//! Data below the window is common to every bank and keeps its address.
+
+use super::OpsError;
+use super::address_table::slice;
+use crate::{HexFile, Range, Segment};
+
+fn check_bank_window(window: Range, bank_size: u32) -> Result<(), OpsError> {
+    if window.end() > 0xFFFF || bank_size == 0 || bank_size > window.length() {
+        return Err(OpsError::InvalidRemapParams(format!(
+            "bank window must lie within 64 KiB and hold a whole bank (window={window}, bank_size={bank_size:#X})"
+        )));
+    }
+    Ok(())
+}
+
+impl HexFile {
+    /// A copy with the data from `window.start()` upwards moved into banks
+    /// of `bank_size` bytes, numbered from `first_bank`. Errors if a bank
+    /// number would pass 0xFFFF.
+    pub fn to_banked(
+        &self,
+        window: Range,
+        bank_size: u32,
+        first_bank: u16,
+    ) -> Result<HexFile, OpsError> {
+        check_bank_window(window, bank_size)?;
+        let mut segments = Vec::new();
+        for segment in self.normalized_segments().iter() {
+            let end = segment.end_address();
+            let mut addr = segment.start_address;
+            loop {
+                let (piece_end, dest) = if addr < window.start() {
+                    (end.min(window.start() - 1), addr)
+                } else {
+                    let offset = addr - window.start();
+                    let bank = u16::try_from(u32::from(first_bank) + offset / bank_size).map_err(
+                        |_| {
+                            OpsError::AddressOverflow(format!(
+                                "{addr:#X} needs a bank number past 0xFFFF"
+                            ))
+                        },
+                    )?;
+                    let in_bank = offset % bank_size;
+                    (
+                        end.min(addr.saturating_add(bank_size - 1 - in_bank)),
+                        u32::from(bank) << 16 | (window.start() + in_bank),
+                    )
+                };
+                let mut piece = slice(segment, addr, piece_end);
+                piece.start_address = dest;
+                segments.push(piece);
+                if piece_end == end {
+                    break;
+                }
+                addr = piece_end + 1;
+            }
+        }
+        Ok(self.with_moved_segments(segments))
+    }
+
+    /// The inverse of [`HexFile::to_banked`] with the same parameters.
+    /// Errors on data outside both the common area and the bank windows.
+    pub fn to_linear(
+        &self,
+        window: Range,
+        bank_size: u32,
+        first_bank: u16,
+    ) -> Result<HexFile, OpsError> {
+        check_bank_window(window, bank_size)?;
+        let bank_end = window.start() + (bank_size - 1);
+        let mut segments = Vec::new();
+        for segment in self.normalized_segments().iter() {
+            let end = segment.end_address();
+            let mut addr = segment.start_address;
+            loop {
+                let bank = addr >> 16;
+                let cpu = addr & 0xFFFF;
+                let (piece_end, dest) = if bank == 0 && cpu < window.start() {
+                    (end.min(window.start() - 1), Some(addr))
+                } else if bank >= u32::from(first_bank)
+                    && (window.start()..=bank_end).contains(&cpu)
+                {
+                    let index = u64::from(bank - u32::from(first_bank));
+                    let linear = u64::from(window.start())
+                        + index * u64::from(bank_size)
+                        + u64::from(cpu - window.start());
+                    (end.min(addr - cpu + bank_end), u32::try_from(linear).ok())
+                } else {
+                    return Err(OpsError::OutsideBankWindow { address: addr });
+                };
+                let dest = dest.ok_or_else(|| {
+                    OpsError::AddressOverflow(format!("bank {bank} runs past 0xFFFFFFFF"))
+                })?;
+                let mut piece = slice(segment, addr, piece_end);
+                piece.start_address = dest;
+                segments.push(piece);
+                if piece_end == end {
+                    break;
+                }
+                addr = piece_end + 1;
+            }
+        }
+        Ok(self.with_moved_segments(segments))
+    }
+
+    fn with_moved_segments(&self, mut segments: Vec<Segment>) -> HexFile {
+        segments.sort_by_key(|s| s.start_address);
+        let mut result = HexFile::with_segments(segments);
+        result.set_metadata(self.metadata().clone());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window() -> Range {
+        Range::from_start_end(0x8000, 0xFFFF).unwrap()
+    }
+
+    fn layout(hf: &HexFile) -> Vec<(u32, usize)> {
+        hf.segments()
+            .iter()
+            .map(|s| (s.start_address, s.len()))
+            .collect()
+    }
+
+    #[test]
+    fn test_to_banked_splits_at_bank_boundaries() {
+        let hf = HexFile::with_segments(vec![
+            Segment::new(0x7FFE, vec![0xAA; 4]),
+            Segment::new(0xFFFE, vec![0xBB; 4]),
+            Segment::new(0x18000, vec![0xCC; 2]),
+        ]);
+        let banked = hf.to_banked(window(), 0x8000, 0).unwrap();
+        assert_eq!(
+            layout(&banked),
+            [
+                (0x7FFE, 2),
+                (0x8000, 2),
+                (0xFFFE, 2),
+                (0x1_8000, 2),
+                (0x2_8000, 2),
+            ]
+        );
+        assert_eq!(
+            banked.read_bytes_contiguous(0x1_8000, 2),
+            Some(vec![0xBB; 2])
+        );
+        assert_eq!(
+            banked.read_bytes_contiguous(0x2_8000, 2),
+            Some(vec![0xCC; 2])
+        );
+
+        let linear = banked.to_linear(window(), 0x8000, 0).unwrap();
+        assert_eq!(linear.normalized_lossy(), hf.normalized_lossy());
+    }
+
+    #[test]
+    fn test_to_banked_first_bank_and_short_banks() {
+        let hf = HexFile::with_segments(vec![Segment::new(0x8000, (0..=0xFF).collect())]);
+        let banked = hf
+            .to_banked(Range::from_start_end(0x8000, 0xBFFF).unwrap(), 0x80, 4)
+            .unwrap();
+        assert_eq!(layout(&banked), [(0x4_8000, 0x80), (0x5_8000, 0x80)]);
+        assert_eq!(banked.read_byte(0x5_8000), Some(0x80));
+    }
+
+    #[test]
+    fn test_to_linear_rejects_data_outside_the_window() {
+        let hf = HexFile::with_segments(vec![Segment::new(0x1_0010, vec![0x01])]);
+        let err = hf.to_linear(window(), 0x8000, 0).unwrap_err();
+        assert!(matches!(
+            err,
+            OpsError::OutsideBankWindow { address: 0x1_0010 }
+        ));
+
+        let hf = HexFile::with_segments(vec![Segment::new(0x8000, vec![0x01])]);
+        assert!(hf.to_linear(window(), 0x8000, 1).is_err());
+    }
+
+    #[test]
+    fn test_to_banked_rejects_window_past_64k() {
+        let hf = HexFile::with_segments(vec![Segment::new(0x8000, vec![0x01])]);
+        let window = Range::from_start_length(0xC000, 0x8000).unwrap();
+        assert!(matches!(
+            hf.to_banked(window, 0x8000, 0),
+            Err(OpsError::InvalidRemapParams(_))
+        ));
+    }
+}
@@ -29,10 +29,12 @@ use ripemd::Ripemd160;
 use sha1::Sha1;
 use sha2::{Digest, Sha256, Sha512};
 
-use crate::{HexFile, OpsError, Range, Segment};
+use crate::{HexFile, OpsError, Range, RangeSet, Segment};
 
 /// Target for checksum output.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum ChecksumTarget {
     /// Write to address in hex file
     Address(u32),
@@ -40,6 +42,8 @@ pub enum ChecksumTarget {
     Append,
     /// Prepend before first data
     Prepend,
+    /// Write at start, overwriting existing data
+    Begin,
     /// Write at end, overwriting existing data
     OverwriteEnd,
     /// Write to external file
@@ -143,6 +147,52 @@ pub struct ChecksumOptions {
     /// When set, this address range is excluded from the checksum calculation.
     /// Used internally when the checksum target is an address within the data.
     pub target_exclude: Option<Range>,
+    /// Feed the excluded target bytes to the algorithm as the gap fill byte (0xFF
+    /// unless `gap_fill` says otherwise) instead of skipping them, so the result
+    /// keeps the full range length but ignores stale data.
+    pub target_as_fill: bool,
+    /// How addresses in the checksum range without data are treated.
+    pub gap_fill: ChecksumGapFill,
+    /// Seed for sum accumulators, or a previous CRC result to continue from so
+    /// that chaining blocks equals one CRC over their concatenation.
+    /// Not accepted by the hash algorithms (10, 11, 15, 19, 20).
+    pub initial: Option<u64>,
+}
+
+/// Handling of gaps inside the checksum range.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ChecksumGapFill {
+    /// Skip gaps; only bytes backed by data are checksummed (0xFF inside forced ranges).
+    #[default]
+    Skip,
+    /// Fail with the first uncovered address run.
+    Error,
+    /// Checksum gaps as this byte value.
+    Fill(u8),
+}
+
+impl ChecksumGapFill {
+    fn fill_byte(self) -> u8 {
+        match self {
+            Self::Fill(byte) => byte,
+            Self::Skip | Self::Error => 0xFF,
+        }
+    }
+}
+
+/// One row of [`HexFile::segment_report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentInfo {
+    pub start_address: u32,
+    /// Last address of the segment (inclusive).
+    pub end_address: u32,
+    pub length: usize,
+    /// CRC-32 of the segment data, as `ChecksumAlgorithm::Crc32` (/CS9) computes it.
+    pub crc32: u32,
+    /// The segment's label, see [`crate::HexFile::label_range`].
+    pub name: Option<String>,
 }
 
 /// One checksum operation in a sequential checksum chain.
@@ -161,11 +211,29 @@ impl Default for ChecksumOptions {
             forced_range: None,
             exclude_ranges: Vec::new(),
             target_exclude: None,
+            target_as_fill: false,
+            gap_fill: ChecksumGapFill::Skip,
+            initial: None,
         }
     }
 }
 
 impl HexFile {
+    /// Address range, length and CRC-32 of every normalized (last-wins)
+    /// segment, in address order. CLI: /XCSV.
+    pub fn segment_report(&self) -> Vec<SegmentInfo> {
+        self.normalized_segments()
+            .iter()
+            .map(|segment| SegmentInfo {
+                start_address: segment.start_address,
+                end_address: segment.end_address(),
+                length: segment.len(),
+                crc32: crc32_iso_hdlc(&segment.data, None),
+                name: segment.name.clone(),
+            })
+            .collect()
+    }
+
     /// Calculate checksum over the hex file data.
     /// Returns the checksum bytes in the specified endianness.
     /// Uses a normalized (last-wins) snapshot for overlap resolution.
@@ -177,8 +245,6 @@ impl HexFile {
     /// Example: /CS1 = ByteSumLe (native LE), little_endian_output=false -> LE
     ///          /CSR1 = ByteSumLe (native LE), little_endian_output=true -> BE
     pub fn calculate_checksum(&self, options: &ChecksumOptions) -> Result<Vec<u8>, OpsError> {
-        let data = self.collect_data_for_checksum(options)?;
-
         // Effective endianness: algorithm's native XOR reversed flag
         // /CS uses algorithm's native format, /CSR inverts it
         let use_le = options.algorithm.native_little_endian() ^ options.little_endian_output;
@@ -206,89 +272,64 @@ impl HexFile {
             value
         }
 
-        let result = match options.algorithm {
-            ChecksumAlgorithm::ByteSumBe | ChecksumAlgorithm::ByteSumLe => {
-                let sum = byte_sum(&data);
-                u16_bytes(sum, use_le)
-            }
-            ChecksumAlgorithm::WordSumBe => {
-                let sum = word_sum_be(&data)?;
-                u16_bytes(sum, use_le)
-            }
-            ChecksumAlgorithm::WordSumLe => {
-                let sum = word_sum_le(&data)?;
-                u16_bytes(sum, use_le)
-            }
-            ChecksumAlgorithm::ByteSumTwosComplement => {
-                let sum = byte_sum(&data);
-                let twos = (!sum).wrapping_add(1);
-                u16_bytes(twos, use_le)
-            }
-            ChecksumAlgorithm::WordSumBeTwosComplement => {
-                let sum = word_sum_be(&data)?;
-                let twos = (!sum).wrapping_add(1);
-                u16_bytes(twos, use_le)
-            }
-            ChecksumAlgorithm::WordSumLeTwosComplement => {
-                let sum = word_sum_le(&data)?;
-                let twos = (!sum).wrapping_add(1);
-                u16_bytes(twos, use_le)
-            }
-            ChecksumAlgorithm::ModularSum => {
-                // HexView method 12: same arithmetic as method 6, but BE output by default.
-                let sum = word_sum_le(&data)?;
-                let twos = (!sum).wrapping_add(1);
-                u16_bytes(twos, use_le)
-            }
-            ChecksumAlgorithm::Crc16NonStandard => {
-                let crc = crc16_non_standard(&data);
-                u16_bytes(crc, use_le)
-            }
-            ChecksumAlgorithm::Sha1 => {
-                reverse_if_requested(Sha1::digest(&data).to_vec(), options.little_endian_output)
-            }
-            ChecksumAlgorithm::Ripemd160 => reverse_if_requested(
-                Ripemd160::digest(&data).to_vec(),
-                options.little_endian_output,
-            ),
-            ChecksumAlgorithm::Md5 => {
-                reverse_if_requested(Md5::digest(&data).to_vec(), options.little_endian_output)
-            }
-            ChecksumAlgorithm::Sha256 => {
-                reverse_if_requested(Sha256::digest(&data).to_vec(), options.little_endian_output)
-            }
-            ChecksumAlgorithm::Sha512AddressLength => {
-                let start = self
-                    .resolve_effective_checksum_range(options)?
-                    .map(|range| range.start())
-                    .unwrap_or_default();
-                let len = u32::try_from(data.len()).map_err(|_| {
-                    OpsError::AddressOverflow(format!(
-                        "checksum data length exceeds u32 for SHA-512 metadata: {}",
-                        data.len()
-                    ))
-                })?;
-                let mut hasher = Sha512::new();
-                hasher.update(start.to_be_bytes());
-                hasher.update(len.to_be_bytes());
-                hasher.update(&data);
-                reverse_if_requested(hasher.finalize().to_vec(), options.little_endian_output)
-            }
-            ChecksumAlgorithm::Crc16 => {
-                let crc = crc16_arc(&data);
-                u16_bytes(crc, use_le)
-            }
-            ChecksumAlgorithm::Crc32 => {
-                let crc = crc32_iso_hdlc(&data);
-                u32_bytes(crc, use_le)
-            }
-            ChecksumAlgorithm::Crc16CcittLe | ChecksumAlgorithm::Crc16CcittBe => {
-                let crc = crc16_ibm_sdlc(&data);
-                u16_bytes(crc, use_le)
+        let initial_max = match options.algorithm {
+            ChecksumAlgorithm::Sha1
+            | ChecksumAlgorithm::Ripemd160
+            | ChecksumAlgorithm::Md5
+            | ChecksumAlgorithm::Sha256
+            | ChecksumAlgorithm::Sha512AddressLength => None,
+            ChecksumAlgorithm::Crc32 => Some(u32::MAX as u64),
+            _ => Some(u16::MAX as u64),
+        };
+        if let Some(value) = options.initial
+            && initial_max.is_none_or(|max| value > max)
+        {
+            return Err(OpsError::InvalidChecksumInitial {
+                algorithm: options.algorithm as u8,
+                value,
+            });
+        }
+        let seed = options.initial.unwrap_or(0) as u16;
+
+        let mut state = ChecksumState::new(options.algorithm, options.initial);
+        if options.algorithm == ChecksumAlgorithm::Sha512AddressLength {
+            let start = self
+                .resolve_effective_checksum_range(options)?
+                .map(|range| range.start())
+                .unwrap_or_default();
+            let mut total: u64 = 0;
+            self.visit_checksum_input(options, &mut |input| total += input.len())?;
+            let len = u32::try_from(total).map_err(|_| {
+                OpsError::AddressOverflow(format!(
+                    "checksum data length exceeds u32 for SHA-512 metadata: {total}"
+                ))
+            })?;
+            state.update(&start.to_be_bytes());
+            state.update(&len.to_be_bytes());
+        }
+        self.visit_checksum_input(options, &mut |input| state.feed(input))?;
+
+        let result = match state.finish()? {
+            ChecksumValue::Sum(sum) => {
+                let sum = sum.wrapping_add(seed);
+                let twos_complement = matches!(
+                    options.algorithm,
+                    ChecksumAlgorithm::ByteSumTwosComplement
+                        | ChecksumAlgorithm::WordSumBeTwosComplement
+                        | ChecksumAlgorithm::WordSumLeTwosComplement
+                        // HexView method 12: same arithmetic as method 6, but BE output by default.
+                        | ChecksumAlgorithm::ModularSum
+                );
+                if twos_complement {
+                    u16_bytes((!sum).wrapping_add(1), use_le)
+                } else {
+                    u16_bytes(sum, use_le)
+                }
             }
-            ChecksumAlgorithm::Crc16CcittLeInit0 | ChecksumAlgorithm::Crc16CcittBeInit0 => {
-                let crc = crc16_xmodem(&data);
-                u16_bytes(crc, use_le)
+            ChecksumValue::Crc16(crc) => u16_bytes(crc, use_le),
+            ChecksumValue::Crc32(crc) => u32_bytes(crc, use_le),
+            ChecksumValue::Digest(digest) => {
+                reverse_if_requested(digest, options.little_endian_output)
             }
         };
 
@@ -311,6 +352,13 @@ impl HexFile {
                     effective_options.target_exclude = Some(target_range);
                 }
             }
+            ChecksumTarget::Begin => {
+                if let Some(start) = self.min_address()
+                    && let Ok(target_range) = Range::from_start_length(start, size)
+                {
+                    effective_options.target_exclude = Some(target_range);
+                }
+            }
             ChecksumTarget::OverwriteEnd => {
                 // Overwrite end writes at (max_address - size + 1)
                 if let Some(end) = self.max_address() {
@@ -330,18 +378,33 @@ impl HexFile {
             ChecksumTarget::Address(addr) => {
                 self.write_bytes(*addr, &result);
             }
+            ChecksumTarget::Begin => {
+                if let Some(start) = self.min_address() {
+                    self.write_bytes(start, &result);
+                }
+            }
             ChecksumTarget::Append => {
                 if let Some(end) = self.max_address() {
-                    let addr = end.checked_add(1).ok_or_else(|| {
-                        OpsError::AddressOverflow("checksum append overflows u32".into())
-                    })?;
+                    // The last result byte must fit too, not just the first.
+                    let addr = end
+                        .checked_add(1)
+                        .filter(|addr| addr.checked_add(result.len() as u32 - 1).is_some())
+                        .ok_or_else(|| {
+                            OpsError::AddressOverflow(format!(
+                                "checksum append of {} bytes after {end:#X} exceeds u32",
+                                result.len()
+                            ))
+                        })?;
                     self.write_bytes(addr, &result);
                 }
             }
             ChecksumTarget::Prepend => {
                 if let Some(start) = self.min_address() {
                     let new_start = start.checked_sub(result.len() as u32).ok_or_else(|| {
-                        OpsError::AddressOverflow("checksum prepend underflows u32".into())
+                        OpsError::AddressUnderflow(format!(
+                            "checksum prepend of {} bytes before {start:#X} goes below 0",
+                            result.len()
+                        ))
                     })?;
                     self.write_bytes(new_start, &result);
                 }
@@ -377,10 +440,77 @@ impl HexFile {
         Ok(out)
     }
 
-    /// Collect contiguous data for checksum calculation.
-    /// If a range is specified, only include data in that range.
-    fn collect_data_for_checksum(&self, options: &ChecksumOptions) -> Result<Vec<u8>, OpsError> {
-        let normalized = self.normalized_lossy();
+    /// Checksum every raw segment on its own (e.g. blocks produced by `split`).
+    ///
+    /// Append/Prepend extend the block's own segment and Begin/OverwriteEnd
+    /// replace its first/last bytes, so block boundaries are kept. Fails if a result would land on
+    /// another block. File targets only return the results; Address is rejected.
+    pub fn checksum_per_segment(
+        &mut self,
+        options: &ChecksumOptions,
+        target: &ChecksumTarget,
+    ) -> Result<Vec<Vec<u8>>, OpsError> {
+        if let ChecksumTarget::Address(addr) = target {
+            return Err(OpsError::BlockChecksum(format!(
+                "fixed address target {addr:#X} is not supported per block"
+            )));
+        }
+
+        let originals = self.segments().to_vec();
+        let mut updated = Vec::with_capacity(originals.len());
+        let mut results = Vec::with_capacity(originals.len());
+        for (index, segment) in originals.iter().enumerate() {
+            if segment.is_empty() {
+                updated.push(segment.clone());
+                continue;
+            }
+            let mut block = HexFile::with_segments(vec![segment.clone()]);
+            results.push(block.checksum(options, target)?);
+            // The result is written adjacent to or inside the block, so it stays contiguous.
+            let Some(block) = block.as_contiguous(0xFF) else {
+                updated.push(segment.clone());
+                continue;
+            };
+
+            let grown_before = segment
+                .start_address
+                .checked_sub(1)
+                .and_then(|end| Range::from_start_end(block.start_address, end).ok());
+            let grown_after = segment
+                .end_address()
+                .checked_add(1)
+                .and_then(|start| Range::from_start_end(start, block.end_address()).ok());
+            for grown in [grown_before, grown_after].into_iter().flatten() {
+                let collision = originals.iter().enumerate().find(|(i, other)| {
+                    *i != index
+                        && !other.is_empty()
+                        && other.start_address <= grown.end()
+                        && grown.start() <= other.end_address()
+                });
+                if let Some((_, other)) = collision {
+                    return Err(OpsError::BlockChecksum(format!(
+                        "checksum for block at {:#X} would overwrite block at {:#X}",
+                        segment.start_address, other.start_address
+                    )));
+                }
+            }
+            updated.push(block);
+        }
+
+        self.set_segments(updated);
+        Ok(results)
+    }
+
+    /// Feed the checksum input to `emit` in address order.
+    ///
+    /// Segment bytes are passed by reference and gaps as [`ChecksumInput::Repeat`],
+    /// so nothing proportional to the range is allocated.
+    fn visit_checksum_input(
+        &self,
+        options: &ChecksumOptions,
+        emit: &mut dyn FnMut(ChecksumInput<'_>),
+    ) -> Result<(), OpsError> {
+        let normalized = self.normalized_segments();
         let needs_word_alignment = matches!(
             options.algorithm,
             ChecksumAlgorithm::WordSumBe
@@ -389,78 +519,26 @@ impl HexFile {
                 | ChecksumAlgorithm::WordSumLeTwosComplement
         );
 
-        let working = if let Some(forced) = options.forced_range.as_ref() {
-            let mut combined = HexFile::new();
-            let fill = build_pattern_data(forced.range, &forced.pattern)?;
-            combined.append_segment(Segment::new(forced.range.start(), fill));
-            for segment in normalized.segments() {
-                combined.append_segment(segment.clone());
-            }
-            combined.normalized_lossy()
-        } else {
-            normalized
+        let target_fill = [options.gap_fill.fill_byte()];
+        let overlay = match options.target_exclude {
+            Some(target) if options.target_as_fill => Some(target),
+            _ => None,
         };
+        let spans = checksum_spans(&normalized, overlay, &target_fill);
 
-        let effective_range = self.resolve_effective_checksum_range(options)?;
-
-        let Some(range) = effective_range else {
-            return Ok(Vec::new());
+        let Some(range) = self.resolve_effective_checksum_range(options)? else {
+            return Ok(());
         };
 
-        let mut excludes = options.exclude_ranges.clone();
-        if let Some(target) = options.target_exclude {
-            excludes.push(target);
-        }
-        let excludes = merge_ranges(excludes);
-        let include_ranges = subtract_ranges(range, &excludes);
-        if include_ranges.is_empty() {
-            return Ok(Vec::new());
-        }
-
-        let mut cap_u64: u64 = 0;
-        let segments = working.segments();
-        let has_forced_range = options.forced_range.is_some();
-
-        if has_forced_range {
-            for r in &include_ranges {
-                cap_u64 = cap_u64.saturating_add(r.length() as u64);
-            }
-        } else {
-            let mut seg_idx = 0usize;
-            let mut inc_idx = 0usize;
-            while seg_idx < segments.len() && inc_idx < include_ranges.len() {
-                let seg = &segments[seg_idx];
-                let inc = include_ranges[inc_idx];
-                if seg.end_address() < inc.start() {
-                    seg_idx += 1;
-                    continue;
-                }
-                if seg.start_address > inc.end() {
-                    inc_idx += 1;
-                    continue;
-                }
-                let start = seg.start_address.max(inc.start());
-                let end = seg.end_address().min(inc.end());
-                cap_u64 = cap_u64.saturating_add((end - start + 1) as u64);
-                if seg.end_address() <= inc.end() {
-                    seg_idx += 1;
-                } else {
-                    inc_idx += 1;
-                }
-            }
+        let mut excludes = RangeSet::from_ranges(&options.exclude_ranges);
+        if let Some(target) = options.target_exclude
+            && !options.target_as_fill
+        {
+            excludes.insert(target);
         }
+        let include_ranges = excludes.gaps_within(range);
 
-        let cap = usize::try_from(cap_u64).map_err(|_| {
-            OpsError::AddressOverflow(format!(
-                "checksum range length exceeds usize (start={:#X}, end={:#X})",
-                range.start(),
-                range.end()
-            ))
-        })?;
-
-        let mut data = Vec::with_capacity(cap);
-
-        let finalize_run = |run_start: u32, run_len: usize| -> Result<(), OpsError> {
+        let finalize_run = |run_start: u32, run_len: u64| -> Result<(), OpsError> {
             if !needs_word_alignment {
                 return Ok(());
             }
@@ -472,7 +550,7 @@ impl HexFile {
             }
             if !run_len.is_multiple_of(2) {
                 return Err(OpsError::LengthNotMultiple {
-                    length: run_len,
+                    length: run_len as usize,
                     expected: 2,
                     operation: "checksum word range".to_string(),
                 });
@@ -480,53 +558,44 @@ impl HexFile {
             Ok(())
         };
 
-        if has_forced_range {
+        // Forced ranges and explicit gap handling walk every include address;
+        // otherwise only bytes backed by data are fed.
+        let dense = options.forced_range.is_some() || options.gap_fill != ChecksumGapFill::Skip;
+
+        if dense {
             for r in &include_ranges {
-                let run_len = usize::try_from(r.length()).map_err(|_| {
-                    OpsError::AddressOverflow(format!(
-                        "checksum range length exceeds usize (start={:#X}, end={:#X})",
-                        r.start(),
-                        r.end()
-                    ))
-                })?;
-                finalize_run(r.start(), run_len)?;
+                finalize_run(r.start(), r.length() as u64)?;
             }
 
-            let mut seg_idx = 0usize;
+            let mut span_idx = 0usize;
             for r in include_ranges {
                 let mut addr = r.start();
-                while seg_idx < segments.len() && segments[seg_idx].end_address() < addr {
-                    seg_idx += 1;
+                while span_idx < spans.len() && spans[span_idx].end < addr {
+                    span_idx += 1;
                 }
                 while addr <= r.end() {
-                    let Some(seg) = segments.get(seg_idx) else {
-                        let len = (r.end() - addr + 1) as usize;
-                        data.resize(data.len() + len, 0xFF);
+                    let Some(span) = spans.get(span_idx) else {
+                        emit_gap(options, addr, r.end(), emit)?;
                         break;
                     };
-                    if seg.start_address > r.end() {
-                        let len = (r.end() - addr + 1) as usize;
-                        data.resize(data.len() + len, 0xFF);
+                    if span.start > r.end() {
+                        emit_gap(options, addr, r.end(), emit)?;
                         break;
                     }
-                    if seg.start_address > addr {
-                        let gap_end = seg.start_address.saturating_sub(1).min(r.end());
-                        let len = (gap_end - addr + 1) as usize;
-                        data.resize(data.len() + len, 0xFF);
+                    if span.start > addr {
+                        let gap_end = (span.start - 1).min(r.end());
+                        emit_gap(options, addr, gap_end, emit)?;
                         addr = gap_end.saturating_add(1);
                         continue;
                     }
 
-                    let seg_start = addr.max(seg.start_address);
-                    let seg_end = seg.end_address().min(r.end());
-                    let offset = (seg_start - seg.start_address) as usize;
-                    let len = (seg_end - seg_start + 1) as usize;
-                    data.extend_from_slice(&seg.data[offset..offset + len]);
+                    let end = span.end.min(r.end());
+                    emit(span.slice(addr, end));
 
-                    if seg.end_address() <= seg_end {
-                        seg_idx += 1;
+                    if span.end <= end {
+                        span_idx += 1;
                     }
-                    if let Some(next_addr) = seg_end.checked_add(1) {
+                    if let Some(next_addr) = end.checked_add(1) {
                         addr = next_addr;
                     } else {
                         break;
@@ -535,24 +604,24 @@ impl HexFile {
             }
         } else {
             let mut run_start: Option<u32> = None;
-            let mut run_len: usize = 0;
+            let mut run_len: u64 = 0;
             let mut prev_end: Option<u32> = None;
-            let mut seg_idx = 0usize;
+            let mut span_idx = 0usize;
             let mut inc_idx = 0usize;
 
-            while seg_idx < segments.len() && inc_idx < include_ranges.len() {
-                let seg = &segments[seg_idx];
+            while span_idx < spans.len() && inc_idx < include_ranges.len() {
+                let span = &spans[span_idx];
                 let inc = include_ranges[inc_idx];
-                if seg.end_address() < inc.start() {
-                    seg_idx += 1;
+                if span.end < inc.start() {
+                    span_idx += 1;
                     continue;
                 }
-                if seg.start_address > inc.end() {
+                if span.start > inc.end() {
                     inc_idx += 1;
                     continue;
                 }
-                let start = seg.start_address.max(inc.start());
-                let end = seg.end_address().min(inc.end());
+                let start = span.start.max(inc.start());
+                let end = span.end.min(inc.end());
 
                 if let Some(prev) = prev_end
                     && start != prev.saturating_add(1)
@@ -566,14 +635,12 @@ impl HexFile {
                     run_start = Some(start);
                 }
 
-                let offset = (start - seg.start_address) as usize;
-                let len = (end - start + 1) as usize;
-                data.extend_from_slice(&seg.data[offset..offset + len]);
-                run_len += len;
+                emit(span.slice(start, end));
+                run_len += (end - start) as u64 + 1;
                 prev_end = Some(end);
 
-                if seg.end_address() <= inc.end() {
-                    seg_idx += 1;
+                if span.end <= inc.end() {
+                    span_idx += 1;
                 } else {
                     inc_idx += 1;
                 }
@@ -584,7 +651,7 @@ impl HexFile {
             }
         }
 
-        Ok(data)
+        Ok(())
     }
 
     fn resolve_effective_checksum_range(
@@ -606,84 +673,408 @@ impl HexFile {
     }
 }
 
-fn build_pattern_data(range: Range, pattern: &[u8]) -> Result<Vec<u8>, OpsError> {
-    let len = usize::try_from(range.length()).map_err(|_| {
-        OpsError::AddressOverflow(format!(
-            "forced range length exceeds usize (start={:#X}, end={:#X})",
-            range.start(),
-            range.end()
-        ))
-    })?;
-    if len == 0 {
-        return Ok(Vec::new());
-    }
-    let fill_pattern = if pattern.is_empty() { &[0xFF] } else { pattern };
-    let mut data = Vec::with_capacity(len);
-    for i in 0..len {
-        data.push(fill_pattern[i % fill_pattern.len()]);
-    }
-    Ok(data)
+/// Gap bytes are fed to the checksum in buffers of at most this size.
+const FILL_CHUNK: usize = 64 * 1024;
+
+/// A piece of checksum input, in address order.
+#[derive(Debug, Clone, Copy)]
+enum ChecksumInput<'a> {
+    Bytes(&'a [u8]),
+    /// `len` bytes of `pattern` repeated, starting `phase` bytes into it.
+    Repeat {
+        pattern: &'a [u8],
+        phase: usize,
+        len: u64,
+    },
 }
 
-fn merge_ranges(mut ranges: Vec<Range>) -> Vec<Range> {
-    ranges.sort_by_key(|r| r.start());
-    let mut merged: Vec<Range> = Vec::new();
-    for r in ranges {
-        if let Some(last) = merged.last_mut() {
-            let last_end = last.end();
-            let extend = last_end
-                .checked_add(1)
-                .map(|v| r.start() <= v)
-                .unwrap_or(false);
-            if r.start() <= last_end || extend {
-                let end = last_end.max(r.end());
-                *last = Range::from_start_end(last.start(), end).expect("range merge");
-                continue;
-            }
+impl ChecksumInput<'_> {
+    fn len(&self) -> u64 {
+        match self {
+            Self::Bytes(bytes) => bytes.len() as u64,
+            Self::Repeat { len, .. } => *len,
         }
-        merged.push(r);
     }
-    merged
 }
 
-fn subtract_ranges(range: Range, excludes: &[Range]) -> Vec<Range> {
-    if excludes.is_empty() {
-        return vec![range];
+/// Input covering `start..=end`.
+#[derive(Debug, Clone, Copy)]
+struct Span<'a> {
+    start: u32,
+    end: u32,
+    input: ChecksumInput<'a>,
+}
+
+impl<'a> Span<'a> {
+    /// The input for `from..=to`, which must lie inside the span.
+    fn slice(&self, from: u32, to: u32) -> ChecksumInput<'a> {
+        let offset = (from - self.start) as usize;
+        let len = (to - from) as usize + 1;
+        match self.input {
+            ChecksumInput::Bytes(bytes) => ChecksumInput::Bytes(&bytes[offset..offset + len]),
+            ChecksumInput::Repeat { pattern, phase, .. } => ChecksumInput::Repeat {
+                pattern,
+                phase: (phase + offset) % pattern.len(),
+                len: len as u64,
+            },
+        }
+    }
+
+    fn sub(&self, from: u32, to: u32) -> Self {
+        Self {
+            start: from,
+            end: to,
+            input: self.slice(from, to),
+        }
     }
-    let mut out: Vec<Range> = Vec::new();
-    let mut cursor = range.start();
-    for ex in excludes {
-        if ex.end() < cursor {
+}
+
+/// Sorted, non-overlapping spans for normalized `segments`, with `overlay`
+/// (if any) replacing whatever it covers by `fill`.
+fn checksum_spans<'a>(
+    segments: &'a [Segment],
+    overlay: Option<Range>,
+    fill: &'a [u8],
+) -> Vec<Span<'a>> {
+    let spans = segments.iter().filter(|s| !s.is_empty()).map(|s| Span {
+        start: s.start_address,
+        end: s.end_address(),
+        input: ChecksumInput::Bytes(&s.data),
+    });
+    let Some(overlay) = overlay else {
+        return spans.collect();
+    };
+
+    let overlay_span = Span {
+        start: overlay.start(),
+        end: overlay.end(),
+        input: ChecksumInput::Repeat {
+            pattern: fill,
+            phase: 0,
+            len: overlay.length() as u64,
+        },
+    };
+    let mut out = Vec::with_capacity(segments.len() + 2);
+    let mut placed = false;
+    for span in spans {
+        if span.end < overlay.start() {
+            out.push(span);
             continue;
         }
-        if ex.start() > range.end() {
-            break;
+        if span.start < overlay.start() {
+            out.push(span.sub(span.start, overlay.start() - 1));
+        }
+        if !placed {
+            out.push(overlay_span);
+            placed = true;
+        }
+        if span.end > overlay.end() {
+            out.push(span.sub(span.start.max(overlay.end() + 1), span.end));
         }
-        let ex_start = ex.start().max(range.start());
-        let ex_end = ex.end().min(range.end());
-        if cursor < ex_start
-            && let Ok(r) = Range::from_start_end(cursor, ex_start - 1)
+    }
+    if !placed {
+        out.push(overlay_span);
+    }
+    out
+}
+
+/// Feed an uncovered address run: the forced-range pattern where it applies,
+/// the gap policy elsewhere.
+fn emit_gap(
+    options: &ChecksumOptions,
+    start: u32,
+    end: u32,
+    emit: &mut dyn FnMut(ChecksumInput<'_>),
+) -> Result<(), OpsError> {
+    let Some(forced) = options.forced_range.as_ref() else {
+        return emit_policy_gap(options.gap_fill, start, end, emit);
+    };
+    let forced_range = forced.range;
+    if start < forced_range.start() {
+        emit_policy_gap(
+            options.gap_fill,
+            start,
+            end.min(forced_range.start() - 1),
+            emit,
+        )?;
+    }
+    let (from, to) = (start.max(forced_range.start()), end.min(forced_range.end()));
+    if from <= to {
+        let pattern: &[u8] = if forced.pattern.is_empty() {
+            &[0xFF]
+        } else {
+            &forced.pattern
+        };
+        emit(ChecksumInput::Repeat {
+            pattern,
+            phase: (from - forced_range.start()) as usize % pattern.len(),
+            len: (to - from) as u64 + 1,
+        });
+    }
+    if end > forced_range.end() {
+        emit_policy_gap(
+            options.gap_fill,
+            start.max(forced_range.end() + 1),
+            end,
+            emit,
+        )?;
+    }
+    Ok(())
+}
+
+/// Feed an uncovered address run according to the gap policy.
+fn emit_policy_gap(
+    gap_fill: ChecksumGapFill,
+    start: u32,
+    end: u32,
+    emit: &mut dyn FnMut(ChecksumInput<'_>),
+) -> Result<(), OpsError> {
+    let byte = match gap_fill {
+        ChecksumGapFill::Error => {
+            return Err(OpsError::RangeNotCovered {
+                start,
+                length: end - start + 1,
+            });
+        }
+        ChecksumGapFill::Fill(byte) => byte,
+        // Only reached inside forced ranges, which historically fill with 0xFF.
+        ChecksumGapFill::Skip => 0xFF,
+    };
+    emit(ChecksumInput::Repeat {
+        pattern: &[byte],
+        phase: 0,
+        len: (end - start) as u64 + 1,
+    });
+    Ok(())
+}
+
+/// Final value of a [`ChecksumState`], before seeding and byte ordering.
+enum ChecksumValue {
+    Sum(u16),
+    Crc16(u16),
+    Crc32(u32),
+    Digest(Vec<u8>),
+}
+
+/// Running checksum, updated with the input in address order.
+enum ChecksumState {
+    ByteSum(u16),
+    WordSum {
+        sum: u16,
+        big_endian: bool,
+        /// First byte of a word split across updates.
+        pending: Option<u8>,
+        length: usize,
+    },
+    Crc16 {
+        crc: fn(&[u8], Option<u16>) -> u16,
+        value: Option<u16>,
+    },
+    Crc32(Option<u32>),
+    Sha1(Sha1),
+    Ripemd160(Ripemd160),
+    Md5(Md5),
+    Sha256(Sha256),
+    Sha512(Sha512),
+}
+
+impl ChecksumState {
+    fn new(algorithm: ChecksumAlgorithm, initial: Option<u64>) -> Self {
+        let word_sum = |big_endian| Self::WordSum {
+            sum: 0,
+            big_endian,
+            pending: None,
+            length: 0,
+        };
+        let crc16 = |crc| Self::Crc16 {
+            crc,
+            value: initial.map(|value| value as u16),
+        };
+        match algorithm {
+            ChecksumAlgorithm::ByteSumBe
+            | ChecksumAlgorithm::ByteSumLe
+            | ChecksumAlgorithm::ByteSumTwosComplement => Self::ByteSum(0),
+            ChecksumAlgorithm::WordSumBe | ChecksumAlgorithm::WordSumBeTwosComplement => {
+                word_sum(true)
+            }
+            ChecksumAlgorithm::WordSumLe
+            | ChecksumAlgorithm::WordSumLeTwosComplement
+            | ChecksumAlgorithm::ModularSum => word_sum(false),
+            ChecksumAlgorithm::Crc16 => crc16(crc16_arc),
+            ChecksumAlgorithm::Crc16NonStandard => crc16(crc16_non_standard),
+            ChecksumAlgorithm::Crc16CcittLe | ChecksumAlgorithm::Crc16CcittBe => {
+                crc16(crc16_ibm_sdlc)
+            }
+            ChecksumAlgorithm::Crc16CcittLeInit0 | ChecksumAlgorithm::Crc16CcittBeInit0 => {
+                crc16(crc16_xmodem)
+            }
+            ChecksumAlgorithm::Crc32 => Self::Crc32(initial.map(|value| value as u32)),
+            ChecksumAlgorithm::Sha1 => Self::Sha1(Sha1::new()),
+            ChecksumAlgorithm::Ripemd160 => Self::Ripemd160(Ripemd160::new()),
+            ChecksumAlgorithm::Md5 => Self::Md5(Md5::new()),
+            ChecksumAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+            ChecksumAlgorithm::Sha512AddressLength => Self::Sha512(Sha512::new()),
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        match self {
+            Self::ByteSum(sum) => *sum = sum.wrapping_add(byte_sum(data)),
+            Self::WordSum {
+                sum,
+                big_endian,
+                pending,
+                length,
+            } => {
+                *length += data.len();
+                if let Some((&second, rest)) = data.split_first()
+                    && let Some(first) = pending.take()
+                {
+                    *sum = sum.wrapping_add(word(first, second, *big_endian));
+                    data = rest;
+                }
+                let (words, odd) = data.split_at(data.len() & !1);
+                let words_sum = if *big_endian {
+                    word_sum_be(words)
+                } else {
+                    word_sum_le(words)
+                };
+                *sum = sum.wrapping_add(words_sum.unwrap_or_default());
+                if let Some(&byte) = odd.first() {
+                    *pending = Some(byte);
+                }
+            }
+            Self::Crc16 { crc, value } => *value = Some(crc(data, *value)),
+            Self::Crc32(value) => *value = Some(crc32_iso_hdlc(data, *value)),
+            Self::Sha1(hasher) => hasher.update(data),
+            Self::Ripemd160(hasher) => hasher.update(data),
+            Self::Md5(hasher) => hasher.update(data),
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Sha512(hasher) => hasher.update(data),
+        }
+    }
+
+    fn feed(&mut self, input: ChecksumInput<'_>) {
+        let (pattern, phase, len) = match input {
+            ChecksumInput::Bytes(bytes) => return self.update(bytes),
+            ChecksumInput::Repeat {
+                pattern,
+                phase,
+                len,
+            } => (pattern, phase, len),
+        };
+        if let [byte] = pattern
+            && self.fill_sum(*byte, len)
         {
-            out.push(r);
+            return;
         }
-        if ex_end == u32::MAX {
-            return out;
+
+        let chunk = FILL_CHUNK.min(len as usize);
+        let buffer: Vec<u8> = pattern
+            .iter()
+            .cycle()
+            .skip(phase)
+            .take(chunk + pattern.len())
+            .copied()
+            .collect();
+        let mut offset = 0;
+        let mut remaining = len;
+        while remaining > 0 {
+            let n = remaining.min(chunk as u64) as usize;
+            self.update(&buffer[offset..offset + n]);
+            offset = (offset + n) % pattern.len();
+            remaining -= n as u64;
         }
-        cursor = ex_end + 1;
-        if cursor > range.end() {
-            return out;
+    }
+
+    /// Add `len` copies of `byte` to a sum in closed form. Returns false for
+    /// algorithms that must see every byte.
+    fn fill_sum(&mut self, byte: u8, len: u64) -> bool {
+        // Sums wrap at 16 bits, so only `count mod 2^16` copies matter.
+        let times = |value: u16, count: u64| value.wrapping_mul(count as u16);
+        match self {
+            Self::ByteSum(sum) => *sum = sum.wrapping_add(times(byte as u16, len)),
+            Self::WordSum {
+                sum,
+                big_endian,
+                pending,
+                length,
+            } => {
+                if len == 0 {
+                    return true;
+                }
+                *length += len as usize;
+                let mut len = len;
+                if let Some(first) = pending.take() {
+                    *sum = sum.wrapping_add(word(first, byte, *big_endian));
+                    len -= 1;
+                }
+                *sum = sum.wrapping_add(times(u16::from_ne_bytes([byte, byte]), len / 2));
+                if len % 2 == 1 {
+                    *pending = Some(byte);
+                }
+            }
+            _ => return false,
         }
+        true
+    }
+
+    fn finish(self) -> Result<ChecksumValue, OpsError> {
+        Ok(match self {
+            Self::ByteSum(sum) => ChecksumValue::Sum(sum),
+            Self::WordSum {
+                sum,
+                big_endian,
+                pending,
+                length,
+            } => {
+                if pending.is_some() {
+                    return Err(OpsError::LengthNotMultiple {
+                        length,
+                        expected: 2,
+                        operation: if big_endian {
+                            "word sum BE"
+                        } else {
+                            "word sum LE"
+                        }
+                        .to_string(),
+                    });
+                }
+                ChecksumValue::Sum(sum)
+            }
+            Self::Crc16 { crc, value } => {
+                ChecksumValue::Crc16(value.unwrap_or_else(|| crc(&[], None)))
+            }
+            Self::Crc32(value) => {
+                ChecksumValue::Crc32(value.unwrap_or_else(|| crc32_iso_hdlc(&[], None)))
+            }
+            Self::Sha1(hasher) => ChecksumValue::Digest(hasher.finalize().to_vec()),
+            Self::Ripemd160(hasher) => ChecksumValue::Digest(hasher.finalize().to_vec()),
+            Self::Md5(hasher) => ChecksumValue::Digest(hasher.finalize().to_vec()),
+            Self::Sha256(hasher) => ChecksumValue::Digest(hasher.finalize().to_vec()),
+            Self::Sha512(hasher) => ChecksumValue::Digest(hasher.finalize().to_vec()),
+        })
     }
-    if cursor <= range.end()
-        && let Ok(r) = Range::from_start_end(cursor, range.end())
-    {
-        out.push(r);
+}
+
+fn word(first: u8, second: u8, big_endian: bool) -> u16 {
+    if big_endian {
+        u16::from_be_bytes([first, second])
+    } else {
+        u16::from_le_bytes([first, second])
     }
-    out
 }
 
 /// Sum all bytes, wrapping to 16-bit.
 fn byte_sum(data: &[u8]) -> u16 {
+    #[cfg(feature = "parallel")]
+    if data.len() >= super::parallel::MIN_LEN {
+        return super::parallel::wrapping_sum(data, serial_byte_sum);
+    }
+    serial_byte_sum(data)
+}
+
+fn serial_byte_sum(data: &[u8]) -> u16 {
     data.iter().fold(0u16, |acc, &b| acc.wrapping_add(b as u16))
 }
 
@@ -696,9 +1087,16 @@ fn word_sum_be(data: &[u8]) -> Result<u16, OpsError> {
             operation: "word sum BE".to_string(),
         });
     }
-    Ok(data.chunks_exact(2).fold(0u16, |acc, chunk| {
-        acc.wrapping_add(u16::from_be_bytes([chunk[0], chunk[1]]))
-    }))
+    fn sum(data: &[u8]) -> u16 {
+        data.chunks_exact(2).fold(0u16, |acc, chunk| {
+            acc.wrapping_add(u16::from_be_bytes([chunk[0], chunk[1]]))
+        })
+    }
+    #[cfg(feature = "parallel")]
+    if data.len() >= super::parallel::MIN_LEN {
+        return Ok(super::parallel::wrapping_sum(data, sum));
+    }
+    Ok(sum(data))
 }
 
 /// Sum 16-bit little-endian words.
@@ -710,20 +1108,60 @@ fn word_sum_le(data: &[u8]) -> Result<u16, OpsError> {
             operation: "word sum LE".to_string(),
         });
     }
-    Ok(data.chunks_exact(2).fold(0u16, |acc, chunk| {
-        acc.wrapping_add(u16::from_le_bytes([chunk[0], chunk[1]]))
-    }))
+    fn sum(data: &[u8]) -> u16 {
+        data.chunks_exact(2).fold(0u16, |acc, chunk| {
+            acc.wrapping_add(u16::from_le_bytes([chunk[0], chunk[1]]))
+        })
+    }
+    #[cfg(feature = "parallel")]
+    if data.len() >= super::parallel::MIN_LEN {
+        return Ok(super::parallel::wrapping_sum(data, sum));
+    }
+    Ok(sum(data))
+}
+
+/// Run a catalog CRC-16, optionally continuing from a previous result so that
+/// `crc(a ++ b) == crc(b, Some(crc(a)))`.
+fn crc16_resume(crc: &crc::Crc<u16>, data: &[u8], previous: Option<u16>) -> u16 {
+    let Some(previous) = previous else {
+        return crc.checksum(data);
+    };
+    let register = previous ^ crc.algorithm.xorout;
+    let initial = if crc.algorithm.refin {
+        register.reverse_bits()
+    } else {
+        register
+    };
+    let mut digest = crc.digest_with_initial(initial);
+    digest.update(data);
+    digest.finalize()
+}
+
+/// CRC-32 counterpart of [`crc16_resume`].
+fn crc32_resume(crc: &crc::Crc<u32>, data: &[u8], previous: Option<u32>) -> u32 {
+    let Some(previous) = previous else {
+        return crc.checksum(data);
+    };
+    let register = previous ^ crc.algorithm.xorout;
+    let initial = if crc.algorithm.refin {
+        register.reverse_bits()
+    } else {
+        register
+    };
+    let mut digest = crc.digest_with_initial(initial);
+    digest.update(data);
+    digest.finalize()
 }
 
 /// CRC-16 with poly 0x8005 (CRC-16-ARC/CRC-16-IBM).
-fn crc16_arc(data: &[u8]) -> u16 {
+fn crc16_arc(data: &[u8], previous: Option<u16>) -> u16 {
     const CRC: crc::Crc<u16> = crc::Crc::<u16>::new(&crc::CRC_16_ARC);
-    CRC.checksum(data)
+    crc16_resume(&CRC, data, previous)
 }
 
 /// CRC-16 non-standard variant from HexView expdatproc method 8.
-fn crc16_non_standard(data: &[u8]) -> u16 {
-    let mut crc = 0xFFFFu16;
+fn crc16_non_standard(data: &[u8], previous: Option<u16>) -> u16 {
+    let mut crc = previous.map_or(0xFFFF, |previous| !previous);
     for &byte in data {
         crc = crc.rotate_left(8);
         crc ^= byte as u16;
@@ -735,21 +1173,29 @@ fn crc16_non_standard(data: &[u8]) -> u16 {
 }
 
 /// CRC-32 IEEE (ISO-HDLC).
-fn crc32_iso_hdlc(data: &[u8]) -> u32 {
+fn crc32_iso_hdlc(data: &[u8], previous: Option<u32>) -> u32 {
     const CRC: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
-    CRC.checksum(data)
+    #[cfg(feature = "parallel")]
+    if data.len() >= super::parallel::MIN_LEN {
+        let crc = super::parallel::crc32(data, |chunk| CRC.checksum(chunk));
+        return match previous {
+            Some(previous) => super::parallel::crc32_combine(previous, crc, data.len() as u64),
+            None => crc,
+        };
+    }
+    crc32_resume(&CRC, data, previous)
 }
 
 /// CRC-16 CCITT with init 0xFFFF (IBM-SDLC, ISO-HDLC).
-fn crc16_ibm_sdlc(data: &[u8]) -> u16 {
+fn crc16_ibm_sdlc(data: &[u8], previous: Option<u16>) -> u16 {
     const CRC: crc::Crc<u16> = crc::Crc::<u16>::new(&crc::CRC_16_IBM_SDLC);
-    CRC.checksum(data)
+    crc16_resume(&CRC, data, previous)
 }
 
 /// CRC-16 CCITT with init 0 (XMODEM).
-fn crc16_xmodem(data: &[u8]) -> u16 {
+fn crc16_xmodem(data: &[u8], previous: Option<u16>) -> u16 {
     const CRC: crc::Crc<u16> = crc::Crc::<u16>::new(&crc::CRC_16_XMODEM);
-    CRC.checksum(data)
+    crc16_resume(&CRC, data, previous)
 }
 
 #[cfg(test)]
@@ -757,6 +1203,36 @@ mod tests {
     use super::*;
     use crate::Segment;
 
+    #[test]
+    fn test_segment_report_uses_normalized_segments() {
+        let hf = HexFile::with_segments(vec![
+            Segment::new(0x2000, vec![0x10, 0x20]),
+            Segment::new(0x1000, b"123456789".to_vec()),
+            Segment::new(0x1009, vec![0xAA]),
+        ]);
+        let report = hf.segment_report();
+        assert_eq!(report.len(), 2);
+        assert_eq!(
+            report[0],
+            SegmentInfo {
+                start_address: 0x1000,
+                end_address: 0x1009,
+                length: 10,
+                crc32: crc32_iso_hdlc(b"123456789\xAA", None),
+                name: None,
+            }
+        );
+        assert_eq!(crc32_iso_hdlc(b"123456789", None), 0xCBF4_3926);
+        let options = ChecksumOptions {
+            algorithm: ChecksumAlgorithm::Crc32,
+            range: Some(Range::from_start_end(0x2000, 0x2001).unwrap()),
+            ..Default::default()
+        };
+        let crc = hf.calculate_checksum(&options).unwrap();
+        assert_eq!(report[1].crc32.to_be_bytes().to_vec(), crc);
+        assert!(HexFile::new().segment_report().is_empty());
+    }
+
     #[test]
     fn test_byte_sum() {
         assert_eq!(byte_sum(&[0x01, 0x02, 0x03, 0x04]), 0x000A);
@@ -839,31 +1315,31 @@ mod tests {
     #[test]
     fn test_crc16_arc() {
         // Known test vector: "123456789" -> 0xBB3D
-        assert_eq!(crc16_arc(b"123456789"), 0xBB3D);
+        assert_eq!(crc16_arc(b"123456789", None), 0xBB3D);
     }
 
     #[test]
     fn test_crc16_non_standard() {
         // HexView algorithm 8 pseudocode reference vector.
-        assert_eq!(crc16_non_standard(b"123456789"), 0xD64E);
+        assert_eq!(crc16_non_standard(b"123456789", None), 0xD64E);
     }
 
     #[test]
     fn test_crc32_iso_hdlc() {
         // Known test vector: "123456789" -> 0xCBF43926
-        assert_eq!(crc32_iso_hdlc(b"123456789"), 0xCBF43926);
+        assert_eq!(crc32_iso_hdlc(b"123456789", None), 0xCBF43926);
     }
 
     #[test]
     fn test_crc16_xmodem() {
         // Known test vector: "123456789" -> 0x31C3
-        assert_eq!(crc16_xmodem(b"123456789"), 0x31C3);
+        assert_eq!(crc16_xmodem(b"123456789", None), 0x31C3);
     }
 
     #[test]
     fn test_crc16_ibm_sdlc() {
         // Known test vector: "123456789" -> 0x906E
-        assert_eq!(crc16_ibm_sdlc(b"123456789"), 0x906E);
+        assert_eq!(crc16_ibm_sdlc(b"123456789", None), 0x906E);
     }
 
     #[test]
@@ -892,6 +1368,48 @@ mod tests {
         assert_eq!(result, vec![0xCB, 0xF4, 0x39, 0x26]);
     }
 
+    #[test]
+    fn test_hexfile_checksum_large_image_matches_serial() {
+        // Over the `parallel` feature's threshold, with a length that is not
+        // a multiple of its chunk size.
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        let data: Vec<u8> = (0..(3 << 20) + 2_046)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+        let hf = HexFile::with_segments(vec![Segment::new(0x1000, data.clone())]);
+        let run = |algorithm, initial| {
+            let options = ChecksumOptions {
+                algorithm,
+                initial,
+                ..Default::default()
+            };
+            hf.calculate_checksum(&options).unwrap()
+        };
+
+        const CRC: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+        let crc = crc32_resume(&CRC, &data, None);
+        assert_eq!(run(ChecksumAlgorithm::Crc32, None), crc.to_be_bytes());
+        let crc = crc32_resume(&CRC, &data, Some(0x1234_5678));
+        assert_eq!(
+            run(ChecksumAlgorithm::Crc32, Some(0x1234_5678)),
+            crc.to_be_bytes()
+        );
+        let sum = serial_byte_sum(&data).wrapping_add(7);
+        assert_eq!(
+            run(ChecksumAlgorithm::ByteSumBe, Some(7)),
+            sum.to_be_bytes()
+        );
+        let sum = data.chunks_exact(2).fold(0u16, |acc, w| {
+            acc.wrapping_add(u16::from_le_bytes([w[0], w[1]]))
+        });
+        assert_eq!(run(ChecksumAlgorithm::WordSumLe, None), sum.to_le_bytes());
+    }
+
     #[test]
     fn test_hexfile_checksum_crc32_le() {
         let hf = HexFile::with_segments(vec![Segment::new(0x1000, b"123456789".to_vec())]);
@@ -947,6 +1465,47 @@ mod tests {
         assert!(matches!(result, Err(OpsError::AddressOverflow(_))));
     }
 
+    #[test]
+    fn test_hexfile_checksum_append_prepend_boundaries() {
+        let run = |algorithm, start: u32, len: usize, target| {
+            let mut hf = HexFile::with_segments(vec![Segment::new(start, vec![0x01; len])]);
+            let options = ChecksumOptions {
+                algorithm,
+                ..Default::default()
+            };
+            let result = hf
+                .checksum(&options, &target)
+                .map(|_| hf.normalized_lossy());
+            (result, hf)
+        };
+        for (algorithm, size) in [
+            (ChecksumAlgorithm::ByteSumBe, 2),
+            (ChecksumAlgorithm::Crc32, 4),
+        ] {
+            // Last fitting end address, then one past it.
+            let last_end = u32::MAX - size;
+            let (ok, _) = run(algorithm, last_end - 3, 4, ChecksumTarget::Append);
+            assert_eq!(ok.unwrap().max_address(), Some(u32::MAX));
+            for end in [last_end + 1, u32::MAX] {
+                let (err, hf) = run(algorithm, end - 1, 2, ChecksumTarget::Append);
+                assert!(matches!(err, Err(OpsError::AddressOverflow(_))), "{end:#X}");
+                assert_eq!(hf.max_address(), Some(end));
+                assert_eq!(hf.min_address(), Some(end - 1));
+            }
+
+            let (ok, _) = run(algorithm, size, 4, ChecksumTarget::Prepend);
+            assert_eq!(ok.unwrap().min_address(), Some(0));
+            for start in [size - 1, 0] {
+                let (err, hf) = run(algorithm, start, 4, ChecksumTarget::Prepend);
+                assert!(
+                    matches!(err, Err(OpsError::AddressUnderflow(_))),
+                    "{start:#X}"
+                );
+                assert_eq!(hf.segments()[0].data, vec![0x01; 4]);
+            }
+        }
+    }
+
     #[test]
     fn test_hexfile_checksum_prepend_underflow() {
         let mut hf = HexFile::with_segments(vec![Segment::new(0x0, vec![0x01])]);
@@ -957,7 +1516,7 @@ mod tests {
             ..Default::default()
         };
         let result = hf.checksum(&options, &ChecksumTarget::Prepend);
-        assert!(matches!(result, Err(OpsError::AddressOverflow(_))));
+        assert!(matches!(result, Err(OpsError::AddressUnderflow(_))));
     }
 
     #[test]
@@ -1018,6 +1577,266 @@ mod tests {
         assert_eq!(&norm.segments()[0].data[..4], &[0xAA, 0xAA, 0xAA, 0xAA]);
     }
 
+    #[test]
+    fn test_hexfile_checksum_target_bytes_independent_of_stored_data() {
+        for target in [
+            ChecksumTarget::Address(0x1002),
+            ChecksumTarget::OverwriteEnd,
+        ] {
+            for target_as_fill in [false, true] {
+                let options = ChecksumOptions {
+                    algorithm: ChecksumAlgorithm::Crc16,
+                    range: Some(Range::from_start_end(0x1000, 0x1003).unwrap()),
+                    target_as_fill,
+                    ..Default::default()
+                };
+                let mut a = HexFile::with_segments(vec![Segment::new(
+                    0x1000,
+                    vec![0x01, 0x02, 0x00, 0x00],
+                )]);
+                let mut b = HexFile::with_segments(vec![Segment::new(
+                    0x1000,
+                    vec![0x01, 0x02, 0xDE, 0xAD],
+                )]);
+                let first = a.checksum(&options, &target).unwrap();
+                let second = b.checksum(&options, &target).unwrap();
+                assert_eq!(first, second, "{target:?} target_as_fill={target_as_fill}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_hexfile_checksum_begin_and_end_ignore_stored_bytes() {
+        // Garbage in the 2-byte destination must not change the stored result.
+        for (target, clean, dirty) in [
+            (
+                ChecksumTarget::Begin,
+                [0x00, 0x00, 0x03, 0x04],
+                [0xDE, 0xAD, 0x03, 0x04],
+            ),
+            (
+                ChecksumTarget::OverwriteEnd,
+                [0x01, 0x02, 0x00, 0x00],
+                [0x01, 0x02, 0xBE, 0xEF],
+            ),
+        ] {
+            for target_as_fill in [false, true] {
+                let options = ChecksumOptions {
+                    algorithm: ChecksumAlgorithm::ByteSumBe,
+                    target_as_fill,
+                    ..Default::default()
+                };
+                let mut a = HexFile::with_segments(vec![Segment::new(0x1000, clean.to_vec())]);
+                let mut b = HexFile::with_segments(vec![Segment::new(0x1000, dirty.to_vec())]);
+                let first = a.checksum(&options, &target).unwrap();
+                assert_eq!(
+                    first,
+                    b.checksum(&options, &target).unwrap(),
+                    "{target:?} target_as_fill={target_as_fill}"
+                );
+                assert_eq!(a.normalized_lossy(), b.normalized_lossy());
+            }
+        }
+
+        let mut hf =
+            HexFile::with_segments(vec![Segment::new(0x1000, vec![0xDE, 0xAD, 0x03, 0x04])]);
+        let options = ChecksumOptions {
+            algorithm: ChecksumAlgorithm::ByteSumBe,
+            ..Default::default()
+        };
+        // 0x03 + 0x04, written over the first two bytes.
+        assert_eq!(
+            hf.checksum(&options, &ChecksumTarget::Begin).unwrap(),
+            vec![0x00, 0x07]
+        );
+        assert_eq!(
+            hf.normalized_lossy().segments()[0].data,
+            vec![0x00, 0x07, 0x03, 0x04]
+        );
+    }
+
+    #[test]
+    fn test_hexfile_checksum_target_as_fill_uses_ff() {
+        // Target bytes at 0x1002-0x1003 are summed as 0xFF: 0x01 + 0x02 + 0xFF + 0xFF = 0x0201
+        let mut hf =
+            HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01, 0x02, 0x03, 0x04])]);
+        let options = ChecksumOptions {
+            algorithm: ChecksumAlgorithm::ByteSumBe,
+            target_as_fill: true,
+            ..Default::default()
+        };
+        let result = hf
+            .checksum(&options, &ChecksumTarget::OverwriteEnd)
+            .unwrap();
+        assert_eq!(result, vec![0x02, 0x01]);
+        assert_eq!(
+            hf.normalized_lossy().segments()[0].data,
+            vec![0x01, 0x02, 0x02, 0x01]
+        );
+    }
+
+    #[test]
+    fn test_hexfile_checksum_gap_fill_byte() {
+        let hf = HexFile::with_segments(vec![
+            Segment::new(0x1000, vec![0x01]),
+            Segment::new(0x1003, vec![0x02]),
+        ]);
+        let options = ChecksumOptions {
+            algorithm: ChecksumAlgorithm::ByteSumBe,
+            range: Some(Range::from_start_end(0x1000, 0x1004).unwrap()),
+            gap_fill: ChecksumGapFill::Fill(0x10),
+            ..Default::default()
+        };
+        // 0x01 + 0x10 + 0x10 + 0x02 + 0x10 = 0x33
+        assert_eq!(hf.calculate_checksum(&options).unwrap(), vec![0x00, 0x33]);
+
+        let skip = ChecksumOptions {
+            gap_fill: ChecksumGapFill::Skip,
+            ..options
+        };
+        assert_eq!(hf.calculate_checksum(&skip).unwrap(), vec![0x00, 0x03]);
+    }
+
+    #[test]
+    fn test_hexfile_checksum_gap_fill_error_names_first_gap() {
+        let hf = HexFile::with_segments(vec![
+            Segment::new(0x1000, vec![0x01]),
+            Segment::new(0x1003, vec![0x02]),
+        ]);
+        let options = ChecksumOptions {
+            algorithm: ChecksumAlgorithm::Crc32,
+            gap_fill: ChecksumGapFill::Error,
+            ..Default::default()
+        };
+        let err = hf.calculate_checksum(&options).unwrap_err();
+        assert!(matches!(
+            err,
+            OpsError::RangeNotCovered {
+                start: 0x1001,
+                length: 2
+            }
+        ));
+    }
+
+    #[test]
+    fn test_hexfile_checksum_sparse_span_does_not_materialize_gaps() {
+        let hf = HexFile::with_segments(vec![
+            Segment::new(0x0, vec![0x12, 0x34]),
+            Segment::new(0xF000_0000, vec![0x56, 0x78]),
+        ]);
+        let run = |algorithm| {
+            let options = ChecksumOptions {
+                algorithm,
+                range: Some(Range::from_start_end(0x0, 0xF000_0001).unwrap()),
+                gap_fill: ChecksumGapFill::Fill(0x10),
+                ..Default::default()
+            };
+            hf.calculate_checksum(&options).unwrap()
+        };
+        // 0xEFFF_FFFE fill bytes between the two segments.
+        let gap = 0xEFFF_FFFEu32 as u16;
+        let bytes = (0x12u16 + 0x34 + 0x56 + 0x78).wrapping_add(gap.wrapping_mul(0x10));
+        assert_eq!(run(ChecksumAlgorithm::ByteSumBe), bytes.to_be_bytes());
+        let words = (0x1234u16 + 0x5678).wrapping_add((gap / 2).wrapping_mul(0x1010));
+        assert_eq!(run(ChecksumAlgorithm::WordSumBe), words.to_be_bytes());
+    }
+
+    #[test]
+    fn test_hexfile_checksum_streamed_fill_matches_materialized() {
+        // Forced pattern longer than one fill chunk, with a period that does
+        // not divide it, interrupted by data.
+        let hf = HexFile::with_segments(vec![Segment::new(0x1_0005, vec![0xAA, 0xBB])]);
+        let range = Range::from_start_end(0x1000, 0x3_0FFF).unwrap();
+        let pattern = vec![0x01, 0x02, 0x03];
+        let mut expected: Vec<u8> = pattern
+            .iter()
+            .cycle()
+            .take(range.length() as usize)
+            .copied()
+            .collect();
+        expected[0xF005..0xF007].copy_from_slice(&[0xAA, 0xBB]);
+
+        for algorithm in [ChecksumAlgorithm::Crc32, ChecksumAlgorithm::Sha256] {
+            let options = ChecksumOptions {
+                algorithm,
+                forced_range: Some(ForcedRange {
+                    range,
+                    pattern: pattern.clone(),
+                }),
+                ..Default::default()
+            };
+            let streamed = hf.calculate_checksum(&options).unwrap();
+            let reference = HexFile::with_segments(vec![Segment::new(0x1000, expected.clone())]);
+            let options = ChecksumOptions {
+                forced_range: None,
+                ..options
+            };
+            assert_eq!(streamed, reference.calculate_checksum(&options).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_hexfile_checksum_crc32_chained_initial_matches_concatenation() {
+        let hf = HexFile::with_segments(vec![Segment::new(0x1000, b"123456789".to_vec())]);
+        let block = |range: Range, initial: Option<u64>| {
+            let options = ChecksumOptions {
+                algorithm: ChecksumAlgorithm::Crc32,
+                range: Some(range),
+                initial,
+                ..Default::default()
+            };
+            hf.calculate_checksum(&options).unwrap()
+        };
+        let first = block(Range::from_start_end(0x1000, 0x1003).unwrap(), None);
+        let previous = u32::from_be_bytes(first.try_into().unwrap()) as u64;
+        let chained = block(
+            Range::from_start_end(0x1004, 0x1008).unwrap(),
+            Some(previous),
+        );
+        let whole = block(Range::from_start_end(0x1000, 0x1008).unwrap(), None);
+        assert_eq!(chained, whole);
+        assert_eq!(whole, vec![0xCB, 0xF4, 0x39, 0x26]);
+    }
+
+    #[test]
+    fn test_crc16_resume_matches_concatenation() {
+        let (a, b) = (b"1234".as_slice(), b"56789".as_slice());
+        let whole = b"123456789";
+        for crc in [crc16_arc, crc16_non_standard, crc16_xmodem, crc16_ibm_sdlc] {
+            assert_eq!(crc(b, Some(crc(a, None))), crc(whole, None));
+        }
+    }
+
+    #[test]
+    fn test_hexfile_checksum_initial_seeds_sum() {
+        let hf = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01, 0x02])]);
+        let options = ChecksumOptions {
+            algorithm: ChecksumAlgorithm::ByteSumBe,
+            initial: Some(0x0100),
+            ..Default::default()
+        };
+        assert_eq!(hf.calculate_checksum(&options).unwrap(), vec![0x01, 0x03]);
+    }
+
+    #[test]
+    fn test_hexfile_checksum_initial_rejected_for_hash_and_wide_values() {
+        let hf = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01, 0x02])]);
+        for (algorithm, initial) in [
+            (ChecksumAlgorithm::Sha256, 0),
+            (ChecksumAlgorithm::Crc16, 0x1_0000),
+        ] {
+            let options = ChecksumOptions {
+                algorithm,
+                initial: Some(initial),
+                ..Default::default()
+            };
+            assert!(matches!(
+                hf.calculate_checksum(&options),
+                Err(OpsError::InvalidChecksumInitial { .. })
+            ));
+        }
+    }
+
     #[test]
     fn test_algorithm_from_index() {
         assert!(ChecksumAlgorithm::from_index(0).is_ok());
@@ -1045,17 +1864,17 @@ mod tests {
 
     #[test]
     fn test_crc16_arc_empty() {
-        assert_eq!(crc16_arc(&[]), 0x0000);
+        assert_eq!(crc16_arc(&[], None), 0x0000);
     }
 
     #[test]
     fn test_crc32_iso_hdlc_empty() {
-        assert_eq!(crc32_iso_hdlc(&[]), 0x00000000);
+        assert_eq!(crc32_iso_hdlc(&[], None), 0x00000000);
     }
 
     #[test]
     fn test_crc16_xmodem_empty() {
-        assert_eq!(crc16_xmodem(&[]), 0x0000);
+        assert_eq!(crc16_xmodem(&[], None), 0x0000);
     }
 
     #[test]
@@ -1180,6 +1999,9 @@ mod tests {
             }),
             exclude_ranges: Vec::new(),
             target_exclude: None,
+            target_as_fill: false,
+            gap_fill: ChecksumGapFill::Skip,
+            initial: None,
         };
         let result = hf.calculate_checksum(&options).unwrap();
         // 0x01 + 0x02 + 0xFF + 0xFF = 0x0201
@@ -1196,12 +2018,46 @@ mod tests {
             forced_range: None,
             exclude_ranges: vec![Range::from_start_end(0x1001, 0x1002).unwrap()],
             target_exclude: None,
+            target_as_fill: false,
+            gap_fill: ChecksumGapFill::Skip,
+            initial: None,
         };
         let result = hf.calculate_checksum(&options).unwrap();
         // 0x01 + 0x04 = 0x05
         assert_eq!(result, vec![0x00, 0x05]);
     }
 
+    #[test]
+    fn test_hexfile_checksum_excludes_covering_address_space() {
+        let hf = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01, 0x02, 0x03, 0x04])]);
+        let options = ChecksumOptions {
+            algorithm: ChecksumAlgorithm::ByteSumBe,
+            range: Some(Range::from_start_end(0, 0xFFFF_FFFE).unwrap()),
+            little_endian_output: false,
+            forced_range: None,
+            exclude_ranges: vec![
+                Range::from_start_end(0x8000_0000, u32::MAX).unwrap(),
+                Range::from_start_end(0, 0x0FFF).unwrap(),
+                Range::from_start_end(0x1002, 0x7FFF_FFFF).unwrap(),
+            ],
+            target_exclude: None,
+            target_as_fill: false,
+            gap_fill: ChecksumGapFill::Skip,
+            initial: None,
+        };
+        // 0x01 + 0x02
+        assert_eq!(hf.calculate_checksum(&options).unwrap(), vec![0x00, 0x03]);
+
+        let options = ChecksumOptions {
+            exclude_ranges: vec![
+                Range::from_start_end(0, 0x7FFF_FFFF).unwrap(),
+                Range::from_start_end(0x8000_0000, u32::MAX).unwrap(),
+            ],
+            ..options
+        };
+        assert_eq!(hf.calculate_checksum(&options).unwrap(), vec![0x00, 0x00]);
+    }
+
     #[test]
     fn test_hexfile_checksum_many_sequential_uses_updated_state() {
         let mut hf =
@@ -1350,4 +2206,72 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_checksum_per_segment_overwrite_end_keeps_blocks() {
+        let mut hf = HexFile::with_segments(vec![Segment::new(
+            0x1000,
+            vec![0x01, 0x02, 0x00, 0x00, 0x03, 0x04, 0x00, 0x00],
+        )]);
+        hf.split(4);
+        let options = ChecksumOptions {
+            algorithm: ChecksumAlgorithm::ByteSumBe,
+            ..Default::default()
+        };
+        let results = hf
+            .checksum_per_segment(&options, &ChecksumTarget::OverwriteEnd)
+            .unwrap();
+        assert_eq!(results, vec![vec![0x00, 0x03], vec![0x00, 0x07]]);
+        assert_eq!(hf.segments().len(), 2);
+        assert_eq!(hf.segments()[0].data, vec![0x01, 0x02, 0x00, 0x03]);
+        assert_eq!(hf.segments()[1].data, vec![0x03, 0x04, 0x00, 0x07]);
+    }
+
+    #[test]
+    fn test_checksum_per_segment_append_to_each_block() {
+        let mut hf = HexFile::with_segments(vec![
+            Segment::new(0x1000, vec![0x01, 0x02]),
+            Segment::new(0x2000, vec![0x10]),
+        ]);
+        let options = ChecksumOptions {
+            algorithm: ChecksumAlgorithm::ByteSumBe,
+            ..Default::default()
+        };
+        let results = hf
+            .checksum_per_segment(&options, &ChecksumTarget::Append)
+            .unwrap();
+        assert_eq!(results, vec![vec![0x00, 0x03], vec![0x00, 0x10]]);
+        assert_eq!(hf.segments().len(), 2);
+        assert_eq!(hf.segments()[0].data, vec![0x01, 0x02, 0x00, 0x03]);
+        assert_eq!(hf.segments()[1].start_address, 0x2000);
+        assert_eq!(hf.segments()[1].data, vec![0x10, 0x00, 0x10]);
+    }
+
+    #[test]
+    fn test_checksum_per_segment_rejects_collision_with_next_block() {
+        let mut hf = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01; 8])]);
+        hf.split(4);
+        let options = ChecksumOptions {
+            algorithm: ChecksumAlgorithm::ByteSumBe,
+            ..Default::default()
+        };
+        let before = hf.clone();
+        let err = hf
+            .checksum_per_segment(&options, &ChecksumTarget::Append)
+            .unwrap_err();
+        assert!(matches!(err, OpsError::BlockChecksum(_)));
+        assert_eq!(hf.segments(), before.segments());
+    }
+
+    #[test]
+    fn test_checksum_per_segment_rejects_address_target() {
+        let mut hf = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01])]);
+        let err = hf
+            .checksum_per_segment(
+                &ChecksumOptions::default(),
+                &ChecksumTarget::Address(0x2000),
+            )
+            .unwrap_err();
+        assert!(matches!(err, OpsError::BlockChecksum(_)));
+    }
 }
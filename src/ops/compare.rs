@@ -0,0 +1,136 @@
+//! Content comparison of two images (CLI: /CMP).
+
+use crate::{HexFile, Range, Segment};
+
+impl HexFile {
+    /// Compare the byte content of two files, ignoring how it was encoded
+    /// (record length, segment order, extended address records). Overlaps are
+    /// resolved last-wins first. With `ranges`, only data inside them counts.
+    ///
+    /// On mismatch, returns the ranges where the bytes differ or only one file
+    /// has data, sorted and with adjacent ranges merged.
+    pub fn content_eq(&self, other: &HexFile, ranges: Option<&[Range]>) -> Result<(), Vec<Range>> {
+        let mut left = self.normalized_lossy();
+        let mut right = other.normalized_lossy();
+        if let Some(ranges) = ranges {
+            left.filter_ranges(ranges);
+            right.filter_ranges(ranges);
+        }
+        let diffs = differing_ranges(left.segments(), right.segments());
+        if diffs.is_empty() { Ok(()) } else { Err(diffs) }
+    }
+}
+
+/// Both inputs must be sorted and non-overlapping.
+fn differing_ranges(left: &[Segment], right: &[Segment]) -> Vec<Range> {
+    // Between two consecutive boundaries each side is either fully covered or empty.
+    let mut bounds: Vec<u64> = left
+        .iter()
+        .chain(right)
+        .flat_map(|s| [s.start_address as u64, s.end_address() as u64 + 1])
+        .collect();
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    let mut diffs: Vec<(u64, u64)> = Vec::new();
+    let (mut li, mut ri) = (0, 0);
+    for pair in bounds.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        match (
+            covering(left, &mut li, start),
+            covering(right, &mut ri, start),
+        ) {
+            (None, None) => {}
+            (Some(l), Some(r)) => {
+                let l = slice(l, start, end);
+                let r = slice(r, start, end);
+                for (i, (a, b)) in l.iter().zip(r).enumerate() {
+                    if a != b {
+                        let addr = start + i as u64;
+                        push_diff(&mut diffs, addr, addr + 1);
+                    }
+                }
+            }
+            _ => push_diff(&mut diffs, start, end),
+        }
+    }
+
+    diffs
+        .into_iter()
+        .filter_map(|(start, end)| Range::from_start_end(start as u32, (end - 1) as u32).ok())
+        .collect()
+}
+
+fn covering<'a>(segments: &'a [Segment], index: &mut usize, addr: u64) -> Option<&'a Segment> {
+    while segments
+        .get(*index)
+        .is_some_and(|s| (s.end_address() as u64) < addr)
+    {
+        *index += 1;
+    }
+    segments
+        .get(*index)
+        .filter(|s| s.start_address as u64 <= addr)
+}
+
+fn slice(segment: &Segment, start: u64, end: u64) -> &[u8] {
+    let base = segment.start_address as u64;
+    &segment.data[(start - base) as usize..(end - base) as usize]
+}
+
+/// Append `[start, end)`, extending the last range when they touch.
+fn push_diff(diffs: &mut Vec<(u64, u64)>, start: u64, end: u64) {
+    if let Some(last) = diffs.last_mut()
+        && last.1 == start
+    {
+        last.1 = end;
+        return;
+    }
+    diffs.push((start, end));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: u32, end: u32) -> Range {
+        Range::from_start_end(start, end).unwrap()
+    }
+
+    #[test]
+    fn test_content_eq_ignores_segment_layout() {
+        let left = HexFile::with_segments(vec![
+            Segment::new(0x2000, vec![0x05]),
+            Segment::new(0x1000, vec![0x01, 0x02, 0x03, 0x04]),
+        ]);
+        let right = HexFile::with_segments(vec![
+            Segment::new(0x1000, vec![0x01, 0x02]),
+            Segment::new(0x1002, vec![0x03, 0x04]),
+            Segment::new(0x2000, vec![0x05]),
+        ]);
+        assert_eq!(left.content_eq(&right, None), Ok(()));
+    }
+
+    #[test]
+    fn test_content_eq_reports_changed_and_missing_bytes() {
+        let left = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x00; 8])]);
+        let right = HexFile::with_segments(vec![
+            Segment::new(0x1000, vec![0x00, 0x11, 0x22, 0x00, 0x00, 0x00]),
+            Segment::new(0x1007, vec![0x00, 0x33]),
+            Segment::new(0xFFFF_FFFF, vec![0x44]),
+        ]);
+        assert_eq!(
+            left.content_eq(&right, None),
+            Err(vec![
+                range(0x1001, 0x1002),
+                range(0x1006, 0x1006),
+                range(0x1008, 0x1008),
+                range(0xFFFF_FFFF, 0xFFFF_FFFF),
+            ])
+        );
+        assert_eq!(
+            left.content_eq(&right, Some(&[range(0x1003, 0x1005)])),
+            Ok(())
+        );
+    }
+}
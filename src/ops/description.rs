@@ -0,0 +1,956 @@
+//! Pipeline description documents (CLI: /PIPE), available with the `serde`
+//! feature.
+//!
+//! A description lists [`PipelineBuilder`] steps, each a table tagged by `op`:
+//!
+//! ```toml
+//! [[steps]]
+//! op = "fill"
+//! ranges = "0x1000-0x10FF"
+//! pattern = "FF"
+//!
+//! [[steps]]
+//! op = "merge"
+//! path = "cal.hex"
+//! mode = "preserve"
+//!
+//! [[steps]]
+//! op = "checksum"
+//! algorithm = 9
+//! target = "append"
+//! ```
+//!
+//! The same document in JSON is `{"steps": [{"op": "fill", ...}, ...]}`.
+//! Ranges use the HexView syntax of the matching CLI option (`START`, `END`
+//! and `ALL` where that option takes them, several joined by `:`), fill
+//! patterns the `/FP` syntax, dates and date formats the `/WDATE` syntax and
+//! `log` steps hold `/L` commands, one per line. Merges name the file to load;
+//! [`PipelineDescription::into_builder`] loads them. Unknown keys are errors.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    AddressTable, AlignOptions, AsciiPad, BankedMapOptions, ChecksumAlgorithm, ChecksumGapFill,
+    ChecksumOptions, ChecksumTarget, DateFormat, DateStamp, FillPattern, HexFile, Range, RangeSpec,
+    RemapOptions,
+};
+
+use super::{
+    LogCommand, MergeMode, Pipeline, PipelineBuilder, PipelineChecksum, PipelineDspic,
+    PipelineGhost, PipelineLabel, PipelineMerge, PipelineStamp, PipelineStep, SwapMode,
+    parse_log_commands, write_log_commands,
+};
+
+/// Steps of a [`PipelineBuilder`] as a TOML or JSON document.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineDescription {
+    #[serde(default)]
+    pub steps: Vec<StepDescription>,
+}
+
+/// One [`PipelineStep`], with merges as file paths.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case", deny_unknown_fields)]
+pub enum StepDescription {
+    /// Without `pattern` the gaps get random bytes. CLI: /FR, /FP.
+    Fill {
+        #[serde(with = "range_specs")]
+        ranges: Vec<RangeSpec>,
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "fill_pattern"
+        )]
+        pattern: Option<FillPattern>,
+    },
+    /// CLI: /FA.
+    FillAll {
+        #[serde(default = "default_fill")]
+        fill_byte: u8,
+    },
+    /// CLI: /CR.
+    Cut {
+        #[serde(with = "range_specs")]
+        ranges: Vec<RangeSpec>,
+    },
+    /// `overwrite` is /MO, `preserve` /MT.
+    Merge {
+        path: PathBuf,
+        #[serde(default)]
+        mode: MergeMode,
+        #[serde(default, skip_serializing_if = "is_default")]
+        offset: i64,
+        #[serde(default, skip_serializing_if = "Option::is_none", with = "text_opt")]
+        range: Option<Range>,
+    },
+    /// CLI: /LABEL.
+    Label {
+        #[serde(with = "text")]
+        range: Range,
+        name: String,
+    },
+    /// CLI: /AR.
+    Filter {
+        #[serde(with = "range_specs")]
+        ranges: Vec<RangeSpec>,
+    },
+    /// CLI: /L.
+    Log {
+        #[serde(with = "log_script")]
+        commands: Vec<LogCommand>,
+    },
+    /// CLI: /AD, /AF, /AL.
+    Align {
+        alignment: u32,
+        #[serde(default = "default_fill")]
+        fill_byte: u8,
+        #[serde(default, skip_serializing_if = "is_default")]
+        align_length: bool,
+    },
+    /// CLI: /SB.
+    Split {
+        size: u32,
+    },
+    /// CLI: /SBA.
+    SplitBoundary {
+        boundary: u32,
+    },
+    SwapWord,
+    SwapLong,
+    /// CLI: /S12MAP, or /S12MAP:... with `pages`.
+    S12Map {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pages: Option<PageWindow>,
+    },
+    /// CLI: /S12XMAP.
+    S12xMap {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pages: Option<PageWindow>,
+    },
+    /// CLI: /S08MAP.
+    S08Map {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pages: Option<PageWindow>,
+    },
+    /// CLI: /REMAP.
+    Remap {
+        start: u32,
+        end: u32,
+        linear: u32,
+        size: u32,
+        inc: u32,
+    },
+    /// CLI: /MAPTAB, with the table file's lines as one string.
+    MapTable {
+        #[serde(with = "text")]
+        table: AddressTable,
+    },
+    /// CLI: /CDSPX.
+    DspicExpand {
+        #[serde(with = "text")]
+        range: Range,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        target: Option<u32>,
+    },
+    /// CLI: /CDSPS.
+    DspicShrink {
+        #[serde(with = "text")]
+        range: Range,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        target: Option<u32>,
+    },
+    /// CLI: /CDSPG.
+    DspicClearGhost {
+        #[serde(with = "text")]
+        range: Range,
+    },
+    /// CLI: /CDSPI.
+    DspicInsertGhost {
+        #[serde(with = "text")]
+        range: Range,
+        #[serde(default, skip_serializing_if = "is_default")]
+        value: u8,
+    },
+    /// CLI: /WS.
+    WriteString {
+        address: u32,
+        text: String,
+        #[serde(default, skip_serializing_if = "is_default")]
+        nul_terminate: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        width: Option<usize>,
+        #[serde(default, skip_serializing_if = "is_default")]
+        fill: u8,
+    },
+    /// Without `date` the UTC system clock is used. CLI: /WDATE, /DATE.
+    WriteDate {
+        address: u32,
+        #[serde(with = "text")]
+        format: DateFormat,
+        #[serde(default, skip_serializing_if = "Option::is_none", with = "text_opt")]
+        date: Option<DateStamp>,
+    },
+    Normalize,
+    /// `algorithm` is the HexView index. CLI: /CS, /CSR, /CSB.
+    Checksum {
+        #[serde(with = "algorithm_index")]
+        algorithm: ChecksumAlgorithm,
+        target: ChecksumTarget,
+        #[serde(default, skip_serializing_if = "Option::is_none", with = "text_opt")]
+        range: Option<RangeSpec>,
+        #[serde(default, skip_serializing_if = "is_default")]
+        little_endian: bool,
+        #[serde(default, skip_serializing_if = "Vec::is_empty", with = "ranges")]
+        exclude: Vec<Range>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        initial: Option<u64>,
+        #[serde(default, skip_serializing_if = "is_default")]
+        target_as_fill: bool,
+        #[serde(default, skip_serializing_if = "is_default")]
+        gap_fill: ChecksumGapFill,
+        #[serde(default, skip_serializing_if = "is_default")]
+        per_block: bool,
+    },
+}
+
+/// Custom pages for a banked map step, as [`BankedMapOptions::paged`]
+/// takes them. Without them the step maps the standard windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PageWindow {
+    pub ppage_base: u8,
+    pub window_start: u32,
+    pub window_size: u32,
+    pub linear_base: u32,
+}
+
+impl PageWindow {
+    fn options(pages: Option<Self>, standard: BankedMapOptions) -> BankedMapOptions {
+        match pages {
+            Some(pages) => BankedMapOptions::paged(
+                pages.ppage_base,
+                pages.window_start,
+                pages.window_size,
+                pages.linear_base,
+            ),
+            None => standard,
+        }
+    }
+
+    fn describe(
+        map: &BankedMapOptions,
+        standard: BankedMapOptions,
+    ) -> Result<Option<Self>, DescriptionError> {
+        if *map == standard {
+            return Ok(None);
+        }
+        let pages = Self {
+            ppage_base: map.bank_min,
+            window_start: map.window_start,
+            window_size: map.window_size,
+            linear_base: map.linear_base,
+        };
+        if Self::options(Some(pages), standard) != *map {
+            return Err(DescriptionError::Unsupported(
+                "a banked map with custom fixed windows",
+            ));
+        }
+        Ok(Some(pages))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DescriptionError {
+    /// `path` locates the bad value, e.g. `steps[1].ranges`.
+    #[error("{}{message}", path_prefix(path))]
+    Invalid { path: String, message: String },
+    /// The step has no document form, e.g. a custom step.
+    #[error("{0} cannot be described")]
+    Unsupported(&'static str),
+    #[error("{0}")]
+    Write(String),
+}
+
+fn path_prefix(path: &str) -> String {
+    if path.is_empty() || path == "." {
+        String::new()
+    } else {
+        format!("{path}: ")
+    }
+}
+
+impl PipelineDescription {
+    pub fn from_toml(text: &str) -> Result<Self, DescriptionError> {
+        let line = |span: Option<std::ops::Range<usize>>| {
+            span.map(|span| {
+                let before = &text.as_bytes()[..span.start.min(text.len())];
+                before.iter().filter(|&&b| b == b'\n').count() + 1
+            })
+        };
+        let invalid = |path: String, err: toml::de::Error| DescriptionError::Invalid {
+            path,
+            message: match line(err.span()) {
+                Some(line) => format!("line {line}: {}", err.message()),
+                None => err.message().to_string(),
+            },
+        };
+        let deserializer =
+            toml::Deserializer::parse(text).map_err(|err| invalid(String::new(), err))?;
+        serde_path_to_error::deserialize(deserializer)
+            .map_err(|err| invalid(err.path().to_string(), err.into_inner()))
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, DescriptionError> {
+        let mut deserializer = serde_json::Deserializer::from_str(text);
+        let description = serde_path_to_error::deserialize(&mut deserializer).map_err(|err| {
+            DescriptionError::Invalid {
+                path: err.path().to_string(),
+                message: err.into_inner().to_string(),
+            }
+        })?;
+        deserializer
+            .end()
+            .map_err(|err| DescriptionError::Invalid {
+                path: String::new(),
+                message: err.to_string(),
+            })?;
+        Ok(description)
+    }
+
+    pub fn to_toml(&self) -> Result<String, DescriptionError> {
+        toml::to_string(self).map_err(|err| DescriptionError::Write(err.to_string()))
+    }
+
+    pub fn to_json(&self) -> Result<String, DescriptionError> {
+        serde_json::to_string_pretty(self).map_err(|err| DescriptionError::Write(err.to_string()))
+    }
+
+    /// Join relative merge paths onto `base`, e.g. the document's directory.
+    pub fn resolve_paths(&mut self, base: &Path) {
+        for step in &mut self.steps {
+            if let StepDescription::Merge { path, .. } = step
+                && path.is_relative()
+            {
+                *path = base.join(&*path);
+            }
+        }
+    }
+
+    /// The steps to run on `hexfile`, with each merge file read by `load`.
+    pub fn into_builder<L, E>(self, hexfile: HexFile, mut load: L) -> Result<PipelineBuilder, E>
+    where
+        L: FnMut(&Path) -> Result<HexFile, E>,
+    {
+        let mut builder = PipelineBuilder::new(hexfile);
+        for step in self.steps {
+            builder.steps.push(step.into_step(&mut load)?);
+        }
+        Ok(builder)
+    }
+}
+
+impl StepDescription {
+    fn into_step<L, E>(self, load: &mut L) -> Result<PipelineStep, E>
+    where
+        L: FnMut(&Path) -> Result<HexFile, E>,
+    {
+        Ok(match self {
+            Self::Fill { ranges, pattern } => PipelineStep::Fill { ranges, pattern },
+            Self::FillAll { fill_byte } => PipelineStep::FillAll {
+                fill_byte,
+                big_image: None,
+            },
+            Self::Cut { ranges } => PipelineStep::Cut(ranges),
+            Self::Merge {
+                path,
+                mode,
+                offset,
+                range,
+            } => PipelineStep::Merge {
+                merge: PipelineMerge {
+                    other: load(&path)?,
+                    offset,
+                    range,
+                    path: Some(path),
+                },
+                mode,
+            },
+            Self::Label { range, name } => PipelineStep::Label(PipelineLabel { range, name }),
+            Self::Filter { ranges } => PipelineStep::Filter(ranges),
+            Self::Log { commands } => PipelineStep::Log(commands),
+            Self::Align {
+                alignment,
+                fill_byte,
+                align_length,
+            } => PipelineStep::Align(AlignOptions {
+                alignment,
+                fill_byte,
+                align_length,
+            }),
+            Self::Split { size } => PipelineStep::Split(size),
+            Self::SplitBoundary { boundary } => PipelineStep::SplitAtBoundaries(boundary),
+            Self::SwapWord => PipelineStep::Swap(SwapMode::Word),
+            Self::SwapLong => PipelineStep::Swap(SwapMode::DWord),
+            Self::S12Map { pages } => {
+                PipelineStep::MapStar12(PageWindow::options(pages, BankedMapOptions::STAR12))
+            }
+            Self::S12xMap { pages } => {
+                PipelineStep::MapStar12x(PageWindow::options(pages, BankedMapOptions::STAR12X))
+            }
+            Self::S08Map { pages } => {
+                PipelineStep::MapStar08(PageWindow::options(pages, BankedMapOptions::STAR08))
+            }
+            Self::Remap {
+                start,
+                end,
+                linear,
+                size,
+                inc,
+            } => PipelineStep::Remap(RemapOptions {
+                start,
+                end,
+                linear,
+                size,
+                inc,
+            }),
+            Self::MapTable { table } => PipelineStep::RemapTable(table),
+            Self::DspicExpand { range, target } => {
+                PipelineStep::DspicExpand(PipelineDspic { range, target })
+            }
+            Self::DspicShrink { range, target } => {
+                PipelineStep::DspicShrink(PipelineDspic { range, target })
+            }
+            Self::DspicClearGhost { range } => PipelineStep::DspicClearGhost(range),
+            Self::DspicInsertGhost { range, value } => {
+                PipelineStep::DspicInsertGhost(PipelineGhost { range, value })
+            }
+            Self::WriteString {
+                address,
+                text,
+                nul_terminate,
+                width,
+                fill,
+            } => PipelineStep::Stamp(PipelineStamp::Ascii {
+                address,
+                text,
+                pad: AsciiPad {
+                    nul_terminate,
+                    width,
+                    fill,
+                },
+            }),
+            Self::WriteDate {
+                address,
+                format,
+                date,
+            } => PipelineStep::Stamp(PipelineStamp::Date {
+                address,
+                format,
+                date: date.unwrap_or_else(DateStamp::now),
+            }),
+            Self::Normalize => PipelineStep::Normalize,
+            Self::Checksum {
+                algorithm,
+                target,
+                range,
+                little_endian,
+                exclude,
+                initial,
+                target_as_fill,
+                gap_fill,
+                per_block,
+            } => PipelineStep::Checksum(PipelineChecksum {
+                options: ChecksumOptions {
+                    algorithm,
+                    little_endian_output: little_endian,
+                    exclude_ranges: exclude,
+                    initial,
+                    target_as_fill,
+                    gap_fill,
+                    ..Default::default()
+                },
+                target,
+                range,
+                per_block,
+            }),
+        })
+    }
+}
+
+impl TryFrom<&PipelineStep> for StepDescription {
+    type Error = DescriptionError;
+
+    /// `FillAll` loses its big-image limits, which only change how it runs.
+    fn try_from(step: &PipelineStep) -> Result<Self, Self::Error> {
+        Ok(match step {
+            PipelineStep::Fill { ranges, pattern } => Self::Fill {
+                ranges: ranges.clone(),
+                pattern: pattern.clone(),
+            },
+            PipelineStep::FillAll { fill_byte, .. } => Self::FillAll {
+                fill_byte: *fill_byte,
+            },
+            PipelineStep::Cut(ranges) => Self::Cut {
+                ranges: ranges.clone(),
+            },
+            PipelineStep::Merge { merge, mode } => Self::Merge {
+                path: merge
+                    .path
+                    .clone()
+                    .ok_or(DescriptionError::Unsupported("a merge without a path"))?,
+                mode: *mode,
+                offset: merge.offset,
+                range: merge.range,
+            },
+            PipelineStep::Label(label) => Self::Label {
+                range: label.range,
+                name: label.name.clone(),
+            },
+            PipelineStep::Filter(ranges) => Self::Filter {
+                ranges: ranges.clone(),
+            },
+            PipelineStep::Log(commands) => Self::Log {
+                commands: commands.clone(),
+            },
+            PipelineStep::Align(align) => Self::Align {
+                alignment: align.alignment,
+                fill_byte: align.fill_byte,
+                align_length: align.align_length,
+            },
+            PipelineStep::Split(size) => Self::Split { size: *size },
+            PipelineStep::SplitAtBoundaries(boundary) => Self::SplitBoundary {
+                boundary: *boundary,
+            },
+            PipelineStep::Swap(SwapMode::Word) => Self::SwapWord,
+            PipelineStep::Swap(SwapMode::DWord) => Self::SwapLong,
+            PipelineStep::MapStar12(map) => Self::S12Map {
+                pages: PageWindow::describe(map, BankedMapOptions::STAR12)?,
+            },
+            PipelineStep::MapStar12x(map) => Self::S12xMap {
+                pages: PageWindow::describe(map, BankedMapOptions::STAR12X)?,
+            },
+            PipelineStep::MapStar08(map) => Self::S08Map {
+                pages: PageWindow::describe(map, BankedMapOptions::STAR08)?,
+            },
+            PipelineStep::Remap(remap) => Self::Remap {
+                start: remap.start,
+                end: remap.end,
+                linear: remap.linear,
+                size: remap.size,
+                inc: remap.inc,
+            },
+            PipelineStep::RemapTable(table) => Self::MapTable {
+                table: table.clone(),
+            },
+            PipelineStep::DspicExpand(op) => Self::DspicExpand {
+                range: op.range,
+                target: op.target,
+            },
+            PipelineStep::DspicShrink(op) => Self::DspicShrink {
+                range: op.range,
+                target: op.target,
+            },
+            PipelineStep::DspicClearGhost(range) => Self::DspicClearGhost { range: *range },
+            PipelineStep::DspicInsertGhost(op) => Self::DspicInsertGhost {
+                range: op.range,
+                value: op.value,
+            },
+            PipelineStep::Stamp(PipelineStamp::Ascii { address, text, pad }) => Self::WriteString {
+                address: *address,
+                text: text.clone(),
+                nul_terminate: pad.nul_terminate,
+                width: pad.width,
+                fill: pad.fill,
+            },
+            PipelineStep::Stamp(PipelineStamp::Date {
+                address,
+                format,
+                date,
+            }) => Self::WriteDate {
+                address: *address,
+                format: format.clone(),
+                date: Some(*date),
+            },
+            PipelineStep::Normalize => Self::Normalize,
+            PipelineStep::Checksum(checksum) => {
+                let options = &checksum.options;
+                if options.forced_range.is_some() {
+                    return Err(DescriptionError::Unsupported("a forced checksum range"));
+                }
+                Self::Checksum {
+                    algorithm: options.algorithm,
+                    target: checksum.target.clone(),
+                    range: checksum.range.or(options.range.map(RangeSpec::from)),
+                    little_endian: options.little_endian_output,
+                    exclude: options.exclude_ranges.clone(),
+                    initial: options.initial,
+                    target_as_fill: options.target_as_fill,
+                    gap_fill: options.gap_fill,
+                    per_block: checksum.per_block,
+                }
+            }
+            PipelineStep::Custom(_) => return Err(DescriptionError::Unsupported("a custom step")),
+        })
+    }
+}
+
+impl TryFrom<&PipelineBuilder> for PipelineDescription {
+    type Error = DescriptionError;
+
+    /// Fills and cuts without ranges do nothing and are left out.
+    fn try_from(builder: &PipelineBuilder) -> Result<Self, Self::Error> {
+        let steps = builder.steps.iter().filter(|step| !step.does_nothing());
+        Ok(Self {
+            steps: steps
+                .map(StepDescription::try_from)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl TryFrom<Pipeline> for PipelineDescription {
+    type Error = DescriptionError;
+
+    /// The steps [`Pipeline::execute`] would run, in that order.
+    fn try_from(pipeline: Pipeline) -> Result<Self, Self::Error> {
+        Self::try_from(&pipeline.into_steps())
+    }
+}
+
+fn default_fill() -> u8 {
+    0xFF
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+/// A value as its `Display` text, read back with `FromStr`.
+mod text {
+    use super::*;
+
+    pub fn serialize<T: fmt::Display, S: serde::Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+        D: serde::Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+mod text_opt {
+    use super::*;
+
+    pub fn serialize<T: fmt::Display, S: serde::Serializer>(
+        value: &Option<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.collect_str(value),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+        D: serde::Deserializer<'de>,
+    {
+        super::text::deserialize(deserializer).map(Some)
+    }
+}
+
+/// `0x1000-0x1FFF:END-0x2000`, as /FR, /CR and /AR take them.
+mod range_specs {
+    use super::*;
+
+    pub fn serialize<S: serde::Serializer>(
+        specs: &[RangeSpec],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let text: Vec<_> = specs.iter().map(RangeSpec::to_string).collect();
+        serializer.serialize_str(&text.join(":"))
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<RangeSpec>, D::Error> {
+        crate::parse_hexview_range_specs(&String::deserialize(deserializer)?)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Concrete ranges joined by `:`, as the /CS exclude suffix takes them.
+mod ranges {
+    use super::*;
+
+    pub fn serialize<S: serde::Serializer>(
+        ranges: &[Range],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let text: Vec<_> = ranges.iter().map(Range::to_string).collect();
+        serializer.serialize_str(&text.join(":"))
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Range>, D::Error> {
+        crate::parse_hexview_ranges(&String::deserialize(deserializer)?)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Hex bytes, or `INC[:start[:step]]` for a byte ramp, as /FP takes them;
+/// `RANDOM[:seed]` for seeded random bytes.
+mod fill_pattern {
+    use super::*;
+
+    pub fn serialize<S: serde::Serializer>(
+        pattern: &Option<FillPattern>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match pattern {
+            Some(FillPattern::Bytes(bytes)) => crate::io::hex_bytes::serialize(bytes, serializer),
+            Some(FillPattern::Increment { start, step }) => {
+                serializer.collect_str(&format_args!("INC:0x{start:02X}:0x{step:02X}"))
+            }
+            Some(FillPattern::Random { seed: Some(seed) }) => {
+                serializer.collect_str(&format_args!("RANDOM:{seed}"))
+            }
+            Some(FillPattern::Random { seed: None }) => serializer.serialize_str("RANDOM"),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<FillPattern>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let mut parts = text.trim().split(':');
+        let kind = parts.next().unwrap_or_default().trim();
+        if kind.eq_ignore_ascii_case("RANDOM") {
+            let seed = match parts.next().map(str::trim) {
+                None => None,
+                Some(seed) => Some(
+                    match seed.strip_prefix("0x").or_else(|| seed.strip_prefix("0X")) {
+                        Some(hex) => u64::from_str_radix(hex, 16),
+                        None => seed.parse(),
+                    }
+                    .map_err(|_| {
+                        serde::de::Error::custom(format!("invalid random seed {seed:?}"))
+                    })?,
+                ),
+            };
+            if parts.next().is_some() {
+                return Err(serde::de::Error::custom(format!(
+                    "invalid fill pattern {text:?}"
+                )));
+            }
+            return Ok(Some(FillPattern::Random { seed }));
+        }
+        if !kind.eq_ignore_ascii_case("INC") {
+            let bytes: Vec<u8> = crate::io::hex_bytes::deserialize(
+                serde::de::value::StrDeserializer::<D::Error>::new(text.trim()),
+            )?;
+            if bytes.is_empty() {
+                return Err(serde::de::Error::custom("empty fill pattern"));
+            }
+            return Ok(Some(FillPattern::Bytes(bytes)));
+        }
+        let mut byte = |default: u8| match parts.next() {
+            Some(part) => {
+                let part = part.trim();
+                match part.strip_prefix("0x").or_else(|| part.strip_prefix("0X")) {
+                    Some(hex) => u8::from_str_radix(hex, 16),
+                    None => part.parse(),
+                }
+                .map_err(|_| serde::de::Error::custom(format!("invalid pattern byte {part:?}")))
+            }
+            None => Ok(default),
+        };
+        let start = byte(0)?;
+        let step = byte(1)?;
+        if parts.next().is_some() {
+            return Err(serde::de::Error::custom(format!(
+                "invalid fill pattern {text:?}"
+            )));
+        }
+        Ok(Some(FillPattern::Increment { start, step }))
+    }
+}
+
+/// `/L` commands, one per line.
+mod log_script {
+    use super::*;
+
+    pub fn serialize<S: serde::Serializer>(
+        commands: &[LogCommand],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let kinds: Vec<_> = commands
+            .iter()
+            .map(|command| command.kind.clone())
+            .collect();
+        serializer.serialize_str(&write_log_commands(&kinds))
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<LogCommand>, D::Error> {
+        parse_log_commands(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The HexView algorithm index, as in /CS9.
+mod algorithm_index {
+    use super::*;
+
+    pub fn serialize<S: serde::Serializer>(
+        algorithm: &ChecksumAlgorithm,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*algorithm as u8)
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ChecksumAlgorithm, D::Error> {
+        ChecksumAlgorithm::from_index(u8::deserialize(deserializer)?)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Segment;
+
+    #[test]
+    fn test_description_round_trips_through_toml_and_json() {
+        let description = PipelineDescription {
+            steps: vec![
+                StepDescription::Fill {
+                    ranges: vec![
+                        Range::from_start_end(0x1000, 0x10FF).unwrap().into(),
+                        RangeSpec::new(Some(0x2000), None),
+                    ],
+                    pattern: Some(FillPattern::Increment { start: 0, step: 2 }),
+                },
+                StepDescription::Fill {
+                    ranges: vec![Range::from_start_end(0x3000, 0x30FF).unwrap().into()],
+                    pattern: Some(FillPattern::Random { seed: Some(0x1234) }),
+                },
+                StepDescription::Merge {
+                    path: PathBuf::from("cal.hex"),
+                    mode: MergeMode::Preserve,
+                    offset: -0x10,
+                    range: Some(Range::from_start_length(0x3000, 0x10).unwrap()),
+                },
+                StepDescription::SwapWord,
+                StepDescription::S12Map {
+                    pages: Some(PageWindow {
+                        ppage_base: 0x20,
+                        window_start: 0x8000,
+                        window_size: 0x2000,
+                        linear_base: 0x400000,
+                    }),
+                },
+                StepDescription::Checksum {
+                    algorithm: ChecksumAlgorithm::Crc32,
+                    target: ChecksumTarget::Address(0x1FFC),
+                    range: Some(RangeSpec::ALL),
+                    little_endian: true,
+                    exclude: vec![Range::from_start_length(0x1800, 4).unwrap()],
+                    initial: None,
+                    target_as_fill: false,
+                    gap_fill: ChecksumGapFill::Fill(0x00),
+                    per_block: false,
+                },
+            ],
+        };
+
+        let toml = description.to_toml().unwrap();
+        assert_eq!(PipelineDescription::from_toml(&toml).unwrap(), description);
+        let json = description.to_json().unwrap();
+        assert_eq!(PipelineDescription::from_json(&json).unwrap(), description);
+    }
+
+    #[test]
+    fn test_description_unknown_key_names_its_path() {
+        let err = PipelineDescription::from_toml(
+            "[[steps]]\nop = \"cut\"\nranges = \"0x0-0xF\"\n\n[[steps]]\nop = \"fill\"\nrange = \"0x0-0xF\"\n",
+        )
+        .unwrap_err();
+        let message = err.to_string();
+        assert!(message.starts_with("steps[1]: "), "{message}");
+        assert!(message.contains("unknown field `range`"), "{message}");
+
+        let err = PipelineDescription::from_json(r#"{"steps": [], "step": []}"#).unwrap_err();
+        assert!(err.to_string().contains("unknown field `step`"), "{err}");
+    }
+
+    #[test]
+    fn test_description_builds_and_describes_pipelines() {
+        let description = PipelineDescription::from_toml(
+            "[[steps]]\nop = \"merge\"\npath = \"patch.hex\"\n\n\
+             [[steps]]\nop = \"checksum\"\nalgorithm = 0\ntarget = \"append\"\n",
+        )
+        .unwrap();
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01])]);
+        let result = description
+            .clone()
+            .into_builder(hexfile, |path| {
+                assert_eq!(path, Path::new("patch.hex"));
+                Ok::<_, std::io::Error>(HexFile::with_segments(vec![Segment::new(
+                    0x1001,
+                    vec![0x02],
+                )]))
+            })
+            .unwrap()
+            .execute_without_log(|_| Vec::new())
+            .unwrap();
+        assert_eq!(result.checksum_bytes, [[[0x00, 0x03]]]);
+
+        let pipeline = Pipeline {
+            merge_opaque: vec![PipelineMerge {
+                other: HexFile::new(),
+                offset: 0,
+                range: None,
+                path: Some(PathBuf::from("patch.hex")),
+            }],
+            checksums: vec![PipelineChecksum::new(
+                ChecksumOptions::default(),
+                ChecksumTarget::Append,
+            )],
+            ..Default::default()
+        };
+        assert_eq!(
+            PipelineDescription::try_from(pipeline).unwrap(),
+            description
+        );
+
+        let custom =
+            PipelineBuilder::new(HexFile::new()).push(PipelineStep::Custom(Box::new(|_| Ok(()))));
+        assert!(matches!(
+            PipelineDescription::try_from(&custom),
+            Err(DescriptionError::Unsupported(_))
+        ));
+    }
+}
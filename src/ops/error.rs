@@ -5,6 +5,9 @@ pub enum OpsError {
     #[error("address overflow: {0}")]
     AddressOverflow(String),
 
+    #[error("address underflow: {0}")]
+    AddressUnderflow(String),
+
     #[error("address {address:#X} not divisible by {divisor}")]
     AddressNotDivisible { address: u32, divisor: u32 },
 
@@ -21,9 +24,35 @@ pub enum OpsError {
     #[error("unsupported checksum algorithm index: {0}")]
     UnsupportedChecksumAlgorithm(u8),
 
+    #[error("invalid initial value {value:#X} for checksum algorithm {algorithm}")]
+    InvalidChecksumInitial { algorithm: u8, value: u64 },
+
+    #[error("per-block checksum: {0}")]
+    BlockChecksum(String),
+
     #[error("invalid remap parameters: {0}")]
     InvalidRemapParams(String),
 
+    #[error("address collision: {0}")]
+    AddressCollision(String),
+
+    #[error("address {address:#X} lies outside the bank window")]
+    OutsideBankWindow { address: u32 },
+
+    #[error("address {address:#X} lies outside every sector")]
+    OutsideSectorMap { address: u32 },
+
+    #[error("invalid stamp: {0}")]
+    InvalidStamp(String),
+
+    #[error("fill span {start:#X}..={end:#X} is {span} bytes, over the {max}-byte limit")]
+    FillSpanTooLarge {
+        start: u32,
+        end: u32,
+        span: u64,
+        max: u64,
+    },
+
     #[error("range not fully covered by data: start {start:#X}, length {length}")]
     RangeNotCovered { start: u32, length: u32 },
 
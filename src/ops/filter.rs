@@ -1,26 +1,112 @@
 use super::OpsError;
-use crate::{HexFile, Range, Segment};
+use super::flags::{random_fill_bytes, random_fill_seed, random_fill_seed_from_time};
+use crate::{HexFile, Range, RangeSet, Segment};
+
+/// Largest span [`HexFile::fill_gaps`] fills before it errors: 256 MiB.
+pub const DEFAULT_MAX_FILL_SPAN: u64 = 256 * 1024 * 1024;
+
+/// Bytes written by a fill, aligned to the start of each filled range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FillPattern {
+    /// Bytes repeated across the range.
+    Bytes(Vec<u8>),
+    /// Ramp `start, start + step, ...`, wrapping past 0xFF.
+    Increment { start: u8, step: u8 },
+    /// Pseudo-random bytes that depend on the seed and the filled range
+    /// (CLI: /FR without /FP, /FRSEED). The same seed and range give the same
+    /// bytes in every version; `None` seeds each range from the clock.
+    ///
+    /// Each range gets its own generator: the state starts as
+    /// `seed ^ (start << 32) ^ length` put through the splitmix64 finalizer
+    /// (a zero result becomes `0x9E3779B97F4A7C15`). For every byte the state
+    /// steps to `state * 6364136223846793005 + 1` (mod 2^64) and the byte is
+    /// bits 32..40 of the new state.
+    Random { seed: Option<u64> },
+}
 
-/// Options for fill operations.
-#[derive(Debug, Clone)]
-pub struct FillOptions {
-    /// Pattern to repeat (default: 0xFF)
-    pub pattern: Vec<u8>,
-    /// If true, overwrites existing data; if false, only fills gaps
-    pub overwrite: bool,
+impl FillPattern {
+    /// Byte at `offset` from the start of the filled range. A
+    /// [`FillPattern::Random`] is treated as filling from address 0, as in
+    /// [`FillPattern::bytes`].
+    pub fn byte_at(&self, offset: usize) -> u8 {
+        match self {
+            Self::Bytes(bytes) => bytes[offset % bytes.len()],
+            Self::Increment { start, step } => {
+                start.wrapping_add((offset as u8).wrapping_mul(*step))
+            }
+            Self::Random { .. } => self.bytes(offset, 1)[0],
+        }
+    }
+
+    /// `len` bytes of the pattern starting `offset` bytes into the range.
+    /// Random bytes depend on the range, so for [`FillPattern::Random`] this
+    /// is the tail of a fill of `0..offset + len`; use
+    /// [`FillPattern::range_bytes`] for a real range.
+    pub fn bytes(&self, offset: usize, len: usize) -> Vec<u8> {
+        if let Self::Random { .. } = self {
+            if len == 0 {
+                return Vec::new();
+            }
+            let end = u32::try_from(offset + len).expect("fill pattern offset within u32");
+            let range = Range::from_start_length(0, end).expect("non-empty range from 0");
+            return self.range_bytes(range)[offset..].to_vec();
+        }
+        (offset..offset + len).map(|i| self.byte_at(i)).collect()
+    }
+
+    /// The bytes a fill of the whole of `range` writes.
+    pub fn range_bytes(&self, range: Range) -> Vec<u8> {
+        match self {
+            Self::Random { seed } => {
+                let seed = match seed {
+                    Some(seed) => random_fill_seed(*seed, range),
+                    None => random_fill_seed_from_time(range),
+                };
+                random_fill_bytes(range, seed)
+            }
+            _ => self.bytes(0, range.length() as usize),
+        }
+    }
+
+    /// One full period; repeating it reproduces the pattern. Random bytes do
+    /// not repeat, so [`FillPattern::Random`] gives its first 256 bytes.
+    pub fn period(&self) -> Vec<u8> {
+        match self {
+            Self::Bytes(bytes) => bytes.clone(),
+            Self::Increment { .. } | Self::Random { .. } => self.bytes(0, 256),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Self::Bytes(bytes) if bytes.is_empty())
+    }
 }
 
-impl Default for FillOptions {
+impl Default for FillPattern {
     fn default() -> Self {
-        Self {
-            pattern: vec![0xFF],
-            overwrite: false,
-        }
+        Self::Bytes(vec![0xFF])
+    }
+}
+
+impl From<Vec<u8>> for FillPattern {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::Bytes(bytes)
     }
 }
 
+/// Options for fill operations.
+#[derive(Debug, Clone, Default)]
+pub struct FillOptions {
+    /// Pattern to write (default: 0xFF)
+    pub pattern: FillPattern,
+    /// If true, overwrites existing data; if false, only fills gaps
+    pub overwrite: bool,
+}
+
 /// Mode for merging files.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum MergeMode {
     /// New data overwrites existing (opaque)
     #[default]
@@ -62,10 +148,10 @@ impl HexFile {
             return;
         }
 
-        let merged_ranges = merge_ranges(ranges);
+        let keep = RangeSet::from_ranges(ranges);
         let mut new_segments = Vec::new();
 
-        for segment in self.segments() {
+        for segment in self.segments_mut().drain(..) {
             if segment.is_empty() {
                 continue;
             }
@@ -75,13 +161,16 @@ impl HexFile {
                     Err(_) => continue,
                 };
 
-            for range in &merged_ranges {
-                if let Some(intersection) = seg_range.intersection(range) {
-                    let start_offset = (intersection.start() - segment.start_address) as usize;
-                    let end_offset = (intersection.end() - segment.start_address) as usize + 1;
-                    let data = segment.data[start_offset..end_offset].to_vec();
-                    new_segments.push(Segment::new(intersection.start(), data));
-                }
+            // Fully kept - move the buffer instead of copying it
+            if keep.clip(seg_range).eq([seg_range]) {
+                new_segments.push(segment);
+                continue;
+            }
+            for intersection in keep.clip(seg_range) {
+                let start_offset = (intersection.start() - segment.start_address) as usize;
+                let end_offset = (intersection.end() - segment.start_address) as usize + 1;
+                let data = segment.data[start_offset..end_offset].to_vec();
+                new_segments.push(segment.piece(intersection.start(), data));
             }
         }
 
@@ -94,40 +183,68 @@ impl HexFile {
     }
 
     /// Remove data within multiple ranges (operates on raw segments; preserves order).
+    /// The ranges are merged once, so the result matches cutting them one by one.
     pub fn cut_ranges(&mut self, ranges: &[Range]) {
-        for range in ranges {
-            let mut new_segments = Vec::new();
+        if ranges.is_empty() {
+            return;
+        }
+        let cuts = RangeSet::from_ranges(ranges);
+        let mut new_segments = Vec::new();
 
-            for segment in self.segments_mut().drain(..) {
-                if segment.is_empty() {
-                    continue;
-                }
-                let seg_start = segment.start_address;
-                let seg_end = segment.end_address();
+        for segment in self.segments_mut().drain(..) {
+            if segment.is_empty() {
+                continue;
+            }
+            let Ok(seg_range) = Range::from_start_end(segment.start_address, segment.end_address())
+            else {
+                new_segments.push(segment);
+                continue;
+            };
 
-                // No overlap - keep entire segment
-                if seg_end < range.start() || seg_start > range.end() {
-                    new_segments.push(segment);
-                    continue;
-                }
+            let kept = cuts.gaps_within(seg_range);
+            // No overlap - keep entire segment
+            if kept == [seg_range] {
+                new_segments.push(segment);
+                continue;
+            }
+            for piece in kept {
+                let start_offset = (piece.start() - seg_range.start()) as usize;
+                let end_offset = (piece.end() - seg_range.start()) as usize + 1;
+                let data = segment.data[start_offset..end_offset].to_vec();
+                new_segments.push(segment.piece(piece.start(), data));
+            }
+        }
 
-                // Keep portion before the cut
-                if seg_start < range.start() {
-                    let end_offset = (range.start() - seg_start) as usize;
-                    let data = segment.data[..end_offset].to_vec();
-                    new_segments.push(Segment::new(seg_start, data));
-                }
+        self.set_segments(new_segments);
+    }
 
-                // Keep portion after the cut
-                if seg_end > range.end() {
-                    let start_offset = (range.end() - seg_start + 1) as usize;
-                    let data = segment.data[start_offset..].to_vec();
-                    new_segments.push(Segment::new(range.end() + 1, data));
+    /// Label the data within `range` as `name`, splitting segments at its
+    /// edges (operates on raw segments; preserves order). Data outside keeps
+    /// its label; gaps stay gaps.
+    pub fn label_range(&mut self, range: Range, name: impl Into<String>) {
+        let name = name.into();
+        let mut new_segments = Vec::with_capacity(self.segments().len());
+        for mut segment in self.segments_mut().drain(..) {
+            let inside = Range::from_start_end(segment.start_address, segment.end_address())
+                .ok()
+                .and_then(|seg_range| seg_range.intersection(&range));
+            let Some(inside) = inside.filter(|_| !segment.is_empty()) else {
+                new_segments.push(segment);
+                continue;
+            };
+            let head_len = (inside.start() - segment.start_address) as usize;
+            let data = segment.data.split_off(head_len);
+            let mut labeled = segment.piece(inside.start(), data);
+            let tail = labeled.data.split_off(inside.length() as usize);
+            labeled.name = Some(name.clone());
+            let tail = segment.piece(inside.end().wrapping_add(1), tail);
+            for piece in [segment, labeled, tail] {
+                if !piece.is_empty() {
+                    new_segments.push(piece);
                 }
             }
-
-            self.set_segments(new_segments);
         }
+        self.set_segments(new_segments);
     }
 
     /// Fill a region with the specified pattern.
@@ -148,12 +265,7 @@ impl HexFile {
             if options.overwrite {
                 // Remove existing data in range, then fill entire range
                 self.cut(*range);
-                let len = range.length() as usize;
-                let mut data = Vec::with_capacity(len);
-                let pattern = &options.pattern;
-                for i in 0..len {
-                    data.push(pattern[i % pattern.len()]);
-                }
+                let data = options.pattern.range_bytes(*range);
                 self.append_segment(Segment::new(range.start(), data));
             } else {
                 // Fill only gaps within the range - existing data preserved
@@ -163,92 +275,52 @@ impl HexFile {
     }
 
     /// Fill gaps within a specific range with a pattern. Does not touch existing data.
-    fn fill_gaps_in_range(&mut self, range: Range, pattern: &[u8]) {
-        // Collect existing data segments that overlap with the range
-        let mut occupied: Vec<(u32, u32)> = Vec::new();
-        for segment in self.segments() {
-            if segment.is_empty() {
-                continue;
-            }
-            let seg_start = segment.start_address;
-            let seg_end = segment.end_address();
-
-            // Check if segment overlaps with range
-            if seg_end >= range.start() && seg_start <= range.end() {
-                let clipped_start = seg_start.max(range.start());
-                let clipped_end = seg_end.min(range.end());
-                occupied.push((clipped_start, clipped_end));
-            }
-        }
-
-        // Sort by start address
-        occupied.sort_by_key(|&(start, _)| start);
-
-        // Merge overlapping/adjacent intervals
-        let mut merged: Vec<(u32, u32)> = Vec::new();
-        for (start, end) in occupied {
-            if let Some(last) = merged.last_mut()
-                && start <= last.1.saturating_add(1)
-            {
-                // Overlapping or adjacent - extend
-                last.1 = last.1.max(end);
-                continue;
-            }
-            merged.push((start, end));
-        }
-
-        // Find gaps and fill them
-        let mut cursor = range.start();
-        for (occ_start, occ_end) in merged {
-            if cursor < occ_start {
-                // Gap from cursor to occ_start-1
-                let gap_start = cursor;
-                let gap_end = occ_start - 1;
-                let len = (gap_end - gap_start + 1) as usize;
-                let offset = (gap_start - range.start()) as usize;
-                let mut data = Vec::with_capacity(len);
-                for i in 0..len {
-                    data.push(pattern[(offset + i) % pattern.len()]);
-                }
-                self.append_segment(Segment::new(gap_start, data));
-            }
-            cursor = occ_end.saturating_add(1);
-        }
-
-        // Fill trailing gap if any
-        if cursor <= range.end() {
-            let gap_start = cursor;
-            let gap_end = range.end();
-            let len = (gap_end - gap_start + 1) as usize;
-            let offset = (gap_start - range.start()) as usize;
-            let mut data = Vec::with_capacity(len);
-            for i in 0..len {
-                data.push(pattern[(offset + i) % pattern.len()]);
-            }
-            self.append_segment(Segment::new(gap_start, data));
+    fn fill_gaps_in_range(&mut self, range: Range, pattern: &FillPattern) {
+        let occupied: RangeSet = self
+            .segments()
+            .iter()
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| Range::from_start_end(s.start_address, s.end_address()).ok())
+            .filter_map(|r| r.intersection(&range))
+            .collect();
+
+        // Random bytes depend on the whole range, so generate them once.
+        let random =
+            matches!(pattern, FillPattern::Random { .. }).then(|| pattern.range_bytes(range));
+        for gap in occupied.gaps_within(range) {
+            let offset = (gap.start() - range.start()) as usize;
+            let len = gap.length() as usize;
+            let data = match &random {
+                Some(bytes) => bytes[offset..offset + len].to_vec(),
+                None => pattern.bytes(offset, len),
+            };
+            self.append_segment(Segment::new(gap.start(), data));
         }
     }
 
     /// Fill all gaps between first and last segment with fill byte.
-    /// Result: single contiguous segment (normalizes with last-wins).
-    /// Returns silently if the span is too large (>= 4GiB).
-    pub fn fill_gaps(&mut self, fill_byte: u8) {
-        let normalized = self.normalized_lossy();
-        let Some(min_addr) = normalized.min_address() else {
-            return;
-        };
-        let Some(max_addr) = normalized.max_address() else {
-            return;
-        };
+    /// Result: single contiguous segment (normalizes with last-wins). With
+    /// labelled segments the segments are kept and each gap gets an
+    /// unlabelled fill segment instead, so the labels survive.
+    /// Errors without allocating if the span exceeds [`DEFAULT_MAX_FILL_SPAN`].
+    pub fn fill_gaps(&mut self, fill_byte: u8) -> Result<(), OpsError> {
+        self.fill_gaps_limited(fill_byte, DEFAULT_MAX_FILL_SPAN)
+    }
 
-        // Compute span in u64 to avoid overflow
-        let span = (max_addr as u64) - (min_addr as u64) + 1;
-        if span > usize::MAX as u64 {
-            return;
+    /// [`fill_gaps`](Self::fill_gaps) with a caller-chosen span limit in bytes.
+    pub fn fill_gaps_limited(&mut self, fill_byte: u8, max_span: u64) -> Result<(), OpsError> {
+        let Some((min_addr, span)) = self.checked_fill_span(max_span)? else {
+            return Ok(());
+        };
+        if self.segments().iter().any(|s| s.name.is_some()) {
+            let end = (min_addr as u64 + span - 1) as u32;
+            let range = Range::from_start_end(min_addr, end).expect("span within u32");
+            self.fill_gaps_in_range(range, &FillPattern::Bytes(vec![fill_byte]));
+            return Ok(());
         }
 
-        let total_len = span as usize;
-        let mut data = vec![fill_byte; total_len];
+        let normalized = self.normalized_lossy();
+        let mut data = vec![fill_byte; span as usize];
 
         // Copy existing data into the buffer
         for segment in normalized.segments() {
@@ -257,11 +329,113 @@ impl HexFile {
         }
 
         self.set_segments(vec![Segment::new(min_addr, data)]);
+        Ok(())
+    }
+
+    /// Fill the gaps between data inside `range` only; data and gaps outside
+    /// it are left alone. Keeps the allocation bounded by the window size.
+    pub fn fill_gaps_within(&mut self, range: Range, fill_byte: u8) {
+        let inside = self
+            .segments()
+            .iter()
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| Range::from_start_end(s.start_address, s.end_address()).ok())
+            .filter_map(|r| r.intersection(&range));
+        let (start, end) = inside.fold((u32::MAX, 0), |(start, end), r| {
+            (start.min(r.start()), end.max(r.end()))
+        });
+        if let Ok(window) = Range::from_start_end(start, end) {
+            self.fill_gaps_in_range(window, &FillPattern::Bytes(vec![fill_byte]));
+        }
+    }
+
+    /// The error [`fill_gaps`](Self::fill_gaps) would return for the span of
+    /// this image, without filling anything. For callers that fill the gaps
+    /// as they write the image out.
+    pub fn check_fill_span(&self) -> Result<(), OpsError> {
+        self.checked_fill_span(DEFAULT_MAX_FILL_SPAN).map(|_| ())
+    }
+
+    /// Start and length of the data span, or an error if it exceeds `max_span`.
+    fn checked_fill_span(&self, max_span: u64) -> Result<Option<(u32, u64)>, OpsError> {
+        let (Some(start), Some(end)) = (self.min_address(), self.max_address()) else {
+            return Ok(None);
+        };
+        let span = end as u64 - start as u64 + 1;
+        if span > max_span.min(usize::MAX as u64) {
+            return Err(OpsError::FillSpanTooLarge {
+                start,
+                end,
+                span,
+                max: max_span,
+            });
+        }
+        Ok(Some((start, span)))
+    }
+
+    /// Like [`fill_gaps`](Self::fill_gaps), but keeps the existing segments and
+    /// fills each gap with new segments of at most `block_size` bytes, so no
+    /// buffer spans the whole image. The content is the same; only the
+    /// segment layout differs. The same span limit applies.
+    pub fn fill_gaps_chunked(&mut self, fill_byte: u8, block_size: usize) -> Result<(), OpsError> {
+        self.checked_fill_span(DEFAULT_MAX_FILL_SPAN)?;
+        let block_size = block_size.max(1);
+        let mut sorted: Vec<&Segment> = self.segments().iter().filter(|s| !s.is_empty()).collect();
+        sorted.sort_by_key(|s| s.start_address);
+        let disjoint = sorted
+            .iter()
+            .all(|s| s.start_address as u64 + s.len() as u64 <= 1 << 32)
+            && sorted
+                .windows(2)
+                .all(|pair| pair[0].end_address() < pair[1].start_address);
+        // Disjoint segments are moved as they are; overlaps need a last-wins copy.
+        let segments = if disjoint {
+            let mut segments = std::mem::take(self.segments_mut());
+            segments.retain(|s| !s.is_empty());
+            segments.sort_by_key(|s| s.start_address);
+            segments
+        } else {
+            self.normalized_lossy().into_segments()
+        };
+
+        let mut filled = Vec::with_capacity(segments.len() * 2);
+        let mut next: Option<u64> = None;
+        for segment in segments {
+            if let Some(mut addr) = next {
+                while addr < segment.start_address as u64 {
+                    let len = (segment.start_address as u64 - addr).min(block_size as u64);
+                    filled.push(Segment::new(addr as u32, vec![fill_byte; len as usize]));
+                    addr += len;
+                }
+            }
+            next = Some(segment.end_address() as u64 + 1);
+            filled.push(segment);
+        }
+        self.set_segments(filled);
+        Ok(())
     }
 
     /// Merge another file into this one (operates on raw segments).
     pub fn merge(&mut self, other: &HexFile, options: &MergeOptions) -> Result<(), OpsError> {
-        let mut other_filtered = other.clone();
+        // Only copy segments the range filter can keep.
+        let other = match options.range {
+            Some(range) => HexFile::with_segments(
+                other
+                    .segments()
+                    .iter()
+                    .filter(|s| s.start_address <= range.end() && range.start() <= s.end_address())
+                    .cloned()
+                    .collect(),
+            ),
+            None => other.clone(),
+        };
+        self.merge_owned(other, options)
+    }
+
+    /// [`merge`](Self::merge), taking `other` by value so its buffers are
+    /// moved instead of copied.
+    pub fn merge_owned(&mut self, other: HexFile, options: &MergeOptions) -> Result<(), OpsError> {
+        let mut other_filtered = other;
 
         // Apply range filter if specified
         if let Some(range) = options.range {
@@ -281,10 +455,12 @@ impl HexFile {
                 }
             }
             MergeMode::Preserve => {
-                // Other data is low priority - prepend so existing wins
-                for segment in other_filtered.into_segments() {
-                    self.prepend_segment(segment);
-                }
+                // Other data is low priority - prepend so existing wins. Each
+                // segment goes in front of the previous one, as prepend_segment would.
+                let mut segments = other_filtered.into_segments();
+                segments.reverse();
+                segments.append(self.segments_mut());
+                self.set_segments(segments);
             }
         }
 
@@ -324,26 +500,6 @@ impl HexFile {
     }
 }
 
-fn merge_ranges(ranges: &[Range]) -> Vec<Range> {
-    let mut sorted = ranges.to_vec();
-    sorted.sort_by_key(|r| r.start());
-
-    let mut merged: Vec<Range> = Vec::new();
-    for range in sorted {
-        if let Some(last) = merged.last_mut()
-            && range.start() <= last.end().saturating_add(1)
-        {
-            let new_end = last.end().max(range.end());
-            if let Ok(merged_range) = Range::from_start_end(last.start(), new_end) {
-                *last = merged_range;
-                continue;
-            }
-        }
-        merged.push(range);
-    }
-    merged
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -414,6 +570,43 @@ mod tests {
         assert_eq!(hf.segments()[0].start_address, 0x2000);
     }
 
+    #[test]
+    fn test_label_range_survives_cut_in_labelled_region() {
+        let mut hf = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01; 0x100])]);
+        hf.label_range(Range::from_start_end(0x1080, 0x10FF).unwrap(), "cal");
+        hf.cut(Range::from_start_end(0x10C0, 0x10CF).unwrap());
+
+        let norm = hf.normalized().unwrap();
+        let blocks: Vec<_> = norm
+            .segments()
+            .iter()
+            .map(|s| (s.start_address, s.end_address(), s.name.as_deref()))
+            .collect();
+        assert_eq!(
+            blocks,
+            [
+                (0x1000, 0x107F, None),
+                (0x1080, 0x10BF, Some("cal")),
+                (0x10D0, 0x10FF, Some("cal")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_label_range_keeps_differently_labelled_neighbours_apart() {
+        let mut hf = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01; 0x20])]);
+        hf.label_range(Range::from_start_end(0x1000, 0x100F).unwrap(), "app");
+        hf.label_range(Range::from_start_end(0x1010, 0x101F).unwrap(), "cal");
+        hf.split(0x8);
+
+        let norm = hf.normalized().unwrap();
+        assert_eq!(norm.segments().len(), 2);
+        assert_eq!(norm.segments()[0].name.as_deref(), Some("app"));
+        assert_eq!(norm.segments()[0].len(), 0x10);
+        assert_eq!(norm.segments()[1].name.as_deref(), Some("cal"));
+        assert_eq!(norm.segments()[1].start_address, 0x1010);
+    }
+
     #[test]
     fn test_fill_creates_segment() {
         let mut hf = HexFile::new();
@@ -433,7 +626,7 @@ mod tests {
         hf.fill(
             Range::from_start_length(0x1000, 8).unwrap(),
             &FillOptions {
-                pattern: vec![0xDE, 0xAD, 0xBE, 0xEF],
+                pattern: FillPattern::Bytes(vec![0xDE, 0xAD, 0xBE, 0xEF]),
                 overwrite: false,
             },
         );
@@ -450,7 +643,7 @@ mod tests {
             Segment::new(0x1000, vec![0xAA, 0xBB]),
             Segment::new(0x1004, vec![0xCC, 0xDD]),
         ]);
-        hf.fill_gaps(0xFF);
+        hf.fill_gaps(0xFF).unwrap();
 
         assert_eq!(hf.segments().len(), 1);
         assert_eq!(hf.segments()[0].start_address, 0x1000);
@@ -564,6 +757,26 @@ mod tests {
         assert_eq!(norm.total_bytes(), 0x101A - 0x1005 + 1);
     }
 
+    #[test]
+    fn test_overlapping_ranges_do_not_duplicate_data() {
+        let ranges = [
+            Range::from_start_end(0x1008, 0x100F).unwrap(),
+            Range::from_start_end(0x1004, 0x100B).unwrap(),
+            Range::from_start_end(0x1010, 0x1011).unwrap(),
+        ];
+        let mut hf = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01; 0x20])]);
+        hf.filter_ranges(&ranges);
+        assert_eq!(hf.segments().len(), 1);
+        assert_eq!(hf.segments()[0].start_address, 0x1004);
+        assert_eq!(hf.segments()[0].len(), 0x0E);
+
+        let mut hf = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01; 0x20])]);
+        hf.cut_ranges(&ranges);
+        assert_eq!(hf.segments().len(), 2);
+        assert_eq!(hf.segments()[0].len(), 0x04);
+        assert_eq!(hf.segments()[1].start_address, 0x1012);
+    }
+
     #[test]
     fn test_cut_head_only() {
         let mut hf = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01; 0x10])]);
@@ -604,13 +817,113 @@ mod tests {
         assert_eq!(norm.segments()[1].start_address, 0x1028);
     }
 
+    #[test]
+    fn test_cut_ranges_matches_sequential_cuts() {
+        // xorshift64, so failures reproduce.
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        let mut next = |bound: u32| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound as u64) as u32
+        };
+        for _ in 0..200 {
+            let segments: Vec<Segment> = (0..1 + next(6))
+                .map(|i| Segment::new(next(0x200), vec![i as u8; 1 + next(0x80) as usize]))
+                .collect();
+            let ranges: Vec<Range> = (0..next(8))
+                .map(|_| {
+                    let start = next(0x280);
+                    Range::from_start_end(start, start + next(0x60)).unwrap()
+                })
+                .collect();
+
+            let mut swept = HexFile::with_segments(segments.clone());
+            swept.cut_ranges(&ranges);
+            let mut sequential = HexFile::with_segments(segments);
+            for range in &ranges {
+                sequential.cut(*range);
+            }
+            assert_eq!(swept.segments(), sequential.segments(), "{ranges:?}");
+        }
+    }
+
+    #[test]
+    fn test_cut_ranges_many_ranges_is_fast() {
+        let mut hf = HexFile::with_segments(
+            (0..100u32)
+                .map(|i| Segment::new(i << 20, vec![0xAA; 0x10_0000]))
+                .collect(),
+        );
+        // 10k ranges, in reverse so the sort matters.
+        let ranges: Vec<Range> = (0..10_000u32)
+            .rev()
+            .map(|i| Range::from_start_end(i * 0x2800, i * 0x2800 + 0xFF).unwrap())
+            .collect();
+        let start = std::time::Instant::now();
+        hf.cut_ranges(&ranges);
+        assert!(start.elapsed() < std::time::Duration::from_secs(2));
+        assert_eq!(hf.total_bytes(), 100 * 0x10_0000 - 10_000 * 0x100);
+    }
+
+    #[test]
+    fn test_fill_increment_wraps_and_follows_range_start() {
+        let mut hf = HexFile::with_segments(vec![Segment::new(0x1002, vec![0xAA; 2])]);
+        hf.fill(
+            Range::from_start_length(0x1000, 0x120).unwrap(),
+            &FillOptions {
+                pattern: FillPattern::Increment {
+                    start: 0xFE,
+                    step: 1,
+                },
+                overwrite: false,
+            },
+        );
+        let data = hf.normalized_lossy().segments()[0].data.clone();
+        assert_eq!(&data[..6], &[0xFE, 0xFF, 0xAA, 0xAA, 0x02, 0x03]);
+        assert_eq!(data[0x101], 0xFF);
+        assert_eq!(data[0x102], 0x00);
+
+        let ramp = FillPattern::Increment {
+            start: 0x10,
+            step: 0x40,
+        };
+        assert_eq!(ramp.bytes(3, 3), vec![0xD0, 0x10, 0x50]);
+        assert_eq!(ramp.period().len(), 256);
+    }
+
+    #[test]
+    fn test_fill_random_repeats_per_seed() {
+        let range = Range::from_start_length(0x1000, 0x40).unwrap();
+        let fill = |seed: u64, overwrite: bool| {
+            let mut hf = HexFile::with_segments(vec![Segment::new(0x1010, vec![0xAA; 4])]);
+            let options = FillOptions {
+                pattern: FillPattern::Random { seed: Some(seed) },
+                overwrite,
+            };
+            hf.fill(range, &options);
+            hf.normalized_lossy().segments()[0].data.clone()
+        };
+
+        let first = fill(42, false);
+        assert_eq!(fill(42, false), first);
+        assert_ne!(fill(43, false), first);
+        assert_eq!(&first[0x10..0x14], &[0xAA; 4]);
+
+        // Gaps get the bytes a fill of the whole range would, as with /FRSEED.
+        let whole = fill(42, true);
+        assert_eq!(whole, crate::seeded_random_fill(42)(range));
+        assert_eq!(&first[..0x10], &whole[..0x10]);
+        assert_eq!(&first[0x14..], &whole[0x14..]);
+    }
+
     #[test]
     fn test_fill_overwrite_partial() {
         let mut hf = HexFile::with_segments(vec![Segment::new(0x1000, vec![0xAA; 8])]);
         hf.fill(
             Range::from_start_length(0x1002, 4).unwrap(),
             &FillOptions {
-                pattern: vec![0xFF],
+                pattern: FillPattern::Bytes(vec![0xFF]),
                 overwrite: true,
             },
         );
@@ -627,7 +940,7 @@ mod tests {
             Segment::new(0x1000, vec![0xAA, 0xBB, 0xCC]),
             Segment::new(0x1001, vec![0xFF]), // overlaps
         ]);
-        hf.fill_gaps(0x00);
+        hf.fill_gaps(0x00).unwrap();
         let seg = &hf.segments()[0];
         assert_eq!(seg.start_address, 0x1000);
         // normalized_lossy: last wins, so 0x1001 = 0xFF
@@ -637,11 +950,98 @@ mod tests {
     #[test]
     fn test_fill_gaps_single_segment() {
         let mut hf = HexFile::with_segments(vec![Segment::new(0x1000, vec![0xAA, 0xBB])]);
-        hf.fill_gaps(0xFF);
+        hf.fill_gaps(0xFF).unwrap();
         assert_eq!(hf.segments().len(), 1);
         assert_eq!(hf.segments()[0].data, vec![0xAA, 0xBB]);
     }
 
+    #[test]
+    fn test_fill_gaps_rejects_huge_span() {
+        let mut hf = HexFile::with_segments(vec![
+            Segment::new(0x0, vec![0x01]),
+            Segment::new(0xBFFF_FFFF, vec![0x02]),
+        ]);
+        let err = hf.fill_gaps(0xFF).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                OpsError::FillSpanTooLarge {
+                    start: 0,
+                    end: 0xBFFF_FFFF,
+                    span: 0xC000_0000,
+                    max: DEFAULT_MAX_FILL_SPAN,
+                }
+            ),
+            "{err}"
+        );
+        assert!(hf.fill_gaps_chunked(0xFF, 0x1000).is_err());
+        assert_eq!(hf.segments().len(), 2);
+
+        let mut small = HexFile::with_segments(vec![
+            Segment::new(0x1000, vec![0x01]),
+            Segment::new(0x1010, vec![0x02]),
+        ]);
+        assert!(small.fill_gaps_limited(0xFF, 0x10).is_err());
+        small.fill_gaps_limited(0xFF, 0x11).unwrap();
+        assert_eq!(small.segments()[0].len(), 0x11);
+    }
+
+    #[test]
+    fn test_fill_gaps_within_window() {
+        let mut hf = HexFile::with_segments(vec![
+            Segment::new(0x0, vec![0x01]),
+            Segment::new(0x1000, vec![0x02]),
+            Segment::new(0x1004, vec![0x03]),
+            Segment::new(0xBFFF_FFFF, vec![0x04]),
+        ]);
+        hf.fill_gaps_within(Range::from_start_end(0x0F00, 0x2000).unwrap(), 0xEE);
+        let normalized = hf.normalized_lossy();
+        assert_eq!(
+            normalized.segments(),
+            &[
+                Segment::new(0x0, vec![0x01]),
+                Segment::new(0x1000, vec![0x02, 0xEE, 0xEE, 0xEE, 0x03]),
+                Segment::new(0xBFFF_FFFF, vec![0x04]),
+            ]
+        );
+
+        let mut empty = HexFile::with_segments(vec![Segment::new(0x0, vec![0x01])]);
+        empty.fill_gaps_within(Range::from_start_end(0x10, 0x20).unwrap(), 0xEE);
+        assert_eq!(empty.segments().len(), 1);
+    }
+
+    #[test]
+    fn test_fill_gaps_chunked_keeps_data_buffers() {
+        let data = vec![0x11; 3];
+        let data_ptr = data.as_ptr();
+        let mut hf = HexFile::with_segments(vec![
+            Segment::new(0x2000, vec![0x22]),
+            Segment::new(0x1000, data),
+        ]);
+        let mut dense = hf.clone();
+        dense.fill_gaps(0xA5).unwrap();
+
+        hf.fill_gaps_chunked(0xA5, 0x400).unwrap();
+        assert_eq!(hf.segments()[0].data.as_ptr(), data_ptr);
+        assert!(hf.segments().iter().all(|s| s.len() <= 0x400));
+        assert_eq!(hf.segments().len(), 6);
+        assert_eq!(hf.normalized_lossy(), dense);
+
+        let mut overlapping = HexFile::with_segments(vec![
+            Segment::new(0x1000, vec![0xAA, 0xBB, 0xCC]),
+            Segment::new(0x1001, vec![0xFF]),
+            Segment::new(0x1005, vec![0xDD]),
+        ]);
+        overlapping.fill_gaps_chunked(0x00, 1).unwrap();
+        assert_eq!(
+            overlapping.normalized_lossy().segments(),
+            &[Segment::new(
+                0x1000,
+                vec![0xAA, 0xFF, 0xCC, 0x00, 0x00, 0xDD]
+            )]
+        );
+    }
+
     #[test]
     fn test_merge_with_negative_offset() {
         let mut hf1 = HexFile::with_segments(vec![Segment::new(0x1000, vec![0xAA])]);
@@ -1,8 +1,9 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::{
-    AlignOptions, ChecksumAlgorithm, ChecksumOptions, ChecksumTarget, FillOptions, ForcedRange,
-    HexFile, MergeMode, MergeOptions, Range, RemapOptions, Segment,
+    AddressTable, AlignOptions, BankedMapOptions, ChecksumAlgorithm, ChecksumOptions,
+    ChecksumTarget, FillOptions, FillPattern, ForcedRange, HexFile, MergeMode, MergeOptions, Range,
+    RemapOptions, Segment,
 };
 
 use super::{LogError, OpsError, execute_log_file};
@@ -12,12 +13,12 @@ fn with_ctx<T>(ctx: &str, result: Result<T, OpsError>) -> Result<T, OpsError> {
 }
 
 /// CLI: /FR with /FP (fill ranges with explicit pattern).
-pub fn flag_fill_ranges_pattern(hexfile: &mut HexFile, ranges: &[Range], pattern: &[u8]) {
+pub fn flag_fill_ranges_pattern(hexfile: &mut HexFile, ranges: &[Range], pattern: &FillPattern) {
     if ranges.is_empty() || pattern.is_empty() {
         return;
     }
     let options = FillOptions {
-        pattern: pattern.to_vec(),
+        pattern: pattern.clone(),
         overwrite: false,
     };
     for range in ranges {
@@ -36,7 +37,8 @@ where
     }
 }
 
-/// CLI: /FR without /FP (random fill helper).
+/// CLI: /FR without /FP (random fill helper). `seed` is the generator state
+/// from [`random_fill_seed`]; see [`FillPattern::Random`] for the algorithm.
 pub fn random_fill_bytes(range: Range, seed: u64) -> Vec<u8> {
     let len = range.length() as usize;
     if len == 0 {
@@ -57,11 +59,28 @@ pub fn random_fill_seed_from_time(range: Range) -> u64 {
         .duration_since(UNIX_EPOCH)
         .map(|d| d.as_nanos() as u64)
         .unwrap_or(0);
-    let seed = now ^ ((range.start() as u64) << 32) ^ (range.length() as u64);
+    random_fill_seed(now, range)
+}
+
+/// CLI: /FRSEED (per-range seed derived from a fixed base seed).
+pub fn random_fill_seed(seed: u64, range: Range) -> u64 {
+    // splitmix64 finalizer: the generator's output ignores the seed's high bits,
+    // so the range start must be spread over all of them.
+    let mut seed = seed ^ ((range.start() as u64) << 32) ^ (range.length() as u64);
+    seed = (seed ^ (seed >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    seed = (seed ^ (seed >> 27)).wrapping_mul(0x94D049BB133111EB);
+    seed ^= seed >> 31;
     // Avoid zero seed to keep the generator non-degenerate.
     if seed == 0 { 0x9E3779B97F4A7C15 } else { seed }
 }
 
+/// CLI: /FR with /FRSEED. A random-fill callback for `Pipeline::execute` that
+/// produces the same bytes for the same seed and range on every run.
+pub fn seeded_random_fill(seed: u64) -> impl FnMut(Range) -> Vec<u8> {
+    let pattern = FillPattern::Random { seed: Some(seed) };
+    move |range| pattern.range_bytes(range)
+}
+
 /// CLI: /CR (cut/remove ranges).
 pub fn flag_cut_ranges(hexfile: &mut HexFile, ranges: &[Range]) {
     hexfile.cut_ranges(ranges);
@@ -70,7 +89,7 @@ pub fn flag_cut_ranges(hexfile: &mut HexFile, ranges: &[Range]) {
 /// CLI: /MT (transparent merge).
 pub fn flag_merge_transparent(
     hexfile: &mut HexFile,
-    other: &HexFile,
+    other: HexFile,
     offset: i64,
     range: Option<Range>,
 ) -> Result<(), OpsError> {
@@ -79,13 +98,13 @@ pub fn flag_merge_transparent(
         offset,
         range,
     };
-    with_ctx("/MT", hexfile.merge(other, &options))
+    with_ctx("/MT", hexfile.merge_owned(other, &options))
 }
 
 /// CLI: /MO (opaque merge).
 pub fn flag_merge_opaque(
     hexfile: &mut HexFile,
-    other: &HexFile,
+    other: HexFile,
     offset: i64,
     range: Option<Range>,
 ) -> Result<(), OpsError> {
@@ -94,7 +113,7 @@ pub fn flag_merge_opaque(
         offset,
         range,
     };
-    with_ctx("/MO", hexfile.merge(other, &options))
+    with_ctx("/MO", hexfile.merge_owned(other, &options))
 }
 
 /// CLI: /AR (filter/keep ranges).
@@ -105,8 +124,17 @@ pub fn flag_filter_ranges(hexfile: &mut HexFile, ranges: &[Range]) {
 }
 
 /// CLI: /FA (fill all gaps with /AF byte).
-pub fn flag_fill_all(hexfile: &mut HexFile, fill_byte: u8) {
-    hexfile.fill_gaps(fill_byte);
+pub fn flag_fill_all(hexfile: &mut HexFile, fill_byte: u8) -> Result<(), OpsError> {
+    with_ctx("/FA", hexfile.fill_gaps(fill_byte))
+}
+
+/// CLI: /FA above /BHFCT, in /BTBS-sized blocks.
+pub fn flag_fill_all_chunked(
+    hexfile: &mut HexFile,
+    fill_byte: u8,
+    block_size: usize,
+) -> Result<(), OpsError> {
+    with_ctx("/FA", hexfile.fill_gaps_chunked(fill_byte, block_size))
 }
 
 /// CLI: /AD, /AL (align), uses /AF as fill.
@@ -129,6 +157,11 @@ pub fn flag_split(hexfile: &mut HexFile, size: u32) {
     hexfile.split(size);
 }
 
+/// CLI: /SBA (split blocks at address boundaries).
+pub fn flag_split_at_boundaries(hexfile: &mut HexFile, boundary: u32) {
+    hexfile.split_at_boundaries(boundary);
+}
+
 /// CLI: /SWAPWORD.
 pub fn flag_swap_word(hexfile: &mut HexFile) -> Result<(), OpsError> {
     with_ctx("/SWAPWORD", hexfile.swap_bytes(crate::SwapMode::Word))
@@ -144,19 +177,25 @@ pub fn flag_remap(hexfile: &mut HexFile, options: &RemapOptions) -> Result<(), O
     with_ctx("/REMAP", hexfile.remap(options))
 }
 
-/// CLI: /S12MAP.
-pub fn flag_map_star12(hexfile: &mut HexFile) -> Result<(), OpsError> {
-    with_ctx("/S12MAP", hexfile.map_star12())
+/// CLI: /MAPTAB.
+pub fn flag_remap_table(hexfile: &mut HexFile, table: &AddressTable) -> Result<(), OpsError> {
+    *hexfile = with_ctx("/MAPTAB", hexfile.remap_table(table))?;
+    Ok(())
+}
+
+/// CLI: /S12MAP, with `BankedMapOptions::STAR12` or custom pages.
+pub fn flag_map_star12(hexfile: &mut HexFile, options: &BankedMapOptions) -> Result<(), OpsError> {
+    with_ctx("/S12MAP", hexfile.map_banked(options))
 }
 
-/// CLI: /S12XMAP.
-pub fn flag_map_star12x(hexfile: &mut HexFile) -> Result<(), OpsError> {
-    with_ctx("/S12XMAP", hexfile.map_star12x())
+/// CLI: /S12XMAP, with `BankedMapOptions::STAR12X` or custom pages.
+pub fn flag_map_star12x(hexfile: &mut HexFile, options: &BankedMapOptions) -> Result<(), OpsError> {
+    with_ctx("/S12XMAP", hexfile.map_banked(options))
 }
 
-/// CLI: /S08MAP.
-pub fn flag_map_star08(hexfile: &mut HexFile) -> Result<(), OpsError> {
-    with_ctx("/S08MAP", hexfile.map_star08())
+/// CLI: /S08MAP, with `BankedMapOptions::STAR08` or custom pages.
+pub fn flag_map_star08(hexfile: &mut HexFile, options: &BankedMapOptions) -> Result<(), OpsError> {
+    with_ctx("/S08MAP", hexfile.map_banked(options))
 }
 
 /// CLI: /CDSPX.
@@ -182,7 +221,17 @@ pub fn flag_dspic_clear_ghost(hexfile: &mut HexFile, range: Range) -> Result<(),
     with_ctx("/CDSPG", hexfile.dspic_clear_ghost(range))
 }
 
-/// CLI: /CS or /CSR (little-endian output).
+/// CLI: /CDSPI.
+pub fn flag_dspic_insert_ghost(
+    hexfile: &mut HexFile,
+    range: Range,
+    ghost: u8,
+) -> Result<(), OpsError> {
+    with_ctx("/CDSPI", hexfile.dspic_insert_ghost(range, ghost))
+}
+
+/// CLI: /CS or /CSR (little-endian output), `;INIT=` supplies `initial`.
+#[allow(clippy::too_many_arguments)]
 pub fn flag_checksum(
     hexfile: &mut HexFile,
     algorithm: ChecksumAlgorithm,
@@ -190,6 +239,7 @@ pub fn flag_checksum(
     little_endian_output: bool,
     forced_range: Option<ForcedRange>,
     exclude_ranges: &[Range],
+    initial: Option<u64>,
     target: &ChecksumTarget,
 ) -> Result<Vec<u8>, OpsError> {
     let context = if little_endian_output { "/CSR" } else { "/CS" };
@@ -200,10 +250,44 @@ pub fn flag_checksum(
         forced_range,
         exclude_ranges: exclude_ranges.to_vec(),
         target_exclude: None, // Set by checksum() based on target
+        target_as_fill: false,
+        gap_fill: crate::ChecksumGapFill::Skip,
+        initial,
     };
     with_ctx(context, hexfile.checksum(&options, target))
 }
 
+/// CLI: /CSB or /CSBR (checksum each block separately, e.g. after /SB).
+#[allow(clippy::too_many_arguments)]
+pub fn flag_checksum_blocks(
+    hexfile: &mut HexFile,
+    algorithm: ChecksumAlgorithm,
+    range: Option<Range>,
+    little_endian_output: bool,
+    forced_range: Option<ForcedRange>,
+    exclude_ranges: &[Range],
+    initial: Option<u64>,
+    target: &ChecksumTarget,
+) -> Result<Vec<Vec<u8>>, OpsError> {
+    let context = if little_endian_output {
+        "/CSBR"
+    } else {
+        "/CSB"
+    };
+    let options = ChecksumOptions {
+        algorithm,
+        range,
+        little_endian_output,
+        forced_range,
+        exclude_ranges: exclude_ranges.to_vec(),
+        target_exclude: None,
+        target_as_fill: false,
+        gap_fill: crate::ChecksumGapFill::Skip,
+        initial,
+    };
+    with_ctx(context, hexfile.checksum_per_segment(&options, target))
+}
+
 /// CLI: /L (execute log file commands).
 pub fn flag_execute_log_file<F, E>(
     hexfile: &mut HexFile,
@@ -227,4 +311,15 @@ mod tests {
         let data = random_fill_bytes(range, 1);
         assert_eq!(data, vec![0x2D, 0xCF, 0x46, 0x29]);
     }
+
+    #[test]
+    fn test_seeded_random_fill_repeats_per_seed() {
+        let first = Range::from_start_length(0x1000, 16).unwrap();
+        let second = Range::from_start_length(0x2000, 16).unwrap();
+        let mut fill = seeded_random_fill(42);
+        let a = fill(first);
+        assert_eq!(seeded_random_fill(42)(first), a);
+        assert_ne!(fill(second), a);
+        assert_ne!(seeded_random_fill(43)(first), a);
+    }
 }
@@ -1,14 +1,79 @@
+//! HexView command log (CLI: /L).
+//!
+//! One command per line; arguments are separated by whitespace and file names
+//! containing spaces are double-quoted. Ranges are `start-end` or `start,length`.
+//!
+//! - `FileOpen <file>`, `FileNew`, `FileClose`
+//! - `FillRange <range> [pattern]`: fill gaps in the range; hex pattern, default `FF`
+//! - `CutRange <range>[:<range>...]`
+//! - `MergeFile <file> [offset [range]]`: opaque merge like /MO, the file's data wins
+//! - `CreateChecksum <method> [@append|@upfront|@begin|@end|@<address>] [range]`: /CS
+//!   method index, placed at `@append` by default
+//! - `FileSaveAs <file>`: the extension picks Intel HEX (`.hex`), S-Record
+//!   (`.s19`, `.s28`, `.s37`, `.srec`, `.mot`) or binary (`.bin`)
+//! - `; text`: comment
+//!
+//! A checksum target of `@file:<path>` writes the result as comma-separated
+//! hex, like `/CS` with a file target. [`write_log_commands`] produces this
+//! syntax (CLI: /LOGOUT).
+
 use std::path::{Path, PathBuf};
 
 use thiserror::Error;
 
-use crate::HexFile;
+use super::{flag_checksum, flag_cut_ranges, flag_fill_ranges_pattern, flag_merge_opaque};
+use crate::range::parse_number;
+use crate::{
+    BinaryWriteOptions, ChecksumAlgorithm, ChecksumTarget, FillPattern, HexFile,
+    IntelHexWriteOptions, OpsError, Range, SRecordWriteOptions, parse_ranges, write_binary,
+    write_intel_hex, write_srec,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum LogCommandKind {
     FileOpen(PathBuf),
     FileClose,
     FileNew,
+    FillRange {
+        range: Range,
+        pattern: Vec<u8>,
+    },
+    CutRange(Vec<Range>),
+    MergeFile {
+        path: PathBuf,
+        offset: i64,
+        range: Option<Range>,
+    },
+    CreateChecksum {
+        algorithm: ChecksumAlgorithm,
+        target: ChecksumTarget,
+        range: Option<Range>,
+    },
+    FileSaveAs {
+        path: PathBuf,
+        format: LogSaveFormat,
+    },
+    Comment(String),
+}
+
+/// Output format of `FileSaveAs`, chosen by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogSaveFormat {
+    IntelHex,
+    SRecord,
+    Binary,
+}
+
+impl LogSaveFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "hex" => Some(Self::IntelHex),
+            "s19" | "s28" | "s37" | "srec" | "mot" => Some(Self::SRecord),
+            "bin" => Some(Self::Binary),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -28,18 +93,168 @@ pub enum LogError {
     #[error("unsupported log command '{command}' on line {line}")]
     UnsupportedCommand { command: String, line: usize },
 
+    #[error("invalid arguments for log command {command} on line {line}: {message}")]
+    InvalidArguments {
+        command: String,
+        line: usize,
+        message: String,
+    },
+
     #[error("log command failed on line {line}: {source}")]
     Load {
         line: usize,
         #[source]
         source: Box<dyn std::error::Error>,
     },
+
+    #[error("log command failed on line {line}: {source}")]
+    Operation {
+        line: usize,
+        #[source]
+        source: OpsError,
+    },
+
+    #[error("log command FileSaveAs failed on line {line}: {source}")]
+    Save {
+        line: usize,
+        #[source]
+        source: Box<dyn std::error::Error>,
+    },
 }
 
 fn strip_quotes(s: &str) -> &str {
     s.trim_matches(|c| c == '"' || c == '\'')
 }
 
+/// Whitespace-separated arguments; a double-quoted argument may contain spaces.
+fn split_arguments(rest: &str) -> Vec<&str> {
+    let mut args = Vec::new();
+    let mut remaining = rest.trim_start();
+    while !remaining.is_empty() {
+        let (arg, tail) = if let Some(quoted) = remaining.strip_prefix('"') {
+            quoted.split_once('"').unwrap_or((quoted, ""))
+        } else {
+            remaining
+                .split_once(char::is_whitespace)
+                .unwrap_or((remaining, ""))
+        };
+        args.push(arg);
+        remaining = tail.trim_start();
+    }
+    args
+}
+
+fn parse_range(arg: &str) -> Result<Range, String> {
+    arg.parse().map_err(|e| format!("range '{arg}': {e}"))
+}
+
+fn parse_offset(arg: &str) -> Result<i64, String> {
+    let (negative, digits) = match arg.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, arg.strip_prefix('+').unwrap_or(arg)),
+    };
+    let value = parse_number(digits).map_err(|e| format!("offset '{arg}': {e}"))? as i64;
+    Ok(if negative { -value } else { value })
+}
+
+fn parse_pattern(arg: &str) -> Result<Vec<u8>, String> {
+    let digits = arg
+        .strip_prefix("0x")
+        .or_else(|| arg.strip_prefix("0X"))
+        .unwrap_or(arg);
+    if digits.is_empty() || !digits.len().is_multiple_of(2) {
+        return Err(format!(
+            "pattern '{arg}' must be an even number of hex digits"
+        ));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16))
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("pattern '{arg}' is not hex"))
+}
+
+fn parse_target(arg: &str) -> Result<ChecksumTarget, String> {
+    if let Some(path) = arg
+        .strip_prefix("file:")
+        .or_else(|| arg.strip_prefix("FILE:"))
+    {
+        return Ok(ChecksumTarget::File(PathBuf::from(path)));
+    }
+    match arg.to_ascii_uppercase().as_str() {
+        "APPEND" => Ok(ChecksumTarget::Append),
+        "UPFRONT" => Ok(ChecksumTarget::Prepend),
+        "BEGIN" => Ok(ChecksumTarget::Begin),
+        "END" => Ok(ChecksumTarget::OverwriteEnd),
+        _ => parse_number(arg)
+            .map(ChecksumTarget::Address)
+            .map_err(|e| format!("target '@{arg}': {e}")),
+    }
+}
+
+fn parse_arguments(cmd_upper: &str, args: &[&str]) -> Result<LogCommandKind, String> {
+    let max_args = match cmd_upper {
+        "CUTRANGE" | "FILESAVEAS" => 1,
+        "FILLRANGE" => 2,
+        _ => 3,
+    };
+    if args.len() > max_args {
+        return Err(format!("unexpected argument '{}'", args[max_args]));
+    }
+    let Some(&first) = args.first() else {
+        return Err("missing argument".to_string());
+    };
+
+    match cmd_upper {
+        "FILLRANGE" => Ok(LogCommandKind::FillRange {
+            range: parse_range(first)?,
+            pattern: match args.get(1) {
+                Some(pattern) => parse_pattern(pattern)?,
+                None => vec![0xFF],
+            },
+        }),
+        "CUTRANGE" => parse_ranges(first)
+            .map(LogCommandKind::CutRange)
+            .map_err(|e| format!("range '{first}': {e}")),
+        "MERGEFILE" => Ok(LogCommandKind::MergeFile {
+            path: PathBuf::from(first),
+            offset: args.get(1).map_or(Ok(0), |arg| parse_offset(arg))?,
+            range: args.get(2).map(|arg| parse_range(arg)).transpose()?,
+        }),
+        "CREATECHECKSUM" => {
+            let index = parse_number(first)
+                .ok()
+                .and_then(|n| u8::try_from(n).ok())
+                .ok_or_else(|| format!("checksum method '{first}' is not a number"))?;
+            let algorithm = ChecksumAlgorithm::from_index(index).map_err(|e| e.to_string())?;
+            let mut target = None;
+            let mut range = None;
+            for &arg in &args[1..] {
+                if let Some(placement) = arg.strip_prefix('@') {
+                    if target.replace(parse_target(placement)?).is_some() {
+                        return Err("checksum target given twice".to_string());
+                    }
+                } else if range.replace(parse_range(arg)?).is_some() {
+                    return Err("checksum range given twice".to_string());
+                }
+            }
+            Ok(LogCommandKind::CreateChecksum {
+                algorithm,
+                target: target.unwrap_or(ChecksumTarget::Append),
+                range,
+            })
+        }
+        _ => {
+            let path = PathBuf::from(first);
+            let format = LogSaveFormat::from_path(&path).ok_or_else(|| {
+                "unknown file extension, expected .hex, .s19/.s28/.s37/.srec/.mot or .bin"
+                    .to_string()
+            })?;
+            Ok(LogCommandKind::FileSaveAs { path, format })
+        }
+    }
+}
+
 /// Parse log commands. CLI: /L.
 pub fn parse_log_commands(content: &str) -> Result<Vec<LogCommand>, LogError> {
     let mut commands = Vec::new();
@@ -50,6 +265,13 @@ pub fn parse_log_commands(content: &str) -> Result<Vec<LogCommand>, LogError> {
         if line.is_empty() {
             continue;
         }
+        if let Some(text) = line.strip_prefix(';') {
+            commands.push(LogCommand {
+                line: line_no,
+                kind: LogCommandKind::Comment(text.trim().to_string()),
+            });
+            continue;
+        }
 
         let mut parts = line.split_whitespace();
         let cmd = parts.next().unwrap_or("");
@@ -66,6 +288,15 @@ pub fn parse_log_commands(content: &str) -> Result<Vec<LogCommand>, LogError> {
             }
             "FILECLOSE" => LogCommandKind::FileClose,
             "FILENEW" => LogCommandKind::FileNew,
+            "FILLRANGE" | "CUTRANGE" | "MERGEFILE" | "CREATECHECKSUM" | "FILESAVEAS" => {
+                parse_arguments(&cmd_upper, &split_arguments(rest)).map_err(|message| {
+                    LogError::InvalidArguments {
+                        command: cmd.to_string(),
+                        line: line_no,
+                        message,
+                    }
+                })?
+            }
             _ => {
                 return Err(LogError::UnsupportedCommand {
                     command: cmd.to_string(),
@@ -83,7 +314,23 @@ pub fn parse_log_commands(content: &str) -> Result<Vec<LogCommand>, LogError> {
     Ok(commands)
 }
 
+fn save_as(
+    hexfile: &HexFile,
+    path: &Path,
+    format: LogSaveFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let data = match format {
+        LogSaveFormat::IntelHex => write_intel_hex(hexfile, &IntelHexWriteOptions::default())?,
+        LogSaveFormat::SRecord => write_srec(hexfile, &SRecordWriteOptions::default())?,
+        LogSaveFormat::Binary => write_binary(hexfile, &BinaryWriteOptions::default())?,
+    };
+    std::fs::write(path, data)?;
+    Ok(())
+}
+
 /// Execute parsed log commands. CLI: /L.
+///
+/// `load` reads the files named by `FileOpen` and `MergeFile`.
 pub fn execute_log_commands<F, E>(
     hexfile: &mut HexFile,
     commands: &[LogCommand],
@@ -94,10 +341,11 @@ where
     E: Into<Box<dyn std::error::Error>>,
 {
     for command in commands {
+        let line = command.line;
         match &command.kind {
             LogCommandKind::FileOpen(path) => {
                 let loaded = load(path).map_err(|err| LogError::Load {
-                    line: command.line,
+                    line,
                     source: err.into(),
                 })?;
                 *hexfile = loaded;
@@ -105,12 +353,129 @@ where
             LogCommandKind::FileClose | LogCommandKind::FileNew => {
                 *hexfile = HexFile::new();
             }
+            LogCommandKind::FillRange { range, pattern } => {
+                flag_fill_ranges_pattern(hexfile, &[*range], &FillPattern::Bytes(pattern.clone()));
+            }
+            LogCommandKind::CutRange(ranges) => {
+                flag_cut_ranges(hexfile, ranges);
+            }
+            LogCommandKind::MergeFile {
+                path,
+                offset,
+                range,
+            } => {
+                let other = load(path).map_err(|err| LogError::Load {
+                    line,
+                    source: err.into(),
+                })?;
+                flag_merge_opaque(hexfile, other, *offset, *range)
+                    .map_err(|source| LogError::Operation { line, source })?;
+            }
+            LogCommandKind::CreateChecksum {
+                algorithm,
+                target,
+                range,
+            } => {
+                let result =
+                    flag_checksum(hexfile, *algorithm, *range, false, None, &[], None, target)
+                        .map_err(|source| LogError::Operation { line, source })?;
+                if let ChecksumTarget::File(path) = target {
+                    let formatted: Vec<String> =
+                        result.iter().map(|b| format!("{b:02X}")).collect();
+                    std::fs::write(path, formatted.join(",")).map_err(|err| LogError::Save {
+                        line,
+                        source: err.into(),
+                    })?;
+                }
+            }
+            LogCommandKind::FileSaveAs { path, format } => {
+                save_as(hexfile, path, *format)
+                    .map_err(|source| LogError::Save { line, source })?;
+            }
+            LogCommandKind::Comment(_) => {}
         }
     }
 
     Ok(())
 }
 
+fn quote_path(path: &Path) -> String {
+    let text = path.display().to_string();
+    if text.contains(char::is_whitespace) {
+        format!("\"{text}\"")
+    } else {
+        text
+    }
+}
+
+fn format_range(range: Range) -> String {
+    format!("0x{:X}-0x{:X}", range.start(), range.end())
+}
+
+/// Serialize commands in the syntax read by [`parse_log_commands`]. CLI: /LOGOUT.
+pub fn write_log_commands(commands: &[LogCommandKind]) -> String {
+    let mut out = String::new();
+    for command in commands {
+        let line = match command {
+            LogCommandKind::FileOpen(path) => format!("FileOpen {}", quote_path(path)),
+            LogCommandKind::FileClose => "FileClose".to_string(),
+            LogCommandKind::FileNew => "FileNew".to_string(),
+            LogCommandKind::FillRange { range, pattern } => {
+                let pattern: String = pattern.iter().map(|b| format!("{b:02X}")).collect();
+                format!("FillRange {} {pattern}", format_range(*range))
+            }
+            LogCommandKind::CutRange(ranges) => {
+                let ranges: Vec<String> = ranges.iter().map(|r| format_range(*r)).collect();
+                format!("CutRange {}", ranges.join(":"))
+            }
+            LogCommandKind::MergeFile {
+                path,
+                offset,
+                range,
+            } => {
+                let sign = if *offset < 0 { "-" } else { "" };
+                let mut line = format!(
+                    "MergeFile {} {sign}0x{:X}",
+                    quote_path(path),
+                    offset.unsigned_abs()
+                );
+                if let Some(range) = range {
+                    line.push(' ');
+                    line.push_str(&format_range(*range));
+                }
+                line
+            }
+            LogCommandKind::CreateChecksum {
+                algorithm,
+                target,
+                range,
+            } => {
+                let target = match target {
+                    ChecksumTarget::Address(address) => format!("@0x{address:X}"),
+                    ChecksumTarget::Append => "@append".to_string(),
+                    ChecksumTarget::Prepend => "@upfront".to_string(),
+                    ChecksumTarget::Begin => "@begin".to_string(),
+                    ChecksumTarget::OverwriteEnd => "@end".to_string(),
+                    ChecksumTarget::File(path) => {
+                        quote_path(&PathBuf::from(format!("@file:{}", path.display())))
+                    }
+                };
+                let mut line = format!("CreateChecksum {} {target}", *algorithm as u8);
+                if let Some(range) = range {
+                    line.push(' ');
+                    line.push_str(&format_range(*range));
+                }
+                line
+            }
+            LogCommandKind::FileSaveAs { path, .. } => format!("FileSaveAs {}", quote_path(path)),
+            LogCommandKind::Comment(text) => format!("; {text}"),
+        };
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
 /// Execute commands from a log file. CLI: /L.
 pub fn execute_log_file<F, E>(hexfile: &mut HexFile, path: &Path, load: F) -> Result<(), LogError>
 where
@@ -146,6 +511,131 @@ mod tests {
         assert!(matches!(err, LogError::MissingFilename { line: 1 }));
     }
 
+    #[test]
+    fn test_parse_log_commands_extended() {
+        let content = "FillRange 0x1000,0x10 A55A\n\
+                       CutRange 0x1004-0x1007:0x100C,2\n\
+                       MergeFile \"patch file.hex\" -0x10 0x2000-0x20FF\n\
+                       CreateChecksum 9 @0x3000 0x1000-0x1FFF\n\
+                       FileSaveAs out.S19\n";
+        let commands = parse_log_commands(content).unwrap();
+        let kinds: Vec<_> = commands.into_iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                LogCommandKind::FillRange {
+                    range: Range::from_start_length(0x1000, 0x10).unwrap(),
+                    pattern: vec![0xA5, 0x5A],
+                },
+                LogCommandKind::CutRange(vec![
+                    Range::from_start_end(0x1004, 0x1007).unwrap(),
+                    Range::from_start_length(0x100C, 2).unwrap(),
+                ]),
+                LogCommandKind::MergeFile {
+                    path: PathBuf::from("patch file.hex"),
+                    offset: -0x10,
+                    range: Some(Range::from_start_end(0x2000, 0x20FF).unwrap()),
+                },
+                LogCommandKind::CreateChecksum {
+                    algorithm: ChecksumAlgorithm::Crc32,
+                    target: ChecksumTarget::Address(0x3000),
+                    range: Some(Range::from_start_end(0x1000, 0x1FFF).unwrap()),
+                },
+                LogCommandKind::FileSaveAs {
+                    path: PathBuf::from("out.S19"),
+                    format: LogSaveFormat::SRecord,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_log_commands_invalid_arguments() {
+        for content in [
+            "FillRange\n",
+            "FillRange 0x1000,0x10 XYZ\n",
+            "CutRange 0x10-0x0\n",
+            "CreateChecksum 99\n",
+            "FileSaveAs out.elf\n",
+            "FileSaveAs a.hex b.hex\n",
+        ] {
+            let err = parse_log_commands(content).unwrap_err();
+            assert!(
+                matches!(err, LogError::InvalidArguments { line: 1, .. }),
+                "{content}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_execute_log_commands_fill_cut_checksum() {
+        let commands =
+            parse_log_commands("FillRange 0x1000,8 AB\nCutRange 0x1006,2\nCreateChecksum 0\n")
+                .unwrap();
+        let mut file = HexFile::with_segments(vec![Segment::new(0x1002, vec![0x01, 0x02])]);
+        execute_log_commands(&mut file, &commands, |_| {
+            Err(std::io::Error::other("no files"))
+        })
+        .unwrap();
+        let normalized = file.normalized_lossy();
+        assert_eq!(
+            normalized.segments(),
+            &[Segment::new(
+                0x1000,
+                vec![0xAB, 0xAB, 0x01, 0x02, 0xAB, 0xAB, 0x02, 0xAF]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_write_log_commands_round_trip() {
+        let kinds = vec![
+            LogCommandKind::FileOpen(PathBuf::from("in put.hex")),
+            LogCommandKind::FileNew,
+            LogCommandKind::FillRange {
+                range: Range::from_start_length(0x1000, 4).unwrap(),
+                pattern: vec![0x01, 0xAB],
+            },
+            LogCommandKind::CutRange(vec![
+                Range::from_start_end(0x0, 0xF).unwrap(),
+                Range::from_start_end(0x100, u32::MAX).unwrap(),
+            ]),
+            LogCommandKind::MergeFile {
+                path: PathBuf::from("patch.hex"),
+                offset: -0x20,
+                range: Some(Range::from_start_end(0x20, 0x2F).unwrap()),
+            },
+            LogCommandKind::CreateChecksum {
+                algorithm: ChecksumAlgorithm::Sha256,
+                target: ChecksumTarget::File(PathBuf::from("sum file.txt")),
+                range: None,
+            },
+            LogCommandKind::CreateChecksum {
+                algorithm: ChecksumAlgorithm::Crc16,
+                target: ChecksumTarget::OverwriteEnd,
+                range: Some(Range::from_start_end(0x1000, 0x1003).unwrap()),
+            },
+            LogCommandKind::CreateChecksum {
+                algorithm: ChecksumAlgorithm::ByteSumBe,
+                target: ChecksumTarget::Begin,
+                range: None,
+            },
+            LogCommandKind::Comment("/CS7 = 1234".to_string()),
+            LogCommandKind::FileSaveAs {
+                path: PathBuf::from("out.bin"),
+                format: LogSaveFormat::Binary,
+            },
+        ];
+        let text = write_log_commands(&kinds);
+        assert!(text.contains("FillRange 0x1000-0x1003 01AB\n"), "{text}");
+        let parsed: Vec<_> = parse_log_commands(&text)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.kind)
+            .collect();
+        assert_eq!(parsed, kinds);
+    }
+
     #[test]
     fn test_execute_log_commands_fileopen() {
         let commands = vec![LogCommand {
@@ -1,26 +1,47 @@
+mod address_table;
+mod bank;
 mod checksum;
+mod compare;
+#[cfg(feature = "serde")]
+mod description;
 mod error;
 mod filter;
 mod flags;
 mod log;
+#[cfg(feature = "parallel")]
+mod parallel;
 mod pipeline;
+mod sector_map;
+mod stamp;
 mod transform;
 
-pub use checksum::{ChecksumAlgorithm, ChecksumJob, ChecksumOptions, ChecksumTarget, ForcedRange};
+pub use address_table::{AddressMapping, AddressTable, AddressTableError};
+pub use checksum::{
+    ChecksumAlgorithm, ChecksumGapFill, ChecksumJob, ChecksumOptions, ChecksumTarget, ForcedRange,
+    SegmentInfo,
+};
+#[cfg(feature = "serde")]
+pub use description::{DescriptionError, PageWindow, PipelineDescription, StepDescription};
 pub use error::OpsError;
-pub use filter::{FillOptions, MergeMode, MergeOptions};
+pub use filter::{DEFAULT_MAX_FILL_SPAN, FillOptions, FillPattern, MergeMode, MergeOptions};
 pub use flags::{
-    flag_align, flag_checksum, flag_cut_ranges, flag_dspic_clear_ghost, flag_dspic_expand,
-    flag_dspic_shrink, flag_execute_log_file, flag_fill_all, flag_fill_ranges_pattern,
-    flag_fill_ranges_random, flag_filter_ranges, flag_map_star08, flag_map_star12,
-    flag_map_star12x, flag_merge_opaque, flag_merge_transparent, flag_remap, flag_split,
-    flag_swap_long, flag_swap_word, random_fill_bytes, random_fill_seed_from_time,
+    flag_align, flag_checksum, flag_checksum_blocks, flag_cut_ranges, flag_dspic_clear_ghost,
+    flag_dspic_expand, flag_dspic_insert_ghost, flag_dspic_shrink, flag_execute_log_file,
+    flag_fill_all, flag_fill_all_chunked, flag_fill_ranges_pattern, flag_fill_ranges_random,
+    flag_filter_ranges, flag_map_star08, flag_map_star12, flag_map_star12x, flag_merge_opaque,
+    flag_merge_transparent, flag_remap, flag_remap_table, flag_split, flag_split_at_boundaries,
+    flag_swap_long, flag_swap_word, random_fill_bytes, random_fill_seed,
+    random_fill_seed_from_time, seeded_random_fill,
 };
 pub use log::{
-    LogCommand, LogCommandKind, LogError, execute_log_commands, execute_log_file,
-    parse_log_commands,
+    LogCommand, LogCommandKind, LogError, LogSaveFormat, execute_log_commands, execute_log_file,
+    parse_log_commands, write_log_commands,
 };
 pub use pipeline::{
-    Pipeline, PipelineChecksum, PipelineDspic, PipelineError, PipelineMerge, PipelineResult,
+    BigImageOptions, CustomStep, Pipeline, PipelineBuilder, PipelineChecksum, PipelineDspic,
+    PipelineError, PipelineGhost, PipelineLabel, PipelineMerge, PipelineRecord, PipelineResult,
+    PipelineStamp, PipelineStep, StageTrace,
 };
+pub use sector_map::{Sector, SectorMap, SectorMapError};
+pub use stamp::{AsciiPad, DateEncoding, DateFormat, DateStamp};
 pub use transform::{AlignOptions, BankedMapOptions, RemapOptions, SwapMode};
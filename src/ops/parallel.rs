@@ -0,0 +1,121 @@
+//! Chunked checksums across rayon workers (feature `parallel`).
+//!
+//! Sums are added per chunk and wrapped; CRC-32 chunk results are joined with
+//! zlib's `crc32_combine`, so both match the serial functions bit for bit.
+
+use rayon::prelude::*;
+
+/// Inputs shorter than this stay on the calling thread.
+pub(super) const MIN_LEN: usize = 1 << 20;
+
+/// Even, so word sums never split a word.
+const CHUNK: usize = 256 << 10;
+
+/// Reflected CRC-32 (ISO-HDLC) polynomial.
+const POLY: u32 = 0xEDB8_8320;
+
+pub(super) fn wrapping_sum(data: &[u8], sum: fn(&[u8]) -> u16) -> u16 {
+    data.par_chunks(CHUNK)
+        .map(sum)
+        .reduce(|| 0, u16::wrapping_add)
+}
+
+/// CRC-32 of `data`, with `crc` computing the CRC of one chunk.
+pub(super) fn crc32(data: &[u8], crc: fn(&[u8]) -> u32) -> u32 {
+    data.par_chunks(CHUNK)
+        .map(|chunk| (crc(chunk), chunk.len() as u64))
+        .reduce(
+            || (0, 0),
+            |(a, len_a), (b, len_b)| (crc32_combine(a, b, len_b), len_a + len_b),
+        )
+        .0
+}
+
+/// CRC-32 of `A ++ B` from `crc(A)`, `crc(B)` and the length of `B`.
+pub(super) fn crc32_combine(crc_a: u32, crc_b: u32, len_b: u64) -> u32 {
+    multmodp(x8nmodp(len_b), crc_a) ^ crc_b
+}
+
+/// `a * b` modulo the polynomial, in reflected bit order.
+fn multmodp(a: u32, mut b: u32) -> u32 {
+    let mut m = 1u32 << 31;
+    let mut p = 0;
+    loop {
+        if a & m != 0 {
+            p ^= b;
+            if a & (m - 1) == 0 {
+                return p;
+            }
+        }
+        m >>= 1;
+        b = if b & 1 != 0 { (b >> 1) ^ POLY } else { b >> 1 };
+    }
+}
+
+/// `x^(8n)` modulo the polynomial: the shift for appending `n` bytes.
+fn x8nmodp(mut n: u64) -> u32 {
+    // x^(2^k), starting at x^8 (k = 3).
+    let mut square = 1u32 << 30;
+    for _ in 0..3 {
+        square = multmodp(square, square);
+    }
+    let mut p = 1u32 << 31;
+    while n != 0 {
+        if n & 1 != 0 {
+            p = multmodp(square, p);
+        }
+        square = multmodp(square, square);
+        n >>= 1;
+    }
+    p
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CRC: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+
+    fn random_bytes(len: usize) -> Vec<u8> {
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    fn byte_sum(data: &[u8]) -> u16 {
+        data.iter().fold(0u16, |acc, &b| acc.wrapping_add(b as u16))
+    }
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        let data = random_bytes(3 * MIN_LEN + 4097);
+        for len in [0, 1, CHUNK - 1, CHUNK + 1, MIN_LEN + 3, data.len()] {
+            let data = &data[..len];
+            assert_eq!(wrapping_sum(data, byte_sum), byte_sum(data), "len {len}");
+            assert_eq!(
+                crc32(data, |c| CRC.checksum(c)),
+                CRC.checksum(data),
+                "len {len}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_crc32_combine() {
+        let data = random_bytes(10_007);
+        for split in [0, 1, 4096, 9_999, data.len()] {
+            let (a, b) = data.split_at(split);
+            assert_eq!(
+                crc32_combine(CRC.checksum(a), CRC.checksum(b), b.len() as u64),
+                CRC.checksum(&data),
+                "split {split}"
+            );
+        }
+    }
+}
@@ -1,17 +1,20 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use thiserror::Error;
 
 use crate::{
-    AlignOptions, ChecksumAlgorithm, ChecksumTarget, ForcedRange, HexFile, Range, RemapOptions,
+    AddressTable, AlignOptions, AsciiPad, BankedMapOptions, ChecksumGapFill, ChecksumOptions,
+    ChecksumTarget, DateFormat, DateStamp, FillPattern, HexFile, Range, RangeError, RangeSet,
+    RangeSpec, RemapOptions, resolve_ranges,
 };
 
 use super::{
-    LogCommand, LogError, OpsError, execute_log_commands, flag_align, flag_checksum,
-    flag_cut_ranges, flag_dspic_clear_ghost, flag_dspic_expand, flag_dspic_shrink, flag_fill_all,
-    flag_fill_ranges_pattern, flag_fill_ranges_random, flag_filter_ranges, flag_map_star08,
-    flag_map_star12, flag_map_star12x, flag_merge_opaque, flag_merge_transparent, flag_remap,
-    flag_split, flag_swap_long, flag_swap_word,
+    LogCommand, LogCommandKind, LogError, MergeMode, OpsError, SwapMode, execute_log_commands,
+    flag_align, flag_cut_ranges, flag_dspic_clear_ghost, flag_dspic_expand,
+    flag_dspic_insert_ghost, flag_dspic_shrink, flag_fill_all, flag_fill_all_chunked,
+    flag_fill_ranges_pattern, flag_fill_ranges_random, flag_map_star08, flag_map_star12,
+    flag_map_star12x, flag_merge_opaque, flag_merge_transparent, flag_remap, flag_remap_table,
+    flag_split, flag_split_at_boundaries, flag_swap_long, flag_swap_word,
 };
 
 #[derive(Debug, Clone)]
@@ -19,16 +22,63 @@ pub struct PipelineMerge {
     pub other: HexFile,
     pub offset: i64,
     pub range: Option<Range>,
+    /// File `other` was loaded from; an opaque merge is recorded as
+    /// `MergeFile` only when this is set.
+    pub path: Option<PathBuf>,
 }
 
+/// One checksum job (CLI: /CS, /CSM, /CSB). Jobs run in order after the
+/// stamps, so each sees the results written by the ones before it.
 #[derive(Debug, Clone)]
 pub struct PipelineChecksum {
-    pub algorithm: ChecksumAlgorithm,
-    pub range: Option<Range>,
-    pub little_endian_output: bool,
-    pub forced_range: Option<ForcedRange>,
-    pub exclude_ranges: Vec<Range>,
+    pub options: ChecksumOptions,
     pub target: ChecksumTarget,
+    /// Resolved against the data when the job runs and used instead of
+    /// `options.range`; a spec that finds no data is an error.
+    pub range: Option<RangeSpec>,
+    /// Checksum each block on its own, one result per block.
+    pub per_block: bool,
+}
+
+impl PipelineChecksum {
+    pub fn new(options: ChecksumOptions, target: ChecksumTarget) -> Self {
+        Self {
+            options,
+            target,
+            range: None,
+            per_block: false,
+        }
+    }
+
+    /// Whether a `CreateChecksum` command reproduces this job. It has no
+    /// endianness, forced range, excludes, seed or gap handling.
+    pub fn is_recordable(&self) -> bool {
+        let options = &self.options;
+        !self.per_block
+            && !options.little_endian_output
+            && options.forced_range.is_none()
+            && options.exclude_ranges.is_empty()
+            && options.initial.is_none()
+            && !options.target_as_fill
+            && options.gap_fill == ChecksumGapFill::Skip
+    }
+}
+
+/// Data written into the image before the checksums.
+#[derive(Debug, Clone)]
+pub enum PipelineStamp {
+    /// CLI: /WS.
+    Ascii {
+        address: u32,
+        text: String,
+        pad: AsciiPad,
+    },
+    /// CLI: /WDATE.
+    Date {
+        address: u32,
+        format: DateFormat,
+        date: DateStamp,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -37,29 +87,82 @@ pub struct PipelineDspic {
     pub target: Option<u32>,
 }
 
+/// dsPIC ghost insertion (CLI: /CDSPI).
+#[derive(Debug, Clone)]
+pub struct PipelineGhost {
+    pub range: Range,
+    pub value: u8,
+}
+
+/// Block label (CLI: /LABEL); see [`HexFile::label_range`].
+#[derive(Debug, Clone)]
+pub struct PipelineLabel {
+    pub range: Range,
+    pub name: String,
+}
+
+/// Low-memory limits for big images (CLI: /BHFCT, /BTBS).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BigImageOptions {
+    /// Images spanning more than this many bytes take the low-memory path.
+    pub threshold: u64,
+    /// Size of the blocks and buffers used on that path.
+    pub block_size: usize,
+}
+
+impl BigImageOptions {
+    /// Whether the span from the lowest to the highest address of `hexfile`
+    /// exceeds the threshold.
+    pub fn applies_to(&self, hexfile: &HexFile) -> bool {
+        match (hexfile.min_address(), hexfile.max_address()) {
+            (Some(min), Some(max)) => max as u64 - min as u64 + 1 > self.threshold,
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Pipeline {
     pub hexfile: HexFile,
-    pub fill_ranges: Vec<Range>,
-    pub fill_pattern: Option<Vec<u8>>,
-    pub cut_ranges: Vec<Range>,
+    /// Range specs resolve against the data as each step reaches them, so an
+    /// `END` in `address_ranges` sees merged data and one in `fill_ranges`
+    /// does not.
+    pub fill_ranges: Vec<RangeSpec>,
+    pub fill_pattern: Option<FillPattern>,
+    pub cut_ranges: Vec<RangeSpec>,
     pub merge_transparent: Vec<PipelineMerge>,
     pub merge_opaque: Vec<PipelineMerge>,
-    pub address_ranges: Vec<Range>,
+    /// Applied in order after the merges, so merged data can be labelled.
+    pub labels: Vec<PipelineLabel>,
+    pub address_ranges: Vec<RangeSpec>,
     pub log_commands: Option<Vec<LogCommand>>,
     pub fill_all: Option<u8>,
+    /// Fill big images in blocks for `fill_all`.
+    pub big_image: Option<BigImageOptions>,
     pub align: Option<AlignOptions>,
     pub split: Option<u32>,
+    /// Split at multiples of this address, before `split`.
+    pub split_boundary: Option<u32>,
     pub swap_word: bool,
     pub swap_long: bool,
-    pub checksum: Option<PipelineChecksum>,
-    pub map_star12: bool,
-    pub map_star12x: bool,
-    pub map_star08: bool,
-    pub remap: Option<RemapOptions>,
+    /// Written after the swaps, in order.
+    pub stamps: Vec<PipelineStamp>,
+    /// Normalize the image once before the checksums so whole-image jobs
+    /// share it. Leave unset to keep the raw blocks for later steps.
+    pub normalize_for_checksums: bool,
+    pub checksums: Vec<PipelineChecksum>,
+    /// `Some(BankedMapOptions::STAR12)` is plain /S12MAP.
+    pub map_star12: Option<BankedMapOptions>,
+    pub map_star12x: Option<BankedMapOptions>,
+    pub map_star08: Option<BankedMapOptions>,
+    /// Applied in order, after the banked maps.
+    pub remap: Vec<RemapOptions>,
+    /// Applied after `remap`.
+    pub address_table: Option<AddressTable>,
     pub dspic_expand: Vec<PipelineDspic>,
     pub dspic_shrink: Vec<PipelineDspic>,
     pub dspic_clear_ghost: Vec<Range>,
+    pub dspic_insert_ghost: Vec<PipelineGhost>,
 }
 
 impl Default for Pipeline {
@@ -71,21 +174,28 @@ impl Default for Pipeline {
             cut_ranges: Vec::new(),
             merge_transparent: Vec::new(),
             merge_opaque: Vec::new(),
+            labels: Vec::new(),
             address_ranges: Vec::new(),
             log_commands: None,
             fill_all: None,
+            big_image: None,
             align: None,
             split: None,
+            split_boundary: None,
             swap_word: false,
             swap_long: false,
-            checksum: None,
-            map_star12: false,
-            map_star12x: false,
-            map_star08: false,
-            remap: None,
+            stamps: Vec::new(),
+            normalize_for_checksums: false,
+            checksums: Vec::new(),
+            map_star12: None,
+            map_star12x: None,
+            map_star08: None,
+            remap: Vec::new(),
+            address_table: None,
             dspic_expand: Vec::new(),
             dspic_shrink: Vec::new(),
             dspic_clear_ghost: Vec::new(),
+            dspic_insert_ghost: Vec::new(),
         }
     }
 }
@@ -96,113 +206,825 @@ pub enum PipelineError {
     Ops(#[from] OpsError),
     #[error(transparent)]
     Log(#[from] LogError),
+    #[error(transparent)]
+    Range(#[from] RangeError),
+    /// Entry `index` of [`Pipeline::stamps`] failed.
+    #[error("stamp {index}: {source}")]
+    Stamp {
+        index: usize,
+        source: Box<PipelineError>,
+    },
+    /// Entry `index` of [`Pipeline::checksums`] failed.
+    #[error("checksum {index}: {source}")]
+    Checksum {
+        index: usize,
+        source: Box<PipelineError>,
+    },
+    /// A [`PipelineBuilder`] step failed; `index` counts from 0 in push order.
+    #[error("step {index}: {source}")]
+    Step {
+        index: usize,
+        source: Box<PipelineError>,
+    },
+}
+
+/// A caller-supplied edit for [`PipelineStep::Custom`].
+pub type CustomStep = Box<dyn Fn(&mut HexFile) -> Result<(), OpsError>>;
+
+/// One operation for a [`PipelineBuilder`]. Each step records itself in the
+/// [`PipelineRecord`] as it does in the fixed [`Pipeline`] order.
+pub enum PipelineStep {
+    /// Fill the gaps in `ranges` with `pattern`, or with the random fill
+    /// bytes when `None` (CLI: /FR, /FP).
+    Fill {
+        ranges: Vec<RangeSpec>,
+        pattern: Option<FillPattern>,
+    },
+    /// Fill every gap between the lowest and highest address (CLI: /FA).
+    FillAll {
+        fill_byte: u8,
+        big_image: Option<BigImageOptions>,
+    },
+    Cut(Vec<RangeSpec>),
+    /// `MergeMode::Preserve` merges like /MT, `MergeMode::Overwrite` like /MO.
+    Merge {
+        merge: PipelineMerge,
+        mode: MergeMode,
+    },
+    /// Label the data in a range (CLI: /LABEL).
+    Label(PipelineLabel),
+    /// Keep only data inside the ranges (CLI: /AR).
+    Filter(Vec<RangeSpec>),
+    Log(Vec<LogCommand>),
+    Align(AlignOptions),
+    Split(u32),
+    /// Split at multiples of this address (CLI: /SBA).
+    SplitAtBoundaries(u32),
+    Swap(SwapMode),
+    Stamp(PipelineStamp),
+    /// Normalize the image in place.
+    Normalize,
+    /// Recorded as /S12MAP; see [`BankedMapOptions::STAR12`].
+    MapStar12(BankedMapOptions),
+    MapStar12x(BankedMapOptions),
+    MapStar08(BankedMapOptions),
+    Remap(RemapOptions),
+    /// Move data by an address table (CLI: /MAPTAB).
+    RemapTable(AddressTable),
+    DspicExpand(PipelineDspic),
+    DspicShrink(PipelineDspic),
+    DspicClearGhost(Range),
+    DspicInsertGhost(PipelineGhost),
+    /// Adds an entry to [`PipelineResult::checksum_bytes`].
+    Checksum(PipelineChecksum),
+    /// Any other edit; recorded as having no `/L` form.
+    Custom(CustomStep),
+}
+
+impl std::fmt::Debug for PipelineStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fill { ranges, pattern } => f
+                .debug_struct("Fill")
+                .field("ranges", ranges)
+                .field("pattern", pattern)
+                .finish(),
+            Self::FillAll {
+                fill_byte,
+                big_image,
+            } => f
+                .debug_struct("FillAll")
+                .field("fill_byte", fill_byte)
+                .field("big_image", big_image)
+                .finish(),
+            Self::Cut(ranges) => f.debug_tuple("Cut").field(ranges).finish(),
+            Self::Merge { merge, mode } => f
+                .debug_struct("Merge")
+                .field("merge", merge)
+                .field("mode", mode)
+                .finish(),
+            Self::Label(label) => f.debug_tuple("Label").field(label).finish(),
+            Self::Filter(ranges) => f.debug_tuple("Filter").field(ranges).finish(),
+            Self::Log(commands) => f.debug_tuple("Log").field(commands).finish(),
+            Self::Align(align) => f.debug_tuple("Align").field(align).finish(),
+            Self::Split(size) => f.debug_tuple("Split").field(size).finish(),
+            Self::SplitAtBoundaries(boundary) => {
+                f.debug_tuple("SplitAtBoundaries").field(boundary).finish()
+            }
+            Self::Swap(mode) => f.debug_tuple("Swap").field(mode).finish(),
+            Self::Stamp(stamp) => f.debug_tuple("Stamp").field(stamp).finish(),
+            Self::Normalize => f.write_str("Normalize"),
+            Self::MapStar12(map) => f.debug_tuple("MapStar12").field(map).finish(),
+            Self::MapStar12x(map) => f.debug_tuple("MapStar12x").field(map).finish(),
+            Self::MapStar08(map) => f.debug_tuple("MapStar08").field(map).finish(),
+            Self::Remap(remap) => f.debug_tuple("Remap").field(remap).finish(),
+            Self::RemapTable(table) => f.debug_tuple("RemapTable").field(table).finish(),
+            Self::DspicExpand(op) => f.debug_tuple("DspicExpand").field(op).finish(),
+            Self::DspicShrink(op) => f.debug_tuple("DspicShrink").field(op).finish(),
+            Self::DspicClearGhost(range) => f.debug_tuple("DspicClearGhost").field(range).finish(),
+            Self::DspicInsertGhost(op) => f.debug_tuple("DspicInsertGhost").field(op).finish(),
+            Self::Checksum(checksum) => f.debug_tuple("Checksum").field(checksum).finish(),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// Steps run by [`Pipeline::execute`] as `/L` commands (CLI: /LOGOUT).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PipelineRecord {
+    pub commands: Vec<LogCommandKind>,
+    /// Steps that ran but have no `/L` equivalent, by CLI option name.
+    pub unrecorded: Vec<&'static str>,
+    /// Valid but suspicious results, e.g. a checksum range with gaps.
+    pub warnings: Vec<String>,
+    /// Worth knowing, e.g. a fill that kept existing data.
+    pub notes: Vec<String>,
+}
+
+impl PipelineRecord {
+    /// Note a step that ran without an `/L` form.
+    pub fn skip(&mut self, option: &'static str) {
+        if !self.unrecorded.contains(&option) {
+            self.unrecorded.push(option);
+        }
+    }
+
+    /// Add the steps of `other` after these.
+    pub fn append(&mut self, other: PipelineRecord) {
+        self.commands.extend(other.commands);
+        for option in other.unrecorded {
+            self.skip(option);
+        }
+        self.warnings.extend(other.warnings);
+        self.notes.extend(other.notes);
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct PipelineResult {
     pub hexfile: HexFile,
-    pub checksum_bytes: Option<Vec<u8>>,
+    /// One entry per checksum step, in order: a single result, or one per
+    /// block for a per-block job.
+    pub checksum_bytes: Vec<Vec<Vec<u8>>>,
+    pub record: PipelineRecord,
 }
 
-impl Pipeline {
+/// A step that just ran, passed to the callback of
+/// [`Pipeline::execute_traced`] (CLI: /VERBOSE).
+#[derive(Debug, Clone, Copy)]
+pub struct StageTrace<'a> {
+    /// Position of the step among all steps, counting from 0.
+    pub index: usize,
+    /// CLI option the step stands for, e.g. `/FR`.
+    pub stage: &'static str,
+    /// The step's settings in short form, e.g. `0x1000-0x10FF pattern FF`.
+    pub params: &'a str,
+    /// The image as the step left it.
+    pub hexfile: &'a HexFile,
+}
+
+/// `/FR 0x1000-0x10FF pattern FF: 1 segment(s), 0x1000-0x10FF, 256 byte(s)`.
+impl std::fmt::Display for StageTrace<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.stage)?;
+        if !self.params.is_empty() {
+            write!(f, " {}", self.params)?;
+        }
+        write!(f, ": {} segment(s), ", self.hexfile.segments().len())?;
+        match (self.hexfile.min_address(), self.hexfile.max_address()) {
+            (Some(min), Some(max)) => write!(f, "0x{min:X}-0x{max:X}")?,
+            _ => f.write_str("no data")?,
+        }
+        write!(f, ", {} byte(s)", self.hexfile.total_bytes())
+    }
+}
+
+/// Steps run in the order they were pushed, for orders the fixed
+/// [`Pipeline`] cannot express (a checksum before a split, two fill passes).
+#[derive(Debug, Default)]
+pub struct PipelineBuilder {
+    pub hexfile: HexFile,
+    pub steps: Vec<PipelineStep>,
+}
+
+impl PipelineBuilder {
+    pub fn new(hexfile: HexFile) -> Self {
+        Self {
+            hexfile,
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn push(mut self, step: PipelineStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Errors are wrapped in [`PipelineError::Step`] with the failing index.
     pub fn execute<F, L, E>(
         self,
-        mut random_fill: F,
-        mut log_loader: L,
+        random_fill: F,
+        log_loader: L,
     ) -> Result<PipelineResult, PipelineError>
     where
         F: FnMut(Range) -> Vec<u8>,
         L: FnMut(&Path) -> Result<HexFile, E>,
         E: Into<Box<dyn std::error::Error>>,
     {
-        let mut hexfile = self.hexfile;
+        self.execute_traced(random_fill, log_loader, |_| {})
+    }
 
-        if self.map_star12 {
-            flag_map_star12(&mut hexfile)?;
-        }
-        if self.map_star12x {
-            flag_map_star12x(&mut hexfile)?;
-        }
-        if self.map_star08 {
-            flag_map_star08(&mut hexfile)?;
-        }
-        if let Some(ref remap) = self.remap {
-            flag_remap(&mut hexfile, remap)?;
-        }
+    /// [`PipelineBuilder::execute`], calling `trace` after each step that
+    /// does something; fills and cuts without ranges are skipped.
+    pub fn execute_traced<F, L, E, T>(
+        self,
+        random_fill: F,
+        log_loader: L,
+        trace: T,
+    ) -> Result<PipelineResult, PipelineError>
+    where
+        F: FnMut(Range) -> Vec<u8>,
+        L: FnMut(&Path) -> Result<HexFile, E>,
+        E: Into<Box<dyn std::error::Error>>,
+        T: FnMut(&StageTrace),
+    {
+        self.run(random_fill, log_loader, trace)
+            .map_err(|(index, err)| PipelineError::Step {
+                index,
+                source: Box::new(err),
+            })
+    }
 
-        for op in &self.dspic_expand {
-            flag_dspic_expand(&mut hexfile, op.range, op.target)?;
-        }
-        for op in &self.dspic_shrink {
-            flag_dspic_shrink(&mut hexfile, op.range, op.target)?;
-        }
-        for range in &self.dspic_clear_ghost {
-            flag_dspic_clear_ghost(&mut hexfile, *range)?;
-        }
+    pub fn execute_without_log<F>(self, random_fill: F) -> Result<PipelineResult, PipelineError>
+    where
+        F: FnMut(Range) -> Vec<u8>,
+    {
+        self.execute(random_fill, |_| {
+            Err(std::io::Error::other("log loader not provided"))
+        })
+    }
 
-        if let Some(ref pattern) = self.fill_pattern {
-            flag_fill_ranges_pattern(&mut hexfile, &self.fill_ranges, pattern);
-        } else {
-            flag_fill_ranges_random(&mut hexfile, &self.fill_ranges, &mut random_fill);
+    fn run<F, L, E, T>(
+        self,
+        mut random_fill: F,
+        mut log_loader: L,
+        mut trace: T,
+    ) -> Result<PipelineResult, (usize, PipelineError)>
+    where
+        F: FnMut(Range) -> Vec<u8>,
+        L: FnMut(&Path) -> Result<HexFile, E>,
+        E: Into<Box<dyn std::error::Error>>,
+        T: FnMut(&StageTrace),
+    {
+        let mut result = PipelineResult {
+            hexfile: self.hexfile,
+            checksum_bytes: Vec::new(),
+            record: PipelineRecord::default(),
+        };
+        for (index, step) in self.steps.into_iter().enumerate() {
+            let traced = (!step.does_nothing()).then(|| step.describe());
+            step.apply(&mut result, &mut random_fill, &mut log_loader)
+                .map_err(|err| (index, err))?;
+            if let Some((stage, params)) = traced {
+                trace(&StageTrace {
+                    index,
+                    stage,
+                    params: &params,
+                    hexfile: &result.hexfile,
+                });
+            }
         }
+        Ok(result)
+    }
+}
 
-        flag_cut_ranges(&mut hexfile, &self.cut_ranges);
+/// The parts of `range` without data, counting `covered` as data.
+fn data_gaps(hexfile: &HexFile, range: Range, covered: &[Range]) -> Vec<Range> {
+    let occupied: RangeSet = hexfile
+        .segments()
+        .iter()
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| Range::from_start_end(s.start_address, s.end_address()).ok())
+        .chain(covered.iter().copied())
+        .filter_map(|r| r.intersection(&range))
+        .collect();
+    occupied.gaps_within(range)
+}
 
-        for merge in &self.merge_transparent {
-            flag_merge_transparent(&mut hexfile, &merge.other, merge.offset, merge.range)?;
-        }
-        for merge in &self.merge_opaque {
-            flag_merge_opaque(&mut hexfile, &merge.other, merge.offset, merge.range)?;
+/// A fill only writes gaps: note one that keeps data, warn about one that
+/// has nothing left to fill.
+fn note_fill_overlap(hexfile: &HexFile, record: &mut PipelineRecord, ranges: &[Range]) {
+    for &range in ranges {
+        match data_gaps(hexfile, range, &[]).as_slice() {
+            [] => record.warnings.push(format!(
+                "/FR range {range} is already full of data; nothing was filled"
+            )),
+            [gap] if *gap == range => {}
+            _ => record.notes.push(format!(
+                "/FR range {range} overlaps existing data, which is kept"
+            )),
         }
+    }
+}
 
-        flag_filter_ranges(&mut hexfile, &self.address_ranges);
+/// Gaps a checksum skips silently; forced and excluded ranges do not count.
+fn warn_checksum_gaps(hexfile: &HexFile, record: &mut PipelineRecord, options: &ChecksumOptions) {
+    let (Some(range), ChecksumGapFill::Skip) = (options.range, options.gap_fill) else {
+        return;
+    };
+    let mut covered = options.exclude_ranges.clone();
+    covered.extend(options.forced_range.as_ref().map(|forced| forced.range));
+    let gaps = data_gaps(hexfile, range, &covered);
+    if let Some(first) = gaps.first() {
+        record.warnings.push(format!(
+            "checksum range {range} has {} gap(s) without data, the first at {first}; \
+             they are skipped",
+            gaps.len()
+        ));
+    }
+}
 
-        if let Some(ref commands) = self.log_commands {
-            execute_log_commands(&mut hexfile, commands, &mut log_loader)?;
-        }
+/// Random fill, recorded with a pattern as long as the range so the log
+/// replays the bytes exactly.
+fn fill_random<F>(
+    hexfile: &mut HexFile,
+    record: &mut PipelineRecord,
+    ranges: &[Range],
+    mut random_fill: F,
+) where
+    F: FnMut(Range) -> Vec<u8>,
+{
+    flag_fill_ranges_random(hexfile, ranges, |range| {
+        let data = random_fill(range);
+        record.commands.push(LogCommandKind::FillRange {
+            range,
+            pattern: data.clone(),
+        });
+        data
+    });
+}
+
+impl PipelineStep {
+    /// Fills and cuts without ranges.
+    pub(crate) fn does_nothing(&self) -> bool {
+        matches!(self, Self::Fill { ranges, .. } | Self::Cut(ranges) if ranges.is_empty())
+    }
 
-        if let Some(fill_byte) = self.fill_all {
-            flag_fill_all(&mut hexfile, fill_byte);
+    /// The CLI option and short settings for a [`StageTrace`].
+    fn describe(&self) -> (&'static str, String) {
+        let ranges = |ranges: &[RangeSpec]| {
+            ranges
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        match self {
+            Self::Fill {
+                ranges: specs,
+                pattern,
+            } => {
+                let pattern = match pattern {
+                    None | Some(FillPattern::Random { seed: None }) => "random".to_string(),
+                    Some(FillPattern::Random { seed: Some(seed) }) => {
+                        format!("random seed {seed}")
+                    }
+                    Some(FillPattern::Bytes(bytes)) => format!(
+                        "pattern {}",
+                        bytes.iter().map(|b| format!("{b:02X}")).collect::<String>()
+                    ),
+                    Some(FillPattern::Increment { start, step }) => {
+                        format!("increment {start:02X}+{step}")
+                    }
+                };
+                ("/FR", format!("{} {pattern}", ranges(specs)))
+            }
+            Self::FillAll { fill_byte, .. } => ("/FA", format!("fill {fill_byte:02X}")),
+            Self::Cut(specs) => ("/CR", ranges(specs)),
+            Self::Merge { merge, mode } => {
+                let stage = match mode {
+                    MergeMode::Preserve => "/MT",
+                    MergeMode::Overwrite => "/MO",
+                };
+                let mut params = match &merge.path {
+                    Some(path) => path.display().to_string(),
+                    None => "image".to_string(),
+                };
+                if merge.offset != 0 {
+                    params.push_str(&format!(" offset {}", merge.offset));
+                }
+                if let Some(range) = merge.range {
+                    params.push_str(&format!(" range {range}"));
+                }
+                (stage, params)
+            }
+            Self::Label(label) => ("/LABEL", format!("{} {}", label.range, label.name)),
+            Self::Filter(specs) => ("/AR", ranges(specs)),
+            Self::Log(commands) => ("/L", format!("{} command(s)", commands.len())),
+            Self::Align(align) => {
+                let mut params = format!("0x{:X} fill {:02X}", align.alignment, align.fill_byte);
+                if align.align_length {
+                    params.push_str(" with length");
+                }
+                ("/AD", params)
+            }
+            Self::Split(size) => ("/SB", format!("0x{size:X}")),
+            Self::SplitAtBoundaries(boundary) => ("/SBA", format!("0x{boundary:X}")),
+            Self::Swap(SwapMode::Word) => ("/SWAPWORD", String::new()),
+            Self::Swap(SwapMode::DWord) => ("/SWAPLONG", String::new()),
+            Self::Stamp(PipelineStamp::Ascii { address, text, .. }) => {
+                ("/WS", format!("0x{address:X} {text:?}"))
+            }
+            Self::Stamp(PipelineStamp::Date {
+                address, format, ..
+            }) => ("/WDATE", format!("0x{address:X} {format}")),
+            Self::Normalize => ("normalize", String::new()),
+            Self::MapStar12(_) => ("/S12MAP", String::new()),
+            Self::MapStar12x(_) => ("/S12XMAP", String::new()),
+            Self::MapStar08(_) => ("/S08MAP", String::new()),
+            Self::Remap(remap) => (
+                "/REMAP",
+                format!(
+                    "0x{:X}-0x{:X} to 0x{:X}",
+                    remap.start, remap.end, remap.linear
+                ),
+            ),
+            Self::RemapTable(table) => {
+                ("/MAPTAB", format!("{} mapping(s)", table.mappings().len()))
+            }
+            Self::DspicExpand(op) => ("/CDSPX", dspic_params(op)),
+            Self::DspicShrink(op) => ("/CDSPS", dspic_params(op)),
+            Self::DspicClearGhost(range) => ("/CDSPG", range.to_string()),
+            Self::DspicInsertGhost(op) => {
+                ("/CDSPI", format!("{} value {:02X}", op.range, op.value))
+            }
+            Self::Checksum(checksum) => {
+                let mut params = format!("algorithm {}", checksum.options.algorithm as u8);
+                match (&checksum.range, checksum.options.range) {
+                    (Some(spec), _) => params.push_str(&format!(" range {spec}")),
+                    (None, Some(range)) => params.push_str(&format!(" range {range}")),
+                    (None, None) => {}
+                }
+                (if checksum.per_block { "/CSB" } else { "/CS" }, params)
+            }
+            Self::Custom(_) => ("custom step", String::new()),
         }
+    }
 
-        if let Some(ref align) = self.align {
-            flag_align(
-                &mut hexfile,
-                align.alignment,
-                align.fill_byte,
-                align.align_length,
-            )?;
+    fn apply<F, L, E>(
+        self,
+        result: &mut PipelineResult,
+        random_fill: &mut F,
+        log_loader: &mut L,
+    ) -> Result<(), PipelineError>
+    where
+        F: FnMut(Range) -> Vec<u8>,
+        L: FnMut(&Path) -> Result<HexFile, E>,
+        E: Into<Box<dyn std::error::Error>>,
+    {
+        let hexfile = &mut result.hexfile;
+        let record = &mut result.record;
+        match self {
+            Self::MapStar12(ref map) => {
+                flag_map_star12(hexfile, map)?;
+                record.skip("/S12MAP");
+            }
+            Self::MapStar12x(ref map) => {
+                flag_map_star12x(hexfile, map)?;
+                record.skip("/S12XMAP");
+            }
+            Self::MapStar08(ref map) => {
+                flag_map_star08(hexfile, map)?;
+                record.skip("/S08MAP");
+            }
+            Self::Remap(ref remap) => {
+                flag_remap(hexfile, remap)?;
+                record.skip("/REMAP");
+            }
+            Self::RemapTable(ref table) => {
+                flag_remap_table(hexfile, table)?;
+                record.skip("/MAPTAB");
+            }
+            Self::DspicExpand(op) => {
+                flag_dspic_expand(hexfile, op.range, op.target)?;
+                record.skip("/CDSPX");
+            }
+            Self::DspicShrink(op) => {
+                flag_dspic_shrink(hexfile, op.range, op.target)?;
+                record.skip("/CDSPS");
+            }
+            Self::DspicClearGhost(range) => {
+                flag_dspic_clear_ghost(hexfile, range)?;
+                record.skip("/CDSPG");
+            }
+            Self::DspicInsertGhost(op) => {
+                flag_dspic_insert_ghost(hexfile, op.range, op.value)?;
+                record.skip("/CDSPI");
+            }
+            Self::Fill {
+                ranges,
+                pattern: Some(FillPattern::Random { seed }),
+            } => {
+                let pattern = FillPattern::Random { seed };
+                let ranges = resolve_ranges(&ranges, hexfile);
+                note_fill_overlap(hexfile, record, &ranges);
+                fill_random(hexfile, record, &ranges, |range| pattern.range_bytes(range));
+            }
+            Self::Fill {
+                ranges,
+                pattern: Some(pattern),
+            } => {
+                let ranges = resolve_ranges(&ranges, hexfile);
+                if !pattern.is_empty() {
+                    note_fill_overlap(hexfile, record, &ranges);
+                }
+                flag_fill_ranges_pattern(hexfile, &ranges, &pattern);
+                if !pattern.is_empty() {
+                    let pattern = pattern.period();
+                    record
+                        .commands
+                        .extend(ranges.iter().map(|&range| LogCommandKind::FillRange {
+                            range,
+                            pattern: pattern.clone(),
+                        }));
+                }
+            }
+            Self::Fill {
+                ranges,
+                pattern: None,
+            } => {
+                let ranges = resolve_ranges(&ranges, hexfile);
+                note_fill_overlap(hexfile, record, &ranges);
+                fill_random(hexfile, record, &ranges, random_fill);
+            }
+            Self::Cut(ranges) => {
+                let ranges = resolve_ranges(&ranges, hexfile);
+                flag_cut_ranges(hexfile, &ranges);
+                if !ranges.is_empty() {
+                    record.commands.push(LogCommandKind::CutRange(ranges));
+                }
+            }
+            Self::Merge {
+                merge,
+                mode: MergeMode::Preserve,
+            } => {
+                flag_merge_transparent(hexfile, merge.other, merge.offset, merge.range)?;
+                record.skip("/MT");
+            }
+            Self::Merge {
+                merge,
+                mode: MergeMode::Overwrite,
+            } => {
+                flag_merge_opaque(hexfile, merge.other, merge.offset, merge.range)?;
+                match merge.path {
+                    Some(path) => record.commands.push(LogCommandKind::MergeFile {
+                        path,
+                        offset: merge.offset,
+                        range: merge.range,
+                    }),
+                    None => record.skip("/MO"),
+                }
+            }
+            Self::Label(label) => {
+                hexfile.label_range(label.range, label.name);
+                record.skip("/LABEL");
+            }
+            Self::Filter(ranges) => {
+                // Specs that resolve to nothing still filter: none left keeps no data.
+                let ranges = resolve_ranges(&ranges, hexfile);
+                hexfile.filter_ranges(&ranges);
+                let outside = complement(&ranges);
+                if !outside.is_empty() {
+                    record.commands.push(LogCommandKind::CutRange(outside));
+                }
+            }
+            Self::Log(commands) => {
+                execute_log_commands(hexfile, &commands, log_loader)?;
+                record
+                    .commands
+                    .extend(commands.into_iter().map(|command| command.kind));
+            }
+            Self::FillAll {
+                fill_byte,
+                big_image,
+            } => {
+                if let (Some(start), Some(end)) = (hexfile.min_address(), hexfile.max_address()) {
+                    record.commands.push(LogCommandKind::FillRange {
+                        range: Range::from_start_end(start, end).expect("min <= max"),
+                        pattern: vec![fill_byte],
+                    });
+                }
+                match big_image {
+                    Some(big) if big.applies_to(hexfile) => {
+                        flag_fill_all_chunked(hexfile, fill_byte, big.block_size)?;
+                    }
+                    _ => flag_fill_all(hexfile, fill_byte)?,
+                }
+            }
+            Self::Align(align) => {
+                flag_align(
+                    hexfile,
+                    align.alignment,
+                    align.fill_byte,
+                    align.align_length,
+                )?;
+                record.skip("/AD");
+            }
+            Self::SplitAtBoundaries(boundary) => {
+                flag_split_at_boundaries(hexfile, boundary);
+                record.skip("/SBA");
+            }
+            Self::Split(size) => {
+                flag_split(hexfile, size);
+                record.skip("/SB");
+            }
+            Self::Swap(SwapMode::Word) => {
+                flag_swap_word(hexfile)?;
+                record.skip("/SWAPWORD");
+            }
+            Self::Swap(SwapMode::DWord) => {
+                flag_swap_long(hexfile)?;
+                record.skip("/SWAPLONG");
+            }
+            Self::Stamp(PipelineStamp::Ascii { address, text, pad }) => {
+                hexfile.write_ascii(address, &text, &pad)?;
+                record.skip("/WS");
+            }
+            Self::Stamp(PipelineStamp::Date {
+                address,
+                format,
+                date,
+            }) => {
+                hexfile.write_date(address, &format, &date)?;
+                record.skip("/WDATE");
+            }
+            Self::Normalize => hexfile.ensure_normalized(),
+            Self::Checksum(mut checksum) => {
+                if let Some(spec) = checksum.range {
+                    let range = spec
+                        .resolve(hexfile)
+                        .ok_or_else(|| RangeError::NoData(spec.to_string()))?;
+                    checksum.options.range = Some(range);
+                }
+                warn_checksum_gaps(hexfile, record, &checksum.options);
+                let results = if checksum.per_block {
+                    hexfile.checksum_per_segment(&checksum.options, &checksum.target)?
+                } else {
+                    vec![hexfile.checksum(&checksum.options, &checksum.target)?]
+                };
+                if checksum.is_recordable() {
+                    record.commands.push(LogCommandKind::CreateChecksum {
+                        algorithm: checksum.options.algorithm,
+                        target: checksum.target,
+                        range: checksum.options.range,
+                    });
+                } else if checksum.per_block {
+                    record.skip("/CSB");
+                } else {
+                    record.skip("/CS");
+                }
+                result.checksum_bytes.push(results);
+            }
+            Self::Custom(edit) => {
+                edit(hexfile)?;
+                record.skip("custom step");
+            }
         }
+        Ok(())
+    }
+}
 
-        if let Some(size) = self.split {
-            flag_split(&mut hexfile, size);
+impl Pipeline {
+    /// The [`PipelineBuilder`] for `steps`, run in the order given.
+    pub fn from_steps(
+        hexfile: HexFile,
+        steps: impl IntoIterator<Item = PipelineStep>,
+    ) -> PipelineBuilder {
+        PipelineBuilder {
+            hexfile,
+            steps: steps.into_iter().collect(),
         }
+    }
 
+    /// These settings as steps in HexView order, which is what
+    /// [`Pipeline::execute`] runs.
+    pub fn into_steps(self) -> PipelineBuilder {
+        let mut steps = Vec::new();
+        steps.extend(self.map_star12.map(PipelineStep::MapStar12));
+        steps.extend(self.map_star12x.map(PipelineStep::MapStar12x));
+        steps.extend(self.map_star08.map(PipelineStep::MapStar08));
+        steps.extend(self.remap.into_iter().map(PipelineStep::Remap));
+        steps.extend(self.address_table.map(PipelineStep::RemapTable));
+        steps.extend(self.dspic_expand.into_iter().map(PipelineStep::DspicExpand));
+        steps.extend(self.dspic_shrink.into_iter().map(PipelineStep::DspicShrink));
+        steps.extend(
+            self.dspic_clear_ghost
+                .into_iter()
+                .map(PipelineStep::DspicClearGhost),
+        );
+        steps.extend(
+            self.dspic_insert_ghost
+                .into_iter()
+                .map(PipelineStep::DspicInsertGhost),
+        );
+        steps.push(PipelineStep::Fill {
+            ranges: self.fill_ranges,
+            pattern: self.fill_pattern,
+        });
+        steps.push(PipelineStep::Cut(self.cut_ranges));
+        steps.extend(
+            self.merge_transparent
+                .into_iter()
+                .map(|merge| PipelineStep::Merge {
+                    merge,
+                    mode: MergeMode::Preserve,
+                }),
+        );
+        steps.extend(
+            self.merge_opaque
+                .into_iter()
+                .map(|merge| PipelineStep::Merge {
+                    merge,
+                    mode: MergeMode::Overwrite,
+                }),
+        );
+        steps.extend(self.labels.into_iter().map(PipelineStep::Label));
+        if !self.address_ranges.is_empty() {
+            steps.push(PipelineStep::Filter(self.address_ranges));
+        }
+        steps.extend(self.log_commands.map(PipelineStep::Log));
+        steps.extend(self.fill_all.map(|fill_byte| PipelineStep::FillAll {
+            fill_byte,
+            big_image: self.big_image,
+        }));
+        steps.extend(self.align.map(PipelineStep::Align));
+        steps.extend(self.split_boundary.map(PipelineStep::SplitAtBoundaries));
+        steps.extend(self.split.map(PipelineStep::Split));
         if self.swap_word {
-            flag_swap_word(&mut hexfile)?;
+            steps.push(PipelineStep::Swap(SwapMode::Word));
         }
         if self.swap_long {
-            flag_swap_long(&mut hexfile)?;
-        }
-
-        let checksum_bytes = if let Some(ref checksum) = self.checksum {
-            Some(flag_checksum(
-                &mut hexfile,
-                checksum.algorithm,
-                checksum.range,
-                checksum.little_endian_output,
-                checksum.forced_range.clone(),
-                &checksum.exclude_ranges,
-                &checksum.target,
-            )?)
-        } else {
-            None
-        };
+            steps.push(PipelineStep::Swap(SwapMode::DWord));
+        }
+        steps.extend(self.stamps.into_iter().map(PipelineStep::Stamp));
+        if self.normalize_for_checksums && !self.checksums.is_empty() {
+            steps.push(PipelineStep::Normalize);
+        }
+        steps.extend(self.checksums.into_iter().map(PipelineStep::Checksum));
+        Self::from_steps(self.hexfile, steps)
+    }
 
-        Ok(PipelineResult {
-            hexfile,
-            checksum_bytes,
-        })
+    pub fn execute<F, L, E>(
+        self,
+        random_fill: F,
+        log_loader: L,
+    ) -> Result<PipelineResult, PipelineError>
+    where
+        F: FnMut(Range) -> Vec<u8>,
+        L: FnMut(&Path) -> Result<HexFile, E>,
+        E: Into<Box<dyn std::error::Error>>,
+    {
+        self.execute_traced(random_fill, log_loader, |_| {})
+    }
+
+    /// [`Pipeline::execute`], calling `trace` after each step that does
+    /// something, in the order of [`Pipeline::into_steps`].
+    pub fn execute_traced<F, L, E, T>(
+        self,
+        random_fill: F,
+        log_loader: L,
+        trace: T,
+    ) -> Result<PipelineResult, PipelineError>
+    where
+        F: FnMut(Range) -> Vec<u8>,
+        L: FnMut(&Path) -> Result<HexFile, E>,
+        E: Into<Box<dyn std::error::Error>>,
+        T: FnMut(&StageTrace),
+    {
+        let builder = self.into_steps();
+        let first = |kind: fn(&PipelineStep) -> bool| {
+            builder.steps.iter().position(kind).unwrap_or(usize::MAX)
+        };
+        let stamps = first(|step| matches!(step, PipelineStep::Stamp(_)));
+        let checksums = first(|step| matches!(step, PipelineStep::Checksum(_)));
+        builder
+            .run(random_fill, log_loader, trace)
+            .map_err(|(index, err)| {
+                let source = Box::new(err);
+                if index >= checksums {
+                    PipelineError::Checksum {
+                        index: index - checksums,
+                        source,
+                    }
+                } else if index >= stamps {
+                    PipelineError::Stamp {
+                        index: index - stamps,
+                        source,
+                    }
+                } else {
+                    *source
+                }
+            })
     }
 
     pub fn execute_without_log<F>(self, random_fill: F) -> Result<PipelineResult, PipelineError>
@@ -215,19 +1037,90 @@ impl Pipeline {
     }
 }
 
+fn dspic_params(op: &PipelineDspic) -> String {
+    match op.target {
+        Some(target) => format!("{} to 0x{target:X}", op.range),
+        None => op.range.to_string(),
+    }
+}
+
+/// Address space not covered by `ranges`, so `/AR` can be recorded as `CutRange`.
+fn complement(ranges: &[Range]) -> Vec<Range> {
+    let mut outside = Vec::new();
+    let mut next = Some(0u32);
+    for range in &RangeSet::from_ranges(ranges) {
+        if let Some(start) = next
+            && start < range.start()
+        {
+            outside.extend(Range::from_start_end(start, range.start() - 1).ok());
+        }
+        next = range.end().checked_add(1);
+    }
+    if let Some(start) = next {
+        outside.extend(Range::from_start_end(start, u32::MAX).ok());
+    }
+    outside
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Segment;
+    use crate::{ChecksumAlgorithm, Segment};
+
+    #[test]
+    fn test_pipeline_records_steps() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01, 0x02])]);
+        let pipeline = Pipeline {
+            hexfile,
+            fill_ranges: vec![Range::from_start_length(0x1004, 2).unwrap().into()],
+            cut_ranges: vec![Range::from_start_length(0x1001, 1).unwrap().into()],
+            merge_opaque: vec![PipelineMerge {
+                other: HexFile::with_segments(vec![Segment::new(0x0, vec![0xEE])]),
+                offset: 0x1008,
+                range: None,
+                path: Some(PathBuf::from("patch.hex")),
+            }],
+            address_ranges: vec![Range::from_start_end(0x1000, 0x10FF).unwrap().into()],
+            fill_all: Some(0xFF),
+            swap_word: true,
+            ..Default::default()
+        };
+
+        let result = pipeline.execute_without_log(|_| vec![0x5A, 0xA5]).unwrap();
+        assert_eq!(
+            result.record.commands,
+            vec![
+                LogCommandKind::FillRange {
+                    range: Range::from_start_length(0x1004, 2).unwrap(),
+                    pattern: vec![0x5A, 0xA5],
+                },
+                LogCommandKind::CutRange(vec![Range::from_start_length(0x1001, 1).unwrap()]),
+                LogCommandKind::MergeFile {
+                    path: PathBuf::from("patch.hex"),
+                    offset: 0x1008,
+                    range: None,
+                },
+                LogCommandKind::CutRange(vec![
+                    Range::from_start_end(0x0, 0xFFF).unwrap(),
+                    Range::from_start_end(0x1100, u32::MAX).unwrap(),
+                ]),
+                LogCommandKind::FillRange {
+                    range: Range::from_start_end(0x1000, 0x1008).unwrap(),
+                    pattern: vec![0xFF],
+                },
+            ]
+        );
+        assert_eq!(result.record.unrecorded, vec!["/SWAPWORD"]);
+    }
 
     #[test]
     fn test_pipeline_fill_cut_align() {
         let hexfile = HexFile::with_segments(vec![Segment::new(0x1001, vec![0xAA, 0xBB])]);
         let pipeline = Pipeline {
             hexfile,
-            fill_ranges: vec![Range::from_start_length(0x1000, 4).unwrap()],
-            fill_pattern: Some(vec![0xFF]),
-            cut_ranges: vec![Range::from_start_end(0x1002, 0x1002).unwrap()],
+            fill_ranges: vec![Range::from_start_length(0x1000, 4).unwrap().into()],
+            fill_pattern: Some(FillPattern::Bytes(vec![0xFF])),
+            cut_ranges: vec![Range::from_start_end(0x1002, 0x1002).unwrap().into()],
             align: Some(AlignOptions {
                 alignment: 4,
                 fill_byte: 0x00,
@@ -244,4 +1137,199 @@ mod tests {
         assert_eq!(norm.segments()[0].start_address, 0x1000);
         assert_eq!(norm.segments()[0].data.len(), 4);
     }
+
+    #[test]
+    fn test_pipeline_traces_steps_that_run() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x1001, vec![0xAA, 0xBB])]);
+        let pipeline = Pipeline {
+            hexfile,
+            fill_ranges: vec![Range::from_start_length(0x1000, 4).unwrap().into()],
+            fill_pattern: Some(FillPattern::Bytes(vec![0xFF])),
+            align: Some(AlignOptions {
+                alignment: 8,
+                fill_byte: 0x00,
+                align_length: true,
+            }),
+            ..Default::default()
+        };
+
+        let mut lines = Vec::new();
+        pipeline
+            .execute_traced(
+                |_| Vec::new(),
+                |_| Err(std::io::Error::other("unused")),
+                |stage| lines.push(format!("{}: {stage}", stage.index)),
+            )
+            .unwrap();
+        assert_eq!(
+            lines,
+            [
+                "0: /FR 0x1000-0x1003 pattern FF: 3 segment(s), 0x1000-0x1003, 4 byte(s)",
+                "2: /AD 0x8 fill 00 with length: 1 segment(s), 0x1000-0x1007, 8 byte(s)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pipeline_checksums_run_in_order_after_stamps() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01, 0x02])]);
+        let append = PipelineChecksum::new(ChecksumOptions::default(), ChecksumTarget::Append);
+        let pipeline = Pipeline {
+            hexfile,
+            stamps: vec![PipelineStamp::Ascii {
+                address: 0x1002,
+                text: "A".to_string(),
+                pad: AsciiPad::default(),
+            }],
+            checksums: vec![append.clone(), append],
+            ..Default::default()
+        };
+
+        let result = pipeline.execute_without_log(|_| Vec::new()).unwrap();
+        // 0x01 + 0x02 + 'A', then the first result appended as well.
+        assert_eq!(result.checksum_bytes, [[[0x00, 0x44]], [[0x00, 0x88]]]);
+        assert_eq!(
+            result.record.commands,
+            vec![
+                LogCommandKind::CreateChecksum {
+                    algorithm: ChecksumAlgorithm::ByteSumBe,
+                    target: ChecksumTarget::Append,
+                    range: None,
+                };
+                2
+            ]
+        );
+        assert_eq!(result.record.unrecorded, vec!["/WS"]);
+    }
+
+    #[test]
+    fn test_pipeline_checksum_error_names_job() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01])]);
+        let mut empty_range =
+            PipelineChecksum::new(ChecksumOptions::default(), ChecksumTarget::Append);
+        empty_range.range = Some(RangeSpec::new(None, Some(0xFFF)));
+        let pipeline = Pipeline {
+            hexfile,
+            checksums: vec![
+                PipelineChecksum::new(ChecksumOptions::default(), ChecksumTarget::Append),
+                empty_range,
+            ],
+            ..Default::default()
+        };
+
+        match pipeline.execute_without_log(|_| Vec::new()).unwrap_err() {
+            PipelineError::Checksum { index, source } => {
+                assert_eq!(index, 1);
+                assert!(
+                    matches!(*source, PipelineError::Range(RangeError::NoData(_))),
+                    "{source:?}"
+                );
+            }
+            other => panic!("expected a checksum error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_pipeline_warns_about_checksum_gaps() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x1000, vec![0x01]),
+            Segment::new(0x1004, vec![0x02]),
+        ]);
+        let mut ranged = PipelineChecksum::new(ChecksumOptions::default(), ChecksumTarget::Append);
+        ranged.range = Some(Range::from_start_end(0x1000, 0x1007).unwrap().into());
+        let mut filled = ranged.clone();
+        filled.options.gap_fill = ChecksumGapFill::Fill(0xFF);
+        let result = Pipeline {
+            hexfile,
+            checksums: vec![ranged, filled],
+            ..Default::default()
+        }
+        .execute_without_log(|_| Vec::new())
+        .unwrap();
+        // Only the job that skips its gaps says so.
+        assert_eq!(
+            result.record.warnings,
+            [
+                "checksum range 0x1000-0x1007 has 2 gap(s) without data, the first at \
+              0x1001-0x1003; they are skipped"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_builder_runs_steps_in_push_order() {
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x1000, vec![0x01]),
+            Segment::new(0x1002, vec![0x02]),
+        ]);
+        let checksum = PipelineChecksum::new(
+            ChecksumOptions::default(),
+            ChecksumTarget::File(PathBuf::from("sum.txt")),
+        );
+
+        // HexView order fills before the checksum.
+        let fixed = Pipeline {
+            hexfile: hexfile.clone(),
+            fill_all: Some(0xFF),
+            checksums: vec![checksum.clone()],
+            ..Default::default()
+        }
+        .execute_without_log(|_| Vec::new())
+        .unwrap();
+        assert_eq!(fixed.checksum_bytes, [[[0x01, 0x02]]]);
+
+        let fill = |pattern: u8| PipelineStep::Fill {
+            ranges: vec![Range::from_start_length(0x1001, 1).unwrap().into()],
+            pattern: Some(FillPattern::Bytes(vec![pattern])),
+        };
+        let custom = PipelineBuilder::new(hexfile)
+            .push(PipelineStep::Checksum(checksum))
+            .push(PipelineStep::Cut(vec![
+                Range::from_start_length(0x1000, 1).unwrap().into(),
+            ]))
+            .push(fill(0xAA))
+            .push(fill(0xBB))
+            .push(PipelineStep::FillAll {
+                fill_byte: 0xFF,
+                big_image: None,
+            })
+            .execute_without_log(|_| Vec::new())
+            .unwrap();
+        assert_eq!(custom.checksum_bytes, [[[0x00, 0x03]]]);
+        // The second fill finds no gap left.
+        assert_eq!(
+            custom.hexfile.normalized_lossy().segments(),
+            [Segment::new(0x1001, vec![0xAA, 0x02])]
+        );
+    }
+
+    #[test]
+    fn test_builder_error_names_step() {
+        let hexfile = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x01])]);
+        let err = Pipeline::from_steps(
+            hexfile,
+            [
+                PipelineStep::Custom(Box::new(|hexfile| {
+                    hexfile.fill_gaps(0xFF)?;
+                    Ok(())
+                })),
+                PipelineStep::Align(AlignOptions {
+                    alignment: 0,
+                    fill_byte: 0xFF,
+                    align_length: false,
+                }),
+            ],
+        )
+        .execute_without_log(|_| Vec::new())
+        .unwrap_err();
+
+        match err {
+            PipelineError::Step { index, ref source } => {
+                assert_eq!(index, 1);
+                assert!(matches!(**source, PipelineError::Ops(_)), "{source:?}");
+            }
+            other => panic!("expected a step error, got {other:?}"),
+        }
+        assert!(err.to_string().starts_with("step 1: "), "{err}");
+    }
 }
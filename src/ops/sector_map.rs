@@ -0,0 +1,223 @@
+//! Flash sector layouts for nonuniform flash (CLI: /AEMAP).
+//!
+//! A sector map file has one sector per line, `start-end` or `start,size`,
+//! optionally followed by `*count` for that many back-to-back sectors of the
+//! same size: `0x0,16K*4` is four 16K sectors from 0. `#` and `;` start a
+//! comment. Sectors may not overlap.
+
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use super::OpsError;
+use crate::{HexFile, Range};
+
+/// One flash sector and the map line that defined it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sector {
+    /// 1-based line in the map file, used in error messages.
+    pub line: usize,
+    pub range: Range,
+}
+
+/// Validated, address-ordered sectors for [`HexFile::touched_sectors`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SectorMap {
+    sectors: Vec<Sector>,
+}
+
+#[derive(Debug, Error)]
+pub enum SectorMapError {
+    #[error("line {line}: {message}")]
+    Syntax { line: usize, message: String },
+
+    #[error("line {line}: sector {range} overlaps line {other}")]
+    Overlap {
+        line: usize,
+        other: usize,
+        range: Range,
+    },
+}
+
+impl SectorMap {
+    /// Sorts the sectors by address and checks that none overlap.
+    pub fn new(mut sectors: Vec<Sector>) -> Result<Self, SectorMapError> {
+        sectors.sort_by_key(|s| (s.range.start(), s.line));
+        if let Some(pair) = sectors
+            .windows(2)
+            .find(|pair| pair[0].range.overlaps(&pair[1].range))
+        {
+            let (first, second) = (pair[0], pair[1]);
+            let (later, earlier) = if first.line < second.line {
+                (second, first)
+            } else {
+                (first, second)
+            };
+            return Err(SectorMapError::Overlap {
+                line: later.line,
+                other: earlier.line,
+                range: later.range,
+            });
+        }
+        Ok(Self { sectors })
+    }
+
+    pub fn sectors(&self) -> &[Sector] {
+        &self.sectors
+    }
+}
+
+impl FromStr for SectorMap {
+    type Err = SectorMapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut sectors = Vec::new();
+        for (index, raw_line) in s.lines().enumerate() {
+            let line = index + 1;
+            let text = raw_line.split(['#', ';']).next().unwrap_or_default().trim();
+            if text.is_empty() {
+                continue;
+            }
+            let syntax = |message: String| SectorMapError::Syntax { line, message };
+            let (range, count) = match text.split_once('*') {
+                Some((range, count)) => {
+                    let count = count
+                        .trim()
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|&count| count > 0)
+                        .ok_or_else(|| {
+                            syntax(format!("invalid sector count '{}'", count.trim()))
+                        })?;
+                    (range, count)
+                }
+                None => (text, 1),
+            };
+            let first: Range = range.trim().parse().map_err(|e| syntax(format!("{e}")))?;
+            for i in 0..count as u64 {
+                let start = first.start() as u64 + i * first.length() as u64;
+                let range = u32::try_from(start)
+                    .ok()
+                    .and_then(|start| Range::from_start_length(start, first.length()).ok())
+                    .ok_or_else(|| syntax(format!("sector {} runs past 0xFFFFFFFF", i + 1)))?;
+                sectors.push(Sector { line, range });
+            }
+        }
+        Self::new(sectors)
+    }
+}
+
+/// One `0x0-0x3FFF` line per sector, which parses back to the same sectors
+/// (line numbers aside).
+impl fmt::Display for SectorMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for sector in &self.sectors {
+            writeln!(f, "{}", sector.range)?;
+        }
+        Ok(())
+    }
+}
+
+impl HexFile {
+    /// The sectors of `map` that hold any data, with neighbouring sectors
+    /// merged into one run. Errors if data lies outside every sector.
+    pub fn touched_sectors(&self, map: &SectorMap) -> Result<Vec<Range>, OpsError> {
+        let sectors = map.sectors();
+        let mut touched = vec![false; sectors.len()];
+        for segment in self.normalized_segments().iter() {
+            let mut address = segment.start_address;
+            let end = segment.end_address();
+            loop {
+                let i = sectors.partition_point(|s| s.range.end() < address);
+                let Some(sector) = sectors.get(i).filter(|s| s.range.contains(address)) else {
+                    return Err(OpsError::OutsideSectorMap { address });
+                };
+                touched[i] = true;
+                match sector.range.end().checked_add(1) {
+                    Some(next) if next <= end => address = next,
+                    _ => break,
+                }
+            }
+        }
+
+        let mut runs: Vec<Range> = Vec::new();
+        for (sector, _) in sectors.iter().zip(touched).filter(|(_, hit)| *hit) {
+            match runs.last_mut() {
+                Some(run) if run.end().checked_add(1) == Some(sector.range.start()) => {
+                    *run = Range::from_start_end(run.start(), sector.range.end())
+                        .expect("sectors are sorted");
+                }
+                _ => runs.push(sector.range),
+            }
+        }
+        Ok(runs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Segment;
+
+    fn range(start: u32, end: u32) -> Range {
+        Range::from_start_end(start, end).unwrap()
+    }
+
+    #[test]
+    fn test_parse_expands_counts() {
+        let map: SectorMap = "# boot\n0x0,16K*2 ; small\n0x8000-0xFFFF\n"
+            .parse()
+            .unwrap();
+        let ranges: Vec<_> = map.sectors().iter().map(|s| (s.line, s.range)).collect();
+        assert_eq!(
+            ranges,
+            [
+                (2, range(0x0, 0x3FFF)),
+                (2, range(0x4000, 0x7FFF)),
+                (3, range(0x8000, 0xFFFF)),
+            ]
+        );
+        assert_eq!(
+            map.to_string(),
+            "0x0-0x3FFF\n0x4000-0x7FFF\n0x8000-0xFFFF\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_reports_line_numbers() {
+        let err = "0x0,16K\n0x2000,16K\n".parse::<SectorMap>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2: sector 0x2000-0x5FFF overlaps line 1"
+        );
+        let err = "0x0,16K*0".parse::<SectorMap>().unwrap_err();
+        assert_eq!(err.to_string(), "line 1: invalid sector count '0'");
+        let err = "0xFFFF0000,64K*2".parse::<SectorMap>().unwrap_err();
+        assert_eq!(err.to_string(), "line 1: sector 2 runs past 0xFFFFFFFF");
+    }
+
+    #[test]
+    fn test_touched_sectors_merges_neighbours() {
+        let map: SectorMap = "0x0,0x100*4\n0x1000,0x100".parse().unwrap();
+        let hexfile = HexFile::with_segments(vec![
+            Segment::new(0x0F0, vec![0; 0x20]),
+            Segment::new(0x310, vec![0; 1]),
+            Segment::new(0x1080, vec![0; 1]),
+        ]);
+        assert_eq!(
+            hexfile.touched_sectors(&map).unwrap(),
+            [
+                range(0x0, 0x1FF),
+                range(0x300, 0x3FF),
+                range(0x1000, 0x10FF)
+            ]
+        );
+
+        let outside = HexFile::with_segments(vec![Segment::new(0x3F0, vec![0; 0x20])]);
+        assert_eq!(
+            outside.touched_sectors(&map).unwrap_err().to_string(),
+            "address 0x400 lies outside every sector"
+        );
+    }
+}
@@ -0,0 +1,353 @@
+//! ASCII strings and build dates written into the image (CLI: /WS, /WDATE).
+//!
+//! Date formats are `BCD:<pattern>` or `ASCII:<pattern>`. Pattern tokens are
+//! `YYYY`, `YY`, `MM`, `DD`, `hh`, `mm` and `ss`. ASCII patterns copy any
+//! other character; BCD patterns hold tokens only and pack two digits per byte.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::OpsError;
+use crate::HexFile;
+
+/// Layout of a string written by [`HexFile::write_ascii`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AsciiPad {
+    /// Append a 0x00 terminator after the text.
+    pub nul_terminate: bool,
+    /// Pad the field to this many bytes with `fill`; the text must fit.
+    pub width: Option<usize>,
+    pub fill: u8,
+}
+
+/// Calendar date and time in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateStamp {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl DateStamp {
+    /// The system clock.
+    pub fn now() -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self::from_unix(secs)
+    }
+
+    pub fn from_unix(secs: u64) -> Self {
+        let days = (secs / 86_400) as i64;
+        let time = secs % 86_400;
+        // Howard Hinnant's civil_from_days.
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        Self {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (time / 3600) as u8,
+            minute: (time / 60 % 60) as u8,
+            second: (time % 60) as u8,
+        }
+    }
+}
+
+/// `YYYY-MM-DD`, optionally followed by `Thh:mm:ss`.
+impl FromStr for DateStamp {
+    type Err = OpsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || OpsError::InvalidStamp(format!("invalid date {s:?}"));
+        let s = s.trim();
+        let (date, time) = s.split_once(['T', ' ']).unwrap_or((s, "00:00:00"));
+        let fields = |text: &str, sep: char| -> Option<Vec<u16>> {
+            text.split(sep).map(|part| part.parse().ok()).collect()
+        };
+        let (Some(date), Some(time)) = (fields(date, '-'), fields(time, ':')) else {
+            return Err(invalid());
+        };
+        let (&[year, month, day], &[hour, minute, second]) = (date.as_slice(), time.as_slice())
+        else {
+            return Err(invalid());
+        };
+        if !(1..=12).contains(&month)
+            || day == 0
+            || day > days_in_month(year, month)
+            || year > 9999
+            || hour > 23
+            || minute > 59
+            || second > 59
+        {
+            return Err(invalid());
+        }
+        Ok(Self {
+            year,
+            month: month as u8,
+            day: day as u8,
+            hour: hour as u8,
+            minute: minute as u8,
+            second: second as u8,
+        })
+    }
+}
+
+/// `YYYY-MM-DDThh:mm:ss`, which parses back to the same stamp.
+impl fmt::Display for DateStamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+fn days_in_month(year: u16, month: u16) -> u16 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateEncoding {
+    Bcd,
+    Ascii,
+}
+
+/// How [`HexFile::write_date`] encodes a date, e.g. `BCD:YYMMDD`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateFormat {
+    pub encoding: DateEncoding,
+    pub pattern: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Piece {
+    Year,
+    ShortYear,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    Literal(char),
+}
+
+const TOKENS: [(&str, Piece); 7] = [
+    ("YYYY", Piece::Year),
+    ("YY", Piece::ShortYear),
+    ("MM", Piece::Month),
+    ("DD", Piece::Day),
+    ("hh", Piece::Hour),
+    ("mm", Piece::Minute),
+    ("ss", Piece::Second),
+];
+
+fn pieces(pattern: &str) -> Vec<Piece> {
+    let mut out = Vec::new();
+    let mut rest = pattern;
+    while let Some(c) = rest.chars().next() {
+        match TOKENS.iter().find(|(token, _)| rest.starts_with(token)) {
+            Some((token, piece)) => {
+                out.push(*piece);
+                rest = &rest[token.len()..];
+            }
+            None => {
+                out.push(Piece::Literal(c));
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    out
+}
+
+impl DateFormat {
+    /// The encoded stamp for `date`.
+    pub fn encode(&self, date: &DateStamp) -> Vec<u8> {
+        let mut digits = String::new();
+        for piece in pieces(&self.pattern) {
+            match piece {
+                Piece::Year => digits.push_str(&format!("{:04}", date.year)),
+                Piece::ShortYear => digits.push_str(&format!("{:02}", date.year % 100)),
+                Piece::Month => digits.push_str(&format!("{:02}", date.month)),
+                Piece::Day => digits.push_str(&format!("{:02}", date.day)),
+                Piece::Hour => digits.push_str(&format!("{:02}", date.hour)),
+                Piece::Minute => digits.push_str(&format!("{:02}", date.minute)),
+                Piece::Second => digits.push_str(&format!("{:02}", date.second)),
+                Piece::Literal(c) => digits.push(c),
+            }
+        }
+        match self.encoding {
+            DateEncoding::Ascii => digits.into_bytes(),
+            DateEncoding::Bcd => digits
+                .as_bytes()
+                .chunks(2)
+                .map(|pair| (pair[0] - b'0') << 4 | (pair[1] - b'0'))
+                .collect(),
+        }
+    }
+}
+
+impl FromStr for DateFormat {
+    type Err = OpsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (encoding, pattern) = s.split_once(':').ok_or_else(|| {
+            OpsError::InvalidStamp(format!("date format {s:?} needs BCD: or ASCII:"))
+        })?;
+        let encoding = match encoding.to_ascii_uppercase().as_str() {
+            "BCD" => DateEncoding::Bcd,
+            "ASCII" => DateEncoding::Ascii,
+            _ => {
+                return Err(OpsError::InvalidStamp(format!(
+                    "unknown date encoding {encoding:?}"
+                )));
+            }
+        };
+        let pieces = pieces(pattern);
+        if pieces.is_empty() {
+            return Err(OpsError::InvalidStamp("empty date pattern".to_string()));
+        }
+        if encoding == DateEncoding::Bcd
+            && let Some(Piece::Literal(c)) = pieces.iter().find(|p| matches!(p, Piece::Literal(_)))
+        {
+            return Err(OpsError::InvalidStamp(format!(
+                "BCD date pattern {pattern:?} has literal {c:?}"
+            )));
+        }
+        if encoding == DateEncoding::Ascii && !pattern.is_ascii() {
+            return Err(OpsError::InvalidStamp(format!(
+                "date pattern {pattern:?} is not ASCII"
+            )));
+        }
+        Ok(Self {
+            encoding,
+            pattern: pattern.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for DateFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let encoding = match self.encoding {
+            DateEncoding::Bcd => "BCD",
+            DateEncoding::Ascii => "ASCII",
+        };
+        write!(f, "{encoding}:{}", self.pattern)
+    }
+}
+
+impl HexFile {
+    /// Write `text` at `addr`, overwriting existing data. CLI: /WS.
+    pub fn write_ascii(&mut self, addr: u32, text: &str, pad: &AsciiPad) -> Result<(), OpsError> {
+        if !text.is_ascii() {
+            return Err(OpsError::InvalidStamp(format!("{text:?} is not ASCII")));
+        }
+        let mut bytes = text.as_bytes().to_vec();
+        if pad.nul_terminate {
+            bytes.push(0);
+        }
+        if let Some(width) = pad.width {
+            if bytes.len() > width {
+                return Err(OpsError::InvalidStamp(format!(
+                    "{text:?} needs {} bytes, field width is {width}",
+                    bytes.len()
+                )));
+            }
+            bytes.resize(width, pad.fill);
+        }
+        self.write_stamp(addr, &bytes)
+    }
+
+    /// Write `date` at `addr` in `format`, overwriting existing data. CLI: /WDATE.
+    pub fn write_date(
+        &mut self,
+        addr: u32,
+        format: &DateFormat,
+        date: &DateStamp,
+    ) -> Result<(), OpsError> {
+        self.write_stamp(addr, &format.encode(date))
+    }
+
+    fn write_stamp(&mut self, addr: u32, bytes: &[u8]) -> Result<(), OpsError> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        if addr.checked_add(bytes.len() as u32 - 1).is_none() {
+            return Err(OpsError::AddressOverflow(format!(
+                "{addr:#X} + {} exceeds u32",
+                bytes.len()
+            )));
+        }
+        self.write_bytes(addr, bytes);
+        self.set_segments(self.normalized_lossy().into_segments());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Segment;
+
+    #[test]
+    fn test_write_ascii_pads_and_overwrites() {
+        let mut hf = HexFile::with_segments(vec![Segment::new(0x100, vec![0xFF; 12])]);
+        let pad = AsciiPad {
+            nul_terminate: true,
+            width: Some(8),
+            fill: b' ',
+        };
+        hf.write_ascii(0x102, "v1.2", &pad).unwrap();
+        assert_eq!(hf.segments().len(), 1);
+        assert_eq!(hf.segments()[0].data, b"\xFF\xFFv1.2\0   \xFF\xFF".to_vec());
+        assert!(hf.write_ascii(0x102, "v1.2.345", &pad).is_err());
+        assert!(hf.write_ascii(0x102, "ü", &AsciiPad::default()).is_err());
+        assert!(
+            hf.write_ascii(0xFFFF_FFFF, "ab", &AsciiPad::default())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_date_formats() {
+        let date: DateStamp = "2026-03-07T09:05:30".parse().unwrap();
+        let bcd: DateFormat = "BCD:YYYYMMDDhhmm".parse().unwrap();
+        assert_eq!(bcd.encode(&date), vec![0x20, 0x26, 0x03, 0x07, 0x09, 0x05]);
+        let ascii: DateFormat = "ascii:YY-MM-DD ss".parse().unwrap();
+        assert_eq!(ascii.encode(&date), b"26-03-07 30".to_vec());
+        assert_eq!(ascii.to_string(), "ASCII:YY-MM-DD ss");
+        assert!("BCD:YY-MM".parse::<DateFormat>().is_err());
+        assert!("YYMMDD".parse::<DateFormat>().is_err());
+        assert!("2026-02-29".parse::<DateStamp>().is_err());
+        assert!("2024-02-29".parse::<DateStamp>().is_ok());
+    }
+
+    #[test]
+    fn test_date_from_unix() {
+        assert_eq!(
+            DateStamp::from_unix(1_709_164_800 + 3_723),
+            "2024-02-29T01:02:03".parse().unwrap()
+        );
+        assert_eq!(DateStamp::from_unix(0), "1970-01-01".parse().unwrap());
+    }
+}
@@ -41,14 +41,69 @@ pub struct RemapOptions {
     pub inc: u32,
 }
 
-/// Options for banked address mapping.
-#[derive(Debug, Clone)]
+/// Options for banked address mapping. Page `bank` is seen at
+/// `(bank << 16) + window_start` and maps to
+/// `linear_base + (bank - bank_min) * window_size`.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BankedMapOptions {
     pub bank_min: u8,
     pub bank_max: u8,
+    pub window_start: u32,
+    pub window_size: u32,
     pub linear_base: u32,
-    pub nonbank_low_base: u32,
-    pub nonbank_high_base: u32,
+    /// Target of the non-banked window at 0x4000-0x7FFF; `None` leaves it.
+    pub nonbank_low_base: Option<u32>,
+    /// Target of the non-banked window at 0xC000-0xFFFF; `None` leaves it.
+    pub nonbank_high_base: Option<u32>,
+}
+
+impl BankedMapOptions {
+    /// HCS12 PPAGE 0x30-0x3F (CLI: /S12MAP).
+    pub const STAR12: Self = Self {
+        bank_min: 0x30,
+        bank_max: 0x3F,
+        window_start: 0x8000,
+        window_size: 0x4000,
+        linear_base: 0x0C0000,
+        nonbank_low_base: Some(0x0F8000),
+        nonbank_high_base: Some(0x0FC000),
+    };
+
+    /// HCS12X PPAGE 0xE0-0xFF (CLI: /S12XMAP).
+    pub const STAR12X: Self = Self {
+        bank_min: 0xE0,
+        bank_max: 0xFF,
+        window_start: 0x8000,
+        window_size: 0x4000,
+        linear_base: 0x780000,
+        nonbank_low_base: Some(0x7F4000),
+        nonbank_high_base: Some(0x7FC000),
+    };
+
+    /// HCS08 PPAGE 0x00-0xFF (CLI: /S08MAP).
+    pub const STAR08: Self = Self {
+        bank_min: 0x00,
+        bank_max: 0xFF,
+        window_start: 0x8000,
+        window_size: 0x4000,
+        linear_base: 0x100000,
+        nonbank_low_base: Some(0x104000),
+        nonbank_high_base: None,
+    };
+
+    /// Pages from `ppage_base` up, without the non-banked windows
+    /// (CLI: /S12MAP:ppage_base,window_start,window_size,linear_base).
+    pub fn paged(ppage_base: u8, window_start: u32, window_size: u32, linear_base: u32) -> Self {
+        Self {
+            bank_min: ppage_base,
+            bank_max: 0xFF,
+            window_start,
+            window_size,
+            linear_base,
+            nonbank_low_base: None,
+            nonbank_high_base: None,
+        }
+    }
 }
 
 fn is_valid_alignment(n: u32) -> bool {
@@ -89,7 +144,7 @@ impl HexFile {
                 // Create fill segment for the alignment gap
                 let fill_len = (segment.start_address - aligned_start) as usize;
                 let fill_data = vec![options.fill_byte; fill_len];
-                result.prepend_segment(Segment::new(aligned_start, fill_data));
+                result.prepend_segment(segment.piece(aligned_start, fill_data));
             }
 
             if options.align_length {
@@ -99,7 +154,7 @@ impl HexFile {
                     // Create fill segment for length alignment
                     let fill_len = (aligned_end - end_addr) as usize;
                     let fill_data = vec![options.fill_byte; fill_len];
-                    result.prepend_segment(Segment::new(end_addr, fill_data));
+                    result.prepend_segment(segment.piece(end_addr, fill_data));
                 }
             }
         }
@@ -131,7 +186,7 @@ impl HexFile {
 
             let mut addr = segment.start_address;
             for chunk in segment.data.chunks(max_size_usize) {
-                new_segments.push(Segment::new(addr, chunk.to_vec()));
+                new_segments.push(segment.piece(addr, chunk.to_vec()));
                 addr += chunk.len() as u32;
             }
         }
@@ -139,6 +194,36 @@ impl HexFile {
         self.set_segments(new_segments);
     }
 
+    /// Split segments wherever they cross a multiple of `boundary`, so no block
+    /// spans two boundary-aligned pages (operates on raw segments).
+    pub fn split_at_boundaries(&mut self, boundary: u32) {
+        if boundary == 0 {
+            return;
+        }
+
+        let boundary = boundary as u64;
+        let mut new_segments: Vec<Segment> = Vec::new();
+        for segment in self.segments_mut().drain(..) {
+            let start = segment.start_address as u64;
+            if start + segment.len() as u64 <= (start / boundary + 1) * boundary {
+                new_segments.push(segment);
+                continue;
+            }
+
+            let mut addr = start;
+            let mut rest = segment.data.as_slice();
+            while !rest.is_empty() {
+                let len = ((addr / boundary + 1) * boundary - addr).min(rest.len() as u64);
+                let (chunk, tail) = rest.split_at(len as usize);
+                new_segments.push(segment.piece(addr as u32, chunk.to_vec()));
+                addr += len;
+                rest = tail;
+            }
+        }
+
+        self.set_segments(new_segments);
+    }
+
     /// Swap bytes within all segments (operates on raw segments).
     /// HexView parity:
     /// - SWAPWORD: no-op if any segment starts on an odd address or total byte count is odd.
@@ -162,12 +247,10 @@ impl HexFile {
             }
             SwapMode::DWord => {
                 const HEXVIEW_SWAPLONG_LIMIT: usize = 0x1000000;
-                let Some(span_start) = self.segments().iter().map(|s| s.start_address).min()
-                else {
+                let Some(span_start) = self.segments().iter().map(|s| s.start_address).min() else {
                     return Ok(());
                 };
-                let Some(span_end) = self.segments().iter().map(|s| s.end_address()).max()
-                else {
+                let Some(span_end) = self.segments().iter().map(|s| s.end_address()).max() else {
                     return Ok(());
                 };
                 let span_len = span_end
@@ -182,9 +265,7 @@ impl HexFile {
 
                 if self.segments().len() == 1 {
                     let segment = &mut self.segments_mut()[0];
-                    if segment.start_address == span_start
-                        && segment.len() == span_len as usize
-                    {
+                    if segment.start_address == span_start && segment.len() == span_len as usize {
                         // HexView quirk: large single spans repeat the first swapped word.
                         if segment.len() >= HEXVIEW_SWAPLONG_LIMIT {
                             if segment.data.len() < 4 {
@@ -371,6 +452,61 @@ impl HexFile {
         Ok(())
     }
 
+    /// Insert dsPIC ghost bytes: 3 bytes -> 4 bytes, the fourth set to
+    /// `ghost`. The result starts at the range start and is a third longer;
+    /// errors if that would run into data past the range.
+    pub fn dspic_insert_ghost(&mut self, range: Range, ghost: u8) -> Result<(), OpsError> {
+        let length = range.length() as usize;
+        if !length.is_multiple_of(3) {
+            return Err(OpsError::LengthNotMultiple {
+                length,
+                expected: 3,
+                operation: format!("/CDSPI range {:#X}-{:#X}", range.start(), range.end()),
+            });
+        }
+
+        let src =
+            self.read_bytes_contiguous(range.start(), length)
+                .ok_or(OpsError::RangeNotCovered {
+                    start: range.start(),
+                    length: range.length(),
+                })?;
+
+        let words = range.length() / 3;
+        let grown = range
+            .end()
+            .checked_add(1)
+            .and_then(|start| Range::from_start_length(start, words).ok())
+            .ok_or_else(|| {
+                OpsError::AddressOverflow(format!(
+                    "dspic ghost insert past 0xFFFFFFFF (range={:#X}-{:#X})",
+                    range.start(),
+                    range.end()
+                ))
+            })?;
+        if let Some(segment) = self
+            .segments()
+            .iter()
+            .find(|s| s.start_address <= grown.end() && s.end_address() >= grown.start())
+        {
+            return Err(OpsError::AddressCollision(format!(
+                "/CDSPI output {:#X}-{:#X} runs into data at {:#X}",
+                range.start(),
+                grown.end(),
+                segment.start_address.max(grown.start())
+            )));
+        }
+
+        let mut out = Vec::with_capacity(length / 3 * 4);
+        for chunk in src.chunks_exact(3) {
+            out.extend_from_slice(chunk);
+            out.push(ghost);
+        }
+
+        self.write_bytes(range.start(), &out);
+        Ok(())
+    }
+
     /// Clear dsPIC ghost bytes: set highest byte in each 4-byte group to 0.
     pub fn dspic_clear_ghost(&mut self, range: Range) -> Result<(), OpsError> {
         let length = range.length() as usize;
@@ -527,55 +663,64 @@ impl HexFile {
                 options.bank_min, options.bank_max
             )));
         }
+        if options.window_size == 0
+            || options.window_start as u64 + options.window_size as u64 > 0x10000
+        {
+            return Err(OpsError::InvalidRemapParams(format!(
+                "page window must be non-empty and within 64 KiB (start={:#X}, size={:#X})",
+                options.window_start, options.window_size
+            )));
+        }
 
         for segment in self.segments_mut() {
             let start = segment.start_address;
             let end = segment.end_address();
 
-            if start >= 0x4000 && end <= 0x7FFF {
+            if let Some(base) = options.nonbank_low_base
+                && start >= 0x4000
+                && end <= 0x7FFF
+            {
                 let offset = start - 0x4000;
-                segment.start_address =
-                    options
-                        .nonbank_low_base
-                        .checked_add(offset)
-                        .ok_or_else(|| {
-                            OpsError::AddressOverflow(format!(
-                                "non-banked low map overflow (base={:#X}, start={:#X})",
-                                options.nonbank_low_base, start
-                            ))
-                        })?;
+                segment.start_address = base.checked_add(offset).ok_or_else(|| {
+                    OpsError::AddressOverflow(format!(
+                        "non-banked low map overflow (base={:#X}, start={:#X})",
+                        base, start
+                    ))
+                })?;
                 continue;
             }
-            if start >= 0xC000 && end <= 0xFFFF {
+            if let Some(base) = options.nonbank_high_base
+                && start >= 0xC000
+                && end <= 0xFFFF
+            {
                 let offset = start - 0xC000;
-                segment.start_address =
-                    options
-                        .nonbank_high_base
-                        .checked_add(offset)
-                        .ok_or_else(|| {
-                            OpsError::AddressOverflow(format!(
-                                "non-banked high map overflow (base={:#X}, start={:#X})",
-                                options.nonbank_high_base, start
-                            ))
-                        })?;
+                segment.start_address = base.checked_add(offset).ok_or_else(|| {
+                    OpsError::AddressOverflow(format!(
+                        "non-banked high map overflow (base={:#X}, start={:#X})",
+                        base, start
+                    ))
+                })?;
                 continue;
             }
 
+            if start > 0xFF_FFFF {
+                continue;
+            }
             let bank = (start >> 16) as u8;
             if bank < options.bank_min || bank > options.bank_max {
                 continue;
             }
 
-            let bank_base = ((bank as u32) << 16) + 0x8000;
-            let bank_end = bank_base + 0x3FFF;
-            if end > bank_end {
+            let bank_base = ((bank as u32) << 16) + options.window_start;
+            let bank_end = bank_base + (options.window_size - 1);
+            if start < bank_base || end > bank_end {
                 continue;
             }
 
             let bank_index = (bank - options.bank_min) as u32;
             let linear_bank_base = options
                 .linear_base
-                .checked_add(bank_index.checked_mul(0x4000).ok_or_else(|| {
+                .checked_add(bank_index.checked_mul(options.window_size).ok_or_else(|| {
                     OpsError::AddressOverflow(format!(
                         "bank base overflow (linear={:#X}, bank_index={})",
                         options.linear_base, bank_index
@@ -602,67 +747,15 @@ impl HexFile {
     }
 
     pub fn map_star12(&mut self) -> Result<(), OpsError> {
-        self.map_banked(&BankedMapOptions {
-            bank_min: 0x30,
-            bank_max: 0x3F,
-            linear_base: 0x0C0000,
-            nonbank_low_base: 0x0F8000,
-            nonbank_high_base: 0x0FC000,
-        })
+        self.map_banked(&BankedMapOptions::STAR12)
     }
 
     pub fn map_star12x(&mut self) -> Result<(), OpsError> {
-        self.map_banked(&BankedMapOptions {
-            bank_min: 0xE0,
-            bank_max: 0xFF,
-            linear_base: 0x780000,
-            nonbank_low_base: 0x7F4000,
-            nonbank_high_base: 0x7FC000,
-        })
+        self.map_banked(&BankedMapOptions::STAR12X)
     }
 
     pub fn map_star08(&mut self) -> Result<(), OpsError> {
-        for segment in self.segments_mut() {
-            let start = segment.start_address;
-            let end = segment.end_address();
-
-            if start >= 0x4000 && end <= 0x7FFF {
-                let offset = start - 0x4000;
-                segment.start_address = 0x104000u32.checked_add(offset).ok_or_else(|| {
-                    OpsError::AddressOverflow(format!(
-                        "star08 low map overflow (start={:#X})",
-                        start
-                    ))
-                })?;
-                continue;
-            }
-
-            let bank = (start >> 16) as u8;
-            let bank_base = ((bank as u32) << 16) + 0x8000;
-            let bank_end = bank_base + 0x3FFF;
-            if start < bank_base || end > bank_end {
-                continue;
-            }
-
-            let linear_bank_base = 0x100000u32
-                .checked_add((bank as u32).checked_mul(0x4000).ok_or_else(|| {
-                    OpsError::AddressOverflow(format!("star08 bank base overflow (bank={})", bank))
-                })?)
-                .ok_or_else(|| {
-                    OpsError::AddressOverflow(format!("star08 bank base overflow (bank={})", bank))
-                })?;
-            segment.start_address =
-                linear_bank_base
-                    .checked_add(start - bank_base)
-                    .ok_or_else(|| {
-                        OpsError::AddressOverflow(format!(
-                            "star08 bank map overflow (start={:#X})",
-                            start
-                        ))
-                    })?;
-        }
-
-        Ok(())
+        self.map_banked(&BankedMapOptions::STAR08)
     }
 }
 
@@ -753,6 +846,41 @@ mod tests {
         assert_eq!(hf.segments()[2].len(), 2);
     }
 
+    #[test]
+    fn test_split_at_boundaries() {
+        // Straddles 0x2000 and 0x3000; the second segment fits in one page.
+        let mut hf = HexFile::with_segments(vec![
+            Segment::new(0x1F80, vec![0xAA; 0x1100]),
+            Segment::new(0x4010, vec![0xBB; 0x10]),
+        ]);
+        hf.split_at_boundaries(0x1000);
+        let blocks: Vec<(u32, usize)> = hf
+            .segments()
+            .iter()
+            .map(|s| (s.start_address, s.len()))
+            .collect();
+        assert_eq!(
+            blocks,
+            vec![
+                (0x1F80, 0x80),
+                (0x2000, 0x1000),
+                (0x3000, 0x80),
+                (0x4010, 0x10)
+            ]
+        );
+
+        // Combined with a size cap, each page is then cut from its own start.
+        hf.split(0x800);
+        assert_eq!(hf.segments().len(), 5);
+        assert_eq!(hf.segments()[2].start_address, 0x2800);
+
+        let mut top = HexFile::with_segments(vec![Segment::new(0xFFFF_FFF0, vec![0xCC; 0x10])]);
+        top.split_at_boundaries(0x8);
+        assert_eq!(top.segments()[1].start_address, 0xFFFF_FFF8);
+        top.split_at_boundaries(0);
+        assert_eq!(top.segments().len(), 2);
+    }
+
     #[test]
     fn test_swap_word() {
         let mut hf =
@@ -928,7 +1056,8 @@ mod tests {
 
     #[test]
     fn test_swap_dword_span_misaligned_noop() {
-        let mut hf = HexFile::with_segments(vec![Segment::new(0x1002, vec![0x01, 0x02, 0x03, 0x04])]);
+        let mut hf =
+            HexFile::with_segments(vec![Segment::new(0x1002, vec![0x01, 0x02, 0x03, 0x04])]);
         hf.swap_bytes(SwapMode::DWord).unwrap();
         assert_eq!(hf.segments()[0].data, vec![0x01, 0x02, 0x03, 0x04]);
     }
@@ -1125,6 +1254,34 @@ mod tests {
         assert_eq!(segments[1].start_address, 0x108000);
     }
 
+    #[test]
+    fn test_map_banked_paged_custom_window() {
+        let mut hf = HexFile::with_segments(vec![
+            Segment::new(0x4000, vec![0xAA]),
+            Segment::new(0x1F8000, vec![0x00]),
+            Segment::new(0x209000, vec![0x01]),
+            Segment::new(0x219FFF, vec![0x02]),
+            Segment::new(0x21A000, vec![0x03]),
+        ]);
+
+        hf.map_banked(&BankedMapOptions::paged(0x20, 0x9000, 0x1000, 0x400000))
+            .unwrap();
+        let mut segments = hf.segments().to_vec();
+        segments.sort_by_key(|s| s.start_address);
+        assert_eq!(segments[0].start_address, 0x4000);
+        assert_eq!(segments[1].start_address, 0x1F8000);
+        assert_eq!(segments[2].start_address, 0x21A000);
+        assert_eq!(segments[3].start_address, 0x400000);
+        assert_eq!(segments[4].start_address, 0x401FFF);
+    }
+
+    #[test]
+    fn test_map_banked_rejects_window_past_64k() {
+        let mut hf = HexFile::with_segments(vec![Segment::new(0x308000, vec![0x01])]);
+        let result = hf.map_banked(&BankedMapOptions::paged(0x30, 0xC000, 0x8000, 0));
+        assert!(matches!(result, Err(OpsError::InvalidRemapParams(_))));
+    }
+
     #[test]
     fn test_dspic_expand_appends_zeros() {
         let mut hf =
@@ -1161,4 +1318,49 @@ mod tests {
         let out = hf.read_bytes_contiguous(0x3000, 8).unwrap();
         assert_eq!(out, vec![0x01, 0x02, 0x03, 0x00, 0x10, 0x11, 0x12, 0x00]);
     }
+
+    #[test]
+    fn test_dspic_insert_ghost_sets_fourth_byte() {
+        let mut hf = HexFile::with_segments(vec![Segment::new(
+            0x3000,
+            vec![0x01, 0x02, 0x03, 0x10, 0x11, 0x12],
+        )]);
+        hf.dspic_insert_ghost(Range::from_start_length(0x3000, 6).unwrap(), 0xFF)
+            .unwrap();
+
+        let out = hf.read_bytes_contiguous(0x3000, 8).unwrap();
+        assert_eq!(out, vec![0x01, 0x02, 0x03, 0xFF, 0x10, 0x11, 0x12, 0xFF]);
+        assert_eq!(hf.max_address(), Some(0x3007));
+    }
+
+    #[test]
+    fn test_dspic_insert_ghost_rejects_growing_into_data() {
+        let mut hf = HexFile::with_segments(vec![
+            Segment::new(0x3000, vec![0x01, 0x02, 0x03]),
+            Segment::new(0x3003, vec![0xAA]),
+        ]);
+        let result = hf.dspic_insert_ghost(Range::from_start_length(0x3000, 3).unwrap(), 0);
+        assert!(matches!(result, Err(OpsError::AddressCollision(_))));
+        assert_eq!(hf.read_bytes_contiguous(0x3003, 1).unwrap(), vec![0xAA]);
+    }
+
+    #[test]
+    fn test_dspic_ops_reject_trailing_partial_word() {
+        let data = vec![0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07];
+        let mut hf = HexFile::with_segments(vec![Segment::new(0x1000, data.clone())]);
+        let range = Range::from_start_length(0x1000, 7).unwrap();
+
+        for result in [
+            hf.dspic_expand(range, Some(0x8000)),
+            hf.dspic_shrink(range, Some(0x8000)),
+            hf.dspic_clear_ghost(range),
+            hf.dspic_insert_ghost(range, 0),
+        ] {
+            assert!(
+                matches!(result, Err(OpsError::LengthNotMultiple { length: 7, .. })),
+                "{result:?}"
+            );
+        }
+        assert_eq!(hf.segments(), [Segment::new(0x1000, data)]);
+    }
 }
@@ -1,7 +1,10 @@
+use std::fmt;
 use std::str::FromStr;
 
 use thiserror::Error;
 
+use crate::HexFile;
+
 #[derive(Debug, Error)]
 pub enum RangeError {
     #[error("invalid range format: {0}")]
@@ -15,15 +18,37 @@ pub enum RangeError {
 
     #[error("zero length range at {start:#X}")]
     ZeroLength { start: u32 },
+
+    #[error("range {0} holds no data")]
+    NoData(String),
 }
 
 /// A memory address range, specified either as start+length or start-end (inclusive).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RangeBounds"))]
 pub struct Range {
     start: u32,
     end: u32, // inclusive
 }
 
+/// Unchecked form of `Range` so deserialization goes through `from_start_end`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RangeBounds {
+    start: u32,
+    end: u32,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RangeBounds> for Range {
+    type Error = RangeError;
+
+    fn try_from(bounds: RangeBounds) -> Result<Self, Self::Error> {
+        Self::from_start_end(bounds.start, bounds.end)
+    }
+}
+
 impl Range {
     /// Create range from start address and length.
     pub fn from_start_length(start: u32, length: u32) -> Result<Self, RangeError> {
@@ -82,8 +107,197 @@ impl Range {
     }
 }
 
+/// Sorted, non-overlapping ranges. Touching ranges are coalesced, except that
+/// the whole 4 GiB space (which no [`Range`] can hold) stays split in two.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RangeSet {
+    ranges: Vec<Range>,
+}
+
+impl RangeSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_ranges(ranges: &[Range]) -> Self {
+        let mut sorted = ranges.to_vec();
+        sorted.sort_by_key(|r| r.start);
+        let mut set = Self::new();
+        // Sorted input only ever merges at the end of the set.
+        for range in sorted {
+            set.insert(range);
+        }
+        set
+    }
+
+    /// Add `range`, merging it with the ranges it overlaps or touches.
+    pub fn insert(&mut self, range: Range) {
+        let first = self
+            .ranges
+            .partition_point(|r| (r.end as u64) + 1 < range.start as u64);
+        let last = self
+            .ranges
+            .partition_point(|r| r.start as u64 <= range.end as u64 + 1);
+        let start = self
+            .ranges
+            .get(first)
+            .map_or(range.start, |r| r.start.min(range.start));
+        let end = match last.checked_sub(1) {
+            Some(i) if i >= first => self.ranges[i].end.max(range.end),
+            _ => range.end,
+        };
+        let rest = self.ranges.split_off(last);
+        self.ranges.truncate(first);
+        self.push_span(start, end);
+        self.ranges.extend(rest);
+    }
+
+    /// Remove every address in `range`, splitting ranges that straddle it.
+    pub fn remove(&mut self, range: Range) {
+        let first = self.ranges.partition_point(|r| r.end < range.start);
+        let last = self.ranges.partition_point(|r| r.start <= range.end);
+        if first >= last {
+            return;
+        }
+        let mut kept = Vec::with_capacity(2);
+        let head = self.ranges[first];
+        if head.start < range.start {
+            kept.push(Range {
+                start: head.start,
+                end: range.start - 1,
+            });
+        }
+        let tail = self.ranges[last - 1];
+        if tail.end > range.end {
+            kept.push(Range {
+                start: range.end + 1,
+                end: tail.end,
+            });
+        }
+        self.ranges.splice(first..last, kept);
+    }
+
+    pub fn union(&self, other: &RangeSet) -> RangeSet {
+        let mut out = self.clone();
+        for range in other {
+            out.insert(*range);
+        }
+        out
+    }
+
+    pub fn subtract(&self, other: &RangeSet) -> RangeSet {
+        let mut out = self.clone();
+        for range in other {
+            out.remove(*range);
+        }
+        out
+    }
+
+    pub fn intersect(&self, other: &RangeSet) -> RangeSet {
+        let mut out = RangeSet::new();
+        for range in other {
+            out.ranges.extend(self.clip(*range));
+        }
+        // Halves of a split 4 GiB range may now be parts of one range.
+        RangeSet::from_ranges(&out.ranges)
+    }
+
+    /// The parts of `range` covered by the set, in address order.
+    pub fn clip(&self, range: Range) -> impl Iterator<Item = Range> + '_ {
+        let first = self.ranges.partition_point(|r| r.end < range.start);
+        self.ranges[first..]
+            .iter()
+            .take_while(move |r| r.start <= range.end)
+            .filter_map(move |r| r.intersection(&range))
+    }
+
+    /// The parts of `range` not covered by the set, in address order.
+    pub fn gaps_within(&self, range: Range) -> Vec<Range> {
+        let mut gaps = Vec::new();
+        let mut cursor = Some(range.start);
+        for covered in self.clip(range) {
+            if let Some(start) = cursor
+                && start < covered.start
+            {
+                gaps.push(Range {
+                    start,
+                    end: covered.start - 1,
+                });
+            }
+            cursor = covered.end.checked_add(1);
+        }
+        if let Some(start) = cursor
+            && start <= range.end
+        {
+            gaps.push(Range {
+                start,
+                end: range.end,
+            });
+        }
+        gaps
+    }
+
+    pub fn contains(&self, addr: u32) -> bool {
+        let index = self.ranges.partition_point(|r| r.end < addr);
+        self.ranges.get(index).is_some_and(|r| r.contains(addr))
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Range> {
+        self.ranges.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn to_vec(&self) -> Vec<Range> {
+        self.ranges.clone()
+    }
+
+    /// Append `start..=end`, which must follow every range in the set.
+    fn push_span(&mut self, start: u32, end: u32) {
+        if start == 0 && end == u32::MAX {
+            self.ranges.push(Range {
+                start: 0,
+                end: u32::MAX - 1,
+            });
+            self.ranges.push(Range {
+                start: u32::MAX,
+                end: u32::MAX,
+            });
+        } else {
+            self.ranges.push(Range { start, end });
+        }
+    }
+}
+
+impl From<RangeSet> for Vec<Range> {
+    fn from(set: RangeSet) -> Self {
+        set.ranges
+    }
+}
+
+impl FromIterator<Range> for RangeSet {
+    fn from_iter<I: IntoIterator<Item = Range>>(iter: I) -> Self {
+        Self::from_ranges(&iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<'a> IntoIterator for &'a RangeSet {
+    type Item = &'a Range;
+    type IntoIter = std::slice::Iter<'a, Range>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ranges.iter()
+    }
+}
+
 /// Parse a number from decimal, hex (0x), or binary (0b or trailing b).
-fn parse_number(s: &str) -> Result<u32, RangeError> {
+pub(crate) fn parse_number(s: &str) -> Result<u32, RangeError> {
     let s = s.trim();
     if s.is_empty() {
         return Err(RangeError::InvalidNumber("empty string".to_string()));
@@ -112,17 +326,38 @@ fn parse_number(s: &str) -> Result<u32, RangeError> {
     u32::from_str_radix(&cleaned, radix).map_err(|e| RangeError::InvalidNumber(e.to_string()))
 }
 
+/// A range length: a number, or a decimal count with a K (KiB) or M (MiB) suffix.
+fn parse_length(s: &str) -> Result<u32, RangeError> {
+    let s = s.trim();
+    let unit: u32 = match s.as_bytes().last() {
+        Some(b'k' | b'K') => 1024,
+        Some(b'm' | b'M') => 1024 * 1024,
+        _ => return parse_number(s),
+    };
+    let count = &s[..s.len() - 1];
+    if count.is_empty() || !count.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(RangeError::InvalidNumber(format!(
+            "{s}: K and M need a decimal count"
+        )));
+    }
+    count
+        .parse::<u32>()
+        .ok()
+        .and_then(|count| count.checked_mul(unit))
+        .ok_or_else(|| RangeError::InvalidNumber(format!("{s} exceeds u32")))
+}
+
 impl FromStr for Range {
     type Err = RangeError;
 
     /// Parse range from string.
     /// Formats:
-    /// - "start,length" (e.g., "0x1000,0x200")
+    /// - "start,length" (e.g., "0x1000,0x200" or "0x8000,32K")
     /// - "start-end" (e.g., "0x1000-0x11FF")
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if let Some((start_str, len_str)) = s.split_once(',') {
             let start = parse_number(start_str)?;
-            let length = parse_number(len_str)?;
+            let length = parse_length(len_str)?;
             Range::from_start_length(start, length)
         } else if let Some((start_str, end_str)) = s.split_once('-') {
             let start = parse_number(start_str)?;
@@ -136,6 +371,13 @@ impl FromStr for Range {
     }
 }
 
+/// `0x1000-0x11FF`, which parses back to the same range.
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:X}-0x{:X}", self.start, self.end)
+    }
+}
+
 /// Parse multiple ranges separated by ':'.
 pub fn parse_ranges(s: &str) -> Result<Vec<Range>, RangeError> {
     s.split(':').map(|part| part.parse()).collect()
@@ -147,6 +389,112 @@ pub fn parse_hexview_ranges(s: &str) -> Result<Vec<Range>, RangeError> {
     parse_ranges(trimmed)
 }
 
+/// A range whose ends may be left open and taken from the data when it is
+/// used: `START-0x3FFF`, `0x8000-END`, or `ALL` for both. Closed specs behave
+/// like the [`Range`] they hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeSpec {
+    start: Option<u32>,
+    end: Option<u32>,
+}
+
+impl RangeSpec {
+    /// From the lowest to the highest data address.
+    pub const ALL: Self = Self {
+        start: None,
+        end: None,
+    };
+
+    /// `None` for a bound taken from the data.
+    pub fn new(start: Option<u32>, end: Option<u32>) -> Self {
+        Self { start, end }
+    }
+
+    /// The range when neither end is open.
+    pub fn fixed(&self) -> Option<Range> {
+        Range::from_start_end(self.start?, self.end?).ok()
+    }
+
+    /// The range against the current data of `hexfile`. `None` when an open
+    /// end meets an empty file or the resolved range is empty.
+    pub fn resolve(&self, hexfile: &HexFile) -> Option<Range> {
+        let start = match self.start {
+            Some(start) => start,
+            None => hexfile.min_address()?,
+        };
+        let end = match self.end {
+            Some(end) => end,
+            None => hexfile.max_address()?,
+        };
+        Range::from_start_end(start, end).ok()
+    }
+}
+
+impl From<Range> for RangeSpec {
+    fn from(range: Range) -> Self {
+        Self {
+            start: Some(range.start),
+            end: Some(range.end),
+        }
+    }
+}
+
+/// Resolve each spec against `hexfile`, dropping those that come out empty.
+pub fn resolve_ranges(specs: &[RangeSpec], hexfile: &HexFile) -> Vec<Range> {
+    specs
+        .iter()
+        .filter_map(|spec| spec.resolve(hexfile))
+        .collect()
+}
+
+impl FromStr for RangeSpec {
+    type Err = RangeError;
+
+    /// Parse a [`Range`], `ALL`, or a `start-end` range with `START` or `END`
+    /// in place of an address (case-insensitive).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        if trimmed.eq_ignore_ascii_case("ALL") {
+            return Ok(Self::ALL);
+        }
+        let open = |part: &str, keyword: &str| part.trim().eq_ignore_ascii_case(keyword);
+        match trimmed.split_once('-') {
+            Some((start, end)) if open(start, "START") || open(end, "END") => {
+                let start = (!open(start, "START"))
+                    .then(|| parse_number(start))
+                    .transpose()?;
+                let end = (!open(end, "END")).then(|| parse_number(end)).transpose()?;
+                Ok(Self { start, end })
+            }
+            _ => trimmed.parse::<Range>().map(Self::from),
+        }
+    }
+}
+
+impl fmt::Display for RangeSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.start, self.end) {
+            (None, None) => write!(f, "ALL"),
+            (start, end) => {
+                match start {
+                    Some(start) => write!(f, "0x{start:X}-")?,
+                    None => write!(f, "START-")?,
+                }
+                match end {
+                    Some(end) => write!(f, "0x{end:X}"),
+                    None => write!(f, "END"),
+                }
+            }
+        }
+    }
+}
+
+/// Like [`parse_hexview_ranges`], also accepting `START`, `END` and `ALL`.
+pub fn parse_hexview_range_specs(s: &str) -> Result<Vec<RangeSpec>, RangeError> {
+    let trimmed = s.trim_matches(|c| c == '"' || c == '\'');
+    trimmed.split(':').map(|part| part.parse()).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -188,6 +536,16 @@ mod tests {
         assert_eq!(r.end(), 0x10FF);
     }
 
+    #[test]
+    fn test_parse_length_with_size_suffix() {
+        let r: Range = "0x8000,32K".parse().unwrap();
+        assert_eq!(r.end(), 0xFFFF);
+        let r: Range = "0,2m".parse().unwrap();
+        assert_eq!(r.length(), 0x20_0000);
+        assert!("0x8000,0x10K".parse::<Range>().is_err());
+        assert!("0x8000-32K".parse::<Range>().is_err());
+    }
+
     #[test]
     fn test_contains() {
         let r = Range::from_start_end(0x1000, 0x1FFF).unwrap();
@@ -373,4 +731,92 @@ mod tests {
         let r3 = Range::from_start_end(0x1001, 0x1001).unwrap();
         assert!(!r1.overlaps(&r3));
     }
+
+    #[test]
+    fn test_range_spec_resolves_against_data() {
+        let hexfile = HexFile::with_segments(vec![
+            crate::Segment::new(0x1000, vec![0; 0x10]),
+            crate::Segment::new(0x8000, vec![0; 0x100]),
+        ]);
+        let specs = parse_hexview_range_specs("'0x4000-END:START-0x1007:all:0x10,4'").unwrap();
+        assert_eq!(
+            resolve_ranges(&specs, &hexfile),
+            vec![
+                Range::from_start_end(0x4000, 0x80FF).unwrap(),
+                Range::from_start_end(0x1000, 0x1007).unwrap(),
+                Range::from_start_end(0x1000, 0x80FF).unwrap(),
+                Range::from_start_end(0x10, 0x13).unwrap(),
+            ]
+        );
+        assert_eq!(specs[0].to_string(), "0x4000-END");
+        assert_eq!(specs[2].to_string(), "ALL");
+        assert_eq!(specs[3].fixed(), Range::from_start_length(0x10, 4).ok());
+
+        let beyond: RangeSpec = "0x9000-end".parse().unwrap();
+        assert_eq!(beyond.resolve(&hexfile), None);
+        assert_eq!(RangeSpec::ALL.resolve(&HexFile::new()), None);
+        assert!("END-0x10".parse::<RangeSpec>().is_err());
+        assert!("0x10-START".parse::<RangeSpec>().is_err());
+    }
+
+    fn set(ranges: &[(u32, u32)]) -> RangeSet {
+        let ranges: Vec<Range> = ranges
+            .iter()
+            .map(|&(start, end)| Range::from_start_end(start, end).unwrap())
+            .collect();
+        RangeSet::from_ranges(&ranges)
+    }
+
+    fn bounds(set: &RangeSet) -> Vec<(u32, u32)> {
+        set.iter().map(|r| (r.start(), r.end())).collect()
+    }
+
+    #[test]
+    fn test_range_set_merges_overlapping_and_touching() {
+        let ranges = set(&[(0x30, 0x3F), (0x10, 0x1F), (0x18, 0x27), (0x28, 0x2B)]);
+        assert_eq!(bounds(&ranges), vec![(0x10, 0x2B), (0x30, 0x3F)]);
+
+        let mut ranges = ranges;
+        ranges.insert(Range::from_start_end(0x2C, 0x2F).unwrap());
+        assert_eq!(bounds(&ranges), vec![(0x10, 0x3F)]);
+        assert!(ranges.contains(0x3F) && !ranges.contains(0x40));
+
+        ranges.remove(Range::from_start_end(0x20, 0x2F).unwrap());
+        assert_eq!(bounds(&ranges), vec![(0x10, 0x1F), (0x30, 0x3F)]);
+        ranges.remove(Range::from_start_end(0x0, 0x10).unwrap());
+        assert_eq!(bounds(&ranges), vec![(0x11, 0x1F), (0x30, 0x3F)]);
+        assert_eq!(Vec::from(ranges).len(), 2);
+    }
+
+    #[test]
+    fn test_range_set_operations() {
+        let a = set(&[(0x00, 0x0F), (0x20, 0x2F)]);
+        let b = set(&[(0x08, 0x27)]);
+        assert_eq!(bounds(&a.union(&b)), vec![(0x00, 0x2F)]);
+        assert_eq!(bounds(&a.subtract(&b)), vec![(0x00, 0x07), (0x28, 0x2F)]);
+        assert_eq!(bounds(&a.intersect(&b)), vec![(0x08, 0x0F), (0x20, 0x27)]);
+        assert_eq!(
+            a.gaps_within(Range::from_start_end(0x08, 0x38).unwrap()),
+            vec![
+                Range::from_start_end(0x10, 0x1F).unwrap(),
+                Range::from_start_end(0x30, 0x38).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_range_set_whole_address_space() {
+        let all = set(&[(0x0, 0x7FFF_FFFF), (0x8000_0000, u32::MAX), (0x10, 0x20)]);
+        assert_eq!(
+            bounds(&all),
+            vec![(0x0, u32::MAX - 1), (u32::MAX, u32::MAX)]
+        );
+        assert!(all.contains(u32::MAX));
+        let top = Range::from_start_end(0xFFFF_FF00, u32::MAX).unwrap();
+        assert!(all.gaps_within(top).is_empty());
+        assert_eq!(bounds(&all.intersect(&all)), bounds(&all));
+        let mut all = all;
+        all.remove(Range::from_start_end(0x10, u32::MAX).unwrap());
+        assert_eq!(bounds(&all), vec![(0x0, 0xF)]);
+    }
 }
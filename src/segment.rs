@@ -1,11 +1,40 @@
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use memmap2::Mmap;
+
+/// Build with [`Segment::new`] or [`Segment::with_data`]; the struct is
+/// `#[non_exhaustive]`, so later fields are not breaking changes.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub struct Segment {
     pub start_address: u32,
-    pub data: Vec<u8>,
+    /// The segment bytes.
+    ///
+    /// Deprecated as a public field: use [`Segment::data`] and
+    /// [`Segment::data_mut`]. It stays public for this release and becomes
+    /// private in the next one.
+    #[cfg_attr(feature = "serde", serde(with = "crate::io::hex_bytes"))]
+    pub data: SegmentData,
+    /// Block label such as "application" or "calibration", set by
+    /// [`crate::HexFile::label_range`]. Pieces split or cut from a segment
+    /// keep it, and neighbours only merge when their labels match.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub name: Option<String>,
 }
 
 impl Segment {
     pub fn new(start_address: u32, data: Vec<u8>) -> Self {
+        Self::with_data(start_address, data.into())
+    }
+
+    /// A segment over existing [`SegmentData`], e.g. a mapped file view.
+    pub fn with_data(start_address: u32, data: SegmentData) -> Self {
         debug_assert!(
             data.len() <= u32::MAX as usize,
             "segment data exceeds u32::MAX bytes"
@@ -13,9 +42,29 @@ impl Segment {
         Self {
             start_address,
             data,
+            name: None,
+        }
+    }
+
+    /// `data` at `start_address`, labelled like this segment. For pieces
+    /// split or copied out of it.
+    pub fn piece(&self, start_address: u32, data: impl Into<SegmentData>) -> Self {
+        Self {
+            name: self.name.clone(),
+            ..Self::with_data(start_address, data.into())
         }
     }
 
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The bytes as a Vec, copying a shared buffer or mapped view first
+    /// (see [`SegmentData::to_mut`]).
+    pub fn data_mut(&mut self) -> &mut Vec<u8> {
+        self.data.to_mut()
+    }
+
     pub fn end_address(&self) -> u32 {
         if self.data.is_empty() {
             self.start_address
@@ -39,9 +88,233 @@ impl Segment {
         self.end_address().checked_add(1) == Some(other.start_address)
     }
 
+    /// Contiguous with `other` and carrying the same label, so normalizing
+    /// merges the two.
+    pub fn joins(&self, other: &Segment) -> bool {
+        self.is_contiguous_with(other) && self.name == other.name
+    }
+
     pub fn merge(&mut self, other: Segment) {
         debug_assert!(self.is_contiguous_with(&other));
-        self.data.extend(other.data);
+        self.data.extend_from_slice(&other.data);
+    }
+}
+
+/// Bytes of a [`Segment`]: a heap buffer, or a read-only view into a
+/// memory-mapped file (see [`crate::parse_binary_mmap`]).
+///
+/// Derefs to `[u8]`. Cloning shares the buffer instead of copying it, so
+/// cloned files and read-only stages cost no image-sized allocation. Mutable
+/// access is copy-on-write: a buffer still shared with a clone, or a mapped
+/// view, is copied into one of its own first, so only segments that are
+/// actually modified are copied.
+#[derive(Clone)]
+pub struct SegmentData(Storage);
+
+#[derive(Clone)]
+enum Storage {
+    Owned(Arc<Vec<u8>>),
+    Mapped {
+        map: Arc<Mmap>,
+        offset: usize,
+        len: usize,
+    },
+}
+
+impl SegmentData {
+    /// View `offset..offset + len` of `map` without copying it.
+    pub(crate) fn mapped(map: Arc<Mmap>, offset: usize, len: usize) -> Self {
+        debug_assert!(offset + len <= map.len());
+        Self(Storage::Mapped { map, offset, len })
+    }
+
+    /// Whether the bytes still live in a file mapping.
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.0, Storage::Mapped { .. })
+    }
+
+    /// Whether another `SegmentData` shares this buffer (always true for a
+    /// mapped view), so the next mutable access copies it.
+    pub fn is_shared(&self) -> bool {
+        match &self.0 {
+            Storage::Owned(data) => Arc::strong_count(data) > 1,
+            Storage::Mapped { .. } => true,
+        }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        match &self.0 {
+            Storage::Owned(data) => data,
+            Storage::Mapped { map, offset, len } => &map[*offset..*offset + *len],
+        }
+    }
+
+    /// The buffer, copied first if it is shared or a mapped view.
+    pub fn to_mut(&mut self) -> &mut Vec<u8> {
+        if let Storage::Mapped { .. } = self.0 {
+            self.0 = Storage::Owned(Arc::new(self.as_slice().to_vec()));
+        }
+        match &mut self.0 {
+            Storage::Owned(data) => Arc::make_mut(data),
+            Storage::Mapped { .. } => unreachable!("converted above"),
+        }
+    }
+
+    /// The bytes as a Vec, copied only if the buffer is shared or mapped.
+    pub fn into_vec(self) -> Vec<u8> {
+        match self.0 {
+            Storage::Owned(data) => Arc::try_unwrap(data).unwrap_or_else(|data| (*data).clone()),
+            Storage::Mapped { .. } => self.as_slice().to_vec(),
+        }
+    }
+
+    pub fn extend_from_slice(&mut self, other: &[u8]) {
+        if !other.is_empty() {
+            self.to_mut().extend_from_slice(other);
+        }
+    }
+
+    pub fn resize(&mut self, new_len: usize, value: u8) {
+        if new_len != self.len() {
+            self.to_mut().resize(new_len, value);
+        }
+    }
+
+    /// Shorten to `len` bytes. A mapped view stays mapped; a shared buffer
+    /// copies only the bytes kept.
+    pub fn truncate(&mut self, new_len: usize) {
+        match &mut self.0 {
+            Storage::Owned(data) if new_len < data.len() => match Arc::get_mut(data) {
+                Some(owned) => owned.truncate(new_len),
+                None => *data = Arc::new(data[..new_len].to_vec()),
+            },
+            Storage::Owned(_) => {}
+            Storage::Mapped { len, .. } => *len = new_len.min(*len),
+        }
+    }
+
+    /// Split off the bytes from `at` onwards. A mapped view stays mapped; a
+    /// shared buffer is copied in two halves.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        match &mut self.0 {
+            Storage::Owned(data) => match Arc::get_mut(data) {
+                Some(owned) => Self::from(owned.split_off(at)),
+                None => {
+                    let tail = Self::from(&data[at..]);
+                    *data = Arc::new(data[..at].to_vec());
+                    tail
+                }
+            },
+            Storage::Mapped { map, offset, len } => {
+                assert!(at <= *len, "split_off index out of bounds");
+                let tail = Self::mapped(Arc::clone(map), *offset + at, *len - at);
+                *len = at;
+                tail
+            }
+        }
+    }
+}
+
+impl Default for SegmentData {
+    fn default() -> Self {
+        Self::from(Vec::new())
+    }
+}
+
+impl Deref for SegmentData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl DerefMut for SegmentData {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.to_mut()
+    }
+}
+
+impl AsRef<[u8]> for SegmentData {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<'a> IntoIterator for &'a SegmentData {
+    type Item = &'a u8;
+    type IntoIter = std::slice::Iter<'a, u8>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
+impl fmt::Debug for SegmentData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_slice(), f)
+    }
+}
+
+impl From<Vec<u8>> for SegmentData {
+    fn from(data: Vec<u8>) -> Self {
+        Self(Storage::Owned(Arc::new(data)))
+    }
+}
+
+impl From<&[u8]> for SegmentData {
+    fn from(data: &[u8]) -> Self {
+        Self::from(data.to_vec())
+    }
+}
+
+impl From<SegmentData> for Vec<u8> {
+    fn from(data: SegmentData) -> Self {
+        data.into_vec()
+    }
+}
+
+impl PartialEq for SegmentData {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for SegmentData {}
+
+impl PartialEq<[u8]> for SegmentData {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl PartialEq<&[u8]> for SegmentData {
+    fn eq(&self, other: &&[u8]) -> bool {
+        self.as_slice() == *other
+    }
+}
+
+impl<const N: usize> PartialEq<[u8; N]> for SegmentData {
+    fn eq(&self, other: &[u8; N]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<const N: usize> PartialEq<&[u8; N]> for SegmentData {
+    fn eq(&self, other: &&[u8; N]) -> bool {
+        self.as_slice() == *other
+    }
+}
+
+impl PartialEq<Vec<u8>> for SegmentData {
+    fn eq(&self, other: &Vec<u8>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl PartialEq<SegmentData> for Vec<u8> {
+    fn eq(&self, other: &SegmentData) -> bool {
+        self.as_slice() == other.as_slice()
     }
 }
 
@@ -49,6 +322,34 @@ impl Segment {
 mod tests {
     use super::Segment;
 
+    #[test]
+    fn test_data_accessors() {
+        let mut seg = Segment::new(0x1000, vec![1, 2]);
+        seg.data_mut().push(3);
+        assert_eq!(seg.data(), [1, 2, 3]);
+        assert_eq!(seg.end_address(), 0x1002);
+    }
+
+    #[test]
+    fn test_clone_shares_data_until_written() {
+        let original = Segment::new(0x1000, vec![1, 2, 3, 4]);
+        let mut copy = original.clone();
+        assert!(original.data.is_shared());
+        assert_eq!(copy.data.as_ptr(), original.data.as_ptr());
+
+        copy.data[0] = 0xFF;
+        assert!(!copy.data.is_shared());
+        assert!(!original.data.is_shared());
+        assert_eq!(original.data, [1, 2, 3, 4]);
+        assert_eq!(copy.data, [0xFF, 2, 3, 4]);
+
+        let mut shared = original.clone();
+        let tail = shared.data.split_off(2);
+        assert_eq!(shared.data, [1, 2]);
+        assert_eq!(tail, [3, 4]);
+        assert_eq!(original.data, [1, 2, 3, 4]);
+    }
+
     #[test]
     fn test_end_address_saturates_on_overflow() {
         let seg = Segment::new(u32::MAX, vec![0xAA, 0xBB]);
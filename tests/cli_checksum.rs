@@ -34,6 +34,141 @@ fn test_cli_checksum_append() {
     );
 }
 
+#[test]
+fn test_cli_checksum_at_map_symbol() {
+    let dir = temp_dir("cli_checksum_sym");
+    let input = dir.join("input.bin");
+    let map = dir.join("app.map");
+    let out = dir.join("out.hex");
+    write_file(&input, &[0x01, 0x02, 0x03, 0x04, 0xFF, 0xFF]);
+    write_file(
+        &map,
+        b" .rodata.__app_crc\n                0x0000000000001004        0x2 crc.o\n                0x0000000000001004                __app_crc\n",
+    );
+
+    let run = |symbol: &str| {
+        run_h3xy(&[
+            format!("/IN:{};0x1000", input.display()),
+            format!("/MAPFILE:{}", map.display()),
+            format!("/CS0:@SYM:{symbol};0x1000-0x1003"),
+            "/XI".to_string(),
+            "-o".to_string(),
+            out.display().to_string(),
+        ])
+    };
+
+    assert_success(&run("__app_crc"));
+    let hexfile = parse_intel_hex(&std::fs::read(&out).unwrap()).unwrap();
+    assert_eq!(
+        hexfile
+            .normalized_lossy()
+            .read_bytes_contiguous(0x1000, 6)
+            .unwrap(),
+        vec![0x01, 0x02, 0x03, 0x04, 0x00, 0x0A]
+    );
+
+    let output = run("__app_missing");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("__app_missing"), "{stderr}");
+}
+
+#[test]
+fn test_cli_checksum_mmap_input_matches_read() {
+    let dir = temp_dir("cli_checksum_mmap");
+    let input = dir.join("input.bin");
+    let data: Vec<u8> = (0..0x3000u32).map(|i| (i * 7) as u8).collect();
+    write_file(&input, &data);
+
+    let run = |extra: &str, out: &str| {
+        let out = dir.join(out);
+        let mut args = vec![
+            format!("/IN:{};0x1000", input.display()),
+            "/CS9:@append".to_string(),
+            "/XI".to_string(),
+            "-o".to_string(),
+            out.display().to_string(),
+        ];
+        if !extra.is_empty() {
+            args.push(extra.to_string());
+        }
+        assert_success(&run_h3xy(&args));
+        std::fs::read(out).unwrap()
+    };
+
+    let read = run("", "read.hex");
+    assert_eq!(run("/MMAP", "mmap.hex"), read);
+    assert_eq!(run("/BTFST=1", "btfst.hex"), read);
+    assert_eq!(std::fs::read(&input).unwrap(), data);
+}
+
+#[test]
+fn test_cli_mmap_input_is_never_overwritten_while_mapped() {
+    let dir = temp_dir("cli_mmap_in_place");
+    let input = dir.join("m.bin");
+    let data: Vec<u8> = (0..0x3000u32).map(|i| (i * 7) as u8).collect();
+    let run = |extra: &[&str]| {
+        let mut args = vec![
+            format!("/IN:{};0x1000", input.display()),
+            "/MMAP".to_string(),
+        ];
+        args.extend(extra.iter().map(|arg| arg.to_string()));
+        run_h3xy(&args)
+    };
+    let path = input.display().to_string();
+
+    // An export onto the /IN file reads it instead of mapping it.
+    write_file(&input, &data);
+    let output = run(&["/XN", "-o", &path]);
+    assert_success(&output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Note: /MMAP: the /IN file is also an output"),
+        "{stderr}"
+    );
+    assert_eq!(std::fs::read(&input).unwrap(), data);
+
+    let output = run(&["/XI", "-o", &path]);
+    assert_success(&output);
+    let written = parse_intel_hex(&std::fs::read(&input).unwrap()).unwrap();
+    assert_eq!(written.segments()[0].start_address, 0x1000);
+    assert_eq!(written.segments()[0].data, data);
+
+    // Any other write to the mapped file is refused and leaves it intact.
+    write_file(&input, &data);
+    let sum = format!("/CS9:{path}");
+    let output = run(&[&sum]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("refusing to overwrite"), "{stderr}");
+    assert_eq!(std::fs::read(&input).unwrap(), data);
+}
+
+#[test]
+fn test_cli_checksum_append_prepend_out_of_address_space() {
+    let dir = temp_dir("cli_checksum_bounds");
+    let input = dir.join("input.bin");
+    write_file(&input, &[0x01, 0x02]);
+
+    for (offset, cs_arg, message) in [
+        ("0xFFFFFFFE", "/CS0:@append", "address overflow"),
+        ("0x0", "/CSR0:@upfront", "address underflow"),
+    ] {
+        let output = run_h3xy(&[
+            format!("/IN:{};{offset}", input.display()),
+            cs_arg.to_string(),
+            "/XI".to_string(),
+            "-o".to_string(),
+            dir.join("out.hex").display().to_string(),
+        ]);
+        assert!(!output.status.success(), "{cs_arg}");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let option = cs_arg.split(':').next().unwrap();
+        assert!(stderr.contains(option), "{stderr}");
+        assert!(stderr.contains(message), "{stderr}");
+    }
+}
+
 #[test]
 fn test_cli_checksum_default_append_without_target() {
     let hexfile = run_checksum_hex(&[0x01, 0x02, 0x03, 0x04], "/CS0");
@@ -66,6 +201,84 @@ fn test_cli_checksum_begin() {
     );
 }
 
+#[test]
+fn test_cli_checksum_overwrite_targets_ignore_stored_bytes() {
+    for (cs_arg, clean, dirty) in [
+        (
+            "/CS0:@begin",
+            [0x00, 0x00, 0x03, 0x04],
+            [0xDE, 0xAD, 0x03, 0x04],
+        ),
+        (
+            "/CS0:@end",
+            [0x01, 0x02, 0x00, 0x00],
+            [0x01, 0x02, 0xBE, 0xEF],
+        ),
+        (
+            "/CS0:@begin;X",
+            [0x00, 0x00, 0x03, 0x04],
+            [0xDE, 0xAD, 0x03, 0x04],
+        ),
+        (
+            "/CS0:@end;X",
+            [0x01, 0x02, 0x00, 0x00],
+            [0x01, 0x02, 0xBE, 0xEF],
+        ),
+    ] {
+        assert_eq!(
+            run_checksum_hex(&clean, cs_arg).normalized_lossy(),
+            run_checksum_hex(&dirty, cs_arg).normalized_lossy(),
+            "{cs_arg}"
+        );
+    }
+}
+
+#[test]
+fn test_cli_checksum_target_as_fill_suffix() {
+    // ;X sums the target bytes at 0x1002-0x1003 as 0xFF: 0x01 + 0x02 + 0xFF + 0xFF = 0x0201
+    let hexfile = run_checksum_hex(&[0x01, 0x02, 0x03, 0x04], "/CS0:@0x1002;0x1000-0x1003;X");
+    let norm = hexfile.normalized_lossy();
+    assert_eq!(
+        norm.read_bytes_contiguous(0x1000, 4).unwrap(),
+        vec![0x01, 0x02, 0x02, 0x01]
+    );
+}
+
+#[test]
+fn test_cli_checksum_gap_fill_suffix() {
+    // Range extends 2 bytes past the data; FILL=00 contributes nothing to the sum.
+    let hexfile = run_checksum_hex(
+        &[0x01, 0x02, 0x03, 0x04],
+        "/CS0:@0x2000;0x1000-0x1005;FILL=00",
+    );
+    let norm = hexfile.normalized_lossy();
+    assert_eq!(
+        norm.read_bytes_contiguous(0x2000, 2).unwrap(),
+        vec![0x00, 0x0A]
+    );
+
+    let hexfile = run_checksum_hex(
+        &[0x01, 0x02, 0x03, 0x04],
+        "/CS0:@0x2000;0x1000-0x1005;FILL=01",
+    );
+    let norm = hexfile.normalized_lossy();
+    assert_eq!(
+        norm.read_bytes_contiguous(0x2000, 2).unwrap(),
+        vec![0x00, 0x0C]
+    );
+}
+
+#[test]
+fn test_cli_checksum_initial_continues_crc32() {
+    // CRC-32 of "1234" is 0x9BE3E0A3; continuing over "56789" yields CRC-32("123456789").
+    let hexfile = run_checksum_hex(b"123456789", "/CS9:@0x2000;0x1004-0x1008;INIT=0x9BE3E0A3");
+    let norm = hexfile.normalized_lossy();
+    assert_eq!(
+        norm.read_bytes_contiguous(0x2000, 4).unwrap(),
+        vec![0xCB, 0xF4, 0x39, 0x26]
+    );
+}
+
 #[test]
 fn test_cli_checksum_overwrite_end() {
     // @end writes checksum at end of data (0x1002-0x1003), excluding those bytes
@@ -156,6 +369,33 @@ fn test_cli_checksum_file_output() {
     assert_eq!(text, "00,0A");
 }
 
+#[test]
+fn test_cli_checksum_stdout_and_file_formats() {
+    let dir = temp_dir("cli_checksum_fmt");
+    let input_path = dir.join("input.bin");
+    let header = dir.join("crc.h");
+    let raw = dir.join("crc.bin");
+    write_file(&input_path, b"123456789");
+
+    // @stdout prints the result even with /S; nothing else reaches stdout.
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        "/S".to_string(),
+        "/CS9:@stdout;FMT=HEX".to_string(),
+        format!("/CS9:{};FMT=C", header.display()),
+        format!("/CS9:{};FMT=BIN", raw.display()),
+        "/CS0:@stdout;FMT=DEC".to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "CBF43926\n477\n");
+    assert_eq!(
+        std::fs::read_to_string(&header).unwrap(),
+        "#define CHECKSUM 0xCBF43926"
+    );
+    assert_eq!(std::fs::read(&raw).unwrap(), vec![0xCB, 0xF4, 0x39, 0x26]);
+}
+
 #[test]
 fn test_cli_checksum_sha256_file_output() {
     let dir = temp_dir("cli_checksum_file_sha256");
@@ -220,6 +460,143 @@ fn test_cli_checksum_multi_sequential_dependency() {
     );
 }
 
+#[test]
+fn test_cli_checksum_repeated_cs_applies_in_order() {
+    let dir = temp_dir("cli_checksum_repeated_cs");
+    let input_path = dir.join("input.bin");
+    let out_path = dir.join("out.hex");
+    write_file(&input_path, b"123456789");
+
+    // CRC-32 over the code area stored at 0x1010, then a byte sum over the
+    // whole image (including the CRC) appended after it.
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        "/CS9:@0x1010;0x1000-0x1008".to_string(),
+        "/CS0:@append".to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out_path.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+
+    let data = std::fs::read(&out_path).unwrap();
+    let norm = parse_intel_hex(&data).unwrap().normalized_lossy();
+    assert_eq!(
+        norm.read_bytes_contiguous(0x1010, 4).unwrap(),
+        vec![0xCB, 0xF4, 0x39, 0x26]
+    );
+    let sum = b"123456789"
+        .iter()
+        .chain(&[0xCB, 0xF4, 0x39, 0x26])
+        .fold(0u16, |acc, &b| acc + b as u16);
+    assert_eq!(
+        norm.read_bytes_contiguous(0x1014, 2).unwrap(),
+        sum.to_be_bytes().to_vec()
+    );
+}
+
+#[test]
+fn test_cli_block_checksum_after_split() {
+    let dir = temp_dir("cli_block_checksum_split");
+    let input_path = dir.join("input.bin");
+    let out_path = dir.join("out.hex");
+    let csum_path = dir.join("csum.txt");
+    write_file(
+        &input_path,
+        &[0x01, 0x02, 0x00, 0x00, 0x10, 0x20, 0x00, 0x00],
+    );
+
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        "/SB:4".to_string(),
+        "/CSB0:@end".to_string(),
+        format!("/CSB0:{}", csum_path.display()),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out_path.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+
+    let data = std::fs::read(&out_path).unwrap();
+    let norm = parse_intel_hex(&data).unwrap().normalized_lossy();
+    assert_eq!(
+        norm.read_bytes_contiguous(0x1000, 8).unwrap(),
+        vec![0x01, 0x02, 0x00, 0x03, 0x10, 0x20, 0x00, 0x30]
+    );
+    let csum = std::fs::read_to_string(&csum_path).unwrap();
+    assert_eq!(csum, "00,06\n00,60");
+}
+
+#[test]
+fn test_cli_block_checksum_begin_after_split() {
+    let dir = temp_dir("cli_block_checksum_begin");
+    let input_path = dir.join("input.bin");
+    let out_path = dir.join("out.hex");
+    write_file(
+        &input_path,
+        &[0xDE, 0xAD, 0x01, 0x02, 0xBE, 0xEF, 0x10, 0x20],
+    );
+
+    let output = run_h3xy(&[
+        format!("/IN:{};0x1000", input_path.display()),
+        "/SB:4".to_string(),
+        "/CSB0:@begin".to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out_path.display().to_string(),
+    ]);
+    assert_success(&output);
+
+    let data = std::fs::read(&out_path).unwrap();
+    let norm = parse_intel_hex(&data).unwrap().normalized_lossy();
+    assert_eq!(
+        norm.read_bytes_contiguous(0x1000, 8).unwrap(),
+        vec![0x00, 0x03, 0x01, 0x02, 0x00, 0x30, 0x10, 0x20]
+    );
+}
+
+#[test]
+fn test_cli_block_checksum_after_boundary_split() {
+    let dir = temp_dir("cli_block_checksum_boundary");
+    let input_path = dir.join("input.bin");
+    let csum_path = dir.join("csum.txt");
+    write_file(&input_path, &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+    // 0x0FFE-0x1005 straddles 0x1000 and 0x1004.
+    let run = |split: &[&str]| {
+        let mut args = vec![format!("/IN:{};0x0FFE", input_path.display())];
+        args.extend(split.iter().map(|arg| arg.to_string()));
+        args.push(format!("/CSB0:{}", csum_path.display()));
+        assert_success(&run_h3xy(&args));
+        std::fs::read_to_string(&csum_path).unwrap()
+    };
+    assert_eq!(run(&["/SBA:4"]), "00,03\n00,12\n00,0F");
+    assert_eq!(run(&["/SBA:4", "/SB:3"]), "00,03\n00,0C\n00,06\n00,0F");
+}
+
+#[test]
+fn test_cli_block_checksum_append_into_next_block_fails() {
+    let dir = temp_dir("cli_block_checksum_collision");
+    let input_path = dir.join("input.bin");
+    let out_path = dir.join("out.hex");
+    write_file(&input_path, &[0xAA; 8]);
+
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        "/SB:4".to_string(),
+        "/CSB0".to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out_path.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("/CSB0"), "{stderr}");
+}
+
 #[test]
 fn test_cli_checksum_multi_mixed_targets_with_file() {
     let dir = temp_dir("cli_checksum_multi_file");
@@ -268,3 +645,34 @@ fn test_cli_checksum_reject_mix_legacy_and_multi() {
     assert!(stderr.contains("invalid option"));
     assert!(stderr.contains("/CSM0:@append"));
 }
+
+#[test]
+fn test_cli_checksums_over_overlapping_merge() {
+    let dir = temp_dir("cli_checksum_merge");
+    let base = dir.join("base.bin");
+    let merge = dir.join("merge.bin");
+    let out_path = dir.join("out.hex");
+    write_file(&base, &[0x01, 0x02, 0x03, 0x04]);
+    write_file(&merge, &[0x10, 0x20, 0x30]);
+
+    let args = vec![
+        format!("/IN:{};0x1000", base.display()),
+        format!("/MO:{};0x1002", merge.display()),
+        "/CS0:@append".to_string(),
+        "/CS0:@0x2000".to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out_path.display().to_string(),
+    ];
+    assert_success(&run_h3xy(&args));
+
+    let hexfile = parse_intel_hex(&std::fs::read(&out_path).unwrap()).unwrap();
+    // 01 02 10 20 30 sums to 0x63; the second sum includes the first.
+    assert_eq!(
+        hexfile.segments(),
+        &[
+            h3xy::Segment::new(0x1000, vec![0x01, 0x02, 0x10, 0x20, 0x30, 0x00, 0x63]),
+            h3xy::Segment::new(0x2000, vec![0x00, 0xC6]),
+        ]
+    );
+}
@@ -1,7 +1,12 @@
 mod common;
 
+use std::io::Write;
+
 use common::{assert_success, run_h3xy, temp_dir, write_file};
-use h3xy::parse_intel_hex;
+use h3xy::{
+    HexFile, IntelHexWriteOptions, SRecordWriteOptions, Segment, parse_intel_hex,
+    parse_intel_hex_16bit, write_intel_hex, write_srec,
+};
 
 #[test]
 fn test_cli_multistage_bin_merge_align_output() {
@@ -39,3 +44,495 @@ fn test_cli_multistage_bin_merge_align_output() {
         vec![0xEE, 0x10, 0x11, 0xAA, 0xBB, 0x14]
     );
 }
+
+#[test]
+fn test_cli_elf_input_lma_and_vma() {
+    let dir = temp_dir("cli_elf_input");
+    let input = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/tiny.elf");
+    let out = dir.join("out.hex");
+
+    let args = vec![
+        input.display().to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+
+    let hexfile = parse_intel_hex(&std::fs::read(&out).unwrap()).unwrap();
+    let norm = hexfile.normalized_lossy();
+    assert_eq!(norm.segments().len(), 1);
+    assert_eq!(norm.segments()[0].start_address, 0x1000);
+    assert_eq!(
+        norm.segments()[0].data,
+        vec![0x01, 0x02, 0x03, 0x04, 0xAA, 0xBB]
+    );
+
+    let args = vec![
+        input.display().to_string(),
+        "/ELFVMA".to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+
+    let hexfile = parse_intel_hex(&std::fs::read(&out).unwrap()).unwrap();
+    let norm = hexfile.normalized_lossy();
+    assert_eq!(norm.segments().len(), 2);
+    assert_eq!(norm.segments()[1].start_address, 0x2000_0000);
+    assert_eq!(norm.segments()[1].data, vec![0xAA, 0xBB]);
+}
+
+#[test]
+fn test_cli_lenient_intel_hex_warnings() {
+    let dir = temp_dir("cli_lenient");
+    let input = dir.join("vendor.hex");
+    let out = dir.join("out.hex");
+    write_file(&input, b":0100000055AA\r\n; vendor footer\r\n");
+
+    let strict = vec![
+        input.display().to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&strict);
+    assert!(!output.status.success());
+
+    let mut lenient = strict.clone();
+    lenient.insert(1, "/LENIENT".to_string());
+    let output = run_h3xy(&lenient);
+    assert_success(&output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("line 2: skipped non-record line"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("missing EOF record"), "{stderr}");
+    let hexfile = parse_intel_hex(&std::fs::read(&out).unwrap()).unwrap();
+    assert_eq!(hexfile.segments()[0].data, vec![0x55]);
+
+    // A leading comment would otherwise make auto-detect fall back to binary.
+    write_file(
+        &input,
+        b"# vendor export\r\n:0100000055AA\r\n:00000001FF\r\n",
+    );
+    lenient.insert(1, "/S".to_string());
+    let output = run_h3xy(&lenient);
+    assert_success(&output);
+    assert!(output.stderr.is_empty());
+    let hexfile = parse_intel_hex(&std::fs::read(&out).unwrap()).unwrap();
+    assert_eq!(hexfile.segments()[0].data, vec![0x55]);
+}
+
+#[test]
+fn test_cli_lenient_srec_record_count() {
+    let input = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/wrong_count.s19");
+    let dir = temp_dir("cli_srec_count");
+    let out = dir.join("out.hex");
+
+    let strict = vec![
+        input.display().to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&strict);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("S5 says 5, found 3"), "{stderr}");
+
+    let mut lenient = strict.clone();
+    lenient.insert(1, "/LENIENT".to_string());
+    let output = run_h3xy(&lenient);
+    assert_success(&output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("line 5: record count says 5, found 3 data records"),
+        "{stderr}"
+    );
+    let hexfile = parse_intel_hex(&std::fs::read(&out).unwrap()).unwrap();
+    let norm = hexfile.normalized_lossy();
+    assert_eq!(norm.segments()[0].data, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(norm.segments()[1].data, vec![0xAA, 0xBB]);
+}
+
+#[test]
+fn test_cli_forced_input_format() {
+    let dir = temp_dir("cli_input_format");
+    let input = dir.join("image.bin");
+    let out = dir.join("out.hex");
+    // All-ASCII binary starting with 0x53 ('S') auto-detects as S-Record.
+    write_file(&input, b"SEQ=1");
+
+    let mut args = vec![
+        input.display().to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+
+    args.insert(1, "/IT:BIN".to_string());
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    let hexfile = parse_intel_hex(&std::fs::read(&out).unwrap()).unwrap();
+    assert_eq!(hexfile.segments()[0].start_address, 0);
+    assert_eq!(hexfile.segments()[0].data, b"SEQ=1".to_vec());
+
+    args[1] = "/IT:IHEX".to_string();
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("forced by /IT"), "{stderr}");
+    assert!(stderr.contains("IHEX"), "{stderr}");
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn test_cli_gzip_input() {
+    let dir = temp_dir("cli_gzip");
+    let hex = dir.join("app.hex.gz");
+    let bin = dir.join("cal.bin.gz");
+    let out = dir.join("out.hex");
+    write_file(&hex, &gzip(b":0200000055AAFF\r\n:00000001FF\r\n"));
+    write_file(&bin, &gzip(&[0x00, 0x80, 0xFF]));
+
+    let args = vec![
+        hex.display().to_string(),
+        format!("/MT:{};0x100", bin.display()),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    let hexfile = parse_intel_hex(&std::fs::read(&out).unwrap()).unwrap();
+    assert_eq!(hexfile.segments()[0].data, vec![0x55, 0xAA]);
+    assert_eq!(hexfile.segments()[1].start_address, 0x100);
+    assert_eq!(hexfile.segments()[1].data, vec![0x00, 0x80, 0xFF]);
+
+    // Binary import goes through the same provider.
+    let args = vec![
+        format!("/IN:{};0x2000", bin.display()),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    let hexfile = parse_intel_hex(&std::fs::read(&out).unwrap()).unwrap();
+    assert_eq!(hexfile.segments()[0].start_address, 0x2000);
+    assert_eq!(hexfile.segments()[0].data, vec![0x00, 0x80, 0xFF]);
+}
+
+#[test]
+fn test_cli_gzip_size_limit() {
+    let dir = temp_dir("cli_gzip_limit");
+    let input = dir.join("bomb.bin.gz");
+    let out = dir.join("out.bin");
+    write_file(&input, &gzip(&vec![0u8; 2 * 1024 * 1024]));
+
+    let args = vec![
+        input.display().to_string(),
+        "/GZMAX=1".to_string(),
+        "/XN".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("exceeds 1048576 bytes"), "{stderr}");
+
+    let output = run_h3xy(
+        &args[..1]
+            .iter()
+            .chain(&args[2..])
+            .cloned()
+            .collect::<Vec<_>>(),
+    );
+    assert_success(&output);
+    assert_eq!(std::fs::metadata(&out).unwrap().len(), 2 * 1024 * 1024);
+}
+
+#[test]
+fn test_cli_intel_hex_16bit_export_roundtrip() {
+    let dir = temp_dir("cli_xi2");
+    let input = dir.join("input.bin");
+    let out = dir.join("out.hex");
+    let data: Vec<u8> = (0u8..24).collect();
+    write_file(&input, &data);
+
+    let args = vec![
+        format!("/IN:{};0x1000", input.display()),
+        "/XI2".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+
+    let text = std::fs::read_to_string(&out).unwrap();
+    assert!(text.starts_with(":10080000"), "{text}");
+    let hexfile = parse_intel_hex_16bit(text.as_bytes()).unwrap();
+    let norm = hexfile.normalized_lossy();
+    assert_eq!(norm.segments().len(), 1);
+    assert_eq!(norm.segments()[0].start_address, 0x1000);
+    assert_eq!(norm.segments()[0].data, data);
+
+    let args = vec![
+        format!("/IN:{};0x1001", input.display()),
+        "/XI2".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_cli_help_screens() {
+    let stdout =
+        |output: &std::process::Output| String::from_utf8_lossy(&output.stdout).into_owned();
+
+    let output = run_h3xy(&[]);
+    assert_success(&output);
+    let overview = stdout(&output);
+    assert!(overview.contains("Checksums:"), "{overview}");
+    assert!(
+        overview.contains("/CS<n>[:target][;param...]"),
+        "{overview}"
+    );
+
+    let output = run_h3xy(&["/S".to_string(), "/?".to_string()]);
+    assert_success(&output);
+    assert_eq!(stdout(&output), overview);
+
+    let output = run_h3xy(&["/HELP:CS".to_string()]);
+    assert_success(&output);
+    let detail = stdout(&output);
+    assert!(detail.contains("9 CRC-32"), "{detail}");
+    assert!(detail.contains("Stage: Checksums"), "{detail}");
+
+    let output = run_h3xy(&["/HELP:NOPE".to_string()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no help for option: NOPE"));
+}
+
+#[test]
+fn test_cli_absolute_unix_paths_are_inputs() {
+    let dir = temp_dir("cli_abs_path");
+    let input = dir.join("x.hex");
+    let cal = dir.join("cal.hex");
+    let out = dir.join("out.hex");
+    write_file(&input, b":020000000102FB\n:00000001FF\n");
+    write_file(&cal, b":01001000559A\n:00000001FF\n");
+
+    let output = run_h3xy(&[
+        input.display().to_string(),
+        format!("/MO:{}", cal.display()),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ]);
+    assert_success(&output);
+    let norm = parse_intel_hex(&std::fs::read(&out).unwrap())
+        .unwrap()
+        .normalized_lossy();
+    assert_eq!(norm.segments()[0].data, vec![0x01, 0x02]);
+    assert_eq!(norm.segments()[1].data, vec![0x55]);
+
+    // A missing file is reported as a missing file, not an invalid option.
+    let missing = dir.join("missing.hex");
+    let output = run_h3xy(&[
+        missing.display().to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ]);
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("invalid option"), "{stderr}");
+    assert!(stderr.contains(&missing.display().to_string()), "{stderr}");
+
+    let output = run_h3xy(&["/BOGUS".to_string()]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid option: /BOGUS"));
+}
+
+#[test]
+fn test_cli_exit_codes_by_category() {
+    let dir = temp_dir("cli_exit_codes");
+    let input = dir.join("in.hex");
+    let log = dir.join("err.log");
+    let split = dir.join("split.hex");
+    let kernel = dir.join("kernel.bin");
+    write_file(&input, b":020000000102FB\n:00000001FF\n");
+    write_file(&split, b":020000000102FB\n:021000000304E7\n:00000001FF\n");
+    let input = input.display().to_string();
+    let split = split.display().to_string();
+    let kernel = kernel.display().to_string();
+
+    let cases: [(&[&str], i32); 6] = [
+        (&["/BOGUS"], 2),
+        (&["/HELP:NOPE"], 2),
+        (&["/CS9:@0x10;0x5-0x1"], 2),
+        (&[&input, "/REMAP:0x0-0x2000,0x0,0x0,0x0"], 4),
+        // A writer refusing the image (/XK takes one segment) is not an I/O error.
+        (&[&split, "/XK", "-o", &kernel], 4),
+        (&[&input, "/MT:a.hex", "/MO:b.hex"], 6),
+    ];
+    for (args, code) in cases {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        let output = run_h3xy(&args);
+        assert_eq!(output.status.code(), Some(code), "{args:?}");
+    }
+
+    let missing = dir.join("missing.hex");
+    let output = run_h3xy(&[
+        format!("/E:{}", log.display()),
+        "/S".to_string(),
+        "--".to_string(),
+        missing.display().to_string(),
+    ]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(output.stderr.is_empty());
+    let contents = std::fs::read_to_string(&log).unwrap();
+    let (time, entry) = contents.split_at(19);
+    assert!(
+        time.as_bytes()[4] == b'-' && time.as_bytes()[13] == b':',
+        "{contents}"
+    );
+    assert!(entry.starts_with(" [E003] "), "{contents}");
+    assert!(entry.ends_with('\n'), "{contents}");
+}
+
+#[test]
+fn test_cli_compare_with_reference() {
+    let dir = temp_dir("cli_compare");
+    let input = dir.join("input.hex");
+    let golden = dir.join("golden.s19");
+    let changed = dir.join("changed.s19");
+    let data: Vec<u8> = (0u8..0x40).collect();
+    let image = HexFile::with_segments(vec![Segment::new(0x8000, data.clone())]);
+    let intel = IntelHexWriteOptions {
+        bytes_per_line: 0x20,
+        ..Default::default()
+    };
+    write_file(&input, &write_intel_hex(&image, &intel).unwrap());
+    // Same bytes as S-Records with a different record length.
+    let srec = SRecordWriteOptions {
+        bytes_per_line: 0x10,
+        ..Default::default()
+    };
+    write_file(&golden, &write_srec(&image, &srec).unwrap());
+    let mut modified = data;
+    modified[0x11] = 0xAA;
+    modified[0x12] = 0xBB;
+    let image = HexFile::with_segments(vec![
+        Segment::new(0x8000, modified),
+        Segment::new(0x9000, vec![0x01]),
+    ]);
+    write_file(&changed, &write_srec(&image, &srec).unwrap());
+
+    let output = run_h3xy(&[
+        input.display().to_string(),
+        format!("/CMP:{}", golden.display()),
+    ]);
+    assert_success(&output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("content matches"));
+
+    let output = run_h3xy(&[
+        input.display().to_string(),
+        format!("/CMP:{}", changed.display()),
+    ]);
+    assert_eq!(output.status.code(), Some(5));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("0x00008011-0x00008012: 11 12 != AA BB\n"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("0x00009000-0x00009000: -- != 01\n"),
+        "{stdout}"
+    );
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("in 2 range(s)"),
+        "{output:?}"
+    );
+
+    let output = run_h3xy(&[
+        input.display().to_string(),
+        "/AR:0x8000-0x800F".to_string(),
+        format!("/CMP:{}", changed.display()),
+    ]);
+    assert_success(&output);
+}
+
+#[test]
+fn test_cli_info_summary() {
+    let dir = temp_dir("cli_info");
+    let input = dir.join("input.hex");
+    let merge = dir.join("merge.s19");
+    let image = HexFile::with_segments(vec![
+        Segment::new(0x1000, vec![0x11; 0x10]),
+        Segment::new(0x2000, vec![0x22; 0x04]),
+    ]);
+    write_file(
+        &input,
+        &write_intel_hex(&image, &IntelHexWriteOptions::default()).unwrap(),
+    );
+    let extra = HexFile::with_segments(vec![Segment::new(0x3000, vec![0x33; 0x08])]);
+    write_file(
+        &merge,
+        &write_srec(&extra, &SRecordWriteOptions::default()).unwrap(),
+    );
+
+    // /INFO needs no -o and skips the later steps (here /FA and /XN).
+    let output = run_h3xy(&[
+        input.display().to_string(),
+        "/S".to_string(),
+        "/INFO".to_string(),
+        "/FA".to_string(),
+        "/XN".to_string(),
+    ]);
+    assert_success(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout,
+        format!(
+            "input: {} (Intel HEX)\n\
+             segments: 2\n\
+             \x20 0x00001000-0x0000100F 16 bytes\n\
+             \x20 0x00002000-0x00002003 4 bytes\n\
+             total: 20 bytes\n\
+             range: 0x00001000-0x00002003\n\
+             gaps: 1 (4080 bytes)\n",
+            input.display()
+        )
+    );
+
+    let output = run_h3xy(&[
+        input.display().to_string(),
+        format!("/MO:{}", merge.display()),
+        "/INFO".to_string(),
+    ]);
+    assert_success(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("segments: 3\n"), "{stdout}");
+    assert!(
+        stdout.contains("  0x00003000-0x00003007 8 bytes\n"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("gaps: 2 ("), "{stdout}");
+}
@@ -35,3 +35,142 @@ fn test_execute_in_memory_merge_opaque_intel_hex() {
 
     let _ = std::fs::remove_dir_all(dir);
 }
+
+#[test]
+fn test_execute_in_memory_returns_every_checksum() {
+    let mut blocks = HashMap::new();
+    let base_hex = h3xy::HexFile::with_segments(vec![h3xy::Segment::new(0x1000, vec![0x01, 0x02])]);
+    blocks.insert("base".to_string(), base_hex);
+
+    let output = cli::execute_in_memory("base /CS0:@append /CS0:@append", &blocks).unwrap();
+    assert_eq!(
+        output.checksum_bytes,
+        vec![vec![0x00, 0x03], vec![0x00, 0x06]]
+    );
+}
+
+#[test]
+fn test_execute_in_memory_errors_are_typed() {
+    use std::error::Error;
+
+    let mut blocks = HashMap::new();
+    let base_hex = h3xy::HexFile::with_segments(vec![h3xy::Segment::new(0x1000, vec![0x01, 0x02])]);
+    blocks.insert("base".to_string(), base_hex);
+
+    let missing = std::env::temp_dir()
+        .join("h3xy_cli_mem_missing")
+        .join("cal2.hex");
+    let err =
+        cli::execute_in_memory(&format!("base /MT:{}", missing.display()), &blocks).unwrap_err();
+    match err.without_context() {
+        cli::CliError::Io {
+            option: Some(option),
+            path: Some(path),
+            source,
+        } => {
+            assert_eq!(option, "/MT");
+            assert_eq!(path, &missing);
+            assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+        }
+        other => panic!("expected an I/O error, got {other:?}"),
+    }
+    assert_eq!(err.exit_code(), cli::EXIT_IO);
+    assert!(err.source().is_some());
+
+    let err = cli::execute_in_memory("base /CS0:@append;START-0xFFF", &blocks).unwrap_err();
+    assert!(
+        matches!(
+            err.without_context(),
+            cli::CliError::Range(h3xy::RangeError::NoData(_))
+        ),
+        "{err:?}"
+    );
+    assert_eq!(err.to_string(), "/CS0: range START-0xFFF holds no data");
+    assert_eq!(err.exit_code(), cli::EXIT_OPERATION);
+}
+
+#[test]
+fn test_execute_in_memory_captured_writes_nothing() {
+    let mut blocks = HashMap::new();
+    blocks.insert(
+        "base".to_string(),
+        h3xy::HexFile::with_segments(vec![
+            h3xy::Segment::new(0x1000, vec![0x01, 0x02]),
+            h3xy::Segment::new(0x2000, vec![0x03]),
+        ]),
+    );
+    blocks.insert(
+        "merge".to_string(),
+        h3xy::HexFile::with_segments(vec![h3xy::Segment::new(0x1000, vec![0xFF])]),
+    );
+
+    let output = cli::execute_in_memory_captured(
+        "base /MO:merge /MKDIR /CS0:h3xy_captured/sum.txt /LOGOUT:h3xy_captured/run.log \
+         /XI -o h3xy_captured/out.hex /XSB -o h3xy_captured/part.bin",
+        &blocks,
+    )
+    .unwrap();
+
+    assert!(!std::path::Path::new("h3xy_captured").exists());
+    assert_eq!(output.checksum_bytes, vec![vec![0x01, 0x04]]);
+    assert_eq!(
+        output.files[std::path::Path::new("h3xy_captured/sum.txt")],
+        b"01,04"
+    );
+    assert!(
+        output
+            .files
+            .contains_key(std::path::Path::new("h3xy_captured/run.log"))
+    );
+
+    assert_eq!(output.outputs.len(), 2);
+    let hex = &output.outputs[0];
+    assert_eq!(
+        hex.format,
+        cli::OutputFormat::IntelHex { record_type: None }
+    );
+    let image = h3xy::parse_intel_hex(hex.bytes().unwrap()).unwrap();
+    assert_eq!(image.read_byte(0x1000), Some(0xFF));
+    assert_eq!(image.read_byte(0x2000), Some(0x03));
+
+    let parts = &output.outputs[1];
+    assert_eq!(
+        parts.format,
+        cli::OutputFormat::SeparateBinary { template: None }
+    );
+    assert_eq!(parts.bytes(), None);
+    let names: Vec<_> = parts.files.keys().map(String::as_str).collect();
+    assert_eq!(names, ["part_1000.bin", "part_2000.bin"]);
+    assert_eq!(parts.files["part_1000.bin"], [0xFF, 0x02]);
+}
+
+#[test]
+fn test_execute_in_memory_captured_c_code_pair() {
+    let dir = std::env::temp_dir().join(format!("h3xy_cli_mem_xc_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let ini = dir.join("c.ini");
+    std::fs::write(&ini, "[CCODE]\nPrefix=drv\n").unwrap();
+    let mut blocks = HashMap::new();
+    blocks.insert(
+        "base".to_string(),
+        h3xy::HexFile::with_segments(vec![h3xy::Segment::new(0x1000, vec![0xAB])]),
+    );
+
+    let output = cli::execute_in_memory_captured(
+        &format!(
+            "base /P:{} /XC -o {}",
+            ini.display(),
+            dir.join("drv.c").display()
+        ),
+        &blocks,
+    )
+    .unwrap();
+
+    assert!(!dir.join("drv.c").exists());
+    let files = &output.outputs[0].files;
+    let names: Vec<_> = files.keys().map(String::as_str).collect();
+    assert_eq!(names, ["drv.c", "drv.h"]);
+    assert!(String::from_utf8_lossy(&files["drv.h"]).contains("drv"));
+
+    let _ = std::fs::remove_dir_all(dir);
+}
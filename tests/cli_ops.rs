@@ -257,25 +257,205 @@ fn test_cli_fill_region_without_pattern_random() {
 }
 
 #[test]
-fn test_cli_threshold_options_noop() {
-    let dir = temp_dir("cli_thresholds");
+fn test_cli_open_ranges_resolve_per_step() {
+    let dir = temp_dir("cli_open_ranges");
     let input = dir.join("input.bin");
+    let other = dir.join("other.bin");
     let out = dir.join("out.hex");
-    write_file(&input, &[0x01, 0x02, 0x03, 0x04]);
+    write_file(&input, &[0x11; 0x10]);
+    write_file(&other, &[0x22; 4]);
 
+    // /FR and /CR run before the merge, so their END is 0x100F; /AR and /CS
+    // run after it and see the merged data up to 0x2003.
     let args = vec![
-        format!("/IN:{};0x0", input.display()),
-        "/BHFCT=10".to_string(),
-        "/BTFST=0x20".to_string(),
-        "/BTBS=0b100".to_string(),
+        format!("/IN:{};0x1000", input.display()),
+        format!("/MO:{};0x2000", other.display()),
+        "/FR:0x0FF0-END".to_string(),
+        "/FP:AA".to_string(),
+        "/CR:0x100C-end".to_string(),
+        "/AR:0x0FF8-END".to_string(),
+        "/CS0:@append;0x2000-END".to_string(),
         "/XI".to_string(),
         "-o".to_string(),
         out.display().to_string(),
     ];
 
-    let hexfile = run_hex_output(args, &out);
-    let norm = hexfile.normalized_lossy();
-    assert_eq!(norm.segments().len(), 1);
+    let hexfile = run_hex_output(args, &out).normalized_lossy();
+    let mut expected = vec![0xAA; 8];
+    expected.extend([0x11; 12]);
+    assert_eq!(hexfile.read_bytes_contiguous(0x0FF8, 20).unwrap(), expected);
+    assert_eq!(
+        hexfile.read_bytes_contiguous(0x2000, 6).unwrap(),
+        vec![0x22, 0x22, 0x22, 0x22, 0x00, 0x88]
+    );
+    assert_eq!(hexfile.segments().len(), 2);
+}
+
+#[test]
+fn test_cli_big_image_thresholds_keep_output() {
+    let dir = temp_dir("cli_thresholds");
+    let first = dir.join("first.bin");
+    let second = dir.join("second.bin");
+    write_file(&first, &[0x01, 0x02, 0x03, 0x04]);
+    write_file(&second, &[0x05; 40]);
+
+    let run = |name: &str, extra: &[&str]| {
+        let out = dir.join(name);
+        let mut args = vec![
+            format!("/IN:{};0xFFF3", first.display()),
+            format!("/MO:{};0x2_3456", second.display()),
+            "/FA".to_string(),
+            "/AF:0x5A".to_string(),
+        ];
+        args.extend(extra.iter().map(|arg| arg.to_string()));
+        args.extend(["-o".to_string(), out.display().to_string()]);
+        assert_success(&run_h3xy(&args));
+        std::fs::read(out).unwrap()
+    };
+
+    // /BHFCT=1 puts the 82 KiB image on the low-memory path: /FA is left to the
+    // writers, or filled in /BTBS blocks when a checksum reads the data first.
+    let thresholds = ["/BHFCT=1", "/BTFST=0x20", "/BTBS=0b1"];
+    for (index, format) in [&["/XI"][..], &["/XS"], &["/XN"], &["/XI", "/CS9:@append"]]
+        .into_iter()
+        .enumerate()
+    {
+        let dense = run(&format!("dense{index}"), format);
+        let low_memory: Vec<&str> = format.iter().chain(&thresholds).copied().collect();
+        assert_eq!(
+            run(&format!("big{index}"), &low_memory),
+            dense,
+            "{format:?}"
+        );
+    }
+    assert_eq!(
+        run("image.bin", &["/XN", "/BHFCT=1"]).len(),
+        0x2_3456 + 40 - 0xFFF3
+    );
+}
+
+#[test]
+fn test_cli_fill_all_rejects_huge_span() {
+    let dir = temp_dir("cli_fa_span");
+    let input = dir.join("input.bin");
+    let out = dir.join("out.bin");
+    write_file(&input, &[0x11]);
+
+    let args = vec![
+        format!("/IN:{};0x0", input.display()),
+        format!("/MO:{};0xBFFF_FFFF", input.display()),
+        "/FA".to_string(),
+        "/XN".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("/FA") && stderr.contains("0x0..=0xBFFFFFFF is 3221225472 bytes"),
+        "{stderr}"
+    );
+    assert!(!out.exists());
+}
+
+#[test]
+fn test_cli_merge_errors_name_option_and_file() {
+    let dir = temp_dir("cli_merge_missing");
+    let input = dir.join("input.hex");
+    let missing = dir.join("cal2.hex");
+    let broken = dir.join("broken.hex");
+    let out = dir.join("out.hex");
+    write_file(&input, b":020000000102FB\r\n:00000001FF\r\n");
+    write_file(
+        &broken,
+        b":020000000102FB\r\n:02001000AABB00\r\n:00000001FF\r\n",
+    );
+
+    let output = run_h3xy(&[
+        input.display().to_string(),
+        format!("/MT:{}", missing.display()),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ]);
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!("/MT:{}: ", missing.display())),
+        "{stderr}"
+    );
+
+    let output = run_h3xy(&[
+        input.display().to_string(),
+        format!("/MO:{}", broken.display()),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ]);
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!("/MO:{}: ", broken.display())) && stderr.contains("line 2"),
+        "{stderr}"
+    );
+    assert!(!out.exists());
+}
+
+/// The gaps of a 128 MiB /FA image are never held in memory on the low-memory path.
+#[cfg(target_os = "linux")]
+#[test]
+fn test_cli_big_image_fill_all_bounded_memory() {
+    let dir = temp_dir("cli_big_fill");
+    let input = dir.join("input.bin");
+    let out = dir.join("out.bin");
+    write_file(&input, &[0x11, 0x22]);
+
+    let run = |extra: &str| {
+        std::process::Command::new("sh")
+            .arg("-c")
+            .arg("ulimit -v 65536 && exec \"$0\" \"$@\"")
+            .arg(env!("CARGO_BIN_EXE_h3xy"))
+            .arg(format!("/IN:{};0x0", input.display()))
+            .arg(format!("/MO:{};0x7FF_FFFE", input.display()))
+            .args(["/FA", extra, "/XN", "-o"])
+            .arg(&out)
+            .output()
+            .unwrap()
+    };
+
+    assert!(!run("/BTBS=64").status.success());
+    assert_success(&run("/BHFCT=1024"));
+    let data = std::fs::read(&out).unwrap();
+    assert_eq!(data.len(), 0x800_0000);
+    assert_eq!(&data[..3], &[0x11, 0x22, 0xFF]);
+    assert_eq!(&data[0x7FF_FFFD..], &[0xFF, 0x11, 0x22]);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_cli_deferred_fill_all_keeps_span_limit() {
+    let dir = temp_dir("cli_big_fill_limit");
+    let input = dir.join("input.bin");
+    let out = dir.join("out.hex");
+    write_file(&input, &[0x11]);
+
+    let output = run_h3xy(&[
+        format!("/IN:{};0x0", input.display()),
+        format!("/MO:{};0xFFFFFFFF", input.display()),
+        "/FA".to_string(),
+        "/BHFCT=1024".to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ]);
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("/FA") && stderr.contains("over the 268435456-byte limit"),
+        "{stderr}"
+    );
+    assert!(!out.exists());
 }
 
 #[test]
@@ -354,6 +534,197 @@ fn test_cli_error_log_on_failure() {
     assert!(!contents.is_empty());
 }
 
+#[test]
+fn test_cli_warnings_and_notes_reach_error_log() {
+    let dir = temp_dir("cli_warnings");
+    let input = dir.join("input.bin");
+    let out = dir.join("out.hex");
+    let log = dir.join("err.log");
+    write_file(&input, &[0x01, 0x02, 0x03, 0x04]);
+
+    let run = |extra: &[&str]| {
+        let _ = std::fs::remove_file(&out);
+        let mut args = vec![
+            format!("/IN:{};0x1000", input.display()),
+            format!("/E:{}", log.display()),
+            "/DATE:2026-03-07T08:09:10".to_string(),
+            "/FR:0x1000,0x10:0x1000,0x4".to_string(),
+            "/FP:00".to_string(),
+            "/XI".to_string(),
+            "-o".to_string(),
+            out.display().to_string(),
+        ];
+        args.extend(extra.iter().map(|arg| arg.to_string()));
+        run_h3xy(&args)
+    };
+    let expected = "2026-03-07 08:09:10 WARNING: /FR range 0x1000-0x1003 is already full of data; \
+                    nothing was filled\n\
+                    2026-03-07 08:09:10 NOTE: /FR range 0x1000-0x100F overlaps existing data, \
+                    which is kept\n";
+
+    let output = run(&[]);
+    assert_success(&output);
+    assert_eq!(std::fs::read_to_string(&log).unwrap(), expected);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Warning: /FR range 0x1000-0x1003 is already full"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("Note: /FR range 0x1000-0x100F"), "{stderr}");
+
+    let output = run(&["/S"]);
+    assert_success(&output);
+    assert!(output.stderr.is_empty());
+    assert_eq!(std::fs::read_to_string(&log).unwrap(), expected);
+
+    let output = run(&["/S", "/WERROR"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(!out.exists());
+    assert_eq!(
+        std::fs::read_to_string(&log).unwrap(),
+        format!("{expected}2026-03-07 08:09:10 [E004] 1 warning(s) treated as errors (/WERROR)\n")
+    );
+}
+
+#[test]
+fn test_cli_werror_counts_export_warnings_and_writes_nothing() {
+    let dir = temp_dir("cli_werror_export");
+    let input = dir.join("input.bin");
+    let out = dir.join("out.hex");
+    let sum = dir.join("sum.txt");
+    write_file(&input, &[0x01, 0x02, 0x03, 0x04]);
+
+    let run = |extra: &[&str]| {
+        let mut args = vec![
+            format!("/IN:{};0x1000", input.display()),
+            format!("/CS0:{}", sum.display()),
+            "/XF:RELAXED".to_string(),
+            "/FORD:module_id=0x7E0".to_string(),
+            "-o".to_string(),
+            out.display().to_string(),
+        ];
+        args.extend(extra.iter().map(|arg| arg.to_string()));
+        run_h3xy(&args)
+    };
+
+    let output = run(&[]);
+    assert_success(&output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Warning: /XF:RELAXED: placeholder [FORDHEADER] values for application"),
+        "{stderr}"
+    );
+    assert!(out.exists() && sum.exists());
+
+    std::fs::remove_file(&out).unwrap();
+    std::fs::remove_file(&sum).unwrap();
+    let output = run(&["/WERROR"]);
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("1 warning(s) treated as errors"),
+        "{stderr}"
+    );
+    assert!(!out.exists());
+    assert!(!sum.exists());
+}
+
+#[test]
+fn test_cli_verbose_prints_each_stage() {
+    let dir = temp_dir("cli_verbose");
+    let input = dir.join("input.bin");
+    let out = dir.join("out.hex");
+    write_file(&input, &[0x01, 0x02, 0x03, 0x04]);
+
+    let run = |extra: &[&str]| {
+        let mut args = vec![
+            format!("/IN:{};0x1000", input.display()),
+            "/FR:0x1004,0x4".to_string(),
+            "/FP:AA".to_string(),
+            "/CR:0x1001,0x1".to_string(),
+            "/AD:8".to_string(),
+            "/XI".to_string(),
+            "-o".to_string(),
+            out.display().to_string(),
+        ];
+        args.extend(extra.iter().map(|arg| arg.to_string()));
+        run_h3xy(&args)
+    };
+
+    let output = run(&["/VERBOSE"]);
+    assert_success(&output);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "/FR 0x1004-0x1007 pattern AA: 2 segment(s), 0x1000-0x1007, 8 byte(s)\n\
+         /CR 0x1001-0x1001: 3 segment(s), 0x1000-0x1007, 7 byte(s)\n\
+         /AD 0x8 fill FF: 1 segment(s), 0x1000-0x1007, 8 byte(s)\n"
+    );
+
+    let output = run(&["-vv"]);
+    assert_success(&output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("/CR 0x1001-0x1001: 3 segment(s), 0x1000-0x1007, 7 byte(s)\n  0x1000-0x1000 (1 byte(s))\n  0x1002-0x1003 (2 byte(s))\n"),
+        "{stderr}"
+    );
+
+    let output = run(&["-vv", "/S"]);
+    assert_success(&output);
+    assert!(output.stderr.is_empty());
+
+    let output = run(&[]);
+    assert_success(&output);
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_cli_error_log_append_keeps_earlier_runs() {
+    let dir = temp_dir("cli_error_log_append");
+    let log = dir.join("err.log");
+    let input = dir.join("in.hex");
+    std::fs::write(&log, "earlier\n").unwrap();
+    write_file(&input, b":020000000102FB\n:00000001FF\n");
+
+    let run = |date: &str, args: &[&str]| {
+        let mut all = vec![
+            format!("/E+:{}", log.display()),
+            format!("/DATE:{date}"),
+            "/S".to_string(),
+        ];
+        all.extend(args.iter().map(|arg| arg.to_string()));
+        run_h3xy(&all)
+    };
+    let missing = dir.join("missing.hex").display().to_string();
+    let output = run("2026-03-07T08:00:00", &["--", &missing]);
+    assert_eq!(output.status.code(), Some(3));
+    let input = input.display().to_string();
+    let output = run("2026-03-07T08:00:05", &[&input, "/MT:a.hex", "/MO:b.hex"]);
+    assert_eq!(output.status.code(), Some(6));
+
+    let contents = std::fs::read_to_string(&log).unwrap();
+    let lines: Vec<_> = contents.lines().collect();
+    assert_eq!(lines.len(), 3, "{contents}");
+    assert_eq!(lines[0], "earlier");
+    assert!(
+        lines[1].starts_with("2026-03-07 08:00:00 [E003] ") && lines[1].contains("missing.hex"),
+        "{contents}"
+    );
+    assert!(
+        lines[2].starts_with("2026-03-07 08:00:05 [E006] "),
+        "{contents}"
+    );
+
+    // /E still starts the log afresh.
+    let output = run_h3xy(&[
+        format!("/E:{}", log.display()),
+        "/S".to_string(),
+        "--".to_string(),
+        missing,
+    ]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(std::fs::read_to_string(&log).unwrap().lines().count(), 1);
+}
+
 #[test]
 fn test_cli_address_range_reduction() {
     let dir = temp_dir("cli_ar");
@@ -431,3 +802,32 @@ fn test_cli_address_range_multiple_ranges() {
     assert_eq!(segments[1].start_address, 0x2000);
     assert_eq!(segments[1].data, vec![0xBA, 0xBB]);
 }
+
+#[test]
+fn test_cli_address_range_overlapping_ranges_no_duplicates() {
+    let dir = temp_dir("cli_ar_overlap");
+    let input = dir.join("input.bin");
+    write_file(&input, &(0..0x300u32).map(|i| i as u8).collect::<Vec<_>>());
+
+    let run = |ar: &str, name: &str| {
+        let out = dir.join(name);
+        let args = vec![
+            format!("/IN:{};0x1000", input.display()),
+            ar.to_string(),
+            "/CS0:@append".to_string(),
+            "/XI".to_string(),
+            "-o".to_string(),
+            out.display().to_string(),
+        ];
+        run_hex_output(args, &out)
+    };
+
+    let overlapping = run("/AR:0x1000-0x10FF:0x1080-0x11FF", "overlap.hex");
+    let merged = run("/AR:0x1000-0x11FF", "merged.hex");
+    // 0x200 data bytes plus the appended 2-byte sum, each byte counted once.
+    let norm = overlapping.normalized().unwrap();
+    assert_eq!(norm.segments().len(), 1);
+    assert_eq!(norm.segments()[0].start_address, 0x1000);
+    assert_eq!(norm.segments()[0].len(), 0x202);
+    assert_eq!(norm, merged.normalized().unwrap());
+}
@@ -169,6 +169,128 @@ fn test_cli_log_file_invalid_command() {
     assert!(!output.status.success());
 }
 
+#[test]
+fn test_cli_log_file_extended_commands() {
+    let dir = temp_dir("cli_log_extended");
+    let fixture =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/log_commands.log");
+    std::fs::copy(&fixture, dir.join("commands.log")).unwrap();
+    let options = IntelHexWriteOptions::default();
+    let app = HexFile::with_segments(vec![Segment::new(0x1000, vec![0x00, 0x01, 0x02, 0x03])]);
+    let patch = HexFile::with_segments(vec![Segment::new(0x1000, vec![0xAA, 0xBB])]);
+    write_file(
+        &dir.join("app.hex"),
+        &write_intel_hex(&app, &options).unwrap(),
+    );
+    write_file(
+        &dir.join("patch.hex"),
+        &write_intel_hex(&patch, &options).unwrap(),
+    );
+
+    // Paths in the log are relative to the working directory.
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_h3xy"))
+        .current_dir(&dir)
+        .args(["/L:commands.log", "/XI", "-o", "out.hex"])
+        .output()
+        .unwrap();
+    assert_success(&output);
+
+    let data = [
+        0x00, 0x01, 0x02, 0x03, 0xAA, 0xBB, 0x5A, 0x5A, 0x5A, 0x5A, 0x5A, 0x5A,
+    ];
+    let crc = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC).checksum(&data);
+    let mut expected = data.to_vec();
+    expected.extend_from_slice(&crc.to_be_bytes());
+
+    assert_eq!(std::fs::read(dir.join("stage.bin")).unwrap(), expected);
+    let srec = h3xy::parse_srec(&std::fs::read(dir.join("stage one.s19")).unwrap()).unwrap();
+    let out = h3xy::parse_intel_hex(&std::fs::read(dir.join("out.hex")).unwrap()).unwrap();
+    for hexfile in [srec, out] {
+        assert_eq!(
+            hexfile.normalized_lossy().segments(),
+            &[Segment::new(0x1000, expected.clone())]
+        );
+    }
+}
+
+#[test]
+fn test_cli_logout_replays_to_identical_output() {
+    let dir = temp_dir("cli_logout");
+    let input = dir.join("input.hex");
+    let patch = dir.join("patch.hex");
+    let log = dir.join("run.log");
+    let first = dir.join("first.hex");
+    let replayed = dir.join("replayed.hex");
+    let options = IntelHexWriteOptions::default();
+    let app = HexFile::with_segments(vec![
+        Segment::new(0x1000, (0u8..0x40).collect()),
+        Segment::new(0x3000, vec![0x55; 8]),
+    ]);
+    let merge = HexFile::with_segments(vec![Segment::new(0x0, vec![0xC0, 0xDE])]);
+    write_file(&input, &write_intel_hex(&app, &options).unwrap());
+    write_file(&patch, &write_intel_hex(&merge, &options).unwrap());
+
+    // /FR without /FP fills with random bytes; the log must carry them.
+    let args = vec![
+        input.display().to_string(),
+        "/FR:0x1040-0x107F".to_string(),
+        "/CR:0x1010,0x10".to_string(),
+        format!("/MO:{};0x1020", patch.display()),
+        "/AR:0x1000-0x1FFF".to_string(),
+        "/CS9:@append".to_string(),
+        format!("/LOGOUT:{}", log.display()),
+        "/XI".to_string(),
+        "-o".to_string(),
+        first.display().to_string(),
+    ];
+    assert_success(&run_h3xy(&args));
+
+    let text = std::fs::read_to_string(&log).unwrap();
+    assert!(
+        text.starts_with(&format!("FileOpen {}\n", input.display())),
+        "{text}"
+    );
+    assert!(
+        text.contains("CreateChecksum 9 @append\n; /CS9 = "),
+        "{text}"
+    );
+
+    let args = vec![
+        input.display().to_string(),
+        format!("/L:{}", log.display()),
+        "/XI".to_string(),
+        "-o".to_string(),
+        replayed.display().to_string(),
+    ];
+    assert_success(&run_h3xy(&args));
+    assert_eq!(
+        std::fs::read(&replayed).unwrap(),
+        std::fs::read(&first).unwrap()
+    );
+}
+
+#[test]
+fn test_cli_logout_rejects_unrecordable_steps() {
+    let dir = temp_dir("cli_logout_align");
+    let input = dir.join("input.bin");
+    let log = dir.join("run.log");
+    write_file(&input, &[0x01, 0x02, 0x03]);
+
+    let args = vec![
+        format!("/IN:{};0x1000", input.display()),
+        "/AD:4".to_string(),
+        format!("/LOGOUT:{}", log.display()),
+        "/XI".to_string(),
+        "-o".to_string(),
+        dir.join("out.hex").display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("/IN, /AD cannot be recorded"), "{stderr}");
+    assert!(!log.exists());
+}
+
 #[test]
 fn test_cli_version_string_written_to_error_log() {
     let dir = temp_dir("cli_version_log");
@@ -223,7 +345,7 @@ fn test_cli_s08map_examples() {
         Segment::new(0x4000, vec![0xAA]),
         Segment::new(0x028000, vec![0xBB]),
     ]);
-    let data = write_intel_hex(&hexfile, &IntelHexWriteOptions::default());
+    let data = write_intel_hex(&hexfile, &IntelHexWriteOptions::default()).unwrap();
     write_file(&input, &data);
 
     let args = vec![
@@ -307,6 +429,58 @@ fn test_cli_dspic_clear_ghost() {
     );
 }
 
+#[test]
+fn test_cli_dspic_insert_ghost() {
+    let dir = temp_dir("cli_cdspi");
+    let input = dir.join("input.bin");
+    let out = dir.join("out.hex");
+    write_file(&input, &[0x01, 0x02, 0x03, 0x10, 0x11, 0x12]);
+
+    let args = vec![
+        format!("/IN:{};0x3000", input.display()),
+        "/CDSPI:0x3000,0x6;0xFF".to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+
+    let hexfile = run_hex_output(args, &out);
+    let out_bytes = hexfile.read_bytes_contiguous(0x3000, 8).unwrap();
+    assert_eq!(
+        out_bytes,
+        vec![0x01, 0x02, 0x03, 0xFF, 0x10, 0x11, 0x12, 0xFF]
+    );
+}
+
+#[test]
+fn test_cli_dspic_trailing_partial_word_is_an_error() {
+    let dir = temp_dir("cli_cdsp_partial");
+    let input = dir.join("input.bin");
+    let out = dir.join("out.hex");
+    write_file(&input, &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07]);
+
+    for option in [
+        "/CDSPX:0x3000,0x7",
+        "/CDSPS:0x3000,0x7",
+        "/CDSPI:0x3000,0x7",
+    ] {
+        let args = vec![
+            format!("/IN:{};0x3000", input.display()),
+            option.to_string(),
+            "/XI".to_string(),
+            "-o".to_string(),
+            out.display().to_string(),
+        ];
+        let output = run_h3xy(&args);
+        assert_eq!(output.status.code(), Some(4), "{option}");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("segment length 7 not a multiple"),
+            "{stderr}"
+        );
+    }
+}
+
 #[test]
 fn test_cli_hex_ascii_single_digit_tokens() {
     let dir = temp_dir("cli_hex_ascii_tokens");
@@ -510,8 +684,10 @@ fn test_cli_remap_basic() {
         &IntelHexWriteOptions {
             bytes_per_line: 16,
             mode: IntelHexMode::ExtendedLinear,
+            ..Default::default()
         },
-    );
+    )
+    .unwrap();
     write_file(&input, &data);
 
     let args = vec![
@@ -566,8 +742,10 @@ fn test_cli_s12map_basic() {
         &IntelHexWriteOptions {
             bytes_per_line: 16,
             mode: IntelHexMode::ExtendedLinear,
+            ..Default::default()
         },
-    );
+    )
+    .unwrap();
     write_file(&input, &data);
 
     let args = vec![
@@ -604,8 +782,10 @@ fn test_cli_s12xmap_basic() {
         &IntelHexWriteOptions {
             bytes_per_line: 16,
             mode: IntelHexMode::ExtendedLinear,
+            ..Default::default()
         },
-    );
+    )
+    .unwrap();
     write_file(&input, &data);
 
     let args = vec![
@@ -625,3 +805,459 @@ fn test_cli_s12xmap_basic() {
     assert_eq!(segments[2].start_address, 0x7F4000);
     assert_eq!(segments[3].start_address, 0x7FC000);
 }
+
+#[test]
+fn test_cli_s12map_custom_params_with_remap() {
+    let dir = temp_dir("cli_s12map_custom");
+    let input = dir.join("input.hex");
+    let out = dir.join("out.hex");
+    let hexfile = HexFile::with_segments(vec![
+        Segment::new(0x4000, vec![0xAA]),
+        Segment::new(0x208000, vec![0x01]),
+        Segment::new(0x218000, vec![0x02]),
+        Segment::new(0x21A000, vec![0x03]),
+    ]);
+    let data = write_intel_hex(
+        &hexfile,
+        &IntelHexWriteOptions {
+            bytes_per_line: 16,
+            mode: IntelHexMode::ExtendedLinear,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    write_file(&input, &data);
+
+    let args = vec![
+        input.display().to_string(),
+        "/s12map:0x20,0x8000,0x2000,0x400000".to_string(),
+        "/remap:0x4000-0x7FFF,0x3F0000,0x4000,0x4000".to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+
+    let hexfile = run_hex_output(args, &out);
+    let mut segments = hexfile.segments().to_vec();
+    segments.sort_by_key(|s| s.start_address);
+    assert_eq!(segments.len(), 4);
+    // Outside the 8 KiB window, so left in place.
+    assert_eq!(segments[0].start_address, 0x21A000);
+    assert_eq!(segments[1].start_address, 0x3F0000);
+    assert_eq!(segments[2].start_address, 0x400000);
+    assert_eq!(segments[3].start_address, 0x402000);
+}
+
+#[test]
+fn test_cli_map_table_fixture() {
+    let dir = temp_dir("cli_maptab");
+    let input = dir.join("input.hex");
+    let out = dir.join("out.hex");
+    let hexfile = HexFile::with_segments(vec![
+        Segment::new(0x0000, vec![0xAA]),
+        Segment::new(0x010000, vec![0x01, 0x02]),
+        Segment::new(0x030000, vec![0x03]),
+    ]);
+    let data = write_intel_hex(
+        &hexfile,
+        &IntelHexWriteOptions {
+            bytes_per_line: 16,
+            mode: IntelHexMode::ExtendedLinear,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    write_file(&input, &data);
+    let table =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/pic18_pages.maptab");
+
+    let args = vec![
+        input.display().to_string(),
+        format!("/MAPTAB:{}", table.display()),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+
+    let hexfile = run_hex_output(args, &out);
+    let segments: Vec<_> = hexfile
+        .segments()
+        .iter()
+        .map(|s| (s.start_address, s.data.to_vec()))
+        .collect();
+    assert_eq!(
+        segments,
+        [
+            (0x0000, vec![0xAA]),
+            (0x1000, vec![0x01, 0x02]),
+            (0x3000, vec![0x03]),
+        ]
+    );
+}
+
+#[test]
+fn test_cli_map_table_overlap_names_lines() {
+    let dir = temp_dir("cli_maptab_overlap");
+    let input = dir.join("input.bin");
+    let table = dir.join("pages.txt");
+    write_file(&input, &[0xAA]);
+    std::fs::write(
+        &table,
+        "0x10000-0x10FFF -> 0x1000\n# page 2\n0x20000-0x20FFF -> 0x1800\n",
+    )
+    .unwrap();
+
+    let args = vec![
+        format!("/IN:{};0x10000", input.display()),
+        format!("/MAPTAB:{}", table.display()),
+    ];
+    let output = run_h3xy(&args);
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("line 3: destination 0x1800-0x27FF overlaps line 1"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_cli_input_patterns_merge_in_sorted_order() {
+    let dir = temp_dir("cli_input_glob");
+    let options = IntelHexWriteOptions::default();
+    let hex = |segments| write_intel_hex(&HexFile::with_segments(segments), &options).unwrap();
+    write_file(
+        &dir.join("app.hex"),
+        &hex(vec![Segment::new(0x1000, vec![0x00, 0x01, 0x02, 0x03])]),
+    );
+    // cal_a sorts first, so it wins where the two calibration files overlap.
+    write_file(
+        &dir.join("cal_b.hex"),
+        &hex(vec![Segment::new(0x2000, vec![0xB0, 0xB1, 0xB2])]),
+    );
+    write_file(
+        &dir.join("cal_a.hex"),
+        &hex(vec![Segment::new(0x2001, vec![0xA1, 0xA2, 0xA3])]),
+    );
+    write_file(
+        &dir.join("patch.hex"),
+        &hex(vec![Segment::new(0x1001, vec![0xEE, 0xEE, 0xEE, 0xEE])]),
+    );
+    let out = dir.join("out.hex");
+
+    // Two positional inputs, the second a pattern: app.hex keeps its bytes.
+    let args = vec![
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+        "--".to_string(),
+        dir.join("app.hex").display().to_string(),
+        dir.join("cal_*.hex").display().to_string(),
+        dir.join("patch.hex").display().to_string(),
+    ];
+    let norm = run_hex_output(args, &out).normalized_lossy();
+    assert_eq!(
+        norm.read_bytes_contiguous(0x1000, 5).unwrap(),
+        vec![0x00, 0x01, 0x02, 0x03, 0xEE]
+    );
+    assert_eq!(
+        norm.read_bytes_contiguous(0x2000, 4).unwrap(),
+        vec![0xB0, 0xA1, 0xA2, 0xA3]
+    );
+
+    // /MO with a pattern merges every match over the input, in sorted order.
+    let args = vec![
+        format!("/MO:{}", dir.join("cal_?.hex").display()),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+        "--".to_string(),
+        dir.join("app.hex").display().to_string(),
+    ];
+    let norm = run_hex_output(args, &out).normalized_lossy();
+    assert_eq!(
+        norm.read_bytes_contiguous(0x2000, 4).unwrap(),
+        vec![0xB0, 0xB1, 0xB2, 0xA3]
+    );
+}
+
+#[test]
+fn test_cli_input_pattern_without_matches() {
+    let dir = temp_dir("cli_input_glob_none");
+    let pattern = dir.join("missing_*.hex").display().to_string();
+    let output = run_h3xy(&[
+        "/XI".to_string(),
+        "-o".to_string(),
+        dir.join("out.hex").display().to_string(),
+        "--".to_string(),
+        pattern.clone(),
+    ]);
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!("no files match {pattern}")),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_cli_random_fill_with_seed_is_reproducible() {
+    let dir = temp_dir("cli_fill_seed");
+    let input = dir.join("input.bin");
+    write_file(&input, &[0x11, 0x22]);
+
+    let run = |name: &str, fill: &[&str]| {
+        let out = dir.join(name);
+        let mut args = vec![
+            format!("/IN:{};0x1000", input.display()),
+            "/FR:0x1000-0x10FF:0x2000,0x40".to_string(),
+        ];
+        args.extend(fill.iter().map(|arg| arg.to_string()));
+        args.extend([
+            "/XI".to_string(),
+            "-o".to_string(),
+            out.display().to_string(),
+        ]);
+        assert_success(&run_h3xy(&args));
+        std::fs::read(out).unwrap()
+    };
+
+    let first = run("first.hex", &["/FRSEED:1234"]);
+    let second = run("second.hex", &["/FP:RANDOM", "/FRSEED:1234"]);
+    assert_eq!(first, second);
+    assert_ne!(run("other.hex", &["/FRSEED:0x4D2F"]), first);
+
+    let hexfile = h3xy::parse_intel_hex(&first).unwrap().normalized_lossy();
+    assert_eq!(
+        hexfile.read_bytes_contiguous(0x1000, 2).unwrap(),
+        [0x11, 0x22]
+    );
+    assert_eq!(hexfile.segments().len(), 2);
+}
+
+#[test]
+fn test_cli_fill_pattern_from_file_and_ramp() {
+    let dir = temp_dir("cli_fill_pattern_file");
+    let input = dir.join("input.bin");
+    let pattern_file = dir.join("pattern.bin");
+    let out = dir.join("out.hex");
+    write_file(&input, &[0x11, 0x22]);
+    // 3000 bytes, so the pattern does not line up with the 8 KiB range.
+    let pattern: Vec<u8> = (0..3000u32).map(|i| (i * 7 / 3) as u8).collect();
+    write_file(&pattern_file, &pattern);
+
+    let args = vec![
+        format!("/IN:{};0x2100", input.display()),
+        "/FR:0x2000,0x2000".to_string(),
+        format!("/FP:@{}", pattern_file.display()),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let norm = run_hex_output(args, &out).normalized_lossy();
+    let data = norm.read_bytes_contiguous(0x2000, 0x2000).unwrap();
+    for (offset, &byte) in data.iter().enumerate() {
+        let expected = match offset {
+            0x100 => 0x11,
+            0x101 => 0x22,
+            _ => pattern[offset % pattern.len()],
+        };
+        assert_eq!(byte, expected, "offset {offset:#X}");
+    }
+
+    let args = vec![
+        format!("/IN:{};0x2100", input.display()),
+        "/FR:0x20F0-0x210F".to_string(),
+        "/FP:INC:0xF8".to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let norm = run_hex_output(args, &out).normalized_lossy();
+    let data = norm.read_bytes_contiguous(0x20F0, 0x20).unwrap();
+    assert_eq!(
+        &data[..0x12],
+        &[
+            0xF8, 0xF9, 0xFA, 0xFB, 0xFC, 0xFD, 0xFE, 0xFF, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05,
+            0x06, 0x07, 0x11, 0x22,
+        ]
+    );
+    assert_eq!(data[0x1F], 0x17);
+
+    let empty = dir.join("empty.bin");
+    write_file(&empty, &[]);
+    let output = run_h3xy(&[
+        format!("/IN:{};0x2100", input.display()),
+        "/FR:0x2000,0x10".to_string(),
+        format!("/FP:@{}", empty.display()),
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("is empty"));
+}
+
+#[test]
+fn test_cli_stamps_are_covered_by_checksum() {
+    let dir = temp_dir("cli_stamps");
+    let input = dir.join("input.bin");
+    let out = dir.join("out.hex");
+    write_file(&input, &[0xFF; 0x20]);
+
+    let args = vec![
+        format!("/IN:{};0x1000", input.display()),
+        "/WS:0x1000;v1.2;NUL;WIDTH=8;FILL=20".to_string(),
+        "/WDATE:0x1008;BCD:YYMMDD".to_string(),
+        "/WDATE:0x100C;ASCII:YYYY-MM-DD".to_string(),
+        "/DATE:2026-03-07".to_string(),
+        "/CS0:@append".to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let norm = run_hex_output(args, &out).normalized_lossy();
+    let data = norm.read_bytes_contiguous(0x1000, 0x22).unwrap();
+    assert_eq!(&data[..8], b"v1.2\0   ");
+    assert_eq!(&data[8..11], &[0x26, 0x03, 0x07]);
+    assert_eq!(data[11], 0xFF);
+    assert_eq!(&data[12..22], b"2026-03-07");
+    let sum: u32 = data[..0x20].iter().map(|&b| b as u32).sum();
+    assert_eq!(&data[0x20..], &(sum as u16).to_be_bytes());
+
+    let args = vec![
+        format!("/IN:{};0x1000", input.display()),
+        "/WS:0x1000;v1.2.3;NUL;WIDTH=4".to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_cli_pipeline_file_example() {
+    let dir = temp_dir("cli_pipe");
+    let input = dir.join("input.bin");
+    let out = dir.join("out.hex");
+    write_file(&input, &[0x01, 0x02]);
+    let description =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/pipeline_variant.toml");
+
+    let args = vec![
+        format!("/IN:{};0x0", input.display()),
+        format!("/PIPE:{}", description.display()),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+
+    let hexfile = run_hex_output(args, &out);
+    assert_eq!(
+        hexfile.read_bytes_contiguous(0x100F, 6).unwrap(),
+        vec![0xFF, 0xDE, 0xAD, 0xBE, 0xEF, 0xFF]
+    );
+    // 0x01 + 0x02 + 252 * 0xFF + 0xDE + 0xAD + 0xBE + 0xEF
+    assert_eq!(
+        hexfile.read_bytes_contiguous(0x1100, 2).unwrap(),
+        vec![0xFE, 0x3F]
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_cli_pipeline_file_unknown_key() {
+    let dir = temp_dir("cli_pipe_unknown");
+    let input = dir.join("input.bin");
+    let description = dir.join("pipe.toml");
+    write_file(&input, &[0x01]);
+    std::fs::write(
+        &description,
+        "[[steps]]\nop = \"fill\"\nranges = \"0x0-0x3\"\npatern = \"00\"\n",
+    )
+    .unwrap();
+
+    let args = vec![
+        format!("/IN:{};0x0", input.display()),
+        format!("/PIPE:{}", description.display()),
+    ];
+    let output = run_h3xy(&args);
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("steps[0]"), "{stderr}");
+    assert!(stderr.contains("unknown field `patern`"), "{stderr}");
+}
+
+#[test]
+fn test_cli_bank_unbank_round_trip() {
+    let dir = temp_dir("cli_bank_round_trip");
+    let input = dir.join("linear.hex");
+    let banked = dir.join("banked.hex");
+    let linear = dir.join("back.hex");
+    let hexfile = HexFile::with_segments(vec![
+        Segment::new(0x0000, vec![0x02, 0x00, 0x30]),
+        Segment::new(0xFFFC, vec![0x11; 8]),
+        Segment::new(0x1A000, vec![0x22; 4]),
+    ]);
+    write_file(
+        &input,
+        &write_intel_hex(&hexfile, &IntelHexWriteOptions::default()).unwrap(),
+    );
+
+    let args = vec![
+        input.display().to_string(),
+        "/BANK:0x8000,0x8000,1".to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        banked.display().to_string(),
+    ];
+    let banked_image = run_hex_output(args, &banked);
+    let layout: Vec<_> = banked_image
+        .segments()
+        .iter()
+        .map(|s| (s.start_address, s.len()))
+        .collect();
+    assert_eq!(
+        layout,
+        [(0x0000, 3), (0x1FFFC, 4), (0x28000, 4), (0x3A000, 4)]
+    );
+    // One extended segment record per bank.
+    let text = std::fs::read_to_string(&banked).unwrap();
+    for record in [":020000021000EC", ":020000022000DC", ":020000023000CC"] {
+        assert!(text.contains(record), "{record} missing from:\n{text}");
+    }
+    assert!(!text.contains(":02000004"), "{text}");
+
+    let args = vec![
+        banked.display().to_string(),
+        "/UNBANK:0x8000,0x8000,1".to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        linear.display().to_string(),
+    ];
+    let round_trip = run_hex_output(args, &linear);
+    assert_eq!(round_trip.normalized_lossy(), hexfile.normalized_lossy());
+}
+
+#[test]
+fn test_cli_unbank_rejects_data_outside_window() {
+    let dir = temp_dir("cli_unbank_outside");
+    let input = dir.join("banked.hex");
+    let hexfile = HexFile::with_segments(vec![Segment::new(0x10100, vec![0x01])]);
+    write_file(
+        &input,
+        &write_intel_hex(&hexfile, &IntelHexWriteOptions::default()).unwrap(),
+    );
+
+    let output = run_h3xy(&[
+        input.display().to_string(),
+        "/UNBANK:0x8000,0x8000".to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        dir.join("out.hex").display().to_string(),
+    ]);
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("/UNBANK: address 0x10100 lies outside the bank window"),
+        "{stderr}"
+    );
+}
@@ -3,6 +3,7 @@ mod common_lines;
 
 use common::{assert_success, run_h3xy, temp_dir, write_file};
 use common_lines::read_nonempty_lines;
+use std::process::Command;
 
 fn parse_hex_pairs(line: &str) -> Vec<u8> {
     let mut out = Vec::new();
@@ -77,6 +78,46 @@ fn test_cli_hex_ascii_separator() {
     assert_eq!(parsed, data);
 }
 
+#[test]
+fn test_cli_hex_ascii_address_prefix_round_trip() {
+    let dir = temp_dir("cli_xa_addr");
+    let input = dir.join("input.hex");
+    let out = dir.join("out.txt");
+    let back = dir.join("back.hex");
+    let hexfile = h3xy::HexFile::with_segments(vec![
+        h3xy::Segment::new(0x1000, vec![0xAA, 0xBB, 0xCC]),
+        h3xy::Segment::new(0x2000, vec![0xDD]),
+    ]);
+    let options = h3xy::IntelHexWriteOptions::default();
+    write_file(&input, &h3xy::write_intel_hex(&hexfile, &options).unwrap());
+
+    let args = vec![
+        input.display().to_string(),
+        "/XA:2:' ':ADDR".to_string(),
+        "/LOWER".to_string(),
+        "/LF".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    assert_eq!(
+        std::fs::read_to_string(&out).unwrap(),
+        "@00001000: aa bb\n@00001002: cc\n@00002000: dd\n"
+    );
+
+    let args = vec![
+        format!("/IA:{}", out.display()),
+        "/XI".to_string(),
+        "-o".to_string(),
+        back.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    let parsed = h3xy::parse_intel_hex(&std::fs::read(&back).unwrap()).unwrap();
+    assert_eq!(parsed.segments(), hexfile.segments());
+}
+
 #[test]
 fn test_cli_hex_ascii_long_line() {
     let dir = temp_dir("cli_xa_long");
@@ -186,6 +227,22 @@ fn test_cli_intel_hex_forced_modes() {
     assert_success(&output);
     let text = std::fs::read_to_string(&out_segment).unwrap();
     assert!(text.contains(":02000002"));
+
+    // Segment records only reach 0xFFFFF.
+    std::fs::remove_file(&out_segment).unwrap();
+    let output = run_h3xy(&[
+        format!("/IN:{};0xFFFFF", input.display()),
+        "/XI:16:2".to_string(),
+        "-o".to_string(),
+        out_segment.display().to_string(),
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("exceeds ExtendedSegment limit 0xFFFFF"),
+        "{stderr}"
+    );
+    assert!(!out_segment.exists());
 }
 
 #[test]
@@ -313,6 +370,50 @@ fn test_cli_srec_reclen() {
     }
 }
 
+#[test]
+fn test_cli_srec_header_and_count_from_ini() {
+    let dir = temp_dir("cli_xs_header_ini");
+    let input = dir.join("input.bin");
+    let ini = dir.join("srec.ini");
+    let out = dir.join("out.s19");
+    write_file(&input, &[0x01, 0x02, 0x03]);
+    write_file(&ini, b"[SREC]\nHeader=HDR\n");
+
+    let args = vec![
+        format!("/IN:{};0x0", input.display()),
+        format!("/P:{}", ini.display()),
+        "/XS:2".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+
+    let lines = read_nonempty_lines(&out);
+    assert_eq!(
+        lines,
+        vec![
+            "S00600004844521B",
+            "S10500000102F7",
+            "S104000203F6",
+            "S5030002FA",
+            "S9030000FC",
+        ]
+    );
+
+    let args = vec![
+        format!("/IN:{};0x0", input.display()),
+        "/XS:hdr=APP".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    let lines = read_nonempty_lines(&out);
+    assert!(lines[0].starts_with("S0060000415050"), "{}", lines[0]);
+    assert!(lines.iter().any(|line| line == "S5030001FB"));
+}
+
 #[test]
 fn test_cli_srec_rectype_requires_reclen() {
     let dir = temp_dir("cli_xs_rectype_reclen");
@@ -330,6 +431,82 @@ fn test_cli_srec_rectype_requires_reclen() {
     assert!(!output.status.success());
 }
 
+#[test]
+fn test_cli_srec_address_overflow_leaves_no_output() {
+    let dir = temp_dir("cli_xs_overflow");
+    let input = dir.join("input.bin");
+    let out = dir.join("out.s19");
+    write_file(&input, &[0xAA, 0xBB]);
+
+    let args = vec![
+        format!("/IN:{};0x10000", input.display()),
+        "/XS:16:0".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+    assert!(!out.exists());
+}
+
+#[test]
+fn test_cli_srec_lf_lowercase() {
+    let dir = temp_dir("cli_xs_lf_lower");
+    let input = dir.join("input.bin");
+    let out = dir.join("out.s19");
+    write_file(&input, &[0xAB, 0xCD]);
+
+    let args = vec![
+        format!("/IN:{};0xABCD", input.display()),
+        "/XS".to_string(),
+        "/LF".to_string(),
+        "/LOWER".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+
+    let text = std::fs::read_to_string(&out).unwrap();
+    assert!(!text.contains('\r'));
+    assert!(text.starts_with("S105abcdabcd"), "{text}");
+
+    let hexfile = h3xy::parse_srec(text.as_bytes()).unwrap();
+    assert_eq!(hexfile.segments()[0].start_address, 0xABCD);
+    assert_eq!(hexfile.segments()[0].data, vec![0xAB, 0xCD]);
+}
+
+#[test]
+fn test_cli_align_records() {
+    let dir = temp_dir("cli_alignrec");
+    let input = dir.join("input.bin");
+    write_file(&input, &[0x5A; 0x20]);
+
+    for (format, name, prefix, addr) in [
+        ("/XI:0x10", "out.hex", ':', 3..7),
+        ("/XS:0x10", "out.s19", 'S', 4..8),
+    ] {
+        let out = dir.join(name);
+        let args = vec![
+            format!("/IN:{};0x1007", input.display()),
+            format.to_string(),
+            "/ALIGNREC".to_string(),
+            "-o".to_string(),
+            out.display().to_string(),
+        ];
+        let output = run_h3xy(&args);
+        assert_success(&output);
+
+        let text = std::fs::read_to_string(&out).unwrap();
+        let addresses: Vec<&str> = text
+            .lines()
+            .filter(|l| l.starts_with(prefix) && l.len() > 20)
+            .map(|l| &l[addr.clone()])
+            .collect();
+        assert_eq!(addresses, vec!["1007", "1010", "1020"], "{text}");
+    }
+}
+
 #[test]
 fn test_cli_intel_hex_rectype_requires_reclen() {
     let dir = temp_dir("cli_xi_rectype_reclen");
@@ -386,17 +563,23 @@ fn test_cli_binary_and_separate_binary() {
     write_file(&base, &[0x01, 0x02]);
     write_file(&merge, &[0x03, 0x04]);
 
-    let args = vec![
-        format!("/IN:{};0x1000", base.display()),
-        format!("/MO:{};0x2000", merge.display()),
-        "/XN".to_string(),
-        "-o".to_string(),
-        out.display().to_string(),
-    ];
-    let output = run_h3xy(&args);
-    assert_success(&output);
-    let data = std::fs::read(&out).unwrap();
-    assert_eq!(data, vec![0x01, 0x02, 0x03, 0x04]);
+    let run = |format: &str| {
+        let output = run_h3xy(&[
+            format!("/IN:{};0x1000", base.display()),
+            format!("/MO:{};0x2000", merge.display()),
+            format.to_string(),
+            "-o".to_string(),
+            out.display().to_string(),
+        ]);
+        assert_success(&output);
+        std::fs::read(&out).unwrap()
+    };
+    let data = run("/XN");
+    assert_eq!(data.len(), 0x1002);
+    assert_eq!(&data[..2], &[0x01, 0x02]);
+    assert!(data[2..0x1000].iter().all(|&b| b == 0xFF));
+    assert_eq!(&data[0x1000..], &[0x03, 0x04]);
+    assert_eq!(run("/XN:RAW"), vec![0x01, 0x02, 0x03, 0x04]);
 
     let out_sep = dir.join("sep.bin");
     let args = vec![
@@ -430,25 +613,372 @@ fn test_cli_binary_and_separate_binary() {
 }
 
 #[test]
-fn test_cli_binary_address_order() {
-    let dir = temp_dir("cli_xn_order");
+fn test_cli_binary_image_window_and_fill() {
+    let dir = temp_dir("cli_xn_image");
     let base = dir.join("base.bin");
     let merge = dir.join("merge.bin");
     let out = dir.join("out.bin");
-    write_file(&base, &[0x01]);
-    write_file(&merge, &[0x02]);
+    write_file(&base, &[0x01, 0x02]);
+    write_file(&merge, &[0x03]);
 
     let args = vec![
-        format!("/IN:{};0x2000", base.display()),
-        format!("/MO:{};0x1000", merge.display()),
-        "/XN".to_string(),
+        format!("/IN:{};0x1000", base.display()),
+        format!("/MO:{};0x1003", merge.display()),
+        "/XN:0x0FFF,0x1004,0x00".to_string(),
         "-o".to_string(),
         out.display().to_string(),
     ];
     let output = run_h3xy(&args);
     assert_success(&output);
     let data = std::fs::read(&out).unwrap();
-    assert_eq!(data, vec![0x02, 0x01]);
+    assert_eq!(data, vec![0x00, 0x01, 0x02, 0x00, 0x03, 0x00]);
+
+    let args = vec![
+        format!("/IN:{};0x1000", base.display()),
+        format!("/MO:{};0x1003", merge.display()),
+        "/XN:,,0xEE".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    let data = std::fs::read(&out).unwrap();
+    assert_eq!(data, vec![0x01, 0x02, 0xEE, 0x03]);
+}
+
+#[test]
+fn test_cli_binary_refuses_huge_implicit_span() {
+    let dir = temp_dir("cli_xn_span");
+    let input = dir.join("input.hex");
+    let out = dir.join("out.bin");
+    let hexfile = h3xy::HexFile::with_segments(vec![
+        h3xy::Segment::new(0x0, vec![0xAA]),
+        h3xy::Segment::new(0xFFFF_FFFF, vec![0xBB]),
+    ]);
+    let options = h3xy::IntelHexWriteOptions::default();
+    write_file(&input, &h3xy::write_intel_hex(&hexfile, &options).unwrap());
+
+    let output = run_h3xy(&[
+        input.display().to_string(),
+        "/XN".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ]);
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("binary image 0x0-0xFFFFFFFF is 4294967296 bytes"),
+        "{stderr}"
+    );
+    assert!(!out.exists());
+}
+
+#[test]
+fn test_cli_csv_segment_report() {
+    let dir = temp_dir("cli_xcsv");
+    let input = dir.join("input.hex");
+    let out = dir.join("regions.csv");
+    let hexfile = h3xy::HexFile::with_segments(vec![
+        h3xy::Segment::new(0x1000, b"123456789".to_vec()),
+        h3xy::Segment::new(0x8000, vec![0x55; 0x40]),
+    ]);
+    let options = h3xy::IntelHexWriteOptions::default();
+    write_file(&input, &h3xy::write_intel_hex(&hexfile, &options).unwrap());
+
+    let args = vec![
+        input.display().to_string(),
+        "/XCSV".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+
+    let rows: Vec<Vec<String>> = read_nonempty_lines(&out)
+        .iter()
+        .map(|line| {
+            line.split(',')
+                .map(|field| {
+                    assert!(field.starts_with('"') && field.ends_with('"'), "{line}");
+                    field.trim_matches('"').to_string()
+                })
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        rows[0],
+        vec!["address_start", "address_end", "length", "crc32"]
+    );
+    let report = hexfile.segment_report();
+    assert_eq!(rows.len(), report.len() + 2);
+    for (row, info) in rows[1..].iter().zip(&report) {
+        let hex = |field: &str| u32::from_str_radix(field.trim_start_matches("0x"), 16).unwrap();
+        assert_eq!(hex(&row[0]), info.start_address);
+        assert_eq!(hex(&row[1]), info.end_address);
+        assert_eq!(row[2].parse::<usize>().unwrap(), info.length);
+        assert_eq!(hex(&row[3]), info.crc32);
+    }
+    assert_eq!(rows[1][3], "0xCBF43926");
+    let total = rows.last().unwrap();
+    assert_eq!(total[0], "TOTAL");
+    assert_eq!(total[2].parse::<usize>().unwrap(), hexfile.total_bytes());
+}
+
+#[test]
+fn test_cli_labels_name_separate_binaries_and_csv_rows() {
+    let dir = temp_dir("cli_label");
+    let input = dir.join("in.bin");
+    write_file(&input, &[0xAA; 0x40]);
+    let run = |extra: &[&str], out: &std::path::Path| {
+        let mut args = vec![
+            format!("/IN:{};0x1000", input.display()),
+            "/LABEL:0x1000-0x100F;boot".to_string(),
+            "/LABEL:0x1010-0x103F;cal".to_string(),
+        ];
+        args.extend(extra.iter().map(|s| s.to_string()));
+        args.extend(["-o".to_string(), out.display().to_string()]);
+        assert_success(&run_h3xy(&args));
+    };
+
+    // Cutting the middle of "cal" leaves two pieces that both keep the label.
+    run(&["/CR:0x1020-0x102F", "/XSB"], &dir.join("out.bin"));
+    assert_eq!(std::fs::read(dir.join("out_boot.bin")).unwrap().len(), 0x10);
+    assert_eq!(
+        std::fs::read(dir.join("out_cal_1010.bin")).unwrap().len(),
+        0x10
+    );
+    assert_eq!(
+        std::fs::read(dir.join("out_cal_1030.bin")).unwrap().len(),
+        0x10
+    );
+    assert!(!dir.join("out_1000.bin").exists());
+
+    let csv = dir.join("out.csv");
+    run(&["/XCSV"], &csv);
+    let lines = read_nonempty_lines(&csv);
+    assert!(lines[0].ends_with(",\"label\""), "{}", lines[0]);
+    assert!(
+        lines[1].starts_with("\"0x00001000\",\"0x0000100F\""),
+        "{}",
+        lines[1]
+    );
+    assert!(lines[1].ends_with(",\"boot\""), "{}", lines[1]);
+    assert!(lines[2].ends_with(",\"cal\""), "{}", lines[2]);
+    assert!(lines[3].starts_with("\"TOTAL\""), "{}", lines[3]);
+}
+
+#[test]
+fn test_cli_separate_binary_name_template() {
+    let dir = temp_dir("cli_xsb_template");
+    let base = dir.join("base.bin");
+    let merge = dir.join("merge.bin");
+    write_file(&base, &[0x01, 0x02]);
+    write_file(&merge, &[0x03]);
+    let run = |template: &str| {
+        run_h3xy(&[
+            format!("/IN:{};0x8000", base.display()),
+            format!("/MO:{};0x1000", merge.display()),
+            format!("/XSB:\"{template}\""),
+            "-o".to_string(),
+            dir.join("img.bin").display().to_string(),
+        ])
+    };
+
+    assert_success(&run("{stem}_{index:02}_{addr:08X}.{ext}"));
+    assert_eq!(
+        std::fs::read(dir.join("img_00_00001000.bin")).unwrap(),
+        vec![0x03]
+    );
+    assert_eq!(
+        std::fs::read(dir.join("img_01_00008000.bin")).unwrap(),
+        vec![0x01, 0x02]
+    );
+
+    let output = run("{stem}.{ext}");
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("blocks at 0x1000 and 0x8000 would both be written to 'img.bin'"),
+        "{stderr}"
+    );
+    assert!(!dir.join("img.bin").exists());
+
+    let output = run("{stem}_{address}.{ext}");
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("unknown placeholder {address} at column 8"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_cli_ford_erase_sectors_from_sector_map() {
+    let dir = temp_dir("cli_aemap");
+    let input = dir.join("in.hex");
+    let map = dir.join("flash.sec");
+    let out = dir.join("out.hex");
+    // 4x16K, 1x64K, 7x128K.
+    std::fs::write(
+        &map,
+        "# ECU flash\n0x0,16K*4\n0x10000,64K\n0x20000,128K*7\n",
+    )
+    .unwrap();
+    let hexfile = h3xy::HexFile::with_segments(vec![
+        h3xy::Segment::new(0x0100, vec![0x11; 0x10]),
+        h3xy::Segment::new(0x3FF0, vec![0x22; 0x20]),
+        h3xy::Segment::new(0xFFF0, vec![0x33; 0x20]),
+        h3xy::Segment::new(0x60000, vec![0x44; 4]),
+        h3xy::Segment::new(0x80000, vec![0x55; 4]),
+        h3xy::Segment::new(0xE0000, vec![0x66; 4]),
+    ]);
+    let options = h3xy::IntelHexWriteOptions::default();
+    write_file(&input, &h3xy::write_intel_hex(&hexfile, &options).unwrap());
+
+    let run = |extra: &[String]| {
+        let mut args = vec![
+            input.display().to_string(),
+            format!("/AEMAP:{}", map.display()),
+            "/AE:0x100".to_string(),
+            "/XF:RELAXED".to_string(),
+            "/DATE:2026-03-07".to_string(),
+        ];
+        args.extend_from_slice(extra);
+        args.extend(["-o".to_string(), out.display().to_string()]);
+        run_h3xy(&args)
+    };
+
+    assert_success(&run(&[]));
+    let lines = read_nonempty_lines(&out);
+    assert!(
+        lines.contains(
+            &"SECTORS>:0x0,0x8000:0xC000,0x14000:0x60000,0x40000:0xE0000,0x20000".to_string()
+        ),
+        "{lines:?}"
+    );
+
+    assert_success(&run(&[
+        "/FORD:flash_erase_sectors=:0x0,0x100000".to_string()
+    ]));
+    assert!(read_nonempty_lines(&out).contains(&"SECTORS>:0x0,0x100000".to_string()));
+
+    std::fs::write(&map, "0x0,16K*4\n").unwrap();
+    let output = run(&[]);
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("address 0x10000 lies outside every sector"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_cli_ini_variables_from_environment_and_def() {
+    let dir = temp_dir("cli_ini_vars");
+    let input = dir.join("in.hex");
+    let ini = dir.join("ford.ini");
+    let out = dir.join("out.hex");
+    write_file(&input, b":0400000001020304F2\n:00000001FF\n");
+    std::fs::write(
+        &ini,
+        "[FORDHEADER]\nMODULE ID = 0x${BUILD_ID}\nCOMMENTS = \"cost $$${COST}\"\n",
+    )
+    .unwrap();
+
+    let run = |extra: &[&str]| {
+        let mut args = vec![
+            input.display().to_string(),
+            format!("/P:{}", ini.display()),
+            "/XF:RELAXED".to_string(),
+            "/DATE:2026-03-07".to_string(),
+        ];
+        args.extend(extra.iter().map(|arg| arg.to_string()));
+        args.extend(["-o".to_string(), out.display().to_string()]);
+        let mut command = Command::new(env!("CARGO_BIN_EXE_h3xy"));
+        command
+            .args(&args)
+            .env("BUILD_ID", "7E0")
+            .env_remove("COST");
+        command.output().unwrap()
+    };
+
+    assert_success(&run(&["/DEF:COST=5"]));
+    let lines = read_nonempty_lines(&out);
+    assert!(lines.contains(&"MODULE ID>0x7E0".to_string()), "{lines:?}");
+    assert!(lines.contains(&"COMMENTS>cost $5".to_string()), "{lines:?}");
+
+    // /DEF wins over the environment.
+    assert_success(&run(&["/DEF:COST=5", "/DEF:BUILD_ID=123"]));
+    assert!(read_nonempty_lines(&out).contains(&"MODULE ID>0x123".to_string()));
+
+    let output = run(&[]);
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("undefined variables"), "{stderr}");
+    assert!(stderr.contains(": COST"), "{stderr}");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_cli_json_export_and_import() {
+    let dir = temp_dir("cli_xj");
+    let base = dir.join("base.bin");
+    let json = dir.join("out.json");
+    let back = dir.join("back.hex");
+    write_file(&base, &[0x01, 0x02, 0x03]);
+
+    let args = vec![
+        format!("/IN:{};0x1000", base.display()),
+        "/XJ".to_string(),
+        "-o".to_string(),
+        json.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    let text = std::fs::read_to_string(&json).unwrap();
+    assert!(text.contains("\"total_bytes\": 3"), "{text}");
+    assert!(text.contains("\"data\": \"010203\""), "{text}");
+
+    let args = vec![
+        json.display().to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        back.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    let lines = read_nonempty_lines(&back);
+    assert_eq!(lines, vec![":03100000010203E7", ":00000001FF"]);
+}
+
+#[test]
+fn test_cli_binary_address_order() {
+    let dir = temp_dir("cli_xn_order");
+    let base = dir.join("base.bin");
+    let merge = dir.join("merge.bin");
+    let out = dir.join("out.bin");
+    write_file(&base, &[0x01]);
+    write_file(&merge, &[0x02]);
+
+    let run = |format: &str| {
+        let output = run_h3xy(&[
+            format!("/IN:{};0x2000", base.display()),
+            format!("/MO:{};0x1000", merge.display()),
+            format.to_string(),
+            "-o".to_string(),
+            out.display().to_string(),
+        ]);
+        assert_success(&output);
+        std::fs::read(&out).unwrap()
+    };
+    // The merged block sits below the input: /XN still starts at 0x1000.
+    let data = run("/XN");
+    assert_eq!(data.len(), 0x1001);
+    assert_eq!(data[0], 0x02);
+    assert!(data[1..0x1000].iter().all(|&b| b == 0xFF));
+    assert_eq!(data[0x1000], 0x01);
+    // /XN:RAW writes the segments as /MO left them, without the gap.
+    assert_eq!(run("/XN:RAW"), vec![0x02, 0x01]);
 }
 
 #[test]
@@ -468,3 +998,627 @@ fn test_cli_output_option_exclusive() {
     let output = run_h3xy(&args);
     assert!(!output.status.success());
 }
+
+#[test]
+fn test_cli_vbf_output_uses_ini_header() {
+    let dir = temp_dir("cli_xvbf");
+    let input = dir.join("input.bin");
+    let ini = dir.join("vbf.ini");
+    let out = dir.join("out.vbf");
+    write_file(&input, &[0x01, 0x02, 0x03]);
+    write_file(
+        &ini,
+        b"[VBF]\nsw_part_number=31400123\nsw_version=AB\necu_address=0x7A1\nsw_part_type=DATA\n",
+    );
+
+    let args = vec![
+        format!("/IN:{};0x8010", input.display()),
+        format!("/P:{}", ini.display()),
+        "/AE:0x100".to_string(),
+        "/XVBF".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+
+    let data = std::fs::read(&out).unwrap();
+    let header_end = data.windows(2).position(|w| w == b"\n}").unwrap() + 1;
+    let header = String::from_utf8_lossy(&data[..header_end]);
+    assert!(header.contains("sw_part_number = \"31400123\";"));
+    assert!(header.contains("sw_part_type = DATA;"));
+    assert!(header.contains("ecu_address = 0x7A1;"));
+    assert!(header.contains("erase = { { 0x00008000, 0x00000100 } };"));
+    // Header ends with "}" and is followed directly by the single data block.
+    assert_eq!(
+        &data[header_end + 1..],
+        &[
+            0x00, 0x00, 0x80, 0x10, 0x00, 0x00, 0x00, 0x03, 0x01, 0x02, 0x03, 0xAD, 0xAD
+        ]
+    );
+}
+
+#[test]
+fn test_cli_vbf_output_requires_part_number() {
+    let dir = temp_dir("cli_xvbf_missing");
+    let input = dir.join("input.bin");
+    let ini = dir.join("vbf.ini");
+    let out = dir.join("out.vbf");
+    write_file(&input, &[0x01]);
+    write_file(&ini, b"ecu_address=0x7A1\n");
+
+    let args = vec![
+        format!("/IN:{};0x0", input.display()),
+        format!("/P:{}", ini.display()),
+        "/XVBF".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("sw_part_number"), "{stderr}");
+}
+
+#[test]
+fn test_cli_vbf_input_round_trip() {
+    let dir = temp_dir("cli_vbf_input");
+    let input = dir.join("input.bin");
+    let ini = dir.join("vbf.ini");
+    let vbf = dir.join("image.vbf");
+    let out = dir.join("out.bin");
+    write_file(&input, &[0x0A, 0x20, 0x7D, 0x00]);
+    write_file(&ini, b"sw_part_number=31400123\necu_address=0x7A1\n");
+
+    let args = vec![
+        format!("/IN:{};0x1000", input.display()),
+        format!("/P:{}", ini.display()),
+        "/XVBF".to_string(),
+        "-o".to_string(),
+        vbf.display().to_string(),
+    ];
+    assert_success(&run_h3xy(&args));
+
+    let args = vec![
+        vbf.display().to_string(),
+        "/XN".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    assert_success(&run_h3xy(&args));
+    assert_eq!(std::fs::read(&out).unwrap(), vec![0x0A, 0x20, 0x7D, 0x00]);
+}
+
+#[test]
+fn test_cli_gm_header_basic_layout() {
+    let dir = temp_dir("cli_xg");
+    let input = dir.join("input.bin");
+    let ini = dir.join("gm.ini");
+    let out = dir.join("out.hex");
+    write_file(&input, &[0x01, 0x02, 0x03, 0x04]);
+    write_file(&ini, b"[GMHEADER]\nModuleId=0x0005\n");
+
+    let args = vec![
+        format!("/IN:{};0x1010", input.display()),
+        format!("/P:{}", ini.display()),
+        "/XG".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    assert_success(&run_h3xy(&args));
+    assert_eq!(
+        std::fs::read_to_string(&out).unwrap(),
+        ":101004000033000500001010000000040102030476\r\n:00000001FF\r\n"
+    );
+}
+
+#[test]
+fn test_cli_gm_header_os_requires_part_number() {
+    let dir = temp_dir("cli_xgc_missing");
+    let input = dir.join("input.bin");
+    let ini = dir.join("gm.ini");
+    let out = dir.join("out.hex");
+    write_file(&input, &[0x01]);
+    write_file(&ini, b"[GMHEADER]\nModuleId=1\nDLS=AA\n");
+
+    let args = vec![
+        format!("/IN:{};0x1000", input.display()),
+        format!("/P:{}", ini.display()),
+        "/XGC:0x0".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("partnumber"), "{stderr}");
+}
+
+#[test]
+fn test_cli_gac_swil_default_output_path() {
+    let dir = temp_dir("cli_xgacswil");
+    let input = dir.join("input.hex");
+    let ini = dir.join("gac.ini");
+    // Three bytes at 0x1000.
+    write_file(&input, b":0310000011223387\n:00000001FF\n");
+    write_file(&ini, b"[GAC]\nPartNumber=PN1\nSwVersion=V2\n");
+
+    let args = vec![
+        input.display().to_string(),
+        format!("/P:{}", ini.display()),
+        "/XGACSWIL".to_string(),
+        "/INPLACE".to_string(),
+    ];
+    assert_success(&run_h3xy(&args));
+
+    let mut expected = b"PN1".to_vec();
+    expected.resize(16, 0);
+    expected.extend_from_slice(b"V2\0\0\0\0\0\0");
+    expected.extend_from_slice(&[0x00, 0x01]);
+    expected.extend_from_slice(&[0, 0, 0, 4, 0, 0, 0x10, 0x00]);
+    expected.extend_from_slice(&[0x22, 0x11, 0xFF, 0x33]);
+    assert_eq!(std::fs::read(dir.join("input.gac")).unwrap(), expected);
+}
+
+#[test]
+fn test_cli_vag_output_blocks_and_trailer() {
+    let dir = temp_dir("cli_xv");
+    let input = dir.join("input.bin");
+    let ini = dir.join("vag.ini");
+    let out = dir.join("out.bin");
+    write_file(&input, &[0x01, 0x02, 0x03]);
+    write_file(&ini, b"[VAG]\nChecksum=0\nBlockSize=0x2\n");
+
+    let args = vec![
+        format!("/IN:{};0x1000", input.display()),
+        format!("/P:{}", ini.display()),
+        "/XV".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    assert_success(&run_h3xy(&args));
+
+    let data = std::fs::read(&out).unwrap();
+    assert_eq!(
+        &data[..12],
+        &[0, 0, 0x10, 0x00, 0, 0, 0, 2, 0x01, 0x02, 0x00, 0x03]
+    );
+    assert_eq!(
+        &data[12..23],
+        &[0, 0, 0x10, 0x02, 0, 0, 0, 1, 0x03, 0x00, 0x03]
+    );
+    assert_eq!(&data[23..27], &[0, 0, 0, 2]);
+}
+
+#[test]
+fn test_cli_vag_output_requires_checksum_key() {
+    let dir = temp_dir("cli_xv_missing");
+    let input = dir.join("input.bin");
+    let ini = dir.join("vag.ini");
+    let out = dir.join("out.bin");
+    write_file(&input, &[0x01]);
+    write_file(&ini, b"[VAG]\nBlockSize=0x100\n");
+
+    let args = vec![
+        format!("/IN:{};0x0", input.display()),
+        format!("/P:{}", ini.display()),
+        "/XV".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("missing [VAG] Checksum"), "{stderr}");
+}
+
+#[test]
+fn test_cli_fiat_binary_with_ini_alignment() {
+    let dir = temp_dir("cli_xb");
+    let input = dir.join("input.bin");
+    let ini = dir.join("fiat.ini");
+    let out = dir.join("out.bin");
+    write_file(&input, &[0x01, 0x02, 0x03]);
+    write_file(&ini, b"Alignment=4\n");
+
+    let args = vec![
+        format!("/IN:{};0x400", input.display()),
+        format!("/P:{}", ini.display()),
+        "/XB".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    assert_success(&run_h3xy(&args));
+    assert_eq!(
+        std::fs::read(&out).unwrap(),
+        vec![
+            0, 0, 0x04, 0x00, 0, 0, 0, 4, 0x01, 0x02, 0x03, 0xFF, 0x01, 0x05
+        ]
+    );
+}
+
+#[test]
+fn test_cli_flash_kernel_single_segment() {
+    let dir = temp_dir("cli_xk");
+    let input = dir.join("input.hex");
+    let out = dir.join("out.bin");
+    write_file(&input, b":021000000102EB\n:00000001FF\n");
+
+    let args = vec![
+        input.display().to_string(),
+        "/XK".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    assert_success(&run_h3xy(&args));
+    assert_eq!(
+        std::fs::read(&out).unwrap(),
+        vec![2, 0, 0, 0, 0x00, 0x10, 0, 0, 0x01, 0x02]
+    );
+
+    write_file(&input, b":021000000102EB\n:01200000AA35\n:00000001FF\n");
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("one segment"), "{stderr}");
+}
+
+#[test]
+fn test_cli_entry_point_from_input_and_override() {
+    let dir = temp_dir("cli_entry_point");
+    let input = dir.join("input.s19");
+    let out = dir.join("out.hex");
+    // S3 data at 0x08000000, S7 entry 0x08000400.
+    write_file(&input, b"S30608000000AA47\r\nS70508000400EE\r\n");
+
+    let args = vec![
+        input.display().to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    let lines = read_nonempty_lines(&out);
+    assert_eq!(lines[lines.len() - 2], ":0400000508000400EB");
+
+    let out = dir.join("out.s37");
+    let args = vec![
+        input.display().to_string(),
+        "/ENTRY:0x08000100".to_string(),
+        "/XS".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    let lines = read_nonempty_lines(&out);
+    assert_eq!(lines.last().unwrap(), "S70508000100F1");
+}
+
+#[test]
+fn test_cli_srec_metadata_survives_conversion() {
+    let dir = temp_dir("cli_srec_metadata");
+    let input = dir.join("input.s19");
+    let out = dir.join("out.s19");
+    write_file(
+        &input,
+        b"S00700004F4F4254C4\r\nS10500000102F7\r\nS5030001FB\r\nS9030100FB\r\n",
+    );
+
+    let args = vec![
+        input.display().to_string(),
+        "/XS".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    let lines = read_nonempty_lines(&out);
+    assert_eq!(
+        lines,
+        vec![
+            "S00700004F4F4254C4",
+            "S10500000102F7",
+            "S5030001FB",
+            "S9030100FB",
+        ]
+    );
+
+    let args = vec![
+        input.display().to_string(),
+        "/XS:hdr=BOOT".to_string(),
+        "/ENTRY:0x200".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    let lines = read_nonempty_lines(&out);
+    assert_eq!(lines[0], "S0070000424F4F54C4");
+    assert_eq!(lines.last().unwrap(), "S9030200FA");
+}
+
+#[test]
+fn test_cli_hex_dump_output() {
+    let dir = temp_dir("cli_xd");
+    let input = dir.join("input.bin");
+    let out = dir.join("out.txt");
+    write_file(&input, b"h3xy\x00\x01");
+
+    let args = vec![
+        format!("/IN:{};0x100", input.display()),
+        "/XD:4".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+
+    let lines = read_nonempty_lines(&out);
+    assert_eq!(
+        lines,
+        vec![
+            "00000100: 68 33 78 79  |h3xy|",
+            "00000104: 00 01        |..|",
+        ]
+    );
+}
+
+#[test]
+fn test_cli_c_code_per_segment_descriptor_table() {
+    let dir = temp_dir("cli_xc_blocks");
+    let input = dir.join("input.bin");
+    let ini = dir.join("c.ini");
+    let out = dir.join("drv.c");
+    write_file(&input, &[0x01, 0x02]);
+    write_file(
+        &ini,
+        b"[CCODE]\nPrefix=flash-drv\nPerSegment=1\nDescriptorTable=1\n",
+    );
+
+    let args = vec![
+        format!("/IN:{};0x1000", input.display()),
+        format!("/P:{}", ini.display()),
+        "/XC".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+
+    let c = std::fs::read_to_string(&out).unwrap();
+    let h = std::fs::read_to_string(dir.join("drv.h")).unwrap();
+    assert!(c.contains("const uint8_t flash_drv_block0[] = {"), "{c}");
+    assert!(
+        c.contains("const flash_drv_block_t flash_drv_blocks[FLASH_DRV_BLOCK_COUNT] = {"),
+        "{c}"
+    );
+    assert!(
+        h.contains("extern const uint8_t flash_drv_block0[];"),
+        "{h}"
+    );
+}
+
+#[test]
+fn test_cli_c_code_qualifier_and_layout_from_ini() {
+    let dir = temp_dir("cli_xc_layout");
+    let input = dir.join("input.bin");
+    let ini = dir.join("c.ini");
+    let out = dir.join("drv.c");
+    write_file(&input, &[0xAB, 0xCD, 0xEF]);
+    write_file(
+        &ini,
+        b"[CCODE]\nPrefix=drv\nQualifier=__flash\nBytesPerLine=2\nUppercaseHex=0\n",
+    );
+
+    let args = vec![
+        format!("/IN:{};0x0", input.display()),
+        format!("/P:{}", ini.display()),
+        "/XC".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+
+    let c = std::fs::read_to_string(&out).unwrap();
+    assert!(
+        c.contains("const __flash uint8_t drvBlk0[] = {\n    0xab, 0xcd,\n    0xef\n};"),
+        "{c}"
+    );
+}
+
+#[test]
+fn test_cli_export_without_output_file_fails_early() {
+    let dir = temp_dir("cli_missing_o");
+    let input = dir.join("input.bin");
+    let log = dir.join("run.log");
+    write_file(&input, &[0x01, 0x02]);
+
+    for format in ["/XI", "/XN", "/XC", "/XF", "/XP"] {
+        let output = run_h3xy(&[
+            format!("/IN:{};0x1000", input.display()),
+            format!("/LOGOUT:{}", log.display()),
+            format.to_string(),
+        ]);
+        assert_eq!(output.status.code(), Some(2), "{format}");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains(&format!("output file required for {format} ")),
+            "{stderr}"
+        );
+        // Nothing ran: not even the log was written, and no path was derived.
+        assert!(!log.exists());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    }
+}
+
+#[test]
+fn test_cli_in_place_derives_output_from_input() {
+    let dir = temp_dir("cli_in_place");
+    let input = dir.join("input.bin");
+    write_file(&input, &[0x01, 0x02]);
+
+    for flag in ["/INPLACE", "--in-place"] {
+        let output = run_h3xy(&[
+            format!("/IN:{};0x1000", input.display()),
+            "-o".to_string(),
+            dir.join("explicit.bin").display().to_string(),
+            "/XP".to_string(),
+            "/XI".to_string(),
+            flag.to_string(),
+        ]);
+        assert_success(&output);
+        let hex = std::fs::read(dir.join("input.hex")).unwrap();
+        let hexfile = h3xy::parse_intel_hex(&hex).unwrap();
+        assert_eq!(hexfile.segments()[0].data, vec![0x01, 0x02]);
+        assert!(dir.join("explicit.bin").exists());
+        assert!(!dir.join("input.bin.bin").exists());
+        std::fs::remove_file(dir.join("input.hex")).unwrap();
+    }
+}
+
+#[test]
+fn test_cli_mkdir_creates_output_directories() {
+    let dir = temp_dir("cli_mkdir");
+    let input = dir.join("input.hex");
+    write_file(
+        &input,
+        b":020000000102FB\r\n:021000000304E7\r\n:00000001FF\r\n",
+    );
+
+    let hex_out = dir.join("a").join("b").join("out.hex");
+    let output = run_h3xy(&[
+        input.display().to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        hex_out.display().to_string(),
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&hex_out.display().to_string()), "{stderr}");
+    assert!(!dir.join("a").exists());
+
+    let output = run_h3xy(&[
+        input.display().to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        hex_out.display().to_string(),
+        "/MKDIR".to_string(),
+    ]);
+    assert_success(&output);
+    let hexfile = h3xy::parse_intel_hex(&std::fs::read(&hex_out).unwrap()).unwrap();
+    assert_eq!(hexfile.segments().len(), 2);
+
+    let sb_out = dir.join("c").join("d").join("part.bin");
+    let output = run_h3xy(&[
+        input.display().to_string(),
+        "/XSB".to_string(),
+        "-o".to_string(),
+        sb_out.display().to_string(),
+        "/MKDIR".to_string(),
+    ]);
+    assert_success(&output);
+    let parts = dir.join("c").join("d");
+    assert_eq!(
+        std::fs::read(parts.join("part_0.bin")).unwrap(),
+        [0x01, 0x02]
+    );
+    assert_eq!(
+        std::fs::read(parts.join("part_1000.bin")).unwrap(),
+        [0x03, 0x04]
+    );
+}
+
+mod rust_export {
+    include!("data/rust_export.rs");
+}
+
+#[test]
+fn test_cli_rust_code_matches_compiled_golden() {
+    let dir = temp_dir("cli_xr");
+    let input = dir.join("input.hex");
+    let ini = dir.join("rust.ini");
+    let out = dir.join("blocks.rs");
+    write_file(
+        &input,
+        b":050000000102030405EC\r\n:02100000AABB89\r\n:00000001FF\r\n",
+    );
+    write_file(&ini, b"[RUST]\nPrefix=flash_drv\nBytesPerLine=4\n");
+
+    let args = vec![
+        input.display().to_string(),
+        format!("/P:{}", ini.display()),
+        "/XR".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+
+    // The golden file is compiled into this test via `include!` above.
+    let golden = include_str!("data/rust_export.rs");
+    assert_eq!(std::fs::read_to_string(&out).unwrap(), golden);
+    let blocks = &rust_export::FLASH_DRV_BLOCKS;
+    assert_eq!(blocks[0].address, 0x0000);
+    assert_eq!(blocks[0].data, &[0x01, 0x02, 0x03, 0x04, 0x05]);
+    assert_eq!(blocks[1].address, 0x1000);
+    assert_eq!(blocks[1].data, &[0xAA, 0xBB]);
+}
+
+#[test]
+fn test_cli_multiple_outputs_from_one_run() {
+    let dir = temp_dir("cli_multi_output");
+    let input = dir.join("input.bin");
+    let out_hex = dir.join("out.hex");
+    let out_bin = dir.join("out.bin");
+    let out_s19 = dir.join("out.s19");
+    write_file(&input, &[0x01, 0x02, 0x03, 0x04]);
+
+    let args = vec![
+        format!("/IN:{};0x1000", input.display()),
+        "/FR:0x1004-0x1007".to_string(),
+        "/FP:AA".to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out_hex.display().to_string(),
+        "/XN".to_string(),
+        "-o".to_string(),
+        out_bin.display().to_string(),
+        "/XS".to_string(),
+        "-o".to_string(),
+        out_s19.display().to_string(),
+    ];
+    assert_success(&run_h3xy(&args));
+
+    let expected = [0x01, 0x02, 0x03, 0x04, 0xAA, 0xAA, 0xAA, 0xAA];
+    let hex = h3xy::parse_intel_hex(&std::fs::read(&out_hex).unwrap()).unwrap();
+    let srec = h3xy::parse_srec(&std::fs::read(&out_s19).unwrap()).unwrap();
+    for parsed in [hex, srec] {
+        let normalized = parsed.normalized_lossy();
+        assert_eq!(normalized.segments().len(), 1);
+        assert_eq!(normalized.segments()[0].start_address, 0x1000);
+        assert_eq!(normalized.segments()[0].data, expected);
+    }
+    assert_eq!(std::fs::read(&out_bin).unwrap(), expected);
+}
+
+#[test]
+fn test_cli_two_formats_for_one_output_rejected() {
+    let dir = temp_dir("cli_multi_output_dup");
+    let input = dir.join("input.bin");
+    write_file(&input, &[0x01]);
+
+    let args = vec![
+        format!("/IN:{};0x1000", input.display()),
+        "/XI".to_string(),
+        "/XS".to_string(),
+        "-o".to_string(),
+        dir.join("out.hex").display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("multiple output formats"), "{stderr}");
+}
@@ -2,8 +2,9 @@ mod common;
 
 use common::{assert_success, run_h3xy, temp_dir, write_file};
 use h3xy::{
-    AlignOptions, BinaryWriteOptions, ChecksumAlgorithm, ChecksumTarget, IntelHexWriteOptions,
-    Pipeline, PipelineMerge, Range, parse_binary, write_binary, write_intel_hex,
+    AlignOptions, BinaryWriteOptions, ChecksumAlgorithm, ChecksumOptions, ChecksumTarget,
+    FillPattern, IntelHexWriteOptions, Pipeline, PipelineChecksum, PipelineMerge, Range,
+    parse_binary, write_binary, write_intel_hex,
 };
 
 #[test]
@@ -40,17 +41,20 @@ fn test_cli_pipeline_parity_basic_chain() {
     let base_hex = parse_binary(&[0x10, 0x11, 0x12, 0x13], 0x1000).unwrap();
     let merge_hex = parse_binary(&[0xAA, 0xBB], 0).unwrap();
 
-    let mut pipeline = Pipeline::default();
-    pipeline.hexfile = base_hex;
-    pipeline.fill_ranges = vec![Range::from_start_end(0x1000, 0x100F).unwrap()];
-    pipeline.fill_pattern = Some(vec![0xF0]);
-    pipeline.cut_ranges = vec![Range::from_start_end(0x1004, 0x1005).unwrap()];
+    let mut pipeline = Pipeline {
+        hexfile: base_hex,
+        ..Default::default()
+    };
+    pipeline.fill_ranges = vec![Range::from_start_end(0x1000, 0x100F).unwrap().into()];
+    pipeline.fill_pattern = Some(FillPattern::Bytes(vec![0xF0]));
+    pipeline.cut_ranges = vec![Range::from_start_end(0x1004, 0x1005).unwrap().into()];
     pipeline.merge_transparent = vec![PipelineMerge {
         other: merge_hex,
         offset: 0x1008,
         range: None,
+        path: None,
     }];
-    pipeline.address_ranges = vec![Range::from_start_end(0x1000, 0x1010).unwrap()];
+    pipeline.address_ranges = vec![Range::from_start_end(0x1000, 0x1010).unwrap().into()];
     pipeline.align = Some(AlignOptions {
         alignment: 4,
         fill_byte: 0x00,
@@ -62,7 +66,7 @@ fn test_cli_pipeline_parity_basic_chain() {
     let result = pipeline
         .execute_without_log(|range| vec![0; range.length() as usize])
         .unwrap();
-    let lib_bytes = write_intel_hex(&result.hexfile, &IntelHexWriteOptions::default());
+    let lib_bytes = write_intel_hex(&result.hexfile, &IntelHexWriteOptions::default()).unwrap();
 
     assert_eq!(cli_bytes, lib_bytes);
 }
@@ -77,35 +81,48 @@ fn test_cli_pipeline_parity_binary_order() {
     write_file(&base, &[0x01, 0x02]);
     write_file(&merge, &[0xAA, 0xBB]);
 
-    let args = vec![
-        format!("/IN:{};0x2000", base.display()),
-        format!("/MT:{};0x1000", merge.display()),
-        "/XN".to_string(),
-        "-o".to_string(),
-        out_cli.display().to_string(),
-    ];
-
-    let output = run_h3xy(&args);
-    assert_success(&output);
-    let cli_bytes = std::fs::read(&out_cli).unwrap();
+    let run = |format: &str| {
+        let output = run_h3xy(&[
+            format!("/IN:{};0x2000", base.display()),
+            format!("/MT:{};0x1000", merge.display()),
+            format.to_string(),
+            "-o".to_string(),
+            out_cli.display().to_string(),
+        ]);
+        assert_success(&output);
+        std::fs::read(&out_cli).unwrap()
+    };
 
     let base_hex = parse_binary(&[0x01, 0x02], 0x2000).unwrap();
     let merge_hex = parse_binary(&[0xAA, 0xBB], 0).unwrap();
 
-    let mut pipeline = Pipeline::default();
-    pipeline.hexfile = base_hex;
+    let mut pipeline = Pipeline {
+        hexfile: base_hex,
+        ..Default::default()
+    };
     pipeline.merge_transparent = vec![PipelineMerge {
         other: merge_hex,
         offset: 0x1000,
         range: None,
+        path: None,
     }];
 
     let result = pipeline
         .execute_without_log(|range| vec![0; range.length() as usize])
         .unwrap();
-    let lib_bytes = write_binary(&result.hexfile, &BinaryWriteOptions::default());
-
-    assert_eq!(cli_bytes, lib_bytes);
+    let image = write_binary(&result.hexfile, &BinaryWriteOptions::default()).unwrap();
+    assert_eq!(run("/XN"), image);
+    assert_eq!(image.len(), 0x1002);
+    assert_eq!(&image[..2], &[0xAA, 0xBB]);
+    assert_eq!(&image[0x1000..], &[0x01, 0x02]);
+
+    let options = BinaryWriteOptions {
+        concatenate_in_order: true,
+        ..Default::default()
+    };
+    let raw = write_binary(&result.hexfile, &options).unwrap();
+    assert_eq!(run("/XN:RAW"), raw);
+    assert_eq!(raw, vec![0xAA, 0xBB, 0x01, 0x02]);
 }
 
 #[test]
@@ -138,10 +155,11 @@ fn test_cli_checksum_parity_begin() {
         false,
         None,
         &[],
+        None,
         &ChecksumTarget::Address(start),
     )
     .unwrap();
-    let lib_bytes = write_intel_hex(&hexfile, &IntelHexWriteOptions::default());
+    let lib_bytes = write_intel_hex(&hexfile, &IntelHexWriteOptions::default()).unwrap();
 
     assert_eq!(cli_bytes, lib_bytes);
 }
@@ -176,6 +194,7 @@ fn test_cli_checksum_parity_little_endian_file() {
         true,
         None,
         &[],
+        None,
         &ChecksumTarget::File(out_sum.clone()),
     )
     .unwrap();
@@ -214,12 +233,15 @@ fn test_cli_pipeline_parity_xsb_split() {
     let base_hex = parse_binary(&[0x01, 0x02], 0x1000).unwrap();
     let merge_hex = parse_binary(&[0xAA], 0).unwrap();
 
-    let mut pipeline = Pipeline::default();
-    pipeline.hexfile = base_hex;
+    let mut pipeline = Pipeline {
+        hexfile: base_hex,
+        ..Default::default()
+    };
     pipeline.merge_opaque = vec![PipelineMerge {
         other: merge_hex,
         offset: 0x2000,
         range: None,
+        path: None,
     }];
 
     let result = pipeline
@@ -232,6 +254,52 @@ fn test_cli_pipeline_parity_xsb_split() {
     assert_eq!(cli_b, segments[1].data);
 }
 
+#[test]
+fn test_cli_pipeline_parity_block_checksum() {
+    let dir = temp_dir("cli_pipeline_parity_csb");
+    let base = dir.join("base.bin");
+    let out_cli = dir.join("out.hex");
+    let data = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+
+    write_file(&base, &data);
+
+    let args = vec![
+        format!("/IN:{};0x1000", base.display()),
+        "/SB:4".to_string(),
+        "/CSB9:@end".to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out_cli.display().to_string(),
+    ];
+
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    let cli_bytes = std::fs::read(&out_cli).unwrap();
+
+    let pipeline = Pipeline {
+        hexfile: parse_binary(&data, 0x1000).unwrap(),
+        split: Some(4),
+        checksums: vec![PipelineChecksum {
+            per_block: true,
+            ..PipelineChecksum::new(
+                ChecksumOptions {
+                    algorithm: ChecksumAlgorithm::from_index(9).unwrap(),
+                    ..Default::default()
+                },
+                ChecksumTarget::OverwriteEnd,
+            )
+        }],
+        ..Default::default()
+    };
+    let result = pipeline
+        .execute_without_log(|range| vec![0; range.length() as usize])
+        .unwrap();
+    assert_eq!(result.checksum_bytes[0].len(), 2);
+    let lib_bytes = write_intel_hex(&result.hexfile, &IntelHexWriteOptions::default()).unwrap();
+
+    assert_eq!(cli_bytes, lib_bytes);
+}
+
 #[test]
 fn test_cli_pipeline_parity_fa_fill_binary() {
     let dir = temp_dir("cli_pipeline_parity_fa");
@@ -254,8 +322,10 @@ fn test_cli_pipeline_parity_fa_fill_binary() {
     let cli_bytes = std::fs::read(&out_cli).unwrap();
 
     let base_hex = parse_binary(&[0xAA], 0x1000).unwrap();
-    let mut pipeline = Pipeline::default();
-    pipeline.hexfile = base_hex;
+    let mut pipeline = Pipeline {
+        hexfile: base_hex,
+        ..Default::default()
+    };
     pipeline.fill_all = Some(0x00);
     let result = pipeline
         .execute_without_log(|range| vec![0; range.length() as usize])
@@ -263,9 +333,74 @@ fn test_cli_pipeline_parity_fa_fill_binary() {
     let lib_bytes = write_binary(
         &result.hexfile,
         &BinaryWriteOptions {
-            fill_gaps: Some(0x00),
+            fill: 0x00,
+            ..Default::default()
         },
-    );
+    )
+    .unwrap();
 
     assert_eq!(cli_bytes, lib_bytes);
 }
+
+#[test]
+fn test_cli_postbuild_script_parity() {
+    let dir = temp_dir("cli_postbuild");
+    let base = dir.join("base.bin");
+    let script = dir.join("post.txt");
+    let out_direct = dir.join("direct.hex");
+    let out_script = dir.join("script.hex");
+
+    write_file(&base, &[0x10, 0x11, 0x12, 0x13]);
+    write_file(
+        &script,
+        b"; postbuild steps\r\n/FR:0x1000-0x100F /FP:F0\r\n\r\n/CS0:@0x2000\r\n/XI:16\r\n",
+    );
+
+    let direct = vec![
+        format!("/IN:{};0x1000", base.display()),
+        "/FR:0x1000-0x100F".to_string(),
+        "/FP:F0".to_string(),
+        "/CS0:@0x2000".to_string(),
+        "/XI:16".to_string(),
+        "-o".to_string(),
+        out_direct.display().to_string(),
+    ];
+    assert_success(&run_h3xy(&direct));
+
+    let scripted = vec![
+        format!("/IN:{};0x1000", base.display()),
+        format!("/PB:{}", script.display()),
+        "-o".to_string(),
+        out_script.display().to_string(),
+    ];
+    assert_success(&run_h3xy(&scripted));
+
+    assert_eq!(
+        std::fs::read(&out_script).unwrap(),
+        std::fs::read(&out_direct).unwrap()
+    );
+}
+
+#[test]
+fn test_cli_postbuild_rejects_nested_script() {
+    let dir = temp_dir("cli_postbuild_nested");
+    let base = dir.join("base.bin");
+    let script = dir.join("post.txt");
+    let out = dir.join("out.hex");
+
+    write_file(&base, &[0x10]);
+    write_file(&script, format!("/PB:{}\n", script.display()).as_bytes());
+
+    let args = vec![
+        format!("/IN:{}", base.display()),
+        format!("/PB:{}", script.display()),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("post.txt:1: nested postbuild"), "{stderr}");
+    assert!(!out.exists());
+}
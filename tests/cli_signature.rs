@@ -1,11 +1,13 @@
 mod common;
 
 use common::{assert_success, run_h3xy, temp_dir, write_file};
+use ed25519_dalek::SigningKey as EdSigningKey;
 use ed25519_dalek::pkcs8::{
     EncodePrivateKey as EdEncodePrivateKey, EncodePublicKey as EdEncodePublicKey,
 };
-use ed25519_dalek::SigningKey as EdSigningKey;
 use h3xy::parse_intel_hex;
+use p256::ecdsa::SigningKey as EcdsaSigningKey;
+use p256::pkcs8::LineEnding;
 use rsa::rand_core::OsRng;
 use rsa::{RsaPrivateKey, RsaPublicKey};
 
@@ -22,7 +24,10 @@ fn write_rsa_keys(dir: &std::path::Path, prefix: &str) -> (std::path::PathBuf, s
     (private_path, public_path)
 }
 
-fn write_ed25519_keys(dir: &std::path::Path, prefix: &str) -> (std::path::PathBuf, std::path::PathBuf) {
+fn write_ed25519_keys(
+    dir: &std::path::Path,
+    prefix: &str,
+) -> (std::path::PathBuf, std::path::PathBuf) {
     let secret = [0x42u8; 32];
     let signing = EdSigningKey::from_bytes(&secret);
     let verifying = signing.verifying_key();
@@ -67,10 +72,14 @@ fn test_cli_sv_fails_with_wrong_key() {
     let args = vec![
         format!("/IN:{};0x1000", input_path.display()),
         format!("/DP32:{};{}", private_path.display(), sig_path.display()),
-        format!("/SV4:{}!{}", wrong_public_path.display(), sig_path.display()),
+        format!(
+            "/SV4:{}!{}",
+            wrong_public_path.display(),
+            sig_path.display()
+        ),
     ];
     let output = run_h3xy(&args);
-    assert!(!output.status.success());
+    assert_eq!(output.status.code(), Some(5));
 }
 
 #[test]
@@ -93,6 +102,220 @@ fn test_cli_dp_sv_ed25519_success() {
     assert_eq!(signature.len(), 64);
 }
 
+fn write_p256_keys(
+    dir: &std::path::Path,
+    prefix: &str,
+    seed: u8,
+) -> (std::path::PathBuf, std::path::PathBuf) {
+    let signing = EcdsaSigningKey::from_slice(&[seed; 32]).unwrap();
+    let verifying = signing.verifying_key();
+    let private_path = dir.join(format!("{prefix}_private.pem"));
+    let public_path = dir.join(format!("{prefix}_public.der"));
+    let private_pem = signing.to_pkcs8_pem(LineEnding::LF).unwrap();
+    let public_der = verifying.to_public_key_der().unwrap();
+    write_file(&private_path, private_pem.as_bytes());
+    write_file(&public_path, public_der.as_bytes());
+    (private_path, public_path)
+}
+
+#[test]
+fn test_cli_dp_sv_ecdsa_p256_raw_and_der() {
+    let dir = temp_dir("cli_sig_ecdsa_ok");
+    let input_path = dir.join("input.bin");
+    let sig_path = dir.join("sig.bin");
+    write_file(&input_path, b"hello-signature");
+    let (private_path, public_path) = write_p256_keys(&dir, "ec_ok", 0x11);
+
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        format!("/DP50:{};{}", private_path.display(), sig_path.display()),
+        format!("/SV12:{}!{}", public_path.display(), sig_path.display()),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    assert_eq!(std::fs::read(&sig_path).unwrap().len(), 64);
+
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        format!("/DP53:{};{}", private_path.display(), sig_path.display()),
+        format!("/SV13:{}!{}", public_path.display(), sig_path.display()),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    let der = std::fs::read(&sig_path).unwrap();
+    assert_eq!(der[0], 0x30);
+    assert_eq!(der.len(), der[1] as usize + 2);
+
+    // The metadata variant signs a different payload.
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        format!("/SV12:{}!{}", public_path.display(), sig_path.display()),
+    ];
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_cli_sv_ecdsa_p256_fails_with_wrong_key() {
+    let dir = temp_dir("cli_sig_ecdsa_fail");
+    let input_path = dir.join("input.bin");
+    let sig_path = dir.join("sig.bin");
+    write_file(&input_path, b"hello-signature");
+    let (private_path, _) = write_p256_keys(&dir, "ec_sign", 0x11);
+    let (_, wrong_public_path) = write_p256_keys(&dir, "ec_wrong", 0x22);
+
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        format!("/DP50:{};{}", private_path.display(), sig_path.display()),
+        format!(
+            "/SV12:{}!{}",
+            wrong_public_path.display(),
+            sig_path.display()
+        ),
+    ];
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_cli_dp_sv_signed_range_ignores_outside_bytes() {
+    let dir = temp_dir("cli_sig_range");
+    let input_path = dir.join("input.bin");
+    let sig_path = dir.join("sig.bin");
+    let out_hex = dir.join("out.hex");
+    let (private_path, public_path) = write_ed25519_keys(&dir, "ed_range");
+    let mut data = vec![0x5Au8; 0x40];
+    write_file(&input_path, &data);
+
+    // Sign 0x1000-0x103F except the slot the signature goes into.
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        format!(
+            "/DP47:@0x1030:{};{};0x1000-0x103F/0x1030-0x106F",
+            private_path.display(),
+            sig_path.display()
+        ),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out_hex.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+
+    // The placed signature and bytes outside the range do not matter.
+    let verify = |input: &std::path::Path| {
+        run_h3xy(&[
+            input.display().to_string(),
+            format!(
+                "/SV9:{}!{};0x1000-0x103F/0x1030-0x106F",
+                public_path.display(),
+                sig_path.display()
+            ),
+        ])
+    };
+    assert_success(&verify(&out_hex));
+
+    data.extend_from_slice(&[0xEE; 0x10]);
+    data[0x30] = 0x00;
+    write_file(&input_path, &data);
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        format!(
+            "/SV9:{}!{};0x1000-0x103F/0x1030-0x106F",
+            public_path.display(),
+            sig_path.display()
+        ),
+    ];
+    assert_success(&run_h3xy(&args));
+
+    data[0x10] ^= 0xFF;
+    write_file(&input_path, &data);
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+}
+
+/// PBES2 (PBKDF2-SHA256, AES-256-CBC) encrypted PKCS#8 PEM, cheap enough for debug builds.
+fn write_encrypted_ed25519_key(dir: &std::path::Path, password: &str) -> std::path::PathBuf {
+    let der = EdSigningKey::from_bytes(&[0x42u8; 32])
+        .to_pkcs8_der()
+        .unwrap();
+    let info = pkcs8::PrivateKeyInfo::try_from(der.as_bytes()).unwrap();
+    let params =
+        pkcs8::pkcs5::pbes2::Parameters::pbkdf2_sha256_aes256cbc(2048, &[0x5A; 16], &[0xA5; 16])
+            .unwrap();
+    let encrypted = info.encrypt_with_params(params, password).unwrap();
+    let path = dir.join("ed_encrypted.pem");
+    let pem = encrypted
+        .to_pem("ENCRYPTED PRIVATE KEY", LineEnding::LF)
+        .unwrap();
+    write_file(&path, pem.as_bytes());
+    path
+}
+
+#[test]
+fn test_cli_dp_encrypted_private_key() {
+    let dir = temp_dir("cli_sig_encrypted");
+    let input_path = dir.join("input.bin");
+    let sig_path = dir.join("sig.bin");
+    let password_path = dir.join("password.txt");
+    write_file(&input_path, b"hello-signature");
+    write_file(&password_path, b"s3cret\n");
+    let (_, public_path) = write_ed25519_keys(&dir, "ed_plain");
+    let key_path = write_encrypted_ed25519_key(&dir, "s3cret");
+
+    for password in [
+        "s3cret".to_string(),
+        format!("file:{}", password_path.display()),
+    ] {
+        let args = vec![
+            format!("/IN:{};0x1000", input_path.display()),
+            format!(
+                "/DP46:{},{password};{}",
+                key_path.display(),
+                sig_path.display()
+            ),
+            format!("/SV8:{}!{}", public_path.display(), sig_path.display()),
+        ];
+        let output = run_h3xy(&args);
+        assert_success(&output);
+    }
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_h3xy"))
+        .env("H3XY_TEST_KEY_PASSWORD", "s3cret")
+        .args([
+            format!("/IN:{};0x1000", input_path.display()),
+            format!(
+                "/DP46:{},env:H3XY_TEST_KEY_PASSWORD;{}",
+                key_path.display(),
+                sig_path.display()
+            ),
+            format!("/SV8:{}!{}", public_path.display(), sig_path.display()),
+        ])
+        .output()
+        .unwrap();
+    assert_success(&output);
+
+    let run_dp = |key_info: String| {
+        let output = run_h3xy(&[
+            format!("/IN:{};0x1000", input_path.display()),
+            format!("/DP46:{key_info}"),
+        ]);
+        assert!(!output.status.success());
+        String::from_utf8_lossy(&output.stderr).to_string()
+    };
+    let stderr = run_dp(format!("{},wrong", key_path.display()));
+    assert!(stderr.contains("wrong password"), "{stderr}");
+    let stderr = run_dp(key_path.display().to_string());
+    assert!(stderr.contains("no password"), "{stderr}");
+    let garbage = dir.join("garbage.pem");
+    write_file(&garbage, b"not a key");
+    let stderr = run_dp(format!("{},s3cret", garbage.display()));
+    assert!(
+        stderr.contains("unable to parse ed25519 private key"),
+        "{stderr}"
+    );
+}
+
 #[test]
 fn test_cli_dp_placement_append_writes_signature_to_data() {
     let dir = temp_dir("cli_sig_dp_place");
@@ -118,6 +341,171 @@ fn test_cli_dp_placement_append_writes_signature_to_data() {
     assert_eq!(&bytes[..4], &[0x10, 0x20, 0x30, 0x40]);
 }
 
+#[test]
+fn test_cli_dp_hmac_and_cmac() {
+    let dir = temp_dir("cli_sig_mac");
+    let input_path = dir.join("input.bin");
+    let hmac_key = dir.join("hmac.key");
+    let cmac_key = dir.join("cmac.key");
+    let mac_path = dir.join("mac.bin");
+    let out_hex = dir.join("out.hex");
+    write_file(&input_path, b"what do ya want for nothing?");
+    write_file(&hmac_key, b"Jefe");
+    write_file(&cmac_key, b"2b7e151628aed2a6abf7158809cf4f3c\n");
+
+    // RFC 4231 test case 2, placed after the data.
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        format!(
+            "/DP60:@append:{};{}",
+            hmac_key.display(),
+            mac_path.display()
+        ),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out_hex.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    let expected = [
+        0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e, 0x6a, 0x04, 0x24, 0x26, 0x08, 0x95, 0x75,
+        0xc7, 0x5a, 0x00, 0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83, 0x9d, 0xec, 0x58, 0xb9, 0x64, 0xec,
+        0x38, 0x43,
+    ];
+    assert_eq!(std::fs::read(&mac_path).unwrap(), expected);
+    let parsed = parse_intel_hex(&std::fs::read(&out_hex).unwrap()).unwrap();
+    let normalized = parsed.normalized_lossy();
+    let bytes = normalized.read_bytes_contiguous(0x1000 + 28, 32).unwrap();
+    assert_eq!(bytes, expected);
+
+    // AES-128-CMAC with a hex key file; the MAC is always 16 bytes.
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        format!("/DP63:{};{}", cmac_key.display(), mac_path.display()),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    assert_eq!(std::fs::read(&mac_path).unwrap().len(), 16);
+
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        format!("/DP62:{}", hmac_key.display()),
+    ];
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+
+    // A missing key file is an error, not a key made of the path text.
+    let missing = dir.join("missing.key");
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        format!("/DP60:{};{}", missing.display(), mac_path.display()),
+    ];
+    let output = run_h3xy(&args);
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!("key file {}", missing.display())),
+        "{stderr}"
+    );
+
+    // Inline keys need the hex: prefix.
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        format!(
+            "/DP62:hex:2b7e151628aed2a6abf7158809cf4f3c;{}",
+            mac_path.display()
+        ),
+    ];
+    assert_success(&run_h3xy(&args));
+    assert_eq!(std::fs::read(&mac_path).unwrap().len(), 16);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cli_dp_external_signer() {
+    let dir = temp_dir("cli_sig_dpext");
+    let input_path = dir.join("input.bin");
+    let sig_path = dir.join("sig.bin");
+    let out_hex = dir.join("out.hex");
+    write_file(&input_path, &[0x10, 0x20, 0x30, 0x40]);
+
+    // The "signer" copies the payload, so the placed signature equals the data.
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        format!(
+            "/DPEXT4:@append:\"cp {{payload}} {{signature}}\";{}",
+            sig_path.display()
+        ),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out_hex.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_success(&output);
+    assert_eq!(std::fs::read(&sig_path).unwrap(), [0x10, 0x20, 0x30, 0x40]);
+    let parsed = parse_intel_hex(&std::fs::read(&out_hex).unwrap()).unwrap();
+    let normalized = parsed.normalized_lossy();
+    assert_eq!(
+        normalized.read_bytes_contiguous(0x1000, 8).unwrap(),
+        [0x10, 0x20, 0x30, 0x40, 0x10, 0x20, 0x30, 0x40]
+    );
+
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        "/DPEXT:\"echo hsm offline >&2; exit 3\"".to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("hsm offline"), "{stderr}");
+
+    // Exit 0 without writing a signature still fails the run.
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        "/DPEXT:true {payload} {signature}".to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("did not write a signature"), "{stderr}");
+
+    // A signature shorter than the declared length would leave stale bytes
+    // in the placement.
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        "/DPEXT8:@append:\"printf abc > {payload}.sig; mv {payload}.sig {signature}\"".to_string(),
+        "/XI".to_string(),
+        "-o".to_string(),
+        out_hex.display().to_string(),
+    ];
+    let output = run_h3xy(&args);
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("signer wrote 3 bytes, expected 8"),
+        "{stderr}"
+    );
+
+    // Placeholders are quoted, so a temp dir with spaces still works.
+    let spaced = dir.join("tmp dir");
+    std::fs::create_dir_all(&spaced).unwrap();
+    let args = vec![
+        format!("/IN:{};0x1000", input_path.display()),
+        format!(
+            "/DPEXT:\"cp {{payload}} {{signature}}\";{}",
+            sig_path.display()
+        ),
+    ];
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_h3xy"))
+        .args(&args)
+        .env("TMPDIR", &spaced)
+        .output()
+        .unwrap();
+    assert_success(&output);
+    assert_eq!(std::fs::read(&sig_path).unwrap(), [0x10, 0x20, 0x30, 0x40]);
+    assert_eq!(std::fs::read_dir(&spaced).unwrap().count(), 0);
+}
+
 fn assert_dp_placement_empty_input_noop(target: &str) {
     let dir = temp_dir("cli_sig_dp_place_empty");
     let input_path = dir.join("input.bin");
@@ -128,7 +516,11 @@ fn assert_dp_placement_empty_input_noop(target: &str) {
 
     let args = vec![
         format!("/IN:{};0x1000", input_path.display()),
-        format!("/DP46:{target}:{};{}", private_path.display(), sig_path.display()),
+        format!(
+            "/DP46:{target}:{};{}",
+            private_path.display(),
+            sig_path.display()
+        ),
         "/XI".to_string(),
         "-o".to_string(),
         out_hex.display().to_string(),
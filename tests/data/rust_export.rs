@@ -0,0 +1,20 @@
+pub struct FlashDrvBlock {
+    pub address: u32,
+    pub data: &'static [u8],
+}
+
+pub static FLASH_DRV_BLOCKS: [FlashDrvBlock; 2] = [
+    FlashDrvBlock {
+        address: 0x00000000,
+        data: &[
+            0x01, 0x02, 0x03, 0x04,
+            0x05,
+        ],
+    },
+    FlashDrvBlock {
+        address: 0x00001000,
+        data: &[
+            0xAA, 0xBB,
+        ],
+    },
+];
@@ -3,8 +3,8 @@
 //! Focus: multi-stage operations and command semantics.
 
 use h3xy::{
-    AlignOptions, ChecksumAlgorithm, ChecksumOptions, ChecksumTarget, FillOptions, HexFile,
-    MergeMode, MergeOptions, Range, Segment,
+    AlignOptions, ChecksumAlgorithm, ChecksumOptions, ChecksumTarget, FillOptions, FillPattern,
+    HexFile, MergeMode, MergeOptions, Range, Segment,
 };
 
 #[test]
@@ -15,7 +15,7 @@ fn test_fill_region_preserves_existing_data() {
     hf.fill(
         Range::from_start_end(0x1004, 0x100B).unwrap(),
         &FillOptions {
-            pattern: vec![0x11, 0x22],
+            pattern: FillPattern::Bytes(vec![0x11, 0x22]),
             overwrite: false,
         },
     );
@@ -89,7 +89,7 @@ fn test_hexview_multistage_order() {
     hf.fill(
         Range::from_start_end(0x1003, 0x1007).unwrap(),
         &FillOptions {
-            pattern: vec![0xAA, 0xBB],
+            pattern: FillPattern::Bytes(vec![0xAA, 0xBB]),
             overwrite: false,
         },
     );
@@ -0,0 +1,108 @@
+//! Peak heap use of a pipeline run over a large image.
+//!
+//! Lives in its own test binary: the counting allocator sees every thread,
+//! so the tests here also take `SERIAL` to run one at a time.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use h3xy::{
+    ChecksumAlgorithm, ChecksumOptions, HexFile, Pipeline, PipelineMerge, RangeSpec, Segment,
+};
+
+struct Counting;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+fn grow(size: usize) {
+    let now = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(now, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        grow(layout.size());
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // Count the old and new blocks as both live while the data moves.
+        grow(new_size);
+        let out = unsafe { System.realloc(ptr, layout, new_size) };
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+        out
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+static SERIAL: Mutex<()> = Mutex::new(());
+
+const MIB: usize = 1 << 20;
+
+#[test]
+fn test_pipeline_merge_and_filter_do_not_copy_image() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let image = HexFile::with_segments(vec![Segment::new(0, vec![0x11; 256 * MIB])]);
+    let patch = HexFile::with_segments(vec![Segment::new(0x0100_0000, vec![0x22; 16 * MIB])]);
+    let pipeline = Pipeline {
+        hexfile: image,
+        merge_opaque: vec![PipelineMerge {
+            other: patch,
+            offset: 0,
+            range: None,
+            path: None,
+        }],
+        address_ranges: vec![RangeSpec::ALL],
+        ..Default::default()
+    };
+
+    let before = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    let result = pipeline.execute_without_log(|_| Vec::new()).unwrap();
+    let extra = PEAK.load(Ordering::Relaxed) - before;
+
+    assert_eq!(result.hexfile.total_bytes(), (256 + 16) * MIB);
+    assert_eq!(result.hexfile.read_byte(0x0100_0000), Some(0x22));
+    assert!(
+        extra < 16 * MIB,
+        "pipeline peaked {} MiB above its inputs",
+        extra / MIB
+    );
+}
+
+#[test]
+fn test_cloned_image_shares_buffers_until_written() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let image = HexFile::with_segments(vec![Segment::new(0, vec![0x11; 256 * MIB])]);
+
+    let before = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    let mut copy = image.clone();
+    let crc = copy
+        .calculate_checksum(&ChecksumOptions {
+            algorithm: ChecksumAlgorithm::Crc32,
+            ..Default::default()
+        })
+        .unwrap();
+    let extra = PEAK.load(Ordering::Relaxed) - before;
+    assert_eq!(crc.len(), 4);
+    assert!(
+        extra < MIB,
+        "clone and checksum peaked {} MiB above the image",
+        extra / MIB
+    );
+
+    // The first write copies the buffer; the original keeps its bytes.
+    copy.write_bytes(0, &[0x22]);
+    assert_eq!(copy.read_byte(0), Some(0x22));
+    assert_eq!(image.read_byte(0), Some(0x11));
+}
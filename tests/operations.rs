@@ -2,7 +2,10 @@
 //!
 //! These tests verify that chaining multiple operations produces correct results.
 
-use h3xy::{AlignOptions, FillOptions, HexFile, MergeMode, MergeOptions, Range, Segment, SwapMode};
+use h3xy::{
+    AddressTable, AlignOptions, FillOptions, FillPattern, HexFile, MergeMode, MergeOptions, Range,
+    Segment, SwapMode,
+};
 
 // --- Cut → Fill → Normalize ---
 
@@ -21,7 +24,7 @@ fn test_cut_fill_normalize() {
     hf.fill(
         Range::from_start_length(0x1008, 8).unwrap(),
         &FillOptions {
-            pattern: vec![0xCC],
+            pattern: FillPattern::Bytes(vec![0xCC]),
             overwrite: false,
         },
     );
@@ -163,7 +166,7 @@ fn test_fill_gaps_then_merge_overwrite() {
     ]);
 
     // Fill gaps
-    hf_a.fill_gaps(0x00);
+    hf_a.fill_gaps(0x00).unwrap();
 
     // Now it's contiguous 0x1000-0x1013, gaps filled with 0x00
     assert_eq!(hf_a.segments().len(), 1);
@@ -198,7 +201,7 @@ fn test_fill_gaps_then_merge_preserve() {
         Segment::new(0x1000, vec![0xAA; 4]),
         Segment::new(0x1010, vec![0xBB; 4]),
     ]);
-    hf_a.fill_gaps(0x00);
+    hf_a.fill_gaps(0x00).unwrap();
 
     let hf_b = HexFile::with_segments(vec![Segment::new(0x1008, vec![0xCC; 4])]);
 
@@ -256,7 +259,7 @@ fn test_filter_cut_fill_align_split() {
     hf.fill(
         Range::from_start_length(0x1040, 0x20).unwrap(),
         &FillOptions {
-            pattern: vec![0xBB],
+            pattern: FillPattern::Bytes(vec![0xBB]),
             overwrite: false,
         },
     );
@@ -270,7 +273,7 @@ fn test_filter_cut_fill_align_split() {
     .unwrap();
 
     // Fill gaps to merge everything
-    hf.fill_gaps(0x00);
+    hf.fill_gaps(0x00).unwrap();
 
     // Now we have a single contiguous segment - verify data integrity
     let norm = hf.normalized_lossy();
@@ -300,7 +303,7 @@ fn test_operations_on_empty_file() {
     // None of these should panic (scale/offset on empty are no-ops)
     hf.filter_range(Range::from_start_end(0x1000, 0x1FFF).unwrap());
     hf.cut(Range::from_start_end(0x1000, 0x1FFF).unwrap());
-    hf.fill_gaps(0xFF);
+    hf.fill_gaps(0xFF).unwrap();
     hf.scale_addresses(2).unwrap();
     hf.offset_addresses(0x1000).unwrap();
     hf.split(16);
@@ -350,3 +353,163 @@ fn test_three_overlapping_segments_last_wins() {
     // 0x1003 = 0x11 (only first covers it)
     assert_eq!(data, vec![0x11, 0x22, 0x33, 0x11]);
 }
+
+// --- Shared normalization (run with --ignored --nocapture for timings) ---
+
+#[test]
+#[ignore = "benchmark"]
+fn bench_read_only_consumers_share_normalization() {
+    use h3xy::{ChecksumOptions, IntelHexWriteOptions, write_intel_hex};
+    use std::time::Instant;
+
+    // 64 MiB in 4 KiB blocks, inserted in reverse so the raw order is unsorted.
+    let blocks = (0..0x4000u32)
+        .rev()
+        .map(|i| Segment::new(i * 0x1000, vec![i as u8; 0x1000]))
+        .collect();
+    let raw = HexFile::with_segments(blocks);
+    let options = ChecksumOptions::default();
+    let consume = |hf: &HexFile| {
+        let sum = hf.calculate_checksum(&options).unwrap();
+        let hex = write_intel_hex(hf, &IntelHexWriteOptions::default()).unwrap();
+        (sum, hex)
+    };
+
+    let start = Instant::now();
+    let expected = (consume(&raw), consume(&raw));
+    let unshared = start.elapsed();
+
+    let start = Instant::now();
+    let mut hf = raw.clone();
+    hf.ensure_normalized();
+    let shared = (consume(&hf), consume(&hf));
+    let shared_time = start.elapsed();
+
+    assert_eq!(shared, expected);
+    println!("per consumer: {unshared:?}, normalized once: {shared_time:?}");
+}
+
+#[test]
+#[ignore = "benchmark"]
+fn bench_read_byte_on_many_segments() {
+    use std::time::Instant;
+
+    // 10k 16-byte segments with 16-byte gaps.
+    let raw = HexFile::with_segments(
+        (0..10_000u32)
+            .map(|i| Segment::new(i * 32, vec![i as u8; 16]))
+            .collect(),
+    );
+    let mut indexed = raw.clone();
+    indexed.ensure_normalized();
+    let addrs: Vec<u32> = (0..1_000_000u32)
+        .map(|i| i.wrapping_mul(7919) % 320_000)
+        .collect();
+
+    let start = Instant::now();
+    let fast: Vec<_> = addrs.iter().map(|&a| indexed.read_byte(a)).collect();
+    let fast_time = start.elapsed();
+
+    let start = Instant::now();
+    let slow: Vec<_> = addrs[..10_000].iter().map(|&a| raw.read_byte(a)).collect();
+    let slow_time = start.elapsed() * 100;
+
+    assert_eq!(fast[..10_000], slow[..]);
+    println!("1M reads: scan ~{slow_time:?}, normalized {fast_time:?}");
+}
+
+#[test]
+#[ignore = "benchmark"]
+fn bench_100k_single_byte_writes() {
+    use std::time::Instant;
+
+    // Patch-style writes: in order (merged into the last segment) and
+    // scattered (one segment each, resolved once by normalized_lossy).
+    let run = |count: u32, addr: fn(u32) -> u32| {
+        let start = Instant::now();
+        let mut hf = HexFile::new();
+        for i in 0..count {
+            hf.write_bytes(addr(i), &[i as u8]);
+        }
+        let normalized = hf.normalized_lossy();
+        (start.elapsed(), hf.segments().len(), normalized)
+    };
+    let in_order = |i: u32| i;
+    let scattered = |i: u32| i.wrapping_mul(7919) % 400_000;
+
+    for (name, addr) in [
+        ("in order", in_order as fn(u32) -> u32),
+        ("scattered", scattered as fn(u32) -> u32),
+    ] {
+        let (small, _, _) = run(10_000, addr);
+        let (large, raw_segments, normalized) = run(100_000, addr);
+        assert_eq!(normalized.read_byte(addr(99_999)), Some(99_999u32 as u8));
+        println!(
+            "{name}: 10k writes {small:?}, 100k writes {large:?} ({:.1}x), {raw_segments} raw segment(s)",
+            large.as_secs_f64() / small.as_secs_f64()
+        );
+    }
+}
+
+#[test]
+#[ignore = "benchmark"]
+fn bench_parse_intel_hex_throughput() {
+    use h3xy::{IntelHexWriteOptions, parse_intel_hex, write_intel_hex};
+    use std::time::Instant;
+
+    let data: Vec<u8> = (0..32u32 << 20).map(|i| ((i * 31) >> 3) as u8).collect();
+    let text = write_intel_hex(
+        &HexFile::with_segments(vec![Segment::new(0, data)]),
+        &IntelHexWriteOptions::default(),
+    )
+    .unwrap();
+
+    let start = Instant::now();
+    let parsed = parse_intel_hex(&text).unwrap();
+    let elapsed = start.elapsed();
+
+    assert_eq!(parsed.total_bytes(), 32 << 20);
+    let mib = text.len() as f64 / (1 << 20) as f64;
+    println!(
+        "{mib:.0} MiB of text in {elapsed:?} ({:.0} MiB/s)",
+        mib / elapsed.as_secs_f64()
+    );
+}
+
+// --- Address table → inverse table ---
+
+#[test]
+fn test_address_table_fixture_round_trip() {
+    let path =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/pic18_pages.maptab");
+    let table: AddressTable = std::fs::read_to_string(path).unwrap().parse().unwrap();
+    let hf = HexFile::with_segments(vec![
+        Segment::new(0x0000, vec![0x01, 0x02]),
+        Segment::new(0x010000, vec![0x11; 0x1000]),
+        Segment::new(0x020800, vec![0x22; 0x10]),
+        Segment::new(0x030FF0, vec![0x33; 0x20]),
+    ]);
+
+    let mapped = hf.remap_table(&table).unwrap();
+    let ranges: Vec<_> = mapped
+        .segments()
+        .iter()
+        .map(|s| (s.start_address, s.len()))
+        .collect();
+    assert_eq!(
+        ranges,
+        [
+            (0x0000, 2),
+            (0x1000, 0x1000),
+            (0x2800, 0x10),
+            (0x3FF0, 0x10),
+            (0x031000, 0x10),
+        ]
+    );
+
+    let back = mapped
+        .remap_table(&table.inverse())
+        .unwrap()
+        .normalized_lossy();
+    assert_eq!(back, hf.normalized_lossy());
+}
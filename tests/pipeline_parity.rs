@@ -1,6 +1,8 @@
 mod common;
 
-use h3xy::{AlignOptions, Pipeline, PipelineMerge, Range, parse_binary, parse_intel_hex};
+use h3xy::{
+    AlignOptions, FillPattern, Pipeline, PipelineMerge, Range, parse_binary, parse_intel_hex,
+};
 
 use common::{assert_success, run_h3xy, temp_dir, write_file};
 
@@ -39,15 +41,16 @@ fn test_pipeline_matches_cli_basic() {
 
     let pipeline = Pipeline {
         hexfile: input_hex,
-        fill_ranges: vec![Range::from_start_end(0x0, 0x7).unwrap()],
-        fill_pattern: Some(vec![0xAA]),
-        cut_ranges: vec![Range::from_start_end(0x2, 0x3).unwrap()],
+        fill_ranges: vec![Range::from_start_end(0x0, 0x7).unwrap().into()],
+        fill_pattern: Some(FillPattern::Bytes(vec![0xAA])),
+        cut_ranges: vec![Range::from_start_end(0x2, 0x3).unwrap().into()],
         merge_opaque: vec![PipelineMerge {
             other: merge_hex,
             offset: 0x6,
             range: None,
+            path: None,
         }],
-        address_ranges: vec![Range::from_start_end(0x0, 0x7).unwrap()],
+        address_ranges: vec![Range::from_start_end(0x0, 0x7).unwrap().into()],
         align: Some(AlignOptions {
             alignment: 4,
             fill_byte: 0x00,